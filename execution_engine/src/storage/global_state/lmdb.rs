@@ -1,7 +1,8 @@
-use std::{collections::HashMap, ops::Deref, sync::Arc};
+use std::{collections::HashMap, ops::Deref, path::Path, sync::Arc};
 
 use casper_hashing::Digest;
 use casper_types::{Key, StoredValue};
+use lmdb::{Cursor, DatabaseFlags, WriteFlags};
 
 use crate::{
     shared::{additive_map::AdditiveMap, newtypes::CorrelationId, transform::Transform},
@@ -19,13 +20,16 @@ use crate::{
         trie_store::{
             lmdb::{LmdbTrieStore, ScratchTrieStore},
             operations::{
-                delete, keys_with_prefix, missing_children, put_trie, read, read_with_proof,
-                DeleteResult, ReadResult,
+                check_integrity, delete, keys_with_prefix, missing_children, put_trie, read,
+                read_with_proof, DeleteResult, ReadResult, TrieIntegrityFault,
             },
         },
     },
 };
 
+/// Number of tries written per transaction when copying the global state.
+const COPY_BATCH_SIZE: usize = 100_000;
+
 /// Global state implemented against LMDB as a backing data store.
 pub struct LmdbGlobalState {
     /// Environment for LMDB.
@@ -64,6 +68,21 @@ impl LmdbGlobalState {
         Ok(LmdbGlobalState::new(environment, trie_store, root_hash))
     }
 
+    /// Creates a state from an existing environment and trie_store without writing to either.
+    ///
+    /// Suitable for environments opened read-only.
+    pub fn open(
+        environment: Arc<LmdbEnvironment>,
+        trie_store: Arc<LmdbTrieStore>,
+    ) -> Result<Self, error::Error> {
+        let (empty_root_hash, _) = compute_empty_root_hash()?;
+        Ok(LmdbGlobalState::new(
+            environment,
+            trie_store,
+            empty_root_hash,
+        ))
+    }
+
     /// Creates a state from an existing environment, store, and root_hash.
     /// Intended to be used for testing.
     pub fn new(
@@ -127,6 +146,58 @@ impl LmdbGlobalState {
     pub fn empty_state_root_hash(&self) -> Digest {
         self.empty_root_hash
     }
+
+    /// Checks the integrity of all tries reachable from the given state roots.
+    ///
+    /// Returns every fault found, or an empty vector if the tries are intact.
+    pub fn check_integrity(
+        &self,
+        correlation_id: CorrelationId,
+        state_roots: Vec<Digest>,
+    ) -> Result<Vec<TrieIntegrityFault>, error::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let faults = check_integrity::<
+            Key,
+            StoredValue,
+            lmdb::RoTransaction,
+            LmdbTrieStore,
+            error::Error,
+        >(correlation_id, &txn, self.trie_store.deref(), state_roots)?;
+        txn.commit()?;
+        Ok(faults)
+    }
+
+    /// Copies all tries into a new environment created in the directory at `path`.
+    ///
+    /// The tries are read within a single read transaction, so the copy is consistent even while
+    /// new tries are being written, and only live records are written, so the copy is compacted.
+    ///
+    /// Returns the number of tries copied.
+    pub fn copy_to<P: AsRef<Path>>(&self, path: P) -> Result<usize, error::Error> {
+        let map_size = self.environment.env().info()?.map_size();
+        let destination = LmdbEnvironment::new(path, map_size, 1, false)?;
+        let destination_store = LmdbTrieStore::new(&destination, None, DatabaseFlags::empty())?;
+
+        let txn = self.environment.create_read_txn()?;
+        let mut cursor = lmdb::Transaction::open_ro_cursor(&txn, self.trie_store.get_db())?;
+        let mut destination_txn = destination.create_read_write_txn()?;
+        let mut copied = 0;
+        for row in cursor.iter() {
+            let (key, value) = row?;
+            // Both databases use the same key ordering, so we can append.
+            destination_txn.put(destination_store.get_db(), &key, &value, WriteFlags::APPEND)?;
+            copied += 1;
+            if copied % COPY_BATCH_SIZE == 0 {
+                destination_txn.commit()?;
+                destination_txn = destination.create_read_write_txn()?;
+            }
+        }
+        destination_txn.commit()?;
+        drop(cursor);
+        txn.commit()?;
+        destination.env().sync(true)?;
+        Ok(copied)
+    }
 }
 
 fn compute_empty_root_hash() -> Result<(Digest, Trie<Key, StoredValue>), error::Error> {
@@ -146,6 +146,30 @@ impl LmdbEnvironment {
         })
     }
 
+    /// Opens an existing `LmdbEnvironment` in read-only mode.
+    ///
+    /// Any attempt to begin a read-write transaction on the returned environment will fail.
+    pub fn open_read_only<P: AsRef<Path>>(
+        path: P,
+        map_size: usize,
+        max_readers: u32,
+    ) -> Result<Self, error::Error> {
+        let env = Environment::new()
+            .set_flags(
+                EnvironmentFlags::NO_SUB_DIR
+                    | EnvironmentFlags::NO_READAHEAD
+                    | EnvironmentFlags::READ_ONLY,
+            )
+            .set_max_dbs(MAX_DBS)
+            .set_map_size(map_size)
+            .set_max_readers(max_readers)
+            .open(&path.as_ref().join(EE_DB_FILENAME))?;
+        Ok(LmdbEnvironment {
+            env,
+            manual_sync_enabled: false,
+        })
+    }
+
     /// Returns a reference to the wrapped `Environment`.
     pub fn env(&self) -> &Environment {
         &self.env
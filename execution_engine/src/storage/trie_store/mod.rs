@@ -12,6 +12,8 @@ use casper_hashing::Digest;
 
 use crate::storage::{store::Store, trie::Trie};
 
+pub use operations::TrieIntegrityFault;
+
 const NAME: &str = "TRIE_STORE";
 
 /// An entity which persists [`Trie`] values at their hashes.
@@ -1,9 +1,14 @@
 #[cfg(test)]
 mod tests;
 
-#[cfg(test)]
-use std::collections::HashSet;
-use std::{borrow::Cow, cmp, collections::VecDeque, convert::TryInto, mem};
+use std::{
+    borrow::Cow,
+    cmp,
+    collections::{HashSet, VecDeque},
+    convert::TryInto,
+    fmt::{self, Display, Formatter},
+    mem,
+};
 
 use either::Either;
 use num_traits::FromPrimitive;
@@ -1219,13 +1224,70 @@ where
     keys_with_prefix(correlation_id, txn, store, root, &[])
 }
 
-#[cfg(test)]
+/// A fault found in a trie store while checking its integrity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrieIntegrityFault {
+    /// The given state root is not a pointer block node.
+    InvalidStateRoot {
+        /// The state root hash.
+        state_root: Digest,
+    },
+    /// A trie referenced by a state root or by a parent trie is not in the store.
+    MissingTrie {
+        /// The key of the missing trie.
+        trie_key: Digest,
+    },
+    /// A stored trie does not hash to the key under which it is stored.
+    CorruptedValue {
+        /// The key under which the trie is stored.
+        trie_key: Digest,
+        /// The actual hash of the stored trie.
+        hash_of_value: Digest,
+    },
+    /// The key of a leaf does not start with the path leading to it.
+    CorruptedAffix {
+        /// The key under which the leaf is stored.
+        trie_key: Digest,
+    },
+}
+
+impl Display for TrieIntegrityFault {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            TrieIntegrityFault::InvalidStateRoot { state_root } => write!(
+                formatter,
+                "state root {} is not a pointer block node",
+                state_root
+            ),
+            TrieIntegrityFault::MissingTrie { trie_key } => {
+                write!(formatter, "missing trie key {}", trie_key)
+            }
+            TrieIntegrityFault::CorruptedValue {
+                trie_key,
+                hash_of_value,
+            } => write!(
+                formatter,
+                "trie key {} has corrupted value (hash of value is {})",
+                trie_key, hash_of_value
+            ),
+            TrieIntegrityFault::CorruptedAffix { trie_key } => write!(
+                formatter,
+                "trie key {} belongs to a leaf with a corrupted affix",
+                trie_key
+            ),
+        }
+    }
+}
+
+/// Walks all tries reachable from the given state roots and returns every integrity fault found.
+///
+/// Each reachable trie is visited once, even if it is shared by several state roots.
 pub fn check_integrity<K, V, T, S, E>(
     _correlation_id: CorrelationId,
     txn: &T,
     store: &S,
     trie_keys_to_visit: Vec<Digest>,
-) -> Result<(), E>
+) -> Result<Vec<TrieIntegrityFault>, E>
 where
     K: ToBytes + FromBytes + Eq + std::fmt::Debug,
     V: ToBytes + FromBytes + std::fmt::Debug,
@@ -1234,13 +1296,15 @@ where
     S::Error: From<T::Error>,
     E: From<S::Error> + From<bytesrepr::Error>,
 {
+    let mut faults = Vec::new();
     for state_root in &trie_keys_to_visit {
         match store.get(txn, state_root)? {
             Some(Trie::Node { .. }) => {}
-            other => panic!(
-                "Should have a pointer block node as state root but received {:?} instead",
-                other
-            ),
+            Some(_) => faults.push(TrieIntegrityFault::InvalidStateRoot {
+                state_root: *state_root,
+            }),
+            // Missing roots are reported when visited below.
+            None => {}
         }
     }
     let mut trie_keys_to_visit: Vec<(Vec<u8>, Digest)> = trie_keys_to_visit
@@ -1259,25 +1323,22 @@ where
                 Digest::hash(&node_bytes)
             };
             if trie_key != hash_of_trie_value {
-                panic!(
-                    "Trie key {:?} has corrupted value {:?} (hash of value is {:?})",
-                    trie_key, trie_value, hash_of_trie_value
-                );
+                faults.push(TrieIntegrityFault::CorruptedValue {
+                    trie_key,
+                    hash_of_value: hash_of_trie_value,
+                });
+                // Don't descend into a corrupted trie.
+                continue;
             }
         }
         match maybe_retrieved_trie {
-            // If we can't find the trie_key; it is missing and we'll return it
-            None => {
-                panic!("Missing trie key: {:?}", trie_key)
-            }
+            // If we can't find the trie_key; it is missing and we'll report it
+            None => faults.push(TrieIntegrityFault::MissingTrie { trie_key }),
             // If we could retrieve the node and it is a leaf, the search can move on
             Some(Trie::Leaf { key, .. }) => {
                 let key_bytes = key.to_bytes()?;
                 if !key_bytes.starts_with(&path) {
-                    panic!(
-                                "Trie key {:?} belongs to a leaf with a corrupted affix. Key bytes: {:?}, Path: {:?}.",
-                                trie_key, key_bytes, path
-                            );
+                    faults.push(TrieIntegrityFault::CorruptedAffix { trie_key });
                 }
             }
             // If we hit a pointer block, queue up all of the nodes it points to
@@ -1302,5 +1363,5 @@ where
             }
         }
     }
-    Ok(())
+    Ok(faults)
 }
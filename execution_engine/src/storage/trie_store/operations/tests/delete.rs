@@ -23,7 +23,13 @@ where
     assert_eq!(counter, 0, "Delete should never deserialize a value");
     let delete_result = delete_result?;
     if let DeleteResult::Deleted(new_root) = delete_result {
-        operations::check_integrity::<K, V, T, S, E>(correlation_id, txn, store, vec![new_root])?;
+        let faults = operations::check_integrity::<K, V, T, S, E>(
+            correlation_id,
+            txn,
+            store,
+            vec![new_root],
+        )?;
+        assert!(faults.is_empty(), "trie integrity faults: {:?}", faults);
     }
     Ok(delete_result)
 }
@@ -18,12 +18,14 @@ use regex::Regex;
 use stats_alloc::{StatsAlloc, INSTRUMENTED_SYSTEM};
 use structopt::StructOpt;
 use toml::{value::Table, Value};
-use tracing::info;
+use tracing::{info, warn};
+
+use casper_types::{AsymmetricType, PublicKey};
 
 use crate::{
-    components::network::Identity as NetworkIdentity,
+    components::{consensus, network::Identity as NetworkIdentity},
     logging,
-    reactor::{main_reactor, Runner},
+    reactor::{archive_reactor, main_reactor, Runner},
     setup_signal_hooks,
     types::{Chainspec, ChainspecRawBytes, ExitCode},
     utils::{Loadable, WithDir},
@@ -60,6 +62,26 @@ pub enum Cli {
         /// <SECTION>.<KEY>=<VALUE>.  For example, '-C=node.chainspec_config_path=chainspec.toml'
         config_ext: Vec<ConfigExt>,
     },
+    /// Run the node in archive mode.
+    ///
+    /// Opens the existing storage and global state read-only and serves them via the RPC, REST
+    /// and event stream servers only. The node does not join the network, take part in consensus
+    /// or sync, so the served data never changes.
+    Archive {
+        /// Path to configuration file.
+        config: PathBuf,
+
+        #[structopt(
+            short = "C",
+            long,
+            env = "NODE_CONFIG",
+            use_delimiter(true),
+            value_delimiter(";")
+        )]
+        /// Overrides and extensions for configuration file entries in the form
+        /// <SECTION>.<KEY>=<VALUE>.  For example, '-C=node.chainspec_config_path=chainspec.toml'
+        config_ext: Vec<ConfigExt>,
+    },
     /// Migrate modified values from the old config as required after an upgrade.
     MigrateConfig {
         /// Path to configuration file of previous version of node.
@@ -78,6 +100,93 @@ pub enum Cli {
         #[structopt(long)]
         new_config: PathBuf,
     },
+    /// Verify the integrity of the stored data.
+    ///
+    /// Re-hashes all stored blocks, checks parent links, finality signatures and deploys,
+    /// cross-checks the completed blocks index and walks the global state tries of all complete
+    /// blocks. Nothing is modified. Should be run while the node is stopped.
+    VerifyStorage {
+        /// Path to configuration file.
+        config: PathBuf,
+        /// Skip verifying the global state tries, which can take a long time.
+        #[structopt(long)]
+        skip_global_state: bool,
+    },
+    /// Run a remote signer holding a validator's secret key.
+    ///
+    /// Serves signing requests from a node configured with `consensus.remote_signer` on a UNIX
+    /// socket. Every signed message is recorded in a double-signing protection log first, and
+    /// requests to sign a message conflicting with a recorded one are refused.
+    RemoteSigner {
+        /// Path to the validator's secret key file.
+        #[structopt(long)]
+        secret_key: PathBuf,
+        /// Path of the UNIX socket to listen on.
+        #[structopt(long)]
+        socket: PathBuf,
+        /// Path to the double-signing protection log, created if it doesn't exist.
+        #[structopt(long)]
+        protection_log: PathBuf,
+    },
+    /// Export a double-signing protection log to JSON.
+    ///
+    /// The export can be imported on another host before moving the validator's key there, so
+    /// that it doesn't sign messages conflicting with ones signed on this host. Should be run
+    /// while the node or remote signer using the log is stopped.
+    ExportDoubleSignProtection {
+        /// Path to the double-signing protection log.
+        #[structopt(long)]
+        protection_log: PathBuf,
+        /// Path of the JSON file to write.
+        #[structopt(long)]
+        output: PathBuf,
+    },
+    /// Import an exported double-signing protection log.
+    ///
+    /// Adds the exported records to the given log, creating it if it doesn't exist. Should be run
+    /// while the node or remote signer using the log is stopped.
+    ImportDoubleSignProtection {
+        /// Path to the double-signing protection log.
+        #[structopt(long)]
+        protection_log: PathBuf,
+        /// Path of the JSON file to read.
+        #[structopt(long)]
+        input: PathBuf,
+    },
+    /// Simulate the consensus protocol with the validators, network and faults of a scenario.
+    ///
+    /// Runs a discrete event simulation of the given number of eras, using the chainspec the
+    /// scenario refers to, and prints the finality latency, throughput, rewards and faults.
+    SimulateConsensus {
+        /// Path to the scenario file.
+        scenario: PathBuf,
+        /// Print the report as JSON.
+        #[structopt(long)]
+        json: bool,
+    },
+    /// Inspect a Zug write-ahead log, and optionally remove a corrupted tail.
+    ///
+    /// Lists the entries, checks their signatures and order, and reports the rounds in which this
+    /// validator already signed an echo or vote. A validator whose WAL is deleted may sign
+    /// messages conflicting with these, so prefer removing a corrupted tail with `--truncate`
+    /// instead. Should be run while the node is stopped.
+    InspectZugWal {
+        /// Path to the WAL file, in the `unit_files` directory of the node's storage.
+        wal: PathBuf,
+        /// This validator's public key, in hex. Defaults to the one in the file name.
+        #[structopt(long, parse(try_from_str = parse_public_key))]
+        public_key: Option<PublicKey>,
+        /// The public key of one of the era's validators, in hex. Other validators' signatures can
+        /// only be verified if all of them are given.
+        #[structopt(long = "validator", parse(try_from_str = parse_public_key))]
+        validators: Vec<PublicKey>,
+        /// Remove unreadable bytes from the end of the file.
+        #[structopt(long)]
+        truncate: bool,
+        /// Print the report as JSON.
+        #[structopt(long)]
+        json: bool,
+    },
 }
 
 #[derive(Debug)]
@@ -141,6 +250,11 @@ impl FromStr for ConfigExt {
     }
 }
 
+/// Parses a hex-encoded public key given on the command line.
+fn parse_public_key(hex: &str) -> Result<PublicKey, String> {
+    PublicKey::from_hex(hex).map_err(|error| format!("invalid public key: {}", error))
+}
+
 impl Cli {
     /// Executes selected CLI command.
     pub async fn run(self) -> anyhow::Result<i32> {
@@ -191,6 +305,44 @@ impl Cli {
                 let exit_code = main_runner.run(&mut rng).await;
                 Ok(exit_code as i32)
             }
+            Cli::Archive { config, config_ext } => {
+                setup_signal_hooks();
+
+                let archive_config = Self::init(&config, config_ext)?;
+                let mut rng = crate::new_rng();
+                let registry = Registry::new();
+
+                let (chainspec, chainspec_raw_bytes) =
+                    <(Chainspec, ChainspecRawBytes)>::from_path(archive_config.dir())?;
+
+                info!(
+                    protocol_version = %chainspec.protocol_version(),
+                    build_version = %crate::VERSION_STRING.as_str(),
+                    "node starting up in archive mode"
+                );
+
+                if !chainspec.is_valid() {
+                    bail!("invalid chainspec");
+                }
+
+                // The archive node never connects to peers, so there is no need to load the
+                // configured identity.
+                let network_identity = NetworkIdentity::with_generated_certs()
+                    .context("failed to create a network identity")?;
+
+                let mut archive_runner = Runner::<archive_reactor::ArchiveReactor>::with_metrics(
+                    archive_config,
+                    Arc::new(chainspec),
+                    Arc::new(chainspec_raw_bytes),
+                    network_identity,
+                    &mut rng,
+                    &registry,
+                )
+                .await?;
+
+                let exit_code = archive_runner.run(&mut rng).await;
+                Ok(exit_code as i32)
+            }
             Cli::MigrateConfig {
                 old_config,
                 new_config,
@@ -235,6 +387,100 @@ impl Cli {
                 )?;
                 Ok(ExitCode::Success as i32)
             }
+            Cli::VerifyStorage {
+                config,
+                skip_global_state,
+            } => {
+                let config = Self::init(&config, vec![])?;
+
+                info!(build_version = %crate::VERSION_STRING.as_str(), "verifying storage");
+                if crate::storage_verification::verify_storage(config, skip_global_state)? {
+                    info!("no faults found");
+                    Ok(ExitCode::Success as i32)
+                } else {
+                    warn!("faults found, see log for details");
+                    Ok(ExitCode::Abort as i32)
+                }
+            }
+            Cli::RemoteSigner {
+                secret_key,
+                socket,
+                protection_log,
+            } => {
+                logging::init()?;
+
+                info!(build_version = %crate::VERSION_STRING.as_str(), "starting remote signer");
+                tokio::task::spawn_blocking(move || {
+                    consensus::run_remote_signer(&secret_key, &socket, &protection_log)
+                })
+                .await??;
+                Ok(ExitCode::Success as i32)
+            }
+            Cli::ExportDoubleSignProtection {
+                protection_log,
+                output,
+            } => {
+                logging::init()?;
+
+                let records = consensus::export_protection_log(&protection_log, &output)?;
+                info!(records, output = %output.display(), "exported double-signing protection log");
+                Ok(ExitCode::Success as i32)
+            }
+            Cli::ImportDoubleSignProtection {
+                protection_log,
+                input,
+            } => {
+                logging::init()?;
+
+                let outcome = consensus::import_protection_log(&protection_log, &input)?;
+                info!(
+                    imported = outcome.imported,
+                    duplicate = outcome.duplicate,
+                    too_old = outcome.too_old,
+                    "imported double-signing protection log"
+                );
+                if outcome.conflicting > 0 {
+                    warn!(
+                        conflicting = outcome.conflicting,
+                        "some imported records conflict with present ones, see log for details"
+                    );
+                    return Ok(ExitCode::Abort as i32);
+                }
+                Ok(ExitCode::Success as i32)
+            }
+            Cli::SimulateConsensus { scenario, json } => {
+                logging::init_to_stderr()?;
+
+                let report =
+                    tokio::task::spawn_blocking(move || consensus::simulate_consensus(&scenario))
+                        .await??;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else {
+                    print!("{}", report);
+                }
+                Ok(ExitCode::Success as i32)
+            }
+            Cli::InspectZugWal {
+                wal,
+                public_key,
+                validators,
+                truncate,
+                json,
+            } => {
+                logging::init_to_stderr()?;
+
+                let report = consensus::inspect_zug_wal(&wal, public_key, validators, truncate)?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else {
+                    print!("{}", report);
+                }
+                if report.has_unresolved_problems() {
+                    return Ok(ExitCode::Abort as i32);
+                }
+                Ok(ExitCode::Success as i32)
+            }
         }
     }
 
@@ -23,7 +23,10 @@ use super::*;
 use crate::{
     components::{
         block_synchronizer::block_acquisition::BlockAcquisitionState,
-        consensus::tests::utils::{ALICE_PUBLIC_KEY, ALICE_SECRET_KEY},
+        consensus::{
+            tests::utils::{ALICE_PUBLIC_KEY, ALICE_SECRET_KEY},
+            LocalSigner,
+        },
     },
     effect::Effect,
     reactor::{EventQueueHandle, QueueKind, Scheduler},
@@ -148,8 +151,7 @@ impl TestEnv {
             Ratio::new(1, 3),
             None,
             EraId::from(0),
-            self.validator_keys[0].clone(),
-            PublicKey::from(self.validator_keys[0].as_ref()),
+            Arc::new(LocalSigner::new(self.validator_keys[0].clone())),
             1,
         );
         validator_matrix
@@ -5,18 +5,24 @@
 mod cl_context;
 mod config;
 mod consensus_protocol;
+mod des;
 mod era_supervisor;
+mod finality_timeline;
 #[macro_use]
 mod highway_core;
 pub(crate) mod error;
 mod leader_sequence;
 mod metrics;
 mod protocols;
+mod signer;
+mod simulation;
 #[cfg(test)]
 pub(crate) mod tests;
 mod traits;
 pub(crate) mod utils;
 mod validator_change;
+mod validator_fault;
+mod validator_performance;
 
 use std::{
     borrow::Cow,
@@ -30,7 +36,8 @@ use derive_more::From;
 use serde::{Deserialize, Serialize};
 use tracing::{info, trace};
 
-use casper_types::{EraId, Timestamp};
+use casper_hashing::Digest;
+use casper_types::{EraId, Signature, Timestamp};
 
 use crate::{
     components::Component,
@@ -60,13 +67,23 @@ pub(crate) use cl_context::ClContext;
 pub(crate) use config::{ChainspecConsensusExt, Config};
 pub(crate) use consensus_protocol::{BlockContext, EraReport, ProposedBlock};
 pub(crate) use era_supervisor::{debug::EraDump, EraSupervisor, SerializedMessage};
+pub(crate) use finality_timeline::BlockTimelines;
 #[cfg(test)]
 pub(crate) use highway_core::highway::Vertex as HighwayVertex;
 pub(crate) use leader_sequence::LeaderSequence;
-pub(crate) use protocols::highway::max_rounds_per_era;
 #[cfg(test)]
 pub(crate) use protocols::highway::HighwayMessage;
+pub(crate) use protocols::{highway::max_rounds_per_era, zug::inspect_zug_wal};
+#[cfg(test)]
+pub(crate) use signer::LocalSigner;
+pub(crate) use signer::{
+    consensus_certificate_message, export_protection_log, import_protection_log, run_remote_signer,
+    sign_in_background, Signer, SignerError, SigningRequest,
+};
+pub(crate) use simulation::simulate_consensus;
 pub(crate) use validator_change::ValidatorChange;
+pub(crate) use validator_fault::ValidatorFault;
+pub(crate) use validator_performance::ValidatorPerformance;
 
 const COMPONENT_NAME: &str = "consensus";
 
@@ -115,12 +132,12 @@ pub(crate) struct ConsensusRequestMessage {
 
 /// An ID to distinguish different timers. What they are used for is specific to each consensus
 /// protocol implementation.
-#[derive(DataSize, Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[derive(DataSize, Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct TimerId(pub u8);
 
 /// An ID to distinguish queued actions. What they are used for is specific to each consensus
 /// protocol implementation.
-#[derive(DataSize, Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[derive(DataSize, Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct ActionId(pub u8);
 
 #[derive(DataSize, Debug, From)]
@@ -164,8 +181,17 @@ pub(crate) enum Event {
         header: Box<BlockHeader>,
         header_hash: BlockHash,
     },
+    /// A block has been marked complete: it is executed and has strict finality signatures.
+    BlockCompleted { height: u64 },
     /// The proposed block has been validated.
     ResolveValidity(ResolveValidity),
+    /// Our signature of a consensus message has been created, or the signer refused or failed to
+    /// sign it.
+    CreatedSignature {
+        era_id: EraId,
+        hash: Digest,
+        signature: Option<Signature>,
+    },
     /// Deactivate the era with the given ID, unless the number of faulty validators increases.
     DeactivateEra {
         era_id: EraId,
@@ -271,6 +297,9 @@ impl Display for Event {
                 "A block has been added to the linear chain: {}",
                 header_hash,
             ),
+            Event::BlockCompleted { height } => {
+                write!(f, "The block at height {} has been marked complete", height)
+            }
             Event::ResolveValidity(ResolveValidity {
                 era_id,
                 sender,
@@ -284,6 +313,17 @@ impl Display for Event {
                 if *valid { "valid" } else { "invalid" },
                 proposed_block,
             ),
+            Event::CreatedSignature {
+                era_id,
+                hash,
+                signature,
+            } => {
+                if signature.is_some() {
+                    write!(f, "created signature of {} in {}", hash, era_id)
+                } else {
+                    write!(f, "failed to sign {} in {}", hash, era_id)
+                }
+            }
             Event::DeactivateEra {
                 era_id, faulty_num, ..
             } => write!(
@@ -439,9 +479,18 @@ where
                 header,
                 header_hash: _,
             } => self.handle_block_added(effect_builder, rng, *header),
+            Event::BlockCompleted { height } => {
+                self.handle_block_completed(height);
+                Effects::new()
+            }
             Event::ResolveValidity(resolve_validity) => {
                 self.resolve_validity(effect_builder, rng, resolve_validity)
             }
+            Event::CreatedSignature {
+                era_id,
+                hash,
+                signature,
+            } => self.handle_created_signature(effect_builder, rng, era_id, hash, signature),
             Event::DeactivateEra {
                 era_id,
                 faulty_num,
@@ -452,6 +501,18 @@ where
                 let validator_changes = self.get_validator_changes();
                 responder.respond(validator_changes).ignore()
             }
+            Event::ConsensusRequest(ConsensusRequest::ValidatorFaults(responder)) => {
+                let validator_faults = self.get_validator_faults();
+                responder.respond(validator_faults).ignore()
+            }
+            Event::ConsensusRequest(ConsensusRequest::ValidatorPerformance(responder)) => {
+                let validator_performance = self.get_validator_performance();
+                responder.respond(validator_performance).ignore()
+            }
+            Event::ConsensusRequest(ConsensusRequest::FinalityTimeline(height, responder)) => {
+                let finality_timeline = self.get_finality_timeline(height);
+                responder.respond(finality_timeline).ignore()
+            }
             Event::DumpState(req @ DumpConsensusStateRequest { era_id, .. }) => {
                 let current_era = match self.current_era() {
                     None => {
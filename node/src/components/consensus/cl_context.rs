@@ -2,40 +2,41 @@ use std::sync::Arc;
 
 use datasize::DataSize;
 use serde::{Deserialize, Serialize};
-use tracing::info;
+use tracing::{error, info};
 
 use casper_hashing::Digest;
-use casper_types::{crypto, PublicKey, SecretKey, Signature};
+use casper_types::{crypto, EraId, PublicKey, SecretKey, Signature};
 
 use crate::{
-    components::consensus::traits::{ConsensusValueT, Context, ValidatorSecret},
+    components::consensus::{
+        signer::{LocalSigner, Signer, SigningRequest},
+        traits::{ConsensusValueT, Context, SigningInput, ValidatorSecret},
+    },
     types::BlockPayload,
 };
 
+/// A validator's signer, used to sign consensus messages in a particular era.
 #[derive(DataSize)]
 pub struct Keypair {
-    secret_key: Arc<SecretKey>,
-    public_key: PublicKey,
+    #[data_size(skip)]
+    signer: Arc<dyn Signer>,
+    era_id: EraId,
 }
 
 impl Keypair {
-    pub(crate) fn new(secret_key: Arc<SecretKey>, public_key: PublicKey) -> Self {
-        Self {
-            secret_key,
-            public_key,
-        }
+    pub(crate) fn new(signer: Arc<dyn Signer>, era_id: EraId) -> Self {
+        Self { signer, era_id }
     }
 
     #[cfg(test)]
     pub(crate) fn public_key(&self) -> &PublicKey {
-        &self.public_key
+        self.signer.public_key()
     }
 }
 
 impl From<Arc<SecretKey>> for Keypair {
     fn from(secret_key: Arc<SecretKey>) -> Self {
-        let public_key: PublicKey = secret_key.as_ref().into();
-        Self::new(secret_key, public_key)
+        Self::new(Arc::new(LocalSigner::new(secret_key)), EraId::new(0))
     }
 }
 
@@ -43,8 +44,23 @@ impl ValidatorSecret for Keypair {
     type Hash = Digest;
     type Signature = Signature;
 
-    fn sign(&self, hash: &Digest) -> Signature {
-        crypto::sign(hash, self.secret_key.as_ref(), &self.public_key)
+    fn sign(&self, input: &SigningInput<Digest>) -> Option<Signature> {
+        let request = SigningRequest::Consensus {
+            era_id: self.era_id,
+            context: input.context,
+            serialized: input.serialized.clone(),
+        };
+        match self.signer.sign(&request) {
+            Ok(signature) => Some(signature),
+            Err(error) => {
+                error!(%error, %request, "failed to sign consensus message");
+                None
+            }
+        }
+    }
+
+    fn signs_instantly(&self) -> bool {
+        self.signer.signs_instantly()
     }
 }
 
@@ -82,17 +98,13 @@ impl Context for ClContext {
 mod specimen_support {
     use super::Keypair;
     use crate::utils::specimen::{Cache, LargestSpecimen, SizeEstimator};
-    use casper_types::{PublicKey, SecretKey};
+    use casper_types::SecretKey;
     use std::sync::Arc;
 
     impl LargestSpecimen for Keypair {
         fn largest_specimen<E: SizeEstimator>(estimator: &E, cache: &mut Cache) -> Self {
             let secret_key = SecretKey::largest_specimen(estimator, cache);
-            let public_key = PublicKey::from(&secret_key);
-            Keypair {
-                secret_key: Arc::new(secret_key),
-                public_key,
-            }
+            Keypair::from(Arc::new(secret_key))
         }
     }
 }
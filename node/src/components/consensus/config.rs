@@ -1,4 +1,7 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use datasize::DataSize;
 use serde::{Deserialize, Serialize};
@@ -9,21 +12,46 @@ use crate::{
     components::consensus::{
         era_supervisor::PAST_EVIDENCE_ERAS,
         protocols::{highway::config::Config as HighwayConfig, zug::config::Config as ZugConfig},
+        signer::{
+            DoubleSignProtection, LocalSigner, ProtectedSigner, RemoteSigner, RemoteSignerConfig,
+            Signer, SignerError,
+        },
         EraId,
     },
     types::Chainspec,
-    utils::{External, LoadError, Loadable},
+    utils::External,
 };
 
 const DEFAULT_MAX_EXECUTION_DELAY: u64 = 3;
 
+/// The file name of the double-signing protection log in the secret key's directory.
+const DOUBLE_SIGN_PROTECTION_FILE_NAME: &str = "double_sign_protection.log";
+
 /// Consensus configuration.
 #[derive(DataSize, Debug, Serialize, Deserialize, Clone)]
 // Disallow unknown fields to ensure config files and command-line overrides contain valid keys.
 #[serde(deny_unknown_fields)]
 pub struct Config {
     /// Path to secret key file.
+    ///
+    /// Ignored if a remote signer is configured.
     pub secret_key_path: External,
+    /// Path to the double-signing protection log, relative to the config directory.
+    ///
+    /// Defaults to a file next to the secret key. Ignored if a remote signer is configured.
+    #[serde(default)]
+    pub double_sign_protection_path: Option<PathBuf>,
+    /// Whether to create a new double-signing protection log if it is missing even though our key
+    /// is in a recent validator set.
+    ///
+    /// Otherwise the node refuses to start in that case, since it could sign messages conflicting
+    /// with ones recorded in the lost log. Only set this if the key has never signed anything
+    /// without the protection, e.g. when first upgrading to a version with the log.
+    #[serde(default)]
+    pub create_missing_double_sign_protection: bool,
+    /// Remote signer configuration. If given, all messages are signed by the remote signer.
+    #[serde(default)]
+    pub remote_signer: Option<RemoteSignerConfig>,
     /// The maximum number of blocks by which execution is allowed to lag behind finalization.
     /// If it is more than that, consensus will pause, and resume once the executor has caught up.
     pub max_execution_delay: u64,
@@ -39,6 +67,9 @@ impl Default for Config {
     fn default() -> Self {
         Config {
             secret_key_path: External::Missing,
+            double_sign_protection_path: None,
+            create_missing_double_sign_protection: false,
+            remote_signer: None,
             max_execution_delay: DEFAULT_MAX_EXECUTION_DELAY,
             highway: HighwayConfig::default(),
             zug: ZugConfig::default(),
@@ -46,17 +77,46 @@ impl Default for Config {
     }
 }
 
-type LoadKeyError = LoadError<<Arc<SecretKey> as Loadable>::Error>;
-
 impl Config {
-    /// Loads the secret key from the configuration file and derives the public key.
-    pub(crate) fn load_keys<P: AsRef<Path>>(
+    /// Creates the signer for our messages: a remote signer if configured, otherwise a local
+    /// signer using the secret key from the configuration file.
+    ///
+    /// The local signer is protected by the double-signing protection log; the remote signer
+    /// keeps its own. If the log is missing although `is_recent_validator` returns `true` for our
+    /// public key, an error is returned unless `create_missing_double_sign_protection` is set.
+    pub(crate) fn load_signer<P, F>(
         &self,
         root: P,
-    ) -> Result<(Arc<SecretKey>, PublicKey), LoadKeyError> {
+        is_recent_validator: F,
+    ) -> Result<Arc<dyn Signer>, SignerError>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&PublicKey) -> bool,
+    {
+        let root = root.as_ref();
+        if let Some(remote_signer_config) = &self.remote_signer {
+            let remote_signer = RemoteSigner::connect(remote_signer_config, root)?;
+            return Ok(Arc::new(remote_signer));
+        }
         let secret_signing_key: Arc<SecretKey> = self.secret_key_path.clone().load(root)?;
-        let public_key: PublicKey = PublicKey::from(secret_signing_key.as_ref());
-        Ok((secret_signing_key, public_key))
+        let protection_path = match (&self.double_sign_protection_path, &self.secret_key_path) {
+            (Some(path), _) => root.join(path),
+            (None, External::Path(secret_key_path)) => root
+                .join(secret_key_path)
+                .with_file_name(DOUBLE_SIGN_PROTECTION_FILE_NAME),
+            (None, External::Missing) => root.join(DOUBLE_SIGN_PROTECTION_FILE_NAME),
+        };
+        if !protection_path.exists()
+            && !self.create_missing_double_sign_protection
+            && is_recent_validator(&PublicKey::from(&*secret_signing_key))
+        {
+            return Err(SignerError::MissingProtectionLog(protection_path));
+        }
+        let protection = DoubleSignProtection::open(&protection_path)?;
+        Ok(Arc::new(ProtectedSigner::new(
+            LocalSigner::new(secret_signing_key),
+            protection,
+        )))
     }
 }
 
@@ -105,3 +165,46 @@ impl ChainspecConsensusExt for Chainspec {
             .saturating_add(1)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use casper_types::SecretKey;
+
+    use super::{Config, DOUBLE_SIGN_PROTECTION_FILE_NAME};
+    use crate::{components::consensus::signer::SignerError, utils::External};
+
+    #[test]
+    fn should_refuse_missing_protection_log_of_recent_validator() {
+        let mut rng = crate::new_rng();
+        let dir = tempfile::tempdir().unwrap();
+        let secret_key_path = dir.path().join("keys").join("secret_key.pem");
+        std::fs::create_dir(dir.path().join("keys")).unwrap();
+        SecretKey::random(&mut rng)
+            .to_file(&secret_key_path)
+            .unwrap();
+        let mut config = Config {
+            secret_key_path: External::Path(secret_key_path),
+            ..Config::default()
+        };
+
+        assert!(matches!(
+            config.load_signer(dir.path(), |_| true),
+            Err(SignerError::MissingProtectionLog(_))
+        ));
+        config.create_missing_double_sign_protection = true;
+        config.load_signer(dir.path(), |_| true).unwrap();
+        // The log is created next to the secret key, and from now on it is required.
+        let protection_path = dir
+            .path()
+            .join("keys")
+            .join(DOUBLE_SIGN_PROTECTION_FILE_NAME);
+        assert!(protection_path.exists());
+        config.create_missing_double_sign_protection = false;
+        config.load_signer(dir.path(), |_| true).unwrap();
+
+        // A node that wasn't a validator recently creates a new log.
+        std::fs::remove_file(&protection_path).unwrap();
+        config.load_signer(dir.path(), |_| false).unwrap();
+        assert!(protection_path.exists());
+    }
+}
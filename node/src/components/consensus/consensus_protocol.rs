@@ -12,7 +12,12 @@ use casper_hashing::Digest;
 use casper_types::{bytesrepr::ToBytes, TimeDiff, Timestamp};
 
 use crate::{
-    components::consensus::{traits::Context, ActionId, TimerId},
+    components::consensus::{
+        traits::{Context, SigningInput},
+        validator_fault::FaultEvidence,
+        validator_performance::ValidatorPerformance,
+        ActionId, TimerId,
+    },
     types::NodeId,
     NodeRng,
 };
@@ -231,6 +236,11 @@ pub(crate) enum ProtocolOutcome<C: Context> {
     /// This is used to inform the deploy buffer, so we don't propose the same deploys again.
     /// Does not need to be raised for proposals this node created itself.
     HandledProposedBlock(ProposedBlock<C>),
+    /// Request a signature of the given message.
+    ///
+    /// The domain logic should create the signature without blocking the protocol, and then call
+    /// `ConsensusProtocol::handle_signature` with the message's hash.
+    CreateSignature(SigningInput<C::Hash>),
 }
 
 /// An API for a single instance of the consensus.
@@ -276,6 +286,15 @@ pub(crate) trait ConsensusProtocol<C: Context>: Send {
     /// Proposes a new value for consensus.
     fn propose(&mut self, proposed_block: ProposedBlock<C>, now: Timestamp) -> ProtocolOutcomes<C>;
 
+    /// Adds our signature of the given hash, requested via `ProtocolOutcome::CreateSignature`, to
+    /// the message it belongs to. The signature is `None` if the signer refused or failed to sign.
+    fn handle_signature(
+        &mut self,
+        hash: C::Hash,
+        signature: Option<C::Signature>,
+        now: Timestamp,
+    ) -> ProtocolOutcomes<C>;
+
     /// Marks the `value` as valid or invalid, based on validation requested via
     /// `ProtocolOutcome::ValidateConsensusvalue`.
     fn resolve_validity(
@@ -315,6 +334,12 @@ pub(crate) trait ConsensusProtocol<C: Context>: Send {
     /// Returns the list of all validators that were observed as faulty in this consensus instance.
     fn validators_with_evidence(&self) -> Vec<&C::ValidatorId>;
 
+    /// Returns the evidence against all validators this instance has direct evidence for.
+    fn validator_faults(&self) -> Vec<FaultEvidence<C::ValidatorId>>;
+
+    /// Returns statistics about every validator's participation in this instance.
+    fn validator_performance(&self) -> BTreeMap<C::ValidatorId, ValidatorPerformance>;
+
     /// Returns whether this instance of a protocol is an active validator.
     fn is_active(&self) -> bool;
 
@@ -323,4 +348,8 @@ pub(crate) trait ConsensusProtocol<C: Context>: Send {
 
     // TODO: Make this less Highway-specific.
     fn next_round_length(&self) -> Option<TimeDiff>;
+
+    /// Returns the current timeout for proposals, if the protocol adapts it to the observed round
+    /// durations.
+    fn current_proposal_timeout(&self) -> Option<TimeDiff>;
 }
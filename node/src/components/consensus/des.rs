@@ -1,13 +1,48 @@
-use std::{cmp::Ordering, collections::BinaryHeap, fmt::Debug};
+//! Basic building blocks for discrete event simulations (DES) of the consensus protocols: a
+//! priority queue of messages, ordered by their delivery time.
+
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    fmt::{self, Debug, Display, Formatter},
+};
+
+use datasize::DataSize;
 
 use casper_types::Timestamp;
 
-use super::consensus_des_testing::{Message, ValidatorId};
+/// The ID of a simulated node.
+#[derive(Debug, Clone, DataSize, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub(crate) struct ValidatorId(pub(crate) u64);
+
+impl Display for ValidatorId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A message from a simulated node.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) struct Message<M: Clone + Debug> {
+    pub(crate) sender: ValidatorId,
+    pub(crate) payload: M,
+}
+
+impl<M: Clone + Debug> Message<M> {
+    pub(crate) fn new(sender: ValidatorId, payload: M) -> Self {
+        Message { sender, payload }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn payload(&self) -> &M {
+        &self.payload
+    }
+}
 
 pub(crate) trait MessageT: PartialEq + Eq + Ord + Clone + Debug {}
 impl<T> MessageT for T where T: PartialEq + Eq + Ord + Clone + Debug {}
 
-/// An entry in the message queue of the test network.
+/// An entry in the message queue of the simulated network.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub(crate) struct QueueEntry<M>
 where
@@ -118,6 +153,8 @@ where
         self.0.push(item)
     }
 
+    /// Drops all messages.
+    #[cfg(test)]
     pub(crate) fn clear(&mut self) {
         self.0.clear();
     }
@@ -31,7 +31,7 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use tracing::{debug, error, info, trace, warn};
 
 use casper_hashing::Digest;
-use casper_types::{AsymmetricType, EraId, PublicKey, SecretKey, TimeDiff, Timestamp};
+use casper_types::{AsymmetricType, EraId, PublicKey, Signature, TimeDiff, Timestamp};
 
 use crate::{
     components::{
@@ -41,8 +41,12 @@ use crate::{
                 ConsensusProtocol, EraReport, FinalizedBlock as CpFinalizedBlock, ProposedBlock,
                 ProtocolOutcome,
             },
+            finality_timeline::{BlockTimelines, FinalityTimeline},
             metrics::Metrics,
+            signer::{self, Signer, SigningRequest},
             validator_change::{ValidatorChange, ValidatorChanges},
+            validator_fault::ValidatorFault,
+            validator_performance::ValidatorPerformance,
             ActionId, ChainspecConsensusExt, Config, ConsensusMessage, ConsensusRequestMessage,
             Event, HighwayProtocol, NewBlockPayload, ReactorEventT, ResolveValidity, TimerId, Zug,
         },
@@ -95,7 +99,9 @@ pub struct EraSupervisor {
     /// Since eras at or before the most recent activation point are never instantiated, shortly
     /// after that there can temporarily be fewer than three entries in the map.
     open_eras: BTreeMap<EraId, Era>,
-    secret_signing_key: Arc<SecretKey>,
+    /// The signer for our consensus messages.
+    #[data_size(skip)]
+    signer: Arc<dyn Signer>,
     public_signing_key: PublicKey,
     chainspec: Arc<Chainspec>,
     config: Config,
@@ -110,6 +116,8 @@ pub struct EraSupervisor {
     next_executed_height: u64,
     #[data_size(skip)]
     metrics: Metrics,
+    /// The times at which recent blocks passed each stage from their proposal to strict finality.
+    finality_timeline: FinalityTimeline,
     /// The path to the folder where unit files will be stored.
     unit_files_folder: PathBuf,
     last_progress: Timestamp,
@@ -127,25 +135,26 @@ impl EraSupervisor {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         storage_dir: &Path,
-        secret_signing_key: Arc<SecretKey>,
-        public_signing_key: PublicKey,
+        signer: Arc<dyn Signer>,
         config: Config,
         chainspec: Arc<Chainspec>,
         registry: &Registry,
     ) -> Result<Self, Error> {
         let unit_files_folder = storage_dir.join("unit_files");
         std::fs::create_dir_all(&unit_files_folder)?;
+        let public_signing_key = signer.public_key().clone();
         info!(our_id = %public_signing_key, "EraSupervisor pubkey",);
         let metrics = Metrics::new(registry)?;
 
         let era_supervisor = Self {
             open_eras: Default::default(),
-            secret_signing_key,
+            signer,
             public_signing_key,
             chainspec,
             config,
             next_block_height: 0,
             metrics,
+            finality_timeline: FinalityTimeline::default(),
             unit_files_folder,
             next_executed_height: 0,
             last_progress: Timestamp::now(),
@@ -252,6 +261,44 @@ impl EraSupervisor {
         result
     }
 
+    /// Returns the evidence against all faulty validators in the open eras, by era.
+    pub(super) fn get_validator_faults(&self) -> Vec<ValidatorFault> {
+        self.open_eras
+            .iter()
+            .flat_map(|(era_id, era)| {
+                era.consensus
+                    .validator_faults()
+                    .into_iter()
+                    .map(move |fault_evidence| ValidatorFault::new(*era_id, fault_evidence))
+            })
+            .collect()
+    }
+
+    /// Returns the current era's ID and each validator's performance in it so far.
+    pub(super) fn get_validator_performance(
+        &self,
+    ) -> Option<(EraId, BTreeMap<PublicKey, ValidatorPerformance>)> {
+        let (era_id, era) = self.open_eras.iter().next_back()?;
+        Some((*era_id, era.consensus.validator_performance()))
+    }
+
+    /// Records that the block at the given height was marked complete, i.e. it was executed and
+    /// has strict finality.
+    pub(super) fn handle_block_completed(&mut self, height: u64) {
+        if let Some((since_executed, since_proposed)) = self
+            .finality_timeline
+            .strict_finality(height, Timestamp::now())
+        {
+            self.metrics
+                .timeline_strict_finality(since_executed, since_proposed);
+        }
+    }
+
+    /// Returns the timeline of the recent block at the given height, or of all recent blocks.
+    pub(super) fn get_finality_timeline(&self, maybe_height: Option<u64>) -> BlockTimelines {
+        self.finality_timeline.get(maybe_height)
+    }
+
     fn era_seed(booking_block_hash: BlockHash, key_block_seed: Digest) -> u64 {
         let result = Digest::hash_pair(booking_block_hash, key_block_seed).value();
         u64::from_le_bytes(result[0..std::mem::size_of::<u64>()].try_into().unwrap())
@@ -377,7 +424,7 @@ impl EraSupervisor {
             vec![]
         } else {
             info!(era = era_id.value(), %our_id, "start voting");
-            let secret = Keypair::new(self.secret_signing_key.clone(), our_id.clone());
+            let secret = Keypair::new(Arc::clone(&self.signer), era_id);
             let instance_id = self.era(era_id).consensus.instance_id();
             let unit_hash_file = self.unit_file(instance_id);
             self.era_mut(era_id).consensus.activate_validator(
@@ -486,6 +533,7 @@ impl EraSupervisor {
             %key_block_hash,
             %instance_id,
             %seed,
+            consensus_protocol = ?self.chainspec.core_config.consensus_protocol,
             era = era_id.value(),
             "starting era",
         );
@@ -499,7 +547,12 @@ impl EraSupervisor {
             .cloned()
             .collect();
 
-        // Create and insert the new era instance.
+        // Create and insert the new era instance. Eras up to an upgrade's activation point are
+        // never instantiated after the restart, so all open eras use the current chainspec's
+        // protocol. That is how an upgrade switches protocols: No protocol state is handed over.
+        // The new instance gets its validators from the immediate switch block like any other
+        // era, and the equivocators from the switch blocks' era reports. Rewards are computed from
+        // the era reports by the contract runtime, independently of the protocol.
         let (consensus, outcomes) = match self.chainspec.core_config.consensus_protocol {
             ConsensusProtocolName::Highway => HighwayProtocol::new_boxed(
                 instance_id,
@@ -633,6 +686,10 @@ impl EraSupervisor {
             }
             Some(era) => {
                 let outcomes = f(&mut *era.consensus, rng);
+                let proposal_timeout = era.consensus.current_proposal_timeout();
+                if self.current_era() == Some(era_id) {
+                    self.metrics.set_proposal_timeout(proposal_timeout);
+                }
                 self.handle_consensus_outcomes(effect_builder, rng, era_id, outcomes)
             }
         }
@@ -779,6 +836,15 @@ impl EraSupervisor {
                     warn!(era = era_id.value(), "new block payload in outdated era");
                     return Effects::new();
                 }
+                let height = self
+                    .era(era_id)
+                    .start_height
+                    .saturating_add(block_context.height());
+                self.finality_timeline.proposal_received(
+                    height,
+                    block_context.timestamp(),
+                    Timestamp::now(),
+                );
                 let proposed_block = ProposedBlock::new(block_payload, block_context);
                 self.delegate_to_era(effect_builder, rng, era_id, move |consensus, _| {
                     consensus.propose(proposed_block, Timestamp::now())
@@ -796,6 +862,13 @@ impl EraSupervisor {
         self.next_executed_height = self
             .next_executed_height
             .max(block_header.height().saturating_add(1));
+        if let Some(since_finalized) = self.finality_timeline.executed(
+            block_header.height(),
+            block_header.block_hash(),
+            Timestamp::now(),
+        ) {
+            self.metrics.timeline_executed(since_finalized);
+        }
         let era_id = block_header.era_id();
         let mut effects = self.update_consensus_pause(effect_builder, rng, era_id);
 
@@ -905,6 +978,19 @@ impl EraSupervisor {
         effects
     }
 
+    pub(super) fn handle_created_signature<REv: ReactorEventT>(
+        &mut self,
+        effect_builder: EffectBuilder<REv>,
+        rng: &mut NodeRng,
+        era_id: EraId,
+        hash: Digest,
+        signature: Option<Signature>,
+    ) -> Effects<Event> {
+        self.delegate_to_era(effect_builder, rng, era_id, |consensus, _| {
+            consensus.handle_signature(hash, signature, Timestamp::now())
+        })
+    }
+
     pub(crate) fn last_progress(&self) -> Timestamp {
         self.last_progress
     }
@@ -1082,6 +1168,10 @@ impl EraSupervisor {
                         )
                     })
                     .collect();
+                // At the end of the era, record how each validator participated in it.
+                let validator_performance = era_report
+                    .is_some()
+                    .then(|| era.consensus.validator_performance());
                 if let Some(era_report) = era_report.as_ref() {
                     info!(
                         inactive = ?era_report.inactive_validators,
@@ -1105,10 +1195,26 @@ impl EraSupervisor {
                     "finalized block"
                 );
                 self.metrics.finalized_block(&finalized_block);
+                let block_timeline = self.finality_timeline.finalized(
+                    era_id,
+                    finalized_block.height(),
+                    timestamp,
+                    Timestamp::now(),
+                );
+                self.metrics.timeline_finalized(block_timeline);
                 // Announce the finalized block.
                 let mut effects = effect_builder
                     .announce_finalized_block(finalized_block.clone())
                     .ignore();
+                if let Some(validator_performance) = validator_performance {
+                    self.metrics
+                        .set_validator_performance(&validator_performance);
+                    effects.extend(
+                        effect_builder
+                            .put_validator_performance_to_storage(era_id, validator_performance)
+                            .ignore(),
+                    );
+                }
                 self.next_block_height = self.next_block_height.max(finalized_block.height() + 1);
                 // Request execution of the finalized block.
                 effects.extend(
@@ -1136,6 +1242,12 @@ impl EraSupervisor {
                     .filter(|pub_key| !self.has_evidence(era_id, (*pub_key).clone()))
                     .cloned()
                     .collect();
+                let height = self.era(era_id).start_height + proposed_block.context().height();
+                self.finality_timeline.proposal_received(
+                    height,
+                    proposed_block.context().timestamp(),
+                    Timestamp::now(),
+                );
                 self.era_mut(era_id)
                     .add_block(proposed_block.clone(), missing_evidence.clone());
                 if let Some(deploy_hash) = proposed_block.contains_replay() {
@@ -1173,6 +1285,31 @@ impl EraSupervisor {
             ProtocolOutcome::HandledProposedBlock(proposed_block) => effect_builder
                 .announce_proposed_block(proposed_block)
                 .ignore(),
+            ProtocolOutcome::CreateSignature(input) => {
+                // Signing can block, e.g. while waiting for a remote signer, so it runs in the
+                // background and the signature is passed back to the protocol as an event.
+                let hash = input.hash;
+                let request = SigningRequest::Consensus {
+                    era_id,
+                    context: input.context,
+                    serialized: input.serialized,
+                };
+                let signer = Arc::clone(&self.signer);
+                signer::sign_in_background(signer, request.clone()).event(move |result| {
+                    let signature = match result {
+                        Ok(signature) => Some(signature),
+                        Err(error) => {
+                            error!(%error, %request, "failed to sign consensus message");
+                            None
+                        }
+                    };
+                    Event::CreatedSignature {
+                        era_id,
+                        hash,
+                        signature,
+                    }
+                })
+            }
             ProtocolOutcome::NewEvidence(pub_key) => {
                 info!(%pub_key, era = era_id.value(), "validator equivocated");
                 let mut effects = effect_builder
@@ -1244,7 +1381,7 @@ impl EraSupervisor {
 /// double-serialization of network messages, or serialization of unsuitable types.
 ///
 /// Note that this type fixates the encoding for all consensus implementations to one scheme.
-#[derive(Clone, DataSize, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[derive(Clone, DataSize, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 #[repr(transparent)]
 pub(crate) struct SerializedMessage(Vec<u8>);
@@ -15,6 +15,8 @@ use crate::components::consensus::{
     consensus_protocol::{ConsensusProtocol, ProposedBlock},
     protocols::{highway::HighwayProtocol, zug::Zug},
 };
+#[cfg(test)]
+use crate::types::chainspec::ConsensusProtocolName;
 
 const CASPER_ENABLE_DETAILED_CONSENSUS_METRICS_ENV_VAR: &str =
     "CASPER_ENABLE_DETAILED_CONSENSUS_METRICS";
@@ -149,6 +151,19 @@ impl Era {
     pub(crate) fn validators(&self) -> &BTreeMap<PublicKey, U512> {
         &self.validators
     }
+
+    /// Returns the consensus protocol this era's instance is running.
+    #[cfg(test)]
+    pub(crate) fn consensus_protocol(&self) -> Option<ConsensusProtocolName> {
+        let any_ref = self.consensus.as_any();
+        if any_ref.is::<HighwayProtocol<ClContext>>() {
+            Some(ConsensusProtocolName::Highway)
+        } else if any_ref.is::<Zug<ClContext>>() {
+            Some(ConsensusProtocolName::Zug)
+        } else {
+            None
+        }
+    }
 }
 
 impl DataSize for Era {
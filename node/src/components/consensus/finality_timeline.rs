@@ -0,0 +1,234 @@
+//! The times at which recent blocks passed each stage from their proposal to strict finality.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+};
+
+use datasize::DataSize;
+use serde::Serialize;
+
+use casper_types::{EraId, TimeDiff, Timestamp};
+
+use crate::types::BlockHash;
+
+/// The number of most recently finalized blocks whose timelines are kept.
+const MAX_BLOCKS: usize = 1000;
+
+/// The times at which a block passed each stage from its proposal to strict finality, as observed
+/// by this node.
+#[derive(Clone, DataSize, Debug, Serialize)]
+pub(crate) struct BlockTimeline {
+    /// The era the block belongs to.
+    pub(crate) era_id: EraId,
+    /// The block's height.
+    pub(crate) height: u64,
+    /// The block's hash, once it has been executed.
+    pub(crate) block_hash: Option<BlockHash>,
+    /// The block's timestamp, i.e. when it was proposed.
+    pub(crate) proposed: Timestamp,
+    /// When this node received or created the proposal, if it did so after the last restart.
+    pub(crate) proposal_received: Option<Timestamp>,
+    /// When the block was finalized by this node's consensus protocol instance.
+    pub(crate) finalized: Timestamp,
+    /// When the block was executed.
+    pub(crate) executed: Option<Timestamp>,
+    /// When the block was marked complete, after it was executed and enough finality signatures
+    /// were collected for strict finality.
+    pub(crate) strict_finality: Option<Timestamp>,
+}
+
+impl Display for BlockTimeline {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        let since = |maybe_time: Option<Timestamp>, maybe_earlier: Option<Timestamp>| match (
+            maybe_time,
+            maybe_earlier,
+        ) {
+            (Some(time), Some(earlier)) => format!("+{}", time.saturating_diff(earlier)),
+            (Some(time), None) => time.to_string(),
+            (None, _) => "-".to_string(),
+        };
+        write!(formatter, "block {} in {}", self.height, self.era_id)?;
+        if let Some(block_hash) = &self.block_hash {
+            write!(formatter, " ({})", block_hash)?;
+        }
+        write!(
+            formatter,
+            ": proposed {}, received {}, finalized {}, executed {}, strict finality {}",
+            self.proposed,
+            since(self.proposal_received, Some(self.proposed)),
+            since(
+                Some(self.finalized),
+                Some(self.proposal_received.unwrap_or(self.proposed))
+            ),
+            since(self.executed, Some(self.finalized)),
+            since(self.strict_finality, self.executed),
+        )
+    }
+}
+
+/// The timelines of a list of blocks, as returned by the diagnostics port.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct BlockTimelines(pub(crate) Vec<BlockTimeline>);
+
+impl Display for BlockTimelines {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(formatter, "no block timelines recorded");
+        }
+        for block_timeline in &self.0 {
+            writeln!(formatter, "{}", block_timeline)?;
+        }
+        Ok(())
+    }
+}
+
+/// Records the timelines of the most recently finalized blocks.
+#[derive(DataSize, Debug, Default)]
+pub(super) struct FinalityTimeline {
+    /// When the proposals of blocks that haven't been finalized yet were received, by height and
+    /// timestamp.
+    proposals_received: BTreeMap<(u64, Timestamp), Timestamp>,
+    /// The timelines of the most recently finalized blocks, by height.
+    blocks: BTreeMap<u64, BlockTimeline>,
+}
+
+impl FinalityTimeline {
+    /// Records that a proposal for a block at the given height was received or created.
+    pub(super) fn proposal_received(&mut self, height: u64, timestamp: Timestamp, now: Timestamp) {
+        if !self.blocks.contains_key(&height) {
+            self.proposals_received
+                .entry((height, timestamp))
+                .or_insert(now);
+        }
+    }
+
+    /// Records that the block at the given height was finalized, and returns its timeline.
+    pub(super) fn finalized(
+        &mut self,
+        era_id: EraId,
+        height: u64,
+        timestamp: Timestamp,
+        now: Timestamp,
+    ) -> &BlockTimeline {
+        let proposal_received = self.proposals_received.remove(&(height, timestamp));
+        // Proposals at this height that didn't get finalized are not needed anymore.
+        self.proposals_received = self
+            .proposals_received
+            .split_off(&(height.saturating_add(1), Timestamp::zero()));
+        while self.blocks.len() >= MAX_BLOCKS {
+            let _ = self.blocks.pop_first();
+        }
+        self.blocks.entry(height).or_insert(BlockTimeline {
+            era_id,
+            height,
+            block_hash: None,
+            proposed: timestamp,
+            proposal_received,
+            finalized: now,
+            executed: None,
+            strict_finality: None,
+        })
+    }
+
+    /// Records that the block at the given height was executed, and returns the time since it was
+    /// finalized. Returns `None` if the block's finalization wasn't recorded.
+    pub(super) fn executed(
+        &mut self,
+        height: u64,
+        block_hash: BlockHash,
+        now: Timestamp,
+    ) -> Option<TimeDiff> {
+        let block_timeline = self.blocks.get_mut(&height)?;
+        if block_timeline.executed.is_some() {
+            return None;
+        }
+        block_timeline.block_hash = Some(block_hash);
+        block_timeline.executed = Some(now);
+        Some(now.saturating_diff(block_timeline.finalized))
+    }
+
+    /// Records that the block at the given height has strict finality, and returns the time since
+    /// it was executed and since it was proposed. Returns `None` if the block's execution wasn't
+    /// recorded.
+    pub(super) fn strict_finality(
+        &mut self,
+        height: u64,
+        now: Timestamp,
+    ) -> Option<(TimeDiff, TimeDiff)> {
+        let block_timeline = self.blocks.get_mut(&height)?;
+        let executed = block_timeline.executed?;
+        if block_timeline.strict_finality.is_some() {
+            return None;
+        }
+        block_timeline.strict_finality = Some(now);
+        Some((
+            now.saturating_diff(executed),
+            now.saturating_diff(block_timeline.proposed),
+        ))
+    }
+
+    /// Returns the timeline of the block at the given height, or of all recorded blocks, in order
+    /// of ascending height.
+    pub(super) fn get(&self, maybe_height: Option<u64>) -> BlockTimelines {
+        let block_timelines = match maybe_height {
+            Some(height) => self.blocks.get(&height).cloned().into_iter().collect(),
+            None => self.blocks.values().cloned().collect(),
+        };
+        BlockTimelines(block_timelines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_record_block_timeline() {
+        let mut timeline = FinalityTimeline::default();
+        let era_id = EraId::new(2);
+        let block_hash = BlockHash::default();
+        let timestamp = Timestamp::from(10_000);
+        let at = |millis: u64| timestamp + TimeDiff::from_millis(millis);
+
+        // Two competing proposals at height 5, and one at height 6.
+        timeline.proposal_received(5, timestamp, at(100));
+        timeline.proposal_received(5, at(50), at(150));
+        timeline.proposal_received(6, at(4_000), at(4_200));
+        // Receiving the same proposal again doesn't change the time it was first received.
+        timeline.proposal_received(5, timestamp, at(300));
+
+        // Height 5 can't be executed or have strict finality before it was finalized.
+        assert_eq!(timeline.executed(5, block_hash, at(1_000)), None);
+        assert_eq!(timeline.strict_finality(5, at(1_000)), None);
+
+        let block_timeline = timeline.finalized(era_id, 5, timestamp, at(2_000));
+        assert_eq!(block_timeline.proposal_received, Some(at(100)));
+        assert_eq!(block_timeline.finalized, at(2_000));
+        // Only the proposal at the next height is still pending.
+        assert_eq!(timeline.proposals_received.len(), 1);
+
+        assert_eq!(timeline.strict_finality(5, at(2_500)), None);
+        assert_eq!(
+            timeline.executed(5, block_hash, at(2_500)),
+            Some(TimeDiff::from_millis(500))
+        );
+        assert_eq!(timeline.executed(5, block_hash, at(2_600)), None);
+        assert_eq!(
+            timeline.strict_finality(5, at(3_000)),
+            Some((TimeDiff::from_millis(500), TimeDiff::from_millis(3_000)))
+        );
+
+        let block_timelines = timeline.get(Some(5)).0;
+        assert_eq!(block_timelines.len(), 1);
+        assert_eq!(block_timelines[0].block_hash, Some(block_hash));
+        assert_eq!(block_timelines[0].strict_finality, Some(at(3_000)));
+        assert!(timeline.get(Some(6)).0.is_empty());
+
+        // A block whose proposal was never seen is recorded without the time it was received.
+        let block_timeline = timeline.finalized(era_id, 6, at(5_000), at(6_000));
+        assert_eq!(block_timeline.proposal_received, None);
+        assert!(timeline.proposals_received.is_empty());
+        assert_eq!(timeline.get(None).0.len(), 2);
+    }
+}
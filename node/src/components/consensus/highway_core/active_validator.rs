@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     fmt::{self, Debug},
     fs::{self, File},
     io::{self, Read, Write},
@@ -14,7 +15,7 @@ use casper_types::{TimeDiff, Timestamp};
 use super::{
     endorsement::{Endorsement, SignedEndorsement},
     evidence::Evidence,
-    highway::{Ping, ValidVertex, Vertex, WireUnit},
+    highway::{HashedWireUnit, Ping, ValidVertex, Vertex, WireUnit},
     state::{self, Panorama, State, Unit},
     ENABLE_ENDORSEMENTS,
 };
@@ -22,7 +23,7 @@ use super::{
 use crate::components::consensus::{
     consensus_protocol::BlockContext,
     highway_core::{highway::SignedWireUnit, state::Fault},
-    traits::{Context, ValidatorSecret},
+    traits::{Context, SigningInput, ValidatorSecret},
     utils::{ValidatorIndex, Weight},
 };
 
@@ -40,6 +41,36 @@ pub(crate) enum Effect<C: Context> {
     ///
     /// When this is returned, the validator automatically deactivates.
     WeAreFaulty(Fault<C>),
+    /// Our new vertex needs to be signed, and `on_signature` needs to be called with the
+    /// signature.
+    RequestSignature(SigningInput<C::Hash>),
+}
+
+/// A vertex created by us that is waiting for its signature.
+#[derive(Clone, DataSize, Debug)]
+enum UnsignedVertex<C: Context> {
+    Unit(HashedWireUnit<C>),
+    Ping {
+        creator: ValidatorIndex,
+        timestamp: Timestamp,
+        instance_id: C::InstanceId,
+    },
+    Endorsement(Endorsement<C>),
+}
+
+impl<C: Context> UnsignedVertex<C> {
+    /// Returns the message that needs to be signed.
+    fn signing_input(&self) -> SigningInput<C::Hash> {
+        match self {
+            UnsignedVertex::Unit(hwunit) => hwunit.signing_input(),
+            UnsignedVertex::Ping {
+                creator,
+                timestamp,
+                instance_id,
+            } => Ping::<C>::signing_input(*creator, *timestamp, *instance_id),
+            UnsignedVertex::Endorsement(endorsement) => endorsement.signing_input(),
+        }
+    }
 }
 
 /// A validator that actively participates in consensus by creating new vertices.
@@ -80,6 +111,8 @@ where
     target_ftt: Weight,
     /// If this flag is set we don't create new units and just send pings instead.
     paused: bool,
+    /// Our vertices that are waiting for their signatures, by hash.
+    unsigned_vertices: BTreeMap<C::Hash, UnsignedVertex<C>>,
 }
 
 impl<C: Context> Debug for ActiveValidator<C> {
@@ -126,9 +159,10 @@ impl<C: Context> ActiveValidator<C> {
             own_last_unit,
             target_ftt,
             paused: false,
+            unsigned_vertices: BTreeMap::new(),
         };
         let mut effects = av.schedule_timer(start_time, state);
-        effects.push(av.send_ping(current_time, instance_id));
+        effects.extend(av.send_ping(current_time, instance_id));
         (av, effects)
     }
 
@@ -196,16 +230,14 @@ impl<C: Context> ActiveValidator<C> {
                 return effects;
             } else if timestamp == r_id + self.witness_offset(r_len) {
                 let panorama = self.panorama_at(state, timestamp);
-                if let Some(witness_unit) =
-                    self.new_unit(panorama, timestamp, None, state, instance_id)
-                {
+                if let Some(effect) = self.new_unit(panorama, timestamp, None, state, instance_id) {
                     if self
                         .latest_unit(state)
                         .map_or(true, |latest_unit| latest_unit.round_id() != r_id)
                     {
                         info!(round_id = %r_id, "sending witness in round with no proposal");
                     }
-                    effects.push(Effect::NewVertex(ValidVertex(Vertex::Unit(witness_unit))));
+                    effects.push(effect);
                     return effects;
                 }
             }
@@ -215,15 +247,22 @@ impl<C: Context> ActiveValidator<C> {
         let one_max_round_ago = timestamp.saturating_sub(state.params().max_round_length());
         if !state.has_ping(self.vidx, one_max_round_ago + TimeDiff::from_millis(1)) {
             warn!(%timestamp, "too many validators offline, sending ping");
-            effects.push(self.send_ping(timestamp, instance_id));
+            effects.extend(self.send_ping(timestamp, instance_id));
         }
         effects
     }
 
-    /// Creates a Ping vertex.
-    pub(crate) fn send_ping(&self, timestamp: Timestamp, instance_id: C::InstanceId) -> Effect<C> {
-        let ping = Ping::new(self.vidx, timestamp, instance_id, &self.secret);
-        Effect::NewVertex(ValidVertex(Vertex::Ping(ping)))
+    /// Creates a Ping vertex, unless the signer refused or failed to sign it.
+    pub(crate) fn send_ping(
+        &mut self,
+        timestamp: Timestamp,
+        instance_id: C::InstanceId,
+    ) -> Option<Effect<C>> {
+        self.sign(UnsignedVertex::Ping {
+            creator: self.vidx,
+            timestamp,
+            instance_id,
+        })
     }
 
     /// Returns whether enough validators are online to finalize values with the target fault
@@ -257,21 +296,43 @@ impl<C: Context> ActiveValidator<C> {
         if self.should_send_confirmation(uhash, now, state) {
             let panorama = state.confirmation_panorama(self.vidx, uhash);
             if panorama.has_correct() {
-                if let Some(confirmation_unit) =
-                    self.new_unit(panorama, now, None, state, instance_id)
-                {
-                    let vv = ValidVertex(Vertex::Unit(confirmation_unit));
-                    effects.push(Effect::NewVertex(vv));
-                }
+                effects.extend(self.new_unit(panorama, now, None, state, instance_id));
             }
         };
         if self.should_endorse(uhash, state) {
-            let endorsement = self.endorse(uhash);
-            effects.extend(vec![Effect::NewVertex(ValidVertex(endorsement))]);
+            effects.extend(self.endorse(uhash));
         }
         effects
     }
 
+    /// Returns actions a validator needs to take upon receiving the requested signature of our
+    /// vertex with the given hash, or `None` if the signer refused or failed to sign it.
+    pub(crate) fn on_signature(
+        &mut self,
+        hash: &C::Hash,
+        signature: Option<C::Signature>,
+        state: &State<C>,
+    ) -> Vec<Effect<C>> {
+        let unsigned = match self.unsigned_vertices.remove(hash) {
+            Some(unsigned) => unsigned,
+            None => {
+                warn!(%hash, "received signature for unknown vertex");
+                return vec![];
+            }
+        };
+        let signature = match signature {
+            Some(signature) => signature,
+            None => return vec![], // The signer already logged the reason.
+        };
+        if let UnsignedVertex::Unit(hwunit) = &unsigned {
+            if hwunit.wire_unit().panorama[self.vidx] != state.panorama()[self.vidx] {
+                error!(%hash, "signed unit would be equivocation; dropping it");
+                return vec![];
+            }
+        }
+        vec![self.add_signature(unsigned, signature)]
+    }
+
     /// Returns actions validator needs to take upon receiving a new evidence.
     /// Endorses all latest units by honest validators that do not mark new perpetrator as faulty
     /// and cite some new message by that validator.
@@ -290,8 +351,7 @@ impl<C: Context> ActiveValidator<C> {
                 let unit = state.unit(v);
                 unit.new_hash_obs(state, vidx)
             })
-            .map(|v| self.endorse(v))
-            .map(|endorsement| Effect::NewVertex(ValidVertex(endorsement)))
+            .filter_map(|v| self.endorse(v))
             .collect()
     }
 
@@ -313,9 +373,7 @@ impl<C: Context> ActiveValidator<C> {
         let maybe_parent_hash = state.fork_choice(&panorama);
         // If the parent is a terminal block, just create a unit without a new block.
         if maybe_parent_hash.map_or(false, |hash| state.is_terminal_block(hash)) {
-            return self
-                .new_unit(panorama, timestamp, None, state, instance_id)
-                .map(|proposal_unit| Effect::NewVertex(ValidVertex(Vertex::Unit(proposal_unit))));
+            return self.new_unit(panorama, timestamp, None, state, instance_id);
         }
         // Otherwise we need to request a new consensus value to propose.
         let ancestor_values = match maybe_parent_hash {
@@ -358,7 +416,6 @@ impl<C: Context> ActiveValidator<C> {
             return vec![];
         }
         self.new_unit(panorama, timestamp, Some(value), state, instance_id)
-            .map(|proposal_unit| Effect::NewVertex(ValidVertex(Vertex::Unit(proposal_unit))))
             .into_iter()
             .collect()
     }
@@ -407,7 +464,8 @@ impl<C: Context> ActiveValidator<C> {
         true
     }
 
-    /// Returns a new unit with the given data, and the correct sequence number.
+    /// Returns the effect creating a new unit with the given data, and the correct sequence
+    /// number.
     ///
     /// Returns `None` if it's not possible to create a valid unit with the given panorama.
     fn new_unit(
@@ -417,7 +475,7 @@ impl<C: Context> ActiveValidator<C> {
         value: Option<C::ConsensusValue>,
         state: &State<C>,
         instance_id: C::InstanceId,
-    ) -> Option<SignedWireUnit<C>> {
+    ) -> Option<Effect<C>> {
         if value.is_none() && !panorama.has_correct() {
             return None; // Wait for the first proposal before creating a unit without a value.
         }
@@ -425,6 +483,11 @@ impl<C: Context> ActiveValidator<C> {
             info!(?self.own_last_unit, "not voting - last own unit unknown");
             return None;
         }
+        if self.is_signing_unit() {
+            // Any other unit would be an equivocation.
+            info!("not voting - waiting for the signature of our last unit");
+            return None;
+        }
         if let Some((prop_context, _)) = self.next_proposal.take() {
             warn!(?prop_context, "canceling proposal due to unit");
         }
@@ -459,14 +522,65 @@ impl<C: Context> ActiveValidator<C> {
             endorsed,
         }
         .into_hashed();
-        let swunit = SignedWireUnit::new(hwunit, &self.secret);
-        write_last_unit(&self.unit_file, swunit.clone()).unwrap_or_else(|err| {
-            panic!(
-                "should successfully write unit's hash to {:?}, got {:?}",
-                self.unit_file, err
-            )
-        });
-        Some(swunit)
+        self.sign(UnsignedVertex::Unit(hwunit))
+    }
+
+    /// Returns whether one of our units is waiting for its signature.
+    fn is_signing_unit(&self) -> bool {
+        self.unsigned_vertices
+            .values()
+            .any(|unsigned| matches!(unsigned, UnsignedVertex::Unit(_)))
+    }
+
+    /// Returns the effect adding the signed vertex to the protocol state, unless the signer refused
+    /// or failed to sign it.
+    ///
+    /// If the signer could block, the vertex is kept until its signature is passed to
+    /// `on_signature`, and an effect requesting the signature is returned instead.
+    fn sign(&mut self, unsigned: UnsignedVertex<C>) -> Option<Effect<C>> {
+        let input = unsigned.signing_input();
+        if self.secret.signs_instantly() {
+            let signature = self.secret.sign(&input)?;
+            return Some(self.add_signature(unsigned, signature));
+        }
+        if self.unsigned_vertices.contains_key(&input.hash) {
+            return None; // We already requested the signature.
+        }
+        self.unsigned_vertices.insert(input.hash, unsigned);
+        Some(Effect::RequestSignature(input))
+    }
+
+    /// Returns the effect adding the vertex with the given signature to the protocol state.
+    fn add_signature(&mut self, unsigned: UnsignedVertex<C>, signature: C::Signature) -> Effect<C> {
+        let vertex = match unsigned {
+            UnsignedVertex::Unit(hashed_wire_unit) => {
+                let swunit = SignedWireUnit {
+                    hashed_wire_unit,
+                    signature,
+                };
+                write_last_unit(&self.unit_file, swunit.clone()).unwrap_or_else(|err| {
+                    panic!(
+                        "should successfully write unit's hash to {:?}, got {:?}",
+                        self.unit_file, err
+                    )
+                });
+                Vertex::Unit(swunit)
+            }
+            UnsignedVertex::Ping {
+                creator,
+                timestamp,
+                instance_id,
+            } => Vertex::Ping(Ping::with_signature(
+                creator,
+                timestamp,
+                instance_id,
+                signature,
+            )),
+            UnsignedVertex::Endorsement(endorsement) => {
+                Vertex::Endorsements(SignedEndorsement::new(endorsement, signature).into())
+            }
+        };
+        Effect::NewVertex(ValidVertex(vertex))
     }
 
     /// Returns a `ScheduleTimer` effect for the next time we need to be called.
@@ -559,11 +673,11 @@ impl<C: Context> ActiveValidator<C> {
                 .any(|(vidx, _)| state.is_faulty(vidx) && unit.new_hash_obs(state, vidx))
     }
 
-    /// Creates endorsement of the `vhash`.
-    fn endorse(&self, vhash: &C::Hash) -> Vertex<C> {
-        let endorsement = Endorsement::new(*vhash, self.vidx);
-        let signature = self.secret.sign(&endorsement.hash());
-        Vertex::Endorsements(SignedEndorsement::new(endorsement, signature).into())
+    /// Creates endorsement of the `vhash`, unless the signer refused or failed to sign it.
+    fn endorse(&mut self, vhash: &C::Hash) -> Option<Effect<C>> {
+        self.sign(UnsignedVertex::Endorsement(Endorsement::new(
+            *vhash, self.vidx,
+        )))
     }
 
     /// Returns a panorama that is valid to use in our own unit at the given timestamp.
@@ -988,7 +1102,8 @@ mod tests {
             TEST_INSTANCE_ID,
         );
 
-        let ping = Vertex::Ping(Ping::new(ALICE, 500.into(), TEST_INSTANCE_ID, &ALICE_SEC));
+        let ping =
+            Vertex::Ping(Ping::new(ALICE, 500.into(), TEST_INSTANCE_ID, &ALICE_SEC).unwrap());
 
         // The ping is suspicious if it is newer than the latest ping (or unit) that has been added
         // to the state.
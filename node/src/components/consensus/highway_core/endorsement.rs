@@ -2,7 +2,10 @@ use datasize::DataSize;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::components::consensus::{traits::Context, utils::ValidatorIndex};
+use crate::components::consensus::{
+    traits::{Context, SigningContext, SigningInput},
+    utils::{self, ValidatorIndex},
+};
 
 /// An error due to an invalid endorsement.
 #[derive(Debug, Error, Eq, PartialEq)]
@@ -43,9 +46,28 @@ impl<C: Context> Endorsement<C> {
     }
 
     pub(crate) fn hash(&self) -> C::Hash {
-        <C as Context>::hash(
-            &bincode::serialize(&(self.unit, self.creator)).expect("serialize endorsement"),
-        )
+        <C as Context>::hash(&self.serialize_fields())
+    }
+
+    /// Returns the message to be signed by the endorsement's creator.
+    pub(crate) fn signing_input(&self) -> SigningInput<C::Hash> {
+        let serialized = self.serialize_fields();
+        SigningInput {
+            hash: <C as Context>::hash(&serialized),
+            serialized,
+            context: SigningContext::HighwayEndorsement,
+        }
+    }
+
+    /// Returns what kind of message `serialized` is, if it is a serialized endorsement.
+    pub(crate) fn signing_context_of(serialized: &[u8]) -> Option<SigningContext> {
+        utils::deserialize_exact::<(C::Hash, ValidatorIndex)>(serialized)?;
+        Some(SigningContext::HighwayEndorsement)
+    }
+
+    /// Serializes the fields the endorsement's hash is computed from.
+    fn serialize_fields(&self) -> Vec<u8> {
+        bincode::serialize(&(self.unit, self.creator)).expect("serialize endorsement")
     }
 }
 
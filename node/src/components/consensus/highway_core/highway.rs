@@ -5,7 +5,11 @@ pub(crate) use vertex::{
     Dependency, Endorsements, HashedWireUnit, Ping, SignedWireUnit, Vertex, WireUnit,
 };
 
-use std::path::PathBuf;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    iter,
+    path::PathBuf,
+};
 
 use datasize::DataSize;
 use thiserror::Error;
@@ -19,10 +23,12 @@ use crate::components::consensus::{
         active_validator::{ActiveValidator, Effect},
         endorsement::{Endorsement, EndorsementError},
         evidence::{Evidence, EvidenceError},
-        state::{Fault, Observation, State, UnitError},
+        state::{self, Fault, Observation, State, UnitError},
     },
-    traits::Context,
-    utils::{Validator, ValidatorIndex, Validators, Weight},
+    traits::{Context, SigningContext},
+    utils::{Validator, ValidatorIndex, ValidatorMap, Validators, Weight},
+    validator_fault::{FaultEvidence, FaultKind, FaultVerification},
+    validator_performance::{Lateness, ValidatorPerformance},
 };
 
 /// If a lot of rounds were skipped between two blocks, log at most this many.
@@ -150,6 +156,11 @@ where
     state: State<C>,
     /// The state of an active validator, who is participating and creating new vertices.
     active_validator: Option<ActiveValidator<C>>,
+    /// The time between each validator's units' timestamps and their arrival.
+    lateness: ValidatorMap<Lateness>,
+    /// The rounds, at minimum round length, that were skipped by a proposal by an honest leader,
+    /// i.e. that lie between the proposal's and its parent's timestamps.
+    skipped_rounds: BTreeSet<Timestamp>,
 }
 
 impl<C: Context> Highway<C> {
@@ -171,11 +182,14 @@ impl<C: Context> Highway<C> {
         let banned = validators.iter_banned_idx();
         let cannot_propose = validators.iter_cannot_propose_idx();
         let state = State::new(weights, params, banned, cannot_propose);
+        let lateness = validators.iter().map(|_| Lateness::default()).collect();
         Highway {
             instance_id,
             validators,
             state,
             active_validator: None,
+            lateness,
+            skipped_rounds: BTreeSet::new(),
         }
     }
 
@@ -455,6 +469,18 @@ impl<C: Context> Highway<C> {
         })
     }
 
+    /// Adds the requested signature to our vertex with the given hash, and adds the vertex to the
+    /// protocol state.
+    pub(crate) fn on_signature(
+        &mut self,
+        hash: &C::Hash,
+        signature: Option<C::Signature>,
+        now: Timestamp,
+    ) -> Vec<Effect<C>> {
+        self.map_active_validator(|av, state| av.on_signature(hash, signature, state), now)
+            .unwrap_or_default()
+    }
+
     pub(crate) fn validators(&self) -> &Validators<C::ValidatorId> {
         &self.validators
     }
@@ -467,6 +493,87 @@ impl<C: Context> Highway<C> {
             .map(|(_, v_id)| v_id)
     }
 
+    /// Returns the evidence against every validator we have direct evidence for, re-verified.
+    pub(crate) fn validator_faults(&self) -> Vec<FaultEvidence<C::ValidatorId>> {
+        self.validators
+            .enumerate_ids()
+            .filter_map(|(vidx, v_id)| {
+                let evidence = self.state.maybe_evidence(vidx)?;
+                let verification = match evidence.validate(
+                    &self.validators,
+                    &self.instance_id,
+                    self.state.params(),
+                ) {
+                    Ok(()) => FaultVerification::Valid,
+                    Err(err) => FaultVerification::Invalid(err.to_string()),
+                };
+                let v_id = v_id.clone();
+                Some(match evidence {
+                    Evidence::Equivocation(unit1, unit2) => FaultEvidence::new(
+                        v_id,
+                        FaultKind::Equivocation,
+                        (unit1, unit2),
+                        evidence,
+                        verification,
+                    ),
+                    Evidence::Endorsements {
+                        endorsement1,
+                        endorsement2,
+                        ..
+                    } => FaultEvidence::new(
+                        v_id,
+                        FaultKind::ConflictingEndorsements,
+                        (endorsement1, endorsement2),
+                        evidence,
+                        verification,
+                    ),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns statistics about every validator's participation in this era.
+    ///
+    /// Units by validators known to be faulty are not counted.
+    pub(crate) fn validator_performance(&self) -> BTreeMap<C::ValidatorId, ValidatorPerformance> {
+        let state = &self.state;
+        let mut proposal_rounds = BTreeSet::new();
+        let mut performance = BTreeMap::new();
+        for (vidx, v_id) in self.validators.enumerate_ids() {
+            let mut validator_performance = ValidatorPerformance {
+                endorsements: state.endorsement_count(vidx),
+                average_lateness: self.lateness[vidx].average(),
+                ..ValidatorPerformance::default()
+            };
+            if let Some(latest_hash) = state.panorama()[vidx].correct() {
+                for (hash, unit) in state.swimlane(latest_hash) {
+                    validator_performance.units_and_votes =
+                        validator_performance.units_and_votes.saturating_add(1);
+                    if unit.block == *hash
+                        && unit.timestamp == unit.round_id()
+                        && state.leader(unit.timestamp) == vidx
+                    {
+                        validator_performance.proposals =
+                            validator_performance.proposals.saturating_add(1);
+                        proposal_rounds.insert(unit.timestamp);
+                    }
+                }
+            }
+            performance.insert(v_id.clone(), validator_performance);
+        }
+        for r_id in self.skipped_rounds.difference(&proposal_rounds) {
+            if let Some(validator_performance) = self
+                .validators
+                .id(state.leader(*r_id))
+                .and_then(|v_id| performance.get_mut(v_id))
+            {
+                validator_performance.missed_proposals =
+                    validator_performance.missed_proposals.saturating_add(1);
+            }
+        }
+        performance
+    }
+
     pub(crate) fn state(&self) -> &State<C> {
         &self.state
     }
@@ -545,7 +652,9 @@ impl<C: Context> Highway<C> {
                     result.extend(self.add_valid_vertex(vv.clone(), timestamp))
                 }
                 Effect::WeAreFaulty(_) => self.deactivate_validator(),
-                Effect::ScheduleTimer(_) | Effect::RequestNewBlock(_) => (),
+                Effect::ScheduleTimer(_)
+                | Effect::RequestNewBlock(_)
+                | Effect::RequestSignature(..) => (),
             }
         }
         result.extend(effects);
@@ -621,8 +730,10 @@ impl<C: Context> Highway<C> {
         let unit_hash = swunit.hash();
         let creator = swunit.wire_unit().creator;
         let was_honest = !self.state.is_faulty(creator);
+        self.lateness[creator].record(now.saturating_diff(swunit.wire_unit().timestamp));
         self.state.add_valid_unit(swunit);
         self.log_if_missing_proposal(&unit_hash);
+        self.record_skipped_rounds(&unit_hash);
         let mut evidence_effects = self
             .state
             .maybe_evidence(creator)
@@ -703,6 +814,51 @@ impl<C: Context> Highway<C> {
             .map(|av| av.next_round_length())
     }
 
+    /// If the unit is a proposal by an honest leader, records the rounds since its parent's
+    /// timestamp as skipped.
+    ///
+    /// A round only counts if it also starts a round at the skipped leader's own round length,
+    /// according to the leader's latest unit cited by the proposal: Otherwise that leader wasn't
+    /// supposed to propose at that time.
+    fn record_skipped_rounds(&mut self, unit_hash: &C::Hash) {
+        let state = &self.state;
+        let unit = state.unit(unit_hash);
+        let r_id = unit.round_id();
+        if unit.timestamp != r_id
+            || unit.block != *unit_hash
+            || state.leader(r_id) != unit.creator
+            || state.is_faulty(unit.creator)
+        {
+            return;
+        }
+        let parent_timestamp = if let Some(parent_hash) = state.block(unit_hash).parent() {
+            state.unit(parent_hash).timestamp
+        } else {
+            state.params().start_timestamp()
+        };
+        let is_leaders_round = |skipped_r_id: &Timestamp| {
+            unit.panorama[state.leader(*skipped_r_id)]
+                .correct()
+                .and_then(|leader_hash| {
+                    state
+                        .swimlane(leader_hash)
+                        .find(|(_, leader_unit)| leader_unit.timestamp <= *skipped_r_id)
+                })
+                .map_or(true, |(_, leader_unit)| {
+                    state::round_id(*skipped_r_id, leader_unit.round_len()) == *skipped_r_id
+                })
+        };
+        let min_round_length = state.params().min_round_length();
+        let skipped: Vec<_> = iter::successors(Some(r_id), |skipped_r_id| {
+            Some(skipped_r_id.saturating_sub(min_round_length))
+        })
+        .skip(1)
+        .take_while(|skipped_r_id| *skipped_r_id > parent_timestamp)
+        .filter(is_leaders_round)
+        .collect();
+        self.skipped_rounds.extend(skipped);
+    }
+
     /// Logs a message if this is a block and any previous blocks were skipped.
     fn log_if_missing_proposal(&self, unit_hash: &C::Hash) {
         let state = &self.state;
@@ -760,9 +916,27 @@ impl<C: Context> Highway<C> {
     }
 }
 
+/// Returns the kinds of Highway message that `serialized` could be the signed serialization of.
+///
+/// This is a unit, endorsement or ping, whichever `serialized` deserializes to exactly.
+pub(crate) fn signing_contexts_of<C: Context>(serialized: &[u8]) -> Vec<SigningContext> {
+    [
+        WireUnit::<C>::signing_context_of(serialized),
+        Endorsement::<C>::signing_context_of(serialized),
+        Ping::<C>::signing_context_of(serialized),
+    ]
+    .iter()
+    .flatten()
+    .copied()
+    .collect()
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
-    use std::{collections::BTreeSet, iter::FromIterator};
+    use std::{
+        collections::BTreeSet,
+        iter::{self, FromIterator},
+    };
 
     use casper_types::Timestamp;
 
@@ -774,10 +948,11 @@ pub(crate) mod tests {
                 WireUnit,
             },
             highway_testing::TEST_INSTANCE_ID,
-            state::{tests::*, Panorama, State},
+            state::{self, tests::*, Panorama, State},
         },
-        traits::ValidatorSecret,
+        traits::{SigningContext, ValidatorSecret},
         utils::Validators,
+        validator_performance::Lateness,
     };
 
     pub(crate) fn test_validators() -> Validators<u32> {
@@ -792,17 +967,26 @@ pub(crate) mod tests {
         Validators::from_iter(vid_weights)
     }
 
+    /// Returns a passive `Highway` instance with the test validators and the given state.
+    fn test_highway(state: State<TestContext>) -> Highway<TestContext> {
+        let validators = test_validators();
+        let lateness = validators.iter().map(|_| Lateness::default()).collect();
+        Highway {
+            instance_id: TEST_INSTANCE_ID,
+            validators,
+            state,
+            active_validator: None,
+            lateness,
+            skipped_rounds: BTreeSet::new(),
+        }
+    }
+
     #[test]
     fn invalid_signature_error() {
         let now: Timestamp = 500.into();
 
         let state: State<TestContext> = State::new_test(WEIGHTS, 0);
-        let mut highway = Highway {
-            instance_id: TEST_INSTANCE_ID,
-            validators: test_validators(),
-            state,
-            active_validator: None,
-        };
+        let mut highway = test_highway(state);
         let wunit = WireUnit {
             panorama: Panorama::new(WEIGHTS.len()),
             creator: CAROL,
@@ -824,7 +1008,15 @@ pub(crate) mod tests {
         assert_eq!(Err(expected), highway.pre_validate_vertex(invalid_vertex));
 
         let hwunit = wunit.into_hashed();
-        let valid_signature = CAROL_SEC.sign(&hwunit.hash());
+        let input = hwunit.signing_input();
+        assert_eq!(
+            input.context,
+            SigningContext::HighwayUnit {
+                seq_number: 0,
+                timestamp: Timestamp::zero(),
+            }
+        );
+        let valid_signature = CAROL_SEC.sign(&input).unwrap();
         let correct_signature_unit = SignedWireUnit {
             hashed_wire_unit: hwunit,
             signature: valid_signature,
@@ -854,12 +1046,7 @@ pub(crate) mod tests {
         let wunit_a = state.wire_unit(&a, TEST_INSTANCE_ID).unwrap();
         let wunit_b = state.wire_unit(&b, TEST_INSTANCE_ID).unwrap();
 
-        let mut highway = Highway {
-            instance_id: TEST_INSTANCE_ID,
-            validators: test_validators(),
-            state: State::new_test(WEIGHTS, 0),
-            active_validator: None,
-        };
+        let mut highway = test_highway(State::new_test(WEIGHTS, 0));
 
         let vertex_end_a = Vertex::Endorsements(end_a);
         let pvv_a = highway.pre_validate_vertex(Vertex::Unit(wunit_a)).unwrap();
@@ -906,21 +1093,16 @@ pub(crate) mod tests {
     #[test]
     fn invalid_evidence() {
         let state: State<TestContext> = State::new_test(WEIGHTS, 0);
-        let highway = Highway {
-            instance_id: TEST_INSTANCE_ID,
-            validators: test_validators(),
-            state,
-            active_validator: None,
-        };
+        let highway = test_highway(state);
 
         let validate = |wunit0: &WireUnit<TestContext>,
                         signer0: &TestSecret,
                         wunit1: &WireUnit<TestContext>,
                         signer1: &TestSecret| {
             let hwunit0 = wunit0.clone().into_hashed();
-            let swunit0 = SignedWireUnit::new(hwunit0, signer0);
+            let swunit0 = SignedWireUnit::new(hwunit0, signer0).unwrap();
             let hwunit1 = wunit1.clone().into_hashed();
-            let swunit1 = SignedWireUnit::new(hwunit1, signer1);
+            let swunit1 = SignedWireUnit::new(hwunit1, signer1).unwrap();
             let evidence = Evidence::Equivocation(swunit0, swunit1);
             let vertex = Vertex::Evidence(evidence);
             highway
@@ -1004,17 +1186,12 @@ pub(crate) mod tests {
         let now: Timestamp = 500.into();
 
         let state: State<TestContext> = State::new_test(WEIGHTS, 0);
-        let highway = Highway {
-            instance_id: TEST_INSTANCE_ID,
-            validators: test_validators(),
-            state,
-            active_validator: None,
-        };
+        let highway = test_highway(state);
 
         // Ping by validator that is not bonded, with an index that is outside of boundaries of the
         // state.
         let ping: Vertex<TestContext> =
-            Vertex::Ping(Ping::new(DAN, now, TEST_INSTANCE_ID, &DAN_SEC));
+            Vertex::Ping(Ping::new(DAN, now, TEST_INSTANCE_ID, &DAN_SEC).unwrap());
         assert!(
             DAN.0 >= WEIGHTS.len() as u32,
             "should use validator that is not bonded"
@@ -1030,19 +1207,70 @@ pub(crate) mod tests {
 
         let state: State<TestContext> = State::new_test(WEIGHTS, 0);
         let target_ftt = state.total_weight() / 3;
-        let mut highway = Highway {
-            instance_id: TEST_INSTANCE_ID,
-            validators: test_validators(),
-            state,
-            active_validator: None,
-        };
+        let mut highway = test_highway(state);
 
         let _effects =
             highway.activate_validator(ALICE.0, ALICE_SEC.clone(), now, None, target_ftt);
 
-        let ping = Vertex::Ping(Ping::new(ALICE, now, TEST_INSTANCE_ID, &ALICE_SEC));
+        let ping = Vertex::Ping(Ping::new(ALICE, now, TEST_INSTANCE_ID, &ALICE_SEC).unwrap());
         assert!(!highway.is_doppelganger_vertex(&ping));
-        let ping = Vertex::Ping(Ping::new(ALICE, later, TEST_INSTANCE_ID, &ALICE_SEC));
+        let ping = Vertex::Ping(Ping::new(ALICE, later, TEST_INSTANCE_ID, &ALICE_SEC).unwrap());
         assert!(highway.is_doppelganger_vertex(&ping));
     }
+
+    #[test]
+    fn validator_performance_counts_missed_rounds_at_the_leaders_round_length(
+    ) -> Result<(), AddUnitError<TestContext>> {
+        let mut state = State::new_test(WEIGHTS, 0);
+        let min_round_len = state.params().min_round_length();
+        let carol_round_len = min_round_len * 4;
+
+        // Carol uses four times the minimum round length, so she only leads the rounds that start
+        // at multiples of that.
+        let c0 = add_unit!(state, CAROL, 0, 2u8, None; N, N, N)?;
+        // Bob's block is the first one, so all earlier rounds were skipped.
+        let mut bob_round = Timestamp::from(1000);
+        bob_round = state::round_id(bob_round, min_round_len);
+        while state.leader(bob_round) != BOB {
+            bob_round += min_round_len;
+        }
+        let b0 = add_unit!(state, BOB, bob_round, 0u8, 0xB; N, N, c0)?;
+
+        let mut highway = test_highway(state);
+        highway.record_skipped_rounds(&c0);
+        highway.record_skipped_rounds(&b0);
+        let performance = highway.validator_performance();
+
+        let skipped_rounds: Vec<Timestamp> = iter::successors(Some(bob_round), |r_id| {
+            Some(r_id.saturating_sub(min_round_len))
+        })
+        .skip(1)
+        .take_while(|r_id| *r_id > Timestamp::zero())
+        .collect();
+        let missed = |vidx, round_len| {
+            skipped_rounds
+                .iter()
+                .filter(|r_id| {
+                    highway.state.leader(**r_id) == vidx
+                        && state::round_id(**r_id, round_len) == **r_id
+                })
+                .count() as u64
+        };
+        // Carol led some rounds at the minimum round length that weren't hers to propose in.
+        assert!(missed(CAROL, min_round_len) > missed(CAROL, carol_round_len));
+
+        let alice = &performance[&ALICE.0];
+        assert_eq!(alice.proposals, 0);
+        assert_eq!(alice.units_and_votes, 0);
+        assert_eq!(alice.missed_proposals, missed(ALICE, min_round_len));
+        let bob = &performance[&BOB.0];
+        assert_eq!(bob.proposals, 1);
+        assert_eq!(bob.units_and_votes, 1);
+        assert_eq!(bob.missed_proposals, missed(BOB, min_round_len));
+        let carol = &performance[&CAROL.0];
+        assert_eq!(carol.proposals, 0);
+        assert_eq!(carol.units_and_votes, 1);
+        assert_eq!(carol.missed_proposals, missed(CAROL, carol_round_len));
+        Ok(())
+    }
 }
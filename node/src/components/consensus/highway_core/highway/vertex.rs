@@ -5,14 +5,16 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use casper_types::Timestamp;
 
+#[cfg(test)]
+use crate::components::consensus::traits::ValidatorSecret;
 use crate::components::consensus::{
     highway_core::{
         endorsement::SignedEndorsement,
         highway::{PingError, VertexError},
         state::Panorama,
     },
-    traits::{Context, ValidatorSecret},
-    utils::{ValidatorIndex, Validators},
+    traits::{Context, SigningContext, SigningInput},
+    utils::{self, ValidatorIndex, Validators},
 };
 
 #[allow(clippy::integer_arithmetic)]
@@ -307,15 +309,17 @@ where
 }
 
 impl<C: Context> SignedWireUnit<C> {
+    /// Signs the unit, or returns `None` if the signer refused or failed to sign.
+    #[cfg(test)]
     pub(crate) fn new(
         hashed_wire_unit: HashedWireUnit<C>,
         secret_key: &C::ValidatorSecret,
-    ) -> Self {
-        let signature = secret_key.sign(&hashed_wire_unit.hash);
-        SignedWireUnit {
+    ) -> Option<Self> {
+        let signature = secret_key.sign(&hashed_wire_unit.signing_input())?;
+        Some(SignedWireUnit {
             hashed_wire_unit,
             signature,
-        }
+        })
     }
 
     pub(crate) fn wire_unit(&self) -> &WireUnit<C> {
@@ -358,6 +362,15 @@ where
         self.hash
     }
 
+    /// Returns the message to be signed by the unit's creator.
+    pub(crate) fn signing_input(&self) -> SigningInput<C::Hash> {
+        SigningInput {
+            hash: self.hash,
+            serialized: bincode::serialize(&self.wire_unit).expect("serialize WireUnit"),
+            context: self.wire_unit.signing_context(),
+        }
+    }
+
     /// Creates a new `HashedWireUnit`. Make sure the `hash` is correct, and identical with the
     /// result of `wire_unit.compute_hash`.
     pub(crate) fn new_with_hash(wire_unit: WireUnit<C>, hash: C::Hash) -> Self {
@@ -431,6 +444,19 @@ impl<C: Context> WireUnit<C> {
         self.panorama[self.creator].correct()
     }
 
+    /// Returns what kind of message the unit's signature is for.
+    pub(crate) fn signing_context(&self) -> SigningContext {
+        SigningContext::HighwayUnit {
+            seq_number: self.seq_number,
+            timestamp: self.timestamp,
+        }
+    }
+
+    /// Returns what kind of message `serialized` is, if it is a serialized unit.
+    pub(crate) fn signing_context_of(serialized: &[u8]) -> Option<SigningContext> {
+        utils::deserialize_exact::<Self>(serialized).map(|wunit| wunit.signing_context())
+    }
+
     /// Returns the unit's hash, which is used as a unit identifier.
     fn compute_hash(&self) -> C::Hash {
         // TODO: Use serialize_into to avoid allocation?
@@ -490,14 +516,30 @@ where
 }
 
 impl<C: Context> Ping<C> {
-    /// Creates a new signed ping.
+    /// Creates a new signed ping, or returns `None` if the signer refused or failed to sign.
+    #[cfg(test)]
     pub(crate) fn new(
         creator: ValidatorIndex,
         timestamp: Timestamp,
         instance_id: C::InstanceId,
         sk: &C::ValidatorSecret,
+    ) -> Option<Self> {
+        let signature = sk.sign(&Self::signing_input(creator, timestamp, instance_id))?;
+        Some(Self::with_signature(
+            creator,
+            timestamp,
+            instance_id,
+            signature,
+        ))
+    }
+
+    /// Creates a new ping with the given signature of its `hash`.
+    pub(crate) fn with_signature(
+        creator: ValidatorIndex,
+        timestamp: Timestamp,
+        instance_id: C::InstanceId,
+        signature: C::Signature,
     ) -> Self {
-        let signature = sk.sign(&Self::hash(creator, timestamp, instance_id));
         Ping {
             creator,
             timestamp,
@@ -540,8 +582,41 @@ impl<C: Context> Ping<C> {
     }
 
     /// Computes the hash of a ping, i.e. of the creator and timestamp.
-    fn hash(creator: ValidatorIndex, timestamp: Timestamp, instance_id: C::InstanceId) -> C::Hash {
-        let bytes = bincode::serialize(&(creator, timestamp, instance_id)).expect("serialize Ping");
-        <C as Context>::hash(&bytes)
+    pub(crate) fn hash(
+        creator: ValidatorIndex,
+        timestamp: Timestamp,
+        instance_id: C::InstanceId,
+    ) -> C::Hash {
+        <C as Context>::hash(&Self::serialize_fields(creator, timestamp, instance_id))
+    }
+
+    /// Returns the message to be signed by the ping's creator.
+    pub(crate) fn signing_input(
+        creator: ValidatorIndex,
+        timestamp: Timestamp,
+        instance_id: C::InstanceId,
+    ) -> SigningInput<C::Hash> {
+        let serialized = Self::serialize_fields(creator, timestamp, instance_id);
+        SigningInput {
+            hash: <C as Context>::hash(&serialized),
+            serialized,
+            context: SigningContext::HighwayPing { timestamp },
+        }
+    }
+
+    /// Returns what kind of message `serialized` is, if it is a serialized ping.
+    pub(crate) fn signing_context_of(serialized: &[u8]) -> Option<SigningContext> {
+        let (_, timestamp, _) =
+            utils::deserialize_exact::<(ValidatorIndex, Timestamp, C::InstanceId)>(serialized)?;
+        Some(SigningContext::HighwayPing { timestamp })
+    }
+
+    /// Serializes the fields the ping's hash is computed from.
+    fn serialize_fields(
+        creator: ValidatorIndex,
+        timestamp: Timestamp,
+        instance_id: C::InstanceId,
+    ) -> Vec<u8> {
+        bincode::serialize(&(creator, timestamp, instance_id)).expect("serialize Ping")
     }
 }
@@ -27,14 +27,12 @@ use super::{
 use crate::{
     components::consensus::{
         consensus_protocol::FinalizedBlock,
-        tests::{
-            consensus_des_testing::{
-                DeliverySchedule, Fault as DesFault, Message, Node, Target, TargetedMessage,
-                ValidatorId, VirtualNet,
-            },
-            queue::QueueEntry,
+        des::QueueEntry,
+        tests::consensus_des_testing::{
+            DeliverySchedule, Fault as DesFault, Message, Node, Target, TargetedMessage,
+            ValidatorId, VirtualNet,
         },
-        traits::{ConsensusValueT, Context, ValidatorSecret},
+        traits::{ConsensusValueT, Context, SigningInput, ValidatorSecret},
         utils::{Validators, Weight},
         BlockContext,
     },
@@ -122,6 +120,7 @@ impl From<Effect<TestContext>> for HighwayMessage {
             Effect::ScheduleTimer(t) => HighwayMessage::Timer(t),
             Effect::RequestNewBlock(block_context) => HighwayMessage::RequestBlock(block_context),
             Effect::WeAreFaulty(fault) => HighwayMessage::WeAreFaulty(Box::new(fault)),
+            Effect::RequestSignature(..) => unreachable!("test secrets sign instantly"),
         }
     }
 }
@@ -283,7 +282,7 @@ impl HighwayValidator {
                                 }
                                 let secret = TestSecret(wunit2.creator.0.into());
                                 let hwunit2 = wunit2.into_hashed();
-                                let swunit2 = SignedWireUnit::new(hwunit2, &secret);
+                                let swunit2 = SignedWireUnit::new(hwunit2, &secret).unwrap();
                                 let vertex2 = Box::new(Vertex::Unit(swunit2));
                                 vec![msg, HighwayMessage::NewVertex(vertex2)]
                             }
@@ -1037,8 +1036,8 @@ impl ValidatorSecret for TestSecret {
     type Hash = HashWrapper;
     type Signature = SignatureWrapper;
 
-    fn sign(&self, data: &Self::Hash) -> Self::Signature {
-        SignatureWrapper(data.0 + self.0)
+    fn sign(&self, input: &SigningInput<Self::Hash>) -> Option<Self::Signature> {
+        Some(SignatureWrapper(input.hash.0 + self.0))
     }
 }
 
@@ -316,6 +316,21 @@ impl<C: Context> State<C> {
         self.endorsements.contains_key(hash)
     }
 
+    /// Returns the number of units the validator has endorsed.
+    pub(crate) fn endorsement_count(&self, idx: ValidatorIndex) -> u64 {
+        let complete = self
+            .endorsements
+            .values()
+            .filter(|signatures| signatures[idx].is_some())
+            .count();
+        let incomplete = self
+            .incomplete_endorsements
+            .values()
+            .filter(|signatures| signatures.contains_key(&idx))
+            .count();
+        complete.saturating_add(incomplete) as u64
+    }
+
     /// Returns hash of unit that needs to be endorsed.
     pub(crate) fn needs_endorsements(&self, unit: &SignedWireUnit<C>) -> Option<C::Hash> {
         unit.wire_unit()
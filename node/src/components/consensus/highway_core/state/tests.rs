@@ -15,7 +15,7 @@ use crate::components::consensus::{
         highway::Dependency,
         highway_testing::{TEST_BLOCK_REWARD, TEST_ENDORSEMENT_EVIDENCE_LIMIT, TEST_INSTANCE_ID},
     },
-    traits::{ConsensusValueT, ValidatorSecret},
+    traits::{ConsensusValueT, SigningInput, ValidatorSecret},
 };
 
 pub(crate) const WEIGHTS: &[Weight] = &[Weight(3), Weight(4), Weight(5)];
@@ -47,8 +47,8 @@ impl ValidatorSecret for TestSecret {
     type Hash = u64;
     type Signature = u64;
 
-    fn sign(&self, data: &Self::Hash) -> Self::Signature {
-        data + u64::from(self.0)
+    fn sign(&self, input: &SigningInput<Self::Hash>) -> Option<Self::Signature> {
+        Some(input.hash + u64::from(self.0))
     }
 }
 
@@ -186,12 +186,12 @@ fn add_unit() -> Result<(), AddUnitError<TestContext>> {
         round_exp: 0u8,
         endorsed: BTreeSet::new(),
     };
-    let unit = SignedWireUnit::new(wunit.clone().into_hashed(), &BOB_SEC);
+    let unit = SignedWireUnit::new(wunit.clone().into_hashed(), &BOB_SEC).unwrap();
     let maybe_err = state.add_unit(unit).err().map(unit_err);
     assert_eq!(Some(UnitError::SequenceNumber), maybe_err);
     // Still not valid: This would be the third unit in the first round.
     wunit.seq_number = 2;
-    let unit = SignedWireUnit::new(wunit.into_hashed(), &BOB_SEC);
+    let unit = SignedWireUnit::new(wunit.into_hashed(), &BOB_SEC).unwrap();
     let maybe_err = state.add_unit(unit).err().map(unit_err);
     assert_eq!(Some(UnitError::ThreeUnitsInRound), maybe_err);
 
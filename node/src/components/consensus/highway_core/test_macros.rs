@@ -74,7 +74,7 @@ macro_rules! add_unit {
         };
         let hwunit = wunit.into_hashed();
         let hash = hwunit.hash();
-        let swunit = SignedWireUnit::new(hwunit, &TestSecret(($creator).0));
+        let swunit = SignedWireUnit::new(hwunit, &TestSecret(($creator).0)).unwrap();
         $state.add_unit(swunit).map(|()| hash)
     }};
     ($state: ident, $creator: expr, $time: expr, $round_exp: expr, $val: expr; $($obs:expr),*) => {{
@@ -102,7 +102,7 @@ macro_rules! add_unit {
         };
         let hwunit = wunit.into_hashed();
         let hash = hwunit.hash();
-        let swunit = SignedWireUnit::new(hwunit, &TestSecret(($creator).0));
+        let swunit = SignedWireUnit::new(hwunit, &TestSecret(($creator).0)).unwrap();
         $state.add_unit(swunit).map(|()| hash)
     }};
 }
@@ -121,7 +121,9 @@ macro_rules! endorse {
         };
 
         let endorsement: Endorsement<TestContext> = Endorsement::new($vote, ($creator));
-        let signature = TestSecret(($creator).0).sign(&endorsement.hash());
+        let signature = TestSecret(($creator).0)
+            .sign(&endorsement.signing_input())
+            .unwrap();
         let endorsements = SignedEndorsement::new(endorsement, signature).into();
         let evidence = $state.find_conflicting_endorsements(&endorsements, &TEST_INSTANCE_ID);
         $state.add_endorsements(endorsements);
@@ -1,8 +1,16 @@
-use prometheus::{Gauge, IntGauge, Registry};
+use std::collections::BTreeMap;
 
-use casper_types::Timestamp;
+use prometheus::{Gauge, Histogram, IntGauge, IntGaugeVec, Opts, Registry};
 
-use crate::{types::FinalizedBlock, unregister_metric};
+use casper_types::{AsymmetricType, PublicKey, TimeDiff, Timestamp};
+
+use super::{finality_timeline::BlockTimeline, ValidatorPerformance};
+use crate::{types::FinalizedBlock, unregister_metric, utils};
+
+// The stages of a block's timeline take between a fraction of a second and a few minutes.
+const TIMELINE_BUCKET_START: f64 = 0.05;
+const TIMELINE_BUCKET_FACTOR: f64 = 2.0;
+const TIMELINE_BUCKET_COUNT: usize = 14;
 
 /// Network metrics to track Consensus
 #[derive(Debug)]
@@ -17,6 +25,29 @@ pub(super) struct Metrics {
     time_of_last_finalized_block: IntGauge,
     /// The current era.
     pub(super) consensus_current_era: IntGauge,
+    /// The current era's proposal timeout, if the protocol adapts it.
+    proposal_timeout: IntGauge,
+    /// The number of proposals in the last completed era, by validator.
+    validator_proposals: IntGaugeVec,
+    /// The number of missed proposals in the last completed era, by validator.
+    validator_missed_proposals: IntGaugeVec,
+    /// The number of units or votes in the last completed era, by validator.
+    validator_units_and_votes: IntGaugeVec,
+    /// The number of endorsements in the last completed era, by validator.
+    validator_endorsements: IntGaugeVec,
+    /// The average lateness of messages in the last completed era, by validator.
+    validator_average_lateness: IntGaugeVec,
+    /// Time from a block's timestamp until this node received its proposal.
+    timeline_proposal_received: Histogram,
+    /// Time from receiving a block's proposal, or from its timestamp if it wasn't received, until
+    /// it was finalized.
+    timeline_finalized: Histogram,
+    /// Time from a block's finalization until it was executed.
+    timeline_executed: Histogram,
+    /// Time from a block's execution until it had strict finality.
+    timeline_strict_finality: Histogram,
+    /// Time from a block's timestamp until it had strict finality.
+    time_to_strict_finality: Histogram,
     /// Registry component.
     registry: Registry,
 }
@@ -39,17 +70,95 @@ impl Metrics {
         )?;
         let consensus_current_era =
             IntGauge::new("consensus_current_era", "the current era in consensus")?;
+        let proposal_timeout = IntGauge::new(
+            "consensus_proposal_timeout",
+            "the current era's proposal timeout in milliseconds, or 0 if the consensus protocol doesn't adapt it",
+        )?;
+        let validator_gauge =
+            |name: &str, help: &str| IntGaugeVec::new(Opts::new(name, help), &["public_key"]);
+        let validator_proposals = validator_gauge(
+            "consensus_validator_proposals",
+            "the number of each validator's proposals that became part of the chain in the last completed era",
+        )?;
+        let validator_missed_proposals = validator_gauge(
+            "consensus_validator_missed_proposals",
+            "the number of rounds in the last completed era in which each validator was the leader but none of their proposals became part of the chain",
+        )?;
+        let validator_units_and_votes = validator_gauge(
+            "consensus_validator_units_and_votes",
+            "the number of Highway units or Zug echoes and votes each validator created in the last completed era",
+        )?;
+        let validator_endorsements = validator_gauge(
+            "consensus_validator_endorsements",
+            "the number of Highway units each validator endorsed in the last completed era",
+        )?;
+        let validator_average_lateness = validator_gauge(
+            "consensus_validator_average_lateness",
+            "the average lateness in milliseconds of each validator's messages in the last completed era",
+        )?;
         registry.register(Box::new(finalization_time.clone()))?;
         registry.register(Box::new(finalized_block_count.clone()))?;
         registry.register(Box::new(consensus_current_era.clone()))?;
         registry.register(Box::new(time_of_last_proposed_block.clone()))?;
         registry.register(Box::new(time_of_last_finalized_block.clone()))?;
+        registry.register(Box::new(proposal_timeout.clone()))?;
+        registry.register(Box::new(validator_proposals.clone()))?;
+        registry.register(Box::new(validator_missed_proposals.clone()))?;
+        registry.register(Box::new(validator_units_and_votes.clone()))?;
+        registry.register(Box::new(validator_endorsements.clone()))?;
+        registry.register(Box::new(validator_average_lateness.clone()))?;
+        let buckets = prometheus::exponential_buckets(
+            TIMELINE_BUCKET_START,
+            TIMELINE_BUCKET_FACTOR,
+            TIMELINE_BUCKET_COUNT,
+        )?;
+        let timeline_proposal_received = utils::register_histogram_metric(
+            registry,
+            "consensus_timeline_proposal_received_seconds",
+            "time in seconds from a block's timestamp until this node received its proposal",
+            buckets.clone(),
+        )?;
+        let timeline_finalized = utils::register_histogram_metric(
+            registry,
+            "consensus_timeline_finalized_seconds",
+            "time in seconds from receiving a block's proposal until it was finalized",
+            buckets.clone(),
+        )?;
+        let timeline_executed = utils::register_histogram_metric(
+            registry,
+            "consensus_timeline_executed_seconds",
+            "time in seconds from a block's finalization until it was executed",
+            buckets.clone(),
+        )?;
+        let timeline_strict_finality = utils::register_histogram_metric(
+            registry,
+            "consensus_timeline_strict_finality_seconds",
+            "time in seconds from a block's execution until it had strict finality",
+            buckets.clone(),
+        )?;
+        let time_to_strict_finality = utils::register_histogram_metric(
+            registry,
+            "consensus_time_to_strict_finality_seconds",
+            "time in seconds from a block's timestamp until it had strict finality",
+            buckets,
+        )?;
         Ok(Metrics {
             finalization_time,
             finalized_block_count,
             time_of_last_proposed_block,
             time_of_last_finalized_block,
             consensus_current_era,
+            proposal_timeout,
+            validator_proposals,
+            validator_missed_proposals,
+            validator_units_and_votes,
+            validator_endorsements,
+            validator_average_lateness,
+            timeline_proposal_received,
+            timeline_finalized,
+            timeline_executed,
+            timeline_strict_finality,
+            time_to_strict_finality,
             registry: registry.clone(),
         })
     }
@@ -69,6 +178,78 @@ impl Metrics {
         self.time_of_last_proposed_block
             .set(Timestamp::now().millis() as i64);
     }
+
+    /// Updates the current era's proposal timeout.
+    pub(super) fn set_proposal_timeout(&mut self, proposal_timeout: Option<TimeDiff>) {
+        self.proposal_timeout
+            .set(proposal_timeout.map_or(0, |timeout| timeout.millis() as i64));
+    }
+
+    /// Records the stages of a newly finalized block's timeline.
+    pub(super) fn timeline_finalized(&mut self, block_timeline: &BlockTimeline) {
+        let received = match block_timeline.proposal_received {
+            Some(proposal_received) => {
+                let since_proposed = proposal_received.saturating_diff(block_timeline.proposed);
+                self.timeline_proposal_received
+                    .observe(since_proposed.millis() as f64 / 1000.0);
+                proposal_received
+            }
+            None => block_timeline.proposed,
+        };
+        let since_received = block_timeline.finalized.saturating_diff(received);
+        self.timeline_finalized
+            .observe(since_received.millis() as f64 / 1000.0);
+    }
+
+    /// Records the time from a block's finalization until it was executed.
+    pub(super) fn timeline_executed(&mut self, since_finalized: TimeDiff) {
+        self.timeline_executed
+            .observe(since_finalized.millis() as f64 / 1000.0);
+    }
+
+    /// Records the time from a block's execution, and from its timestamp, until it had strict
+    /// finality.
+    pub(super) fn timeline_strict_finality(
+        &mut self,
+        since_executed: TimeDiff,
+        since_proposed: TimeDiff,
+    ) {
+        self.timeline_strict_finality
+            .observe(since_executed.millis() as f64 / 1000.0);
+        self.time_to_strict_finality
+            .observe(since_proposed.millis() as f64 / 1000.0);
+    }
+
+    /// Replaces the per-validator metrics with the statistics of a completed era.
+    pub(super) fn set_validator_performance(
+        &mut self,
+        validator_performance: &BTreeMap<PublicKey, ValidatorPerformance>,
+    ) {
+        let gauges = [
+            &self.validator_proposals,
+            &self.validator_missed_proposals,
+            &self.validator_units_and_votes,
+            &self.validator_endorsements,
+            &self.validator_average_lateness,
+        ];
+        for gauge in gauges.iter() {
+            gauge.reset();
+        }
+        for (public_key, performance) in validator_performance {
+            let public_key = public_key.to_hex();
+            let labels = [public_key.as_str()];
+            let values = [
+                performance.proposals,
+                performance.missed_proposals,
+                performance.units_and_votes,
+                performance.endorsements,
+                performance.average_lateness.millis(),
+            ];
+            for (gauge, value) in gauges.iter().zip(values.iter()) {
+                gauge.with_label_values(&labels).set(*value as i64);
+            }
+        }
+    }
 }
 
 impl Drop for Metrics {
@@ -78,5 +259,16 @@ impl Drop for Metrics {
         unregister_metric!(self.registry, self.consensus_current_era);
         unregister_metric!(self.registry, self.time_of_last_finalized_block);
         unregister_metric!(self.registry, self.time_of_last_proposed_block);
+        unregister_metric!(self.registry, self.proposal_timeout);
+        unregister_metric!(self.registry, self.validator_proposals);
+        unregister_metric!(self.registry, self.validator_missed_proposals);
+        unregister_metric!(self.registry, self.validator_units_and_votes);
+        unregister_metric!(self.registry, self.validator_endorsements);
+        unregister_metric!(self.registry, self.validator_average_lateness);
+        unregister_metric!(self.registry, self.timeline_proposal_received);
+        unregister_metric!(self.registry, self.timeline_finalized);
+        unregister_metric!(self.registry, self.timeline_executed);
+        unregister_metric!(self.registry, self.timeline_strict_finality);
+        unregister_metric!(self.registry, self.time_to_strict_finality);
     }
 }
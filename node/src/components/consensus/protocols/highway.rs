@@ -39,6 +39,8 @@ use crate::{
         protocols,
         traits::{ConsensusValueT, Context},
         utils::ValidatorIndex,
+        validator_fault::FaultEvidence,
+        validator_performance::ValidatorPerformance,
         ActionId, TimerId,
     },
     types::{Chainspec, NodeId},
@@ -248,6 +250,7 @@ impl<C: Context + 'static> HighwayProtocol<C> {
                 error!("this validator is faulty: {:?}", fault);
                 vec![ProtocolOutcome::WeAreFaulty]
             }
+            AvEffect::RequestSignature(input) => vec![ProtocolOutcome::CreateSignature(input)],
         }
     }
 
@@ -1007,6 +1010,16 @@ where
         self.process_av_effects(effects, now)
     }
 
+    fn handle_signature(
+        &mut self,
+        hash: C::Hash,
+        signature: Option<C::Signature>,
+        now: Timestamp,
+    ) -> ProtocolOutcomes<C> {
+        let effects = self.highway.on_signature(&hash, signature, now);
+        self.process_av_effects(effects, now)
+    }
+
     fn resolve_validity(
         &mut self,
         proposed_block: ProposedBlock<C>,
@@ -1111,6 +1124,14 @@ where
         self.highway.validators_with_evidence().collect()
     }
 
+    fn validator_faults(&self) -> Vec<FaultEvidence<C::ValidatorId>> {
+        self.highway.validator_faults()
+    }
+
+    fn validator_performance(&self) -> BTreeMap<C::ValidatorId, ValidatorPerformance> {
+        self.highway.validator_performance()
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -1126,6 +1147,10 @@ where
     fn next_round_length(&self) -> Option<TimeDiff> {
         self.highway.next_round_length()
     }
+
+    fn current_proposal_timeout(&self) -> Option<TimeDiff> {
+        None
+    }
 }
 
 /// Maximum possible rounds in one era.
@@ -1,6 +1,7 @@
 use std::{collections::BTreeSet, sync::Arc};
 
-use casper_types::{testing::TestRng, PublicKey, TimeDiff, Timestamp, U512};
+use casper_types::{testing::TestRng, EraId, PublicKey, TimeDiff, Timestamp, U512};
+use tempfile::tempdir;
 
 use crate::{
     components::consensus::{
@@ -17,10 +18,11 @@ use crate::{
         protocols::highway::{
             config::Config as HighwayConfig, HighwayMessage, HighwayProtocol, ACTION_ID_VERTEX,
         },
+        signer::{start_test_remote_signer, Signer},
         tests::utils::{
             new_test_chainspec, ALICE_NODE_ID, ALICE_PUBLIC_KEY, ALICE_SECRET_KEY, BOB_PUBLIC_KEY,
         },
-        traits::Context,
+        traits::{Context, SigningContext, ValidatorSecret},
         utils::{ValidatorIndex, Weight},
         SerializedMessage,
     },
@@ -121,7 +123,7 @@ fn send_a_wire_unit_with_too_small_a_round_exp() {
     };
     let alice_keypair: Keypair = Keypair::from(Arc::clone(&*ALICE_SECRET_KEY));
     let highway_message: HighwayMessage<ClContext> = HighwayMessage::NewVertex(Vertex::Unit(
-        SignedWireUnit::new(wunit.into_hashed(), &alice_keypair),
+        SignedWireUnit::new(wunit.into_hashed(), &alice_keypair).unwrap(),
     ));
     let mut highway_protocol = new_test_highway_protocol(validators, vec![]);
     let sender = *ALICE_NODE_ID;
@@ -151,7 +153,7 @@ fn send_a_valid_wire_unit() {
     };
     let alice_keypair: Keypair = Keypair::from(Arc::clone(&*ALICE_SECRET_KEY));
     let highway_message: HighwayMessage<ClContext> = HighwayMessage::NewVertex(Vertex::Unit(
-        SignedWireUnit::new(wunit.into_hashed(), &alice_keypair),
+        SignedWireUnit::new(wunit.into_hashed(), &alice_keypair).unwrap(),
     ));
 
     let mut highway_protocol = new_test_highway_protocol(validators, vec![]);
@@ -199,7 +201,7 @@ fn detect_doppelganger() {
     };
     let alice_keypair: Keypair = Keypair::from(Arc::clone(&*ALICE_SECRET_KEY));
     let highway_message: HighwayMessage<ClContext> = HighwayMessage::NewVertex(Vertex::Unit(
-        SignedWireUnit::new(wunit.into_hashed(), &alice_keypair),
+        SignedWireUnit::new(wunit.into_hashed(), &alice_keypair).unwrap(),
     ));
     let mut highway_protocol = new_test_highway_protocol(validators, vec![]);
     // Activate ALICE as validator.
@@ -223,6 +225,61 @@ fn detect_doppelganger() {
     panic!("failed to return DoppelgangerDetected effect");
 }
 
+#[test]
+fn signs_in_background_if_signer_could_block() {
+    let validators = vec![
+        (ALICE_PUBLIC_KEY.clone(), 100),
+        (BOB_PUBLIC_KEY.clone(), 100),
+    ];
+    let mut highway_protocol = new_test_highway_protocol(validators, vec![]);
+    let now = Timestamp::zero();
+
+    // Alice's secret key is held by a remote signer, so signing could block.
+    let dir = tempdir().unwrap();
+    let remote_signer = start_test_remote_signer(Arc::clone(&*ALICE_SECRET_KEY), dir.path());
+    let signer: Arc<dyn Signer> = Arc::new(remote_signer);
+    let alice_keypair = Keypair::new(Arc::clone(&signer), EraId::new(0));
+
+    // Upon activation Alice creates a ping, but only requests its signature instead of signing it.
+    let outcomes = highway_protocol.activate_validator(
+        ALICE_PUBLIC_KEY.clone(),
+        Keypair::new(signer, EraId::new(0)),
+        now,
+        None,
+    );
+    assert!(!outcomes
+        .iter()
+        .any(|outcome| matches!(outcome, ProtocolOutcome::CreatedGossipMessage(_))));
+    let input = outcomes
+        .iter()
+        .find_map(|outcome| match outcome {
+            ProtocolOutcome::CreateSignature(input) => Some(input.clone()),
+            _ => None,
+        })
+        .expect("missing CreateSignature outcome");
+    assert_eq!(
+        input.context,
+        SigningContext::HighwayPing { timestamp: now }
+    );
+
+    // Once signed, the ping is gossiped.
+    let signature = alice_keypair.sign(&input);
+    assert!(signature.is_some());
+    let outcomes = highway_protocol.handle_signature(input.hash, signature, now);
+    let gossip: Vec<HighwayMessage<ClContext>> = outcomes
+        .iter()
+        .filter_map(|outcome| match outcome {
+            ProtocolOutcome::CreatedGossipMessage(msg) => Some(msg.deserialize_expect()),
+            _ => None,
+        })
+        .collect();
+    assert!(
+        matches!(&*gossip, [HighwayMessage::NewVertex(Vertex::Ping(_))]),
+        "unexpected gossip: {:?}",
+        gossip
+    );
+}
+
 #[test]
 fn max_rounds_per_era_returns_the_correct_value_for_prod_chainspec_value() {
     let max_rounds_per_era = max_rounds_per_era(
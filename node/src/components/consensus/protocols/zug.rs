@@ -65,6 +65,7 @@ mod round;
 #[cfg(test)]
 mod tests;
 mod wal;
+mod wal_inspection;
 
 use std::{
     any::Any,
@@ -92,8 +93,10 @@ use crate::{
         },
         era_supervisor::SerializedMessage,
         protocols,
-        traits::{ConsensusValueT, Context},
+        traits::{ConsensusValueT, Context, SigningContext, ValidatorSecret},
         utils::{ValidatorIndex, ValidatorMap, Validators, Weight},
+        validator_fault::{FaultEvidence, FaultKind, FaultVerification},
+        validator_performance::{Lateness, ValidatorPerformance},
         ActionId, LeaderSequence, TimerId,
     },
     types::{Chainspec, NodeId},
@@ -108,6 +111,7 @@ use round::Round;
 use wal::{Entry, ReadWal, WriteWal};
 
 pub(crate) use message::{Message, SyncRequest};
+pub(crate) use wal_inspection::inspect_zug_wal;
 
 /// The timer for syncing with a random peer.
 const TIMER_ID_SYNC_PEER: TimerId = TimerId(0);
@@ -126,6 +130,18 @@ pub(crate) type RoundId = u32;
 type ProposalsAwaitingParent = HashSet<(RoundId, NodeId)>;
 type ProposalsAwaitingValidation<C> = HashSet<(RoundId, HashedProposal<C>, NodeId)>;
 
+/// An echo or vote created by us that is waiting for its signature.
+#[derive(Debug, DataSize)]
+struct UnsignedMessage<C>
+where
+    C: Context,
+{
+    round_id: RoundId,
+    content: Content<C>,
+    /// Our proposal, if the message is an echo of it. It is gossiped together with the echo.
+    proposal: Option<HashedProposal<C>>,
+}
+
 /// Contains the portion of the state required for an active validator to participate in the
 /// protocol.
 #[derive(DataSize)]
@@ -201,6 +217,11 @@ where
     write_wal: Option<WriteWal<C>>,
     /// The rewards based on the finalized rounds so far.
     rewards: BTreeMap<C::ValidatorId, u64>,
+    /// The time between the start of the current round and the arrival of each validator's echoes
+    /// and votes in it.
+    lateness: ValidatorMap<Lateness>,
+    /// Our messages that are waiting for their signatures, by hash.
+    unsigned_messages: BTreeMap<C::Hash, UnsignedMessage<C>>,
 }
 
 impl<C: Context + 'static> Zug<C> {
@@ -224,6 +245,9 @@ impl<C: Context + 'static> Zug<C> {
                 config.proposal_timeout.millis() as f64
                     * (config.proposal_grace_period as f64 / 100.0 + 1.0)
             });
+        // The bounds can differ from the previous era's, e.g. after an upgrade.
+        let proposal_timeout_millis =
+            clamp_proposal_timeout(proposal_timeout_millis, config, &params);
 
         let mut can_propose: ValidatorMap<bool> = weights.iter().map(|_| true).collect();
         for vidx in validators.iter_cannot_propose_idx() {
@@ -237,6 +261,7 @@ impl<C: Context + 'static> Zug<C> {
         let leader_sequence = LeaderSequence::new(seed, &weights, can_propose);
 
         let rewards = validators.iter().map(|v| (v.id().clone(), 0)).collect();
+        let lateness = weights.iter().map(|_| Lateness::default()).collect();
 
         info!(
             instance_id = %params.instance_id(),
@@ -268,6 +293,8 @@ impl<C: Context + 'static> Zug<C> {
             next_scheduled_update: Timestamp::MAX,
             write_wal: None,
             rewards,
+            lateness,
+            unsigned_messages: BTreeMap::new(),
         }
     }
 
@@ -290,6 +317,7 @@ impl<C: Context + 'static> Zug<C> {
         let params = Params::new(
             instance_id,
             core_config.minimum_block_time,
+            chainspec.highway_config.maximum_round_length,
             era_start_time,
             core_config.minimum_era_height,
             era_start_time + core_config.era_duration,
@@ -626,47 +654,55 @@ impl<C: Context + 'static> Zug<C> {
         self.leader_sequence.leader(u64::from(round_id))
     }
 
+    /// If we are an active validator and it would be safe for us to sign this message and we
+    /// haven't signed it before, we sign it, add it to our state and gossip it to the network,
+    /// together with our `proposal` if the message is an echo of it.
+    ///
+    /// If the signer could block, the message is kept until its signature is passed to
+    /// `handle_signature`, and an outcome requesting the signature is returned instead.
+    ///
+    /// Does not call `update`!
     fn create_message(
         &mut self,
         round_id: RoundId,
         content: Content<C>,
-    ) -> Option<SignedMessage<C>> {
-        let (validator_idx, secret_key) = if let Some(active_validator) = &self.active_validator {
-            (active_validator.idx, &active_validator.secret)
-        } else {
-            return None;
+        proposal: Option<HashedProposal<C>>,
+    ) -> ProtocolOutcomes<C> {
+        let (validator_idx, signs_instantly) = match &self.active_validator {
+            Some(active_validator) => (
+                active_validator.idx,
+                active_validator.secret.signs_instantly(),
+            ),
+            None => return vec![],
         };
         if self.paused {
-            return None;
+            return vec![];
         }
         let already_signed = match &content {
             Content::Echo(_) => self.has_echoed(round_id, validator_idx),
             Content::Vote(_) => self.has_voted(round_id, validator_idx),
         };
-        if already_signed {
-            return None;
+        if already_signed || self.is_signing(round_id, &content) {
+            return vec![];
         }
-        let signed_msg = SignedMessage::sign_new(
+        let input =
+            SignedMessage::signing_input(round_id, self.instance_id(), &content, validator_idx);
+        let unsigned_msg = UnsignedMessage {
             round_id,
-            *self.instance_id(),
             content,
-            validator_idx,
-            secret_key,
-        );
-        // We only return the new message if we are able to record it. If that fails we
-        // wouldn't know about our own message after a restart and risk double-signing.
-        if self.record_entry(&Entry::SignedMessage(signed_msg.clone()))
-            && self.add_content(signed_msg.clone())
-        {
-            Some(signed_msg)
-        } else {
-            debug!(
-                our_idx = self.our_idx(),
-                %round_id,
-                ?content,
-                "couldn't record a signed message in the WAL or add it to the protocol state"
-            );
-            None
+            proposal,
+        };
+        if !signs_instantly {
+            self.unsigned_messages.insert(input.hash, unsigned_msg);
+            return vec![ProtocolOutcome::CreateSignature(input)];
+        }
+        let maybe_signature = self
+            .active_validator
+            .as_ref()
+            .and_then(|active_validator| active_validator.secret.sign(&input));
+        match maybe_signature {
+            Some(signature) => self.add_own_message(unsigned_msg, signature),
+            None => vec![],
         }
     }
 
@@ -679,14 +715,82 @@ impl<C: Context + 'static> Zug<C> {
         round_id: RoundId,
         content: Content<C>,
     ) -> ProtocolOutcomes<C> {
-        let maybe_signed_msg = self.create_message(round_id, content);
-        maybe_signed_msg
-            .into_iter()
-            .map(|signed_msg| {
+        self.create_message(round_id, content, None)
+    }
+
+    /// Returns whether our echo or vote, like `content`, in that round is waiting for its
+    /// signature. In that case we must not sign another one.
+    fn is_signing(&self, round_id: RoundId, content: &Content<C>) -> bool {
+        self.unsigned_messages.values().any(|unsigned_msg| {
+            unsigned_msg.round_id == round_id && unsigned_msg.content.is_same_kind(content)
+        })
+    }
+
+    /// Adds our message with the given signature to the protocol state, and returns the outcome
+    /// gossiping it, or the proposal it is an echo of.
+    fn add_own_message(
+        &mut self,
+        unsigned_msg: UnsignedMessage<C>,
+        signature: C::Signature,
+    ) -> ProtocolOutcomes<C> {
+        let UnsignedMessage {
+            round_id,
+            content,
+            proposal,
+        } = unsigned_msg;
+        let validator_idx = match &self.active_validator {
+            Some(active_validator) => active_validator.idx,
+            None => return vec![],
+        };
+        let signed_msg = SignedMessage {
+            round_id,
+            instance_id: *self.instance_id(),
+            content,
+            validator_idx,
+            signature,
+        };
+        // We only gossip the new message if we are able to record it. If that fails we
+        // wouldn't know about our own message after a restart and risk double-signing.
+        if !self.record_entry(&Entry::SignedMessage(signed_msg.clone()))
+            || !self.add_content(signed_msg.clone())
+        {
+            debug!(
+                our_idx = self.our_idx(),
+                %round_id,
+                ?content,
+                "couldn't record a signed message in the WAL or add it to the protocol state"
+            );
+            return vec![];
+        }
+        let hashed_prop = match proposal {
+            Some(hashed_prop) => hashed_prop,
+            None => {
                 let message = Message::Signed(signed_msg);
-                ProtocolOutcome::CreatedGossipMessage(SerializedMessage::from_message(&message))
-            })
-            .collect()
+                return vec![ProtocolOutcome::CreatedGossipMessage(
+                    SerializedMessage::from_message(&message),
+                )];
+            }
+        };
+        let prop_msg = Message::Proposal {
+            round_id,
+            proposal: hashed_prop.inner().clone(),
+            instance_id: *self.instance_id(),
+            echo: signed_msg,
+        };
+        if !self.record_entry(&Entry::Proposal(hashed_prop.inner().clone(), round_id)) {
+            error!(
+                our_idx = self.our_idx(),
+                "could not record own proposal in WAL"
+            );
+            vec![]
+        } else if self.round_mut(round_id).insert_proposal(hashed_prop) {
+            self.mark_dirty(round_id);
+            vec![ProtocolOutcome::CreatedGossipMessage(
+                SerializedMessage::from_message(&prop_msg),
+            )]
+        } else {
+            vec![]
+        }
     }
 
     /// When we receive evidence for a fault, we must notify the rest of the network of this
@@ -1071,7 +1175,12 @@ impl<C: Context + 'static> Zug<C> {
             );
         } else {
             self.record_entry(&Entry::SignedMessage(signed_msg.clone()));
+            let in_current_round = signed_msg.round_id == self.current_round;
             if self.add_content(signed_msg) {
+                if in_current_round && self.current_round_start <= now {
+                    self.lateness[validator_idx]
+                        .record(now.saturating_diff(self.current_round_start));
+                }
                 return self.update(now);
             }
         }
@@ -1870,6 +1979,11 @@ impl<C: Context + 'static> Zug<C> {
         if self.round_mut(self.current_round).has_proposal() {
             return vec![]; // We already made a proposal.
         }
+        if self.unsigned_messages.values().any(|unsigned_msg| {
+            unsigned_msg.round_id == self.current_round && unsigned_msg.proposal.is_some()
+        }) {
+            return vec![]; // Our proposal is waiting for the signature of its echo.
+        }
         let ancestor_values = match maybe_parent_round_id {
             Some(parent_round_id)
                 if self.accepted_switch_block(parent_round_id)
@@ -1896,34 +2010,9 @@ impl<C: Context + 'static> Zug<C> {
     /// Creates a new proposal message in the current round, and a corresponding signed echo,
     /// inserts them into our protocol state and gossips them.
     fn create_echo_and_proposal(&mut self, proposal: Proposal<C>) -> ProtocolOutcomes<C> {
-        let round_id = self.current_round;
-        let hashed_prop = HashedProposal::new(proposal.clone());
+        let hashed_prop = HashedProposal::new(proposal);
         let echo_content = Content::Echo(*hashed_prop.hash());
-        let echo = if let Some(echo) = self.create_message(round_id, echo_content) {
-            echo
-        } else {
-            return vec![];
-        };
-        let prop_msg = Message::Proposal {
-            round_id,
-            proposal,
-            instance_id: *self.instance_id(),
-            echo,
-        };
-        if !self.record_entry(&Entry::Proposal(hashed_prop.inner().clone(), round_id)) {
-            error!(
-                our_idx = self.our_idx(),
-                "could not record own proposal in WAL"
-            );
-            vec![]
-        } else if self.round_mut(round_id).insert_proposal(hashed_prop) {
-            self.mark_dirty(round_id);
-            vec![ProtocolOutcome::CreatedGossipMessage(
-                SerializedMessage::from_message(&prop_msg),
-            )]
-        } else {
-            vec![]
-        }
+        self.create_message(self.current_round, echo_content, Some(hashed_prop))
     }
 
     /// Returns a parent if a block with that parent could be proposed in the current round, and the
@@ -1995,6 +2084,8 @@ impl<C: Context + 'static> Zug<C> {
             let min_timeout = (self.config.proposal_timeout.millis() as f64).max(target_timeout);
             self.proposal_timeout_millis = self.proposal_timeout_millis.max(min_timeout);
         }
+        self.proposal_timeout_millis =
+            clamp_proposal_timeout(self.proposal_timeout_millis, &self.config, &self.params);
         debug!(our_idx = self.our_idx(), %self.proposal_timeout_millis, "proposal timeout updated");
     }
 
@@ -2249,6 +2340,28 @@ where
         outcomes
     }
 
+    fn handle_signature(
+        &mut self,
+        hash: C::Hash,
+        signature: Option<C::Signature>,
+        now: Timestamp,
+    ) -> ProtocolOutcomes<C> {
+        let unsigned_msg = match self.unsigned_messages.remove(&hash) {
+            Some(unsigned_msg) => unsigned_msg,
+            None => {
+                warn!(our_idx = self.our_idx(), %hash, "received signature for unknown message");
+                return vec![];
+            }
+        };
+        let signature = match signature {
+            Some(signature) => signature,
+            None => return vec![], // The signer already logged the reason.
+        };
+        let mut outcomes = self.add_own_message(unsigned_msg, signature);
+        outcomes.extend(self.update(now));
+        outcomes
+    }
+
     fn resolve_validity(
         &mut self,
         proposed_block: ProposedBlock<C>,
@@ -2374,6 +2487,76 @@ where
             .unwrap_or_default()
     }
 
+    fn validator_faults(&self) -> Vec<FaultEvidence<C::ValidatorId>> {
+        self.faults
+            .iter()
+            .sorted_by_key(|(v_idx, _)| **v_idx)
+            .filter_map(|(v_idx, fault)| {
+                let (signed_msg, content2, signature2) = match fault {
+                    Fault::Direct(signed_msg, content2, signature2) => {
+                        (signed_msg, content2, signature2)
+                    }
+                    Fault::Banned | Fault::Indirect => return None,
+                };
+                let validator_id = self.validators.id(*v_idx)?;
+                let signed_msg2 = signed_msg.with(*content2, *signature2);
+                let verification = if signed_msg.instance_id != *self.params.instance_id() {
+                    FaultVerification::Invalid("wrong instance ID".to_string())
+                } else if !signed_msg.content.contradicts(content2) {
+                    FaultVerification::Invalid("contents don't conflict".to_string())
+                } else if !signed_msg.verify_signature(validator_id)
+                    || !signed_msg2.verify_signature(validator_id)
+                {
+                    FaultVerification::Invalid("invalid signature".to_string())
+                } else {
+                    FaultVerification::Valid
+                };
+                // The evidence in the same form as in an `Evidence` message.
+                let evidence = (signed_msg, content2, signature2);
+                Some(FaultEvidence::new(
+                    validator_id.clone(),
+                    FaultKind::ConflictingMessages,
+                    (signed_msg, &signed_msg2),
+                    &evidence,
+                    verification,
+                ))
+            })
+            .collect()
+    }
+
+    fn validator_performance(&self) -> BTreeMap<C::ValidatorId, ValidatorPerformance> {
+        let mut performance: ValidatorMap<ValidatorPerformance> = self
+            .lateness
+            .iter()
+            .map(|lateness| ValidatorPerformance {
+                average_lateness: lateness.average(),
+                ..ValidatorPerformance::default()
+            })
+            .collect();
+        for (round_id, round) in &self.rounds {
+            let leader_performance = &mut performance[round.leader()];
+            if round.accepted_proposal().is_some() {
+                leader_performance.proposals = leader_performance.proposals.saturating_add(1);
+            } else if *round_id < self.current_round {
+                leader_performance.missed_proposals =
+                    leader_performance.missed_proposals.saturating_add(1);
+            }
+            let echoers = round.echoes().values().flat_map(BTreeMap::keys);
+            let voters = [false, true]
+                .iter()
+                .flat_map(|vote| round.votes(*vote).keys_some());
+            for validator_idx in echoers.copied().chain(voters) {
+                let validator_performance = &mut performance[validator_idx];
+                validator_performance.units_and_votes =
+                    validator_performance.units_and_votes.saturating_add(1);
+            }
+        }
+        self.validators
+            .enumerate_ids()
+            .map(|(validator_idx, validator_id)| (validator_id.clone(), performance[validator_idx]))
+            .collect()
+    }
+
     fn set_paused(&mut self, paused: bool, now: Timestamp) -> ProtocolOutcomes<C> {
         if self.paused && !paused {
             info!(
@@ -2422,6 +2605,40 @@ where
     fn next_round_length(&self) -> Option<TimeDiff> {
         Some(self.params.min_block_time())
     }
+
+    fn current_proposal_timeout(&self) -> Option<TimeDiff> {
+        Some(self.proposal_timeout())
+    }
+}
+
+/// Returns the proposal timeout, in milliseconds, limited to the configured minimum and the
+/// chainspec's maximum round length. If the two conflict, the chainspec takes precedence.
+fn clamp_proposal_timeout<C: Context>(
+    proposal_timeout_millis: f64,
+    config: &config::Config,
+    params: &Params<C>,
+) -> f64 {
+    proposal_timeout_millis
+        .max(config.proposal_timeout.millis() as f64)
+        .min(params.max_round_length().millis() as f64)
+}
+
+/// Returns what kind of Zug message `serialized` is, if it is the signed serialization of an echo
+/// or vote.
+pub(crate) fn signing_context_of<C: Context>(serialized: &[u8]) -> Option<SigningContext> {
+    SignedMessage::<C>::signing_context_of(serialized)
+}
+
+/// Returns the message a validator signs to echo the proposal with the given hash.
+#[cfg(test)]
+pub(crate) fn echo_signing_input<C: Context>(
+    round_id: RoundId,
+    instance_id: &C::InstanceId,
+    proposal_hash: C::Hash,
+    validator_idx: ValidatorIndex,
+) -> crate::components::consensus::traits::SigningInput<C::Hash> {
+    let content = Content::Echo(proposal_hash);
+    SignedMessage::<C>::signing_input(round_id, instance_id, &content, validator_idx)
 }
 
 mod specimen_support {
@@ -2548,6 +2765,7 @@ mod specimen_support {
                 LargestSpecimen::largest_specimen(estimator, cache),
                 &LargestSpecimen::largest_specimen(estimator, cache),
             )
+            .expect("should sign")
         }
     }
 
@@ -16,7 +16,8 @@ pub struct Config {
     /// Log inactive or faulty validators periodically, with this interval. 0 means disabled.
     #[serde(with = "serde_option_time_diff")]
     pub log_participation_interval: Option<TimeDiff>,
-    /// The minimal and initial timeout for a proposal.
+    /// The minimal and initial timeout for a proposal. It never exceeds the chainspec's
+    /// `highway.maximum_round_length`, though.
     pub proposal_timeout: TimeDiff,
     /// The additional proposal delay that is still considered fast enough, in percent. This should
     /// take into account variables like empty vs. full blocks, network traffic etc.
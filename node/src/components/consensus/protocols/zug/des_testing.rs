@@ -25,14 +25,12 @@ use crate::{
         consensus_protocol::{
             ConsensusProtocol, FinalizedBlock, ProposedBlock, ProtocolOutcome, ProtocolOutcomes,
         },
-        tests::{
-            consensus_des_testing::{
-                DeliverySchedule, Fault as DesFault, Message, Node, Target, TargetedMessage,
-                ValidatorId, VirtualNet,
-            },
-            queue::QueueEntry,
+        des::QueueEntry,
+        tests::consensus_des_testing::{
+            DeliverySchedule, Fault as DesFault, Message, Node, Target, TargetedMessage,
+            ValidatorId, VirtualNet,
         },
-        traits::{ConsensusValueT, Context, ValidatorSecret},
+        traits::{ConsensusValueT, Context, SigningInput, ValidatorSecret},
         utils::{Validators, Weight},
         ActionId, BlockContext, SerializedMessage, TimerId,
     },
@@ -56,6 +54,7 @@ impl Display for ConsensusValue {
 }
 
 const TEST_MIN_ROUND_LEN: TimeDiff = TimeDiff::from_millis(1 << 12);
+const TEST_MAX_ROUND_LEN: TimeDiff = TimeDiff::from_millis(1 << 19);
 const TEST_END_HEIGHT: u64 = 100000;
 pub(crate) const TEST_INSTANCE_ID: u64 = 42;
 
@@ -151,6 +150,7 @@ impl From<ProtocolOutcome<TestContext>> for ZugMessage {
             ProtocolOutcome::HandledProposedBlock(proposed_block) => {
                 ZugMessage::HandledProposedBlock(proposed_block)
             }
+            ProtocolOutcome::CreateSignature(..) => unreachable!("test secrets sign instantly"),
         }
     }
 }
@@ -313,7 +313,8 @@ impl ZugValidator {
                                     )),
                                     signed_msg.validator_idx,
                                     &TestSecret(signed_msg.validator_idx.0.into()),
-                                );
+                                )
+                                .unwrap();
                                 vec![
                                     ZugMessage::GossipMessage(SerializedMessage::from_message(
                                         &ZugProtocolMessage::Signed(conflicting_message),
@@ -328,7 +329,8 @@ impl ZugValidator {
                                     Content::<TestContext>::Vote(!vote),
                                     signed_msg.validator_idx,
                                     &TestSecret(signed_msg.validator_idx.0.into()),
-                                );
+                                )
+                                .unwrap();
                                 vec![
                                     ZugMessage::GossipMessage(SerializedMessage::from_message(
                                         &ZugProtocolMessage::Signed(conflicting_message),
@@ -886,6 +888,7 @@ impl<DS: DeliveryStrategy> ZugTestHarnessBuilder<DS> {
         let params = Params::new(
             instance_id,
             TEST_MIN_ROUND_LEN,
+            TEST_MAX_ROUND_LEN,
             start_time,
             self.end_height,
             start_time, // Length depends only on block number.
@@ -1009,8 +1012,8 @@ impl ValidatorSecret for TestSecret {
     type Hash = HashWrapper;
     type Signature = SignatureWrapper;
 
-    fn sign(&self, data: &Self::Hash) -> Self::Signature {
-        SignatureWrapper(data.0 + self.0)
+    fn sign(&self, input: &SigningInput<Self::Hash>) -> Option<Self::Signature> {
+        Some(SignatureWrapper(input.hash.0 + self.0))
     }
 }
 
@@ -8,8 +8,8 @@ use either::Either;
 use crate::{
     components::consensus::{
         protocols::zug::{Proposal, RoundId},
-        traits::{ConsensusNetworkMessage, Context, ValidatorSecret},
-        utils::ValidatorIndex,
+        traits::{ConsensusNetworkMessage, Context, SigningContext, SigningInput, ValidatorSecret},
+        utils::{self, ValidatorIndex},
     },
     utils::ds,
 };
@@ -104,6 +104,25 @@ impl<C: Context> Content<C> {
             _ => false,
         }
     }
+
+    /// Returns whether both are echoes, or both are votes.
+    pub(crate) fn is_same_kind(&self, other: &Content<C>) -> bool {
+        matches!(
+            (self, other),
+            (Content::Vote(_), Content::Vote(_)) | (Content::Echo(_), Content::Echo(_))
+        )
+    }
+
+    /// Returns what kind of message a signature of this content in the given round is for.
+    pub(crate) fn signing_context(&self, round_id: RoundId) -> SigningContext {
+        match self {
+            Content::Echo(_) => SigningContext::ZugEcho { round_id },
+            Content::Vote(vote) => SigningContext::ZugVote {
+                round_id,
+                vote: *vote,
+            },
+        }
+    }
 }
 
 // This has to be implemented manually because of the <C> generic parameter, which isn't
@@ -129,21 +148,24 @@ where
 
 impl<C: Context> SignedMessage<C> {
     /// Creates a new signed message with a valid signature.
+    ///
+    /// Returns `None` if the signer refused or failed to sign.
     pub(crate) fn sign_new(
         round_id: RoundId,
         instance_id: C::InstanceId,
         content: Content<C>,
         validator_idx: ValidatorIndex,
         secret: &C::ValidatorSecret,
-    ) -> SignedMessage<C> {
-        let hash = Self::hash_fields(round_id, &instance_id, &content, validator_idx);
-        SignedMessage {
+    ) -> Option<SignedMessage<C>> {
+        let input = Self::signing_input(round_id, &instance_id, &content, validator_idx);
+        let signature = secret.sign(&input)?;
+        Some(SignedMessage {
             round_id,
             instance_id,
             content,
             validator_idx,
-            signature: secret.sign(&hash),
-        }
+            signature,
+        })
     }
 
     /// Creates a new signed message with the alternative content and signature.
@@ -167,17 +189,52 @@ impl<C: Context> SignedMessage<C> {
     }
 
     /// Returns the hash of all fields except the signature.
-    fn hash_fields(
+    pub(super) fn hash_fields(
         round_id: RoundId,
         instance_id: &C::InstanceId,
         content: &Content<C>,
         validator_idx: ValidatorIndex,
     ) -> C::Hash {
         let serialized_fields =
-            bincode::serialize(&(round_id, instance_id, content, validator_idx))
-                .expect("failed to serialize fields");
+            Self::serialize_fields(round_id, instance_id, content, validator_idx);
         <C as Context>::hash(&serialized_fields)
     }
+
+    /// Returns the message to be signed, i.e. all fields except the signature.
+    pub(super) fn signing_input(
+        round_id: RoundId,
+        instance_id: &C::InstanceId,
+        content: &Content<C>,
+        validator_idx: ValidatorIndex,
+    ) -> SigningInput<C::Hash> {
+        let serialized = Self::serialize_fields(round_id, instance_id, content, validator_idx);
+        SigningInput {
+            hash: <C as Context>::hash(&serialized),
+            serialized,
+            context: content.signing_context(round_id),
+        }
+    }
+
+    /// Returns what kind of message `serialized` is, if it is the serialization of the fields of a
+    /// signed message.
+    pub(super) fn signing_context_of(serialized: &[u8]) -> Option<SigningContext> {
+        let (round_id, _, content, _) =
+            utils::deserialize_exact::<(RoundId, C::InstanceId, Content<C>, ValidatorIndex)>(
+                serialized,
+            )?;
+        Some(content.signing_context(round_id))
+    }
+
+    /// Serializes all fields except the signature.
+    fn serialize_fields(
+        round_id: RoundId,
+        instance_id: &C::InstanceId,
+        content: &Content<C>,
+        validator_idx: ValidatorIndex,
+    ) -> Vec<u8> {
+        bincode::serialize(&(round_id, instance_id, content, validator_idx))
+            .expect("failed to serialize fields")
+    }
 }
 
 /// Partial information about the sender's protocol state. The receiver should send missing data.
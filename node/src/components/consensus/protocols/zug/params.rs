@@ -13,6 +13,7 @@ where
 {
     instance_id: C::InstanceId,
     min_block_time: TimeDiff,
+    max_round_length: TimeDiff,
     start_timestamp: Timestamp,
     end_height: u64,
     end_timestamp: Timestamp,
@@ -24,6 +25,7 @@ impl<C: Context> Params<C> {
     pub(crate) fn new(
         instance_id: C::InstanceId,
         min_block_time: TimeDiff,
+        max_round_length: TimeDiff,
         start_timestamp: Timestamp,
         end_height: u64,
         end_timestamp: Timestamp,
@@ -32,6 +34,7 @@ impl<C: Context> Params<C> {
         Params {
             instance_id,
             min_block_time,
+            max_round_length,
             start_timestamp,
             end_height,
             end_timestamp,
@@ -49,6 +52,11 @@ impl<C: Context> Params<C> {
         self.min_block_time
     }
 
+    /// Returns the upper limit for the proposal timeout.
+    pub(crate) fn max_round_length(&self) -> TimeDiff {
+        self.max_round_length
+    }
+
     /// Returns the start timestamp of the era.
     pub(crate) fn start_timestamp(&self) -> Timestamp {
         self.start_timestamp
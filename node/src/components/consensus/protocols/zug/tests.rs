@@ -2,7 +2,7 @@ use super::*;
 
 use std::{collections::BTreeSet, sync::Arc};
 
-use casper_types::{PublicKey, SecretKey, Timestamp, U512};
+use casper_types::{EraId, PublicKey, SecretKey, Timestamp, U512};
 use tempfile::tempdir;
 use tracing::info;
 
@@ -13,11 +13,12 @@ use crate::{
         consensus_protocol::{ConsensusProtocol, ProtocolOutcome},
         leader_sequence,
         protocols::common,
+        signer::{start_test_remote_signer, Signer},
         tests::utils::{
             new_test_chainspec, ALICE_NODE_ID, ALICE_PUBLIC_KEY, ALICE_SECRET_KEY, BOB_PUBLIC_KEY,
             BOB_SECRET_KEY, CAROL_PUBLIC_KEY, CAROL_SECRET_KEY,
         },
-        traits::Context,
+        traits::{Context, SigningContext},
     },
     testing,
     types::BlockPayload,
@@ -77,7 +78,7 @@ fn create_signed_message(
 ) -> SignedMessage<ClContext> {
     let validator_idx = validators.get_index(keypair.public_key()).unwrap();
     let instance_id = ClContext::hash(INSTANCE_ID_DATA);
-    SignedMessage::sign_new(round_id, instance_id, content, validator_idx, keypair)
+    SignedMessage::sign_new(round_id, instance_id, content, validator_idx, keypair).unwrap()
 }
 
 /// Creates a `Message::Signed`.
@@ -534,6 +535,21 @@ fn zug_no_fault() {
     expect_finalized(&outcomes, &[(&proposal3, 2)]);
     assert!(sc_c.finalized_switch_block());
 
+    // Bob's proposal in round 0 was skipped, Alice's in rounds 1 and 2 and Carol's in round 3 were
+    // accepted. Round 4 is still current.
+    let performance = sc_c.validator_performance();
+    let counts = |public_key: &PublicKey| {
+        let validator_performance = &performance[public_key];
+        (
+            validator_performance.proposals,
+            validator_performance.missed_proposals,
+            validator_performance.units_and_votes,
+        )
+    };
+    assert_eq!(counts(&*ALICE_PUBLIC_KEY), (2, 0, 7));
+    assert_eq!(counts(&*BOB_PUBLIC_KEY), (0, 1, 4));
+    assert_eq!(counts(&*CAROL_PUBLIC_KEY), (1, 0, 9));
+
     info!("restoring protocol now");
 
     let mut zug = new_test_zug(weights, vec![], leader_seq);
@@ -544,6 +560,71 @@ fn zug_no_fault() {
     assert!(zug.finalized_switch_block());
 }
 
+/// Tests that if signing could block, messages are only gossiped once their signature has been
+/// passed back to the protocol, and no conflicting message is signed in the meantime.
+#[test]
+fn zug_signs_in_background_if_signer_could_block() {
+    testing::init_logging();
+    let mut rng = crate::new_rng();
+    let (weights, validators) = abc_weights(60, 30, 10);
+    let bob_idx = validators.get_index(&*BOB_PUBLIC_KEY).unwrap();
+    let carol_idx = validators.get_index(&*CAROL_PUBLIC_KEY).unwrap();
+    let sender = *ALICE_NODE_ID;
+
+    let mut timestamp = Timestamp::from(100000);
+
+    let leader_seq = &[bob_idx];
+    let mut sc_c = new_test_zug(weights, vec![], leader_seq);
+    let dir = tempdir().unwrap();
+    sc_c.open_wal(dir.path().join("wal"), timestamp);
+
+    // Carol's secret key is held by a remote signer, so signing could block.
+    let remote_signer = start_test_remote_signer(Arc::clone(&*CAROL_SECRET_KEY), dir.path());
+    let signer: Arc<dyn Signer> = Arc::new(remote_signer);
+    let carol_kp = Keypair::new(Arc::clone(&signer), EraId::new(0));
+    let bob_kp = Keypair::from(BOB_SECRET_KEY.clone());
+
+    let carol_signing_kp = Keypair::new(signer, EraId::new(0));
+    sc_c.activate_validator(CAROL_PUBLIC_KEY.clone(), carol_signing_kp, timestamp, None);
+
+    let proposal0 = Proposal::<ClContext> {
+        timestamp,
+        maybe_block: Some(new_payload(false)),
+        maybe_parent_round_id: None,
+        inactive: None,
+    };
+    let hash0 = proposal0.hash();
+
+    timestamp += sc_c.params.min_block_time();
+
+    // Carol receives Bob's proposal in round 0, but only requests the signature of her echo.
+    let msg = create_proposal_message(0, &proposal0, &validators, &bob_kp);
+    let mut outcomes = sc_c.handle_message(&mut rng, sender, msg, timestamp);
+    assert!(remove_gossip(&validators, &mut outcomes).is_empty());
+    let input = outcomes
+        .iter()
+        .find_map(|outcome| match outcome {
+            ProtocolOutcome::CreateSignature(input) => Some(input.clone()),
+            _ => None,
+        })
+        .expect("missing CreateSignature outcome");
+    assert_eq!(input.context, SigningContext::ZugEcho { round_id: 0 });
+
+    // While the signature is pending, she doesn't request another one for an echo in round 0.
+    let outcomes = sc_c.handle_timer(timestamp, timestamp, TIMER_ID_UPDATE, &mut rng);
+    assert!(!outcomes
+        .iter()
+        .any(|outcome| matches!(outcome, ProtocolOutcome::CreateSignature(..))));
+
+    // Once signed, the echo is gossiped.
+    let signature = carol_kp.sign(&input);
+    assert!(signature.is_some());
+    let mut outcomes = sc_c.handle_signature(input.hash, signature, timestamp);
+    let mut gossip = remove_gossip(&validators, &mut outcomes);
+    assert!(remove_signed(&mut gossip, 0, carol_idx, echo(hash0)));
+    assert!(gossip.is_empty(), "unexpected gossip: {:?}", gossip);
+}
+
 /// Tests that a faulty validator counts towards every quorum.
 ///
 /// In this scenario Alice has 60% of the weight, Bob 10% and Carol 30%. Carol is offline and Bob is
@@ -610,6 +691,24 @@ fn zug_faults() {
     let msg = create_message(&validators, 3, vote(false), &carol_kp);
     let outcomes = zug.handle_message(&mut rng, sender, msg, timestamp);
     assert!(outcomes.contains(&ProtocolOutcome::FttExceeded));
+
+    // Both faults are reported, with conflicting messages that can be verified independently.
+    let faults = zug.validator_faults();
+    let faulty: BTreeSet<_> = faults.iter().map(|fault| &fault.validator_id).collect();
+    let expected: BTreeSet<_> = vec![&*BOB_PUBLIC_KEY, &*CAROL_PUBLIC_KEY]
+        .into_iter()
+        .collect();
+    assert_eq!(expected, faulty);
+    for fault in &faults {
+        assert_eq!(FaultKind::ConflictingMessages, fault.kind);
+        assert_eq!(FaultVerification::Valid, fault.verification);
+        let msg1: SignedMessage<ClContext> = bincode::deserialize(&fault.messages.0).unwrap();
+        let msg2: SignedMessage<ClContext> = bincode::deserialize(&fault.messages.1).unwrap();
+        assert_eq!(msg1.round_id, msg2.round_id);
+        assert!(msg1.content.contradicts(&msg2.content));
+        assert!(msg1.verify_signature(&fault.validator_id));
+        assert!(msg2.verify_signature(&fault.validator_id));
+    }
 }
 
 /// Tests that a `SyncRequest` message is periodically sent to a random peer.
@@ -1034,3 +1133,34 @@ fn update_proposal_timeout() {
         );
     }
 }
+
+#[test]
+fn proposal_timeout_is_bounded() {
+    let mut rng = crate::new_rng();
+
+    let (weights, _validators) = abc_weights(1, 2, 3);
+    let mut zug = new_test_zug(weights, vec![], &[]);
+    let _outcomes = zug.handle_timer(
+        Timestamp::from(100000),
+        Timestamp::from(100000),
+        TIMER_ID_UPDATE,
+        &mut rng,
+    );
+    let round_start = zug.current_round_start;
+
+    // If proposals are consistently slow, the timeout stops growing at the maximum round length.
+    for _ in 0..1000 {
+        zug.update_proposal_timeout(round_start + TimeDiff::from_seconds(10000));
+    }
+    assert_eq!(zug.params.max_round_length(), zug.proposal_timeout());
+    assert_eq!(
+        Some(zug.params.max_round_length()),
+        zug.current_proposal_timeout()
+    );
+
+    // If they are consistently fast, it goes down to the configured minimum.
+    for _ in 0..1000 {
+        zug.update_proposal_timeout(round_start + TimeDiff::from_millis(1));
+    }
+    assert_eq!(zug.config.proposal_timeout, zug.proposal_timeout());
+}
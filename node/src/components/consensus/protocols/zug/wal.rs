@@ -133,31 +133,13 @@ impl<C: Context> ReadWal<C> {
         // Remember the current position: If we encounter an unreadable entry we trim the file at
         // this point so we can continue appending entries after it.
         let position = self.reader.stream_position()?;
-
-        // Deserialize the size of the entry, in bytes, as a u64.
-        let mut entry_size_buf = [0u8; mem::size_of::<u64>()];
-        if let Err(err) = self.reader.read_exact(&mut entry_size_buf) {
-            if err.kind() == io::ErrorKind::UnexpectedEof {
-                self.trim_file(position)?;
-                return Ok(None);
-            }
-            return Err(ReadWalError::OtherIOError(err));
-        }
-        let entry_size = u64::from_le_bytes(entry_size_buf) as usize;
-
-        // Read the serialized entry itself.
-        let mut entry_buf = vec![0; entry_size];
-        if let Err(err) = self.reader.read_exact(&mut entry_buf) {
-            if err.kind() == io::ErrorKind::UnexpectedEof {
+        match read_entry(&mut self.reader)? {
+            Some(entry) => Ok(Some(entry)),
+            None => {
                 self.trim_file(position)?;
-                return Ok(None);
+                Ok(None)
             }
-            return Err(ReadWalError::OtherIOError(err));
         }
-
-        // Deserialize and return the entry.
-        let entry = bincode::deserialize(&entry_buf).map_err(ReadWalError::CouldNotDeserialize)?;
-        Ok(Some(entry))
     }
 
     /// Trims the file to the given length and logs a warning if any bytes were removed.
@@ -174,6 +156,48 @@ impl<C: Context> ReadWal<C> {
     }
 }
 
+/// Reads the entry at the reader's current position.
+///
+/// Returns `Ok(None)` if the file ends before the entry is complete, including if there are 0 bytes
+/// left.
+pub(super) fn read_entry<C: Context>(
+    reader: &mut BufReader<File>,
+) -> Result<Option<Entry<C>>, ReadWalError> {
+    // Deserialize the size of the entry, in bytes, as a u64.
+    let mut entry_size_buf = [0u8; mem::size_of::<u64>()];
+    if let Err(err) = reader.read_exact(&mut entry_size_buf) {
+        if err.kind() == io::ErrorKind::UnexpectedEof {
+            return Ok(None);
+        }
+        return Err(ReadWalError::OtherIOError(err));
+    }
+    let entry_size = u64::from_le_bytes(entry_size_buf);
+
+    // If the size was only partially written, it can exceed the rest of the file: Don't allocate
+    // a buffer for it.
+    let remaining = reader
+        .get_ref()
+        .metadata()?
+        .len()
+        .saturating_sub(reader.stream_position()?);
+    if entry_size > remaining {
+        return Ok(None);
+    }
+
+    // Read the serialized entry itself.
+    let mut entry_buf = vec![0; entry_size as usize];
+    if let Err(err) = reader.read_exact(&mut entry_buf) {
+        if err.kind() == io::ErrorKind::UnexpectedEof {
+            return Ok(None);
+        }
+        return Err(ReadWalError::OtherIOError(err));
+    }
+
+    // Deserialize and return the entry.
+    let entry = bincode::deserialize(&entry_buf).map_err(ReadWalError::CouldNotDeserialize)?;
+    Ok(Some(entry))
+}
+
 #[cfg(test)]
 mod tests {
     use std::iter::from_fn;
@@ -216,6 +240,7 @@ mod tests {
                 validator_idx,
                 &alice_keypair,
             )
+            .unwrap()
         })
     }
 
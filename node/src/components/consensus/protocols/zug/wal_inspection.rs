@@ -0,0 +1,712 @@
+//! Inspection and repair of Zug write-ahead logs.
+//!
+//! The WAL is what keeps a restarted validator from signing messages that conflict with the ones it
+//! signed before, so deleting it after a crash risks an equivocation. Instead, it can be inspected
+//! while the node is stopped: This lists the entries, checks their signatures and order, reports
+//! the rounds in which this validator already signed an echo or vote, and can cut off a corrupted
+//! tail, after which the node accepts the WAL again.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display, Formatter},
+    fs::OpenOptions,
+    io::{self, BufReader, Seek},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use thiserror::Error;
+
+use casper_hashing::Digest;
+use casper_types::{AsymmetricType, PublicKey, Timestamp};
+
+use super::{
+    wal::{self, Entry, ReadWalError},
+    Content, Proposal, RoundId, SignedMessage,
+};
+use crate::components::consensus::{cl_context::ClContext, utils::ValidatorIndex};
+
+/// The prefix of the WAL file names, followed by the instance ID and the validator's public key.
+const FILE_NAME_PREFIX: &str = "unit_";
+/// The extension of the WAL file names.
+const FILE_NAME_SUFFIX: &str = ".dat";
+
+/// Error inspecting a WAL.
+#[derive(Debug, Error)]
+pub(crate) enum WalInspectionError {
+    /// The WAL file could not be opened, read or truncated.
+    #[error("could not access WAL file {0}: {1}")]
+    Io(PathBuf, io::Error),
+    /// Our public key is not among the given validators.
+    #[error("{0} is not among the given validators")]
+    UnknownPublicKey(PublicKey),
+}
+
+/// Whether a signature was verified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub(crate) enum SignatureStatus {
+    /// The signature is valid.
+    Valid,
+    /// The signature is invalid, or the signer's index doesn't belong to any validator.
+    Invalid,
+    /// The signer's public key is unknown.
+    Unverified,
+}
+
+/// The signed content of an echo or vote.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub(crate) enum SignedContent {
+    /// An echo of the proposal with the given hash.
+    Echo(Digest),
+    /// A vote.
+    Vote(bool),
+}
+
+impl From<Content<ClContext>> for SignedContent {
+    fn from(content: Content<ClContext>) -> Self {
+        match content {
+            Content::Echo(hash) => SignedContent::Echo(hash),
+            Content::Vote(vote) => SignedContent::Vote(vote),
+        }
+    }
+}
+
+impl Display for SignedContent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SignedContent::Echo(hash) => write!(f, "echo {}", hash),
+            SignedContent::Vote(vote) => write!(f, "vote {}", vote),
+        }
+    }
+}
+
+/// The content of a WAL entry.
+#[derive(Debug, Serialize)]
+pub(crate) enum EntryContent {
+    /// A signed echo or vote.
+    Signed(SignedContent),
+    /// A proposal.
+    Proposal {
+        /// The proposal's hash, which the echoes refer to.
+        hash: Digest,
+        /// The proposal's timestamp.
+        timestamp: Timestamp,
+        /// The parent round, or `None` for the first block in the era.
+        parent_round_id: Option<RoundId>,
+        /// The number of deploys and transfers, or `None` if the proposal has no block.
+        deploys: Option<usize>,
+    },
+    /// Evidence that a validator signed two conflicting contents.
+    Evidence(SignedContent, SignedContent),
+}
+
+impl Display for EntryContent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EntryContent::Signed(content) => write!(f, "{}", content),
+            EntryContent::Proposal {
+                hash,
+                timestamp,
+                parent_round_id,
+                deploys,
+            } => {
+                write!(f, "proposal {} at {}", hash, timestamp)?;
+                if let Some(parent_round_id) = parent_round_id {
+                    write!(f, ", parent round {}", parent_round_id)?;
+                }
+                match deploys {
+                    Some(deploys) => write!(f, ", {} deploys", deploys),
+                    None => write!(f, ", no block"),
+                }
+            }
+            EntryContent::Evidence(content, conflicting_content) => {
+                write!(f, "evidence: {} and {}", content, conflicting_content)
+            }
+        }
+    }
+}
+
+/// A WAL entry in readable form.
+#[derive(Debug, Serialize)]
+pub(crate) struct WalEntry {
+    /// The entry's position in the file, in bytes.
+    pub(crate) offset: u64,
+    /// The round the entry belongs to.
+    pub(crate) round_id: RoundId,
+    /// The index of the validator who signed the entry, or `None` for a proposal.
+    pub(crate) validator_idx: Option<u32>,
+    /// The entry's content.
+    pub(crate) content: EntryContent,
+    /// The result of verifying the entry's signatures, or `None` for a proposal.
+    pub(crate) signature: Option<SignatureStatus>,
+}
+
+impl Display for WalEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>10}: round {}: {}",
+            self.offset, self.round_id, self.content
+        )?;
+        if let Some(validator_idx) = self.validator_idx {
+            write!(f, " by validator {}", validator_idx)?;
+        }
+        if let Some(signature) = self.signature {
+            write!(f, " ({:?} signature)", signature)?;
+        }
+        Ok(())
+    }
+}
+
+/// The echo and vote this validator signed in a round.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct SignedRound {
+    /// The hash of the proposal we echoed.
+    pub(crate) echo: Option<Digest>,
+    /// Our vote.
+    pub(crate) vote: Option<bool>,
+}
+
+/// A problem found in a WAL.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub(crate) enum WalProblem {
+    /// The file ends with bytes that don't form a valid entry.
+    CorruptedTail {
+        /// The position of the first unreadable entry.
+        offset: u64,
+        /// The number of unreadable bytes.
+        length: u64,
+        /// Why the entry couldn't be read.
+        error: String,
+    },
+    /// An entry has an invalid signature.
+    InvalidSignature { offset: u64 },
+    /// An entry belongs to a different consensus instance than the WAL.
+    InstanceMismatch { offset: u64 },
+    /// A proposal's parent was not recorded before it.
+    MissingParent {
+        offset: u64,
+        parent_round_id: RoundId,
+    },
+    /// Evidence whose two contents don't conflict.
+    InvalidEvidence { offset: u64 },
+    /// This validator signed conflicting contents in a round.
+    OwnEquivocation { offset: u64, round_id: RoundId },
+}
+
+impl Display for WalProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WalProblem::CorruptedTail {
+                offset,
+                length,
+                error,
+            } => write!(
+                f,
+                "{} unreadable bytes at offset {}: {}",
+                length, offset, error
+            ),
+            WalProblem::InvalidSignature { offset } => {
+                write!(f, "invalid signature in entry at offset {}", offset)
+            }
+            WalProblem::InstanceMismatch { offset } => {
+                write!(f, "entry at offset {} belongs to another instance", offset)
+            }
+            WalProblem::MissingParent {
+                offset,
+                parent_round_id,
+            } => write!(
+                f,
+                "proposal at offset {} comes before its parent in round {}",
+                offset, parent_round_id
+            ),
+            WalProblem::InvalidEvidence { offset } => {
+                write!(f, "invalid evidence at offset {}", offset)
+            }
+            WalProblem::OwnEquivocation { offset, round_id } => write!(
+                f,
+                "entry at offset {} conflicts with what this validator signed in round {}",
+                offset, round_id
+            ),
+        }
+    }
+}
+
+/// The result of inspecting a WAL.
+#[derive(Debug, Serialize)]
+pub(crate) struct WalReport {
+    /// The path of the WAL file.
+    pub(crate) path: PathBuf,
+    /// The ID of the consensus instance the WAL belongs to.
+    pub(crate) instance_id: Option<Digest>,
+    /// This validator's public key.
+    pub(crate) public_key: Option<PublicKey>,
+    /// This validator's index, if known.
+    pub(crate) validator_idx: Option<u32>,
+    /// The readable entries.
+    pub(crate) entries: Vec<WalEntry>,
+    /// The echoes and votes this validator signed, by round.
+    pub(crate) signed_rounds: BTreeMap<RoundId, SignedRound>,
+    /// The problems found.
+    pub(crate) problems: Vec<WalProblem>,
+    /// The size of the file, in bytes.
+    pub(crate) file_length: u64,
+    /// The size of the readable part of the file, in bytes.
+    pub(crate) valid_length: u64,
+    /// Whether the unreadable part of the file was removed.
+    pub(crate) truncated: bool,
+}
+
+impl WalReport {
+    /// Returns whether there are problems other than a corrupted tail that was removed.
+    pub(crate) fn has_unresolved_problems(&self) -> bool {
+        self.problems
+            .iter()
+            .any(|problem| !self.truncated || !matches!(problem, WalProblem::CorruptedTail { .. }))
+    }
+}
+
+impl Display for WalReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "WAL {}", self.path.display())?;
+        if let Some(instance_id) = &self.instance_id {
+            writeln!(f, "instance: {:?}", instance_id)?;
+        }
+        match (&self.public_key, self.validator_idx) {
+            (Some(public_key), Some(idx)) => {
+                writeln!(f, "validator: {} (index {})", public_key, idx)?
+            }
+            (Some(public_key), None) => {
+                writeln!(f, "validator: {} (no messages signed)", public_key)?
+            }
+            (None, _) => writeln!(f, "validator: unknown")?,
+        }
+        writeln!(f, "entries:")?;
+        for entry in &self.entries {
+            writeln!(f, "  {}", entry)?;
+        }
+        writeln!(f, "rounds signed by this validator:")?;
+        for (round_id, signed_round) in &self.signed_rounds {
+            write!(f, "  round {}:", round_id)?;
+            if let Some(hash) = signed_round.echo {
+                write!(f, " echo {}", hash)?;
+            }
+            if let Some(vote) = signed_round.vote {
+                write!(f, " vote {}", vote)?;
+            }
+            writeln!(f)?;
+        }
+        if self.problems.is_empty() {
+            writeln!(f, "no problems found")?;
+        } else {
+            writeln!(f, "problems:")?;
+            for problem in &self.problems {
+                writeln!(f, "  {}", problem)?;
+            }
+        }
+        write!(
+            f,
+            "{} of {} bytes readable",
+            self.valid_length, self.file_length
+        )?;
+        if self.truncated {
+            write!(f, ", unreadable bytes removed")?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Inspects the Zug WAL at the given path, and removes a corrupted tail if `truncate` is set.
+///
+/// Our public key and the instance ID are taken from the file name, unless the key is given.
+/// Signatures by other validators can only be verified if the era's validators are given.
+pub(crate) fn inspect_zug_wal(
+    wal_path: &Path,
+    maybe_public_key: Option<PublicKey>,
+    validators: Vec<PublicKey>,
+    truncate: bool,
+) -> Result<WalReport, WalInspectionError> {
+    let io_err = |err| WalInspectionError::Io(wal_path.to_path_buf(), err);
+    let (instance_id, key_from_file_name) = parse_file_name(wal_path).unzip();
+    let mut inspection = Inspection::new(instance_id, maybe_public_key.or(key_from_file_name));
+    inspection.set_validators(validators)?;
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(truncate)
+        .open(wal_path)
+        .map_err(io_err)?;
+    let file_length = file.metadata().map_err(io_err)?.len();
+    let mut reader = BufReader::new(file);
+    let mut valid_length = 0;
+    loop {
+        let offset = reader.stream_position().map_err(io_err)?;
+        let error = match wal::read_entry::<ClContext>(&mut reader) {
+            Ok(Some(entry)) => {
+                inspection.inspect(offset, entry);
+                continue;
+            }
+            Ok(None) if offset == file_length => None,
+            Ok(None) => Some("incomplete entry".to_string()),
+            Err(ReadWalError::CouldNotDeserialize(err)) => Some(err.to_string()),
+            Err(ReadWalError::OtherIOError(err)) => return Err(io_err(err)),
+            Err(ReadWalError::FileCouldntBeCreated(_, err)) => return Err(io_err(err)),
+        };
+        valid_length = offset;
+        if let Some(error) = error {
+            inspection.problems.push(WalProblem::CorruptedTail {
+                offset,
+                length: file_length.saturating_sub(offset),
+                error,
+            });
+        }
+        break;
+    }
+
+    let truncated = truncate && valid_length < file_length;
+    if truncated {
+        reader.get_mut().set_len(valid_length).map_err(io_err)?;
+    }
+
+    Ok(WalReport {
+        path: wal_path.to_path_buf(),
+        instance_id: inspection.instance_id,
+        public_key: inspection.public_key,
+        validator_idx: inspection.validator_idx.map(|idx| idx.0),
+        entries: inspection.entries,
+        signed_rounds: inspection.signed_rounds,
+        problems: inspection.problems,
+        file_length,
+        valid_length,
+        truncated,
+    })
+}
+
+/// Returns the instance ID and public key encoded in a WAL file name, if it has the usual form.
+fn parse_file_name(wal_path: &Path) -> Option<(Digest, PublicKey)> {
+    let file_name = wal_path.file_name()?.to_str()?;
+    let name = file_name
+        .strip_prefix(FILE_NAME_PREFIX)?
+        .strip_suffix(FILE_NAME_SUFFIX)?;
+    let (instance_id_hex, public_key_hex) = name.split_once('_')?;
+    let instance_id = Digest::from_hex(instance_id_hex).ok()?;
+    let public_key = PublicKey::from_hex(public_key_hex).ok()?;
+    Some((instance_id, public_key))
+}
+
+/// The state of an ongoing inspection.
+struct Inspection {
+    instance_id: Option<Digest>,
+    public_key: Option<PublicKey>,
+    validator_idx: Option<ValidatorIndex>,
+    /// The era's validators, sorted by public key, or empty if unknown.
+    validators: Vec<PublicKey>,
+    /// The rounds for which a proposal was recorded.
+    proposal_rounds: BTreeSet<RoundId>,
+    entries: Vec<WalEntry>,
+    signed_rounds: BTreeMap<RoundId, SignedRound>,
+    problems: Vec<WalProblem>,
+}
+
+impl Inspection {
+    fn new(instance_id: Option<Digest>, public_key: Option<PublicKey>) -> Self {
+        Inspection {
+            instance_id,
+            public_key,
+            validator_idx: None,
+            validators: Vec::new(),
+            proposal_rounds: BTreeSet::new(),
+            entries: Vec::new(),
+            signed_rounds: BTreeMap::new(),
+            problems: Vec::new(),
+        }
+    }
+
+    /// Sets the era's validators and looks up our index among them.
+    fn set_validators(&mut self, mut validators: Vec<PublicKey>) -> Result<(), WalInspectionError> {
+        if validators.is_empty() {
+            return Ok(());
+        }
+        // Validator indices refer to the validators ordered by public key.
+        validators.sort();
+        validators.dedup();
+        if let Some(public_key) = &self.public_key {
+            let idx = validators
+                .binary_search(public_key)
+                .map_err(|_| WalInspectionError::UnknownPublicKey(public_key.clone()))?;
+            self.validator_idx = Some(ValidatorIndex(idx as u32));
+        }
+        self.validators = validators;
+        Ok(())
+    }
+
+    /// Checks the entry and adds it to the report.
+    fn inspect(&mut self, offset: u64, entry: Entry<ClContext>) {
+        let wal_entry = match entry {
+            Entry::SignedMessage(signed_msg) => {
+                let signature = self.check_message(offset, &signed_msg);
+                if signature == SignatureStatus::Valid
+                    && Some(signed_msg.validator_idx) == self.validator_idx
+                {
+                    self.record_own_content(offset, signed_msg.round_id, signed_msg.content);
+                }
+                WalEntry {
+                    offset,
+                    round_id: signed_msg.round_id,
+                    validator_idx: Some(signed_msg.validator_idx.0),
+                    content: EntryContent::Signed(signed_msg.content.into()),
+                    signature: Some(signature),
+                }
+            }
+            Entry::Proposal(proposal, round_id) => self.check_proposal(offset, proposal, round_id),
+            Entry::Evidence(signed_msg, content2, signature2) => {
+                let mut signature = self.check_message(offset, &signed_msg);
+                let signed_msg2 = signed_msg.with(content2, signature2);
+                if signature == SignatureStatus::Valid {
+                    signature = self.check_message(offset, &signed_msg2);
+                }
+                if !signed_msg.content.contradicts(&content2) {
+                    self.problems.push(WalProblem::InvalidEvidence { offset });
+                } else if signature == SignatureStatus::Valid
+                    && Some(signed_msg.validator_idx) == self.validator_idx
+                {
+                    self.problems.push(WalProblem::OwnEquivocation {
+                        offset,
+                        round_id: signed_msg.round_id,
+                    });
+                }
+                WalEntry {
+                    offset,
+                    round_id: signed_msg.round_id,
+                    validator_idx: Some(signed_msg.validator_idx.0),
+                    content: EntryContent::Evidence(signed_msg.content.into(), content2.into()),
+                    signature: Some(signature),
+                }
+            }
+        };
+        self.entries.push(wal_entry);
+    }
+
+    /// Checks a signed message's instance ID and signature.
+    fn check_message(
+        &mut self,
+        offset: u64,
+        signed_msg: &SignedMessage<ClContext>,
+    ) -> SignatureStatus {
+        match self.instance_id {
+            None => self.instance_id = Some(signed_msg.instance_id),
+            Some(instance_id) if instance_id != signed_msg.instance_id => {
+                self.problems.push(WalProblem::InstanceMismatch { offset });
+            }
+            Some(_) => {}
+        }
+        let signature = self.check_signature(signed_msg);
+        if signature == SignatureStatus::Invalid {
+            self.problems.push(WalProblem::InvalidSignature { offset });
+        }
+        signature
+    }
+
+    /// Verifies the signature if the signer's public key is known.
+    ///
+    /// If the validators are not known, this only verifies our own signatures: We learn our index
+    /// from the first message that has a valid signature by our key.
+    fn check_signature(&mut self, signed_msg: &SignedMessage<ClContext>) -> SignatureStatus {
+        let maybe_signer = if !self.validators.is_empty() {
+            match self.validators.get(signed_msg.validator_idx.0 as usize) {
+                Some(public_key) => Some(public_key),
+                None => return SignatureStatus::Invalid,
+            }
+        } else if self.validator_idx.is_none() {
+            match &self.public_key {
+                Some(public_key) if signed_msg.verify_signature(public_key) => {
+                    self.validator_idx = Some(signed_msg.validator_idx);
+                    return SignatureStatus::Valid;
+                }
+                _ => None,
+            }
+        } else if self.validator_idx == Some(signed_msg.validator_idx) {
+            self.public_key.as_ref()
+        } else {
+            None
+        };
+        match maybe_signer {
+            Some(public_key) if signed_msg.verify_signature(public_key) => SignatureStatus::Valid,
+            Some(_) => SignatureStatus::Invalid,
+            None => SignatureStatus::Unverified,
+        }
+    }
+
+    /// Checks that the proposal's parent was recorded before it.
+    fn check_proposal(
+        &mut self,
+        offset: u64,
+        proposal: Proposal<ClContext>,
+        round_id: RoundId,
+    ) -> WalEntry {
+        if let Some(parent_round_id) = proposal.maybe_parent_round_id {
+            if parent_round_id >= round_id || !self.proposal_rounds.contains(&parent_round_id) {
+                self.problems.push(WalProblem::MissingParent {
+                    offset,
+                    parent_round_id,
+                });
+            }
+        }
+        let _ = self.proposal_rounds.insert(round_id);
+        WalEntry {
+            offset,
+            round_id,
+            validator_idx: None,
+            content: EntryContent::Proposal {
+                hash: proposal.hash(),
+                timestamp: proposal.timestamp,
+                parent_round_id: proposal.maybe_parent_round_id,
+                deploys: proposal
+                    .maybe_block
+                    .as_ref()
+                    .map(|block| block.deploy_and_transfer_hashes().count()),
+            },
+            signature: None,
+        }
+    }
+
+    /// Records an echo or vote we signed, and reports it if it conflicts with an earlier one.
+    fn record_own_content(&mut self, offset: u64, round_id: RoundId, content: Content<ClContext>) {
+        let signed_round = self.signed_rounds.entry(round_id).or_default();
+        let conflicts = match content {
+            Content::Echo(hash) => *signed_round.echo.get_or_insert(hash) != hash,
+            Content::Vote(vote) => *signed_round.vote.get_or_insert(vote) != vote,
+        };
+        if conflicts {
+            self.problems
+                .push(WalProblem::OwnEquivocation { offset, round_id });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{self, OpenOptions},
+        io::Write,
+        sync::Arc,
+    };
+
+    use casper_types::{SecretKey, U512};
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::components::consensus::{cl_context::Keypair, protocols::common, traits::Context};
+
+    #[test]
+    fn should_inspect_and_truncate_wal() {
+        let alice_secret_key = Arc::new(SecretKey::ed25519_from_bytes([1; 32]).unwrap());
+        let alice_public_key = PublicKey::from(&*alice_secret_key);
+        let bob_secret_key = Arc::new(SecretKey::ed25519_from_bytes([2; 32]).unwrap());
+        let bob_public_key = PublicKey::from(&*bob_secret_key);
+        let validator_keys = vec![alice_public_key.clone(), bob_public_key.clone()];
+        let validators = common::validators::<ClContext>(
+            &Default::default(),
+            &Default::default(),
+            validator_keys
+                .iter()
+                .map(|public_key| (public_key.clone(), U512::from(100)))
+                .collect(),
+        );
+        let instance_id = ClContext::hash(&[1, 2, 3]);
+        let sign = |secret_key: &Arc<SecretKey>, round_id, content| {
+            let keypair = Keypair::from(Arc::clone(secret_key));
+            let validator_idx = validators.get_index(keypair.public_key()).unwrap();
+            SignedMessage::sign_new(round_id, instance_id, content, validator_idx, &keypair)
+                .unwrap()
+        };
+        let proposal = Proposal::dummy(Timestamp::zero(), 0);
+        let hash = proposal.hash();
+        let other_hash = ClContext::hash(&[4, 5, 6]);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(format!(
+            "unit_{:?}_{}.dat",
+            instance_id,
+            alice_public_key.to_hex()
+        ));
+        let mut write_wal: wal::WriteWal<ClContext> = wal::WriteWal::new(&path).unwrap();
+        let entries = vec![
+            // Bob's proposal in round 1 comes before the proposal for its parent round.
+            Entry::Proposal(proposal.clone(), 1),
+            Entry::SignedMessage(sign(&bob_secret_key, 1, Content::Echo(hash))),
+            Entry::SignedMessage(sign(&alice_secret_key, 1, Content::Echo(hash))),
+            Entry::SignedMessage(sign(&alice_secret_key, 1, Content::Vote(true))),
+            Entry::SignedMessage(sign(&alice_secret_key, 2, Content::Vote(false))),
+            // Alice echoes a different proposal in round 1.
+            Entry::SignedMessage(sign(&alice_secret_key, 1, Content::Echo(other_hash))),
+        ];
+        for entry in &entries {
+            write_wal.record_entry(entry).unwrap();
+        }
+        drop(write_wal);
+        let valid_length = fs::metadata(&path).unwrap().len();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[1, 0, 0, 0, 0, 0, 0, 0, 42]).unwrap();
+        drop(file);
+
+        // Without the validators, only Alice's signatures can be verified.
+        let report = inspect_zug_wal(&path, None, vec![], false).unwrap();
+        assert_eq!(report.instance_id, Some(instance_id));
+        assert_eq!(report.public_key, Some(alice_public_key.clone()));
+        assert_eq!(report.entries.len(), entries.len());
+        assert_eq!(
+            report.entries[1].signature,
+            Some(SignatureStatus::Unverified)
+        );
+        assert_eq!(report.entries[2].signature, Some(SignatureStatus::Valid));
+        assert_eq!(report.problems.len(), 3);
+        assert_eq!(
+            report.problems[0],
+            WalProblem::MissingParent {
+                offset: 0,
+                parent_round_id: 0
+            }
+        );
+        assert_eq!(
+            report.problems[1],
+            WalProblem::OwnEquivocation {
+                offset: report.entries[5].offset,
+                round_id: 1
+            }
+        );
+        assert!(matches!(
+            report.problems[2],
+            WalProblem::CorruptedTail { offset, length: 9, .. } if offset == valid_length
+        ));
+        let mut expected_rounds = BTreeMap::new();
+        expected_rounds.insert(
+            1,
+            SignedRound {
+                echo: Some(hash),
+                vote: Some(true),
+            },
+        );
+        expected_rounds.insert(
+            2,
+            SignedRound {
+                echo: None,
+                vote: Some(false),
+            },
+        );
+        assert_eq!(report.signed_rounds, expected_rounds);
+        assert_eq!(report.valid_length, valid_length);
+        assert!(!report.truncated);
+        assert!(report.has_unresolved_problems());
+
+        // With the validators, Bob's signatures are verified too. Now remove the corrupted tail.
+        let report = inspect_zug_wal(&path, None, validator_keys.clone(), true).unwrap();
+        assert_eq!(report.entries[1].signature, Some(SignatureStatus::Valid));
+        assert!(report.truncated);
+        assert_eq!(fs::metadata(&path).unwrap().len(), valid_length);
+
+        let report = inspect_zug_wal(&path, None, validator_keys, false).unwrap();
+        assert_eq!(report.problems.len(), 2);
+        assert_eq!(report.file_length, valid_length);
+    }
+}
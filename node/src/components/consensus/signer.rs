@@ -0,0 +1,283 @@
+//! Signing of consensus messages and finality signatures.
+//!
+//! A validator's messages are signed by a [`Signer`], which either holds the secret key in memory
+//! or forwards each request to a separate signer process over a UNIX socket, so that the secret key
+//! never has to be present on the node's host. Every request identifies what is being signed, so
+//! that a [`ProtectedSigner`] can refuse to sign two conflicting messages.
+//!
+//! Since signing can block, e.g. while waiting for the remote signer or writing the protection
+//! log, signatures are created on a blocking thread via [`sign_in_background`], unless the signer
+//! [signs instantly](Signer::signs_instantly).
+
+mod protection;
+mod remote;
+mod server;
+
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use datasize::DataSize;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::task::JoinError;
+
+use casper_hashing::Digest;
+use casper_types::{crypto, EraId, PublicKey, SecretKey, Signature};
+
+use super::traits::SigningContext;
+use crate::{
+    types::BlockHash,
+    utils::{LoadError, Loadable},
+};
+pub(crate) use protection::{
+    export_protection_log, import_protection_log, DoubleSignProtection, ProtectionError,
+};
+pub(crate) use remote::{RemoteSigner, RemoteSignerConfig};
+pub(crate) use server::run_remote_signer;
+#[cfg(test)]
+pub(crate) use server::start_test_remote_signer;
+
+/// The prefix of the message a consensus certificate is signed over.
+const CONSENSUS_CERTIFICATE_TAG: &[u8] = b"casper-consensus-certificate";
+
+/// Error loading the secret key of a local signer.
+pub(crate) type LoadSecretKeyError = LoadError<<Arc<SecretKey> as Loadable>::Error>;
+
+/// A request to sign a message with the validator's secret key.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum SigningRequest {
+    /// A consensus message in the given era.
+    Consensus {
+        /// The era of the consensus instance the message belongs to.
+        era_id: EraId,
+        /// What kind of message is signed.
+        context: SigningContext,
+        /// The serialized message. The signature is created over its hash.
+        serialized: Vec<u8>,
+    },
+    /// A finality signature for a block.
+    FinalitySignature {
+        /// The era of the block.
+        era_id: EraId,
+        /// The height of the block.
+        block_height: u64,
+        /// The hash of the block.
+        block_hash: BlockHash,
+    },
+    /// A consensus certificate for a network handshake, proving that the node holds the key.
+    ConsensusCertificate {
+        /// The ID of the connection the handshake is sent on.
+        connection_id: Digest,
+    },
+}
+
+impl SigningRequest {
+    /// Returns the bytes the signature is created over.
+    pub(crate) fn message(&self) -> Vec<u8> {
+        match self {
+            SigningRequest::Consensus { serialized, .. } => {
+                Digest::hash(serialized).value().to_vec()
+            }
+            // This needs to be in sync with `FinalitySignature::is_verified`.
+            SigningRequest::FinalitySignature {
+                era_id, block_hash, ..
+            } => {
+                let mut bytes = block_hash.inner().into_vec();
+                bytes.extend_from_slice(&era_id.to_le_bytes());
+                bytes
+            }
+            // This needs to be in sync with `ConsensusCertificate::validate`.
+            SigningRequest::ConsensusCertificate { connection_id } => {
+                consensus_certificate_message(connection_id)
+            }
+        }
+    }
+}
+
+impl Display for SigningRequest {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SigningRequest::Consensus {
+                era_id,
+                context,
+                serialized,
+            } => write!(
+                formatter,
+                "{} {} in {}",
+                context,
+                Digest::hash(serialized),
+                era_id
+            ),
+            SigningRequest::FinalitySignature {
+                era_id,
+                block_height,
+                block_hash,
+            } => write!(
+                formatter,
+                "finality signature for block {} at height {} in {}",
+                block_hash, block_height, era_id
+            ),
+            SigningRequest::ConsensusCertificate { .. } => {
+                write!(formatter, "consensus certificate")
+            }
+        }
+    }
+}
+
+/// Error signing a message.
+#[derive(Debug, Error)]
+pub(crate) enum SignerError {
+    /// Failed to load the secret key.
+    #[error("failed to load secret key: {0}")]
+    LoadSecretKey(#[from] LoadSecretKeyError),
+    /// The connection to the remote signer failed.
+    #[error("remote signer connection failed: {0}")]
+    Io(#[from] io::Error),
+    /// A message to or from the remote signer could not be (de)serialized.
+    #[error("failed to serialize remote signer message: {0}")]
+    Serialization(#[from] bincode::Error),
+    /// A message from the remote signer exceeded the size limit.
+    #[error("remote signer message of {0} bytes exceeds size limit")]
+    MessageTooLarge(usize),
+    /// The remote signer sent a response not matching our request.
+    #[error("unexpected response from remote signer")]
+    UnexpectedResponse,
+    /// The remote signer refused to sign.
+    #[error("remote signer refused to sign: {0}")]
+    Refused(String),
+    /// The double-signing protection log could not be used.
+    #[error(transparent)]
+    Protection(#[from] ProtectionError),
+    /// The double-signing protection log of a validator is missing.
+    #[error(
+        "double-signing protection log {} is missing, but our key is in a recent validator set; \
+        restore or import the log, or set `consensus.create_missing_double_sign_protection` if the \
+        key never signed without it",
+        .0.display()
+    )]
+    MissingProtectionLog(PathBuf),
+    /// The blocking task signing the request failed.
+    #[error("signing task failed: {0}")]
+    Task(JoinError),
+}
+
+/// Signs messages on behalf of a validator.
+pub(crate) trait Signer: Send + Sync + Debug {
+    /// Returns the public key of the validator.
+    fn public_key(&self) -> &PublicKey;
+
+    /// Signs the given request.
+    ///
+    /// This may block, see `signs_instantly`.
+    fn sign(&self, request: &SigningRequest) -> Result<Signature, SignerError>;
+
+    /// Returns `true` if `sign` never blocks, so that it can be called from the reactor.
+    fn signs_instantly(&self) -> bool {
+        false
+    }
+}
+
+/// Returns the bytes a consensus certificate for the given connection is signed over.
+///
+/// The tag makes them longer than the hash of a consensus message or the message of a finality
+/// signature, so that a certificate can never be passed off as either.
+pub(crate) fn consensus_certificate_message(connection_id: &Digest) -> Vec<u8> {
+    let mut bytes = CONSENSUS_CERTIFICATE_TAG.to_vec();
+    bytes.extend_from_slice(connection_id.value());
+    bytes
+}
+
+/// Signs the given request on a blocking thread, so that a slow signer doesn't hold up the caller.
+pub(crate) async fn sign_in_background(
+    signer: Arc<dyn Signer>,
+    request: SigningRequest,
+) -> Result<Signature, SignerError> {
+    match tokio::task::spawn_blocking(move || signer.sign(&request)).await {
+        Ok(result) => result,
+        Err(error) => Err(SignerError::Task(error)),
+    }
+}
+
+/// A signer holding the secret key in memory.
+#[derive(DataSize)]
+pub(crate) struct LocalSigner {
+    secret_key: Arc<SecretKey>,
+    public_key: PublicKey,
+}
+
+impl LocalSigner {
+    /// Creates a new local signer.
+    pub(crate) fn new(secret_key: Arc<SecretKey>) -> Self {
+        let public_key = PublicKey::from(secret_key.as_ref());
+        LocalSigner {
+            secret_key,
+            public_key,
+        }
+    }
+}
+
+impl Debug for LocalSigner {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("LocalSigner")
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Signer for LocalSigner {
+    fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    fn sign(&self, request: &SigningRequest) -> Result<Signature, SignerError> {
+        Ok(crypto::sign(
+            request.message(),
+            &self.secret_key,
+            &self.public_key,
+        ))
+    }
+
+    fn signs_instantly(&self) -> bool {
+        true
+    }
+}
+
+/// A signer refusing to sign messages conflicting with ones it signed before.
+#[derive(Debug)]
+pub(crate) struct ProtectedSigner<S> {
+    /// The signer creating the signatures.
+    signer: S,
+    /// The record of all messages signed before.
+    protection: Mutex<DoubleSignProtection>,
+}
+
+impl<S: Signer> ProtectedSigner<S> {
+    /// Creates a new signer, protected by the given double-signing protection log.
+    pub(crate) fn new(signer: S, protection: DoubleSignProtection) -> Self {
+        ProtectedSigner {
+            signer,
+            protection: Mutex::new(protection),
+        }
+    }
+}
+
+impl<S: Signer> Signer for ProtectedSigner<S> {
+    fn public_key(&self) -> &PublicKey {
+        self.signer.public_key()
+    }
+
+    fn sign(&self, request: &SigningRequest) -> Result<Signature, SignerError> {
+        // The lock is held while signing, so that conflicting requests are strictly ordered.
+        let mut protection = self
+            .protection
+            .lock()
+            .expect("double-signing protection lock poisoned");
+        protection.check_and_record(request)?;
+        self.signer.sign(request)
+    }
+}
@@ -0,0 +1,545 @@
+//! Double-signing protection of a signer.
+//!
+//! Every signed message which could conflict with another one is recorded in an append-only log,
+//! keyed by the era and the slot it occupies, e.g. the sequence number of a Highway unit. A request
+//! to sign a different message for an occupied slot is refused.
+//!
+//! The slot is derived from the consensus message itself rather than trusted from the request, and
+//! a message that is not exactly one kind of consensus message, or not the kind claimed, is
+//! refused.
+//!
+//! The same log is used by the node when signing locally and by the remote signer, and covers both
+//! Highway and Zug. It can be exported to and imported from JSON, so that it can be moved along
+//! with the validator's key to a new host.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Write},
+    mem,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use casper_hashing::Digest;
+use casper_types::EraId;
+
+use super::SigningRequest;
+use crate::components::consensus::{
+    highway_core::highway, protocols::zug, traits::SigningContext, ClContext,
+};
+
+/// The number of eras, counting back from the most recent one, whose records are kept.
+///
+/// Requests for older eras are refused.
+const RETAINED_ERAS: u64 = 10;
+
+/// A slot which can only be occupied by a single signed message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+enum Slot {
+    /// A Highway unit with the given sequence number.
+    HighwayUnit { seq_number: u64 },
+    /// A Zug echo in the given round.
+    ZugEcho { round_id: u32 },
+    /// A Zug vote in the given round.
+    ZugVote { round_id: u32 },
+    /// A finality signature for a block at the given height.
+    FinalitySignature { block_height: u64 },
+}
+
+/// A recorded signed message.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Record {
+    era_id: EraId,
+    slot: Slot,
+    hash: Digest,
+}
+
+impl Record {
+    /// Returns the record for the given request, or `None` if the request cannot conflict with any
+    /// other request.
+    ///
+    /// Returns an error if a consensus message is not of the kind the request claims.
+    fn for_request(request: &SigningRequest) -> Result<Option<Self>, ProtectionError> {
+        let (era_id, slot, hash) = match request {
+            SigningRequest::Consensus {
+                era_id,
+                context,
+                serialized,
+            } => {
+                if consensus_message_context(serialized) != Some(*context) {
+                    return Err(ProtectionError::UnexpectedMessage {
+                        request: Box::new(request.clone()),
+                    });
+                }
+                let slot = match *context {
+                    SigningContext::HighwayUnit { seq_number, .. } => {
+                        Slot::HighwayUnit { seq_number }
+                    }
+                    SigningContext::ZugEcho { round_id } => Slot::ZugEcho { round_id },
+                    SigningContext::ZugVote { round_id, .. } => Slot::ZugVote { round_id },
+                    SigningContext::HighwayEndorsement | SigningContext::HighwayPing { .. } => {
+                        return Ok(None)
+                    }
+                };
+                (*era_id, slot, Digest::hash(serialized))
+            }
+            SigningRequest::FinalitySignature {
+                era_id,
+                block_height,
+                block_hash,
+            } => (
+                *era_id,
+                Slot::FinalitySignature {
+                    block_height: *block_height,
+                },
+                *block_hash.inner(),
+            ),
+            SigningRequest::ConsensusCertificate { .. } => return Ok(None),
+        };
+        Ok(Some(Record { era_id, slot, hash }))
+    }
+}
+
+/// Returns what kind of consensus message `serialized` is, or `None` if it is not exactly one kind
+/// of message a validator signs.
+fn consensus_message_context(serialized: &[u8]) -> Option<SigningContext> {
+    let mut contexts = highway::signing_contexts_of::<ClContext>(serialized);
+    contexts.extend(zug::signing_context_of::<ClContext>(serialized));
+    match contexts[..] {
+        [context] => Some(context),
+        _ => None,
+    }
+}
+
+/// Error checking a request against the double-signing protection.
+#[derive(Debug, Error)]
+pub(crate) enum ProtectionError {
+    /// The request conflicts with a message signed before.
+    #[error("{request} conflicts with previously signed {previous}")]
+    Equivocation {
+        /// The refused request.
+        request: Box<SigningRequest>,
+        /// The hash of the message signed before.
+        previous: Digest,
+    },
+    /// The request's message is not a consensus message of the claimed kind.
+    #[error("{request} is not a consensus message of that kind")]
+    UnexpectedMessage {
+        /// The refused request.
+        request: Box<SigningRequest>,
+    },
+    /// The request is for an era whose records were already discarded.
+    #[error("{request} is for an era older than {oldest_era}")]
+    EraTooOld {
+        /// The refused request.
+        request: Box<SigningRequest>,
+        /// The oldest era for which requests are accepted.
+        oldest_era: EraId,
+    },
+    /// The log could not be read or written.
+    #[error("double-signing protection log error: {0}")]
+    Io(#[from] io::Error),
+    /// A record could not be (de)serialized.
+    #[error("failed to serialize double-signing protection record: {0}")]
+    Serialization(#[from] bincode::Error),
+    /// An export could not be (de)serialized.
+    #[error("failed to serialize double-signing protection export: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// All records of a double-signing protection log, for moving it to another host.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ProtectionExport {
+    /// The recorded signed messages.
+    records: Vec<Record>,
+}
+
+/// The result of importing records into a double-signing protection log.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct ImportOutcome {
+    /// The number of records that were added.
+    pub(crate) imported: usize,
+    /// The number of records that were already present.
+    pub(crate) duplicate: usize,
+    /// The number of records that conflict with present ones, i.e. that equivocate.
+    pub(crate) conflicting: usize,
+    /// The number of records that were skipped because their era is no longer retained.
+    pub(crate) too_old: usize,
+}
+
+/// The double-signing protection database of a signer.
+#[derive(Debug)]
+pub(crate) struct DoubleSignProtection {
+    /// The log all new records are appended to.
+    writer: BufWriter<File>,
+    /// All records of the retained eras.
+    records: BTreeMap<(EraId, Slot), Digest>,
+    /// The oldest era for which requests are accepted.
+    oldest_era: EraId,
+}
+
+impl DoubleSignProtection {
+    /// Opens the log at the given path, creating it if it doesn't exist.
+    ///
+    /// Records of eras that are no longer retained are discarded, and the log is compacted.
+    pub(crate) fn open(path: &Path) -> Result<Self, ProtectionError> {
+        let mut records = BTreeMap::new();
+        if path.exists() {
+            let mut reader = BufReader::new(File::open(path)?);
+            while let Some(record) = read_record(&mut reader)? {
+                let _ = records.insert((record.era_id, record.slot), record.hash);
+            }
+        }
+        let newest_era = records
+            .keys()
+            .next_back()
+            .map_or(EraId::new(0), |(era_id, _)| *era_id);
+        let oldest_era = newest_era.saturating_sub(RETAINED_ERAS);
+        records.retain(|(era_id, _), _| *era_id >= oldest_era);
+
+        // Rewrite the retained records and atomically replace the log with them.
+        let compacted_path = path.with_extension("compacting");
+        {
+            let mut writer = BufWriter::new(File::create(&compacted_path)?);
+            for ((era_id, slot), hash) in &records {
+                let record = Record {
+                    era_id: *era_id,
+                    slot: *slot,
+                    hash: *hash,
+                };
+                write_record(&mut writer, &record)?;
+            }
+            writer.into_inner().map_err(|error| error.into_error())?;
+        }
+        fs::rename(&compacted_path, path)?;
+        info!(
+            path = %path.display(),
+            records = records.len(),
+            %oldest_era,
+            "opened double-signing protection log"
+        );
+
+        let file = OpenOptions::new().append(true).open(path)?;
+        Ok(DoubleSignProtection {
+            writer: BufWriter::new(file),
+            records,
+            oldest_era,
+        })
+    }
+
+    /// Checks that signing the given request cannot equivocate, and records it if so.
+    ///
+    /// Signing the same message twice is allowed.  The record is persisted before returning, so
+    /// the request must be refused if this returns an error.
+    pub(crate) fn check_and_record(
+        &mut self,
+        request: &SigningRequest,
+    ) -> Result<(), ProtectionError> {
+        let record = match Record::for_request(request)? {
+            Some(record) => record,
+            None => return Ok(()),
+        };
+        if record.era_id < self.oldest_era {
+            return Err(ProtectionError::EraTooOld {
+                request: Box::new(request.clone()),
+                oldest_era: self.oldest_era,
+            });
+        }
+        match self.records.get(&(record.era_id, record.slot)) {
+            Some(previous) if *previous == record.hash => return Ok(()),
+            Some(previous) => {
+                warn!(%request, %previous, "refusing to sign conflicting message");
+                return Err(ProtectionError::Equivocation {
+                    request: Box::new(request.clone()),
+                    previous: *previous,
+                });
+            }
+            None => (),
+        }
+
+        write_record(&mut self.writer, &record)?;
+        self.sync()?;
+        let _ = self
+            .records
+            .insert((record.era_id, record.slot), record.hash);
+        Ok(())
+    }
+
+    /// Returns all records, for importing them into another log.
+    pub(crate) fn export(&self) -> ProtectionExport {
+        let records = self
+            .records
+            .iter()
+            .map(|((era_id, slot), hash)| Record {
+                era_id: *era_id,
+                slot: *slot,
+                hash: *hash,
+            })
+            .collect();
+        ProtectionExport { records }
+    }
+
+    /// Adds the exported records of another log to this one.
+    ///
+    /// Records conflicting with present ones are not imported, and neither are records of eras
+    /// older than the ones retained here.
+    pub(crate) fn import(
+        &mut self,
+        export: ProtectionExport,
+    ) -> Result<ImportOutcome, ProtectionError> {
+        let mut outcome = ImportOutcome::default();
+        for record in export.records {
+            if record.era_id < self.oldest_era {
+                outcome.too_old += 1;
+                continue;
+            }
+            match self.records.get(&(record.era_id, record.slot)) {
+                Some(hash) if *hash == record.hash => outcome.duplicate += 1,
+                Some(hash) => {
+                    warn!(
+                        era_id = %record.era_id,
+                        slot = ?record.slot,
+                        present = %hash,
+                        imported = %record.hash,
+                        "imported record conflicts with present one"
+                    );
+                    outcome.conflicting += 1;
+                }
+                None => {
+                    write_record(&mut self.writer, &record)?;
+                    let _ = self
+                        .records
+                        .insert((record.era_id, record.slot), record.hash);
+                    outcome.imported += 1;
+                }
+            }
+        }
+        self.sync()?;
+        Ok(outcome)
+    }
+
+    /// Flushes all appended records to disk.
+    fn sync(&mut self) -> Result<(), ProtectionError> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()?;
+        Ok(())
+    }
+}
+
+/// Exports the log at `log_path` to a JSON file at `output_path`.
+///
+/// Returns the number of exported records.
+pub(crate) fn export_protection_log(
+    log_path: &Path,
+    output_path: &Path,
+) -> Result<usize, ProtectionError> {
+    let export = DoubleSignProtection::open(log_path)?.export();
+    let writer = BufWriter::new(File::create(output_path)?);
+    serde_json::to_writer_pretty(writer, &export)?;
+    Ok(export.records.len())
+}
+
+/// Imports the JSON file at `input_path` into the log at `log_path`, creating the log if it doesn't
+/// exist.
+pub(crate) fn import_protection_log(
+    log_path: &Path,
+    input_path: &Path,
+) -> Result<ImportOutcome, ProtectionError> {
+    let export = serde_json::from_reader(BufReader::new(File::open(input_path)?))?;
+    DoubleSignProtection::open(log_path)?.import(export)
+}
+
+/// Appends the given record to the writer, prefixed by its size.
+fn write_record<W: Write>(writer: &mut W, record: &Record) -> Result<(), ProtectionError> {
+    let size = bincode::serialized_size(record)?;
+    writer.write_all(&size.to_le_bytes())?;
+    bincode::serialize_into(writer, record)?;
+    Ok(())
+}
+
+/// Reads the next record, or returns `None` at the end of the log.
+///
+/// An incomplete record at the end of the log, left by a crash while writing it, is ignored: its
+/// message was never signed, since records are persisted before signing.
+fn read_record<R: Read>(reader: &mut R) -> Result<Option<Record>, ProtectionError> {
+    let mut size_buf = [0u8; mem::size_of::<u64>()];
+    let mut record_buf = match reader.read_exact(&mut size_buf) {
+        Ok(()) => vec![0; u64::from_le_bytes(size_buf) as usize],
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+    match reader.read_exact(&mut record_buf) {
+        Ok(()) => Ok(Some(bincode::deserialize(&record_buf)?)),
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => {
+            warn!("ignoring incomplete record at the end of the double-signing protection log");
+            Ok(None)
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use casper_hashing::Digest;
+    use casper_types::EraId;
+
+    use super::{DoubleSignProtection, ImportOutcome, ProtectionError, RETAINED_ERAS};
+    use crate::components::consensus::{
+        highway_core::highway::Ping,
+        protocols::zug,
+        signer::SigningRequest,
+        traits::{SigningContext, SigningInput},
+        utils::ValidatorIndex,
+        ClContext,
+    };
+
+    fn request(era_id: u64, input: SigningInput<Digest>) -> SigningRequest {
+        SigningRequest::Consensus {
+            era_id: EraId::new(era_id),
+            context: input.context,
+            serialized: input.serialized,
+        }
+    }
+
+    fn echo(era_id: u64, round_id: u32, hash: Digest) -> SigningRequest {
+        let instance_id = Digest::hash([era_id as u8]);
+        let input =
+            zug::echo_signing_input::<ClContext>(round_id, &instance_id, hash, ValidatorIndex(0));
+        request(era_id, input)
+    }
+
+    #[test]
+    fn should_refuse_equivocations_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("protection.log");
+        let (hash0, hash1) = (Digest::hash([0]), Digest::hash([1]));
+
+        let mut protection = DoubleSignProtection::open(&path).unwrap();
+        protection.check_and_record(&echo(5, 1, hash0)).unwrap();
+        // Signing the same message again is fine, and so is a different slot.
+        protection.check_and_record(&echo(5, 1, hash0)).unwrap();
+        protection.check_and_record(&echo(5, 2, hash1)).unwrap();
+        protection.check_and_record(&echo(6, 1, hash1)).unwrap();
+        assert!(matches!(
+            protection.check_and_record(&echo(5, 1, hash1)),
+            Err(ProtectionError::Equivocation { .. })
+        ));
+        // Pings can never conflict.
+        let ping = request(
+            5,
+            Ping::<ClContext>::signing_input(ValidatorIndex(0), 0.into(), hash0),
+        );
+        protection.check_and_record(&ping).unwrap();
+        protection.check_and_record(&ping).unwrap();
+        drop(protection);
+
+        // The records survive reopening the log.
+        let mut protection = DoubleSignProtection::open(&path).unwrap();
+        assert!(matches!(
+            protection.check_and_record(&echo(5, 2, hash0)),
+            Err(ProtectionError::Equivocation { .. })
+        ));
+        protection.check_and_record(&echo(5, 2, hash1)).unwrap();
+
+        // Old eras are discarded once a newer era is recorded, and requests for them refused.
+        protection
+            .check_and_record(&echo(6 + RETAINED_ERAS, 1, hash0))
+            .unwrap();
+        drop(protection);
+        let mut protection = DoubleSignProtection::open(&path).unwrap();
+        assert!(matches!(
+            protection.check_and_record(&echo(5, 3, hash0)),
+            Err(ProtectionError::EraTooOld { .. })
+        ));
+        assert!(matches!(
+            protection.check_and_record(&echo(6, 1, hash0)),
+            Err(ProtectionError::Equivocation { .. })
+        ));
+    }
+
+    #[test]
+    fn should_import_exported_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let (hash0, hash1) = (Digest::hash([0]), Digest::hash([1]));
+
+        let mut old_host = DoubleSignProtection::open(&dir.path().join("old.log")).unwrap();
+        old_host.check_and_record(&echo(5, 1, hash0)).unwrap();
+        old_host.check_and_record(&echo(5, 2, hash0)).unwrap();
+        old_host.check_and_record(&echo(5, 3, hash0)).unwrap();
+        let export = old_host.export();
+
+        // Round-trip through JSON.
+        let json = serde_json::to_string(&export).unwrap();
+        assert_eq!(export, serde_json::from_str(&json).unwrap());
+
+        let mut new_host = DoubleSignProtection::open(&dir.path().join("new.log")).unwrap();
+        new_host.check_and_record(&echo(5, 2, hash0)).unwrap();
+        new_host.check_and_record(&echo(5, 3, hash1)).unwrap();
+        let outcome = new_host.import(export).unwrap();
+        assert_eq!(
+            outcome,
+            ImportOutcome {
+                imported: 1,
+                duplicate: 1,
+                conflicting: 1,
+                too_old: 0,
+            }
+        );
+        assert!(matches!(
+            new_host.check_and_record(&echo(5, 1, hash1)),
+            Err(ProtectionError::Equivocation { .. })
+        ));
+        new_host.check_and_record(&echo(5, 1, hash0)).unwrap();
+        drop(new_host);
+
+        // The imported records are persisted.
+        let mut new_host = DoubleSignProtection::open(&dir.path().join("new.log")).unwrap();
+        assert!(matches!(
+            new_host.check_and_record(&echo(5, 1, hash1)),
+            Err(ProtectionError::Equivocation { .. })
+        ));
+    }
+
+    #[test]
+    fn should_refuse_messages_not_of_the_claimed_kind() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut protection =
+            DoubleSignProtection::open(&dir.path().join("protection.log")).unwrap();
+
+        // An echo claimed to be a ping would not occupy its slot.
+        let echo_as_ping = match echo(5, 1, Digest::hash([0])) {
+            SigningRequest::Consensus {
+                era_id, serialized, ..
+            } => SigningRequest::Consensus {
+                era_id,
+                context: SigningContext::HighwayPing {
+                    timestamp: 0.into(),
+                },
+                serialized,
+            },
+            _ => unreachable!(),
+        };
+        assert!(matches!(
+            protection.check_and_record(&echo_as_ping),
+            Err(ProtectionError::UnexpectedMessage { .. })
+        ));
+
+        // Neither can arbitrary data be signed as a consensus message.
+        let garbage = SigningRequest::Consensus {
+            era_id: EraId::new(5),
+            context: SigningContext::ZugEcho { round_id: 1 },
+            serialized: vec![1, 2, 3],
+        };
+        assert!(matches!(
+            protection.check_and_record(&garbage),
+            Err(ProtectionError::UnexpectedMessage { .. })
+        ));
+        protection
+            .check_and_record(&echo(5, 1, Digest::hash([0])))
+            .unwrap();
+    }
+}
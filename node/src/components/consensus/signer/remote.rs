@@ -0,0 +1,200 @@
+//! Client side of the remote signer.
+//!
+//! Requests and responses are exchanged over a UNIX socket, each serialized with bincode and
+//! prefixed with its length as a little-endian `u32`.
+
+use std::{
+    convert::TryFrom,
+    fmt::{self, Debug, Formatter},
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use datasize::DataSize;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tracing::{info, warn};
+
+use casper_types::{PublicKey, Signature, TimeDiff};
+
+use super::{Signer, SignerError, SigningRequest};
+
+/// The maximum size of a request or response.
+///
+/// Requests contain the full consensus message, so this needs to fit a unit with a block proposal,
+/// which can be as large as a network message.
+const MAX_MESSAGE_SIZE: usize = 32 * 1024 * 1024;
+
+/// Remote signer configuration.
+#[derive(DataSize, Debug, Clone, Deserialize, Serialize)]
+// Disallow unknown fields to ensure config files and command-line overrides contain valid keys.
+#[serde(deny_unknown_fields)]
+pub struct RemoteSignerConfig {
+    /// Path to the UNIX socket the remote signer listens on, relative to the config directory.
+    pub socket_path: PathBuf,
+    /// The time to wait for a response from the remote signer before giving up.
+    #[serde(default = "default_request_timeout")]
+    pub request_timeout: TimeDiff,
+}
+
+fn default_request_timeout() -> TimeDiff {
+    TimeDiff::from_seconds(5)
+}
+
+/// A request to the remote signer.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) enum SignerRequest {
+    /// Requests the public key of the signer.
+    PublicKey,
+    /// Requests a signature.
+    Sign(SigningRequest),
+}
+
+/// A response from the remote signer.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) enum SignerResponse {
+    /// The public key of the signer.
+    PublicKey(PublicKey),
+    /// The requested signature.
+    Signature(Signature),
+    /// The signer refused the request, with the reason.
+    Refused(String),
+}
+
+/// A signer forwarding every request to a remote signer process.
+pub(crate) struct RemoteSigner {
+    /// The path to the remote signer's socket.
+    socket_path: PathBuf,
+    /// The read and write timeout of the socket.
+    timeout: Duration,
+    /// The public key of the remote signer.
+    public_key: PublicKey,
+    /// The connection to the remote signer, if currently connected.
+    connection: Mutex<Option<UnixStream>>,
+}
+
+impl RemoteSigner {
+    /// Connects to the remote signer and fetches its public key.
+    pub(crate) fn connect(
+        config: &RemoteSignerConfig,
+        root: &Path,
+    ) -> Result<RemoteSigner, SignerError> {
+        let socket_path = root.join(&config.socket_path);
+        let timeout = Duration::from(config.request_timeout);
+        let mut stream = connect(&socket_path, timeout)?;
+        let public_key = match exchange(&mut stream, &SignerRequest::PublicKey)? {
+            SignerResponse::PublicKey(public_key) => public_key,
+            SignerResponse::Refused(reason) => return Err(SignerError::Refused(reason)),
+            SignerResponse::Signature(_) => return Err(SignerError::UnexpectedResponse),
+        };
+        info!(socket = %socket_path.display(), %public_key, "connected to remote signer");
+        Ok(RemoteSigner {
+            socket_path,
+            timeout,
+            public_key,
+            connection: Mutex::new(Some(stream)),
+        })
+    }
+
+    /// Sends the request over the current connection, connecting first if necessary.
+    fn send(
+        &self,
+        connection: &mut Option<UnixStream>,
+        request: &SignerRequest,
+    ) -> Result<SignerResponse, SignerError> {
+        if connection.is_none() {
+            *connection = Some(connect(&self.socket_path, self.timeout)?);
+        }
+        let result = exchange(connection.as_mut().expect("should be connected"), request);
+        if result.is_err() {
+            // The stream may be out of sync with the signer now; don't reuse it.
+            *connection = None;
+        }
+        result
+    }
+}
+
+impl Debug for RemoteSigner {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("RemoteSigner")
+            .field("socket_path", &self.socket_path)
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    fn sign(&self, request: &SigningRequest) -> Result<Signature, SignerError> {
+        let request = SignerRequest::Sign(request.clone());
+        let mut connection = self
+            .connection
+            .lock()
+            .expect("remote signer connection lock poisoned");
+        let response = match self.send(&mut connection, &request) {
+            // A broken connection, e.g. due to a restart of the signer, is retried once.
+            Err(SignerError::Io(error)) => {
+                warn!(%error, "remote signer connection failed; reconnecting");
+                self.send(&mut connection, &request)?
+            }
+            result => result?,
+        };
+        match response {
+            SignerResponse::Signature(signature) => Ok(signature),
+            SignerResponse::Refused(reason) => Err(SignerError::Refused(reason)),
+            SignerResponse::PublicKey(_) => Err(SignerError::UnexpectedResponse),
+        }
+    }
+}
+
+/// Connects to the socket at the given path.
+fn connect(socket_path: &Path, timeout: Duration) -> Result<UnixStream, SignerError> {
+    let stream = UnixStream::connect(socket_path)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    Ok(stream)
+}
+
+/// Sends the request and waits for the response.
+fn exchange(
+    stream: &mut UnixStream,
+    request: &SignerRequest,
+) -> Result<SignerResponse, SignerError> {
+    write_message(stream, request)?;
+    read_message(stream)
+}
+
+/// Writes a length-prefixed message.
+pub(super) fn write_message<W: Write, T: Serialize>(
+    writer: &mut W,
+    message: &T,
+) -> Result<(), SignerError> {
+    let serialized = bincode::serialize(message)?;
+    let size = u32::try_from(serialized.len())
+        .ok()
+        .filter(|size| *size as usize <= MAX_MESSAGE_SIZE)
+        .ok_or(SignerError::MessageTooLarge(serialized.len()))?;
+    writer.write_all(&size.to_le_bytes())?;
+    writer.write_all(&serialized)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a length-prefixed message.
+pub(super) fn read_message<R: Read, T: DeserializeOwned>(reader: &mut R) -> Result<T, SignerError> {
+    let mut size_buf = [0u8; 4];
+    reader.read_exact(&mut size_buf)?;
+    let size = u32::from_le_bytes(size_buf) as usize;
+    if size > MAX_MESSAGE_SIZE {
+        return Err(SignerError::MessageTooLarge(size));
+    }
+    let mut buf = vec![0; size];
+    reader.read_exact(&mut buf)?;
+    Ok(bincode::deserialize(&buf)?)
+}
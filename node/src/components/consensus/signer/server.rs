@@ -0,0 +1,245 @@
+//! Server side of the remote signer.
+//!
+//! The remote signer holds the validator's secret key and serves signing requests from a node on a
+//! UNIX socket. Every request is checked against the double-signing protection log before it is
+//! signed.
+//!
+//! Only the user running the remote signer can connect to the socket: it is created with mode
+//! `0600`, and connections from processes of other users are closed right away.
+
+use std::{
+    fs, io,
+    os::unix::{
+        io::AsRawFd,
+        net::{UnixListener, UnixStream},
+    },
+    path::Path,
+    sync::Arc,
+    thread,
+};
+
+use tracing::{debug, info, warn};
+
+use casper_types::SecretKey;
+
+use super::{
+    remote::{self, SignerRequest, SignerResponse},
+    DoubleSignProtection, LocalSigner, ProtectedSigner, Signer, SignerError,
+};
+#[cfg(test)]
+use super::{RemoteSigner, RemoteSignerConfig};
+use crate::utils::{umask, LoadError, Loadable};
+
+/// The umask the socket is created with, making it readable and writable only by its owner.
+const SOCKET_UMASK: umask::Mode = 0o177;
+
+/// Runs the remote signer until the listener fails.
+///
+/// Loads the secret key from `secret_key_path`, then serves signing requests on a UNIX socket
+/// created at `socket_path`, recording all signed messages in the double-signing protection log at
+/// `protection_path`.
+pub(crate) fn run_remote_signer(
+    secret_key_path: &Path,
+    socket_path: &Path,
+    protection_path: &Path,
+) -> Result<(), SignerError> {
+    let secret_key = <Arc<SecretKey> as Loadable>::from_path(secret_key_path).map_err(|error| {
+        LoadError::Failed {
+            path: secret_key_path.to_owned(),
+            error,
+        }
+    })?;
+    let protection = DoubleSignProtection::open(protection_path)?;
+    let signer = ProtectedSigner::new(LocalSigner::new(secret_key), protection);
+
+    // A socket left behind by a previous run would make binding fail.
+    match fs::remove_file(socket_path) {
+        Ok(()) => debug!(socket = %socket_path.display(), "removed stale socket"),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => (),
+        Err(error) => return Err(error.into()),
+    }
+    let listener = bind(socket_path)?;
+    info!(
+        socket = %socket_path.display(),
+        public_key = %signer.public_key(),
+        "remote signer listening"
+    );
+    serve(listener, signer)
+}
+
+/// Runs a remote signer holding `secret_key` on a background thread, with its socket and
+/// double-signing protection log in `dir`, and returns a signer connected to it.
+#[cfg(test)]
+pub(crate) fn start_test_remote_signer(secret_key: Arc<SecretKey>, dir: &Path) -> RemoteSigner {
+    let protection = DoubleSignProtection::open(&dir.join("protection.log")).unwrap();
+    let signer = ProtectedSigner::new(LocalSigner::new(secret_key), protection);
+    let listener = bind(&dir.join("signer.sock")).unwrap();
+    let _ = thread::spawn(move || serve(listener, signer));
+    let config = RemoteSignerConfig {
+        socket_path: "signer.sock".into(),
+        request_timeout: "5sec".parse().unwrap(),
+    };
+    RemoteSigner::connect(&config, dir).unwrap()
+}
+
+/// Creates the socket at the given path, accessible only by the current user.
+fn bind(socket_path: &Path) -> io::Result<UnixListener> {
+    // This sets the umask for the entire process, but the remote signer doesn't create any other
+    // files concurrently.
+    let _umask_guard = umask::temp_umask(SOCKET_UMASK);
+    UnixListener::bind(socket_path)
+}
+
+/// Serves every connection to the listener on a separate thread.
+///
+/// Connections from processes running as a different user are closed.
+fn serve(listener: UnixListener, signer: ProtectedSigner<LocalSigner>) -> Result<(), SignerError> {
+    let signer = Arc::new(signer);
+    // SAFETY: `geteuid` has no preconditions and always succeeds.
+    let our_uid = unsafe { libc::geteuid() };
+    loop {
+        let (stream, _) = listener.accept()?;
+        match peer_uid(&stream) {
+            Ok(uid) if uid == our_uid => (),
+            Ok(uid) => {
+                warn!(
+                    uid,
+                    "refused remote signer client running as a different user"
+                );
+                continue;
+            }
+            Err(error) => {
+                warn!(%error, "refused remote signer client whose user could not be determined");
+                continue;
+            }
+        }
+        let signer = Arc::clone(&signer);
+        let _ = thread::Builder::new()
+            .name("remote-signer-connection".to_string())
+            .spawn(move || {
+                info!("remote signer client connected");
+                match handle_connection(stream, &*signer) {
+                    Ok(()) => info!("remote signer client disconnected"),
+                    Err(error) => warn!(%error, "remote signer connection failed"),
+                }
+            })?;
+    }
+}
+
+/// Returns the effective user ID of the process on the other end of the stream.
+#[cfg(target_os = "linux")]
+fn peer_uid(stream: &UnixStream) -> io::Result<libc::uid_t> {
+    let mut credentials = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut length = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    // SAFETY: `credentials` and `length` are valid for writes, and `length` is the size of
+    // `credentials`.
+    let outcome = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut credentials as *mut libc::ucred as *mut libc::c_void,
+            &mut length,
+        )
+    };
+    if outcome != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(credentials.uid)
+}
+
+/// Returns the effective user ID of the process on the other end of the stream.
+#[cfg(not(target_os = "linux"))]
+fn peer_uid(stream: &UnixStream) -> io::Result<libc::uid_t> {
+    let (mut uid, mut gid) = (0, 0);
+    // SAFETY: `uid` and `gid` are valid for writes.
+    let outcome = unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) };
+    if outcome != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(uid)
+}
+
+/// Handles the requests of a single client until it disconnects.
+fn handle_connection(
+    mut stream: UnixStream,
+    signer: &ProtectedSigner<LocalSigner>,
+) -> Result<(), SignerError> {
+    loop {
+        let request = match remote::read_message(&mut stream) {
+            Ok(request) => request,
+            Err(SignerError::Io(error)) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                return Ok(())
+            }
+            Err(error) => return Err(error),
+        };
+        let response = match request {
+            SignerRequest::PublicKey => SignerResponse::PublicKey(signer.public_key().clone()),
+            SignerRequest::Sign(signing_request) => match signer.sign(&signing_request) {
+                Ok(signature) => {
+                    debug!(request = %signing_request, "signed");
+                    SignerResponse::Signature(signature)
+                }
+                Err(error) => SignerResponse::Refused(error.to_string()),
+            },
+        };
+        remote::write_message(&mut stream, &response)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, os::unix::fs::PermissionsExt, sync::Arc};
+
+    use casper_hashing::Digest;
+    use casper_types::{crypto, EraId, PublicKey, SecretKey};
+
+    use super::start_test_remote_signer;
+    use crate::components::consensus::{
+        protocols::zug,
+        signer::{Signer, SignerError, SigningRequest},
+        utils::ValidatorIndex,
+        ClContext,
+    };
+
+    #[test]
+    fn should_sign_remotely_and_refuse_equivocations() {
+        let mut rng = crate::new_rng();
+        let dir = tempfile::tempdir().unwrap();
+        let secret_key = Arc::new(SecretKey::random(&mut rng));
+        let public_key = PublicKey::from(&*secret_key);
+        let remote_signer = start_test_remote_signer(secret_key, dir.path());
+        assert_eq!(*remote_signer.public_key(), public_key);
+        let socket_metadata = fs::metadata(dir.path().join("signer.sock")).unwrap();
+        assert_eq!(socket_metadata.permissions().mode() & 0o777, 0o600);
+
+        let echo = |proposal_hash| {
+            let instance_id = Digest::hash([1]);
+            let input = zug::echo_signing_input::<ClContext>(
+                0,
+                &instance_id,
+                proposal_hash,
+                ValidatorIndex(0),
+            );
+            let request = SigningRequest::Consensus {
+                era_id: EraId::new(1),
+                context: input.context,
+                serialized: input.serialized,
+            };
+            (input.hash, request)
+        };
+        // The signer computes the hash itself, and signs it.
+        let (hash, request) = echo(Digest::hash([0]));
+        let signature = remote_signer.sign(&request).unwrap();
+        crypto::verify(hash.value(), &signature, &public_key).unwrap();
+        let (_, conflicting_request) = echo(Digest::hash([1]));
+        assert!(matches!(
+            remote_signer.sign(&conflicting_request),
+            Err(SignerError::Refused(_))
+        ));
+    }
+}
@@ -0,0 +1,1044 @@
+//! Discrete event simulation of the consensus protocols, driven by scenario files.
+//!
+//! Every validator of a scenario runs the actual Highway or Zug implementation, connected to the
+//! others by a simulated network with random message latencies. Time is simulated, too, so that
+//! many eras can be simulated in a few seconds. This allows evaluating the effect of chainspec
+//! changes like `minimum_block_time`, `maximum_round_length` or `finality_threshold_fraction` on
+//! finality latency, throughput and rewards, with or without crashing and equivocating validators.
+
+mod report;
+mod scenario;
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use rand::{seq::IteratorRandom, Rng, SeedableRng};
+use thiserror::Error;
+use tracing::{debug, info};
+
+use casper_hashing::Digest;
+use casper_types::{EraId, PublicKey, SecretKey, TimeDiff, Timestamp, U512};
+
+use super::{
+    cl_context::{ClContext, Keypair},
+    consensus_protocol::{
+        BlockContext, ConsensusProtocol, FinalizedBlock, ProposedBlock, ProtocolOutcome,
+        ProtocolOutcomes,
+    },
+    des::{Message, Queue, QueueEntry, ValidatorId},
+    era_supervisor::{PAST_EVIDENCE_ERAS, PAST_OPEN_ERAS},
+    signer::{LocalSigner, Signer, SigningRequest},
+    ActionId, Config, HighwayProtocol, SerializedMessage, TimerId, Zug,
+};
+use crate::{
+    tls::{KeyFingerprint, Sha512},
+    types::{
+        chainspec::{self, ConsensusProtocolName},
+        BlockPayload, Chainspec, NodeId,
+    },
+    NodeRng,
+};
+use report::Recorder;
+pub(crate) use report::SimulationReport;
+use scenario::{Fault, Scenario};
+
+/// The first simulated era.
+const FIRST_ERA_ID: EraId = EraId::new(0);
+
+/// Error running a consensus simulation.
+#[derive(Debug, Error)]
+pub(crate) enum SimulationError {
+    /// Error reading the scenario file.
+    #[error("could not read scenario file {}: {error}", path.display())]
+    ReadScenario {
+        /// The path of the scenario file.
+        path: PathBuf,
+        /// The underlying error.
+        error: io::Error,
+    },
+
+    /// Error parsing the scenario file.
+    #[error("could not parse scenario file: {0}")]
+    ParseScenario(#[from] toml::de::Error),
+
+    /// Error loading the chainspec.
+    #[error("error loading chainspec: {0}")]
+    LoadChainspec(chainspec::Error),
+
+    /// The scenario is inconsistent.
+    #[error("invalid scenario: {0}")]
+    InvalidScenario(String),
+
+    /// Error creating the directory for the simulated nodes' unit hash files and WALs.
+    #[error("could not create temporary directory: {0}")]
+    TempDir(io::Error),
+}
+
+/// Runs the simulation defined in the scenario file.
+pub(crate) fn simulate_consensus(
+    scenario_path: &Path,
+) -> Result<SimulationReport, SimulationError> {
+    let (scenario, chainspecs) = Scenario::load(scenario_path)?;
+    let unit_files_dir = tempfile::tempdir().map_err(SimulationError::TempDir)?;
+    let consensus_protocols: Vec<_> = chainspecs
+        .values()
+        .map(|chainspec| chainspec.core_config.consensus_protocol)
+        .collect();
+    info!(
+        ?consensus_protocols,
+        validators = scenario.validators.len(),
+        eras = scenario.eras,
+        "starting consensus simulation"
+    );
+    Ok(Simulation::new(scenario, chainspecs, unit_files_dir.path()).run())
+}
+
+/// An event delivered to a simulated node.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Event {
+    /// The node starts, or restarts after a crash.
+    Start,
+    /// The node crashes.
+    Crash,
+    /// A protocol message from another node.
+    Message {
+        era_id: EraId,
+        payload: SerializedMessage,
+    },
+    /// A request from another node, to be answered with a protocol message.
+    Request {
+        era_id: EraId,
+        payload: SerializedMessage,
+    },
+    /// A timer scheduled by the protocol instance in the node's run with the given number.
+    Timer {
+        run: u64,
+        era_id: EraId,
+        timestamp: Timestamp,
+        timer_id: TimerId,
+    },
+    /// An action queued by the protocol instance in the node's run with the given number.
+    Action {
+        run: u64,
+        era_id: EraId,
+        action_id: ActionId,
+    },
+    /// Stop voting in the era, unless there is new evidence: Then check again after `delay`.
+    DeactivateEra {
+        run: u64,
+        era_id: EraId,
+        faulty_num: usize,
+        delay: TimeDiff,
+    },
+}
+
+/// The parameters of an era's protocol instances, the same on all nodes.
+struct EraSetup {
+    instance_id: Digest,
+    seed: u64,
+    start_time: Timestamp,
+    faulty: HashSet<PublicKey>,
+    inactive: HashSet<PublicKey>,
+}
+
+/// An era's protocol instance on a simulated node.
+struct SimEra {
+    consensus: Box<dyn ConsensusProtocol<ClContext>>,
+    /// The validators accused in the era's finalized blocks, or seen equivocating by them.
+    accusations: BTreeSet<PublicKey>,
+}
+
+/// A simulated node, running the consensus protocol for one of the scenario's validators.
+struct SimNode {
+    /// The index of the validator in the scenario.
+    validator: usize,
+    /// The node's ID in the simulated network.
+    node_id: NodeId,
+    /// Whether this is a second node using the validator's key, i.e. an equivocator.
+    doppelganger: bool,
+    /// Whether the node is up.
+    running: bool,
+    /// Whether a `Start` event for this node is pending.
+    start_pending: bool,
+    /// The number of times the node was started. Timers from earlier runs are ignored.
+    run: u64,
+    /// The time the node was last started.
+    start_time: Timestamp,
+    /// The open eras' protocol instances.
+    eras: BTreeMap<EraId, SimEra>,
+    /// Messages and requests for eras the node hasn't created yet, with their senders.
+    future_events: BTreeMap<EraId, Vec<(ValidatorId, Event)>>,
+    /// Whether the node has finalized the switch block of the last simulated era.
+    finished: bool,
+}
+
+impl SimNode {
+    fn new(validator: usize, node_id: NodeId, doppelganger: bool) -> Self {
+        SimNode {
+            validator,
+            node_id,
+            doppelganger,
+            running: false,
+            start_pending: false,
+            run: 0,
+            start_time: Timestamp::zero(),
+            eras: BTreeMap::new(),
+            future_events: BTreeMap::new(),
+            finished: false,
+        }
+    }
+
+    /// Buffers a message or request for an era the node hasn't created yet. Messages for older
+    /// eras are dropped.
+    fn buffer(&mut self, era_id: EraId, sender: ValidatorId, event: Event) {
+        if self
+            .eras
+            .keys()
+            .next_back()
+            .map_or(false, |current_era_id| era_id > *current_era_id)
+        {
+            self.future_events
+                .entry(era_id)
+                .or_default()
+                .push((sender, event));
+        }
+    }
+}
+
+/// The state of a running simulation.
+struct Simulation {
+    scenario: Scenario,
+    /// The chainspecs, by the first era they apply to.
+    chainspecs: BTreeMap<EraId, Chainspec>,
+    config: Config,
+    /// The directory containing the nodes' unit hash files and WALs.
+    unit_files_dir: PathBuf,
+    /// The validators' secret keys, in the order of the scenario.
+    secret_keys: Vec<Arc<SecretKey>>,
+    /// The validators' public keys, in the order of the scenario.
+    public_keys: Vec<PublicKey>,
+    /// The validators' weights.
+    validator_weights: BTreeMap<PublicKey, U512>,
+    /// The simulated nodes, indexed by their `ValidatorId`: first one per validator, then the
+    /// doppelgangers.
+    nodes: Vec<SimNode>,
+    /// The parameters of every era that any node has reached.
+    era_setups: BTreeMap<EraId, EraSetup>,
+    /// The events scheduled for delivery.
+    queue: Queue<Event>,
+    /// The current simulated time.
+    now: Timestamp,
+    rng: NodeRng,
+    recorder: Recorder,
+}
+
+impl Simulation {
+    fn new(
+        scenario: Scenario,
+        chainspecs: BTreeMap<EraId, Chainspec>,
+        unit_files_dir: &Path,
+    ) -> Self {
+        let mut rng = NodeRng::seed_from_u64(scenario.seed);
+        let secret_keys: Vec<Arc<SecretKey>> = scenario
+            .validators
+            .iter()
+            .map(|_| {
+                let secret_key = SecretKey::ed25519_from_bytes(rng.gen::<[u8; 32]>())
+                    .expect("32 bytes should be a valid ed25519 secret key");
+                Arc::new(secret_key)
+            })
+            .collect();
+        let public_keys: Vec<PublicKey> = secret_keys
+            .iter()
+            .map(|secret_key| PublicKey::from(&**secret_key))
+            .collect();
+        let validator_weights = public_keys
+            .iter()
+            .zip(&scenario.validators)
+            .map(|(public_key, validator)| (public_key.clone(), U512::from(validator.weight)))
+            .collect();
+
+        let doppelgangers = scenario.faults.iter().filter_map(|fault| match fault {
+            Fault::Equivocate { validator, .. } => Some((*validator, true)),
+            Fault::Crash { .. } => None,
+        });
+        let nodes = (0..scenario.validators.len())
+            .map(|validator| (validator, false))
+            .chain(doppelgangers)
+            .map(|(validator, doppelganger)| {
+                let node_id =
+                    NodeId::from(KeyFingerprint::from(Sha512::new(rng.gen::<[u8; 32]>())));
+                SimNode::new(validator, node_id, doppelganger)
+            })
+            .collect();
+
+        let start_time = Timestamp::zero();
+        let consensus_protocols = chainspecs
+            .iter()
+            .map(|(era_id, chainspec)| (*era_id, chainspec.core_config.consensus_protocol))
+            .collect();
+        let mut simulation = Simulation {
+            scenario,
+            chainspecs,
+            config: Config::default(),
+            unit_files_dir: unit_files_dir.to_path_buf(),
+            secret_keys,
+            public_keys,
+            validator_weights,
+            nodes,
+            era_setups: BTreeMap::new(),
+            queue: Queue::default(),
+            now: start_time,
+            rng,
+            recorder: Recorder::new(start_time, consensus_protocols),
+        };
+        simulation.add_era_setup(FIRST_ERA_ID, start_time, HashSet::new(), HashSet::new());
+
+        // All validators start at the beginning, the doppelgangers and restarts are scheduled as
+        // defined in the scenario.
+        for idx in 0..simulation.scenario.validators.len() {
+            simulation.schedule_start(idx, start_time);
+        }
+        let mut next_doppelganger = simulation.scenario.validators.len();
+        for fault in simulation.scenario.faults.clone() {
+            match fault {
+                Fault::Crash {
+                    validator,
+                    at,
+                    restart,
+                } => {
+                    simulation.schedule(validator, start_time.saturating_add(at), Event::Crash);
+                    if let Some(restart) = restart {
+                        simulation.schedule_start(validator, start_time.saturating_add(restart));
+                    }
+                }
+                Fault::Equivocate { at, .. } => {
+                    simulation.schedule_start(next_doppelganger, start_time.saturating_add(at));
+                    next_doppelganger = next_doppelganger.saturating_add(1);
+                }
+            }
+        }
+        simulation
+    }
+
+    /// Runs the simulation until all nodes have finalized the last era's switch block, or the
+    /// maximum duration is reached.
+    fn run(mut self) -> SimulationReport {
+        let end_time = self.now.saturating_add(self.scenario.max_duration);
+        while let Some(delivery_time) = self.queue.peek().map(|entry| entry.delivery_time) {
+            if delivery_time > end_time || self.is_finished() {
+                break;
+            }
+            if let Some(entry) = self.queue.pop() {
+                self.now = delivery_time;
+                self.handle_entry(entry);
+            }
+        }
+        let validators: Vec<(PublicKey, u64)> = self
+            .public_keys
+            .iter()
+            .cloned()
+            .zip(
+                self.scenario
+                    .validators
+                    .iter()
+                    .map(|validator| validator.weight),
+            )
+            .collect();
+        self.recorder
+            .into_report(self.scenario.eras, &validators, self.now)
+    }
+
+    /// Returns whether every node that is up or will restart has finalized the last era.
+    fn is_finished(&self) -> bool {
+        self.nodes
+            .iter()
+            .filter(|node| !node.doppelganger)
+            .all(|node| node.finished || (!node.running && !node.start_pending))
+    }
+
+    /// Handles an event delivered to a node.
+    fn handle_entry(&mut self, entry: QueueEntry<Event>) {
+        let QueueEntry {
+            delivery_time: now,
+            recipient,
+            message: Message { sender, payload },
+        } = entry;
+        let idx = recipient.0 as usize;
+        let sender_idx = sender.0 as usize;
+        match payload {
+            Event::Start => self.start_node(idx, now),
+            Event::Crash => {
+                info!(node = idx, %now, "node crashed");
+                let node = &mut self.nodes[idx];
+                node.running = false;
+                node.eras.clear();
+                node.future_events.clear();
+            }
+            // Everything else is lost while the node is down.
+            _ if !self.nodes[idx].running => {}
+            Event::Message { era_id, payload } => {
+                let sender_id = self.nodes[sender_idx].node_id;
+                let node = &mut self.nodes[idx];
+                let outcomes = match node.eras.get_mut(&era_id) {
+                    Some(era) => {
+                        era.consensus
+                            .handle_message(&mut self.rng, sender_id, payload, now)
+                    }
+                    None => {
+                        node.buffer(era_id, sender, Event::Message { era_id, payload });
+                        return;
+                    }
+                };
+                self.handle_outcomes(idx, era_id, outcomes, now);
+            }
+            Event::Request { era_id, payload } => {
+                let sender_id = self.nodes[sender_idx].node_id;
+                let node = &mut self.nodes[idx];
+                let (outcomes, response) = match node.eras.get_mut(&era_id) {
+                    Some(era) => {
+                        era.consensus
+                            .handle_request_message(&mut self.rng, sender_id, payload, now)
+                    }
+                    None => {
+                        node.buffer(era_id, sender, Event::Request { era_id, payload });
+                        return;
+                    }
+                };
+                if let Some(payload) = response {
+                    self.send(idx, sender_idx, Event::Message { era_id, payload }, now);
+                }
+                self.handle_outcomes(idx, era_id, outcomes, now);
+            }
+            Event::Timer {
+                run,
+                era_id,
+                timestamp,
+                timer_id,
+            } => {
+                let node = &mut self.nodes[idx];
+                let outcomes = match node.eras.get_mut(&era_id) {
+                    Some(era) if run == node.run => {
+                        era.consensus
+                            .handle_timer(timestamp, now, timer_id, &mut self.rng)
+                    }
+                    _ => return,
+                };
+                self.handle_outcomes(idx, era_id, outcomes, now);
+            }
+            Event::Action {
+                run,
+                era_id,
+                action_id,
+            } => {
+                let node = &mut self.nodes[idx];
+                let outcomes = match node.eras.get_mut(&era_id) {
+                    Some(era) if run == node.run => era.consensus.handle_action(action_id, now),
+                    _ => return,
+                };
+                self.handle_outcomes(idx, era_id, outcomes, now);
+            }
+            Event::DeactivateEra {
+                run,
+                era_id,
+                faulty_num,
+                delay,
+            } => {
+                let node = &mut self.nodes[idx];
+                let era = match node.eras.get_mut(&era_id) {
+                    Some(era) if run == node.run => era,
+                    _ => return,
+                };
+                let new_faulty_num = era.consensus.validators_with_evidence().len();
+                if new_faulty_num == faulty_num {
+                    debug!(node = idx, era = era_id.value(), "stop voting in era");
+                    era.consensus.deactivate_validator();
+                } else {
+                    let event = Event::DeactivateEra {
+                        run,
+                        era_id,
+                        faulty_num: new_faulty_num,
+                        delay,
+                    };
+                    self.schedule(idx, now.saturating_add(delay), event);
+                }
+            }
+        }
+    }
+
+    /// Starts the node in the most recent era any node has reached.
+    fn start_node(&mut self, idx: usize, now: Timestamp) {
+        let node = &mut self.nodes[idx];
+        node.running = true;
+        node.start_pending = false;
+        node.run = node.run.saturating_add(1);
+        node.start_time = now;
+        if let Some(era_id) = self.era_setups.keys().next_back().copied() {
+            info!(node = idx, era = era_id.value(), %now, "node started");
+            self.create_era(idx, era_id, now);
+        }
+    }
+
+    /// Creates the era's protocol instance on the node and activates it.
+    fn create_era(&mut self, idx: usize, era_id: EraId, now: Timestamp) {
+        let setup = match self.era_setups.get(&era_id) {
+            Some(setup) => setup,
+            None => return,
+        };
+        let node = &self.nodes[idx];
+        if node.eras.contains_key(&era_id) {
+            return;
+        }
+        debug!(node = idx, era = era_id.value(), %now, "creating era");
+        // Like a node restarted for an upgrade, the first era after it doesn't build on the
+        // previous era's instance, which may even belong to a different protocol.
+        let is_activation_era = era_id.value() > 0 && self.chainspecs.contains_key(&era_id);
+        let prev_era = era_id
+            .checked_sub(1)
+            .filter(|_| !is_activation_era)
+            .and_then(|prev_era_id| node.eras.get(&prev_era_id));
+        let unit_file = self
+            .unit_files_dir
+            .join(format!("unit_{}_{}.dat", era_id.value(), idx));
+        let chainspec = self.chainspec(era_id);
+        let (mut consensus, mut outcomes): (
+            Box<dyn ConsensusProtocol<ClContext>>,
+            ProtocolOutcomes<ClContext>,
+        ) = match chainspec.core_config.consensus_protocol {
+            ConsensusProtocolName::Highway => HighwayProtocol::new_boxed(
+                setup.instance_id,
+                self.validator_weights.clone(),
+                &setup.faulty,
+                &setup.inactive,
+                chainspec,
+                &self.config,
+                prev_era.map(|era| &*era.consensus),
+                setup.start_time,
+                setup.seed,
+                now,
+            ),
+            ConsensusProtocolName::Zug => Zug::new_boxed(
+                setup.instance_id,
+                self.validator_weights.clone(),
+                &setup.faulty,
+                &setup.inactive,
+                chainspec,
+                &self.config,
+                prev_era.map(|era| &*era.consensus),
+                setup.start_time,
+                setup.seed,
+                now,
+                unit_file.clone(),
+            ),
+        };
+
+        // Mark validators as faulty for which we have evidence in the previous era.
+        let validators_with_evidence: Vec<PublicKey> = prev_era
+            .into_iter()
+            .flat_map(|era| era.consensus.validators_with_evidence())
+            .cloned()
+            .collect();
+        for public_key in &validators_with_evidence {
+            consensus.mark_faulty(public_key);
+        }
+
+        outcomes.extend(consensus.handle_is_current(now));
+        let validator = node.validator;
+        let signer = LocalSigner::new(Arc::clone(&self.secret_keys[validator]));
+        outcomes.extend(consensus.activate_validator(
+            self.public_keys[validator].clone(),
+            Keypair::new(Arc::new(signer), era_id),
+            now,
+            Some(unit_file),
+        ));
+
+        let node = &mut self.nodes[idx];
+        let _ = node.eras.insert(
+            era_id,
+            SimEra {
+                consensus,
+                accusations: BTreeSet::new(),
+            },
+        );
+        // Like the era supervisor, only keep the eras needed to validate evidence.
+        let earliest_open_era = era_id.saturating_sub(PAST_OPEN_ERAS);
+        let earliest_active_era = era_id.saturating_sub(PAST_EVIDENCE_ERAS);
+        node.eras.retain(|era_id, era| {
+            if *era_id < earliest_active_era {
+                era.consensus.set_evidence_only();
+            }
+            *era_id >= earliest_open_era
+        });
+        // Deliver the messages the node received before it reached this era.
+        let buffered = node.future_events.remove(&era_id).unwrap_or_default();
+        for (sender, event) in buffered {
+            let entry = QueueEntry::new(now, ValidatorId(idx as u64), Message::new(sender, event));
+            self.queue.push(entry);
+        }
+        self.handle_outcomes(idx, era_id, outcomes, now);
+    }
+
+    /// Handles the outcomes of the node's protocol instance in the given era.
+    fn handle_outcomes(
+        &mut self,
+        idx: usize,
+        era_id: EraId,
+        outcomes: ProtocolOutcomes<ClContext>,
+        now: Timestamp,
+    ) {
+        for outcome in outcomes {
+            self.handle_outcome(idx, era_id, outcome, now);
+        }
+    }
+
+    fn handle_outcome(
+        &mut self,
+        idx: usize,
+        era_id: EraId,
+        outcome: ProtocolOutcome<ClContext>,
+        now: Timestamp,
+    ) {
+        match outcome {
+            ProtocolOutcome::CreatedGossipMessage(payload) => {
+                for to in 0..self.nodes.len() {
+                    if to != idx {
+                        let event = Event::Message {
+                            era_id,
+                            payload: payload.clone(),
+                        };
+                        self.send(idx, to, event, now);
+                    }
+                }
+            }
+            ProtocolOutcome::CreatedTargetedMessage(payload, node_id) => {
+                if let Some(to) = self.node_index(&node_id) {
+                    self.send(idx, to, Event::Message { era_id, payload }, now);
+                }
+            }
+            ProtocolOutcome::CreatedMessageToRandomPeer(payload) => {
+                if let Some(to) = self.random_peer(idx) {
+                    self.send(idx, to, Event::Message { era_id, payload }, now);
+                }
+            }
+            ProtocolOutcome::CreatedRequestToRandomPeer(payload) => {
+                if let Some(to) = self.random_peer(idx) {
+                    self.send(idx, to, Event::Request { era_id, payload }, now);
+                }
+            }
+            ProtocolOutcome::ScheduleTimer(timestamp, timer_id) => {
+                let event = Event::Timer {
+                    run: self.nodes[idx].run,
+                    era_id,
+                    timestamp,
+                    timer_id,
+                };
+                self.schedule(idx, timestamp.max(now), event);
+            }
+            ProtocolOutcome::QueueAction(action_id) => {
+                let event = Event::Action {
+                    run: self.nodes[idx].run,
+                    era_id,
+                    action_id,
+                };
+                self.schedule(idx, now, event);
+            }
+            ProtocolOutcome::CreateNewBlock(block_context) => {
+                self.propose(idx, era_id, block_context, now)
+            }
+            ProtocolOutcome::FinalizedBlock(finalized_block) => {
+                self.handle_finalized_block(idx, era_id, finalized_block, now)
+            }
+            ProtocolOutcome::ValidateConsensusValue { proposed_block, .. } => {
+                // The simulated blocks contain no deploys, so they are always valid.
+                let outcomes = match self.nodes[idx].eras.get_mut(&era_id) {
+                    Some(era) => era.consensus.resolve_validity(proposed_block, true, now),
+                    None => return,
+                };
+                self.handle_outcomes(idx, era_id, outcomes, now);
+            }
+            ProtocolOutcome::CreateSignature(input) => {
+                let secret_key = &self.secret_keys[self.nodes[idx].validator];
+                let signer = LocalSigner::new(Arc::clone(secret_key));
+                let hash = input.hash;
+                let request = SigningRequest::Consensus {
+                    era_id,
+                    context: input.context,
+                    serialized: input.serialized,
+                };
+                let signature = signer.sign(&request).ok();
+                let outcomes = match self.nodes[idx].eras.get_mut(&era_id) {
+                    Some(era) => era.consensus.handle_signature(hash, signature, now),
+                    None => return,
+                };
+                self.handle_outcomes(idx, era_id, outcomes, now);
+            }
+            ProtocolOutcome::NewEvidence(public_key) => {
+                debug!(node = idx, era = era_id.value(), %public_key, "validator equivocated");
+                self.recorder.evidence(&public_key, now);
+                for (_, era) in self.nodes[idx].eras.range_mut(era_id.successor()..) {
+                    era.consensus.mark_faulty(&public_key);
+                }
+            }
+            ProtocolOutcome::SendEvidence(node_id, public_key) => {
+                let earliest_era = era_id.saturating_sub(PAST_EVIDENCE_ERAS);
+                let past_eras: Vec<EraId> = self.nodes[idx]
+                    .eras
+                    .range(earliest_era..era_id)
+                    .map(|(era_id, _)| *era_id)
+                    .collect();
+                for past_era_id in past_eras {
+                    let outcomes = self.nodes[idx].eras[&past_era_id]
+                        .consensus
+                        .send_evidence(node_id, &public_key);
+                    self.handle_outcomes(idx, past_era_id, outcomes, now);
+                }
+            }
+            ProtocolOutcome::DoppelgangerDetected => self.recorder.doppelganger_detected(),
+            ProtocolOutcome::FttExceeded => self.recorder.ftt_exceeded(),
+            ProtocolOutcome::WeAreFaulty
+            | ProtocolOutcome::Disconnect(_)
+            | ProtocolOutcome::HandledProposedBlock(_) => {}
+        }
+    }
+
+    /// Proposes a block without deploys, accusing the validators we have evidence against.
+    fn propose(
+        &mut self,
+        idx: usize,
+        era_id: EraId,
+        block_context: BlockContext<ClContext>,
+        now: Timestamp,
+    ) {
+        let node = &mut self.nodes[idx];
+        let faulty = self.era_setups.get(&era_id).map(|setup| &setup.faulty);
+        let accusations: BTreeSet<PublicKey> = node
+            .eras
+            .range(era_id.saturating_sub(PAST_EVIDENCE_ERAS)..=era_id)
+            .flat_map(|(_, era)| era.consensus.validators_with_evidence())
+            .filter(|public_key| faulty.map_or(true, |faulty| !faulty.contains(*public_key)))
+            .cloned()
+            .collect();
+        let random_bit = self.rng.gen();
+        let block_payload = BlockPayload::new(
+            vec![],
+            vec![],
+            accusations.into_iter().collect(),
+            random_bit,
+        );
+        let proposed_block = ProposedBlock::new(Arc::new(block_payload), block_context);
+        let outcomes = match node.eras.get_mut(&era_id) {
+            Some(era) => era.consensus.propose(proposed_block, now),
+            None => return,
+        };
+        self.handle_outcomes(idx, era_id, outcomes, now);
+    }
+
+    /// Records a block finalized by the node. If it is a switch block, schedules deactivating the
+    /// era and starts the next one.
+    fn handle_finalized_block(
+        &mut self,
+        idx: usize,
+        era_id: EraId,
+        finalized_block: FinalizedBlock<ClContext>,
+        now: Timestamp,
+    ) {
+        let node = &mut self.nodes[idx];
+        let era = match node.eras.get_mut(&era_id) {
+            Some(era) => era,
+            None => return,
+        };
+        era.accusations
+            .extend(finalized_block.equivocators.iter().cloned());
+        era.accusations
+            .extend(finalized_block.value.accusations().iter().cloned());
+        let faulty_num = era.consensus.validators_with_evidence().len();
+        let accusations = era.accusations.clone();
+        let run = node.run;
+        // Blocks a node catches up on after it started don't count towards the finality latency,
+        // and neither do a doppelganger's.
+        let count_latency = !node.doppelganger && finalized_block.timestamp >= node.start_time;
+        self.recorder
+            .finalized(era_id, &finalized_block, now, count_latency);
+
+        let terminal_block_data = match finalized_block.terminal_block_data {
+            Some(terminal_block_data) => terminal_block_data,
+            None => return,
+        };
+        // Keep voting for as long as it took to finalize the switch block, so that the other nodes
+        // can finalize it, too.
+        let delay = now.saturating_diff(finalized_block.timestamp);
+        let event = Event::DeactivateEra {
+            run,
+            era_id,
+            faulty_num,
+            delay,
+        };
+        self.schedule(idx, now.saturating_add(delay), event);
+
+        let next_era_id = era_id.successor();
+        if next_era_id.value() >= self.scenario.eras {
+            self.nodes[idx].finished = true;
+            return;
+        }
+        if !self.era_setups.contains_key(&next_era_id) {
+            info!(
+                era = next_era_id.value(),
+                start_time = %finalized_block.timestamp,
+                "era started"
+            );
+            let inactive = terminal_block_data
+                .inactive_validators
+                .into_iter()
+                .collect();
+            self.add_era_setup(
+                next_era_id,
+                finalized_block.timestamp,
+                accusations.into_iter().collect(),
+                inactive,
+            );
+        }
+        self.create_era(idx, next_era_id, now);
+    }
+
+    /// Adds the parameters of a new era.
+    fn add_era_setup(
+        &mut self,
+        era_id: EraId,
+        start_time: Timestamp,
+        faulty: HashSet<PublicKey>,
+        inactive: HashSet<PublicKey>,
+    ) {
+        let setup = EraSetup {
+            instance_id: Digest::hash_pair(self.chainspec(era_id).hash(), era_id.to_le_bytes()),
+            seed: self.rng.gen(),
+            start_time,
+            faulty,
+            inactive,
+        };
+        let _ = self.era_setups.insert(era_id, setup);
+    }
+
+    /// Returns the chainspec that applies to the given era, taking upgrades into account.
+    fn chainspec(&self, era_id: EraId) -> &Chainspec {
+        self.chainspecs
+            .range(..=era_id)
+            .next_back()
+            .map(|(_, chainspec)| chainspec)
+            .expect("there should be a chainspec for the first era")
+    }
+
+    /// Returns the index of the node with the given ID.
+    fn node_index(&self, node_id: &NodeId) -> Option<usize> {
+        self.nodes.iter().position(|node| node.node_id == *node_id)
+    }
+
+    /// Returns the index of a random node other than the given one.
+    fn random_peer(&mut self, idx: usize) -> Option<usize> {
+        (0..self.nodes.len())
+            .filter(|to| *to != idx)
+            .choose(&mut self.rng)
+    }
+
+    /// Sends an event from one node to another, with a random latency.
+    fn send(&mut self, from: usize, to: usize, event: Event, now: Timestamp) {
+        let network = &self.scenario.network;
+        let latency = TimeDiff::from_millis(
+            self.rng
+                .gen_range(network.min_latency.millis()..=network.max_latency.millis()),
+        );
+        let validators = &self.scenario.validators;
+        let delivery_time = now
+            .saturating_add(latency)
+            .saturating_add(validators[self.nodes[from].validator].latency)
+            .saturating_add(validators[self.nodes[to].validator].latency);
+        self.recorder.message_sent();
+        let message = Message::new(ValidatorId(from as u64), event);
+        self.queue.push(QueueEntry::new(
+            delivery_time,
+            ValidatorId(to as u64),
+            message,
+        ));
+    }
+
+    /// Schedules an event for the node at the given time.
+    fn schedule(&mut self, idx: usize, time: Timestamp, event: Event) {
+        let id = ValidatorId(idx as u64);
+        self.queue
+            .push(QueueEntry::new(time, id, Message::new(id, event)));
+    }
+
+    /// Schedules starting the node at the given time.
+    fn schedule_start(&mut self, idx: usize, time: Timestamp) {
+        self.nodes[idx].start_pending = true;
+        self.schedule(idx, time, Event::Start);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// Simulates `eras` eras with four validators, using the production chainspec with shorter
+    /// eras and the given upgrades and faults, and returns the report as JSON.
+    fn simulate(
+        consensus_protocol: &str,
+        eras: u64,
+        upgrades: &[(u64, &str)],
+        faults: &str,
+    ) -> serde_json::Value {
+        let dir = tempfile::tempdir().expect("should create temporary directory");
+        let path = dir.path().join("scenario.toml");
+        let validators = "[[validators]]\nweight = 100\n".repeat(4);
+        let upgrades: String = upgrades
+            .iter()
+            .map(|(activation_era, consensus_protocol)| {
+                format!(
+                    "[[upgrades]]\nactivation_era = {}\nconsensus_protocol = \"{}\"\n",
+                    activation_era, consensus_protocol
+                )
+            })
+            .collect();
+        let scenario = format!(
+            r#"
+            eras = {}
+            max_duration = "1h"
+
+            [chainspec]
+            path = "{}/../resources/production"
+            consensus_protocol = "{}"
+            era_duration = "1min"
+            minimum_era_height = 5
+            minimum_block_time = "4s"
+
+            [network]
+            min_latency = "50ms"
+            max_latency = "300ms"
+
+            {}
+            {}
+            {}
+            "#,
+            eras,
+            env!("CARGO_MANIFEST_DIR"),
+            consensus_protocol,
+            validators,
+            upgrades,
+            faults
+        );
+        fs::write(&path, scenario).expect("should write scenario");
+        simulate_file(&path)
+    }
+
+    /// Runs the simulation defined in the scenario file and returns the report as JSON.
+    fn simulate_file(path: &Path) -> serde_json::Value {
+        let report = simulate_consensus(path).expect("simulation should succeed");
+        serde_json::to_value(&report).expect("should serialize report")
+    }
+
+    /// Asserts that the given validator's equivocation was detected.
+    fn assert_equivocation_detected(report: &serde_json::Value, validator: usize) {
+        assert!(
+            !report["validators"][validator]["equivocation_detected"].is_null(),
+            "{:#}",
+            report
+        );
+    }
+
+    /// Asserts that the simulation completed without safety violations, and returns the
+    /// consensus protocol used in each era.
+    fn assert_completed(report: &serde_json::Value, eras: u64) -> Vec<String> {
+        assert_eq!(report["completed"], true, "{:#}", report);
+        assert_eq!(report["safety_violations"], 0, "{:#}", report);
+        let era_summaries = report["eras"].as_array().cloned().unwrap_or_default();
+        assert_eq!(era_summaries.len() as u64, eras, "{:#}", report);
+        assert!(report["blocks"].as_u64().unwrap_or_default() >= eras.saturating_mul(5));
+        era_summaries
+            .iter()
+            .map(|era| {
+                era["consensus_protocol"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_simulate_highway() {
+        assert_completed(&simulate("Highway", 2, &[], ""), 2);
+    }
+
+    #[test]
+    fn should_simulate_zug() {
+        assert_completed(&simulate("Zug", 2, &[], ""), 2);
+    }
+
+    #[test]
+    fn should_switch_from_highway_to_zug_at_upgrade() {
+        let report = simulate("Highway", 3, &[(1, "Zug")], "");
+        assert_eq!(
+            assert_completed(&report, 3),
+            vec!["Highway", "Zug", "Zug"],
+            "{:#}",
+            report
+        );
+    }
+
+    #[test]
+    fn should_switch_protocols_at_consecutive_upgrades() {
+        let report = simulate("Zug", 3, &[(1, "Highway"), (2, "Zug")], "");
+        assert_eq!(
+            assert_completed(&report, 3),
+            vec!["Zug", "Highway", "Zug"],
+            "{:#}",
+            report
+        );
+    }
+
+    /// Crashes and restarts one validator and starts a doppelganger of another one, so that the
+    /// restarted node has to avoid equivocating and the doppelganger gets caught.
+    const FAULTS: &str = r#"
+        [[faults]]
+        kind = "crash"
+        validator = 1
+        at = "30s"
+        restart = "1min 30s"
+
+        [[faults]]
+        kind = "equivocate"
+        validator = 3
+        at = "40s"
+    "#;
+
+    #[test]
+    fn should_simulate_highway_with_crash_and_equivocation() {
+        let report = simulate("Highway", 3, &[], FAULTS);
+        assert_completed(&report, 3);
+        assert_equivocation_detected(&report, 3);
+        assert!(report["validators"][1]["equivocation_detected"].is_null());
+    }
+
+    #[test]
+    fn should_simulate_zug_with_crash_and_equivocation() {
+        let report = simulate("Zug", 3, &[], FAULTS);
+        assert_completed(&report, 3);
+        assert_equivocation_detected(&report, 3);
+        assert!(report["validators"][1]["equivocation_detected"].is_null());
+    }
+
+    #[test]
+    fn should_simulate_example_scenario() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../resources/local/simulation-scenario.toml");
+        let report = simulate_file(&path);
+        assert_completed(&report, 5);
+        assert_equivocation_detected(&report, 4);
+        assert!(report["validators"][1]["equivocation_detected"].is_null());
+    }
+}
@@ -0,0 +1,375 @@
+//! The statistics collected during a consensus simulation, and the resulting report.
+
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    fmt::{self, Display, Formatter},
+};
+
+use serde::Serialize;
+use tracing::warn;
+
+use casper_types::{EraId, PublicKey, TimeDiff, Timestamp};
+
+use super::FIRST_ERA_ID;
+use crate::{
+    components::consensus::{cl_context::ClContext, consensus_protocol::FinalizedBlock},
+    types::chainspec::ConsensusProtocolName,
+};
+
+/// Collects statistics while the simulation runs.
+pub(super) struct Recorder {
+    /// The start of the simulation.
+    start_time: Timestamp,
+    /// The consensus protocols, by the first era they are used in.
+    consensus_protocols: BTreeMap<EraId, ConsensusProtocolName>,
+    /// Every finalized block, as first finalized by any node, by era and relative height.
+    blocks: BTreeMap<(EraId, u64), FinalizedBlock<ClContext>>,
+    /// The time between proposal and finalization, for every block and node.
+    latencies: Vec<TimeDiff>,
+    /// The start times of the eras, i.e. the timestamps of the previous eras' switch blocks.
+    era_starts: BTreeMap<EraId, Timestamp>,
+    /// The eras whose switch block has been finalized.
+    eras: Vec<EraSummary>,
+    /// The validators' rewards, summed up over all eras.
+    rewards: BTreeMap<PublicKey, u64>,
+    /// The number of eras in which each validator was inactive.
+    inactive_eras: BTreeMap<PublicKey, u64>,
+    /// The first time evidence against each equivocating validator was found.
+    evidence: BTreeMap<PublicKey, Timestamp>,
+    /// The number of times nodes disagreed on a finalized block.
+    safety_violations: u64,
+    /// The number of messages sent between nodes.
+    messages_sent: u64,
+    /// The number of times a node detected a doppelganger.
+    doppelgangers_detected: u64,
+    /// Whether any node saw the fault tolerance threshold exceeded.
+    ftt_exceeded: bool,
+}
+
+impl Recorder {
+    /// Creates a recorder for a simulation starting at `start_time`, using the given consensus
+    /// protocols from the given eras on.
+    pub(super) fn new(
+        start_time: Timestamp,
+        consensus_protocols: BTreeMap<EraId, ConsensusProtocolName>,
+    ) -> Self {
+        let mut era_starts = BTreeMap::new();
+        let _ = era_starts.insert(FIRST_ERA_ID, start_time);
+        Recorder {
+            start_time,
+            consensus_protocols,
+            blocks: BTreeMap::new(),
+            latencies: Vec::new(),
+            era_starts,
+            eras: Vec::new(),
+            rewards: BTreeMap::new(),
+            inactive_eras: BTreeMap::new(),
+            evidence: BTreeMap::new(),
+            safety_violations: 0,
+            messages_sent: 0,
+            doppelgangers_detected: 0,
+            ftt_exceeded: false,
+        }
+    }
+
+    /// Records a block finalized by a node at time `now`.
+    ///
+    /// If `count_latency` is `false`, the block is only checked against the other nodes' blocks,
+    /// e.g. because the node is catching up on blocks proposed before it started.
+    pub(super) fn finalized(
+        &mut self,
+        era_id: EraId,
+        finalized_block: &FinalizedBlock<ClContext>,
+        now: Timestamp,
+        count_latency: bool,
+    ) {
+        if count_latency {
+            self.latencies
+                .push(now.saturating_diff(finalized_block.timestamp));
+        }
+        match self.blocks.entry((era_id, finalized_block.relative_height)) {
+            Entry::Occupied(entry) => {
+                if entry.get() != finalized_block {
+                    warn!(
+                        era = era_id.value(),
+                        height = finalized_block.relative_height,
+                        "nodes finalized conflicting blocks"
+                    );
+                    self.safety_violations = self.safety_violations.saturating_add(1);
+                }
+                return;
+            }
+            Entry::Vacant(entry) => {
+                let _ = entry.insert(finalized_block.clone());
+            }
+        }
+        let terminal_block_data = match &finalized_block.terminal_block_data {
+            Some(terminal_block_data) => terminal_block_data,
+            None => return,
+        };
+        for (public_key, reward) in &terminal_block_data.rewards {
+            let total = self.rewards.entry(public_key.clone()).or_default();
+            *total = total.saturating_add(*reward);
+        }
+        for public_key in &terminal_block_data.inactive_validators {
+            let count = self.inactive_eras.entry(public_key.clone()).or_default();
+            *count = count.saturating_add(1);
+        }
+        let start_time = self
+            .era_starts
+            .get(&era_id)
+            .copied()
+            .unwrap_or(finalized_block.timestamp);
+        let _ = self
+            .era_starts
+            .insert(era_id.successor(), finalized_block.timestamp);
+        let consensus_protocol = self.consensus_protocol(era_id);
+        self.eras.push(EraSummary {
+            era_id,
+            consensus_protocol,
+            start: start_time.saturating_diff(self.start_time),
+            duration: finalized_block.timestamp.saturating_diff(start_time),
+            blocks: finalized_block.relative_height.saturating_add(1),
+            equivocators: finalized_block.equivocators.clone(),
+            inactive_validators: terminal_block_data.inactive_validators.clone(),
+        });
+    }
+
+    /// Returns the consensus protocol used in the given era.
+    fn consensus_protocol(&self, era_id: EraId) -> ConsensusProtocolName {
+        self.consensus_protocols
+            .range(..=era_id)
+            .next_back()
+            .map(|(_, consensus_protocol)| *consensus_protocol)
+            .expect("there should be a consensus protocol for the first era")
+    }
+
+    /// Records new evidence against an equivocating validator.
+    pub(super) fn evidence(&mut self, public_key: &PublicKey, now: Timestamp) {
+        let _ = self.evidence.entry(public_key.clone()).or_insert(now);
+    }
+
+    /// Records a message sent between two nodes.
+    pub(super) fn message_sent(&mut self) {
+        self.messages_sent = self.messages_sent.saturating_add(1);
+    }
+
+    /// Records a node detecting a doppelganger.
+    pub(super) fn doppelganger_detected(&mut self) {
+        self.doppelgangers_detected = self.doppelgangers_detected.saturating_add(1);
+    }
+
+    /// Records a node seeing the fault tolerance threshold exceeded.
+    pub(super) fn ftt_exceeded(&mut self) {
+        self.ftt_exceeded = true;
+    }
+
+    /// Creates the report of a simulation of `eras` eras that ended at `end_time`, with the given
+    /// validators and weights.
+    pub(super) fn into_report(
+        self,
+        eras: u64,
+        validators: &[(PublicKey, u64)],
+        end_time: Timestamp,
+    ) -> SimulationReport {
+        let start_time = self.start_time;
+        let simulated_time = end_time.saturating_diff(start_time);
+        let blocks = self.blocks.len() as u64;
+        let blocks_per_minute = if simulated_time.millis() == 0 {
+            0.0
+        } else {
+            blocks as f64 * 60_000.0 / simulated_time.millis() as f64
+        };
+        let validators = validators
+            .iter()
+            .map(|(public_key, weight)| ValidatorSummary {
+                public_key: public_key.clone(),
+                weight: *weight,
+                rewards: self.rewards.get(public_key).copied().unwrap_or_default(),
+                inactive_eras: self
+                    .inactive_eras
+                    .get(public_key)
+                    .copied()
+                    .unwrap_or_default(),
+                equivocation_detected: self
+                    .evidence
+                    .get(public_key)
+                    .map(|timestamp| timestamp.saturating_diff(start_time)),
+            })
+            .collect();
+        SimulationReport {
+            consensus_protocol: self.consensus_protocol(FIRST_ERA_ID),
+            completed: self.eras.len() as u64 >= eras,
+            simulated_time,
+            messages_sent: self.messages_sent,
+            blocks,
+            blocks_per_minute,
+            finality_latency: LatencySummary::new(self.latencies),
+            eras: self.eras,
+            validators,
+            safety_violations: self.safety_violations,
+            doppelgangers_detected: self.doppelgangers_detected,
+            ftt_exceeded: self.ftt_exceeded,
+        }
+    }
+}
+
+/// The result of a consensus simulation.
+#[derive(Debug, Serialize)]
+pub(crate) struct SimulationReport {
+    /// The consensus protocol of the first era. Upgrades can switch to a different one later.
+    consensus_protocol: ConsensusProtocolName,
+    /// Whether all eras ended before the maximum duration.
+    completed: bool,
+    /// The simulated time until all nodes finalized the last switch block, or the simulation
+    /// stopped.
+    simulated_time: TimeDiff,
+    /// The number of messages sent between nodes.
+    messages_sent: u64,
+    /// The number of finalized blocks.
+    blocks: u64,
+    /// The number of finalized blocks per minute of simulated time.
+    blocks_per_minute: f64,
+    /// The time between proposal and finalization, over all blocks and nodes.
+    finality_latency: Option<LatencySummary>,
+    /// The eras that ended.
+    eras: Vec<EraSummary>,
+    /// The validators.
+    validators: Vec<ValidatorSummary>,
+    /// The number of times nodes finalized conflicting blocks.
+    safety_violations: u64,
+    /// The number of times a node detected a doppelganger.
+    doppelgangers_detected: u64,
+    /// Whether any node saw the fault tolerance threshold exceeded.
+    ftt_exceeded: bool,
+}
+
+/// The distribution of finality latencies.
+#[derive(Debug, Serialize)]
+struct LatencySummary {
+    min: TimeDiff,
+    median: TimeDiff,
+    p90: TimeDiff,
+    p99: TimeDiff,
+    max: TimeDiff,
+    mean: TimeDiff,
+}
+
+impl LatencySummary {
+    /// Returns the summary of the given latencies, or `None` if there are none.
+    #[allow(clippy::integer_arithmetic)] // The number of latencies is small enough.
+    fn new(mut latencies: Vec<TimeDiff>) -> Option<Self> {
+        if latencies.is_empty() {
+            return None;
+        }
+        latencies.sort();
+        let count = latencies.len();
+        let percentile = |p: usize| latencies[(count - 1) * p / 100];
+        let total: u64 = latencies.iter().map(TimeDiff::millis).sum();
+        Some(LatencySummary {
+            min: percentile(0),
+            median: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: percentile(100),
+            mean: TimeDiff::from_millis(total / count as u64),
+        })
+    }
+}
+
+/// An era that ended during the simulation.
+#[derive(Debug, Serialize)]
+struct EraSummary {
+    era_id: EraId,
+    /// The consensus protocol used in the era.
+    consensus_protocol: ConsensusProtocolName,
+    /// The time from the start of the simulation to the start of the era.
+    start: TimeDiff,
+    /// The time from the start of the era to its switch block.
+    duration: TimeDiff,
+    /// The number of blocks in the era, including the switch block.
+    blocks: u64,
+    equivocators: Vec<PublicKey>,
+    inactive_validators: Vec<PublicKey>,
+}
+
+/// A validator's results over all eras.
+#[derive(Debug, Serialize)]
+struct ValidatorSummary {
+    public_key: PublicKey,
+    weight: u64,
+    /// The validator's rewards, summed up over all eras.
+    rewards: u64,
+    /// The number of eras in which the validator was inactive.
+    inactive_eras: u64,
+    /// The time from the start of the simulation until evidence of the validator's equivocation
+    /// was first found, if it equivocated.
+    equivocation_detected: Option<TimeDiff>,
+}
+
+impl Display for SimulationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:?}: {} eras in {} of simulated time{}",
+            self.consensus_protocol,
+            self.eras.len(),
+            self.simulated_time,
+            if self.completed {
+                ""
+            } else {
+                " (stopped at the maximum duration)"
+            }
+        )?;
+        writeln!(
+            f,
+            "finalized blocks: {} ({:.2} per minute)",
+            self.blocks, self.blocks_per_minute
+        )?;
+        match &self.finality_latency {
+            Some(latency) => writeln!(
+                f,
+                "finality latency: min {}, median {}, 90th percentile {}, 99th percentile {}, max \
+                 {}, mean {}",
+                latency.min, latency.median, latency.p90, latency.p99, latency.max, latency.mean
+            )?,
+            None => writeln!(f, "finality latency: no blocks finalized")?,
+        }
+        writeln!(f, "messages sent: {}", self.messages_sent)?;
+        writeln!(f, "safety violations: {}", self.safety_violations)?;
+        writeln!(f, "doppelgangers detected: {}", self.doppelgangers_detected)?;
+        writeln!(
+            f,
+            "fault tolerance threshold exceeded: {}",
+            self.ftt_exceeded
+        )?;
+        writeln!(f, "eras:")?;
+        for era in &self.eras {
+            writeln!(
+                f,
+                "  {} ({:?}): started after {}, lasted {}, {} blocks, {} equivocators, {} \
+                 inactive validators",
+                era.era_id,
+                era.consensus_protocol,
+                era.start,
+                era.duration,
+                era.blocks,
+                era.equivocators.len(),
+                era.inactive_validators.len()
+            )?;
+        }
+        writeln!(f, "validators:")?;
+        for validator in &self.validators {
+            write!(
+                f,
+                "  {}: weight {}, rewards {}, inactive in {} eras",
+                validator.public_key, validator.weight, validator.rewards, validator.inactive_eras
+            )?;
+            match validator.equivocation_detected {
+                Some(time) => writeln!(f, ", equivocation detected after {}", time)?,
+                None => writeln!(f)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,332 @@
+//! The scenario file of a consensus simulation.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use num_rational::Ratio;
+use serde::Deserialize;
+
+use casper_types::{EraId, TimeDiff};
+
+use super::{SimulationError, FIRST_ERA_ID};
+use crate::{
+    types::{
+        chainspec::{ActivationPoint, ConsensusProtocolName},
+        Chainspec, ChainspecRawBytes,
+    },
+    utils::Loadable,
+};
+
+/// A scenario to simulate: the validators, the network, the faults and the chainspec settings.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Scenario {
+    /// The number of eras to simulate.
+    pub(crate) eras: u64,
+    /// The seed of the simulation's random number generator.
+    #[serde(default)]
+    pub(crate) seed: u64,
+    /// The simulation stops after this amount of simulated time, even if not all eras have ended.
+    pub(crate) max_duration: TimeDiff,
+    /// The chainspec to use, with overrides.
+    pub(crate) chainspec: ChainspecSettings,
+    /// The simulated network.
+    pub(crate) network: NetworkSettings,
+    /// The validators.
+    pub(crate) validators: Vec<ValidatorSettings>,
+    /// The faulty behavior of validators.
+    #[serde(default)]
+    pub(crate) faults: Vec<Fault>,
+    /// The upgrades that switch to a different consensus protocol, in order.
+    #[serde(default)]
+    pub(crate) upgrades: Vec<Upgrade>,
+}
+
+/// The chainspec to use, and the settings to override in it.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ChainspecSettings {
+    /// The directory containing the `chainspec.toml`, relative to the scenario file.
+    pub(crate) path: PathBuf,
+    /// Overrides `core.consensus_protocol`.
+    #[serde(default)]
+    pub(crate) consensus_protocol: Option<ConsensusProtocolName>,
+    /// Overrides `core.era_duration`.
+    #[serde(default)]
+    pub(crate) era_duration: Option<TimeDiff>,
+    /// Overrides `core.minimum_era_height`.
+    #[serde(default)]
+    pub(crate) minimum_era_height: Option<u64>,
+    /// Overrides `core.minimum_block_time`.
+    #[serde(default)]
+    pub(crate) minimum_block_time: Option<TimeDiff>,
+    /// Overrides `core.finality_threshold_fraction`.
+    #[serde(default)]
+    pub(crate) finality_threshold_fraction: Option<Ratio<u64>>,
+    /// Overrides `highway.maximum_round_length`.
+    #[serde(default)]
+    pub(crate) maximum_round_length: Option<TimeDiff>,
+}
+
+/// The latencies of the simulated network.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct NetworkSettings {
+    /// The minimum time it takes to deliver a message.
+    pub(crate) min_latency: TimeDiff,
+    /// The maximum time it takes to deliver a message, not counting the validators' latencies.
+    pub(crate) max_latency: TimeDiff,
+}
+
+/// A validator.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ValidatorSettings {
+    /// The validator's weight.
+    pub(crate) weight: u64,
+    /// The additional latency of every message sent or received by this validator.
+    #[serde(default)]
+    pub(crate) latency: TimeDiff,
+}
+
+/// An upgrade, activated at the beginning of an era.
+///
+/// Like on a real network, the eras before the activation point keep using the old chainspec, and
+/// all eras from the activation point on use the upgraded one.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Upgrade {
+    /// The first era that uses the upgraded chainspec.
+    pub(crate) activation_era: u64,
+    /// The consensus protocol from the activation era on.
+    pub(crate) consensus_protocol: ConsensusProtocolName,
+}
+
+/// A validator's faulty behavior. The validator is given by its index in the list of validators,
+/// and all times are relative to the start of the simulation.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub(crate) enum Fault {
+    /// The validator's node crashes, and optionally restarts.
+    ///
+    /// Messages to the node are lost while it is down. After a restart it has to catch up on the
+    /// current era, and it uses its unit hash file or WAL to avoid equivocating.
+    Crash {
+        /// The index of the validator.
+        validator: usize,
+        /// When the node crashes.
+        at: TimeDiff,
+        /// When the node restarts. It stays down if this is omitted.
+        #[serde(default)]
+        restart: Option<TimeDiff>,
+    },
+    /// A second node with the validator's key is started, which doesn't know about the first
+    /// node's messages and will equivocate.
+    Equivocate {
+        /// The index of the validator.
+        validator: usize,
+        /// When the second node starts.
+        #[serde(default)]
+        at: TimeDiff,
+    },
+}
+
+impl Fault {
+    /// Returns the index of the faulty validator.
+    fn validator(&self) -> usize {
+        match self {
+            Fault::Crash { validator, .. } | Fault::Equivocate { validator, .. } => *validator,
+        }
+    }
+}
+
+impl Scenario {
+    /// Reads the scenario file and loads the chainspec it refers to, with the overrides applied.
+    ///
+    /// Returns the chainspec of the first era, and of each upgrade's activation era.
+    pub(crate) fn load(
+        path: &Path,
+    ) -> Result<(Scenario, BTreeMap<EraId, Chainspec>), SimulationError> {
+        let contents = fs::read_to_string(path).map_err(|error| SimulationError::ReadScenario {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        let scenario: Scenario = toml::from_str(&contents)?;
+        scenario.validate()?;
+
+        let root = path.parent().unwrap_or_else(|| Path::new("."));
+        let (mut chainspec, _) =
+            <(Chainspec, ChainspecRawBytes)>::from_path(root.join(&scenario.chainspec.path))
+                .map_err(SimulationError::LoadChainspec)?;
+        scenario.chainspec.apply(&mut chainspec);
+        if !chainspec.is_valid() {
+            return Err(SimulationError::InvalidScenario(
+                "the chainspec with the overrides applied is invalid".to_string(),
+            ));
+        }
+        let mut chainspecs = BTreeMap::new();
+        let mut upgraded = chainspec.clone();
+        for upgrade in &scenario.upgrades {
+            let activation_era = EraId::from(upgrade.activation_era);
+            upgraded.protocol_config.activation_point = ActivationPoint::EraId(activation_era);
+            upgraded.core_config.consensus_protocol = upgrade.consensus_protocol;
+            let _ = chainspecs.insert(activation_era, upgraded.clone());
+        }
+        let _ = chainspecs.insert(FIRST_ERA_ID, chainspec);
+        Ok((scenario, chainspecs))
+    }
+
+    /// Checks that the scenario is consistent.
+    fn validate(&self) -> Result<(), SimulationError> {
+        let invalid = |msg: &str| Err(SimulationError::InvalidScenario(msg.to_string()));
+        if self.eras == 0 {
+            return invalid("the number of eras must be positive");
+        }
+        if self.validators.is_empty() {
+            return invalid("there must be at least one validator");
+        }
+        if self
+            .validators
+            .iter()
+            .any(|validator| validator.weight == 0)
+        {
+            return invalid("validator weights must be positive");
+        }
+        if self.network.min_latency > self.network.max_latency {
+            return invalid("min_latency must not be greater than max_latency");
+        }
+        for fault in &self.faults {
+            if fault.validator() >= self.validators.len() {
+                return invalid("a fault refers to a validator index that doesn't exist");
+            }
+            if let Fault::Crash {
+                at,
+                restart: Some(restart),
+                ..
+            } = fault
+            {
+                if restart <= at {
+                    return invalid("a validator must restart after it crashed");
+                }
+            }
+        }
+        let mut last_activation_era = 0;
+        for upgrade in &self.upgrades {
+            if upgrade.activation_era <= last_activation_era {
+                return invalid("upgrade activation eras must be positive and increasing");
+            }
+            if upgrade.activation_era >= self.eras {
+                return invalid("an upgrade must activate in one of the simulated eras");
+            }
+            last_activation_era = upgrade.activation_era;
+        }
+        Ok(())
+    }
+}
+
+impl ChainspecSettings {
+    /// Applies the overrides to the chainspec.
+    fn apply(&self, chainspec: &mut Chainspec) {
+        let core_config = &mut chainspec.core_config;
+        if let Some(consensus_protocol) = self.consensus_protocol {
+            core_config.consensus_protocol = consensus_protocol;
+        }
+        if let Some(era_duration) = self.era_duration {
+            core_config.era_duration = era_duration;
+        }
+        if let Some(minimum_era_height) = self.minimum_era_height {
+            core_config.minimum_era_height = minimum_era_height;
+        }
+        if let Some(minimum_block_time) = self.minimum_block_time {
+            core_config.minimum_block_time = minimum_block_time;
+        }
+        if let Some(finality_threshold_fraction) = self.finality_threshold_fraction {
+            core_config.finality_threshold_fraction = finality_threshold_fraction;
+        }
+        if let Some(maximum_round_length) = self.maximum_round_length {
+            chainspec.highway_config.maximum_round_length = maximum_round_length;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_scenario() {
+        let scenario: Scenario = toml::from_str(
+            r#"
+            eras = 3
+            max_duration = "2h"
+
+            [chainspec]
+            path = "../../local"
+            consensus_protocol = "Zug"
+            minimum_block_time = "8s"
+            finality_threshold_fraction = [1, 4]
+
+            [network]
+            min_latency = "50ms"
+            max_latency = "300ms"
+
+            [[validators]]
+            weight = 100
+
+            [[validators]]
+            weight = 50
+            latency = "1s"
+
+            [[faults]]
+            kind = "crash"
+            validator = 0
+            at = "10min"
+            restart = "15min"
+
+            [[faults]]
+            kind = "equivocate"
+            validator = 1
+
+            [[upgrades]]
+            activation_era = 2
+            consensus_protocol = "Highway"
+            "#,
+        )
+        .expect("should parse scenario");
+        scenario.validate().expect("scenario should be valid");
+        assert_eq!(
+            scenario.chainspec.consensus_protocol,
+            Some(ConsensusProtocolName::Zug)
+        );
+        assert_eq!(
+            scenario.chainspec.finality_threshold_fraction,
+            Some(Ratio::new(1, 4))
+        );
+        assert_eq!(scenario.validators[1].latency, TimeDiff::from_seconds(1));
+        assert!(matches!(
+            scenario.faults[1],
+            Fault::Equivocate { validator: 1, at } if at == TimeDiff::default()
+        ));
+
+        assert_eq!(scenario.upgrades[0].activation_era, 2);
+        assert_eq!(
+            scenario.upgrades[0].consensus_protocol,
+            ConsensusProtocolName::Highway
+        );
+
+        let mut late_upgrade = scenario.clone();
+        late_upgrade.upgrades[0].activation_era = 3;
+        assert!(late_upgrade.validate().is_err());
+
+        let mut invalid = scenario;
+        invalid.faults.push(Fault::Crash {
+            validator: 2,
+            at: TimeDiff::default(),
+            restart: None,
+        });
+        assert!(invalid.validate().is_err());
+    }
+}
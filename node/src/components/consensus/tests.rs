@@ -1,6 +1,4 @@
 /// Basic building blocks for the Discrete Event Simulator (DES).
 pub(crate) mod consensus_des_testing;
-/// Message queue.
-pub(crate) mod queue;
 /// Miscellaneous code shared among consensus tests
 pub(crate) mod utils;
@@ -1,14 +1,12 @@
 use std::{
     collections::BTreeMap,
-    fmt::{Debug, Display, Formatter},
-    hash::Hash,
+    fmt::{Debug, Formatter},
 };
 
-use datasize::DataSize;
-
 use casper_types::Timestamp;
 
-use super::queue::{MessageT, Queue, QueueEntry};
+pub(crate) use crate::components::consensus::des::{Message, ValidatorId};
+use crate::components::consensus::des::{MessageT, Queue, QueueEntry};
 
 /// Enum defining recipients of the message.
 #[derive(Debug)]
@@ -17,22 +15,6 @@ pub(crate) enum Target {
     AllExcept(ValidatorId),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub(crate) struct Message<M: Clone + Debug> {
-    pub(crate) sender: ValidatorId,
-    pub(crate) payload: M,
-}
-
-impl<M: Clone + Debug> Message<M> {
-    pub(crate) fn new(sender: ValidatorId, payload: M) -> Self {
-        Message { sender, payload }
-    }
-
-    pub(crate) fn payload(&self) -> &M {
-        &self.payload
-    }
-}
-
 pub(crate) struct TargetedMessage<M: Clone + Debug> {
     pub(crate) message: Message<M>,
     pub(crate) target: Target,
@@ -54,15 +36,6 @@ impl<M: Clone + Debug> TargetedMessage<M> {
     }
 }
 
-#[derive(Debug, Clone, DataSize, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
-pub(crate) struct ValidatorId(pub(crate) u64);
-
-impl Display for ValidatorId {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
-    }
-}
-
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum Fault {
     /// The validator does not send any messages within the interval between the timestamps.
@@ -1,10 +1,12 @@
 use std::{
-    fmt::{Debug, Display},
+    fmt::{self, Debug, Display, Formatter},
     hash::Hash,
 };
 
 use datasize::DataSize;
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use casper_types::Timestamp;
 
 /// A validator identifier.
 pub trait ValidatorIdT: Eq + Ord + Clone + Debug + Hash + Send + DataSize + Display {}
@@ -39,13 +41,92 @@ impl<H> HashT for H where
 {
 }
 
+/// The kind of consensus message a hash is signed for.
+///
+/// This allows a signer to detect requests to sign two conflicting messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SigningContext {
+    /// A Highway unit.
+    HighwayUnit {
+        /// The unit's sequence number.
+        seq_number: u64,
+        /// The unit's timestamp.
+        timestamp: Timestamp,
+    },
+    /// A Highway endorsement.
+    HighwayEndorsement,
+    /// A Highway ping.
+    HighwayPing {
+        /// The ping's timestamp.
+        timestamp: Timestamp,
+    },
+    /// A Zug echo.
+    ZugEcho {
+        /// The round the echo belongs to.
+        round_id: u32,
+    },
+    /// A Zug vote.
+    ZugVote {
+        /// The round the vote belongs to.
+        round_id: u32,
+        /// The vote itself.
+        vote: bool,
+    },
+}
+
+impl Display for SigningContext {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SigningContext::HighwayUnit {
+                seq_number,
+                timestamp,
+            } => write!(formatter, "Highway unit {} at {}", seq_number, timestamp),
+            SigningContext::HighwayEndorsement => write!(formatter, "Highway endorsement"),
+            SigningContext::HighwayPing { timestamp } => {
+                write!(formatter, "Highway ping at {}", timestamp)
+            }
+            SigningContext::ZugEcho { round_id } => {
+                write!(formatter, "Zug echo in round {}", round_id)
+            }
+            SigningContext::ZugVote { round_id, vote } => {
+                write!(formatter, "Zug vote {} in round {}", vote, round_id)
+            }
+        }
+    }
+}
+
+/// A consensus message to be signed.
+///
+/// Besides the hash that is signed, this contains the serialized message itself, so that a signer
+/// which doesn't trust the node can compute the hash and tell what kind of message it is signing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SigningInput<H> {
+    /// The hash of the message, which is what the signature is created over.
+    pub hash: H,
+    /// The serialized message; `hash` is its hash.
+    pub serialized: Vec<u8>,
+    /// What kind of message is signed.
+    pub context: SigningContext,
+}
+
 /// A validator's secret signing key.
 pub trait ValidatorSecret: Send + DataSize {
     type Hash: DataSize;
 
     type Signature: Eq + PartialEq + Clone + Debug + Hash + Serialize + DeserializeOwned + DataSize;
 
-    fn sign(&self, hash: &Self::Hash) -> Self::Signature;
+    /// Signs the given message.
+    ///
+    /// Returns `None` if the signer refused or failed to sign.
+    fn sign(&self, input: &SigningInput<Self::Hash>) -> Option<Self::Signature>;
+
+    /// Returns `true` if `sign` never blocks.
+    ///
+    /// Otherwise the protocol must not call `sign` itself, but request the signature via
+    /// `ProtocolOutcome::CreateSignature` instead.
+    fn signs_instantly(&self) -> bool {
+        true
+    }
 }
 
 /// The collection of types the user can choose for cryptography, IDs, transactions, etc.
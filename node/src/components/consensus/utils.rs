@@ -1,5 +1,16 @@
 mod validators;
 mod weight;
 
+use serde::{de::DeserializeOwned, Serialize};
+
 pub(crate) use validators::{Validator, ValidatorIndex, ValidatorMap, Validators};
 pub(crate) use weight::Weight;
+
+/// Deserializes a `T` from `bytes`, if they are exactly its `bincode` serialization.
+///
+/// This rejects trailing bytes and any non-canonical encoding, so that the value is the one whose
+/// hash was computed over `bytes`.
+pub(crate) fn deserialize_exact<T: Serialize + DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+    let value: T = bincode::deserialize(bytes).ok()?;
+    (bincode::serialize(&value).ok()? == bytes).then_some(value)
+}
@@ -0,0 +1,140 @@
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use casper_types::{bytesrepr::Bytes, EraId, PublicKey};
+
+use crate::rpcs::docs::DocExample;
+
+static VALIDATOR_FAULT: Lazy<ValidatorFault> = Lazy::new(|| ValidatorFault {
+    era_id: EraId::new(1),
+    public_key: PublicKey::doc_example().clone(),
+    kind: FaultKind::ConflictingMessages,
+    message_1: vec![1, 0, 0, 0, 0, 0, 0, 0].into(),
+    message_2: vec![2, 0, 0, 0, 0, 0, 0, 0].into(),
+    evidence: vec![1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0].into(),
+    verification: FaultVerification::Valid,
+});
+
+/// The kind of fault proven by a validator's conflicting messages.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, JsonSchema, Eq, PartialEq)]
+pub enum FaultKind {
+    /// The validator created two Highway units with the same sequence number.
+    Equivocation,
+    /// The validator endorsed two Highway units on conflicting forks.
+    ConflictingEndorsements,
+    /// The validator signed two contradicting Zug echoes or votes in the same round.
+    ConflictingMessages,
+}
+
+/// The result of verifying the evidence of a fault against the era's validators.
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, Eq, PartialEq)]
+pub enum FaultVerification {
+    /// The signatures are valid and the messages conflict.
+    Valid,
+    /// The evidence doesn't prove a fault, for the given reason.
+    Invalid(String),
+}
+
+/// The evidence of a validator's fault, as found by a consensus protocol instance.
+pub(crate) struct FaultEvidence<VID> {
+    /// The faulty validator.
+    pub(super) validator_id: VID,
+    /// The kind of fault.
+    pub(super) kind: FaultKind,
+    /// The two conflicting signed messages, bincode-serialized.
+    pub(super) messages: (Vec<u8>, Vec<u8>),
+    /// The complete evidence, bincode-serialized.
+    pub(super) evidence: Vec<u8>,
+    /// The result of verifying the evidence.
+    pub(super) verification: FaultVerification,
+}
+
+impl<VID> FaultEvidence<VID> {
+    /// Creates the evidence of a fault, serializing the conflicting messages and the complete
+    /// evidence.
+    pub(crate) fn new<M, E>(
+        validator_id: VID,
+        kind: FaultKind,
+        messages: (&M, &M),
+        evidence: &E,
+        verification: FaultVerification,
+    ) -> Self
+    where
+        M: Serialize,
+        E: Serialize + ?Sized,
+    {
+        let serialize = |item: &M| bincode::serialize(item).expect("should serialize message");
+        FaultEvidence {
+            validator_id,
+            kind,
+            messages: (serialize(messages.0), serialize(messages.1)),
+            evidence: bincode::serialize(evidence).expect("should serialize evidence"),
+            verification,
+        }
+    }
+}
+
+/// A validator's fault in an era, with the conflicting messages proving it.
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ValidatorFault {
+    /// The era in which the fault occurred.
+    era_id: EraId,
+    /// The public key of the faulty validator.
+    public_key: PublicKey,
+    /// The kind of fault.
+    kind: FaultKind,
+    /// The first of the conflicting messages.
+    #[schemars(
+        with = "String",
+        description = "The first of the conflicting signed messages, bincode-serialized and \
+        hex-encoded: a Highway unit or endorsement, or a Zug echo or vote."
+    )]
+    message_1: Bytes,
+    /// The second of the conflicting messages.
+    #[schemars(
+        with = "String",
+        description = "The second of the conflicting signed messages, bincode-serialized and \
+        hex-encoded: a Highway unit or endorsement, or a Zug echo or vote."
+    )]
+    message_2: Bytes,
+    /// The complete evidence.
+    #[schemars(
+        with = "String",
+        description = "The complete evidence, bincode-serialized and hex-encoded. For Highway \
+        this is the evidence as gossiped between nodes, which for conflicting endorsements also \
+        contains the endorsed units. For Zug this is the first message together with the \
+        second message's content and signature, as in an evidence message."
+    )]
+    evidence: Bytes,
+    /// The result of verifying the evidence when the request was handled.
+    verification: FaultVerification,
+}
+
+impl ValidatorFault {
+    pub(crate) fn new(era_id: EraId, fault_evidence: FaultEvidence<PublicKey>) -> Self {
+        let FaultEvidence {
+            validator_id,
+            kind,
+            messages: (message_1, message_2),
+            evidence,
+            verification,
+        } = fault_evidence;
+        ValidatorFault {
+            era_id,
+            public_key: validator_id,
+            kind,
+            message_1: message_1.into(),
+            message_2: message_2.into(),
+            evidence: evidence.into(),
+            verification,
+        }
+    }
+}
+
+impl DocExample for ValidatorFault {
+    fn doc_example() -> &'static Self {
+        &VALIDATOR_FAULT
+    }
+}
@@ -0,0 +1,51 @@
+use datasize::DataSize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use casper_types::TimeDiff;
+
+/// A validator's participation in an era's consensus, as observed by this node.
+#[derive(
+    Clone, Copy, DataSize, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(deny_unknown_fields)]
+pub struct ValidatorPerformance {
+    /// The number of the validator's proposals that became part of the chain.
+    pub proposals: u64,
+    /// The number of rounds in which the validator was the leader but no proposal of theirs
+    /// became part of the chain.
+    pub missed_proposals: u64,
+    /// The number of Highway units, or Zug echoes and votes, the validator created.
+    pub units_and_votes: u64,
+    /// The number of Highway units the validator endorsed.
+    pub endorsements: u64,
+    /// The average time between a Highway unit's timestamp and its arrival, or between the start
+    /// of a Zug round and the arrival of an echo or vote in it.
+    pub average_lateness: TimeDiff,
+}
+
+/// The lateness of a validator's messages, summed up to compute the average.
+#[derive(Clone, Copy, DataSize, Debug, Default)]
+pub(crate) struct Lateness {
+    /// The sum of the messages' lateness, in milliseconds.
+    total_millis: u64,
+    /// The number of messages.
+    count: u64,
+}
+
+impl Lateness {
+    /// Records the lateness of a message.
+    pub(crate) fn record(&mut self, lateness: TimeDiff) {
+        self.total_millis = self.total_millis.saturating_add(lateness.millis());
+        self.count = self.count.saturating_add(1);
+    }
+
+    /// Returns the average lateness of the recorded messages, or zero if there are none.
+    pub(crate) fn average(&self) -> TimeDiff {
+        TimeDiff::from_millis(
+            self.total_millis
+                .checked_div(self.count)
+                .unwrap_or_default(),
+        )
+    }
+}
@@ -2,6 +2,7 @@
 
 mod config;
 mod error;
+mod key_history;
 mod metrics;
 mod operations;
 #[cfg(test)]
@@ -32,8 +33,8 @@ use casper_execution_engine::{
         self,
         engine_config::{FeeHandling, RefundHandling},
         genesis::GenesisError,
-        ChainspecRegistry, DeployItem, EngineConfigBuilder, EngineState, GenesisSuccess,
-        SystemContractRegistry, UpgradeConfig, UpgradeSuccess,
+        ChainspecRegistry, DeployItem, EngineConfig, EngineConfigBuilder, EngineState,
+        GenesisSuccess, SystemContractRegistry, UpgradeConfig, UpgradeSuccess,
     },
     shared::{newtypes::CorrelationId, system_config::SystemConfig, wasm_config::WasmConfig},
     storage::{
@@ -65,6 +66,7 @@ use crate::{
 };
 pub(crate) use config::Config;
 pub(crate) use error::{BlockExecutionError, ConfigError};
+use key_history::KeyHistoryFilter;
 use metrics::Metrics;
 pub use operations::execute_finalized_block;
 use operations::execute_only;
@@ -212,6 +214,9 @@ pub(crate) struct ContractRuntime {
     system_contract_registry: Option<SystemContractRegistry>,
     activation_point: ActivationPoint,
     prune_batch_size: u64,
+    /// Selection of the keys whose value history is recorded in storage.
+    #[data_size(skip)]
+    key_history_filter: Arc<KeyHistoryFilter>,
 }
 
 impl Debug for ContractRuntime {
@@ -494,6 +499,7 @@ impl ContractRuntime {
                         let shared_pre_state = Arc::clone(&self.execution_pre_state);
                         let activation_point = self.activation_point;
                         let prune_batch_size = self.prune_batch_size;
+                        let key_history_filter = Arc::clone(&self.key_history_filter);
                         effects.extend(
                             Self::execute_finalized_block_or_requeue(
                                 engine_state,
@@ -508,6 +514,7 @@ impl ContractRuntime {
                                 activation_point,
                                 key_block_height_for_activation_point,
                                 prune_batch_size,
+                                key_history_filter,
                                 meta_block_state,
                             )
                             .ignore(),
@@ -585,6 +592,21 @@ impl ContractRuntime {
                 }
                 .ignore()
             }
+            ContractRuntimeRequest::SnapshotGlobalState { dir, responder } => {
+                trace!(dir = %dir.display(), "snapshot global state request");
+                let engine_state = Arc::clone(&self.engine_state);
+                async move {
+                    let result = run_intensive_task(move || {
+                        engine_state
+                            .get_state()
+                            .copy_to(dir)
+                            .map_err(engine_state::Error::from)
+                    })
+                    .await;
+                    responder.respond(result).await
+                }
+                .ignore()
+            }
         }
     }
 }
@@ -612,14 +634,6 @@ impl ContractRuntime {
         refund_handling: RefundHandling,
         fee_handling: FeeHandling,
     ) -> Result<Self, ConfigError> {
-        // TODO: This is bogus, get rid of this
-        let execution_pre_state = Arc::new(Mutex::new(ExecutionPreState {
-            pre_state_root_hash: Default::default(),
-            next_block_height: 0,
-            parent_hash: Default::default(),
-            parent_seed: Default::default(),
-        }));
-
         let environment = Arc::new(LmdbEnvironment::new(
             storage_dir,
             contract_runtime_config.max_global_state_size_or_default(),
@@ -634,22 +648,104 @@ impl ContractRuntime {
         )?);
 
         let global_state = LmdbGlobalState::empty(environment, trie_store)?;
-        let engine_config = EngineConfigBuilder::new()
-            .with_max_query_depth(contract_runtime_config.max_query_depth_or_default())
-            .with_max_associated_keys(max_associated_keys)
-            .with_max_runtime_call_stack_height(max_runtime_call_stack_height)
-            .with_minimum_delegation_amount(minimum_delegation_amount)
-            .with_strict_argument_checking(strict_argument_checking)
-            .with_vesting_schedule_period_millis(vesting_schedule_period_millis)
-            .with_max_delegators_per_validator(max_delegators_per_validator)
-            .with_wasm_config(wasm_config)
-            .with_system_config(system_config)
-            .with_administrative_accounts(administrative_accounts)
-            .with_allow_auction_bids(allow_auction_bids)
-            .with_allow_unrestricted_transfers(allow_unrestricted_transfers)
-            .with_refund_handling(refund_handling)
-            .with_fee_handling(fee_handling)
-            .build();
+        let key_history_filter = KeyHistoryFilter::new(contract_runtime_config)?;
+        let engine_config = engine_config(
+            contract_runtime_config,
+            wasm_config,
+            system_config,
+            max_associated_keys,
+            max_runtime_call_stack_height,
+            minimum_delegation_amount,
+            strict_argument_checking,
+            vesting_schedule_period_millis,
+            max_delegators_per_validator,
+            administrative_accounts,
+            allow_auction_bids,
+            allow_unrestricted_transfers,
+            refund_handling,
+            fee_handling,
+        );
+
+        Self::with_global_state(
+            global_state,
+            engine_config,
+            protocol_version,
+            activation_point,
+            prune_batch_size,
+            key_history_filter,
+            registry,
+        )
+    }
+
+    /// Creates a contract runtime on top of an existing global state, without ever writing to it.
+    ///
+    /// The returned component can serve queries and speculative execution, but committing any
+    /// changes to the global state will fail.
+    pub(crate) fn new_read_only(
+        storage_dir: &Path,
+        contract_runtime_config: &Config,
+        chainspec: &Chainspec,
+        registry: &Registry,
+    ) -> Result<Self, ConfigError> {
+        let environment = Arc::new(LmdbEnvironment::open_read_only(
+            storage_dir,
+            contract_runtime_config.max_global_state_size_or_default(),
+            contract_runtime_config.max_readers_or_default(),
+        )?);
+        let trie_store = Arc::new(LmdbTrieStore::open(&environment, None)?);
+        let global_state = LmdbGlobalState::open(environment, trie_store)?;
+
+        let core_config = &chainspec.core_config;
+        let max_delegators_per_validator = if core_config.max_delegators_per_validator == 0 {
+            None
+        } else {
+            Some(core_config.max_delegators_per_validator)
+        };
+        let engine_config = engine_config(
+            contract_runtime_config,
+            chainspec.wasm_config,
+            chainspec.system_costs_config,
+            core_config.max_associated_keys,
+            core_config.max_runtime_call_stack_height,
+            core_config.minimum_delegation_amount,
+            core_config.strict_argument_checking,
+            core_config.vesting_schedule_period.millis(),
+            max_delegators_per_validator,
+            core_config.administrators.clone(),
+            core_config.allow_auction_bids,
+            core_config.allow_unrestricted_transfers,
+            core_config.refund_handling,
+            core_config.fee_handling,
+        );
+
+        Self::with_global_state(
+            global_state,
+            engine_config,
+            chainspec.protocol_config.version,
+            chainspec.protocol_config.activation_point,
+            core_config.prune_batch_size,
+            // Nothing is ever executed, so there is no history to record.
+            KeyHistoryFilter::default(),
+            registry,
+        )
+    }
+
+    fn with_global_state(
+        global_state: LmdbGlobalState,
+        engine_config: EngineConfig,
+        protocol_version: ProtocolVersion,
+        activation_point: ActivationPoint,
+        prune_batch_size: u64,
+        key_history_filter: KeyHistoryFilter,
+        registry: &Registry,
+    ) -> Result<Self, ConfigError> {
+        // TODO: This is bogus, get rid of this
+        let execution_pre_state = Arc::new(Mutex::new(ExecutionPreState {
+            pre_state_root_hash: Default::default(),
+            next_block_height: 0,
+            parent_hash: Default::default(),
+            parent_seed: Default::default(),
+        }));
 
         let engine_state = Arc::new(EngineState::new(global_state, engine_config));
 
@@ -665,6 +761,7 @@ impl ContractRuntime {
             system_contract_registry: None,
             activation_point,
             prune_batch_size,
+            key_history_filter: Arc::new(key_history_filter),
         })
     }
 
@@ -756,6 +853,7 @@ impl ContractRuntime {
         activation_point: ActivationPoint,
         key_block_height_for_activation_point: u64,
         prune_batch_size: u64,
+        key_history_filter: Arc<KeyHistoryFilter>,
         mut meta_block_state: MetaBlockState,
     ) where
         REv: From<ContractRuntimeRequest>
@@ -767,14 +865,17 @@ impl ContractRuntime {
     {
         debug!("ContractRuntime: execute_finalized_block_or_requeue");
         let contract_runtime_metrics = metrics.clone();
-        let BlockAndExecutionResults {
-            block,
-            approvals_hashes,
-            execution_results,
-            maybe_step_effect_and_upcoming_era_validators,
-        } = match run_intensive_task(move || {
+        let (
+            BlockAndExecutionResults {
+                block,
+                approvals_hashes,
+                execution_results,
+                maybe_step_effect_and_upcoming_era_validators,
+            },
+            key_history,
+        ) = match run_intensive_task(move || {
             debug!("ContractRuntime: execute_finalized_block");
-            execute_finalized_block(
+            let block_and_execution_results = execute_finalized_block(
                 engine_state.as_ref(),
                 Some(contract_runtime_metrics),
                 protocol_version,
@@ -784,11 +885,14 @@ impl ContractRuntime {
                 activation_point.era_id(),
                 key_block_height_for_activation_point,
                 prune_batch_size,
-            )
+            )?;
+            let key_history =
+                key_history_filter.collect(engine_state.as_ref(), &block_and_execution_results)?;
+            Ok::<_, BlockExecutionError>((block_and_execution_results, key_history))
         })
         .await
         {
-            Ok(block_and_execution_results) => block_and_execution_results,
+            Ok(results) => results,
             Err(error) => {
                 error!(%error, "failed to execute block");
                 return fatal!(effect_builder, "{}", error).await;
@@ -877,6 +981,11 @@ impl ContractRuntime {
                 .put_execution_results_to_storage(*block.hash(), execution_results_map)
                 .await;
         }
+        if !key_history.is_empty() {
+            effect_builder
+                .put_key_history_to_storage(block.height(), key_history)
+                .await;
+        }
         if meta_block_state
             .register_as_executed()
             .was_already_registered()
@@ -977,8 +1086,46 @@ impl ContractRuntime {
     }
 }
 
+/// Builds the execution engine's configuration, shared by read-write and read-only runtimes.
+#[allow(clippy::too_many_arguments)]
+fn engine_config(
+    contract_runtime_config: &Config,
+    wasm_config: WasmConfig,
+    system_config: SystemConfig,
+    max_associated_keys: u32,
+    max_runtime_call_stack_height: u32,
+    minimum_delegation_amount: u64,
+    strict_argument_checking: bool,
+    vesting_schedule_period_millis: u64,
+    max_delegators_per_validator: Option<u32>,
+    administrative_accounts: BTreeSet<PublicKey>,
+    allow_auction_bids: bool,
+    allow_unrestricted_transfers: bool,
+    refund_handling: RefundHandling,
+    fee_handling: FeeHandling,
+) -> EngineConfig {
+    EngineConfigBuilder::new()
+        .with_max_query_depth(contract_runtime_config.max_query_depth_or_default())
+        .with_max_associated_keys(max_associated_keys)
+        .with_max_runtime_call_stack_height(max_runtime_call_stack_height)
+        .with_minimum_delegation_amount(minimum_delegation_amount)
+        .with_strict_argument_checking(strict_argument_checking)
+        .with_vesting_schedule_period_millis(vesting_schedule_period_millis)
+        .with_max_delegators_per_validator(max_delegators_per_validator)
+        .with_wasm_config(wasm_config)
+        .with_system_config(system_config)
+        .with_administrative_accounts(administrative_accounts)
+        .with_allow_auction_bids(allow_auction_bids)
+        .with_allow_unrestricted_transfers(allow_unrestricted_transfers)
+        .with_refund_handling(refund_handling)
+        .with_fee_handling(fee_handling)
+        .build()
+}
+
 #[cfg(test)]
 mod trie_chunking_tests {
+    use std::path::Path;
+
     use casper_execution_engine::{
         core::engine_state::engine_config::{DEFAULT_FEE_HANDLING, DEFAULT_REFUND_HANDLING},
         shared::{
@@ -997,7 +1144,11 @@ mod trie_chunking_tests {
     use crate::{
         components::fetcher::FetchResponse,
         contract_runtime::{Config as ContractRuntimeConfig, ContractRuntime},
-        types::{ActivationPoint, ChunkingError, TrieOrChunk, TrieOrChunkId, ValueOrChunk},
+        types::{
+            ActivationPoint, Chainspec, ChainspecRawBytes, ChunkingError, TrieOrChunk,
+            TrieOrChunkId, ValueOrChunk,
+        },
+        utils::Loadable,
     };
 
     use super::ContractRuntimeError;
@@ -1051,9 +1202,17 @@ mod trie_chunking_tests {
     // Returns [`ContractRuntime`] instance and the new Merkle root after applying the `test_pair`.
     fn create_test_state(test_pair: [TestPair; 2]) -> (ContractRuntime, Digest) {
         let temp_dir = tempdir().unwrap();
+        create_test_state_in(temp_dir.path(), test_pair)
+    }
+
+    // Like `create_test_state`, but keeps the global state in `storage_dir`.
+    fn create_test_state_in(
+        storage_dir: &Path,
+        test_pair: [TestPair; 2],
+    ) -> (ContractRuntime, Digest) {
         let contract_runtime = ContractRuntime::new(
             ProtocolVersion::default(),
-            temp_dir.path(),
+            storage_dir,
             &ContractRuntimeConfig::default(),
             WasmConfig::default(),
             SystemConfig::default(),
@@ -1101,6 +1260,28 @@ mod trie_chunking_tests {
         }
     }
 
+    #[test]
+    fn serves_tries_from_read_only_global_state() {
+        let temp_dir = tempdir().unwrap();
+        let root_hash = {
+            let (contract_runtime, root_hash) =
+                create_test_state_in(temp_dir.path(), create_test_pairs_with_large_data());
+            contract_runtime.engine_state().flush_environment().unwrap();
+            root_hash
+        };
+
+        let (chainspec, _) = <(Chainspec, ChainspecRawBytes)>::from_resources("local");
+        let contract_runtime = ContractRuntime::new_read_only(
+            temp_dir.path(),
+            &ContractRuntimeConfig::default(),
+            &chainspec,
+            &Registry::default(),
+        )
+        .unwrap();
+        let trie = read_trie(&contract_runtime, TrieOrChunkId(0, root_hash));
+        assert!(matches!(trie, ValueOrChunk::Value(_)));
+    }
+
     #[test]
     fn returns_trie_or_chunk() {
         let (contract_runtime, root_hash) = create_test_state(create_test_pairs_with_large_data());
@@ -9,7 +9,7 @@ const DEFAULT_MAX_QUERY_DEPTH: u64 = 5;
 const DEFAULT_MANUAL_SYNC_ENABLED: bool = true;
 
 /// Contract runtime configuration.
-#[derive(Clone, Copy, DataSize, Debug, Deserialize, Serialize)]
+#[derive(Clone, DataSize, Debug, Deserialize, Serialize)]
 // Disallow unknown fields to ensure config files and command-line overrides contain valid keys.
 #[serde(deny_unknown_fields)]
 pub struct Config {
@@ -31,6 +31,18 @@ pub struct Config {
     ///
     /// Defaults to `true`.
     pub enable_manual_sync: Option<bool>,
+    /// Key tags of the global state keys whose value history is recorded, e.g. `"Balance"` or
+    /// `"Bid"`.
+    ///
+    /// Defaults to none.
+    #[serde(default)]
+    pub key_history_tags: Vec<String>,
+    /// Prefixes of the formatted global state keys whose value history is recorded, e.g.
+    /// `"hash-"`.
+    ///
+    /// Defaults to none.
+    #[serde(default)]
+    pub key_history_prefixes: Vec<String>,
 }
 
 impl Config {
@@ -67,6 +79,8 @@ impl Default for Config {
             max_readers: Some(DEFAULT_MAX_READERS),
             max_query_depth: Some(DEFAULT_MAX_QUERY_DEPTH),
             enable_manual_sync: Some(DEFAULT_MANUAL_SYNC_ENABLED),
+            key_history_tags: Vec::new(),
+            key_history_prefixes: Vec::new(),
         }
     }
 }
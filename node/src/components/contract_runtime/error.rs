@@ -23,6 +23,9 @@ pub(crate) enum ConfigError {
     /// Error initializing metrics.
     #[error("failed to initialize metrics for contract runtime: {0}")]
     Prometheus(#[from] prometheus::Error),
+    /// Unknown key tag in the key history settings.
+    #[error("unknown key tag in key_history_tags: {0}")]
+    InvalidKeyTag(String),
 }
 
 /// An error during block execution.
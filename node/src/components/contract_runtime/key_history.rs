@@ -0,0 +1,163 @@
+//! Selection of the global state keys whose value history is recorded in storage.
+
+use std::collections::BTreeSet;
+
+use casper_execution_engine::{
+    core::engine_state::{self, EngineState},
+    shared::{execution_journal::ExecutionJournal, newtypes::CorrelationId, transform::Transform},
+    storage::global_state::{lmdb::LmdbGlobalState, StateProvider, StateReader},
+};
+use casper_hashing::Digest;
+use casper_types::{
+    bytesrepr::ToBytes, ExecutionResult, Key, KeyTag, Tagged, Transform as JsonTransform,
+};
+
+use super::{BlockAndExecutionResults, Config, ConfigError};
+use crate::types::{DeployHash, DeployHeader};
+
+/// Filter selecting the keys recorded in the key history index.
+#[derive(Debug, Default)]
+pub(super) struct KeyHistoryFilter {
+    tags: BTreeSet<KeyTag>,
+    prefixes: Vec<String>,
+}
+
+impl KeyHistoryFilter {
+    /// Creates the filter from the `key_history_tags` and `key_history_prefixes` settings.
+    pub(super) fn new(config: &Config) -> Result<Self, ConfigError> {
+        let tags = config
+            .key_history_tags
+            .iter()
+            .map(|name| parse_key_tag(name).ok_or_else(|| ConfigError::InvalidKeyTag(name.clone())))
+            .collect::<Result<_, _>>()?;
+        Ok(KeyHistoryFilter {
+            tags,
+            prefixes: config.key_history_prefixes.clone(),
+        })
+    }
+
+    /// Returns `true` if no keys are selected, i.e. the index is disabled.
+    pub(super) fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.prefixes.is_empty()
+    }
+
+    fn matches(&self, key: &Key) -> bool {
+        let tag: KeyTag = key.tag();
+        if self.tags.contains(&tag) {
+            return true;
+        }
+        if self.prefixes.is_empty() {
+            return false;
+        }
+        let formatted = key.to_formatted_string();
+        self.prefixes
+            .iter()
+            .any(|prefix| formatted.starts_with(prefix.as_str()))
+    }
+
+    /// Returns the selected keys written by the executed block, each with the hash of its value
+    /// after the block.
+    pub(super) fn collect(
+        &self,
+        engine_state: &EngineState<LmdbGlobalState>,
+        block_and_execution_results: &BlockAndExecutionResults,
+    ) -> Result<Vec<(Key, Option<Digest>)>, engine_state::Error> {
+        let keys = self.written_keys(
+            &block_and_execution_results.execution_results,
+            block_and_execution_results
+                .maybe_step_effect_and_upcoming_era_validators
+                .as_ref()
+                .map(|step_effect| &step_effect.step_execution_journal),
+        );
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let state_root_hash = *block_and_execution_results.block.header().state_root_hash();
+        value_hashes(engine_state, state_root_hash, keys)
+    }
+
+    /// Returns the selected keys written by a block's deploys or by its step.
+    ///
+    /// Reads, i.e. identity transforms, are not considered writes.
+    fn written_keys(
+        &self,
+        execution_results: &[(DeployHash, DeployHeader, ExecutionResult)],
+        step_execution_journal: Option<&ExecutionJournal>,
+    ) -> BTreeSet<Key> {
+        let mut keys = BTreeSet::new();
+        if self.is_empty() {
+            return keys;
+        }
+        for (_, _, execution_result) in execution_results {
+            let effect = match execution_result {
+                ExecutionResult::Failure { effect, .. }
+                | ExecutionResult::Success { effect, .. } => effect,
+            };
+            for entry in &effect.transforms {
+                if matches!(entry.transform, JsonTransform::Identity) {
+                    continue;
+                }
+                if let Ok(key) = Key::from_formatted_str(&entry.key) {
+                    if self.matches(&key) {
+                        keys.insert(key);
+                    }
+                }
+            }
+        }
+        for (key, transform) in step_execution_journal
+            .into_iter()
+            .flat_map(|journal| journal.iter())
+        {
+            if *transform != Transform::Identity && self.matches(key) {
+                keys.insert(*key);
+            }
+        }
+        keys
+    }
+}
+
+/// Returns the hash of the serialized value under each of the keys in the global state with the
+/// given root, or `None` if there is no value under a key.
+fn value_hashes(
+    engine_state: &EngineState<LmdbGlobalState>,
+    state_root_hash: Digest,
+    keys: BTreeSet<Key>,
+) -> Result<Vec<(Key, Option<Digest>)>, engine_state::Error> {
+    let correlation_id = CorrelationId::new();
+    let reader = engine_state
+        .get_state()
+        .checkout(state_root_hash)?
+        .ok_or(engine_state::Error::RootNotFound(state_root_hash))?;
+    keys.into_iter()
+        .map(|key| {
+            let maybe_value_hash = match reader.read(correlation_id, &key)? {
+                Some(value) => Some(Digest::hash(value.to_bytes()?)),
+                None => None,
+            };
+            Ok((key, maybe_value_hash))
+        })
+        .collect()
+}
+
+/// Parses the name of a key tag, as used in the config.
+fn parse_key_tag(name: &str) -> Option<KeyTag> {
+    let tag = match name {
+        "Account" => KeyTag::Account,
+        "Hash" => KeyTag::Hash,
+        "URef" => KeyTag::URef,
+        "Transfer" => KeyTag::Transfer,
+        "DeployInfo" => KeyTag::DeployInfo,
+        "EraInfo" => KeyTag::EraInfo,
+        "Balance" => KeyTag::Balance,
+        "Bid" => KeyTag::Bid,
+        "Withdraw" => KeyTag::Withdraw,
+        "Dictionary" => KeyTag::Dictionary,
+        "SystemContractRegistry" => KeyTag::SystemContractRegistry,
+        "EraSummary" => KeyTag::EraSummary,
+        "Unbond" => KeyTag::Unbond,
+        "ChainspecRegistry" => KeyTag::ChainspecRegistry,
+        "ChecksumRegistry" => KeyTag::ChecksumRegistry,
+        _ => return None,
+    };
+    Some(tag)
+}
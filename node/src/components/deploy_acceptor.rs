@@ -82,6 +82,10 @@ pub(crate) enum Error {
         /// The timestamp when the node validated the expiry timestamp.
         current_node_timestamp: Timestamp,
     },
+
+    /// The node is running in archive mode, serving existing data only.
+    #[error("deploys are not accepted by a node running in archive mode")]
+    ArchiveMode,
 }
 
 impl Error {
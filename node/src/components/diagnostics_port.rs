@@ -25,7 +25,10 @@ use crate::{
     effect::{
         announcements::ControlAnnouncement,
         diagnostics_port::DumpConsensusStateRequest,
-        requests::{NetworkInfoRequest, SetNodeStopRequest},
+        requests::{
+            ConsensusRequest, ContractRuntimeRequest, NetworkInfoRequest, SetNodeStopRequest,
+            StorageRequest,
+        },
         EffectBuilder, EffectExt, Effects,
     },
     reactor::main_reactor::MainEvent,
@@ -108,6 +111,9 @@ where
         + From<ControlAnnouncement>
         + From<NetworkInfoRequest>
         + From<SetNodeStopRequest>
+        + From<StorageRequest>
+        + From<ContractRuntimeRequest>
+        + From<ConsensusRequest>
         + Send,
 {
     type Event = Event;
@@ -162,6 +168,9 @@ where
         + From<ControlAnnouncement>
         + From<NetworkInfoRequest>
         + From<SetNodeStopRequest>
+        + From<StorageRequest>
+        + From<ContractRuntimeRequest>
+        + From<ConsensusRequest>
         + Send,
 {
     fn state(&self) -> &ComponentState {
@@ -186,6 +195,9 @@ where
         + From<ControlAnnouncement>
         + From<NetworkInfoRequest>
         + From<SetNodeStopRequest>
+        + From<StorageRequest>
+        + From<ContractRuntimeRequest>
+        + From<ConsensusRequest>
         + Send,
 {
     type Error = Error;
@@ -1,5 +1,6 @@
 use std::{
     fmt::{self, Display, Formatter},
+    path::PathBuf,
     str::FromStr,
 };
 
@@ -86,12 +87,18 @@ pub(super) enum Action {
     SetLogFilter { directive: String },
     /// Dump the state of the consensus component.
     ///
-    /// It is recommended to set the output format to `bincode` if the data is to be visualized
+    /// Set the output format to `json` if the data is to be visualized with `era-dump-viz`
     /// after.
     DumpConsensus {
         /// Era to dump. If omitted, dumps the latest era.
         era: Option<u64>,
     },
+    /// Show when recently finalized blocks were proposed, received, finalized, executed and
+    /// reached strict finality.
+    FinalityTimeline {
+        /// Height of the block to show. If omitted, shows all recently finalized blocks.
+        height: Option<u64>,
+    },
     /// Dump the event queues.
     DumpQueues,
     /// Get detailed networking insights.
@@ -111,6 +118,21 @@ pub(super) enum Action {
         #[structopt(short, long)]
         clear: bool,
     },
+    /// Take a consistent snapshot of the storage and global state.
+    ///
+    /// The snapshot is taken as of the latest complete block while the node keeps running. The
+    /// databases are written to the given directory, which must not exist yet or be empty, along
+    /// with a `manifest.json` recording the block hash, height and state root hash.
+    Snapshot {
+        /// Directory to write the snapshot to.
+        dir: PathBuf,
+    },
+    /// Rotate the node's network identity.
+    ///
+    /// The certificate and secret key are loaded again from the paths configured in
+    /// `[network.identity]`, or generated if none are configured. New connections use the new
+    /// identity, while existing ones are replaced during the configured drain period.
+    RotateIdentity,
     /// Close connection server-side.
     Quit,
 }
@@ -140,7 +162,21 @@ mod tests {
         let cmd = Command::from_line("dump-consensus 123").expect("command parsing failed");
         assert!(matches!(cmd.action, Action::DumpConsensus { era } if era == Some(123)));
 
+        let cmd = Command::from_line("finality-timeline").expect("command parsing failed");
+        assert!(matches!(
+            cmd.action,
+            Action::FinalityTimeline { height: None }
+        ));
+
         let cmd = Command::from_line("dump-queues").expect("command parsing failed");
         assert!(matches!(cmd.action, Action::DumpQueues));
+
+        let cmd = Command::from_line("snapshot /tmp/snapshot").expect("command parsing failed");
+        assert!(
+            matches!(cmd.action, Action::Snapshot { dir } if dir.to_str() == Some("/tmp/snapshot"))
+        );
+
+        let cmd = Command::from_line("rotate-identity").expect("command parsing failed");
+        assert!(matches!(cmd.action, Action::RotateIdentity));
     }
 }
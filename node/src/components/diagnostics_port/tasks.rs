@@ -3,7 +3,7 @@ use std::{
     fmt::{self, Debug, Display, Formatter},
     fs::{self, File},
     io,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use bincode::{
@@ -21,6 +21,8 @@ use tokio::{
 };
 use tracing::{debug, info, info_span, warn, Instrument};
 
+use casper_execution_engine::core::engine_state;
+use casper_hashing::Digest;
 use casper_types::EraId;
 use tracing_subscriber::{filter::ParseError, EnvFilter};
 
@@ -29,17 +31,24 @@ use super::{
     util::ShowUnixAddr,
 };
 use crate::{
-    components::consensus::EraDump,
+    components::{consensus::EraDump, storage::SnapshotError},
     effect::{
         announcements::{ControlAnnouncement, QueueDumpFormat},
         diagnostics_port::DumpConsensusStateRequest,
-        requests::{NetworkInfoRequest, SetNodeStopRequest},
+        requests::{
+            ConsensusRequest, ContractRuntimeRequest, NetworkInfoRequest, SetNodeStopRequest,
+            StorageRequest,
+        },
         EffectBuilder,
     },
     logging,
+    types::BlockHash,
     utils::{display_error, opt_display::OptDisplay},
 };
 
+/// Filename of the manifest written along with a snapshot.
+const SNAPSHOT_MANIFEST_FILENAME: &str = "manifest.json";
+
 /// Success or failure response.
 ///
 /// This response is sent back to clients after every operation (unless suppressed in quiet mode),
@@ -141,6 +150,50 @@ enum ObtainDumpError {
     ReopenTempFile(#[source] io::Error),
 }
 
+/// Description of a snapshot, written along with it.
+#[derive(Debug, Serialize)]
+struct SnapshotManifest {
+    /// Hash of the latest complete block contained in the snapshot.
+    block_hash: BlockHash,
+    /// Height of that block.
+    block_height: u64,
+    /// State root hash of that block.
+    state_root_hash: Digest,
+}
+
+impl Display for SnapshotManifest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "snapshot at block {} (height {}), state root hash {}",
+            self.block_hash, self.block_height, self.state_root_hash
+        )
+    }
+}
+
+/// Error taking a snapshot.
+#[derive(Debug, Error)]
+enum TakeSnapshotError {
+    /// The snapshot directory already exists and is not empty.
+    #[error("snapshot directory {} is not empty", .0.display())]
+    DirectoryNotEmpty(PathBuf),
+    /// Error trying to create the snapshot directory.
+    #[error("could not create snapshot directory")]
+    CreateDirectory(#[source] io::Error),
+    /// Error copying the storage databases.
+    #[error("could not copy storage")]
+    Storage(#[source] SnapshotError),
+    /// Error copying the global state.
+    #[error("could not copy global state")]
+    GlobalState(#[source] engine_state::Error),
+    /// Error trying to serialize the manifest.
+    #[error("could not serialize manifest")]
+    SerializeManifest(#[source] serde_json::Error),
+    /// Error trying to write the manifest.
+    #[error("could not write manifest")]
+    WriteManifest(#[source] io::Error),
+}
+
 impl Session {
     /// Creates a serializer for an `EraDump`.
     fn create_era_dump_serializer(&self) -> fn(&EraDump<'_>) -> Result<Vec<u8>, Cow<'static, str>> {
@@ -202,6 +255,9 @@ impl Session {
             + From<ControlAnnouncement>
             + From<NetworkInfoRequest>
             + From<SetNodeStopRequest>
+            + From<StorageRequest>
+            + From<ContractRuntimeRequest>
+            + From<ConsensusRequest>
             + Send,
     {
         debug!(%line, "line received");
@@ -290,6 +346,13 @@ impl Session {
                             }
                         }
                     }
+                    Action::FinalityTimeline { height } => {
+                        let timelines =
+                            effect_builder.get_consensus_finality_timeline(height).await;
+                        self.send_outcome(writer, &Outcome::success("collected block timelines"))
+                            .await?;
+                        self.send_to_client(writer, &timelines).await?;
+                    }
                     Action::DumpQueues => {
                         // Note: The preferable approach would be to use a tempfile instead of a
                         //       named one in a temporary directory, and return it through the
@@ -316,12 +379,16 @@ impl Session {
                             }
                         };
                     }
-                    Action::NetInfo => {
-                        self.send_outcome(writer, &Outcome::success("collecting insights"))
-                            .await?;
-                        let insights = effect_builder.get_network_insights().await;
-                        self.send_to_client(writer, &insights).await?;
-                    }
+                    Action::NetInfo => match effect_builder.get_network_insights().await {
+                        Ok(insights) => {
+                            self.send_outcome(writer, &Outcome::success("network insights"))
+                                .await?;
+                            self.send_to_client(writer, &insights).await?;
+                        }
+                        Err(err) => {
+                            self.send_outcome(writer, &Outcome::failed(err)).await?;
+                        }
+                    },
                     Action::Stop { at, clear } => {
                         let (msg, stop_at) = if clear {
                             ("clearing stopping point", None)
@@ -336,6 +403,37 @@ impl Session {
                         )
                         .await?;
                     }
+                    Action::Snapshot { ref dir } => {
+                        match take_snapshot(effect_builder, dir).await {
+                            Ok(manifest) => {
+                                self.send_outcome(writer, &Outcome::success("snapshot taken"))
+                                    .await?;
+                                self.send_to_client(writer, &manifest).await?;
+                            }
+                            Err(err) => {
+                                self.send_outcome(
+                                    writer,
+                                    &Outcome::failed(format!(
+                                        "failed to take snapshot: {}",
+                                        display_error(&err)
+                                    )),
+                                )
+                                .await?;
+                            }
+                        }
+                    }
+                    Action::RotateIdentity => {
+                        match effect_builder.rotate_network_identity().await {
+                            Ok(node_id) => {
+                                self.send_outcome(writer, &Outcome::success("identity rotated"))
+                                    .await?;
+                                self.send_to_client(writer, &node_id).await?;
+                            }
+                            Err(err) => {
+                                self.send_outcome(writer, &Outcome::failed(err)).await?;
+                            }
+                        }
+                    }
                     Action::Quit => {
                         self.send_outcome(writer, &Outcome::success("goodbye!"))
                             .await?;
@@ -441,6 +539,54 @@ impl Session {
     }
 }
 
+/// Takes a snapshot of the storage and global state in `dir`.
+///
+/// The storage is copied first: every block it marks as complete has had its global state written
+/// beforehand, so the global state copied afterwards contains the state of all of them.
+async fn take_snapshot<REv>(
+    effect_builder: EffectBuilder<REv>,
+    dir: &Path,
+) -> Result<SnapshotManifest, TakeSnapshotError>
+where
+    REv: From<StorageRequest> + From<ContractRuntimeRequest> + Send,
+{
+    match fs::read_dir(dir) {
+        Ok(mut entries) => {
+            if entries.next().is_some() {
+                return Err(TakeSnapshotError::DirectoryNotEmpty(dir.to_owned()));
+            }
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            fs::create_dir_all(dir).map_err(TakeSnapshotError::CreateDirectory)?;
+        }
+        Err(err) => return Err(TakeSnapshotError::CreateDirectory(err)),
+    }
+
+    let block_header = effect_builder
+        .take_storage_snapshot(dir.to_owned())
+        .await
+        .map_err(TakeSnapshotError::Storage)?;
+    info!(block_hash = %block_header.block_hash(), "storage snapshot taken");
+
+    let tries = effect_builder
+        .take_global_state_snapshot(dir.to_owned())
+        .await
+        .map_err(TakeSnapshotError::GlobalState)?;
+    info!(tries, "global state snapshot taken");
+
+    let manifest = SnapshotManifest {
+        block_hash: block_header.block_hash(),
+        block_height: block_header.height(),
+        state_root_hash: *block_header.state_root_hash(),
+    };
+    let serialized =
+        serde_json::to_vec_pretty(&manifest).map_err(TakeSnapshotError::SerializeManifest)?;
+    fs::write(dir.join(SNAPSHOT_MANIFEST_FILENAME), serialized)
+        .map_err(TakeSnapshotError::WriteManifest)?;
+
+    Ok(manifest)
+}
+
 /// Error while trying to set the global log filter.
 #[derive(Debug, Error)]
 enum SetLogFilterError {
@@ -478,6 +624,9 @@ where
         + From<ControlAnnouncement>
         + From<NetworkInfoRequest>
         + From<SetNodeStopRequest>
+        + From<StorageRequest>
+        + From<ContractRuntimeRequest>
+        + From<ConsensusRequest>
         + Send,
 {
     debug!("accepted new connection on diagnostics port");
@@ -522,6 +671,9 @@ pub(super) async fn server<REv>(
         + From<ControlAnnouncement>
         + From<NetworkInfoRequest>
         + From<SetNodeStopRequest>
+        + From<StorageRequest>
+        + From<ContractRuntimeRequest>
+        + From<ConsensusRequest>
         + Send,
 {
     let handling_shutdown_receiver = shutdown_receiver.clone();
@@ -601,7 +753,10 @@ mod tests {
         effect::{
             announcements::ControlAnnouncement,
             diagnostics_port::DumpConsensusStateRequest,
-            requests::{NetworkInfoRequest, SetNodeStopRequest},
+            requests::{
+                ConsensusRequest, ContractRuntimeRequest, NetworkInfoRequest, SetNodeStopRequest,
+                StorageRequest,
+            },
             EffectBuilder, EffectExt, Effects,
         },
         reactor::{
@@ -661,6 +816,12 @@ mod tests {
         NetworkInfoRequest(NetworkInfoRequest),
         #[from]
         SetNodeStopRequest(SetNodeStopRequest),
+        #[from]
+        StorageRequest(StorageRequest),
+        #[from]
+        ContractRuntimeRequest(ContractRuntimeRequest),
+        #[from]
+        ConsensusRequest(ConsensusRequest),
     }
 
     impl Display for Event {
@@ -707,7 +868,10 @@ mod tests {
                 Event::DumpConsensusStateRequest(_)
                 | Event::SetNodeStopRequest(_)
                 | Event::ControlAnnouncement(_)
-                | Event::NetworkInfoRequest(_) => {
+                | Event::NetworkInfoRequest(_)
+                | Event::StorageRequest(_)
+                | Event::ContractRuntimeRequest(_)
+                | Event::ConsensusRequest(_) => {
                     panic!("unexpected: {}", event)
                 }
             }
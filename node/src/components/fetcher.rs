@@ -18,7 +18,7 @@ use prometheus::Registry;
 use tracing::trace;
 
 use crate::{
-    components::Component,
+    components::{network::reputation::PeerObservation, Component},
     effect::{
         announcements::{
             FetchedNewBlockAnnouncement, FetchedNewFinalitySignatureAnnouncement,
@@ -139,14 +139,18 @@ where
             Event::GotInvalidRemotely { .. } => Effects::new(),
             Event::AbsentRemotely { id, peer } => {
                 trace!(TAG=%T::TAG, %id, %peer, "item absent on the remote node");
-                self.signal(
+                let mut effects = effect_builder
+                    .announce_peer_behavior_observed(peer, PeerObservation::ItemAbsent)
+                    .ignore();
+                effects.extend(self.signal(
                     id.clone(),
                     Err(Error::Absent {
                         id: Box::new(id),
                         peer,
                     }),
                     peer,
-                )
+                ));
+                effects
             }
             Event::RejectedRemotely { id, peer } => {
                 trace!(TAG=%T::TAG, %id, %peer, "peer rejected fetch request");
@@ -159,14 +163,31 @@ where
                     peer,
                 )
             }
-            Event::TimeoutPeer { id, peer } => self.signal(
-                id.clone(),
-                Err(Error::TimedOut {
-                    id: Box::new(id),
+            Event::TimeoutPeer { id, peer } => {
+                // The timeout only counts against the peer if the item is still awaited from it.
+                let is_awaited = self
+                    .item_handles()
+                    .get(&id)
+                    .and_then(|item_handles| item_handles.get(&peer))
+                    .map_or(false, |item_handle| !item_handle.has_no_responders());
+                let mut effects = Effects::new();
+                if is_awaited {
+                    effects.extend(
+                        effect_builder
+                            .announce_peer_behavior_observed(peer, PeerObservation::FetchTimedOut)
+                            .ignore(),
+                    );
+                }
+                effects.extend(self.signal(
+                    id.clone(),
+                    Err(Error::TimedOut {
+                        id: Box::new(id),
+                        peer,
+                    }),
                     peer,
-                }),
-                peer,
-            ),
+                ));
+                effects
+            }
             Event::PutToStorage { item, peer } => {
                 let mut effects =
                     Self::announce_fetched_new_item(effect_builder, (*item).clone(), peer).ignore();
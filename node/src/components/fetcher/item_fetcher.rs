@@ -9,7 +9,10 @@ use tracing::{debug, error, trace};
 
 use super::{Error, Event, FetchResponder, FetchedData, ItemHandle, Metrics};
 use crate::{
-    components::{fetcher::FetchItem, network::blocklist::BlocklistJustification},
+    components::{
+        fetcher::FetchItem,
+        network::{blocklist::BlocklistJustification, reputation::PeerObservation},
+    },
     effect::{
         announcements::{
             FetchedNewBlockAnnouncement, FetchedNewFinalitySignatureAnnouncement,
@@ -173,12 +176,18 @@ pub(super) trait ItemFetcher<T: FetchItem + 'static> {
                 )
                 .ignore()
         } else {
+            let mut effects = effect_builder
+                .announce_peer_behavior_observed(peer, PeerObservation::FetchSucceeded)
+                .ignore();
             match Self::put_to_storage(effect_builder, *item.clone()) {
-                StoringState::WontStore(item) => self.signal(item.fetch_id(), Ok(item), peer),
+                StoringState::WontStore(item) => {
+                    effects.extend(self.signal(item.fetch_id(), Ok(item), peer))
+                }
                 StoringState::Enqueued(store_future) => {
-                    store_future.event(move |_| Event::PutToStorage { item, peer })
+                    effects.extend(store_future.event(move |_| Event::PutToStorage { item, peer }))
                 }
             }
+            effects
         }
     }
 
@@ -250,6 +250,10 @@ impl ReactorTrait for Reactor {
                 self.storage
                     .handle_event(effect_builder, rng, request.into()),
             ),
+            // Reputation changes are of no interest to these tests.
+            Event::BlocklistAnnouncement(PeerBehaviorAnnouncement::BehaviorObserved { .. }) => {
+                Effects::new()
+            }
             Event::TrieDemand(_)
             | Event::ContractRuntimeRequest(_)
             | Event::BlockAccumulatorRequest(_)
@@ -8,6 +8,7 @@ mod item_provider;
 mod message;
 mod metrics;
 mod provider_impls;
+mod reconciliation;
 mod tests;
 
 use std::{
@@ -21,9 +22,9 @@ use prometheus::Registry;
 use tracing::{debug, error, trace, warn};
 
 use crate::{
-    components::Component,
+    components::{network::reputation::PeerObservation, Component},
     effect::{
-        announcements::GossiperAnnouncement,
+        announcements::{GossiperAnnouncement, PeerBehaviorAnnouncement},
         incoming::GossiperIncoming,
         requests::{BeginGossipRequest, NetworkRequest, StorageRequest},
         EffectBuilder, EffectExt, Effects, GossipTarget,
@@ -33,12 +34,14 @@ use crate::{
     NodeRng,
 };
 pub(crate) use config::Config;
+use config::ReconciliationConfig;
 pub(crate) use event::Event;
 pub(crate) use gossip_item::{GossipItem, LargeGossipItem, SmallGossipItem};
-use gossip_table::{GossipAction, GossipTable};
+use gossip_table::{GossipAction, GossipTable, ShouldGossip};
 use item_provider::ItemProvider;
 pub(crate) use message::Message;
 use metrics::Metrics;
+use reconciliation::{BloomFilter, Reconciliation, Round};
 
 /// The component which gossips to peers and handles incoming gossip messages from peers.
 #[allow(clippy::type_complexity)]
@@ -52,6 +55,7 @@ where
     validate_and_store_timeout: Duration,
     name: &'static str,
     metrics: Metrics,
+    reconciliation: Option<Reconciliation<T::Id>>,
 }
 
 impl<const ID_IS_COMPLETE_ITEM: bool, T: GossipItem + 'static> Gossiper<ID_IS_COMPLETE_ITEM, T> {
@@ -71,9 +75,21 @@ impl<const ID_IS_COMPLETE_ITEM: bool, T: GossipItem + 'static> Gossiper<ID_IS_CO
             validate_and_store_timeout: config.validate_and_store_timeout().into(),
             name,
             metrics: Metrics::new(name, registry)?,
+            reconciliation: None,
         })
     }
 
+    /// Enables set reconciliation of recently received items, if set in the given config.
+    ///
+    /// With reconciliation enabled, items received from peers are not pushed onwards, but offered
+    /// to peers whose reconciliation filter lacks them.
+    pub(crate) fn with_reconciliation(mut self, config: ReconciliationConfig) -> Self {
+        self.reconciliation = config
+            .enabled
+            .then(|| Reconciliation::new(config, self.table.finished_entry_duration()));
+        self
+    }
+
     /// This could be the first time we've encountered this item in the gossiper (e.g. the
     /// `Network` component requesting that we gossip an address, or the `DeployAcceptor` having
     /// accepted a deploy which we received from a client), or it could be the result of this
@@ -91,29 +107,59 @@ impl<const ID_IS_COMPLETE_ITEM: bool, T: GossipItem + 'static> Gossiper<ID_IS_CO
         REv: From<NetworkRequest<Message<T>>> + From<GossiperAnnouncement<T>> + Send,
     {
         debug!(item=%item_id, %source, "received new gossip item");
+        let mut effects = self.record_for_reconciliation(effect_builder, &item_id);
         match self
             .table
             .new_complete_data(&item_id, source.node_id(), target)
         {
             GossipAction::ShouldGossip(should_gossip) => {
                 self.metrics.items_received.inc();
-                Self::gossip(
-                    effect_builder,
-                    item_id,
-                    should_gossip.target,
-                    should_gossip.count,
-                    should_gossip.exclude_peers,
-                )
+                let relayed = source.node_id().is_some();
+                effects.extend(self.push(effect_builder, item_id, should_gossip, relayed));
             }
-            GossipAction::Noop => Effects::new(),
+            GossipAction::Noop => (),
             GossipAction::AnnounceFinished => {
-                effect_builder.announce_finished_gossiping(item_id).ignore()
+                effects.extend(effect_builder.announce_finished_gossiping(item_id).ignore())
             }
             GossipAction::GetRemainder { .. } | GossipAction::AwaitingRemainder => {
                 error!("can't be waiting for remainder since we hold the complete data");
-                Effects::new()
             }
         }
+        effects
+    }
+
+    /// Gossips the given item ID as instructed by the gossip table.
+    ///
+    /// If reconciliation is enabled, items relayed from peers are not pushed onwards, and their
+    /// gossiping is finished instead, since peers lacking them will learn of them by reconciling.
+    /// This does not apply while more items were received recently than a filter can hold.
+    /// Items we pushed are not pushed to peers whose reconciliation filter claims they hold them.
+    fn push<REv>(
+        &mut self,
+        effect_builder: EffectBuilder<REv>,
+        item_id: T::Id,
+        should_gossip: ShouldGossip,
+        relayed: bool,
+    ) -> Effects<Event<T>>
+    where
+        REv: From<NetworkRequest<Message<T>>> + From<GossiperAnnouncement<T>> + Send,
+    {
+        let ShouldGossip {
+            count,
+            mut exclude_peers,
+            target,
+            ..
+        } = should_gossip;
+        if let Some(reconciliation) = self.reconciliation.as_ref() {
+            if relayed && !reconciliation.is_saturated() {
+                if self.table.reduce_in_flight_count(&item_id, count) {
+                    return effect_builder.announce_finished_gossiping(item_id).ignore();
+                }
+                return Effects::new();
+            }
+            exclude_peers.extend(reconciliation.likely_holders(&item_id));
+        }
+        Self::gossip(effect_builder, item_id, target, count, exclude_peers)
     }
 
     /// Gossips the given item ID to `count` random peers excluding the indicated ones.
@@ -273,23 +319,31 @@ impl<const ID_IS_COMPLETE_ITEM: bool, T: GossipItem + 'static> Gossiper<ID_IS_CO
         action: GossipAction,
     ) -> Effects<Event<T>>
     where
-        REv: From<NetworkRequest<Message<T>>> + From<GossiperAnnouncement<T>> + Send,
+        REv: From<NetworkRequest<Message<T>>>
+            + From<GossiperAnnouncement<T>>
+            + From<PeerBehaviorAnnouncement>
+            + Send,
     {
         let mut effects = match action {
             GossipAction::ShouldGossip(should_gossip) => {
                 debug!(item=%item_id, %sender, %should_gossip, "received gossip request");
                 self.metrics.items_received.inc();
+                let is_already_held = should_gossip.is_already_held;
                 // Gossip the item ID.
-                let mut effects = Self::gossip(
-                    effect_builder,
-                    item_id.clone(),
-                    should_gossip.target,
-                    should_gossip.count,
-                    should_gossip.exclude_peers,
-                );
+                let relayed = true;
+                let mut effects =
+                    self.push(effect_builder, item_id.clone(), should_gossip, relayed);
+
+                if !is_already_held {
+                    effects.extend(
+                        effect_builder
+                            .announce_peer_behavior_observed(sender, PeerObservation::UsefulGossip)
+                            .ignore(),
+                    );
+                }
 
                 // If this is a new complete item to us, announce it.
-                if ID_IS_COMPLETE_ITEM && !should_gossip.is_already_held {
+                if ID_IS_COMPLETE_ITEM && !is_already_held {
                     debug!(item=%item_id, "announcing new complete gossip item received");
                     effects.extend(
                         effect_builder
@@ -301,7 +355,7 @@ impl<const ID_IS_COMPLETE_ITEM: bool, T: GossipItem + 'static> Gossiper<ID_IS_CO
                 // Send a response to the sender indicating whether we already hold the item.
                 let reply = Message::GossipResponse {
                     item_id: item_id.clone(),
-                    is_already_held: should_gossip.is_already_held,
+                    is_already_held,
                 };
                 effects.extend(effect_builder.send_message(sender, reply).ignore());
                 effects
@@ -316,6 +370,11 @@ impl<const ID_IS_COMPLETE_ITEM: bool, T: GossipItem + 'static> Gossiper<ID_IS_CO
                     is_already_held: false,
                 };
                 let mut effects = effect_builder.send_message(sender, reply).ignore();
+                effects.extend(
+                    effect_builder
+                        .announce_peer_behavior_observed(sender, PeerObservation::UsefulGossip)
+                        .ignore(),
+                );
                 let item_id_clone = item_id.clone();
                 effects.extend(
                     effect_builder
@@ -375,6 +434,20 @@ impl<const ID_IS_COMPLETE_ITEM: bool, T: GossipItem + 'static> Gossiper<ID_IS_CO
         Self: ItemProvider<T>,
     {
         let mut effects: Effects<_> = Effects::new();
+        let was_offered = self
+            .reconciliation
+            .as_mut()
+            .map_or(false, |reconciliation| {
+                reconciliation.take_offer(&item_id, sender)
+            });
+        if is_already_held {
+            if was_offered {
+                self.metrics.reconciliation_items_redundant.inc();
+            } else {
+                self.metrics.gossip_responses_redundant.inc();
+            }
+        }
+
         if !self.table.has_entry(&item_id) {
             debug!(
                 item = %item_id,
@@ -384,6 +457,23 @@ impl<const ID_IS_COMPLETE_ITEM: bool, T: GossipItem + 'static> Gossiper<ID_IS_CO
             return effects;
         }
 
+        // Offers made while reconciling are not part of the push accounted for by the table, so
+        // only the item itself needs to be sent, if the peer lacks it.
+        if was_offered {
+            if !is_already_held && !ID_IS_COMPLETE_ITEM {
+                effects.extend(
+                    Self::get_from_storage(effect_builder, item_id.clone()).event(
+                        move |maybe_item| Event::GetFromStorageResult {
+                            item_id,
+                            requester: sender,
+                            maybe_item,
+                        },
+                    ),
+                );
+            }
+            return effects;
+        }
+
         let action = if is_already_held {
             self.table.already_infected(&item_id, sender)
         } else {
@@ -540,6 +630,115 @@ impl<const ID_IS_COMPLETE_ITEM: bool, T: GossipItem + 'static> Gossiper<ID_IS_CO
         Effects::new()
     }
 
+    /// Records an item we hold for reconciliation, scheduling a reconciliation round if none is.
+    fn record_for_reconciliation<REv>(
+        &mut self,
+        effect_builder: EffectBuilder<REv>,
+        item_id: &T::Id,
+    ) -> Effects<Event<T>>
+    where
+        REv: Send,
+    {
+        let reconciliation = match self.reconciliation.as_mut() {
+            Some(reconciliation) => reconciliation,
+            None => return Effects::new(),
+        };
+        if !reconciliation.record(item_id.clone()) {
+            return Effects::new();
+        }
+        effect_builder
+            .set_timeout(reconciliation.interval())
+            .event(|_| Event::Reconcile)
+    }
+
+    /// Starts a reconciliation round, sending the filter of recently received items to random
+    /// peers.
+    fn reconcile<REv>(&mut self, effect_builder: EffectBuilder<REv>) -> Effects<Event<T>>
+    where
+        REv: From<NetworkRequest<Message<T>>> + Send,
+    {
+        let reconciliation = match self.reconciliation.as_mut() {
+            Some(reconciliation) => reconciliation,
+            None => {
+                error!("should not start reconciliation round while reconciliation is disabled");
+                return Effects::new();
+            }
+        };
+        let filter = match reconciliation.start_round() {
+            Round::Filter(filter) => filter,
+            Round::Saturated => {
+                // Relayed items are pushed onwards in the meantime.
+                debug!("too many recently received items to reconcile, skipping round");
+                return effect_builder
+                    .set_timeout(reconciliation.interval())
+                    .event(|_| Event::Reconcile);
+            }
+            Round::Idle => return Effects::new(),
+        };
+        self.metrics.reconciliation_rounds.inc();
+
+        let mut effects = effect_builder
+            .gossip_message(
+                Message::Reconcile(filter),
+                GossipTarget::All,
+                reconciliation.fanout(),
+                HashSet::new(),
+            )
+            .ignore();
+        effects.extend(
+            effect_builder
+                .set_timeout(reconciliation.interval())
+                .event(|_| Event::Reconcile),
+        );
+        effects
+    }
+
+    /// Handles a filter of recently received items from a peer, offering it the items we recently
+    /// received which are missing from the filter, up to a limit per filter.
+    fn handle_reconcile<REv>(
+        &mut self,
+        effect_builder: EffectBuilder<REv>,
+        filter: BloomFilter,
+        sender: NodeId,
+    ) -> Effects<Event<T>>
+    where
+        REv: From<NetworkRequest<Message<T>>> + Send,
+    {
+        let reconciliation = match self.reconciliation.as_mut() {
+            Some(reconciliation) => reconciliation,
+            None => {
+                debug!(%sender, "ignoring reconciliation filter since reconciliation is disabled");
+                return Effects::new();
+            }
+        };
+        if !filter.is_valid() {
+            debug!(%sender, "ignoring invalid reconciliation filter");
+            return Effects::new();
+        }
+
+        let offered = match reconciliation.handle_filter(sender, filter) {
+            Some(offered) => offered,
+            None => {
+                debug!(%sender, "ignoring reconciliation filter sent too soon after the last one");
+                return Effects::new();
+            }
+        };
+
+        let mut effects = Effects::new();
+        for item_id in offered {
+            if !self.table.has_entry(&item_id) {
+                continue;
+            }
+            self.metrics.reconciliation_items_offered.inc();
+            effects.extend(
+                effect_builder
+                    .send_message(sender, Message::Gossip(item_id))
+                    .ignore(),
+            );
+        }
+        effects
+    }
+
     /// Updates the gossiper metrics from the state of the gossip table.
     fn update_gossip_table_metrics(&self) {
         self.metrics
@@ -558,6 +757,7 @@ where
     REv: From<NetworkRequest<Message<T>>>
         + From<StorageRequest>
         + From<GossiperAnnouncement<T>>
+        + From<PeerBehaviorAnnouncement>
         + Send,
     Self: ItemProvider<T>,
 {
@@ -617,6 +817,7 @@ where
                 Message::Item(item) => {
                     self.handle_item_received_from_peer(effect_builder, item, sender)
                 }
+                Message::Reconcile(filter) => self.handle_reconcile(effect_builder, filter, sender),
             },
             Event::CheckItemReceivedTimeout { item_id } => {
                 self.check_item_received_timeout(effect_builder, item_id)
@@ -643,6 +844,7 @@ where
                 Some(item) => Self::got_from_storage(effect_builder, item, requester),
                 None => self.failed_to_get_from_storage(effect_builder, item_id),
             },
+            Event::Reconcile => self.reconcile(effect_builder),
         };
         self.update_gossip_table_metrics();
         effects
@@ -660,6 +862,7 @@ where
     REv: From<NetworkRequest<Message<T>>>
         + From<StorageRequest>
         + From<GossiperAnnouncement<T>>
+        + From<PeerBehaviorAnnouncement>
         + Send,
     Self: ItemProvider<T>,
 {
@@ -719,6 +922,7 @@ where
                     debug!(%item_id, %sender, "unexpected get response for small item");
                     Effects::new()
                 }
+                Message::Reconcile(filter) => self.handle_reconcile(effect_builder, filter, sender),
             },
             Event::CheckItemReceivedTimeout { item_id } => {
                 error!(%item_id, "should not timeout item-received for small item");
@@ -739,6 +943,7 @@ where
                 );
                 Effects::new()
             }
+            Event::Reconcile => self.reconcile(effect_builder),
         };
         self.update_gossip_table_metrics();
         effects
@@ -762,6 +967,7 @@ impl<const ID_IS_COMPLETE_ITEM: bool, T: GossipItem + 'static> Debug
                 "validate_and_store_timeout",
                 &self.validate_and_store_timeout,
             )
+            .field("reconciliation", &self.reconciliation)
             .finish()
     }
 }
@@ -782,6 +988,7 @@ impl<const ID_IS_COMPLETE_ITEM: bool, T: GossipItem + 'static> DataSize
             validate_and_store_timeout,
             name,
             metrics: _,
+            reconciliation,
         } = self;
 
         table.estimate_heap_size()
@@ -789,5 +996,6 @@ impl<const ID_IS_COMPLETE_ITEM: bool, T: GossipItem + 'static> DataSize
             + get_from_peer_timeout.estimate_heap_size()
             + validate_and_store_timeout.estimate_heap_size()
             + name.estimate_heap_size()
+            + reconciliation.estimate_heap_size()
     }
 }
@@ -19,6 +19,10 @@ pub(super) const DEFAULT_FINISHED_ENTRY_DURATION: &str = "60sec";
 const DEFAULT_GOSSIP_REQUEST_TIMEOUT: &str = "10sec";
 const DEFAULT_GET_REMAINDER_TIMEOUT: &str = "60sec";
 const DEFAULT_VALIDATE_AND_STORE_TIMEOUT: &str = "60sec";
+const DEFAULT_RECONCILIATION_INTERVAL: &str = "5sec";
+const DEFAULT_RECONCILIATION_FANOUT: u8 = 3;
+const DEFAULT_RECONCILIATION_WINDOW: &str = "60sec";
+const DEFAULT_RECONCILIATION_BITS_PER_ITEM: u8 = 10;
 #[cfg(test)]
 const SMALL_TIMEOUTS_FINISHED_ENTRY_DURATION: &str = "2sec";
 #[cfg(test)]
@@ -56,6 +60,9 @@ pub struct Config {
     /// The timeout duration for a newly-received, gossiped item to be validated and stored by
     /// another component before the gossiper abandons waiting to gossip the item onwards.
     pub validate_and_store_timeout: TimeDiff,
+    /// Set reconciliation of deploys, as an alternative to pushing every received deploy onwards.
+    #[serde(default)]
+    pub deploy_reconciliation: ReconciliationConfig,
 }
 
 impl Config {
@@ -78,6 +85,7 @@ impl Config {
             gossip_request_timeout,
             get_remainder_timeout,
             validate_and_store_timeout,
+            deploy_reconciliation: ReconciliationConfig::default(),
         })
     }
 
@@ -121,6 +129,10 @@ impl Config {
     pub(crate) fn validate_and_store_timeout(&self) -> TimeDiff {
         self.validate_and_store_timeout
     }
+
+    pub(crate) fn deploy_reconciliation(&self) -> ReconciliationConfig {
+        self.deploy_reconciliation
+    }
 }
 
 impl Default for Config {
@@ -133,6 +145,43 @@ impl Default for Config {
             get_remainder_timeout: TimeDiff::from_str(DEFAULT_GET_REMAINDER_TIMEOUT).unwrap(),
             validate_and_store_timeout: TimeDiff::from_str(DEFAULT_VALIDATE_AND_STORE_TIMEOUT)
                 .unwrap(),
+            deploy_reconciliation: ReconciliationConfig::default(),
+        }
+    }
+}
+
+/// Configuration options for the set reconciliation of recently received items.
+///
+/// With reconciliation enabled, items received from peers are not pushed onwards.  Instead, a
+/// bloom filter of all recently received items is periodically sent to a few random peers, which
+/// offer back the items missing from it.
+#[derive(Copy, Clone, DataSize, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReconciliationConfig {
+    /// Whether to reconcile recently received items with peers instead of pushing them onwards.
+    pub enabled: bool,
+    /// The interval between two reconciliation rounds.
+    pub interval: TimeDiff,
+    /// The number of random peers to send our filter to in each round.
+    pub fanout: u8,
+    /// The duration for which a received item is included in our filter.
+    ///
+    /// Capped at `finished_entry_duration`, since items can only be offered while in the gossip
+    /// table.
+    pub window: TimeDiff,
+    /// The number of filter bits per item.  Higher values lower the rate of false positives, i.e.
+    /// items not offered to a peer although it lacks them, at the cost of larger filters.
+    pub bits_per_item: u8,
+}
+
+impl Default for ReconciliationConfig {
+    fn default() -> Self {
+        ReconciliationConfig {
+            enabled: false,
+            interval: TimeDiff::from_str(DEFAULT_RECONCILIATION_INTERVAL).unwrap(),
+            fanout: DEFAULT_RECONCILIATION_FANOUT,
+            window: TimeDiff::from_str(DEFAULT_RECONCILIATION_WINDOW).unwrap(),
+            bits_per_item: DEFAULT_RECONCILIATION_BITS_PER_ITEM,
         }
     }
 }
@@ -172,6 +221,7 @@ mod tests {
             get_remainder_timeout: TimeDiff::from_str(DEFAULT_GET_REMAINDER_TIMEOUT).unwrap(),
             validate_and_store_timeout: TimeDiff::from_str(DEFAULT_VALIDATE_AND_STORE_TIMEOUT)
                 .unwrap(),
+            deploy_reconciliation: ReconciliationConfig::default(),
         };
 
         // Parsing should fail.
@@ -56,6 +56,9 @@ pub(crate) enum Event<T: GossipItem> {
         requester: NodeId,
         maybe_item: Option<Box<T>>,
     },
+    /// The interval between two reconciliation rounds has elapsed and we should send our filter of
+    /// recently received items to peers.
+    Reconcile,
 }
 
 impl<T: GossipItem> Display for Event<T> {
@@ -119,6 +122,7 @@ impl<T: GossipItem> Display for Event<T> {
                     write!(formatter, "failed to get {} from storage", item_id)
                 }
             }
+            Event::Reconcile => write!(formatter, "reconcile recently received items"),
         }
     }
 }
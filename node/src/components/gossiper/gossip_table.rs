@@ -210,6 +210,11 @@ impl<T> GossipTable<T> {
     pub(super) fn items_finished(&self) -> usize {
         self.finished.len()
     }
+
+    /// The duration for which finished entries are kept.
+    pub(super) fn finished_entry_duration(&self) -> Duration {
+        self.finished_entry_duration
+    }
 }
 
 impl<T: Clone + Eq + Hash + Display> GossipTable<T> {
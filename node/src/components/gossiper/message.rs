@@ -6,7 +6,7 @@ use std::{
 use serde::{Deserialize, Serialize};
 use strum::EnumDiscriminants;
 
-use super::GossipItem;
+use super::{reconciliation::BloomFilter, GossipItem};
 
 #[derive(Clone, Debug, Deserialize, Serialize, EnumDiscriminants)]
 #[strum_discriminants(derive(strum::EnumIter))]
@@ -26,6 +26,9 @@ pub(crate) enum Message<T: GossipItem> {
     // Response to either a `GossipResponse` with `is_already_held` set to `false` or to a
     // `GetItem` message. Contains the actual item requested.
     Item(Box<T>),
+    /// Bloom filter of the IDs of items recently received by the sender.  The recipient should
+    /// send a `Gossip` message for each of its recently received items missing from the filter.
+    Reconcile(BloomFilter),
 }
 
 impl<T: GossipItem> Display for Message<T> {
@@ -42,6 +45,7 @@ impl<T: GossipItem> Display for Message<T> {
             ),
             Message::GetItem(item_id) => write!(formatter, "gossip-get-item({})", item_id),
             Message::Item(item) => write!(formatter, "gossip-item({})", item.gossip_id()),
+            Message::Reconcile(_) => write!(formatter, "gossip-reconcile"),
         }
     }
 }
@@ -76,6 +80,9 @@ mod specimen_support {
                     MessageDiscriminants::Item => {
                         Message::Item(LargestSpecimen::largest_specimen(estimator, cache))
                     }
+                    MessageDiscriminants::Reconcile => {
+                        Message::Reconcile(LargestSpecimen::largest_specimen(estimator, cache))
+                    }
                 },
             )
         }
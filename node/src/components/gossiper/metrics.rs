@@ -15,6 +15,14 @@ pub(super) struct Metrics {
     pub(super) table_items_current: IntGauge,
     /// Number of items in the gossip table that are finished.
     pub(super) table_items_finished: IntGauge,
+    /// Number of responses to pushed gossip requests from peers which already held the item.
+    pub(super) gossip_responses_redundant: IntCounter,
+    /// Number of reconciliation rounds, i.e. filters of recently received items sent to peers.
+    pub(super) reconciliation_rounds: IntCounter,
+    /// Number of items offered to peers because they were missing from the peer's filter.
+    pub(super) reconciliation_items_offered: IntCounter,
+    /// Number of offered items which the peer already held.
+    pub(super) reconciliation_items_redundant: IntCounter,
     /// Reference to the registry for unregistering.
     registry: Registry,
 }
@@ -51,12 +59,45 @@ impl Metrics {
                 name
             ),
         )?;
+        let gossip_responses_redundant = IntCounter::new(
+            format!("{}_gossip_responses_redundant", name),
+            format!(
+                "number of responses to gossip requests of the {} from peers already holding the \
+                item",
+                name
+            ),
+        )?;
+        let reconciliation_rounds = IntCounter::new(
+            format!("{}_reconciliation_rounds", name),
+            format!(
+                "number of filters of recently received items sent by the {}",
+                name
+            ),
+        )?;
+        let reconciliation_items_offered = IntCounter::new(
+            format!("{}_reconciliation_items_offered", name),
+            format!(
+                "number of items offered by the {} to peers missing them from their filter",
+                name
+            ),
+        )?;
+        let reconciliation_items_redundant = IntCounter::new(
+            format!("{}_reconciliation_items_redundant", name),
+            format!(
+                "number of items offered by the {} which the peer already held",
+                name
+            ),
+        )?;
 
         registry.register(Box::new(items_received.clone()))?;
         registry.register(Box::new(times_gossiped.clone()))?;
         registry.register(Box::new(times_ran_out_of_peers.clone()))?;
         registry.register(Box::new(table_items_current.clone()))?;
         registry.register(Box::new(table_items_finished.clone()))?;
+        registry.register(Box::new(gossip_responses_redundant.clone()))?;
+        registry.register(Box::new(reconciliation_rounds.clone()))?;
+        registry.register(Box::new(reconciliation_items_offered.clone()))?;
+        registry.register(Box::new(reconciliation_items_redundant.clone()))?;
 
         Ok(Metrics {
             items_received,
@@ -64,6 +105,10 @@ impl Metrics {
             times_ran_out_of_peers,
             table_items_current,
             table_items_finished,
+            gossip_responses_redundant,
+            reconciliation_rounds,
+            reconciliation_items_offered,
+            reconciliation_items_redundant,
             registry: registry.clone(),
         })
     }
@@ -76,5 +121,9 @@ impl Drop for Metrics {
         unregister_metric!(self.registry, self.times_ran_out_of_peers);
         unregister_metric!(self.registry, self.table_items_current);
         unregister_metric!(self.registry, self.table_items_finished);
+        unregister_metric!(self.registry, self.gossip_responses_redundant);
+        unregister_metric!(self.registry, self.reconciliation_rounds);
+        unregister_metric!(self.registry, self.reconciliation_items_offered);
+        unregister_metric!(self.registry, self.reconciliation_items_redundant);
     }
 }
@@ -0,0 +1,453 @@
+//! Set reconciliation of recently received items.
+//!
+//! Instead of pushing every item received from a peer onwards, a gossiper with reconciliation
+//! enabled periodically sends a bloom filter of the IDs of the items it received recently to a few
+//! random peers.  A peer receiving such a filter offers back every recently received item of its
+//! own which is missing from the filter, so that items are only announced to peers which are
+//! likely to lack them.  Items received from clients are still pushed as usual, which seeds them
+//! into the network.
+//!
+//! The size of a filter is limited, so once more items were received recently than a filter can
+//! represent with the configured bits per item, no filters are sent and relayed items are pushed
+//! onwards as usual, until the number of recent items drops below the capacity again.
+//!
+//! Since filters are sent by peers, every filter is checked to be at least a minimum size, at most
+//! one filter per peer is handled in half an interval, and only a limited number of items is
+//! offered in response to a single filter.
+
+#[cfg(not(test))]
+use std::time::Instant;
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    time::Duration,
+};
+
+use datasize::DataSize;
+#[cfg(test)]
+use fake_instant::FakeClock as Instant;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use casper_hashing::Digest;
+
+use super::config::ReconciliationConfig;
+use crate::{
+    types::NodeId,
+    utils::specimen::{Cache, LargestSpecimen, SizeEstimator},
+};
+
+/// The minimum number of 64 bit words of a filter.
+///
+/// A tiny filter claims almost no items, so would make us offer every recently received item.
+const MIN_FILTER_WORDS: usize = 16;
+/// The maximum number of 64 bit words of a filter, limiting a filter to 64 KiB.
+const MAX_FILTER_WORDS: usize = 8 * 1024;
+/// The maximum number of items offered to a peer in response to a single filter.
+///
+/// Further missing items are offered in response to the peer's next filters.
+pub(super) const MAX_OFFERS_PER_FILTER: usize = 256;
+/// The maximum number of hash functions of a filter.
+const MAX_HASH_COUNT: u8 = 16;
+
+/// A bloom filter of item IDs.
+///
+/// The bit positions are derived from the Blake2b digest of the serialized ID, so that all nodes
+/// agree on them regardless of their build.
+#[derive(Clone, DataSize, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub(crate) struct BloomFilter {
+    /// The bits of the filter.
+    words: Vec<u64>,
+    /// The number of bits set per ID.
+    hash_count: u8,
+}
+
+impl BloomFilter {
+    /// Returns the number of items a filter can hold with the given number of bits per item.
+    pub(super) fn capacity(bits_per_item: u8) -> usize {
+        MAX_FILTER_WORDS * 64 / usize::from(bits_per_item.max(1))
+    }
+
+    /// Creates an empty filter sized for the given number of items.
+    ///
+    /// Beyond the filter's capacity, the false positive rate rises quickly.
+    pub(super) fn new(item_count: usize, bits_per_item: u8) -> Self {
+        let bits_per_item = bits_per_item.max(1);
+        let bit_count = item_count.saturating_mul(usize::from(bits_per_item));
+        let word_count = (bit_count / 64 + 1).clamp(MIN_FILTER_WORDS, MAX_FILTER_WORDS);
+        // The false positive rate is lowest with `bits_per_item * ln(2)` hash functions.
+        let hash_count = (f64::from(bits_per_item) * std::f64::consts::LN_2).round() as u8;
+        BloomFilter {
+            words: vec![0; word_count],
+            hash_count: hash_count.clamp(1, MAX_HASH_COUNT),
+        }
+    }
+
+    /// Returns `false` if the filter received from a peer is malformed or exceeds our limits.
+    pub(super) fn is_valid(&self) -> bool {
+        (MIN_FILTER_WORDS..=MAX_FILTER_WORDS).contains(&self.words.len())
+            && (1..=MAX_HASH_COUNT).contains(&self.hash_count)
+    }
+
+    /// Adds the given ID to the filter.
+    pub(super) fn insert<I: Serialize>(&mut self, item_id: &I) {
+        for index in self.bit_indices(item_id) {
+            self.words[index / 64] |= 1 << (index % 64);
+        }
+    }
+
+    /// Returns `true` if the given ID is possibly in the filter, `false` if it definitely isn't.
+    pub(super) fn contains<I: Serialize>(&self, item_id: &I) -> bool {
+        self.bit_indices(item_id)
+            .into_iter()
+            .all(|index| self.words[index / 64] & (1 << (index % 64)) != 0)
+    }
+
+    /// Returns the indices of the bits representing the given ID, using double hashing.
+    fn bit_indices<I: Serialize>(&self, item_id: &I) -> Vec<usize> {
+        let digest = match bincode::serialize(item_id) {
+            Ok(serialized_id) => Digest::hash(serialized_id).value(),
+            Err(error) => {
+                error!(%error, "failed to serialize item id for bloom filter");
+                return Vec::new();
+            }
+        };
+        let mut first = [0; 8];
+        let mut second = [0; 8];
+        first.copy_from_slice(&digest[..8]);
+        second.copy_from_slice(&digest[8..16]);
+        let first = u64::from_le_bytes(first);
+        // An odd step visits distinct positions for every hash function.
+        let second = u64::from_le_bytes(second) | 1;
+        let bit_count = self.words.len() as u64 * 64;
+        (0..u64::from(self.hash_count))
+            .map(|index| (first.wrapping_add(index.wrapping_mul(second)) % bit_count) as usize)
+            .collect()
+    }
+}
+
+impl LargestSpecimen for BloomFilter {
+    fn largest_specimen<E: SizeEstimator>(_estimator: &E, _cache: &mut Cache) -> Self {
+        BloomFilter {
+            words: vec![u64::MAX; MAX_FILTER_WORDS],
+            hash_count: MAX_HASH_COUNT,
+        }
+    }
+}
+
+/// The outcome of starting a reconciliation round.
+#[derive(Debug)]
+pub(super) enum Round {
+    /// The filter of all recently received items, to be sent to peers.
+    Filter(BloomFilter),
+    /// More items were received recently than a filter can hold, so no filter should be sent.
+    /// Rounds continue to be scheduled.
+    Saturated,
+    /// No item was received recently, no further round is scheduled until the next item is
+    /// recorded.
+    Idle,
+}
+
+/// The reconciliation state of a gossiper.
+#[derive(DataSize, Debug)]
+pub(super) struct Reconciliation<I> {
+    /// See `ReconciliationConfig::interval`.
+    interval: Duration,
+    /// See `ReconciliationConfig::fanout`.
+    fanout: usize,
+    /// See `ReconciliationConfig::window`, capped at the duration items stay in the gossip table.
+    window: Duration,
+    /// See `ReconciliationConfig::bits_per_item`.
+    bits_per_item: u8,
+    /// The IDs of recently received items, with the time they drop out of the window.
+    recent: HashMap<I, Instant>,
+    /// The latest filter received from each peer, with the time it expires.
+    peer_filters: HashMap<NodeId, (Instant, BloomFilter)>,
+    /// Items offered to peers which have not responded yet, with the time the offer expires.
+    offers: HashMap<(I, NodeId), Instant>,
+    /// The time from which the next filter of each peer is handled.
+    next_filter_due: HashMap<NodeId, Instant>,
+    /// Whether the next reconciliation round is scheduled.
+    round_scheduled: bool,
+}
+
+impl<I: Clone + Eq + Hash + Serialize> Reconciliation<I> {
+    /// Creates a new reconciliation state.
+    pub(super) fn new(config: ReconciliationConfig, finished_entry_duration: Duration) -> Self {
+        Reconciliation {
+            interval: config.interval.into(),
+            fanout: usize::from(config.fanout),
+            window: Duration::from(config.window).min(finished_entry_duration),
+            bits_per_item: config.bits_per_item,
+            recent: HashMap::new(),
+            peer_filters: HashMap::new(),
+            offers: HashMap::new(),
+            next_filter_due: HashMap::new(),
+            round_scheduled: false,
+        }
+    }
+
+    /// Returns the interval between two reconciliation rounds.
+    pub(super) fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the number of peers to send our filter to in each round.
+    pub(super) fn fanout(&self) -> usize {
+        self.fanout
+    }
+
+    /// Records an item we hold.
+    ///
+    /// Returns `true` if no reconciliation round is scheduled yet, in which case the caller must
+    /// schedule one.
+    pub(super) fn record(&mut self, item_id: I) -> bool {
+        let expiry = Instant::now() + self.window;
+        let _ = self.recent.entry(item_id).or_insert(expiry);
+        !std::mem::replace(&mut self.round_scheduled, true)
+    }
+
+    /// Returns `true` if more items were received recently than a filter can hold.
+    ///
+    /// A filter holding these items would claim almost any item, so peers would never be offered
+    /// the items they lack. Relayed items should be pushed onwards instead.
+    pub(super) fn is_saturated(&self) -> bool {
+        self.recent.len() > BloomFilter::capacity(self.bits_per_item)
+    }
+
+    /// Starts a reconciliation round, returning the filter of all recently received items.
+    pub(super) fn start_round(&mut self) -> Round {
+        self.purge();
+        if self.recent.is_empty() {
+            self.round_scheduled = false;
+            return Round::Idle;
+        }
+        if self.is_saturated() {
+            return Round::Saturated;
+        }
+        let mut filter = BloomFilter::new(self.recent.len(), self.bits_per_item);
+        for item_id in self.recent.keys() {
+            filter.insert(item_id);
+        }
+        Round::Filter(filter)
+    }
+
+    /// Handles a filter received from a peer.
+    ///
+    /// Returns up to `MAX_OFFERS_PER_FILTER` recently received items missing from the filter, which
+    /// should be offered to the peer.  Items offered before which the peer didn't respond to yet
+    /// are not offered again.
+    ///
+    /// Returns `None` if the peer already sent a filter less than half an interval ago, in which
+    /// case the filter is ignored.
+    pub(super) fn handle_filter(&mut self, peer: NodeId, filter: BloomFilter) -> Option<Vec<I>> {
+        self.purge();
+        let now = Instant::now();
+        if self.next_filter_due.contains_key(&peer) {
+            return None;
+        }
+        let _ = self.next_filter_due.insert(peer, now + self.interval / 2);
+
+        let expiry = now + self.window;
+        let missing: Vec<I> = self
+            .recent
+            .keys()
+            .filter(|item_id| {
+                !filter.contains(item_id) && !self.offers.contains_key(&((*item_id).clone(), peer))
+            })
+            .take(MAX_OFFERS_PER_FILTER)
+            .cloned()
+            .collect();
+        for item_id in &missing {
+            let _ = self.offers.insert((item_id.clone(), peer), expiry);
+        }
+        let _ = self.peer_filters.insert(peer, (expiry, filter));
+        Some(missing)
+    }
+
+    /// Removes an outstanding offer of the given item to the given peer.
+    ///
+    /// Returns `true` if there was such an offer.
+    pub(super) fn take_offer(&mut self, item_id: &I, peer: NodeId) -> bool {
+        self.offers.remove(&(item_id.clone(), peer)).is_some()
+    }
+
+    /// Returns the peers whose latest filter claims they hold the given item.
+    pub(super) fn likely_holders(&self, item_id: &I) -> HashSet<NodeId> {
+        self.peer_filters
+            .iter()
+            .filter(|(_, (_, filter))| filter.contains(item_id))
+            .map(|(peer, _)| *peer)
+            .collect()
+    }
+
+    /// Removes all items, filters and offers which dropped out of the window.
+    fn purge(&mut self) {
+        let now = Instant::now();
+        self.recent.retain(|_, expiry| *expiry > now);
+        self.peer_filters.retain(|_, (expiry, _)| *expiry > now);
+        self.offers.retain(|_, expiry| *expiry > now);
+        self.next_filter_due.retain(|_, due| *due > now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use casper_types::TimeDiff;
+
+    use super::{
+        BloomFilter, Instant, Reconciliation, Round, MAX_OFFERS_PER_FILTER, MIN_FILTER_WORDS,
+    };
+    use crate::{components::gossiper::config::ReconciliationConfig, types::NodeId};
+
+    #[test]
+    fn bloom_filter_should_contain_inserted_ids() {
+        let mut filter = BloomFilter::new(1000, 10);
+        for item_id in 0..1000_u64 {
+            filter.insert(&item_id);
+        }
+        assert!(filter.is_valid());
+        assert!((0..1000_u64).all(|item_id| filter.contains(&item_id)));
+
+        // With 10 bits per item, the false positive rate should be around 1%.
+        let false_positives = (1000..11000_u64)
+            .filter(|item_id| filter.contains(item_id))
+            .count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn bloom_filter_below_minimum_size_should_be_invalid() {
+        let filter = BloomFilter::new(1, 10);
+        assert!(filter.is_valid());
+        assert_eq!(filter.words.len(), MIN_FILTER_WORDS);
+
+        let tiny_filter = BloomFilter {
+            words: vec![0; MIN_FILTER_WORDS - 1],
+            ..filter
+        };
+        assert!(!tiny_filter.is_valid());
+    }
+
+    #[test]
+    fn should_offer_missing_items_once() {
+        let mut rng = crate::new_rng();
+        let peer = NodeId::random(&mut rng);
+        let config = ReconciliationConfig {
+            enabled: true,
+            window: TimeDiff::from_str("10sec").unwrap(),
+            ..Default::default()
+        };
+        let mut reconciliation = Reconciliation::new(config, Duration::from_secs(60));
+
+        assert!(reconciliation.record(1_u64));
+        assert!(!reconciliation.record(2));
+        assert!(!reconciliation.record(3));
+
+        // The peer holds item 1 only, so should be offered the other two.
+        let mut peer_filter = BloomFilter::new(1, 10);
+        peer_filter.insert(&1_u64);
+        let mut offered = reconciliation
+            .handle_filter(peer, peer_filter.clone())
+            .unwrap();
+        offered.sort_unstable();
+        assert_eq!(offered, vec![2, 3]);
+        assert!(reconciliation.likely_holders(&1).contains(&peer));
+        assert!(reconciliation.likely_holders(&2).is_empty());
+
+        // Filters sent more than once per half an interval are ignored.
+        assert!(reconciliation
+            .handle_filter(peer, peer_filter.clone())
+            .is_none());
+        Instant::advance_time(2_500);
+
+        // Outstanding offers are not repeated.
+        assert!(reconciliation.take_offer(&2, peer));
+        assert!(!reconciliation.take_offer(&2, peer));
+        assert_eq!(
+            reconciliation.handle_filter(peer, peer_filter.clone()),
+            Some(vec![2])
+        );
+
+        // Our own filter covers all recent items.
+        let our_filter = match reconciliation.start_round() {
+            Round::Filter(filter) => filter,
+            round => panic!("unexpected round: {:?}", round),
+        };
+        assert!((1..=3_u64).all(|item_id| our_filter.contains(&item_id)));
+
+        // Once the window has passed, there is nothing left to reconcile.
+        Instant::advance_time(7_501);
+        assert_eq!(
+            reconciliation.handle_filter(peer, peer_filter),
+            Some(Vec::new())
+        );
+        assert!(matches!(reconciliation.start_round(), Round::Idle));
+        assert!(reconciliation.record(4));
+    }
+
+    #[test]
+    fn should_cap_offers_per_filter() {
+        let mut rng = crate::new_rng();
+        let peer = NodeId::random(&mut rng);
+        let mut reconciliation =
+            Reconciliation::new(ReconciliationConfig::default(), Duration::from_secs(60));
+
+        let item_count = MAX_OFFERS_PER_FILTER as u64 + 10;
+        for item_id in 0..item_count {
+            let _ = reconciliation.record(item_id);
+        }
+
+        // The remaining items are offered in response to the next filter.
+        let empty_filter = BloomFilter::new(1, 10);
+        let first = reconciliation
+            .handle_filter(peer, empty_filter.clone())
+            .unwrap();
+        assert_eq!(first.len(), MAX_OFFERS_PER_FILTER);
+        Instant::advance_time(2_500);
+        let second = reconciliation.handle_filter(peer, empty_filter).unwrap();
+        assert_eq!(second.len(), 10);
+        assert!(second.iter().all(|item_id| !first.contains(item_id)));
+    }
+
+    #[test]
+    fn should_not_send_filter_beyond_capacity() {
+        let config = ReconciliationConfig {
+            enabled: true,
+            window: TimeDiff::from_str("10sec").unwrap(),
+            ..Default::default()
+        };
+        let capacity = BloomFilter::capacity(config.bits_per_item) as u64;
+        let mut reconciliation = Reconciliation::new(config, Duration::from_secs(60));
+
+        // A full filter still keeps the false positive rate low.
+        for item_id in 0..capacity {
+            let _ = reconciliation.record(item_id);
+        }
+        assert!(!reconciliation.is_saturated());
+        let filter = match reconciliation.start_round() {
+            Round::Filter(filter) => filter,
+            round => panic!("unexpected round: {:?}", round),
+        };
+        assert!(filter.is_valid());
+        assert!((0..capacity).all(|item_id| filter.contains(&item_id)));
+        let false_positives = (capacity..capacity + 10000)
+            .filter(|item_id| filter.contains(item_id))
+            .count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+
+        // Beyond its capacity, no filter is sent.
+        assert!(!reconciliation.record(capacity));
+        assert!(reconciliation.is_saturated());
+        assert!(matches!(reconciliation.start_round(), Round::Saturated));
+
+        // Once the items dropped out of the window, filters are sent again.
+        Instant::advance_time(10_001);
+        assert!(matches!(reconciliation.start_round(), Round::Idle));
+        assert!(reconciliation.record(0));
+        assert!(!reconciliation.is_saturated());
+        assert!(matches!(reconciliation.start_round(), Round::Filter(_)));
+    }
+}
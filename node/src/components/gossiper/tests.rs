@@ -4,6 +4,7 @@
 use std::{
     collections::{BTreeSet, HashMap},
     iter,
+    str::FromStr,
     sync::Arc,
 };
 
@@ -23,14 +24,18 @@ use super::*;
 use crate::{
     components::{
         deploy_acceptor,
-        in_memory_network::{self, InMemoryNetwork, NetworkController},
+        in_memory_network::{
+            self,
+            conditions::{Latency, LinkConditions},
+            InMemoryNetwork, NetworkController,
+        },
         network::{GossipedAddress, Identity as NetworkIdentity},
         storage::{self, Storage},
     },
     effect::{
         announcements::{
             ControlAnnouncement, DeployAcceptorAnnouncement, FatalAnnouncement,
-            GossiperAnnouncement,
+            GossiperAnnouncement, PeerBehaviorAnnouncement,
         },
         incoming::{
             ConsensusDemand, ConsensusMessageIncoming, FinalitySignatureIncoming,
@@ -79,6 +84,8 @@ enum Event {
     DeployGossiperAnnouncement(#[serde(skip_serializing)] GossiperAnnouncement<Deploy>),
     #[from]
     DeployGossiperIncoming(GossiperIncoming<Deploy>),
+    #[from]
+    PeerBehaviorAnnouncement(PeerBehaviorAnnouncement),
 }
 
 impl ReactorEvent for Event {
@@ -176,7 +183,8 @@ impl reactor::Reactor for Reactor {
             "deploy_gossiper",
             config,
             registry,
-        )?;
+        )?
+        .with_reconciliation(config.deploy_reconciliation());
 
         let reactor = Reactor {
             network,
@@ -314,6 +322,7 @@ impl reactor::Reactor for Reactor {
                 self.deploy_gossiper
                     .handle_event(effect_builder, rng, incoming.into()),
             ),
+            Event::PeerBehaviorAnnouncement(_ann) => Effects::new(),
         }
     }
 }
@@ -385,6 +394,132 @@ async fn should_gossip() {
     }
 }
 
+#[tokio::test]
+async fn should_gossip_over_slow_links() {
+    const NETWORK_SIZE: usize = 5;
+    const DEPLOY_COUNT: usize = 10;
+    const TIMEOUT: Duration = Duration::from_secs(20);
+    const QUIET_FOR: Duration = Duration::from_millis(200);
+
+    NetworkController::<NodeMessage>::create_active();
+    NetworkController::<NodeMessage>::set_default_link_conditions(LinkConditions {
+        latency: Latency::Uniform {
+            min: Duration::from_millis(5),
+            max: Duration::from_millis(50),
+        },
+        drop_probability: 0.0,
+        bandwidth: Some(1024 * 1024),
+    });
+    let mut network = TestingNetwork::<Reactor>::new();
+    let mut rng = crate::new_rng();
+
+    let node_ids = network.add_nodes(&mut rng, NETWORK_SIZE).await;
+
+    let (all_deploy_hashes, deploys): (BTreeSet<_>, Vec<_>) = iter::repeat_with(|| {
+        let deploy = Arc::new(Deploy::random_valid_native_transfer(&mut rng));
+        (*deploy.hash(), deploy)
+    })
+    .take(DEPLOY_COUNT)
+    .unzip();
+
+    for (index, deploy) in deploys.into_iter().enumerate() {
+        network
+            .process_injected_effect_on(
+                &node_ids[index % NETWORK_SIZE],
+                announce_deploy_received(deploy),
+            )
+            .await;
+    }
+
+    let all_deploys_held = |nodes: &HashMap<NodeId, Runner<ConditionCheckReactor<Reactor>>>| {
+        nodes.values().all(|runner| {
+            let hashes = runner.reactor().inner().storage.get_all_deploy_hashes();
+            all_deploy_hashes == hashes
+        })
+    };
+    network.settle_on(&mut rng, all_deploys_held, TIMEOUT).await;
+    network.settle(&mut rng, QUIET_FOR, TIMEOUT).await;
+
+    NetworkController::<NodeMessage>::remove_active();
+}
+
+#[tokio::test]
+async fn should_gossip_with_reconciliation() {
+    const NETWORK_SIZE: usize = 10;
+    const DEPLOY_COUNT: usize = 20;
+    const TIMEOUT: Duration = Duration::from_secs(20);
+    const QUIET_FOR: Duration = Duration::from_millis(200);
+
+    let config = Config {
+        deploy_reconciliation: ReconciliationConfig {
+            enabled: true,
+            interval: TimeDiff::from_str("100ms").unwrap(),
+            // Rounds stop once the window has passed, letting the network settle.
+            window: TimeDiff::from_str("2sec").unwrap(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    NetworkController::<NodeMessage>::create_active();
+    let mut network = TestingNetwork::<Reactor>::new();
+    let mut rng = crate::new_rng();
+
+    let mut node_ids = Vec::new();
+    for _ in 0..NETWORK_SIZE {
+        let (node_id, _runner) = network
+            .add_node_with_config(config, &mut rng)
+            .await
+            .unwrap();
+        node_ids.push(node_id);
+    }
+
+    let (all_deploy_hashes, deploys): (BTreeSet<_>, Vec<_>) = iter::repeat_with(|| {
+        let deploy = Arc::new(Deploy::random_valid_native_transfer(&mut rng));
+        (*deploy.hash(), deploy)
+    })
+    .take(DEPLOY_COUNT)
+    .unzip();
+
+    // Every node receives a deploy from a client, so that every node sends filters.
+    for (index, deploy) in deploys.into_iter().enumerate() {
+        network
+            .process_injected_effect_on(
+                &node_ids[index % NETWORK_SIZE],
+                announce_deploy_received(deploy),
+            )
+            .await;
+    }
+
+    // Relayed deploys are not pushed onwards, so the nodes beyond the infection target of the
+    // original pushes can only have learned of them by reconciling.
+    let all_deploys_held = |nodes: &HashMap<NodeId, Runner<ConditionCheckReactor<Reactor>>>| {
+        nodes.values().all(|runner| {
+            let hashes = runner.reactor().inner().storage.get_all_deploy_hashes();
+            all_deploy_hashes == hashes
+        })
+    };
+    network.settle_on(&mut rng, all_deploys_held, TIMEOUT).await;
+    network.settle(&mut rng, QUIET_FOR, TIMEOUT).await;
+
+    let offered: u64 = network
+        .nodes()
+        .values()
+        .map(|runner| {
+            runner
+                .reactor()
+                .inner()
+                .deploy_gossiper
+                .metrics
+                .reconciliation_items_offered
+                .get()
+        })
+        .sum();
+    assert!(offered > 0, "no items were offered while reconciling");
+
+    NetworkController::<NodeMessage>::remove_active();
+}
+
 #[tokio::test]
 async fn should_get_from_alternate_source() {
     const NETWORK_SIZE: usize = 3;
@@ -3,6 +3,15 @@
 //! The `InMemoryNetwork` represents a full virtual network with flawless connectivity and delivery
 //! by default.
 //!
+//! # Network conditions
+//!
+//! To exercise nodes under realistic faults, the `NetworkController` can give links a latency
+//! distribution, a probability of dropping messages and a bandwidth cap, either for all links
+//! through `set_default_link_conditions` or per directed link through `set_link_conditions`. Groups
+//! of nodes can be cut off from the rest of the network with `schedule_partition`, either right
+//! away or after a delay, and healed later on. Delays are based on `tokio` time, so tests can use a
+//! paused clock.
+//!
 //! # Setup
 //!
 //! The network itself is managed by a `NetworkController` that can be used to create networking
@@ -276,18 +285,24 @@
 //! # }); // end of tokio::block_on
 //! ```
 
+pub(crate) mod conditions;
+
 use std::{
     any::Any,
     cell::RefCell,
     collections::{HashMap, HashSet},
     fmt::{self, Display, Formatter},
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
 };
 
 use rand::seq::IteratorRandom;
 use serde::Serialize;
-use tokio::sync::mpsc::{self, error::SendError};
-use tracing::{debug, error, info, warn};
+use tokio::{
+    sync::mpsc::{self, error::SendError},
+    time::Instant,
+};
+use tracing::{debug, error, info, trace, warn};
 
 use casper_types::testing::TestRng;
 
@@ -300,6 +315,7 @@ use crate::{
     NodeRng,
 };
 
+use self::conditions::{Fate, LinkConditions, NetworkConditions};
 use super::network::FromIncoming;
 
 const COMPONENT_NAME: &str = "in_memory_network";
@@ -307,6 +323,9 @@ const COMPONENT_NAME: &str = "in_memory_network";
 /// A network.
 type Network<P> = Arc<RwLock<HashMap<NodeId, mpsc::UnboundedSender<(NodeId, P)>>>>;
 
+/// The conditions of a network, shared by all of its nodes.
+type SharedConditions = Arc<Mutex<NetworkConditions>>;
+
 /// An in-memory network events.
 #[derive(Debug, Serialize)]
 pub(crate) struct Event<P>(NetworkRequest<P>);
@@ -336,6 +355,8 @@ thread_local! {
 pub(crate) struct NetworkController<P> {
     /// Channels for network communication.
     nodes: Network<P>,
+    /// The simulated conditions of the links between nodes.
+    conditions: SharedConditions,
 }
 
 impl<P> NetworkController<P>
@@ -347,6 +368,7 @@ where
         let _ = logging::init();
         NetworkController {
             nodes: Default::default(),
+            conditions: Default::default(),
         }
     }
 
@@ -425,6 +447,82 @@ where
         })
     }
 
+    /// Sets the conditions of all links without specific conditions on the active network.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock has been poisoned, there is no active network or the active
+    /// network is not of the correct message type.
+    pub(crate) fn set_default_link_conditions(conditions: LinkConditions) {
+        Self::with_active_conditions(|network_conditions| {
+            network_conditions.set_default(conditions)
+        })
+    }
+
+    /// Sets the conditions of the directed link from `from` to `to` on the active network.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock has been poisoned, there is no active network or the active
+    /// network is not of the correct message type.
+    pub(crate) fn set_link_conditions(from: NodeId, to: NodeId, conditions: LinkConditions) {
+        Self::with_active_conditions(|network_conditions| {
+            network_conditions.set_link(from, to, conditions)
+        })
+    }
+
+    /// Schedules a partition of the given nodes from all other nodes on the active network.
+    ///
+    /// The partition starts after `start_after` and heals after another `heal_after`, or only
+    /// through `heal_partitions` if `None`. Messages in flight are lost if a partition separates
+    /// their sender and recipient by the time they arrive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock has been poisoned, there is no active network or the active
+    /// network is not of the correct message type.
+    pub(crate) fn schedule_partition<I>(
+        isolated: I,
+        start_after: Duration,
+        heal_after: Option<Duration>,
+    ) where
+        I: IntoIterator<Item = NodeId>,
+    {
+        let isolated = isolated.into_iter().collect();
+        Self::with_active_conditions(|network_conditions| {
+            network_conditions.partition(isolated, start_after, heal_after, Instant::now())
+        })
+    }
+
+    /// Heals all partitions of the active network, including scheduled ones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock has been poisoned, there is no active network or the active
+    /// network is not of the correct message type.
+    pub(crate) fn heal_partitions() {
+        Self::with_active_conditions(NetworkConditions::heal_all)
+    }
+
+    /// Applies the given function to the conditions of the active network.
+    fn with_active_conditions<F>(f: F)
+    where
+        F: FnOnce(&mut NetworkConditions),
+    {
+        ACTIVE_NETWORK.with(|active_network| {
+            let controller = active_network
+                .borrow()
+                .as_ref()
+                .expect("tried to change conditions without active network set")
+                .downcast_ref::<Self>()
+                .expect("active network has wrong message type")
+                .conditions
+                .clone();
+            let mut conditions = controller.lock().expect("poisoned lock");
+            f(&mut conditions)
+        })
+    }
+
     /// Creates a new networking node with a random node ID.
     ///
     /// Returns the already connected new networking component for new node.
@@ -436,7 +534,12 @@ where
     where
         REv: Send + FromIncoming<P>,
     {
-        InMemoryNetwork::new_with_data(event_queue, NodeId::random(rng), self.nodes.clone())
+        InMemoryNetwork::new_with_data(
+            event_queue,
+            NodeId::random(rng),
+            self.nodes.clone(),
+            self.conditions.clone(),
+        )
     }
 }
 
@@ -448,6 +551,12 @@ pub(crate) struct InMemoryNetwork<P> {
 
     /// The nodes map, contains the incoming channel for each virtual node.
     nodes: Network<P>,
+
+    /// The simulated conditions of the links between nodes.
+    conditions: SharedConditions,
+
+    /// The queues of delayed messages in flight to each recipient, delivered in the order sent.
+    link_queues: HashMap<NodeId, mpsc::UnboundedSender<(Instant, P)>>,
 }
 
 impl<P> InMemoryNetwork<P>
@@ -469,6 +578,7 @@ where
         event_queue: EventQueueHandle<REv>,
         node_id: NodeId,
         nodes: Network<P>,
+        conditions: SharedConditions,
     ) -> Self
     where
         REv: Send + FromIncoming<P>,
@@ -484,7 +594,12 @@ where
 
         tokio::spawn(receiver_task(event_queue, receiver));
 
-        InMemoryNetwork { node_id, nodes }
+        InMemoryNetwork {
+            node_id,
+            nodes,
+            conditions,
+            link_queues: HashMap::new(),
+        }
     }
 
     /// Returns this node's ID.
@@ -496,35 +611,132 @@ where
 
 impl<P> InMemoryNetwork<P>
 where
-    P: Display,
+    P: Display + Serialize + Send + 'static,
 {
     /// Internal helper, sends a payload to a node, ignoring but logging all errors.
+    ///
+    /// The payload is delayed or dropped according to the network conditions. Delayed payloads are
+    /// queued per recipient, so that they arrive in the order they were sent.
     fn send(
-        &self,
+        &mut self,
         nodes: &HashMap<NodeId, mpsc::UnboundedSender<(NodeId, P)>>,
         dest: NodeId,
         payload: P,
+        rng: &mut NodeRng,
     ) {
         if dest == self.node_id {
             panic!("can't send message to self");
         }
 
-        match nodes.get(&dest) {
-            Some(sender) => {
-                if let Err(SendError((_, msg))) = sender.send((self.node_id, payload)) {
-                    warn!(%dest, %msg, "could not send message (send error)");
+        let sender = match nodes.get(&dest) {
+            Some(sender) => sender.clone(),
+            None => {
+                info!(%dest, %payload, "dropping message to non-existent recipient");
+                return;
+            }
+        };
 
-                    // We do nothing else, the message is just dropped.
-                }
+        let now = Instant::now();
+        let fate = match self.conditions.lock() {
+            Ok(mut conditions) => conditions.fate(
+                self.node_id,
+                dest,
+                || bincode::serialized_size(&payload).unwrap_or_default(),
+                now,
+                rng,
+            ),
+            Err(_) => {
+                error!("network conditions lock has been poisoned");
+                return;
+            }
+        };
+
+        let delay = match fate {
+            Fate::Dropped => {
+                trace!(%dest, %payload, "simulating loss of message");
+                return;
             }
-            None => info!(%dest, %payload, "dropping message to non-existent recipient"),
+            Fate::Delivered(delay) => delay,
+        };
+
+        // Messages only bypass the queue if no earlier message to the recipient can be in flight.
+        if delay.is_zero() && !self.link_queues.contains_key(&dest) {
+            deliver(&sender, self.node_id, dest, payload);
+            return;
+        }
+
+        let our_id = self.node_id;
+        let (nodes, conditions) = (&self.nodes, &self.conditions);
+        let queue = self.link_queues.entry(dest).or_insert_with(|| {
+            let (queue_sender, queue_receiver) = mpsc::unbounded_channel();
+            tokio::spawn(link_task(
+                queue_receiver,
+                nodes.clone(),
+                conditions.clone(),
+                our_id,
+                dest,
+            ));
+            queue_sender
+        });
+        if let Err(SendError((_, payload))) = queue.send((now + delay, payload)) {
+            warn!(%dest, %payload, "could not queue message (send error)");
+        }
+    }
+}
+
+/// Delivers the delayed messages sent over a single link, in the order they were sent.
+///
+/// A message never overtakes an earlier one, even if it was given a shorter delay.
+async fn link_task<P: Display>(
+    mut queue: mpsc::UnboundedReceiver<(Instant, P)>,
+    nodes: Network<P>,
+    conditions: SharedConditions,
+    our_id: NodeId,
+    dest: NodeId,
+) {
+    while let Some((deliver_at, payload)) = queue.recv().await {
+        tokio::time::sleep_until(deliver_at).await;
+        // A partition may have started while the message was in flight.
+        let is_partitioned = conditions.lock().map_or(false, |conditions| {
+            conditions.is_partitioned(&our_id, &dest, Instant::now())
+        });
+        if is_partitioned {
+            trace!(%dest, %payload, "simulating loss of message in flight");
+            continue;
+        }
+
+        // The recipient may have been removed while the message was in flight.
+        let sender = match nodes.read() {
+            Ok(nodes) => nodes.get(&dest).cloned(),
+            Err(_) => {
+                error!("network lock has been poisoned");
+                return;
+            }
+        };
+        match sender {
+            Some(sender) => deliver(&sender, our_id, dest, payload),
+            None => info!(%dest, %payload, "dropping message to removed recipient"),
         }
     }
 }
 
+/// Delivers a payload to a node's incoming channel, ignoring but logging all errors.
+fn deliver<P: Display>(
+    sender: &mpsc::UnboundedSender<(NodeId, P)>,
+    our_id: NodeId,
+    dest: NodeId,
+    payload: P,
+) {
+    if let Err(SendError((_, msg))) = sender.send((our_id, payload)) {
+        warn!(%dest, %msg, "could not send message (send error)");
+
+        // We do nothing else, the message is just dropped.
+    }
+}
+
 impl<P, REv> Component<REv> for InMemoryNetwork<P>
 where
-    P: Display + Clone,
+    P: Display + Clone + Serialize + Send + 'static,
 {
     type Event = Event<P>;
 
@@ -545,8 +757,9 @@ where
                     panic!("can't send message to self");
                 }
 
-                if let Ok(guard) = self.nodes.read() {
-                    self.send(&guard, *dest, *payload);
+                let nodes = Arc::clone(&self.nodes);
+                if let Ok(guard) = nodes.read() {
+                    self.send(&guard, *dest, *payload, rng);
                 } else {
                     error!("network lock has been poisoned")
                 };
@@ -558,9 +771,10 @@ where
                 auto_closing_responder,
                 era_id: _,
             } => {
-                if let Ok(guard) = self.nodes.read() {
+                let nodes = Arc::clone(&self.nodes);
+                if let Ok(guard) = nodes.read() {
                     for dest in guard.keys().filter(|&node_id| node_id != &self.node_id) {
-                        self.send(&guard, *dest, *payload.clone());
+                        self.send(&guard, *dest, *payload.clone(), rng);
                     }
                 } else {
                     error!("network lock has been poisoned")
@@ -575,7 +789,8 @@ where
                 auto_closing_responder,
                 gossip_target: _,
             } => {
-                if let Ok(guard) = self.nodes.read() {
+                let nodes = Arc::clone(&self.nodes);
+                if let Ok(guard) = nodes.read() {
                     let chosen: HashSet<_> = guard
                         .keys()
                         .filter(|&node_id| !exclude.contains(node_id) && node_id != &self.node_id)
@@ -585,7 +800,7 @@ where
                         .collect();
                     // Not terribly efficient, but will always get us the maximum amount of nodes.
                     for dest in chosen.iter() {
-                        self.send(&guard, *dest, *payload.clone());
+                        self.send(&guard, *dest, *payload.clone(), rng);
                     }
                     auto_closing_responder.respond(chosen).ignore()
                 } else {
@@ -618,3 +833,48 @@ async fn receiver_task<REv, P>(
 
     debug!("receiver shutting down")
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex, RwLock},
+        time::Duration,
+    };
+
+    use tokio::{sync::mpsc, time::Instant};
+
+    use super::link_task;
+    use crate::types::NodeId;
+
+    #[tokio::test]
+    async fn should_deliver_delayed_messages_in_order() {
+        tokio::time::pause();
+        let mut rng = crate::new_rng();
+        let (our_id, dest) = (NodeId::random(&mut rng), NodeId::random(&mut rng));
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let nodes = Arc::new(RwLock::new(HashMap::from([(dest, sender)])));
+
+        let (queue_sender, queue_receiver) = mpsc::unbounded_channel();
+        tokio::spawn(link_task(
+            queue_receiver,
+            nodes,
+            Arc::new(Mutex::new(Default::default())),
+            our_id,
+            dest,
+        ));
+
+        // The later messages are given shorter delays, but must not overtake the first one.
+        let now = Instant::now();
+        for (delay_ms, payload) in [(300, 1u64), (100, 2), (0, 3)] {
+            queue_sender
+                .send((now + Duration::from_millis(delay_ms), payload))
+                .expect("link task should be running");
+        }
+
+        for expected in 1..=3 {
+            assert_eq!(receiver.recv().await, Some((our_id, expected)));
+        }
+        assert!(Instant::now() >= now + Duration::from_millis(300));
+    }
+}
@@ -0,0 +1,312 @@
+//! Simulated network conditions.
+//!
+//! By default, every message is delivered instantly. Each directed link between two nodes can be
+//! given a latency distribution, a probability of dropping messages and a bandwidth cap, and
+//! partitions can be scheduled to cut off groups of nodes from the rest of the network for a
+//! while.
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use rand::Rng;
+use tokio::time::Instant;
+
+use crate::{types::NodeId, NodeRng};
+
+/// The distribution of the latency of a link.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Latency {
+    /// Every message is delayed by the same amount.
+    Fixed(Duration),
+    /// Messages are delayed by an amount chosen uniformly at random from the given range.
+    Uniform {
+        /// The smallest delay.
+        min: Duration,
+        /// The largest delay.
+        max: Duration,
+    },
+    /// Messages are usually delayed by `base`, but by `spike` with the given probability.
+    Spiky {
+        /// The usual delay.
+        base: Duration,
+        /// The delay of a spike.
+        spike: Duration,
+        /// The probability of a spike, between 0.0 and 1.0.
+        spike_probability: f64,
+    },
+}
+
+impl Latency {
+    /// Samples the delay of a single message.
+    fn sample(&self, rng: &mut NodeRng) -> Duration {
+        match *self {
+            Latency::Fixed(delay) => delay,
+            Latency::Uniform { min, max } if min < max => rng.gen_range(min..=max),
+            Latency::Uniform { min, .. } => min,
+            Latency::Spiky {
+                base,
+                spike,
+                spike_probability,
+            } => {
+                if rng.gen_bool(spike_probability.clamp(0.0, 1.0)) {
+                    spike
+                } else {
+                    base
+                }
+            }
+        }
+    }
+}
+
+/// The conditions of a directed link between two nodes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct LinkConditions {
+    /// The latency of the link.
+    pub(crate) latency: Latency,
+    /// The probability of a message being dropped, between 0.0 and 1.0.
+    pub(crate) drop_probability: f64,
+    /// The number of bytes per second the link can transmit, or `None` if unlimited.
+    pub(crate) bandwidth: Option<u64>,
+}
+
+impl Default for LinkConditions {
+    fn default() -> Self {
+        LinkConditions {
+            latency: Latency::Fixed(Duration::ZERO),
+            drop_probability: 0.0,
+            bandwidth: None,
+        }
+    }
+}
+
+/// A partition, cutting off a group of nodes from all others during a period of time.
+#[derive(Clone, Debug)]
+struct Partition {
+    /// The nodes cut off from the rest of the network.
+    isolated: HashSet<NodeId>,
+    /// The time the partition starts.
+    start: Instant,
+    /// The time the partition heals, or `None` if it never heals on its own.
+    heal: Option<Instant>,
+}
+
+impl Partition {
+    /// Returns `true` if the partition separates the two nodes at the given time.
+    fn separates(&self, from: &NodeId, to: &NodeId, now: Instant) -> bool {
+        now >= self.start
+            && self.heal.map_or(true, |heal| now < heal)
+            && self.isolated.contains(from) != self.isolated.contains(to)
+    }
+}
+
+/// What happens to a message sent over a link.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Fate {
+    /// The message is lost.
+    Dropped,
+    /// The message is delivered after the given delay.
+    Delivered(Duration),
+}
+
+/// The conditions of all links of a network.
+#[derive(Debug, Default)]
+pub(super) struct NetworkConditions {
+    /// The conditions of links without specific conditions.
+    default: LinkConditions,
+    /// The conditions of specific directed links.
+    links: HashMap<(NodeId, NodeId), LinkConditions>,
+    /// Scheduled partitions.
+    partitions: Vec<Partition>,
+    /// The time until which each bandwidth-capped link is busy transmitting earlier messages.
+    busy_until: HashMap<(NodeId, NodeId), Instant>,
+}
+
+impl NetworkConditions {
+    /// Sets the conditions of all links without specific conditions.
+    pub(super) fn set_default(&mut self, conditions: LinkConditions) {
+        self.default = conditions;
+    }
+
+    /// Sets the conditions of the directed link from `from` to `to`.
+    pub(super) fn set_link(&mut self, from: NodeId, to: NodeId, conditions: LinkConditions) {
+        self.links.insert((from, to), conditions);
+    }
+
+    /// Schedules a partition of the given nodes from all others.
+    ///
+    /// The partition starts after `start_after` and heals after another `heal_after`, if given.
+    pub(super) fn partition(
+        &mut self,
+        isolated: HashSet<NodeId>,
+        start_after: Duration,
+        heal_after: Option<Duration>,
+        now: Instant,
+    ) {
+        let start = now + start_after;
+        self.partitions.push(Partition {
+            isolated,
+            start,
+            heal: heal_after.map(|heal_after| start + heal_after),
+        });
+    }
+
+    /// Heals all partitions, including scheduled ones.
+    pub(super) fn heal_all(&mut self) {
+        self.partitions.clear();
+    }
+
+    /// Returns `true` if a partition currently separates the two nodes.
+    pub(super) fn is_partitioned(&self, from: &NodeId, to: &NodeId, now: Instant) -> bool {
+        self.partitions
+            .iter()
+            .any(|partition| partition.separates(from, to, now))
+    }
+
+    /// Determines the fate of a message sent from `from` to `to`.
+    ///
+    /// The size of the message is only computed through `size` if the link has a bandwidth cap.
+    pub(super) fn fate<F>(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        size: F,
+        now: Instant,
+        rng: &mut NodeRng,
+    ) -> Fate
+    where
+        F: FnOnce() -> u64,
+    {
+        self.partitions
+            .retain(|partition| partition.heal.map_or(true, |heal| now < heal));
+        if self.is_partitioned(&from, &to, now) {
+            return Fate::Dropped;
+        }
+
+        let conditions = self.links.get(&(from, to)).copied().unwrap_or(self.default);
+        if conditions.drop_probability > 0.0
+            && rng.gen_bool(conditions.drop_probability.clamp(0.0, 1.0))
+        {
+            return Fate::Dropped;
+        }
+
+        // A bandwidth-capped link transmits one message after the other.
+        let transmitted = match conditions.bandwidth {
+            Some(bandwidth) => {
+                let transmission_time =
+                    Duration::from_secs_f64(size() as f64 / bandwidth.max(1) as f64);
+                let busy_until = self.busy_until.entry((from, to)).or_insert(now);
+                *busy_until = (*busy_until).max(now) + transmission_time;
+                *busy_until
+            }
+            None => now,
+        };
+
+        Fate::Delivered(transmitted - now + conditions.latency.sample(rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, time::Duration};
+
+    use tokio::time::Instant;
+
+    use super::{Fate, Latency, LinkConditions, NetworkConditions};
+    use crate::types::NodeId;
+
+    #[test]
+    fn should_deliver_instantly_by_default() {
+        let mut rng = crate::new_rng();
+        let (a, b) = (NodeId::random(&mut rng), NodeId::random(&mut rng));
+        let mut conditions = NetworkConditions::default();
+        assert_eq!(
+            conditions.fate(a, b, || 1000, Instant::now(), &mut rng),
+            Fate::Delivered(Duration::ZERO)
+        );
+        // Messages are only sized on bandwidth-capped links.
+        assert_eq!(
+            conditions.fate(
+                a,
+                b,
+                || panic!("should not size message"),
+                Instant::now(),
+                &mut rng
+            ),
+            Fate::Delivered(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn should_apply_link_conditions() {
+        let mut rng = crate::new_rng();
+        let (a, b) = (NodeId::random(&mut rng), NodeId::random(&mut rng));
+        let mut conditions = NetworkConditions::default();
+        let now = Instant::now();
+
+        conditions.set_link(
+            a,
+            b,
+            LinkConditions {
+                latency: Latency::Fixed(Duration::from_millis(50)),
+                drop_probability: 0.0,
+                bandwidth: Some(1000),
+            },
+        );
+        // Messages queue up behind each other on a bandwidth-capped link.
+        assert_eq!(
+            conditions.fate(a, b, || 500, now, &mut rng),
+            Fate::Delivered(Duration::from_millis(550))
+        );
+        assert_eq!(
+            conditions.fate(a, b, || 500, now, &mut rng),
+            Fate::Delivered(Duration::from_millis(1050))
+        );
+        // The reverse direction is unaffected.
+        assert_eq!(
+            conditions.fate(b, a, || 500, now, &mut rng),
+            Fate::Delivered(Duration::ZERO)
+        );
+
+        conditions.set_default(LinkConditions {
+            drop_probability: 1.0,
+            ..LinkConditions::default()
+        });
+        assert_eq!(conditions.fate(b, a, || 500, now, &mut rng), Fate::Dropped);
+    }
+
+    #[test]
+    fn should_partition_and_heal() {
+        let mut rng = crate::new_rng();
+        let (a, b, c) = (
+            NodeId::random(&mut rng),
+            NodeId::random(&mut rng),
+            NodeId::random(&mut rng),
+        );
+        let mut conditions = NetworkConditions::default();
+        let now = Instant::now();
+
+        conditions.partition(
+            HashSet::from([a]),
+            Duration::from_secs(1),
+            Some(Duration::from_secs(2)),
+            now,
+        );
+        assert!(!conditions.is_partitioned(&a, &b, now));
+
+        let during = now + Duration::from_secs(2);
+        assert_eq!(conditions.fate(a, b, || 1, during, &mut rng), Fate::Dropped);
+        assert_eq!(conditions.fate(c, a, || 1, during, &mut rng), Fate::Dropped);
+        assert_eq!(
+            conditions.fate(b, c, || 1, during, &mut rng),
+            Fate::Delivered(Duration::ZERO)
+        );
+
+        let healed = now + Duration::from_secs(3);
+        assert_eq!(
+            conditions.fate(a, b, || 1, healed, &mut rng),
+            Fate::Delivered(Duration::ZERO)
+        );
+    }
+}
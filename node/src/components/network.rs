@@ -22,10 +22,17 @@
 //!
 //! Nodes gossip their public listening addresses periodically, and will try to establish and
 //! maintain an outgoing connection to any new address learned.
+//!
+//! Addresses successfully dialed are recorded in a persistent address book. On startup, the good
+//! addresses in it are dialed first, with the configured known addresses only being dialed after a
+//! short delay.
 
+mod address_book;
 mod bincode_format;
 pub(crate) mod blocklist;
+mod capture;
 mod chain_info;
+mod compression;
 mod config;
 mod counting_format;
 mod error;
@@ -34,27 +41,33 @@ mod gossiped_address;
 mod health;
 mod identity;
 mod insights;
+mod lanes;
 mod limiter;
 mod message;
 mod message_pack_format;
 mod metrics;
 mod outgoing;
+mod quic;
+pub(crate) mod reputation;
+mod sentry;
 mod symmetry;
 pub(crate) mod tasks;
 #[cfg(test)]
 mod tests;
+mod transport;
 
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
     fmt::{self, Debug, Display, Formatter},
-    io,
+    io, mem,
     net::{SocketAddr, TcpListener},
+    path::Path,
     sync::{Arc, Weak},
     time::{Duration, Instant},
 };
 
 use datasize::DataSize;
-use futures::{future::BoxFuture, FutureExt};
+use futures::{future::BoxFuture, Future, FutureExt};
 use itertools::Itertools;
 use prometheus::Registry;
 use rand::{
@@ -63,48 +76,63 @@ use rand::{
 };
 use serde::{Deserialize, Serialize};
 use tokio::{
-    net::TcpStream,
     sync::{
         mpsc::{self, UnboundedSender},
         watch,
     },
     task::JoinHandle,
 };
-use tokio_openssl::SslStream;
 use tokio_util::codec::LengthDelimitedCodec;
 use tracing::{debug, error, info, trace, warn, Instrument, Span};
 
-use casper_types::{EraId, PublicKey, SecretKey};
+use casper_types::{EraId, PublicKey, Timestamp};
 
+#[cfg(test)]
+pub(crate) use self::capture::{read_capture, CaptureRecord};
 pub(crate) use self::{
     bincode_format::BincodeFormat,
+    capture::{CaptureConfig, CaptureDirection},
+    compression::CompressionConfig,
     config::{Config, IdentityConfig},
     error::Error,
     event::Event,
     gossiped_address::GossipedAddress,
     identity::Identity,
     insights::NetworkInsights,
+    lanes::LanesConfig,
     message::{
         generate_largest_serialized_message, EstimatorWeights, FromIncoming, Message, MessageKind,
         Payload,
     },
+    quic::QuicConfig,
+    reputation::ReputationConfig,
+    sentry::SentryConfig,
 };
 use self::{
     blocklist::BlocklistJustification,
+    capture::{Capture, CAPTURE_DIR_NAME},
     chain_info::ChainInfo,
+    compression::{CompressingFormat, Compressor},
     counting_format::{ConnectionId, CountingFormat, Role},
     error::{ConnectionError, Result},
     event::{IncomingConnection, OutgoingConnection},
     health::{HealthConfig, TaggedTimestamp},
+    lanes::{LaneLimiters, OutgoingLanes},
     limiter::Limiter,
     message::NodeKeyPair,
     metrics::Metrics,
     outgoing::{DialOutcome, DialRequest, OutgoingConfig, OutgoingManager},
+    quic::QuicEndpoint,
+    reputation::{PeerObservation, PeerScores},
+    sentry::SentryTopology,
     symmetry::ConnectionSymmetry,
     tasks::{MessageQueueItem, NetworkContext},
+    transport::Transport,
 };
 use crate::{
-    components::{gossiper::GossipItem, Component, ComponentState, InitializedComponent},
+    components::{
+        consensus::Signer, gossiper::GossipItem, Component, ComponentState, InitializedComponent,
+    },
     effect::{
         announcements::PeerBehaviorAnnouncement,
         requests::{BeginGossipRequest, NetworkInfoRequest, NetworkRequest, StorageRequest},
@@ -134,6 +162,12 @@ const BASE_RECONNECTION_TIMEOUT: Duration = Duration::from_secs(1);
 /// Interval during which to perform outgoing manager housekeeping.
 const OUTGOING_MANAGER_SWEEP_INTERVAL: Duration = Duration::from_secs(1);
 
+/// Delay before dialing the known addresses, if there are good addresses in the address book.
+const KNOWN_ADDRESSES_FALLBACK_DELAY: Duration = Duration::from_secs(10);
+
+/// Minimum interval between two saves of the address book.
+const ADDRESS_BOOK_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
 /// How often to send a ping down a healthy connection.
 const PING_INTERVAL: Duration = Duration::from_secs(30);
 
@@ -180,6 +214,20 @@ where
     /// Tracks nodes that have announced themselves as nodes that are syncing.
     syncing_nodes: HashSet<NodeId>,
 
+    /// Reputation scores of peers.
+    peer_scores: PeerScores,
+
+    /// Persistent record of outgoing addresses.
+    address_book: address_book::AddressBook,
+    /// The last time the address book was saved.
+    last_address_book_save: Instant,
+    /// Known addresses not yet learned, as good addresses from the address book are tried first.
+    pending_known_addresses: Vec<SocketAddr>,
+
+    /// Capture of the network traffic, if enabled.
+    #[data_size(skip)]
+    capture: Option<Capture>,
+
     channel_management: Option<ChannelManagement>,
 
     /// Networking metrics.
@@ -190,6 +238,10 @@ where
     #[data_size(skip)]
     outgoing_limiter: Limiter,
 
+    /// The byte budgets of the outgoing message lanes.
+    #[data_size(skip)]
+    lane_limiters: LaneLimiters,
+
     /// The limiter for incoming resource usage.
     ///
     /// This is not incoming bandwidth but an independent resource estimate.
@@ -199,6 +251,22 @@ where
     /// The era that is considered the active era by the network component.
     active_era: EraId,
 
+    /// Our role in a sentry topology, resolved on initialization.
+    sentry_topology: SentryTopology,
+    /// The sentries whose announcement to relay consensus messages is honored.
+    trusted_sentries: HashSet<NodeId>,
+    /// Trusted sentries we have an outgoing connection to, which announced that they relay
+    /// consensus messages to the validators they protect.
+    consensus_relays: HashSet<NodeId>,
+    /// Peers we have an outgoing connection to, which announced that they accept set
+    /// reconciliation filters of gossiped items.
+    reconciliation_peers: HashSet<NodeId>,
+
+    /// The number of times our identity has been rotated.
+    identity_rotations: u64,
+    /// Outgoing connections established with our previous identity, yet to be replaced.
+    draining_outgoing: HashSet<SocketAddr>,
+
     /// The state of this component.
     state: ComponentState,
 }
@@ -224,6 +292,9 @@ struct ChannelManagement {
     /// connections should be closed.
     #[data_size(skip)]
     close_incoming_receiver: watch::Receiver<()>,
+    /// Channel signaling the incoming connections established with our previous identity to close.
+    #[data_size(skip)]
+    retiring_incoming_sender: Option<watch::Sender<()>>,
 }
 
 impl<REv, P> Network<REv, P>
@@ -238,14 +309,18 @@ where
         + From<BeginGossipRequest<GossipedAddress>>,
 {
     /// Creates a new network component instance.
+    ///
+    /// If `storage_dir` is given, the peer address book is persisted in it, as are network traffic
+    /// captures if enabled.
     #[allow(clippy::type_complexity)]
     pub(crate) fn new<C: Into<ChainInfo>>(
         cfg: Config,
         our_identity: Identity,
-        node_key_pair: Option<(Arc<SecretKey>, PublicKey)>,
+        node_key_pair: Option<Arc<dyn Signer>>,
         registry: &Registry,
         chain_info_source: C,
         validator_matrix: ValidatorMatrix,
+        storage_dir: Option<&Path>,
     ) -> Result<Network<REv, P>> {
         let net_metrics = Arc::new(Metrics::new(registry)?);
 
@@ -255,6 +330,9 @@ where
             validator_matrix.clone(),
         );
 
+        let lane_limiters =
+            LaneLimiters::new(&cfg.lanes, &net_metrics.lanes, validator_matrix.clone());
+
         let incoming_limiter = Limiter::new(
             cfg.max_incoming_message_rate_non_validators,
             net_metrics.accumulated_incoming_limiter_delay.clone(),
@@ -285,18 +363,53 @@ where
             &net_metrics,
         ));
 
+        let peer_scores = PeerScores::new(&cfg.reputation);
+        let address_book = match storage_dir {
+            Some(storage_dir) => address_book::AddressBook::load(storage_dir),
+            None => address_book::AddressBook::new_volatile(),
+        };
+        let capture = match storage_dir {
+            Some(storage_dir) if cfg.capture.enabled => {
+                let directory = storage_dir.join(CAPTURE_DIR_NAME);
+                match Capture::start(&cfg.capture, &directory, Arc::downgrade(&net_metrics)) {
+                    Ok(capture) => Some(capture),
+                    Err(error) => {
+                        warn!(%error, dir = %directory.display(), "failed to start network capture");
+                        None
+                    }
+                }
+            }
+            None if cfg.capture.enabled => {
+                warn!("network capture enabled, but there is no storage directory to capture into");
+                None
+            }
+            _ => None,
+        };
+
         let component = Network {
             cfg,
             context,
             outgoing_manager,
             connection_symmetries: HashMap::new(),
             syncing_nodes: HashSet::new(),
+            peer_scores,
+            address_book,
+            last_address_book_save: Instant::now(),
+            pending_known_addresses: Vec::new(),
+            capture,
             channel_management: None,
             net_metrics,
             outgoing_limiter,
+            lane_limiters,
             incoming_limiter,
             // We start with an empty set of validators for era 0 and expect to be updated.
             active_era: EraId::new(0),
+            sentry_topology: SentryTopology::Open,
+            trusted_sentries: HashSet::new(),
+            consensus_relays: HashSet::new(),
+            reconciliation_peers: HashSet::new(),
+            identity_rotations: 0,
+            draining_outgoing: HashSet::new(),
             state: ComponentState::Uninitialized,
         };
 
@@ -304,8 +417,21 @@ where
     }
 
     fn initialize(&mut self, effect_builder: EffectBuilder<REv>) -> Result<Effects<Event<P>>> {
+        self.sentry_topology = SentryTopology::resolve(&self.cfg.sentry)?;
+        self.trusted_sentries = sentry::parse_trusted_sentries(&self.cfg.sentry)?;
+
         let mut known_addresses = HashSet::new();
-        for address in &self.cfg.known_addresses {
+        // A node protected by sentries connects to its sentries only, while a sentry also dials
+        // the nodes it protects.
+        let configured_addresses = match self.sentry_topology {
+            SentryTopology::Protected { .. } => &self.cfg.sentry.sentry_addresses,
+            SentryTopology::Open | SentryTopology::Sentry { .. } => &self.cfg.known_addresses,
+        };
+        let protected_addresses = match self.sentry_topology {
+            SentryTopology::Sentry { .. } => self.cfg.sentry.protected_addresses.as_slice(),
+            SentryTopology::Open | SentryTopology::Protected { .. } => &[],
+        };
+        for address in configured_addresses.iter().chain(protected_addresses) {
             match utils::resolve_address(address) {
                 Ok(known_address) => {
                     if !known_addresses.insert(known_address) {
@@ -344,9 +470,31 @@ where
             public_addr.set_port(local_addr.port());
         }
 
+        // If enabled, QUIC connections are accepted on the UDP port matching the TCP port.
+        let quic_endpoint = if self.cfg.quic.enabled {
+            let identity = self.context.identity();
+            let endpoint = QuicEndpoint::bind(
+                &self.cfg.quic,
+                local_addr,
+                &identity.tls_certificate,
+                &identity.secret_key,
+                self.context.network_ca().cloned(),
+            )?;
+            info!(%local_addr, "accepting QUIC connections");
+            Some(endpoint)
+        } else {
+            None
+        };
+
         Arc::get_mut(&mut self.context)
             .expect("should be no other pointers")
-            .initialize(public_addr, effect_builder.into_inner());
+            .initialize(
+                public_addr,
+                effect_builder.into_inner(),
+                self.sentry_topology.incoming_allowlist(),
+                self.sentry_topology.is_sentry(),
+                quic_endpoint,
+            );
 
         let protocol_version = self.context.chain_info().protocol_version;
         // Run the server task.
@@ -372,25 +520,55 @@ where
             server_join_handle: Some(server_join_handle),
             close_incoming_sender: Some(close_incoming_sender),
             close_incoming_receiver,
+            retiring_incoming_sender: None,
         };
 
         self.channel_management = Some(channel_management);
 
-        // Learn all known addresses and mark them as unforgettable.
+        // Dial the good addresses from the address book first. Only if there are none, the known
+        // addresses are learned right away, otherwise after a delay.
         let now = Instant::now();
-        let dial_requests: Vec<_> = known_addresses
-            .into_iter()
-            .filter_map(|addr| self.outgoing_manager.learn_addr(addr, true, now))
+        let good_addresses = if self.sentry_topology.is_protected() {
+            Vec::new()
+        } else {
+            self.address_book.good_addresses()
+        };
+        let mut dial_requests: Vec<_> = good_addresses
+            .iter()
+            .filter(|addr| **addr != public_addr)
+            .filter_map(|addr| self.outgoing_manager.learn_addr(*addr, false, now))
             .collect();
+        let delay_known_addresses = !dial_requests.is_empty();
+        if delay_known_addresses {
+            info!(
+                count = dial_requests.len(),
+                "dialing peers from address book before known addresses"
+            );
+            self.pending_known_addresses = known_addresses.into_iter().collect();
+        } else {
+            dial_requests.extend(self.learn_known_addresses(known_addresses, now));
+        }
 
         let mut effects = self.process_dial_requests(dial_requests);
 
-        // Start broadcasting our public listening address.
-        effects.extend(
-            effect_builder
-                .set_timeout(self.cfg.initial_gossip_delay.into())
-                .event(|_| Event::GossipOurAddress),
-        );
+        if delay_known_addresses {
+            effects.extend(
+                effect_builder
+                    .set_timeout(KNOWN_ADDRESSES_FALLBACK_DELAY)
+                    .event(|_| Event::LearnKnownAddresses),
+            );
+        }
+
+        // Start broadcasting our public listening address, unless it must be kept secret.
+        if self.sentry_topology.is_protected() {
+            info!("protected by sentries, not gossiping our address");
+        } else {
+            effects.extend(
+                effect_builder
+                    .set_timeout(self.cfg.initial_gossip_delay.into())
+                    .event(|_| Event::GossipOurAddress),
+            );
+        }
 
         // Start regular housekeeping of the outgoing connections.
         effects.extend(
@@ -410,10 +588,78 @@ where
             .expect("component not initialized properly")
     }
 
-    /// Queues a message to be sent to validator nodes in the given era.
+    /// Learns the given known addresses and marks them as unforgettable.
+    fn learn_known_addresses(
+        &mut self,
+        known_addresses: impl IntoIterator<Item = SocketAddr>,
+        now: Instant,
+    ) -> Vec<DialRequest<OutgoingHandle<P>>> {
+        known_addresses
+            .into_iter()
+            .filter_map(|addr| {
+                let request = self.outgoing_manager.learn_addr(addr, true, now);
+                if request.is_none() {
+                    // The address was already learned from the address book or through gossip.
+                    self.outgoing_manager.mark_unforgettable(addr);
+                }
+                request
+            })
+            .collect()
+    }
+
+    /// Saves the address book in the background, if it changed since it was last saved.
+    fn save_address_book(&mut self, now: Instant) -> Effects<Event<P>> {
+        self.last_address_book_save = now;
+        match self.address_book_saver() {
+            Some(saver) => saver.ignore(),
+            None => Effects::new(),
+        }
+    }
+
+    /// Returns a future writing the address book to disk on a blocking thread, if it changed since
+    /// it was last saved.
+    fn address_book_saver(&mut self) -> Option<impl Future<Output = ()> + Send + 'static> {
+        let (path, contents) = match self.address_book.take_changes(Timestamp::now())? {
+            Ok(changes) => changes,
+            Err(ref err) => {
+                warn!(err = display_error(err), "failed to save peer address book");
+                return None;
+            }
+        };
+        Some(async move {
+            match tokio::task::spawn_blocking(move || {
+                address_book::write_address_book(&path, &contents)
+            })
+            .await
+            {
+                Ok(Ok(())) => debug!("saved peer address book"),
+                Ok(Err(ref err)) => {
+                    warn!(err = display_error(err), "failed to save peer address book")
+                }
+                Err(ref err) => warn!(
+                    err = display_error(err),
+                    "failed to join peer address book saving task"
+                ),
+            }
+        })
+    }
+
+    /// Queues a message to be sent to validator nodes in the given era, and to the sentries
+    /// relaying it to the validators they protect.
+    ///
+    /// If we are protected by sentries, the message is sent to the sentries instead, which relay
+    /// it.
     fn broadcast_message_to_validators(&self, msg: Arc<Message<P>>, era_id: EraId) {
         self.net_metrics.broadcast_requests.inc();
 
+        if self.sentry_topology.is_protected() {
+            for peer_id in self.outgoing_manager.connected_peers() {
+                self.send_message(peer_id, msg.clone(), None);
+            }
+            debug!(msg = %msg, era = era_id.value(), "broadcast to sentries");
+            return;
+        }
+
         let mut total_connected_validators_in_era = 0;
         let mut total_outgoing_manager_connected_peers = 0;
 
@@ -422,6 +668,9 @@ where
             if self.outgoing_limiter.is_validator_in_era(era_id, &peer_id) {
                 total_connected_validators_in_era += 1;
                 self.send_message(peer_id, msg.clone(), None)
+            } else if self.consensus_relays.contains(&peer_id) {
+                // Validators protected by sentries are only reachable through them.
+                self.send_message(peer_id, msg.clone(), None)
             }
         }
 
@@ -445,14 +694,35 @@ where
     ) -> HashSet<NodeId> {
         let is_validator_in_era =
             |era: EraId, peer_id: &NodeId| self.outgoing_limiter.is_validator_in_era(era, peer_id);
-        let peer_ids = choose_gossip_peers(
+        // Peers with a poor reputation are only chosen if there are not enough other peers.
+        let now = Instant::now();
+        // Peers running older versions would not understand reconciliation filters.
+        let requires_reconciliation = msg.payload_requires_reconciliation();
+        let (preferred, deprioritized): (Vec<_>, Vec<_>) = self
+            .outgoing_manager
+            .connected_peers()
+            .filter(|peer_id| {
+                !requires_reconciliation || self.reconciliation_peers.contains(peer_id)
+            })
+            .partition(|peer_id| !self.peer_scores.is_deprioritized(peer_id, now));
+        let mut peer_ids = choose_gossip_peers(
             rng,
             gossip_target,
             count,
             exclude.clone(),
-            self.outgoing_manager.connected_peers(),
-            is_validator_in_era,
+            preferred.into_iter(),
+            &is_validator_in_era,
         );
+        if peer_ids.len() < count {
+            peer_ids.extend(choose_gossip_peers(
+                rng,
+                gossip_target,
+                count - peer_ids.len(),
+                exclude.clone(),
+                deprioritized.into_iter(),
+                &is_validator_in_era,
+            ));
+        }
 
         // todo!() - consider sampling more validators (for example: 10%, but not fewer than 5)
 
@@ -490,6 +760,11 @@ where
         msg: Arc<Message<P>>,
         opt_responder: Option<AutoClosingResponder<()>>,
     ) {
+        if msg.payload_requires_reconciliation() && !self.reconciliation_peers.contains(&dest) {
+            debug!(our_id=%self.context.our_id(), %dest, ?msg, "dropped outgoing message, peer does not accept reconciliation");
+            return;
+        }
+
         // Try to send the message.
         if let Some(connection) = self.outgoing_manager.get_route(dest) {
             if msg.payload_is_unsafe_for_syncing_nodes() && self.syncing_nodes.contains(&dest) {
@@ -499,12 +774,24 @@ where
                 error!(kind=%msg.classify(), node_id=%dest, "sending unsafe message to syncing node");
             }
 
+            if let Some(capture) = &self.capture {
+                capture.record(CaptureDirection::Outgoing, dest, &msg);
+            }
+
             if let Err(msg) = connection.sender.send((msg, opt_responder)) {
                 // We lost the connection, but that fact has not reached us yet.
                 warn!(our_id=%self.context.our_id(), %dest, ?msg, "dropped outgoing message, lost connection");
             } else {
                 self.net_metrics.queued_messages.inc();
             }
+        } else if self.sentry_topology.is_protected()
+            && matches!(msg.classify(), MessageKind::Consensus)
+        {
+            // We are only connected to our sentries, which relay consensus messages on.
+            debug!(our_id=%self.context.our_id(), %dest, ?msg, "sending message through sentries");
+            for peer_id in self.outgoing_manager.connected_peers() {
+                self.send_message(peer_id, msg.clone(), None);
+            }
         } else {
             // We are not connected, so the reconnection is likely already in progress.
             debug!(our_id=%self.context.our_id(), %dest, ?msg, "dropped outgoing message, no connection");
@@ -573,11 +860,15 @@ where
 
                 info!(%public_addr, "new incoming connection established");
 
-                // Learn the address the peer gave us.
-                let dial_requests =
-                    self.outgoing_manager
-                        .learn_addr(public_addr, false, Instant::now());
-                let mut effects = self.process_dial_requests(dial_requests);
+                // Learn the address the peer gave us, unless we only connect to our sentries.
+                let mut effects = if self.sentry_topology.is_protected() {
+                    Effects::new()
+                } else {
+                    let dial_requests =
+                        self.outgoing_manager
+                            .learn_addr(public_addr, false, Instant::now());
+                    self.process_dial_requests(dial_requests)
+                };
 
                 // Update connection symmetries.
                 if self
@@ -722,6 +1013,7 @@ where
                 let mut requests = Vec::new();
 
                 if let Some(justification) = self.is_blockable_offense_for_outgoing(&error) {
+                    self.address_book.forget(&peer_addr);
                     requests.extend(
                         self.outgoing_manager
                             .block_addr(peer_addr, now, justification)
@@ -730,6 +1022,7 @@ where
                 }
 
                 // Now we can proceed with the regular updates.
+                self.address_book.record_failure(peer_addr);
                 requests.extend(
                     self.outgoing_manager
                         .handle_dial_outcome(DialOutcome::Failed {
@@ -745,6 +1038,7 @@ where
             OutgoingConnection::Loopback { peer_addr } => {
                 // Loopback connections are marked, but closed.
                 info!("successful outgoing loopback connection, will be dropped");
+                self.address_book.forget(&peer_addr);
                 let request = self
                     .outgoing_manager
                     .handle_dial_outcome(DialOutcome::Loopback { addr: peer_addr });
@@ -756,8 +1050,12 @@ where
                 peer_consensus_public_key,
                 sink,
                 is_syncing,
+                relays_consensus,
+                accepts_reconciliation,
             } => {
                 info!("new outgoing connection established");
+                self.address_book
+                    .record_success(peer_addr, peer_id, Timestamp::now());
 
                 let (sender, receiver) = mpsc::unbounded_channel();
                 let handle = OutgoingHandle { sender, peer_addr };
@@ -783,13 +1081,28 @@ where
                     self.connection_completed(peer_id);
                     self.update_syncing_nodes_set(peer_id, is_syncing);
                 }
+                if relays_consensus {
+                    if self.trusted_sentries.contains(&peer_id) {
+                        self.consensus_relays.insert(peer_id);
+                    } else {
+                        warn!(
+                            "ignoring announcement to relay consensus messages by untrusted peer"
+                        );
+                    }
+                }
+                if accepts_reconciliation {
+                    self.reconciliation_peers.insert(peer_id);
+                }
 
                 effects.extend(
                     tasks::message_sender(
                         receiver,
                         sink,
                         self.outgoing_limiter
-                            .create_handle(peer_id, peer_consensus_public_key),
+                            .create_handle(peer_id, peer_consensus_public_key.clone()),
+                        OutgoingLanes::new(&self.cfg.lanes, &self.net_metrics.lanes),
+                        self.lane_limiters
+                            .create_handles(peer_id, peer_consensus_public_key),
                         self.net_metrics.queued_messages.clone(),
                     )
                     .instrument(span)
@@ -866,9 +1179,21 @@ where
         peer_id: NodeId,
         peer_addr: SocketAddr,
     ) -> Effects<Event<P>> {
+        // A connection that was replaced closes once its queue is drained, while the connection
+        // replacing it remains open and routed.
+        if let Some(handle) = self.outgoing_manager.get_route(peer_id) {
+            if handle.peer_addr == peer_addr && !handle.sender.is_closed() {
+                debug!(%peer_addr, "replaced outgoing connection closed");
+                return Effects::new();
+            }
+        }
+
         let requests = self
             .outgoing_manager
             .handle_connection_drop(peer_addr, Instant::now());
+        self.address_book.record_seen(peer_addr, Timestamp::now());
+        // The connection is re-established with our current identity.
+        self.draining_outgoing.remove(&peer_addr);
 
         self.connection_symmetries
             .entry(peer_id)
@@ -876,6 +1201,9 @@ where
             .unmark_outgoing(Instant::now());
 
         self.outgoing_limiter.remove_connected_validator(&peer_id);
+        self.lane_limiters.remove_connected_validator(&peer_id);
+        self.consensus_relays.remove(&peer_id);
+        self.reconciliation_peers.remove(&peer_id);
 
         self.process_dial_requests(requests)
     }
@@ -899,7 +1227,8 @@ where
                         }),
                 ),
                 DialRequest::Disconnect { handle: _, span } => {
-                    // Dropping the `handle` is enough to signal the connection to shutdown.
+                    // Dropping the `handle` is enough to signal the connection to shutdown, after
+                    // the messages already queued on it have been sent.
                     span.in_scope(|| {
                         debug!("dropping connection, as requested");
                     })
@@ -929,6 +1258,10 @@ where
     where
         REv: FromIncoming<P> + From<PeerBehaviorAnnouncement>,
     {
+        if let Some(capture) = &self.capture {
+            capture.record(CaptureDirection::Incoming, peer_id, &msg);
+        }
+
         span.in_scope(|| match msg {
             Message::Handshake { .. } => {
                 // We should never receive a handshake message on an established connection. Simply
@@ -945,7 +1278,8 @@ where
             }
             Message::Pong { nonce } => {
                 // Record the time the pong arrived and forward it to outgoing.
-                let pong = TaggedTimestamp::from_parts(Instant::now(), nonce);
+                let now = Instant::now();
+                let pong = TaggedTimestamp::from_parts(now, nonce);
                 if self.outgoing_manager.record_pong(peer_id, pong) {
                     effect_builder
                         .announce_block_peer_with_justification(
@@ -954,15 +1288,65 @@ where
                         )
                         .ignore()
                 } else {
+                    if self.outgoing_manager.is_latest_pong(peer_id, nonce) {
+                        // A timely pong can never lower the score, so the peer cannot get banned.
+                        self.peer_scores
+                            .observe(peer_id, PeerObservation::PongReceived, now);
+                    }
                     Effects::new()
                 }
             }
             Message::Payload(payload) => {
+                if let Some(addr) = payload.gossiped_address() {
+                    if self.sentry_topology.is_protected_address(&addr) {
+                        debug!(%addr, "dropping gossip of protected address");
+                        return Effects::new();
+                    }
+                }
+                if self.sentry_topology.is_sentry()
+                    && matches!(payload.message_kind(), MessageKind::Consensus)
+                {
+                    self.relay_consensus_message(peer_id, &payload);
+                }
                 effect_builder.announce_incoming(peer_id, payload).ignore()
             }
         })
     }
 
+    /// Returns `true` if the peer is a node protected by us, acting as its sentry.
+    fn is_protected_peer(&self, peer_id: NodeId) -> bool {
+        self.outgoing_manager
+            .get_route(peer_id)
+            .map_or(false, |handle| {
+                self.sentry_topology.is_protected_address(&handle.peer_addr)
+            })
+    }
+
+    /// Relays a consensus message received by a sentry.
+    ///
+    /// Messages from protected nodes are passed on to the validators of the active era, the
+    /// other sentries and the other protected nodes, while messages from anyone else, including
+    /// replies addressed to us on behalf of the protected nodes, are passed on to the protected
+    /// nodes.
+    fn relay_consensus_message(&self, sender: NodeId, payload: &P) {
+        let sender_is_protected = self.is_protected_peer(sender);
+        let msg = Arc::new(Message::Payload(payload.clone()));
+        for peer_id in self.outgoing_manager.connected_peers() {
+            if peer_id == sender {
+                continue;
+            }
+            if self.is_protected_peer(peer_id)
+                || (sender_is_protected
+                    && (self.consensus_relays.contains(&peer_id)
+                        || self
+                            .outgoing_limiter
+                            .is_validator_in_era(self.active_era, &peer_id)))
+            {
+                self.send_message(peer_id, msg.clone(), None);
+            }
+        }
+    }
+
     /// Emits an announcement that a connection has been completed.
     fn connection_completed(&self, peer_id: NodeId) {
         trace!(num_peers = self.peers().len(), new_peer=%peer_id, "connection complete");
@@ -1005,17 +1389,134 @@ where
         ret
     }
 
+    /// Returns up to `count` fully-connected peers in random order, where peers with a poor
+    /// reputation are only included if there are not enough other peers.
     pub(crate) fn fully_connected_peers_random(
         &self,
         rng: &mut NodeRng,
         count: usize,
     ) -> Vec<NodeId> {
-        self.connection_symmetries
+        let now = Instant::now();
+        let (preferred, deprioritized): (Vec<_>, Vec<_>) = self
+            .connection_symmetries
             .iter()
             .filter_map(|(node_id, sym)| {
                 matches!(sym, ConnectionSymmetry::Symmetric { .. }).then(|| *node_id)
             })
-            .choose_multiple(rng, count)
+            .partition(|node_id| !self.peer_scores.is_deprioritized(node_id, now));
+        let mut peers = preferred.into_iter().choose_multiple(rng, count);
+        if peers.len() < count {
+            let missing = count - peers.len();
+            peers.extend(deprioritized.into_iter().choose_multiple(rng, missing));
+        }
+        peers
+    }
+
+    /// Returns the current reputation scores of peers.
+    pub(crate) fn peer_scores(&self) -> BTreeMap<NodeId, i32> {
+        self.peer_scores.snapshot(Instant::now())
+    }
+
+    /// Rotates our identity, loading it again from the configuration.
+    ///
+    /// All new connections use the new identity right away. Outgoing connections established
+    /// before are replaced one after the other during the drain period, each staying in use until
+    /// its replacement is established. After the drain period, the remaining incoming ones are
+    /// closed, so that peers reconnect to us using our new identity.
+    ///
+    /// Returns our new [`NodeId`].
+    fn rotate_identity(
+        &mut self,
+        effect_builder: EffectBuilder<REv>,
+    ) -> (Result<NodeId, String>, Effects<Event<P>>) {
+        let previous_id = self.context.our_id();
+        let rotated = match self.context.identity().rotate(&self.cfg) {
+            Ok(rotated) => rotated,
+            Err(ref err) => {
+                warn!(err = display_error(err), "failed to load new identity");
+                return (
+                    Err(format!(
+                        "failed to load new identity: {}",
+                        display_error(err)
+                    )),
+                    Effects::new(),
+                );
+            }
+        };
+        let our_id = match self.context.rotate_identity(rotated) {
+            Ok(our_id) => our_id,
+            Err(ref err) => {
+                warn!(err = display_error(err), "failed to rotate identity");
+                return (
+                    Err(format!("failed to rotate identity: {}", display_error(err))),
+                    Effects::new(),
+                );
+            }
+        };
+        self.identity_rotations += 1;
+        info!(%previous_id, %our_id, "rotated network identity");
+
+        // Incoming connections established from now on are closed separately from the old ones. If
+        // a previous rotation is still being drained, its connections are closed right away.
+        let channel_management = self
+            .channel_management
+            .as_mut()
+            .expect("component not initialized properly");
+        let (close_incoming_sender, close_incoming_receiver) = watch::channel(());
+        channel_management.retiring_incoming_sender = channel_management
+            .close_incoming_sender
+            .replace(close_incoming_sender);
+        channel_management.close_incoming_receiver = close_incoming_receiver;
+
+        // Spread replacing the outgoing connections evenly across the drain period.
+        let drain_period = Duration::from(self.cfg.identity_drain_period);
+        self.draining_outgoing = self
+            .outgoing_manager
+            .connected_peers()
+            .filter_map(|peer_id| self.outgoing_manager.get_addr(peer_id))
+            .collect();
+        let count = self.draining_outgoing.len() as u32;
+        let mut effects: Effects<Event<P>> = self
+            .draining_outgoing
+            .iter()
+            .enumerate()
+            .flat_map(|(index, &peer_addr)| {
+                effect_builder
+                    .set_timeout(drain_period / count * index as u32)
+                    .event(move |_| Event::ReplaceOutgoing { peer_addr })
+            })
+            .collect();
+        let rotation = self.identity_rotations;
+        effects.extend(
+            effect_builder
+                .set_timeout(drain_period)
+                .event(move |_| Event::RetireIdentity { rotation }),
+        );
+
+        (Ok(our_id), effects)
+    }
+
+    /// Blocks the current outgoing address of a peer and forgets its reputation score.
+    fn block_peer(
+        &mut self,
+        peer_id: NodeId,
+        justification: BlocklistJustification,
+        now: Instant,
+    ) -> Effects<Event<P>> {
+        self.peer_scores.reset(&peer_id);
+
+        // TODO: We do not have a proper by-node-ID blocklist, but rather only block the current
+        // outgoing address of a peer.
+        info!(%peer_id, %justification, "adding peer to blocklist");
+
+        if let Some(addr) = self.outgoing_manager.get_addr(peer_id) {
+            self.address_book.forget(&addr);
+            let requests = self.outgoing_manager.block_addr(addr, now, justification);
+            self.process_dial_requests(requests)
+        } else {
+            // Peer got away with it, no longer an outgoing connection.
+            Effects::new()
+        }
     }
 
     pub(crate) fn has_sufficient_fully_connected_peers(&self) -> bool {
@@ -1044,6 +1545,7 @@ where
                 // Close the shutdown socket, causing the server to exit.
                 drop(channel_management.shutdown_sender.take());
                 drop(channel_management.close_incoming_sender.take());
+                drop(channel_management.retiring_incoming_sender.take());
 
                 // Wait for the server to exit cleanly.
                 if let Some(join_handle) = channel_management.server_join_handle.take() {
@@ -1060,6 +1562,11 @@ where
                 }
             }
 
+            // Save the address book, so it is available after a restart.
+            if let Some(saver) = self.address_book_saver() {
+                saver.await;
+            }
+
             // Ensure there are no ongoing metrics updates.
             utils::wait_for_arc_drop(
                 self.net_metrics,
@@ -1168,6 +1675,9 @@ where
                 | Event::GossipOurAddress
                 | Event::PeerAddressReceived(_)
                 | Event::SweepOutgoing
+                | Event::LearnKnownAddresses
+                | Event::ReplaceOutgoing { .. }
+                | Event::RetireIdentity { .. }
                 | Event::BlocklistAnnouncement(_) => {
                     warn!(
                         ?event,
@@ -1216,8 +1726,16 @@ where
                             .respond(self.fully_connected_peers_random(rng, count))
                             .ignore(),
                         NetworkInfoRequest::Insight { responder } => responder
-                            .respond(NetworkInsights::collect_from_component(self))
+                            .respond(Ok(NetworkInsights::collect_from_component(self)))
                             .ignore(),
+                        NetworkInfoRequest::PeerScores { responder } => {
+                            responder.respond(self.peer_scores()).ignore()
+                        }
+                        NetworkInfoRequest::RotateIdentity { responder } => {
+                            let (result, mut effects) = self.rotate_identity(effect_builder);
+                            effects.extend(responder.respond(result).ignore());
+                            effects
+                        }
                     }
                 }
                 Event::GossipOurAddress => {
@@ -1238,19 +1756,33 @@ where
                     effects
                 }
                 Event::PeerAddressReceived(gossiped_address) => {
-                    let requests = self.outgoing_manager.learn_addr(
-                        gossiped_address.into(),
-                        false,
-                        Instant::now(),
-                    );
-                    self.process_dial_requests(requests)
+                    let addr: SocketAddr = gossiped_address.into();
+                    // Protected nodes only connect to their sentries, and sentries must not
+                    // connect to protected nodes through gossiped addresses.
+                    if self.sentry_topology.is_protected()
+                        || self.sentry_topology.is_protected_address(&addr)
+                    {
+                        Effects::new()
+                    } else {
+                        let requests =
+                            self.outgoing_manager
+                                .learn_addr(addr, false, Instant::now());
+                        self.process_dial_requests(requests)
+                    }
                 }
                 Event::SweepOutgoing => {
                     let now = Instant::now();
                     let requests = self.outgoing_manager.perform_housekeeping(rng, now);
+                    self.peer_scores.prune(now);
 
                     let mut effects = self.process_dial_requests(requests);
 
+                    if now.saturating_duration_since(self.last_address_book_save)
+                        >= ADDRESS_BOOK_SAVE_INTERVAL
+                    {
+                        effects.extend(self.save_address_book(now));
+                    }
+
                     effects.extend(
                         effect_builder
                             .set_timeout(OUTGOING_MANAGER_SWEEP_INTERVAL)
@@ -1259,28 +1791,67 @@ where
 
                     effects
                 }
-                Event::BlocklistAnnouncement(announcement) => match announcement {
-                    PeerBehaviorAnnouncement::OffenseCommitted {
-                        offender,
-                        justification,
-                    } => {
-                        // TODO: We do not have a proper by-node-ID blocklist, but rather only block
-                        // the current outgoing address of a peer.
-                        info!(%offender, %justification, "adding peer to blocklist after transgression");
-
-                        if let Some(addr) = self.outgoing_manager.get_addr(*offender) {
-                            let requests = self.outgoing_manager.block_addr(
-                                addr,
-                                Instant::now(),
-                                *justification,
+                Event::LearnKnownAddresses => {
+                    let known_addresses = mem::take(&mut self.pending_known_addresses);
+                    let requests = self.learn_known_addresses(known_addresses, Instant::now());
+                    self.process_dial_requests(requests)
+                }
+                Event::ReplaceOutgoing { peer_addr } => {
+                    if self.draining_outgoing.remove(&peer_addr) {
+                        let request = self.outgoing_manager.replace_connection(peer_addr);
+                        self.process_dial_requests(request)
+                    } else {
+                        Effects::new()
+                    }
+                }
+                Event::RetireIdentity { rotation } => {
+                    // Only the latest rotation is retired, earlier ones were retired by it.
+                    if rotation == self.identity_rotations {
+                        if let Some(sender) =
+                            self.channel_management
+                                .as_mut()
+                                .and_then(|channel_management| {
+                                    channel_management.retiring_incoming_sender.take()
+                                })
+                        {
+                            info!(
+                                "closing incoming connections established with previous identity"
                             );
-                            self.process_dial_requests(requests)
-                        } else {
-                            // Peer got away with it, no longer an outgoing connection.
-                            Effects::new()
+                            drop(sender);
                         }
                     }
-                },
+                    Effects::new()
+                }
+                Event::BlocklistAnnouncement(announcement) => {
+                    let now = Instant::now();
+                    match announcement {
+                        PeerBehaviorAnnouncement::OffenseCommitted {
+                            offender,
+                            justification,
+                        } => {
+                            if justification.warrants_immediate_ban()
+                                || self.peer_scores.penalize(*offender, now)
+                            {
+                                self.block_peer(*offender, *justification, now)
+                            } else {
+                                info!(%offender, %justification, "lowering reputation of peer after transgression");
+                                Effects::new()
+                            }
+                        }
+                        PeerBehaviorAnnouncement::BehaviorObserved { peer, observation } => {
+                            if self.peer_scores.observe(*peer, observation, now) {
+                                let score = self.peer_scores.score(&peer, now).round() as i32;
+                                self.block_peer(
+                                    *peer,
+                                    BlocklistJustification::LowReputation { score },
+                                    now,
+                                )
+                            } else {
+                                Effects::new()
+                            }
+                        }
+                    }
+                }
             },
         }
     }
@@ -1316,15 +1887,12 @@ where
     }
 }
 
-/// Transport type alias for base encrypted connections.
-type Transport = SslStream<TcpStream>;
-
 /// A framed transport for `Message`s.
 pub(crate) type FullTransport<P> = tokio_serde::Framed<
     FramedTransport,
     Message<P>,
     Arc<Message<P>>,
-    CountingFormat<BincodeFormat>,
+    CountingFormat<CompressingFormat<BincodeFormat>>,
 >;
 
 pub(crate) type FramedTransport = tokio_util::codec::Framed<Transport, LengthDelimitedCodec>;
@@ -1332,11 +1900,13 @@ pub(crate) type FramedTransport = tokio_util::codec::Framed<Transport, LengthDel
 /// Constructs a new full transport on a stream.
 ///
 /// A full transport contains the framing as well as the encoding scheme used to send messages.
+/// Messages are compressed if compression was negotiated, and counted as sent over the wire.
 fn full_transport<P>(
     metrics: Weak<Metrics>,
     connection_id: ConnectionId,
     framed: FramedTransport,
     role: Role,
+    compressor: Option<Compressor>,
 ) -> FullTransport<P>
 where
     for<'de> P: Serialize + Deserialize<'de>,
@@ -1344,7 +1914,12 @@ where
 {
     tokio_serde::Framed::new(
         framed,
-        CountingFormat::new(metrics, connection_id, role, BincodeFormat::default()),
+        CountingFormat::new(
+            metrics.clone(),
+            connection_id,
+            role,
+            CompressingFormat::new(metrics, compressor, BincodeFormat::default()),
+        ),
     )
 }
 
@@ -0,0 +1,327 @@
+//! Persistent peer address book.
+//!
+//! The address book records the outgoing addresses the node has dialed, along with the ID of the
+//! node found at each address, when it was last seen and how often dialing it succeeded. It is
+//! persisted in the storage directory, so that after a restart the node can dial peers known to be
+//! good before falling back to the configured known addresses.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use datasize::DataSize;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use casper_types::{TimeDiff, Timestamp};
+
+use crate::types::NodeId;
+
+/// The name of the address book file in the storage directory.
+const ADDRESS_BOOK_FILENAME: &str = "peer_address_book.json";
+
+/// The maximum number of addresses kept in the address book.
+const MAX_ENTRIES: usize = 1000;
+
+/// Addresses not seen for longer than this are dropped from the address book.
+const MAX_ENTRY_AGE: TimeDiff = TimeDiff::from_seconds(14 * 24 * 60 * 60);
+
+/// The lowest success rate of dialing an address for it to be considered good.
+const MIN_SUCCESS_RATE: f64 = 0.5;
+
+/// Error loading or saving the address book.
+#[derive(Debug, Error)]
+pub(super) enum AddressBookError {
+    /// Failed to read the address book file.
+    #[error("could not read address book {}: {}", .0.display(), .1)]
+    Read(PathBuf, #[source] io::Error),
+    /// Failed to write the address book file.
+    #[error("could not write address book {}: {}", .0.display(), .1)]
+    Write(PathBuf, #[source] io::Error),
+    /// The address book file is malformed.
+    #[error("could not parse address book {}: {}", .0.display(), .1)]
+    Parse(PathBuf, #[source] serde_json::Error),
+    /// Failed to serialize the address book.
+    #[error("could not serialize address book: {0}")]
+    Serialize(#[source] serde_json::Error),
+}
+
+/// A single address book record.
+#[derive(Clone, DataSize, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct AddressBookEntry {
+    /// The address of the peer.
+    address: SocketAddr,
+    /// The ID of the node last found at the address, if any.
+    node_id: Option<NodeId>,
+    /// The last time a connection to the address was established or found alive.
+    last_seen: Timestamp,
+    /// The number of successful dials.
+    successes: u32,
+    /// The number of failed dials.
+    failures: u32,
+}
+
+impl AddressBookEntry {
+    /// Returns the fraction of dials to the address which succeeded.
+    fn success_rate(&self) -> f64 {
+        let attempts = self.successes.saturating_add(self.failures);
+        if attempts == 0 {
+            return 0.0;
+        }
+        f64::from(self.successes) / f64::from(attempts)
+    }
+
+    /// Returns `true` if the address is worth dialing first on startup.
+    fn is_good(&self) -> bool {
+        self.successes > 0 && self.success_rate() >= MIN_SUCCESS_RATE
+    }
+}
+
+/// The address book of outgoing peer addresses.
+#[derive(DataSize, Debug)]
+pub(super) struct AddressBook {
+    /// The file the address book is persisted in, or `None` if it is not persisted.
+    path: Option<PathBuf>,
+    /// The records, by address.
+    entries: BTreeMap<SocketAddr, AddressBookEntry>,
+    /// Whether the records changed since the address book was last saved.
+    is_dirty: bool,
+}
+
+impl AddressBook {
+    /// Creates an address book which is not persisted.
+    pub(super) fn new_volatile() -> Self {
+        AddressBook {
+            path: None,
+            entries: BTreeMap::new(),
+            is_dirty: false,
+        }
+    }
+
+    /// Loads the address book persisted in the given storage directory.
+    ///
+    /// If the address book does not exist or cannot be read, an empty one is created.
+    pub(super) fn load(storage_dir: &Path) -> Self {
+        let path = storage_dir.join(ADDRESS_BOOK_FILENAME);
+        let entries = match read_entries(&path) {
+            Ok(entries) => entries,
+            Err(AddressBookError::Read(_, error)) if error.kind() == io::ErrorKind::NotFound => {
+                debug!(path = %path.display(), "no peer address book found");
+                Vec::new()
+            }
+            Err(error) => {
+                warn!(%error, "starting with an empty peer address book");
+                Vec::new()
+            }
+        };
+
+        let mut address_book = AddressBook {
+            path: Some(path),
+            entries: entries
+                .into_iter()
+                .map(|entry| (entry.address, entry))
+                .collect(),
+            is_dirty: false,
+        };
+        address_book.prune(Timestamp::now());
+        debug!(
+            entries = address_book.entries.len(),
+            "loaded peer address book"
+        );
+        address_book
+    }
+
+    /// Returns the good addresses, best first.
+    ///
+    /// Addresses are ordered by their dial success rate, then by when they were last seen.
+    pub(super) fn good_addresses(&self) -> Vec<SocketAddr> {
+        let mut good: Vec<_> = self
+            .entries
+            .values()
+            .filter(|entry| entry.is_good())
+            .collect();
+        good.sort_by(|a, b| {
+            b.success_rate()
+                .total_cmp(&a.success_rate())
+                .then_with(|| b.last_seen.cmp(&a.last_seen))
+        });
+        good.into_iter().map(|entry| entry.address).collect()
+    }
+
+    /// Records a successful dial of the given address, which led to the given node.
+    pub(super) fn record_success(&mut self, address: SocketAddr, node_id: NodeId, now: Timestamp) {
+        // The node may have moved to a new address, in which case the old one is stale.
+        self.entries
+            .retain(|addr, entry| *addr == address || entry.node_id != Some(node_id));
+
+        let entry = self
+            .entries
+            .entry(address)
+            .or_insert_with(|| AddressBookEntry {
+                address,
+                node_id: None,
+                last_seen: now,
+                successes: 0,
+                failures: 0,
+            });
+        entry.node_id = Some(node_id);
+        entry.last_seen = now;
+        entry.successes = entry.successes.saturating_add(1);
+        self.is_dirty = true;
+    }
+
+    /// Records a failed dial of the given address.
+    ///
+    /// Only addresses which were dialed successfully before are tracked.
+    pub(super) fn record_failure(&mut self, address: SocketAddr) {
+        if let Some(entry) = self.entries.get_mut(&address) {
+            entry.failures = entry.failures.saturating_add(1);
+            self.is_dirty = true;
+        }
+    }
+
+    /// Records that a connection to the given address was alive until now.
+    pub(super) fn record_seen(&mut self, address: SocketAddr, now: Timestamp) {
+        if let Some(entry) = self.entries.get_mut(&address) {
+            entry.last_seen = now;
+            self.is_dirty = true;
+        }
+    }
+
+    /// Removes the given address, e.g. because it belongs to a blocked peer or to ourselves.
+    pub(super) fn forget(&mut self, address: &SocketAddr) {
+        if self.entries.remove(address).is_some() {
+            self.is_dirty = true;
+        }
+    }
+
+    /// Returns the file to persist the address book in, along with its serialized contents, if
+    /// there were changes since it was last saved.
+    pub(super) fn take_changes(
+        &mut self,
+        now: Timestamp,
+    ) -> Option<Result<(PathBuf, Vec<u8>), AddressBookError>> {
+        let path = self.path.clone()?;
+        if !self.is_dirty {
+            return None;
+        }
+        self.prune(now);
+        self.is_dirty = false;
+        let entries: Vec<_> = self.entries.values().collect();
+        Some(
+            serde_json::to_vec_pretty(&entries)
+                .map(|contents| (path, contents))
+                .map_err(AddressBookError::Serialize),
+        )
+    }
+
+    /// Drops records which are too old, and the least recently seen ones above the size limit.
+    fn prune(&mut self, now: Timestamp) {
+        let count_before = self.entries.len();
+        self.entries
+            .retain(|_, entry| now.saturating_diff(entry.last_seen) <= MAX_ENTRY_AGE);
+        if self.entries.len() > MAX_ENTRIES {
+            let mut last_seen: Vec<_> =
+                self.entries.values().map(|entry| entry.last_seen).collect();
+            last_seen.sort_unstable_by(|a, b| b.cmp(a));
+            let cutoff = last_seen[MAX_ENTRIES - 1];
+            self.entries.retain(|_, entry| entry.last_seen >= cutoff);
+        }
+        if self.entries.len() != count_before {
+            self.is_dirty = true;
+        }
+    }
+}
+
+/// Reads the records of the address book file.
+fn read_entries(path: &Path) -> Result<Vec<AddressBookEntry>, AddressBookError> {
+    let contents =
+        fs::read(path).map_err(|error| AddressBookError::Read(path.to_owned(), error))?;
+    serde_json::from_slice(&contents)
+        .map_err(|error| AddressBookError::Parse(path.to_owned(), error))
+}
+
+/// Atomically replaces the address book file with the given contents.
+pub(super) fn write_address_book(path: &Path, contents: &[u8]) -> Result<(), AddressBookError> {
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, contents)
+        .and_then(|()| fs::rename(&temp_path, path))
+        .map_err(|error| AddressBookError::Write(path.to_owned(), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use casper_types::{testing::TestRng, TimeDiff, Timestamp};
+
+    use super::{write_address_book, AddressBook};
+    use crate::types::NodeId;
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn should_order_good_addresses_by_success_rate() {
+        let mut rng = TestRng::new();
+        let mut address_book = AddressBook::new_volatile();
+        let now = Timestamp::now();
+
+        address_book.record_success(addr(1), NodeId::random(&mut rng), now);
+        address_book.record_failure(addr(1));
+
+        address_book.record_success(addr(2), NodeId::random(&mut rng), now);
+
+        address_book.record_success(addr(3), NodeId::random(&mut rng), now);
+        address_book.record_failure(addr(3));
+        address_book.record_failure(addr(3));
+
+        // Failures of addresses never dialed successfully are not tracked.
+        address_book.record_failure(addr(4));
+
+        assert_eq!(address_book.good_addresses(), vec![addr(2), addr(1)]);
+    }
+
+    #[test]
+    fn should_forget_stale_address_of_moved_node() {
+        let mut rng = TestRng::new();
+        let mut address_book = AddressBook::new_volatile();
+        let node_id = NodeId::random(&mut rng);
+        let now = Timestamp::now();
+
+        address_book.record_success(addr(1), node_id, now);
+        address_book.record_success(addr(2), node_id, now);
+
+        assert_eq!(address_book.good_addresses(), vec![addr(2)]);
+    }
+
+    #[test]
+    fn should_persist_across_restarts() {
+        let mut rng = TestRng::new();
+        let storage_dir = tempfile::tempdir().unwrap();
+        let now = Timestamp::now();
+
+        let mut address_book = AddressBook::load(storage_dir.path());
+        assert!(address_book.good_addresses().is_empty());
+        address_book.record_success(addr(1), NodeId::random(&mut rng), now);
+        address_book.record_success(
+            addr(2),
+            NodeId::random(&mut rng),
+            now - TimeDiff::from_seconds(60),
+        );
+
+        let (path, contents) = address_book.take_changes(now).unwrap().unwrap();
+        write_address_book(&path, &contents).unwrap();
+        assert!(address_book.take_changes(now).is_none());
+
+        let reloaded = AddressBook::load(storage_dir.path());
+        assert_eq!(reloaded.entries, address_book.entries);
+        assert_eq!(reloaded.good_addresses(), vec![addr(1), addr(2)]);
+    }
+}
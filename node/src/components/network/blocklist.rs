@@ -57,6 +57,36 @@ pub(crate) enum BlocklistJustification {
     DishonestPeer,
     /// Peer sent too many finality signatures.
     SentTooManyFinalitySignatures { max_allowed: u32 },
+    /// Peer's reputation score fell below the ban threshold.
+    LowReputation {
+        /// The peer's score at the time it was blocked.
+        score: i32,
+    },
+}
+
+impl BlocklistJustification {
+    /// Returns `true` if the offense is severe enough to block the peer right away, rather than
+    /// lowering its reputation score.
+    ///
+    /// Provably malicious or incompatible behavior is blocked immediately. Only offenses an honest
+    /// but slow or lagging peer could commit are penalized gradually.
+    pub(crate) fn warrants_immediate_ban(&self) -> bool {
+        match self {
+            BlocklistJustification::SentBadItem { .. }
+            | BlocklistJustification::SentInvalidItem { .. }
+            | BlocklistJustification::SentBadFinalitySignature { .. }
+            | BlocklistJustification::SentBadBlock { .. }
+            | BlocklistJustification::SentInvalidConsensusValue { .. }
+            | BlocklistJustification::PongLimitExceeded
+            | BlocklistJustification::BadConsensusBehavior
+            | BlocklistJustification::WrongNetwork { .. }
+            | BlocklistJustification::WrongChainspecHash { .. }
+            | BlocklistJustification::MissingChainspecHash
+            | BlocklistJustification::DishonestPeer
+            | BlocklistJustification::LowReputation { .. } => true,
+            BlocklistJustification::SentTooManyFinalitySignatures { .. } => false,
+        }
+    }
 }
 
 impl Display for BlocklistJustification {
@@ -105,6 +135,9 @@ impl Display for BlocklistJustification {
                 f,
                 "sent too many finality signatures: maximum {max_allowed} signatures are allowed"
             ),
+            BlocklistJustification::LowReputation { score } => {
+                write!(f, "reputation score fell to {}", score)
+            }
         }
     }
 }
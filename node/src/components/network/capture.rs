@@ -0,0 +1,387 @@
+//! Capture of network traffic.
+//!
+//! If enabled, every message received from or sent to a peer is written to a capture file in the
+//! storage directory, along with the time and the peer's ID. Captures are split across a rotating
+//! set of files of limited size, with the oldest file being deleted once the configured number of
+//! files is exceeded.
+//!
+//! Records are written by a background thread. If it falls behind, e.g. due to a slow disk, further
+//! records are dropped and counted rather than queued without bound.
+//!
+//! Each record is prefixed with its length as a little-endian `u32`, followed by the record
+//! serialized with bincode. Captures can be read back and replayed into a test reactor to
+//! reproduce the exact sequence of messages a node received.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Weak,
+    },
+    thread::{self, JoinHandle},
+};
+
+use datasize::DataSize;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use casper_types::Timestamp;
+
+use super::{Message, Metrics};
+use crate::types::NodeId;
+
+/// The name of the capture directory within the storage directory.
+pub(super) const CAPTURE_DIR_NAME: &str = "network_capture";
+
+/// The prefix of capture file names, followed by the file's index.
+const CAPTURE_FILE_PREFIX: &str = "capture-";
+
+/// The extension of capture files.
+const CAPTURE_FILE_EXTENSION: &str = "bin";
+
+/// The number of records which may be waiting for the writer before further records are dropped.
+const CAPTURE_QUEUE_CAPACITY: usize = 4096;
+
+/// Network traffic capture configuration.
+#[derive(DataSize, Debug, Clone, Deserialize, Serialize)]
+// Disallow unknown fields to ensure config files and command-line overrides contain valid keys.
+#[serde(deny_unknown_fields)]
+pub struct CaptureConfig {
+    /// Whether to capture network traffic.
+    pub enabled: bool,
+    /// The size in bytes after which a new capture file is started.
+    pub max_file_size: u64,
+    /// The number of capture files to keep.
+    pub max_files: u32,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        CaptureConfig {
+            enabled: false,
+            max_file_size: 64 * 1024 * 1024,
+            max_files: 16,
+        }
+    }
+}
+
+/// Whether a captured message was received or sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum CaptureDirection {
+    /// The message was received from the peer.
+    Incoming,
+    /// The message was sent to the peer.
+    Outgoing,
+}
+
+/// A single captured message.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct CaptureRecord<M> {
+    /// The time the message was received or queued for sending.
+    pub(crate) timestamp: Timestamp,
+    /// The peer the message was received from or sent to.
+    pub(crate) peer_id: NodeId,
+    /// Whether the message was received or sent.
+    pub(crate) direction: CaptureDirection,
+    /// The message itself.
+    pub(crate) message: M,
+}
+
+/// Handle to the background thread writing the capture files.
+#[derive(Debug)]
+pub(super) struct Capture {
+    /// Sender of serialized records to the writer thread.
+    sender: Option<SyncSender<Vec<u8>>>,
+    /// Join handle of the writer thread.
+    writer_handle: Option<JoinHandle<()>>,
+    /// Metrics counting dropped records.
+    metrics: Weak<Metrics>,
+}
+
+impl Capture {
+    /// Starts capturing into the given directory.
+    pub(super) fn start(
+        config: &CaptureConfig,
+        directory: &Path,
+        metrics: Weak<Metrics>,
+    ) -> io::Result<Self> {
+        let writer = CaptureWriter::new(config, directory)?;
+        let (sender, receiver) = mpsc::sync_channel(CAPTURE_QUEUE_CAPACITY);
+        let writer_handle = thread::Builder::new()
+            .name("network-capture".to_string())
+            .spawn(move || writer.run(receiver))?;
+        debug!(directory = %directory.display(), "capturing network traffic");
+        Ok(Capture {
+            sender: Some(sender),
+            writer_handle: Some(writer_handle),
+            metrics,
+        })
+    }
+
+    /// Records a message received from or sent to the given peer.
+    pub(super) fn record<P: Serialize>(
+        &self,
+        direction: CaptureDirection,
+        peer_id: NodeId,
+        message: &Message<P>,
+    ) {
+        let record = CaptureRecord {
+            timestamp: Timestamp::now(),
+            peer_id,
+            direction,
+            message,
+        };
+        let serialized = match bincode::serialize(&record) {
+            Ok(serialized) => serialized,
+            Err(error) => {
+                warn!(%error, "failed to serialize captured message");
+                return;
+            }
+        };
+        if let Some(sender) = &self.sender {
+            match sender.try_send(serialized) {
+                Ok(()) => (),
+                Err(TrySendError::Full(_)) => Metrics::record_dropped_capture_record(&self.metrics),
+                // The writer only stops after an I/O error, which it already logged.
+                Err(TrySendError::Disconnected(_)) => (),
+            }
+        }
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        // Closing the channel stops the writer once it has written all pending records.
+        drop(self.sender.take());
+        if let Some(writer_handle) = self.writer_handle.take() {
+            if writer_handle.join().is_err() {
+                warn!("network capture writer panicked");
+            }
+        }
+    }
+}
+
+/// Writer of a rotating set of capture files.
+struct CaptureWriter {
+    /// The directory holding the capture files.
+    directory: PathBuf,
+    /// The size after which a new file is started.
+    max_file_size: u64,
+    /// The number of files to keep.
+    max_files: u64,
+    /// The index of the current file.
+    index: u64,
+    /// The current file.
+    file: BufWriter<File>,
+    /// The number of bytes written to the current file.
+    file_size: u64,
+}
+
+impl CaptureWriter {
+    /// Creates a writer, continuing after the newest existing capture file.
+    fn new(config: &CaptureConfig, directory: &Path) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+        let index = capture_file_indices(directory)?
+            .last()
+            .map_or(0, |newest| newest + 1);
+        let mut writer = CaptureWriter {
+            directory: directory.to_owned(),
+            max_file_size: config.max_file_size,
+            max_files: u64::from(config.max_files.max(1)),
+            index,
+            file: BufWriter::new(File::create(capture_file_path(directory, index))?),
+            file_size: 0,
+        };
+        writer.remove_old_files()?;
+        Ok(writer)
+    }
+
+    /// Writes all records received through the channel until it is closed.
+    fn run(mut self, receiver: Receiver<Vec<u8>>) {
+        while let Ok(record) = receiver.recv() {
+            let result = self.write(&record).and_then(|()| {
+                // Write all records that queued up in the meantime before flushing.
+                while let Ok(record) = receiver.try_recv() {
+                    self.write(&record)?;
+                }
+                self.file.flush()
+            });
+            if let Err(error) = result {
+                warn!(%error, "failed to write network capture, stopping capture");
+                return;
+            }
+        }
+    }
+
+    /// Writes a single serialized record, rotating the files if necessary.
+    fn write(&mut self, record: &[u8]) -> io::Result<()> {
+        if self.file_size > 0 && self.file_size + record.len() as u64 + 4 > self.max_file_size {
+            self.rotate()?;
+        }
+        let length = u32::try_from(record.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "record too large"))?;
+        self.file.write_all(&length.to_le_bytes())?;
+        self.file.write_all(record)?;
+        self.file_size += record.len() as u64 + 4;
+        Ok(())
+    }
+
+    /// Starts a new capture file, deleting the oldest ones above the limit.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        self.index += 1;
+        self.file = BufWriter::new(File::create(capture_file_path(
+            &self.directory,
+            self.index,
+        ))?);
+        self.file_size = 0;
+        self.remove_old_files()
+    }
+
+    fn remove_old_files(&self) -> io::Result<()> {
+        for index in capture_file_indices(&self.directory)? {
+            if index + self.max_files <= self.index {
+                fs::remove_file(capture_file_path(&self.directory, index))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the path of the capture file with the given index.
+fn capture_file_path(directory: &Path, index: u64) -> PathBuf {
+    directory.join(format!(
+        "{}{:08}.{}",
+        CAPTURE_FILE_PREFIX, index, CAPTURE_FILE_EXTENSION
+    ))
+}
+
+/// Returns the indices of the capture files in the given directory, oldest first.
+fn capture_file_indices(directory: &Path) -> io::Result<Vec<u64>> {
+    let mut indices = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(CAPTURE_FILE_EXTENSION) {
+            continue;
+        }
+        let maybe_index = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.strip_prefix(CAPTURE_FILE_PREFIX))
+            .and_then(|index| index.parse().ok());
+        if let Some(index) = maybe_index {
+            indices.push(index);
+        }
+    }
+    indices.sort_unstable();
+    Ok(indices)
+}
+
+/// Reads all records of the capture files in the given directory, oldest first.
+#[cfg(test)]
+pub(crate) fn read_capture<P>(directory: &Path) -> io::Result<Vec<CaptureRecord<Message<P>>>>
+where
+    P: serde::de::DeserializeOwned,
+{
+    let mut records = Vec::new();
+    for index in capture_file_indices(directory)? {
+        let contents = fs::read(capture_file_path(directory, index))?;
+        let mut remainder = contents.as_slice();
+        while !remainder.is_empty() {
+            let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "truncated record");
+            if remainder.len() < 4 {
+                return Err(truncated());
+            }
+            let (length, rest) = remainder.split_at(4);
+            let length = u32::from_le_bytes([length[0], length[1], length[2], length[3]]) as usize;
+            if rest.len() < length {
+                return Err(truncated());
+            }
+            let (record, rest) = rest.split_at(length);
+            records.push(
+                bincode::deserialize(record)
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?,
+            );
+            remainder = rest;
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Weak;
+
+    use casper_types::testing::TestRng;
+
+    use super::{
+        capture_file_indices, read_capture, Capture, CaptureConfig, CaptureDirection, Message,
+    };
+    use crate::types::NodeId;
+
+    #[test]
+    fn should_read_back_captured_messages() {
+        let mut rng = TestRng::new();
+        let directory = tempfile::tempdir().unwrap();
+        let peer_id = NodeId::random(&mut rng);
+
+        let capture =
+            Capture::start(&CaptureConfig::default(), directory.path(), Weak::new()).unwrap();
+        capture.record(
+            CaptureDirection::Incoming,
+            peer_id,
+            &Message::Payload("first".to_string()),
+        );
+        capture.record(
+            CaptureDirection::Outgoing,
+            peer_id,
+            &Message::Payload("second".to_string()),
+        );
+        drop(capture);
+
+        let records = read_capture::<String>(directory.path()).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].peer_id, peer_id);
+        assert_eq!(records[0].direction, CaptureDirection::Incoming);
+        assert!(matches!(&records[0].message, Message::Payload(text) if text == "first"));
+        assert_eq!(records[1].direction, CaptureDirection::Outgoing);
+        assert!(matches!(&records[1].message, Message::Payload(text) if text == "second"));
+    }
+
+    #[test]
+    fn should_rotate_files() {
+        let mut rng = TestRng::new();
+        let directory = tempfile::tempdir().unwrap();
+        let peer_id = NodeId::random(&mut rng);
+        let config = CaptureConfig {
+            enabled: true,
+            max_file_size: 200,
+            max_files: 3,
+        };
+
+        let capture = Capture::start(&config, directory.path(), Weak::new()).unwrap();
+        for index in 0..20 {
+            capture.record(
+                CaptureDirection::Incoming,
+                peer_id,
+                &Message::Payload(format!("{:064}", index)),
+            );
+        }
+        drop(capture);
+
+        let indices = capture_file_indices(directory.path()).unwrap();
+        assert_eq!(indices.len(), 3);
+        let records = read_capture::<String>(directory.path()).unwrap();
+        let last = records.last().expect("should have records");
+        assert!(matches!(&last.message, Message::Payload(text) if text.ends_with("19")));
+
+        // A restarted capture continues after the newest file.
+        let capture = Capture::start(&config, directory.path(), Weak::new()).unwrap();
+        drop(capture);
+        let restarted = capture_file_indices(directory.path()).unwrap();
+        assert_eq!(restarted.last(), Some(&(indices.last().unwrap() + 1)));
+        assert_eq!(restarted.len(), 3);
+    }
+}
@@ -0,0 +1,277 @@
+//! Compression of network messages.
+//!
+//! Peers advertise whether they accept compressed messages in their handshake. If both ends of a
+//! connection do, every message sent over it is prefixed with a single tag byte indicating whether
+//! the remainder is zstd-compressed. Only messages above a configurable size are compressed, as
+//! small messages like consensus votes barely shrink and would only cost CPU time.
+
+use std::{
+    io::{self, Read},
+    pin::Pin,
+    sync::{Arc, Weak},
+};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use datasize::DataSize;
+use pin_project::pin_project;
+use serde::{Deserialize, Serialize};
+use tokio_serde::{Deserializer, Serializer};
+use zstd::stream::read::Decoder;
+
+use super::{Message, Metrics};
+
+/// Tag byte of a message sent as-is.
+const TAG_UNCOMPRESSED: u8 = 0;
+
+/// Tag byte of a zstd-compressed message.
+const TAG_ZSTD: u8 = 1;
+
+/// Message compression configuration.
+#[derive(DataSize, Debug, Clone, Deserialize, Serialize)]
+// Disallow unknown fields to ensure config files and command-line overrides contain valid keys.
+#[serde(deny_unknown_fields)]
+pub struct CompressionConfig {
+    /// Whether to accept and send compressed messages.
+    pub enabled: bool,
+    /// Messages smaller than this, in bytes, are never compressed.
+    pub threshold: u32,
+    /// The zstd compression level.
+    pub level: i32,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        CompressionConfig {
+            enabled: true,
+            threshold: 1024,
+            level: 3,
+        }
+    }
+}
+
+/// The compression settings negotiated for a single connection.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Compressor {
+    /// Messages smaller than this are sent uncompressed.
+    threshold: usize,
+    /// The zstd compression level.
+    level: i32,
+    /// The largest size a message may have after decompression.
+    maximum_message_size: usize,
+}
+
+impl Compressor {
+    /// Negotiates compression for a connection.
+    ///
+    /// Returns `None` unless we and the peer both accept compressed messages.
+    pub(super) fn negotiate(
+        config: &CompressionConfig,
+        peer_accepts_compression: bool,
+        maximum_message_size: u32,
+    ) -> Option<Self> {
+        if !config.enabled || !peer_accepts_compression {
+            return None;
+        }
+        Some(Compressor {
+            threshold: config.threshold as usize,
+            level: config.level,
+            maximum_message_size: maximum_message_size as usize,
+        })
+    }
+
+    /// Frames a serialized message, compressing it if it is large enough to be worth it.
+    fn compress(&self, serialized: Bytes) -> io::Result<Bytes> {
+        if serialized.len() >= self.threshold {
+            let compressed = zstd::bulk::compress(&serialized, self.level)?;
+            // Incompressible data can grow slightly, in which case we send it as-is.
+            if compressed.len() < serialized.len() {
+                return Ok(tagged(TAG_ZSTD, &compressed));
+            }
+        }
+        Ok(tagged(TAG_UNCOMPRESSED, &serialized))
+    }
+
+    /// Unframes a received message, decompressing it if necessary.
+    fn decompress(&self, framed: &BytesMut) -> io::Result<BytesMut> {
+        let (tag, body) = framed
+            .split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing compression tag"))?;
+        match *tag {
+            TAG_UNCOMPRESSED => Ok(BytesMut::from(body)),
+            TAG_ZSTD => {
+                // Reading at most one byte more than allowed guards against decompression bombs
+                // without allocating a buffer of the maximum message size up front.
+                let mut decompressed = Vec::new();
+                Decoder::with_buffer(body)?
+                    .take(self.maximum_message_size as u64 + 1)
+                    .read_to_end(&mut decompressed)?;
+                if decompressed.len() > self.maximum_message_size {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "decompressed message exceeds the maximum size of {} bytes",
+                            self.maximum_message_size
+                        ),
+                    ));
+                }
+                Ok(BytesMut::from(decompressed.as_slice()))
+            }
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown compression tag {}", other),
+            )),
+        }
+    }
+}
+
+/// Returns the given body prefixed with the tag byte.
+fn tagged(tag: u8, body: &[u8]) -> Bytes {
+    let mut framed = BytesMut::with_capacity(body.len() + 1);
+    framed.put_u8(tag);
+    framed.put_slice(body);
+    framed.freeze()
+}
+
+/// A serializer/deserializer wrapper compressing network messages.
+///
+/// Messages pass through unchanged if compression was not negotiated for the connection.
+#[pin_project]
+#[derive(Debug)]
+pub struct CompressingFormat<F> {
+    /// The actual serializer performing the work.
+    #[pin]
+    inner: F,
+    /// The negotiated compression, if any.
+    compressor: Option<Compressor>,
+    /// Metrics to update.
+    metrics: Weak<Metrics>,
+}
+
+impl<F> CompressingFormat<F> {
+    /// Creates a new compressing formatter.
+    pub(super) fn new(metrics: Weak<Metrics>, compressor: Option<Compressor>, inner: F) -> Self {
+        CompressingFormat {
+            inner,
+            compressor,
+            metrics,
+        }
+    }
+}
+
+impl<F, P> Serializer<Arc<Message<P>>> for CompressingFormat<F>
+where
+    F: Serializer<Arc<Message<P>>, Error = io::Error>,
+{
+    type Error = io::Error;
+
+    #[inline]
+    fn serialize(self: Pin<&mut Self>, item: &Arc<Message<P>>) -> Result<Bytes, Self::Error> {
+        let this = self.project();
+        let serialized = F::serialize(this.inner, item)?;
+        match this.compressor {
+            Some(compressor) => {
+                let uncompressed_size = serialized.len() as u64;
+                let framed = compressor.compress(serialized)?;
+                Metrics::record_compression_out(
+                    this.metrics,
+                    uncompressed_size,
+                    framed.len() as u64,
+                );
+                Ok(framed)
+            }
+            None => Ok(serialized),
+        }
+    }
+}
+
+impl<F, P> Deserializer<Message<P>> for CompressingFormat<F>
+where
+    F: Deserializer<Message<P>, Error = io::Error>,
+{
+    type Error = io::Error;
+
+    #[inline]
+    fn deserialize(self: Pin<&mut Self>, src: &BytesMut) -> Result<Message<P>, Self::Error> {
+        let this = self.project();
+        match this.compressor {
+            Some(compressor) => {
+                let unframed = compressor.decompress(src)?;
+                Metrics::record_compression_in(
+                    this.metrics,
+                    unframed.len() as u64,
+                    src.len() as u64,
+                );
+                F::deserialize(this.inner, &unframed)
+            }
+            None => F::deserialize(this.inner, src),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+
+    use super::{CompressionConfig, Compressor, TAG_UNCOMPRESSED, TAG_ZSTD};
+
+    fn compressor() -> Compressor {
+        Compressor::negotiate(&CompressionConfig::default(), true, 1024 * 1024).unwrap()
+    }
+
+    #[test]
+    fn should_negotiate_only_if_both_sides_accept() {
+        let config = CompressionConfig::default();
+        assert!(Compressor::negotiate(&config, true, 1024).is_some());
+        assert!(Compressor::negotiate(&config, false, 1024).is_none());
+
+        let disabled = CompressionConfig {
+            enabled: false,
+            ..CompressionConfig::default()
+        };
+        assert!(Compressor::negotiate(&disabled, true, 1024).is_none());
+    }
+
+    #[test]
+    fn should_compress_large_messages_only() {
+        let compressor = compressor();
+
+        let small = Bytes::from(vec![7; 100]);
+        let framed = compressor.compress(small.clone()).unwrap();
+        assert_eq!(framed[0], TAG_UNCOMPRESSED);
+        assert_eq!(
+            compressor.decompress(&BytesMut::from(&framed[..])).unwrap(),
+            small
+        );
+
+        let large = Bytes::from(vec![7; 100_000]);
+        let framed = compressor.compress(large.clone()).unwrap();
+        assert_eq!(framed[0], TAG_ZSTD);
+        assert!(framed.len() < 1000);
+        assert_eq!(
+            compressor.decompress(&BytesMut::from(&framed[..])).unwrap(),
+            large
+        );
+    }
+
+    #[test]
+    fn should_reject_oversized_and_malformed_messages() {
+        let compressor = compressor();
+        let framed_zstd = |size: usize| {
+            let mut framed = vec![TAG_ZSTD];
+            framed.extend(zstd::bulk::compress(&vec![0; size], 3).unwrap());
+            BytesMut::from(framed.as_slice())
+        };
+        assert!(compressor.decompress(&framed_zstd(1024 * 1024)).is_ok());
+        assert!(compressor
+            .decompress(&framed_zstd(1024 * 1024 + 1))
+            .is_err());
+        assert!(compressor
+            .decompress(&framed_zstd(2 * 1024 * 1024))
+            .is_err());
+
+        assert!(compressor.decompress(&BytesMut::new()).is_err());
+        assert!(compressor
+            .decompress(&BytesMut::from(&[9, 1, 2][..]))
+            .is_err());
+    }
+}
@@ -6,7 +6,10 @@ use casper_types::{ProtocolVersion, TimeDiff};
 use datasize::DataSize;
 use serde::{Deserialize, Serialize};
 
-use super::EstimatorWeights;
+use super::{
+    CaptureConfig, CompressionConfig, EstimatorWeights, LanesConfig, QuicConfig, ReputationConfig,
+    SentryConfig,
+};
 
 /// Default binding address.
 ///
@@ -32,6 +35,9 @@ const DEFAULT_MAX_ADDR_PENDING_TIME: TimeDiff = TimeDiff::from_seconds(60);
 /// Default timeout during which the handshake needs to be completed.
 const DEFAULT_HANDSHAKE_TIMEOUT: TimeDiff = TimeDiff::from_seconds(20);
 
+/// Default time during which connections are drained after rotating the identity.
+const DEFAULT_IDENTITY_DRAIN_PERIOD: TimeDiff = TimeDiff::from_seconds(60);
+
 impl Default for Config {
     fn default() -> Self {
         Config {
@@ -52,6 +58,13 @@ impl Default for Config {
             tarpit_chance: 0.2,
             max_in_flight_demands: 50,
             blocklist_retain_duration: TimeDiff::from_seconds(600),
+            identity_drain_period: DEFAULT_IDENTITY_DRAIN_PERIOD,
+            reputation: ReputationConfig::default(),
+            compression: CompressionConfig::default(),
+            lanes: LanesConfig::default(),
+            sentry: SentryConfig::default(),
+            capture: CaptureConfig::default(),
+            quic: QuicConfig::default(),
             identity: None,
         }
     }
@@ -111,6 +124,27 @@ pub struct Config {
     pub max_in_flight_demands: u32,
     /// Duration peers are kept on the block list, before being redeemed.
     pub blocklist_retain_duration: TimeDiff,
+    /// Time during which connections established before rotating the identity are replaced.
+    #[serde(default = "default_identity_drain_period")]
+    pub identity_drain_period: TimeDiff,
+    /// Peer reputation scoring configuration.
+    #[serde(default)]
+    pub reputation: ReputationConfig,
+    /// Message compression configuration.
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    /// Outgoing message lanes configuration.
+    #[serde(default)]
+    pub lanes: LanesConfig,
+    /// Sentry topology configuration.
+    #[serde(default)]
+    pub sentry: SentryConfig,
+    /// Network traffic capture configuration.
+    #[serde(default)]
+    pub capture: CaptureConfig,
+    /// QUIC transport configuration.
+    #[serde(default)]
+    pub quic: QuicConfig,
     /// Network identity configuration option.
     ///
     /// An identity will be automatically generated when starting up a node if this option is
@@ -118,6 +152,10 @@ pub struct Config {
     pub identity: Option<IdentityConfig>,
 }
 
+fn default_identity_drain_period() -> TimeDiff {
+    DEFAULT_IDENTITY_DRAIN_PERIOD
+}
+
 #[cfg(test)]
 /// Reduced gossip interval for local testing.
 const DEFAULT_TEST_GOSSIP_INTERVAL: TimeDiff = TimeDiff::from_seconds(1);
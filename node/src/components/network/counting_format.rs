@@ -45,6 +45,8 @@ impl Display for TraceId {
 /// Classifies each message given and updates the `NetworkingMetrics` accordingly. Also emits a
 /// TRACE-level message to the `net_out` and `net_in` target with a per-message unique hash when
 /// a message is sent or received.
+///
+/// Message sizes are recorded as they appear on the wire, i.e. after compression.
 #[pin_project]
 #[derive(Debug)]
 pub struct CountingFormat<F> {
@@ -207,6 +209,16 @@ impl TlsRandomData {
         }
     }
 
+    /// Wraps keying material exported from a QUIC connection.
+    ///
+    /// Unlike the TLS nonces, exported keying material is derived from the connection's secrets
+    /// and does not leak them, so it can be used as is.
+    fn from_keying_material(keying_material: [u8; 12]) -> Self {
+        Self {
+            combined_random: keying_material,
+        }
+    }
+
     /// Creates random `TlsRandomData`.
     #[cfg(test)]
     fn random(rng: &mut TestRng) -> Self {
@@ -259,10 +271,9 @@ impl ConnectionId {
         TraceId(truncated)
     }
 
-    #[inline]
-    /// Returns a reference to the raw bytes of the connection ID.
-    pub(crate) fn as_bytes(&self) -> &[u8] {
-        &self.0
+    /// Returns the connection ID as a digest of the same bytes.
+    pub(super) fn to_digest(self) -> Digest {
+        Digest::from(self.0)
     }
 
     /// Creates a new connection ID from an existing SSL connection.
@@ -271,6 +282,20 @@ impl ConnectionId {
         Self::create(TlsRandomData::collect(ssl), our_id, their_id)
     }
 
+    /// Creates a new connection ID from keying material exported from a QUIC connection.
+    #[inline]
+    pub(crate) fn from_keying_material(
+        keying_material: [u8; 12],
+        our_id: NodeId,
+        their_id: NodeId,
+    ) -> Self {
+        Self::create(
+            TlsRandomData::from_keying_material(keying_material),
+            our_id,
+            their_id,
+        )
+    }
+
     /// Creates a random `ConnectionId`.
     #[cfg(test)]
     pub(super) fn random(rng: &mut TestRng) -> Self {
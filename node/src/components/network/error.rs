@@ -21,6 +21,15 @@ pub enum Error {
     /// We do not have any known hosts.
     #[error("could not resolve at least one known host (or none provided)")]
     EmptyKnownHosts,
+    /// We are configured both to be protected by sentries and to be a sentry ourselves.
+    #[error("sentry addresses and protected addresses cannot both be configured")]
+    ConflictingSentryConfig,
+    /// None of the configured sentry addresses could be resolved.
+    #[error("could not resolve at least one sentry address")]
+    EmptySentries,
+    /// A configured trusted sentry is not a valid node ID.
+    #[error("invalid trusted sentry node id {0:?}")]
+    InvalidSentryId(String),
     /// Failed to create a TCP listener.
     #[error("failed to create listener on {1}")]
     ListenerCreation(
@@ -71,6 +80,31 @@ pub enum Error {
         #[from]
         LoadCertError,
     ),
+    /// Failed to convert our certificate or secret key for use with QUIC.
+    #[error("failed to convert identity for QUIC")]
+    QuicCertificate(
+        #[serde(skip_serializing)]
+        #[source]
+        ErrorStack,
+    ),
+    /// Our secret key uses a curve not supported by QUIC connections.
+    #[error("secret key curve not supported for QUIC")]
+    QuicUnsupportedKey,
+    /// Failed to create the QUIC crypto configuration.
+    #[error("failed to create QUIC crypto configuration")]
+    QuicCrypto(
+        #[serde(skip_serializing)]
+        #[source]
+        rustls::Error,
+    ),
+    /// Failed to create a QUIC endpoint.
+    #[error("failed to create QUIC endpoint on {1}")]
+    QuicEndpointCreation(
+        #[serde(skip_serializing)]
+        #[source]
+        io::Error,
+        SocketAddr,
+    ),
 }
 
 // Manual implementation for `DataSize` - the type contains too many FFI variants that are hard to
@@ -126,6 +160,40 @@ pub enum ConnectionError {
         #[source]
         ssl::Error,
     ),
+    /// Failed to initiate a QUIC connection.
+    #[error("failed to initiate QUIC connection")]
+    QuicConnect(
+        #[serde(skip_serializing)]
+        #[source]
+        quinn::ConnectError,
+    ),
+    /// QUIC connection failed.
+    #[error("QUIC connection failed")]
+    QuicConnection(
+        #[serde(skip_serializing)]
+        #[source]
+        quinn::ConnectionError,
+    ),
+    /// QUIC connection was not established in time.
+    #[error("QUIC connection timed out")]
+    QuicTimeout,
+    /// The certificate presented by the peer over QUIC could not be decoded.
+    #[error("could not decode QUIC peer certificate")]
+    InvalidQuicPeerCertificate(
+        #[serde(skip_serializing)]
+        #[source]
+        ErrorStack,
+    ),
+    /// Could not export keying material from a QUIC connection.
+    #[error("could not export QUIC keying material")]
+    QuicKeyingMaterial,
+    /// Failed to open or accept the streams of a QUIC connection.
+    #[error("QUIC stream setup failed")]
+    QuicStreams(
+        #[serde(skip_serializing)]
+        #[source]
+        IoError<io::Error>,
+    ),
     /// Remote failed to present a client/server certificate.
     #[error("no client certificate presented")]
     NoPeerCertificate,
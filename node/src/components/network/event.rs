@@ -2,18 +2,20 @@ use std::{
     fmt::{self, Debug, Display, Formatter},
     io, mem,
     net::SocketAddr,
-    sync::Arc,
 };
 
 use derive_more::From;
-use futures::stream::{SplitSink, SplitStream};
 use serde::Serialize;
 use static_assertions::const_assert;
 use tracing::Span;
 
 use casper_types::PublicKey;
 
-use super::{error::ConnectionError, FullTransport, GossipedAddress, Message, NodeId};
+use super::{
+    error::ConnectionError,
+    transport::{IncomingStream, OutgoingSink},
+    GossipedAddress, Message, NodeId,
+};
 use crate::{
     effect::{
         announcements::PeerBehaviorAnnouncement,
@@ -91,6 +93,20 @@ pub(crate) enum Event<P> {
     /// Housekeeping for the outgoing manager.
     SweepOutgoing,
 
+    /// The configured known addresses should be learned, after dialing the address book first.
+    LearnKnownAddresses,
+
+    /// An outgoing connection established with our previous identity should be replaced.
+    ReplaceOutgoing {
+        peer_addr: SocketAddr,
+    },
+
+    /// Incoming connections established with the identity preceding the given rotation should be
+    /// closed.
+    RetireIdentity {
+        rotation: u64,
+    },
+
     /// Blocklist announcement.
     #[from]
     BlocklistAnnouncement(PeerBehaviorAnnouncement),
@@ -141,6 +157,15 @@ impl<P: Display> Display for Event<P> {
             Event::SweepOutgoing => {
                 write!(f, "sweep outgoing connections")
             }
+            Event::LearnKnownAddresses => {
+                write!(f, "learn known addresses")
+            }
+            Event::ReplaceOutgoing { peer_addr } => {
+                write!(f, "replace outgoing connection to {}", peer_addr)
+            }
+            Event::RetireIdentity { rotation } => {
+                write!(f, "retire identity preceding rotation {}", rotation)
+            }
         }
     }
 }
@@ -178,7 +203,7 @@ pub(crate) enum IncomingConnection<P> {
         peer_consensus_public_key: Option<PublicKey>,
         /// Stream of incoming messages. for incoming connections.
         #[serde(skip_serializing)]
-        stream: SplitStream<FullTransport<P>>,
+        stream: IncomingStream<P>,
     },
 }
 
@@ -248,9 +273,13 @@ pub(crate) enum OutgoingConnection<P> {
         peer_consensus_public_key: Option<PublicKey>,
         /// Sink for outgoing messages.
         #[serde(skip_serializing)]
-        sink: SplitSink<FullTransport<P>, Arc<Message<P>>>,
+        sink: OutgoingSink<P>,
         /// Holds the information whether the remote node is syncing.
         is_syncing: bool,
+        /// Whether the remote node is a sentry relaying consensus messages.
+        relays_consensus: bool,
+        /// Whether the remote node accepts set reconciliation filters of gossiped items.
+        accepts_reconciliation: bool,
     },
 }
 
@@ -272,6 +301,8 @@ impl<P> Display for OutgoingConnection<P> {
                 peer_consensus_public_key,
                 sink: _,
                 is_syncing,
+                relays_consensus: _,
+                accepts_reconciliation: _,
             } => {
                 write!(
                     f,
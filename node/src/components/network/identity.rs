@@ -26,6 +26,8 @@ pub(crate) enum Error {
     LoadCert(#[from] LoadCertError),
     #[error(transparent)]
     LoadSecretKey(#[from] LoadSecretKeyError),
+    #[error("the network CA certificate cannot be changed without a restart")]
+    NetworkCaChanged,
 }
 
 /// An ephemeral [PKey<Private>] and [TlsCert] that identifies this node
@@ -71,6 +73,20 @@ impl Identity {
         Ok(Identity::new(secret_key, x509_cert, Some(network_ca)))
     }
 
+    /// Loads the identity replacing this one, from the configuration it was originally loaded from.
+    ///
+    /// A node without a configured identity generates a new one. The network CA must not change.
+    pub(super) fn rotate(&self, config: &Config) -> Result<Self, Error> {
+        let rotated = match &config.identity {
+            Some(identity) => Self::from_identity_config(identity)?,
+            None => Self::with_generated_certs()?,
+        };
+        if rotated.network_ca.as_deref() != self.network_ca.as_deref() {
+            return Err(Error::NetworkCaChanged);
+        }
+        Ok(rotated)
+    }
+
     pub(crate) fn with_generated_certs() -> Result<Self, Error> {
         let (not_yet_validated_x509_cert, secret_key) =
             tls::generate_node_cert().map_err(Error::CouldNotGenerateTlsCertificate)?;
@@ -6,11 +6,11 @@
 //! insights should neither be abused just because they are available.
 
 use std::{
-    collections::{BTreeSet, HashSet},
+    collections::{BTreeMap, BTreeSet, HashSet},
     fmt::{self, Debug, Display, Formatter},
     net::SocketAddr,
     sync::atomic::Ordering,
-    time::{Duration, SystemTime},
+    time::{Duration, Instant, SystemTime},
 };
 
 use casper_types::{EraId, PublicKey};
@@ -49,6 +49,8 @@ pub(crate) struct NetworkInsights {
     outgoing_connections: Vec<(SocketAddr, OutgoingInsight)>,
     /// Map of incoming connections.
     connection_symmetries: Vec<(NodeId, ConnectionSymmetryInsight)>,
+    /// Reputation scores of peers.
+    peer_scores: BTreeMap<NodeId, i32>,
 }
 
 /// Insight into an outgoing connection.
@@ -319,6 +321,7 @@ impl NetworkInsights {
                 .debug_inspect_unspent_allowance(),
             outgoing_connections,
             connection_symmetries,
+            peer_scores: net.peer_scores.snapshot(Instant::now()),
         }
     }
 }
@@ -380,6 +383,12 @@ impl Display for NetworkInsights {
             f.write_str("\n")?;
         }
 
+        f.write_str("peer reputation scores:\n")?;
+        writeln!(f, "peer ID         score")?;
+        for (peer_id, score) in &self.peer_scores {
+            writeln!(f, "{:10}  {:>5}", peer_id, score)?;
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,516 @@
+//! Prioritized outgoing message lanes.
+//!
+//! Every outgoing connection sorts its queued messages into lanes by their [`MessageKind`], so
+//! that a large trie chunk does not hold up a consensus vote queued behind it. Lanes are served
+//! using deficit round robin: on each round, a lane is credited a number of bytes proportional to
+//! its weight, and may send messages as long as its credit lasts. Lanes are visited in order of
+//! priority, i.e. consensus first, then finality signatures, then gossip and finally bulk
+//! transfers.
+//!
+//! Each lane can additionally be subject to a byte budget for non-validating peers, enforced by a
+//! per-lane [`Limiter`] shared by all connections. A lane which has exhausted its budget is
+//! skipped, instead of blocking the other lanes.
+//!
+//! On QUIC connections, every lane is sent on a stream of its own by a separate writer, fed by a
+//! queue of its own, so that a stalled stream only holds up its own lane.
+
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display, Formatter},
+    time::Duration,
+};
+
+use datasize::DataSize;
+use prometheus::{Counter, IntCounter, IntGauge, Registry};
+use serde::{Deserialize, Serialize};
+
+use casper_types::PublicKey;
+
+use super::{
+    limiter::{Limiter, LimiterHandle},
+    MessageKind,
+};
+use crate::{
+    types::{NodeId, ValidatorMatrix},
+    unregister_metric,
+};
+
+/// The number of lanes.
+pub(super) const LANE_COUNT: usize = 4;
+
+/// Bytes credited to a lane per unit of weight on every round.
+const QUANTUM_PER_WEIGHT: u64 = 16 * 1024;
+
+/// A lane of outgoing messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Lane {
+    /// Consensus and protocol messages, like pings.
+    Consensus,
+    /// Gossiped finality signatures.
+    FinalitySignatures,
+    /// Gossiped deploys, blocks and addresses.
+    Gossip,
+    /// Bulk transfers, like fetched deploys, blocks and tries.
+    Bulk,
+}
+
+impl Lane {
+    /// All lanes, in order of priority.
+    pub(super) const ALL: [Lane; LANE_COUNT] = [
+        Lane::Consensus,
+        Lane::FinalitySignatures,
+        Lane::Gossip,
+        Lane::Bulk,
+    ];
+
+    /// Returns the lane messages of the given kind are sent on.
+    pub(super) fn of(kind: MessageKind) -> Self {
+        match kind {
+            MessageKind::Protocol | MessageKind::Consensus => Lane::Consensus,
+            MessageKind::FinalitySignatureGossip => Lane::FinalitySignatures,
+            MessageKind::DeployGossip | MessageKind::BlockGossip | MessageKind::AddressGossip => {
+                Lane::Gossip
+            }
+            MessageKind::DeployTransfer
+            | MessageKind::BlockTransfer
+            | MessageKind::TrieTransfer
+            | MessageKind::Other => Lane::Bulk,
+        }
+    }
+
+    /// Returns the index of the lane in lane-indexed arrays.
+    pub(super) fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl Display for Lane {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Lane::Consensus => f.write_str("consensus"),
+            Lane::FinalitySignatures => f.write_str("finality_signatures"),
+            Lane::Gossip => f.write_str("gossip"),
+            Lane::Bulk => f.write_str("bulk"),
+        }
+    }
+}
+
+/// Configuration of a single lane.
+#[derive(DataSize, Debug, Clone, Deserialize, Serialize)]
+// Disallow unknown fields to ensure config files and command-line overrides contain valid keys.
+#[serde(deny_unknown_fields)]
+pub struct LaneConfig {
+    /// The share of bandwidth of the lane, relative to the other lanes. At least 1.
+    pub weight: u32,
+    /// Maximum number of bytes per second sent on the lane to non-validating peers, across all
+    /// connections. Unlimited if 0.
+    pub max_byte_rate_non_validators: u32,
+}
+
+impl LaneConfig {
+    const fn new(weight: u32) -> Self {
+        LaneConfig {
+            weight,
+            max_byte_rate_non_validators: 0,
+        }
+    }
+
+    /// Returns the bytes credited to the lane on every round.
+    fn quantum(&self) -> u64 {
+        u64::from(self.weight.max(1)) * QUANTUM_PER_WEIGHT
+    }
+}
+
+/// Configuration of the outgoing message lanes.
+#[derive(DataSize, Debug, Clone, Deserialize, Serialize)]
+// Disallow unknown fields to ensure config files and command-line overrides contain valid keys.
+#[serde(deny_unknown_fields)]
+pub struct LanesConfig {
+    /// Lane for consensus and protocol messages.
+    pub consensus: LaneConfig,
+    /// Lane for gossiped finality signatures.
+    pub finality_signatures: LaneConfig,
+    /// Lane for gossiped deploys, blocks and addresses.
+    pub gossip: LaneConfig,
+    /// Lane for bulk transfers.
+    pub bulk: LaneConfig,
+}
+
+impl LanesConfig {
+    fn get(&self, lane: Lane) -> &LaneConfig {
+        match lane {
+            Lane::Consensus => &self.consensus,
+            Lane::FinalitySignatures => &self.finality_signatures,
+            Lane::Gossip => &self.gossip,
+            Lane::Bulk => &self.bulk,
+        }
+    }
+}
+
+impl Default for LanesConfig {
+    fn default() -> Self {
+        LanesConfig {
+            consensus: LaneConfig::new(8),
+            finality_signatures: LaneConfig::new(4),
+            gossip: LaneConfig::new(2),
+            bulk: LaneConfig::new(1),
+        }
+    }
+}
+
+/// Metrics of a single lane, aggregated across all connections.
+#[derive(Clone, Debug)]
+pub(super) struct LaneMetrics {
+    /// Volume in bytes of messages waiting to be sent on the lane.
+    queued_bytes: IntGauge,
+    /// Volume in bytes of messages sent on the lane.
+    out_bytes: IntCounter,
+    /// Count of messages sent on the lane.
+    out_count: IntCounter,
+    /// Total time the lane spent throttled by its byte budget, in seconds.
+    limiter_delay: Counter,
+}
+
+impl LaneMetrics {
+    /// Creates and registers the metrics of the given lane.
+    pub(super) fn new(lane: Lane, registry: &Registry) -> Result<Self, prometheus::Error> {
+        let queued_bytes = IntGauge::new(
+            format!("net_lane_{}_queued_bytes", lane),
+            format!("volume in bytes of messages queued on the {} lane", lane),
+        )?;
+        let out_bytes = IntCounter::new(
+            format!("net_lane_{}_out_bytes", lane),
+            format!("volume in bytes of messages sent on the {} lane", lane),
+        )?;
+        let out_count = IntCounter::new(
+            format!("net_lane_{}_out_count", lane),
+            format!("count of messages sent on the {} lane", lane),
+        )?;
+        let limiter_delay = Counter::new(
+            format!("net_lane_{}_limiter_delay", lane),
+            format!(
+                "seconds the {} lane spent throttled by its byte budget for non-validators",
+                lane
+            ),
+        )?;
+
+        registry.register(Box::new(queued_bytes.clone()))?;
+        registry.register(Box::new(out_bytes.clone()))?;
+        registry.register(Box::new(out_count.clone()))?;
+        registry.register(Box::new(limiter_delay.clone()))?;
+
+        Ok(LaneMetrics {
+            queued_bytes,
+            out_bytes,
+            out_count,
+            limiter_delay,
+        })
+    }
+
+    /// Records an item of the given size being queued on the lane.
+    pub(super) fn record_queued(&self, size: u32) {
+        self.queued_bytes.add(i64::from(size));
+    }
+
+    /// Records a queued item of the given size being taken off the lane to be sent.
+    pub(super) fn record_sent(&self, size: u32) {
+        self.queued_bytes.sub(i64::from(size));
+        self.out_bytes.inc_by(u64::from(size));
+        self.out_count.inc();
+    }
+
+    /// Records a queued item of the given size being dropped without being sent.
+    pub(super) fn record_dropped(&self, size: u32) {
+        self.queued_bytes.sub(i64::from(size));
+    }
+
+    /// Unregisters the metrics.
+    pub(super) fn unregister(&self, registry: &Registry) {
+        unregister_metric!(registry, self.queued_bytes);
+        unregister_metric!(registry, self.out_bytes);
+        unregister_metric!(registry, self.out_count);
+        unregister_metric!(registry, self.limiter_delay);
+    }
+}
+
+/// The byte budgets of all lanes.
+#[derive(Debug)]
+pub(super) struct LaneLimiters([Limiter; LANE_COUNT]);
+
+impl LaneLimiters {
+    /// Creates the limiters enforcing the configured byte budgets.
+    pub(super) fn new(
+        config: &LanesConfig,
+        metrics: &[LaneMetrics; LANE_COUNT],
+        validator_matrix: ValidatorMatrix,
+    ) -> Self {
+        LaneLimiters(Lane::ALL.map(|lane| {
+            Limiter::new(
+                config.get(lane).max_byte_rate_non_validators,
+                metrics[lane.index()].limiter_delay.clone(),
+                validator_matrix.clone(),
+            )
+        }))
+    }
+
+    /// Creates the handles for a connection to the given peer.
+    pub(super) fn create_handles(
+        &self,
+        peer_id: NodeId,
+        consensus_key: Option<PublicKey>,
+    ) -> LaneLimiterHandles {
+        LaneLimiterHandles(
+            Lane::ALL
+                .map(|lane| self.0[lane.index()].create_handle(peer_id, consensus_key.clone())),
+        )
+    }
+
+    /// Removes the peer from the connected validators of all limiters.
+    pub(super) fn remove_connected_validator(&self, peer_id: &NodeId) {
+        for limiter in &self.0 {
+            limiter.remove_connected_validator(peer_id);
+        }
+    }
+}
+
+/// The byte budgets of all lanes, as seen by a single connection.
+#[derive(Debug)]
+pub(super) struct LaneLimiterHandles([LimiterHandle; LANE_COUNT]);
+
+impl LaneLimiterHandles {
+    /// Requests an allowance of `size` bytes on the given lane, without waiting.
+    pub(super) fn try_request_allowance(&self, lane: Lane, size: u32) -> Result<(), Duration> {
+        self.0[lane.index()].try_request_allowance(size)
+    }
+
+    /// Waits until an allowance of `size` bytes is granted on the given lane.
+    pub(super) async fn request_allowance(&self, lane: Lane, size: u32) {
+        self.0[lane.index()].request_allowance(size).await
+    }
+}
+
+/// The outcome of scheduling the next message.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum Scheduled<T> {
+    /// The next message to send.
+    Item(T),
+    /// All lanes holding messages are throttled, for at least the given duration.
+    Throttled(Duration),
+}
+
+/// The queue of a single lane.
+#[derive(Debug)]
+struct LaneQueue<T> {
+    /// The queued items, along with their estimated size.
+    items: VecDeque<(T, u32)>,
+    /// Bytes credited to the lane on every round.
+    quantum: u64,
+    /// Bytes the lane may still send in the current round.
+    deficit: u64,
+    /// Metrics to update.
+    metrics: LaneMetrics,
+}
+
+/// The outgoing message lanes of a single connection.
+#[derive(Debug)]
+pub(super) struct OutgoingLanes<T> {
+    /// The queues, indexed by lane.
+    lanes: [LaneQueue<T>; LANE_COUNT],
+    /// The index of the lane currently being served.
+    current: usize,
+    /// The total number of queued items.
+    len: usize,
+}
+
+impl<T> OutgoingLanes<T> {
+    /// Creates a new set of empty lanes.
+    pub(super) fn new(config: &LanesConfig, metrics: &[LaneMetrics; LANE_COUNT]) -> Self {
+        OutgoingLanes {
+            lanes: Lane::ALL.map(|lane| LaneQueue {
+                items: VecDeque::new(),
+                quantum: config.get(lane).quantum(),
+                deficit: 0,
+                metrics: metrics[lane.index()].clone(),
+            }),
+            current: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of queued items.
+    pub(super) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no items are queued.
+    pub(super) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the metrics of the given lane.
+    pub(super) fn metrics(&self, lane: Lane) -> &LaneMetrics {
+        &self.lanes[lane.index()].metrics
+    }
+
+    /// Queues an item of the given estimated size on a lane.
+    pub(super) fn push(&mut self, lane: Lane, size: u32, item: T) {
+        let queue = &mut self.lanes[lane.index()];
+        queue.metrics.record_queued(size);
+        queue.items.push_back((item, size));
+        self.len += 1;
+    }
+
+    /// Schedules the next item to send.
+    ///
+    /// `admit` is asked for the byte budget of the item's lane before it is taken off the queue,
+    /// and can refuse it by returning the time until the lane has budget again. Returns `None` if
+    /// no items are queued.
+    pub(super) fn pop<F>(&mut self, mut admit: F) -> Option<Scheduled<T>>
+    where
+        F: FnMut(Lane, u32) -> Result<(), Duration>,
+    {
+        let mut throttled: [Option<Duration>; LANE_COUNT] = [None; LANE_COUNT];
+
+        loop {
+            let mut any_ready = false;
+            let mut shortest_delay: Option<Duration> = None;
+            for (queue, delay) in self.lanes.iter().zip(throttled.iter()) {
+                if queue.items.is_empty() {
+                    continue;
+                }
+                match delay {
+                    Some(delay) => {
+                        shortest_delay = Some(shortest_delay.map_or(*delay, |d| d.min(*delay)))
+                    }
+                    None => any_ready = true,
+                }
+            }
+            if !any_ready {
+                return shortest_delay.map(Scheduled::Throttled);
+            }
+
+            let index = self.current;
+            let queue = &mut self.lanes[index];
+            match queue.items.front() {
+                Some(&(_, size)) if throttled[index].is_none() => {
+                    if u64::from(size) <= queue.deficit {
+                        match admit(Lane::ALL[index], size) {
+                            Ok(()) => {
+                                queue.deficit -= u64::from(size);
+                                let (item, _) = queue.items.pop_front()?;
+                                queue.metrics.record_sent(size);
+                                self.len -= 1;
+                                return Some(Scheduled::Item(item));
+                            }
+                            Err(delay) => throttled[index] = Some(delay),
+                        }
+                    }
+                }
+                Some(_) => {}
+                // Idle lanes do not accumulate credit.
+                None => queue.deficit = 0,
+            }
+
+            // Move on to the next lane, crediting it for the new round.
+            self.current = (self.current + 1) % LANE_COUNT;
+            let next = &mut self.lanes[self.current];
+            if !next.items.is_empty() && throttled[self.current].is_none() {
+                next.deficit += next.quantum;
+            }
+        }
+    }
+}
+
+impl<T> Drop for OutgoingLanes<T> {
+    fn drop(&mut self) {
+        for queue in &self.lanes {
+            let queued_bytes: i64 = queue.items.iter().map(|(_, size)| i64::from(*size)).sum();
+            queue.metrics.queued_bytes.sub(queued_bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use prometheus::Registry;
+
+    use super::{Lane, LaneMetrics, LanesConfig, OutgoingLanes, Scheduled, QUANTUM_PER_WEIGHT};
+
+    fn lanes() -> OutgoingLanes<(Lane, u32)> {
+        let registry = Registry::new();
+        let metrics = Lane::ALL.map(|lane| LaneMetrics::new(lane, &registry).unwrap());
+        OutgoingLanes::new(&LanesConfig::default(), &metrics)
+    }
+
+    fn push(lanes: &mut OutgoingLanes<(Lane, u32)>, lane: Lane, size: u32, count: u32) {
+        for id in 0..count {
+            lanes.push(lane, size, (lane, id));
+        }
+    }
+
+    fn pop_unlimited(lanes: &mut OutgoingLanes<(Lane, u32)>) -> (Lane, u32) {
+        match lanes.pop(|_, _| Ok(())) {
+            Some(Scheduled::Item(item)) => item,
+            other => panic!("expected an item, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_share_bandwidth_by_weight() {
+        let mut lanes = lanes();
+        let size = QUANTUM_PER_WEIGHT as u32;
+        push(&mut lanes, Lane::Bulk, size, 100);
+        push(&mut lanes, Lane::Consensus, size, 100);
+
+        let consensus_sent = (0..90)
+            .map(|_| pop_unlimited(&mut lanes))
+            .filter(|(lane, _)| *lane == Lane::Consensus)
+            .count();
+        // The consensus lane has eight times the weight of the bulk lane.
+        assert_eq!(consensus_sent, 80);
+
+        // Items within a lane are sent in order.
+        assert_eq!(pop_unlimited(&mut lanes), (Lane::Bulk, 10));
+        assert_eq!(pop_unlimited(&mut lanes), (Lane::Consensus, 80));
+        assert_eq!(lanes.len(), 108);
+    }
+
+    #[test]
+    fn should_send_consensus_ahead_of_large_backlog() {
+        let mut lanes = lanes();
+        push(&mut lanes, Lane::Bulk, 8 * 1024 * 1024, 10);
+        assert_eq!(pop_unlimited(&mut lanes), (Lane::Bulk, 0));
+
+        push(&mut lanes, Lane::Consensus, 200, 1);
+        assert_eq!(pop_unlimited(&mut lanes), (Lane::Consensus, 0));
+        assert_eq!(pop_unlimited(&mut lanes), (Lane::Bulk, 1));
+    }
+
+    #[test]
+    fn should_skip_throttled_lanes() {
+        let mut lanes = lanes();
+        push(&mut lanes, Lane::Bulk, 100, 2);
+        push(&mut lanes, Lane::Gossip, 100, 1);
+
+        let bulk_throttled = |lane: Lane, _| {
+            if lane == Lane::Bulk {
+                Err(Duration::from_millis(300))
+            } else {
+                Ok(())
+            }
+        };
+        assert_eq!(
+            lanes.pop(bulk_throttled),
+            Some(Scheduled::Item((Lane::Gossip, 0)))
+        );
+        assert_eq!(
+            lanes.pop(bulk_throttled),
+            Some(Scheduled::Throttled(Duration::from_millis(300)))
+        );
+        assert_eq!(pop_unlimited(&mut lanes), (Lane::Bulk, 0));
+        assert_eq!(pop_unlimited(&mut lanes), (Lane::Bulk, 1));
+        assert_eq!(lanes.pop(bulk_throttled), None);
+    }
+}
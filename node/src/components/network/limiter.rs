@@ -20,6 +20,9 @@ use crate::types::{NodeId, ValidatorMatrix};
 /// Amount of resource allowed to buffer in `Limiter`.
 const STORED_BUFFER_SECS: Duration = Duration::from_secs(2);
 
+/// Time to wait before retrying a non-blocking request while another requestor holds the lock.
+const CONTENDED_RETRY_DELAY: Duration = Duration::from_millis(10);
+
 /// A limiter dividing resources into two classes based on their validator status.
 ///
 /// Any consumer of a specific resource is expected to call `create_handle` for every peer and use
@@ -199,6 +202,68 @@ pub(super) struct LimiterHandle {
 impl LimiterHandle {
     /// Waits until the requester is allocated `amount` additional resources.
     pub(super) async fn request_allowance(&self, amount: u32) {
+        if !self.is_limited() {
+            return;
+        }
+
+        // We are a low-priority sender. Obtain a lock on the resources and wait an appropriate
+        // amount of time to fill them up.
+        let mut resources = self.data.resources.lock().await;
+
+        while resources.available < 0 {
+            // We are still maintaining the lock here to avoid issues with other low-priority
+            // requestors.
+            resources.refill(self.data.resources_per_second);
+
+            // If we do not have enough resources available, sleep until we do.
+            if resources.available < 0 {
+                let estimated_time_remaining =
+                    resources.time_until_available(self.data.resources_per_second);
+
+                // Note: This sleep call is the reason we are using a tokio mutex instead of a
+                //       regular `std` one, as we are holding it across the await point here.
+                tokio::time::sleep(estimated_time_remaining).await;
+                self.data
+                    .wait_time_sec
+                    .inc_by(estimated_time_remaining.as_secs_f64());
+            }
+        }
+
+        // Subtract the amount. If available resources go negative as a result, it is the next
+        // sender's problem.
+        resources.available -= amount as i64;
+    }
+
+    /// Requests `amount` additional resources without waiting.
+    ///
+    /// If the resources are exhausted, returns the estimated time until they are available again.
+    pub(super) fn try_request_allowance(&self, amount: u32) -> Result<(), Duration> {
+        if !self.is_limited() {
+            return Ok(());
+        }
+
+        let mut resources = match self.data.resources.try_lock() {
+            Ok(resources) => resources,
+            // Another requestor is currently waiting for the resources to refill.
+            Err(_) => return Err(CONTENDED_RETRY_DELAY),
+        };
+
+        resources.refill(self.data.resources_per_second);
+        if resources.available < 0 {
+            let estimated_time_remaining =
+                resources.time_until_available(self.data.resources_per_second);
+            self.data
+                .wait_time_sec
+                .inc_by(estimated_time_remaining.as_secs_f64());
+            return Err(estimated_time_remaining);
+        }
+
+        resources.available -= amount as i64;
+        Ok(())
+    }
+
+    /// Returns whether the requester is subject to limiting at all.
+    fn is_limited(&self) -> bool {
         // As a first step, determine the peer class by checking if our id is in the validator set.
 
         if self.validator_matrix.is_empty() {
@@ -206,7 +271,7 @@ impl LimiterHandle {
             // reserved resources. In this case, do not limit at all.
             trace!("empty set of validators, not limiting resources at all");
 
-            return;
+            return false;
         }
 
         let peer_class = if let Some(ref public_key) = self.consumer_id.consensus_key {
@@ -223,63 +288,35 @@ impl LimiterHandle {
         };
 
         match peer_class {
-            PeerClass::Validator => {
-                // No limit imposed on validators.
-            }
-            PeerClass::NonValidator => {
-                if self.data.resources_per_second == 0 {
-                    return;
-                }
-
-                let max_stored_resource = ((self.data.resources_per_second as f64)
-                    * STORED_BUFFER_SECS.as_secs_f64())
-                    as u32;
-
-                // We are a low-priority sender. Obtain a lock on the resources and wait an
-                // appropriate amount of time to fill them up.
-                {
-                    let mut resources = self.data.resources.lock().await;
-
-                    while resources.available < 0 {
-                        // Determine time delta since last refill.
-                        let now = Instant::now();
-                        let elapsed = now - resources.last_refill;
-                        resources.last_refill = now;
-
-                        // Add appropriate amount of resources, capped at `max_stored_bytes`. We
-                        // are still maintaining the lock here to avoid issues with other
-                        // low-priority requestors.
-                        resources.available += ((elapsed.as_nanos()
-                            * self.data.resources_per_second as u128)
-                            / 1_000_000_000) as i64;
-                        resources.available = resources.available.min(max_stored_resource as i64);
-
-                        // If we do not have enough resources available, sleep until we do.
-                        if resources.available < 0 {
-                            let estimated_time_remaining = Duration::from_millis(
-                                (-resources.available) as u64 * 1000
-                                    / self.data.resources_per_second as u64,
-                            );
-
-                            // Note: This sleep call is the reason we are using a tokio mutex
-                            //       instead of a regular `std` one, as we are holding it across the
-                            //       await point here.
-                            tokio::time::sleep(estimated_time_remaining).await;
-                            self.data
-                                .wait_time_sec
-                                .inc_by(estimated_time_remaining.as_secs_f64());
-                        }
-                    }
-
-                    // Subtract the amount. If available resources go negative as a result, it
-                    // is the next sender's problem.
-                    resources.available -= amount as i64;
-                }
-            }
+            // No limit imposed on validators.
+            PeerClass::Validator => false,
+            PeerClass::NonValidator => self.data.resources_per_second != 0,
         }
     }
 }
 
+impl ResourceData {
+    /// Adds the resources accrued since the last refill, capped at the amount allowed to buffer.
+    fn refill(&mut self, resources_per_second: u32) {
+        let max_stored_resource =
+            ((resources_per_second as f64) * STORED_BUFFER_SECS.as_secs_f64()) as u32;
+
+        // Determine time delta since last refill.
+        let now = Instant::now();
+        let elapsed = now - self.last_refill;
+        self.last_refill = now;
+
+        self.available +=
+            ((elapsed.as_nanos() * resources_per_second as u128) / 1_000_000_000) as i64;
+        self.available = self.available.min(max_stored_resource as i64);
+    }
+
+    /// Returns the estimated time until the deficit is paid off.
+    fn time_until_available(&self, resources_per_second: u32) -> Duration {
+        Duration::from_millis((-self.available).max(0) as u64 * 1000 / resources_per_second as u64)
+    }
+}
+
 /// An identity for a consumer.
 #[derive(Debug)]
 struct ConsumerId {
@@ -299,7 +336,9 @@ mod tests {
     use tokio::time::Instant;
 
     use super::{Limiter, NodeId, PublicKey};
-    use crate::{testing::init_logging, types::ValidatorMatrix};
+    use crate::{
+        components::consensus::LocalSigner, testing::init_logging, types::ValidatorMatrix,
+    };
 
     /// Something that happens almost immediately, with some allowance for test jitter.
     const SHORT_TIME: Duration = Duration::from_millis(250);
@@ -335,6 +374,24 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn try_request_reports_time_until_allowance() {
+        let mut rng = crate::new_rng();
+
+        let validator_matrix =
+            ValidatorMatrix::new_with_validator(Arc::new(SecretKey::random(&mut rng)));
+        let limiter = Limiter::new(1_000, new_wait_time_sec(), validator_matrix);
+        let handle = limiter.create_handle(NodeId::random(&mut rng), None);
+
+        // The first request always succeeds, putting the allowance into a deficit of 2 seconds.
+        assert!(handle.try_request_allowance(2000).is_ok());
+        let delay = handle
+            .try_request_allowance(1)
+            .expect_err("allowance should be exhausted");
+        assert!(delay > Duration::from_secs(1));
+        assert!(delay <= Duration::from_secs(2));
+    }
+
     #[tokio::test]
     async fn active_validator_is_unlimited() {
         let mut rng = crate::new_rng();
@@ -453,7 +510,6 @@ mod tests {
         let mut rng = crate::new_rng();
 
         let secret_key = SecretKey::random(&mut rng);
-        let consensus_key = PublicKey::from(&secret_key);
         let wait_metric = new_wait_time_sec();
         let limiter = Limiter::new(
             1_000,
@@ -462,8 +518,7 @@ mod tests {
                 Ratio::new(1, 3),
                 None,
                 EraId::from(0),
-                Arc::new(secret_key),
-                consensus_key.clone(),
+                Arc::new(LocalSigner::new(Arc::new(secret_key))),
                 2,
             ),
         );
@@ -11,14 +11,18 @@ use serde::{
     Deserialize, Deserializer, Serialize, Serializer,
 };
 use strum::EnumDiscriminants;
+use tracing::error;
 
 use casper_hashing::Digest;
 #[cfg(test)]
 use casper_types::testing::TestRng;
-use casper_types::{crypto, AsymmetricType, ProtocolVersion, PublicKey, SecretKey, Signature};
+#[cfg(test)]
+use casper_types::SecretKey;
+use casper_types::{crypto, AsymmetricType, ProtocolVersion, PublicKey, Signature};
 
 use super::{counting_format::ConnectionId, health::Nonce, BincodeFormat};
 use crate::{
+    components::consensus::{self, Signer, SigningRequest},
     effect::EffectBuilder,
     protocol,
     types::{Chainspec, NodeId},
@@ -55,6 +59,15 @@ pub(crate) enum Message<P> {
         /// Hash of the chainspec the node is running.
         #[serde(default)]
         chainspec_hash: Option<Digest>,
+        /// True if the node accepts compressed messages.
+        #[serde(default)]
+        accepts_compression: bool,
+        /// True if the node is a sentry relaying consensus messages.
+        #[serde(default)]
+        relays_consensus: bool,
+        /// True if the node understands set reconciliation filters of gossiped items.
+        #[serde(default)]
+        accepts_reconciliation: bool,
     },
     /// A ping request.
     Ping {
@@ -112,6 +125,15 @@ impl<P: Payload> Message<P> {
         }
     }
 
+    /// Returns whether or not the payload may only be sent to peers accepting reconciliation.
+    #[inline]
+    pub(super) fn payload_requires_reconciliation(&self) -> bool {
+        match self {
+            Message::Handshake { .. } | Message::Ping { .. } | Message::Pong { .. } => false,
+            Message::Payload(payload) => payload.requires_reconciliation(),
+        }
+    }
+
     /// Attempts to create a demand-event from this message.
     ///
     /// Succeeds if the outer message contains a payload that can be converted into a demand.
@@ -136,24 +158,20 @@ impl<P: Payload> Message<P> {
     }
 }
 
-/// A pair of secret keys used by consensus.
+/// The signer of the consensus key, used to prove to peers that we are a validator.
 pub(super) struct NodeKeyPair {
-    secret_key: Arc<SecretKey>,
-    public_key: PublicKey,
+    signer: Arc<dyn Signer>,
 }
 
 impl NodeKeyPair {
     /// Creates a new key pair for consensus signing.
-    pub(super) fn new(key_pair: (Arc<SecretKey>, PublicKey)) -> Self {
-        Self {
-            secret_key: key_pair.0,
-            public_key: key_pair.1,
-        }
+    pub(super) fn new(signer: Arc<dyn Signer>) -> Self {
+        Self { signer }
     }
 
-    /// Sign a value using this keypair.
-    fn sign<T: AsRef<[u8]>>(&self, value: T) -> Signature {
-        crypto::sign(value, &self.secret_key, &self.public_key)
+    /// Returns the public key of the consensus key.
+    fn public_key(&self) -> &PublicKey {
+        self.signer.public_key()
     }
 }
 
@@ -170,17 +188,32 @@ pub(crate) struct ConsensusCertificate {
 
 impl ConsensusCertificate {
     /// Creates a new consensus certificate from a connection ID and key pair.
-    pub(super) fn create(connection_id: ConnectionId, key_pair: &NodeKeyPair) -> Self {
-        let signature = key_pair.sign(connection_id.as_bytes());
-        ConsensusCertificate {
-            public_key: key_pair.public_key.clone(),
-            signature,
+    ///
+    /// The certificate is signed on a blocking thread, as the signer may need to wait for a remote
+    /// signer. Returns `None` if the signer refused or failed to sign the certificate.
+    pub(super) async fn create(
+        connection_id: ConnectionId,
+        key_pair: &NodeKeyPair,
+    ) -> Option<Self> {
+        let request = SigningRequest::ConsensusCertificate {
+            connection_id: connection_id.to_digest(),
+        };
+        match consensus::sign_in_background(Arc::clone(&key_pair.signer), request).await {
+            Ok(signature) => Some(ConsensusCertificate {
+                public_key: key_pair.public_key().clone(),
+                signature,
+            }),
+            Err(error) => {
+                error!(%error, "failed to sign consensus certificate");
+                None
+            }
         }
     }
 
     /// Validates a certificate, returning a `PublicKey` if valid.
     pub(super) fn validate(self, connection_id: ConnectionId) -> Result<PublicKey, crypto::Error> {
-        crypto::verify(connection_id.as_bytes(), &self.signature, &self.public_key)?;
+        let message = consensus::consensus_certificate_message(&connection_id.to_digest());
+        crypto::verify(message, &self.signature, &self.public_key)?;
         Ok(self.public_key)
     }
 
@@ -189,10 +222,13 @@ impl ConsensusCertificate {
     fn random(rng: &mut TestRng) -> Self {
         let secret_key = SecretKey::random(rng);
         let public_key = PublicKey::from(&secret_key);
-        ConsensusCertificate::create(
-            ConnectionId::random(rng),
-            &NodeKeyPair::new((Arc::new(secret_key), public_key)),
-        )
+        let message =
+            consensus::consensus_certificate_message(&ConnectionId::random(rng).to_digest());
+        let signature = crypto::sign(message, &secret_key, &public_key);
+        ConsensusCertificate {
+            public_key,
+            signature,
+        }
     }
 }
 
@@ -293,16 +329,22 @@ impl<P: Display> Display for Message<P> {
                 consensus_certificate,
                 is_syncing,
                 chainspec_hash,
+                accepts_compression,
+                relays_consensus,
+                accepts_reconciliation,
             } => {
                 write!(
                     f,
-                    "handshake: {}, public addr: {}, protocol_version: {}, consensus_certificate: {}, is_syncing: {}, chainspec_hash: {}",
+                    "handshake: {}, public addr: {}, protocol_version: {}, consensus_certificate: {}, is_syncing: {}, chainspec_hash: {}, accepts_compression: {}, relays_consensus: {}, accepts_reconciliation: {}",
                     network_name,
                     public_addr,
                     protocol_version,
                     OptDisplay::new(consensus_certificate.as_ref(), "none"),
                     is_syncing,
-                    OptDisplay::new(chainspec_hash.as_ref(), "none")
+                    OptDisplay::new(chainspec_hash.as_ref(), "none"),
+                    accepts_compression,
+                    relays_consensus,
+                    accepts_reconciliation
                 )
             }
             Message::Ping { nonce } => write!(f, "ping({})", nonce),
@@ -376,6 +418,17 @@ pub(crate) trait Payload:
     ///
     /// This functionality should be removed once multiplexed networking lands.
     fn is_unsafe_for_syncing_peers(&self) -> bool;
+
+    /// Indicates a message may only be sent to peers which announced in their handshake that they
+    /// understand set reconciliation filters.
+    fn requires_reconciliation(&self) -> bool {
+        false
+    }
+
+    /// Returns the address announced by the payload, if it is part of address gossip.
+    fn gossiped_address(&self) -> Option<SocketAddr> {
+        None
+    }
 }
 
 /// Network message conversion support.
@@ -464,6 +517,9 @@ mod specimen_support {
                         consensus_certificate: LargestSpecimen::largest_specimen(estimator, cache),
                         is_syncing: LargestSpecimen::largest_specimen(estimator, cache),
                         chainspec_hash: LargestSpecimen::largest_specimen(estimator, cache),
+                        accepts_compression: LargestSpecimen::largest_specimen(estimator, cache),
+                        relays_consensus: LargestSpecimen::largest_specimen(estimator, cache),
+                        accepts_reconciliation: LargestSpecimen::largest_specimen(estimator, cache),
                     },
                     MessageDiscriminants::Ping => Message::Ping {
                         nonce: LargestSpecimen::largest_specimen(estimator, cache),
@@ -768,6 +824,9 @@ mod tests {
             consensus_certificate: Some(ConsensusCertificate::random(&mut rng)),
             is_syncing: false,
             chainspec_hash: Some(Digest::hash("example-chainspec")),
+            accepts_compression: true,
+            relays_consensus: true,
+            accepts_reconciliation: true,
         };
 
         let legacy_handshake: V1_0_0_Message = roundtrip_message(&modern_handshake);
@@ -802,6 +861,9 @@ mod tests {
             consensus_certificate,
             is_syncing,
             chainspec_hash,
+            accepts_compression,
+            relays_consensus,
+            accepts_reconciliation,
         } = modern_handshake
         {
             assert_eq!(network_name, "example-handshake");
@@ -809,7 +871,10 @@ mod tests {
             assert_eq!(protocol_version, ProtocolVersion::V1_0_0);
             assert!(consensus_certificate.is_none());
             assert!(!is_syncing);
-            assert!(chainspec_hash.is_none())
+            assert!(chainspec_hash.is_none());
+            assert!(!accepts_compression);
+            assert!(!relays_consensus);
+            assert!(!accepts_reconciliation);
         } else {
             panic!("did not expect modern handshake to deserialize to anything but")
         }
@@ -826,6 +891,9 @@ mod tests {
             consensus_certificate,
             is_syncing,
             chainspec_hash,
+            accepts_compression,
+            relays_consensus,
+            accepts_reconciliation,
         } = modern_handshake
         {
             assert!(!is_syncing);
@@ -834,7 +902,10 @@ mod tests {
             assert_eq!(protocol_version, ProtocolVersion::V1_0_0);
             assert!(consensus_certificate.is_none());
             assert!(!is_syncing);
-            assert!(chainspec_hash.is_none())
+            assert!(chainspec_hash.is_none());
+            assert!(!accepts_compression);
+            assert!(!relays_consensus);
+            assert!(!accepts_reconciliation);
         } else {
             panic!("did not expect modern handshake to deserialize to anything but")
         }
@@ -851,6 +922,9 @@ mod tests {
             consensus_certificate,
             is_syncing,
             chainspec_hash,
+            accepts_compression,
+            relays_consensus,
+            accepts_reconciliation,
         } = modern_handshake
         {
             assert_eq!(network_name, "example-handshake");
@@ -878,7 +952,10 @@ mod tests {
                 .unwrap()
             );
             assert!(!is_syncing);
-            assert!(chainspec_hash.is_none())
+            assert!(chainspec_hash.is_none());
+            assert!(!accepts_compression);
+            assert!(!relays_consensus);
+            assert!(!accepts_reconciliation);
         } else {
             panic!("did not expect modern handshake to deserialize to anything but")
         }
@@ -895,6 +972,9 @@ mod tests {
             consensus_certificate,
             is_syncing,
             chainspec_hash,
+            accepts_compression,
+            relays_consensus,
+            accepts_reconciliation,
         } = modern_handshake
         {
             assert!(!is_syncing);
@@ -922,7 +1002,10 @@ mod tests {
                 .unwrap()
             );
             assert!(!is_syncing);
-            assert!(chainspec_hash.is_none())
+            assert!(chainspec_hash.is_none());
+            assert!(!accepts_compression);
+            assert!(!relays_consensus);
+            assert!(!accepts_reconciliation);
         } else {
             panic!("did not expect modern handshake to deserialize to anything but")
         }
@@ -3,7 +3,11 @@ use std::sync::Weak;
 use prometheus::{Counter, IntCounter, IntGauge, Registry};
 use tracing::debug;
 
-use super::{outgoing::OutgoingMetrics, MessageKind};
+use super::{
+    lanes::{Lane, LaneMetrics, LANE_COUNT},
+    outgoing::OutgoingMetrics,
+    MessageKind,
+};
 use crate::unregister_metric;
 
 /// Network-type agnostic networking metrics.
@@ -55,6 +59,8 @@ pub(super) struct Metrics {
     pub(super) out_bytes_trie_transfer: IntCounter,
     /// Volume in bytes of outgoing messages with other payload.
     pub(super) out_bytes_other: IntCounter,
+    /// Volume in bytes saved by compressing outgoing messages.
+    pub(super) out_bytes_saved_by_compression: IntCounter,
 
     /// Number of outgoing connections in connecting state.
     pub(super) out_state_connecting: IntGauge,
@@ -85,6 +91,8 @@ pub(super) struct Metrics {
     pub(super) in_bytes_trie_transfer: IntCounter,
     /// Volume in bytes of incoming messages with other payload.
     pub(super) in_bytes_other: IntCounter,
+    /// Volume in bytes saved by compression of incoming messages.
+    pub(super) in_bytes_saved_by_compression: IntCounter,
 
     /// Count of incoming messages that are protocol overhead.
     pub(super) in_count_protocol: IntCounter,
@@ -115,6 +123,12 @@ pub(super) struct Metrics {
     /// Total time spent delaying incoming traffic from non-validators due to limiter, in seconds.
     pub(super) accumulated_incoming_limiter_delay: Counter,
 
+    /// Number of captured messages dropped as the capture writer fell behind.
+    pub(super) dropped_capture_records: IntCounter,
+
+    /// Metrics of the outgoing message lanes, indexed by lane.
+    pub(super) lanes: [LaneMetrics; LANE_COUNT],
+
     /// Registry instance.
     registry: Registry,
 }
@@ -215,6 +229,10 @@ impl Metrics {
             "net_out_bytes_other",
             "volume in bytes of outgoing messages with other payload",
         )?;
+        let out_bytes_saved_by_compression = IntCounter::new(
+            "net_out_bytes_saved_by_compression",
+            "volume in bytes saved by compressing outgoing messages",
+        )?;
 
         let out_state_connecting = IntGauge::new(
             "out_state_connecting",
@@ -318,6 +336,10 @@ impl Metrics {
             "net_in_bytes_other",
             "volume in bytes of incoming messages with other payload",
         )?;
+        let in_bytes_saved_by_compression = IntCounter::new(
+            "net_in_bytes_saved_by_compression",
+            "volume in bytes saved by compression of incoming messages",
+        )?;
 
         let requests_for_trie_accepted = IntCounter::new(
             "requests_for_trie_accepted",
@@ -337,6 +359,11 @@ impl Metrics {
             "seconds spent delaying incoming traffic from non-validators due to limiter, in seconds."
         )?;
 
+        let dropped_capture_records = IntCounter::new(
+            "net_dropped_capture_records",
+            "number of captured messages dropped as the capture writer fell behind",
+        )?;
+
         registry.register(Box::new(broadcast_requests.clone()))?;
         registry.register(Box::new(direct_message_requests.clone()))?;
         registry.register(Box::new(queued_messages.clone()))?;
@@ -363,6 +390,7 @@ impl Metrics {
         registry.register(Box::new(out_bytes_block_transfer.clone()))?;
         registry.register(Box::new(out_bytes_trie_transfer.clone()))?;
         registry.register(Box::new(out_bytes_other.clone()))?;
+        registry.register(Box::new(out_bytes_saved_by_compression.clone()))?;
 
         registry.register(Box::new(out_state_connecting.clone()))?;
         registry.register(Box::new(out_state_waiting.clone()))?;
@@ -391,12 +419,21 @@ impl Metrics {
         registry.register(Box::new(in_bytes_block_transfer.clone()))?;
         registry.register(Box::new(in_bytes_trie_transfer.clone()))?;
         registry.register(Box::new(in_bytes_other.clone()))?;
+        registry.register(Box::new(in_bytes_saved_by_compression.clone()))?;
 
         registry.register(Box::new(requests_for_trie_accepted.clone()))?;
         registry.register(Box::new(requests_for_trie_finished.clone()))?;
 
         registry.register(Box::new(accumulated_outgoing_limiter_delay.clone()))?;
         registry.register(Box::new(accumulated_incoming_limiter_delay.clone()))?;
+        registry.register(Box::new(dropped_capture_records.clone()))?;
+
+        let lanes = [
+            LaneMetrics::new(Lane::Consensus, registry)?,
+            LaneMetrics::new(Lane::FinalitySignatures, registry)?,
+            LaneMetrics::new(Lane::Gossip, registry)?,
+            LaneMetrics::new(Lane::Bulk, registry)?,
+        ];
 
         Ok(Metrics {
             broadcast_requests,
@@ -423,6 +460,7 @@ impl Metrics {
             out_bytes_block_transfer,
             out_bytes_trie_transfer,
             out_bytes_other,
+            out_bytes_saved_by_compression,
             out_state_connecting,
             out_state_waiting,
             out_state_connected,
@@ -448,10 +486,13 @@ impl Metrics {
             in_bytes_block_transfer,
             in_bytes_trie_transfer,
             in_bytes_other,
+            in_bytes_saved_by_compression,
             requests_for_trie_accepted,
             requests_for_trie_finished,
             accumulated_outgoing_limiter_delay,
             accumulated_incoming_limiter_delay,
+            dropped_capture_records,
+            lanes,
             registry: registry.clone(),
         })
     }
@@ -556,6 +597,37 @@ impl Metrics {
         }
     }
 
+    /// Records the size of an outgoing message before and after compression.
+    pub(super) fn record_compression_out(this: &Weak<Self>, uncompressed: u64, compressed: u64) {
+        if let Some(metrics) = this.upgrade() {
+            metrics
+                .out_bytes_saved_by_compression
+                .inc_by(uncompressed.saturating_sub(compressed));
+        } else {
+            debug!("not recording metrics, component already shut down");
+        }
+    }
+
+    /// Records the size of an incoming message before and after decompression.
+    pub(super) fn record_compression_in(this: &Weak<Self>, uncompressed: u64, compressed: u64) {
+        if let Some(metrics) = this.upgrade() {
+            metrics
+                .in_bytes_saved_by_compression
+                .inc_by(uncompressed.saturating_sub(compressed));
+        } else {
+            debug!("not recording metrics, component already shut down");
+        }
+    }
+
+    /// Records that a captured message was dropped.
+    pub(super) fn record_dropped_capture_record(this: &Weak<Self>) {
+        if let Some(metrics) = this.upgrade() {
+            metrics.dropped_capture_records.inc();
+        } else {
+            debug!("not recording metrics, component already shut down");
+        }
+    }
+
     /// Creates a set of outgoing metrics that is connected to this set of metrics.
     pub(super) fn create_outgoing_metrics(&self) -> OutgoingMetrics {
         OutgoingMetrics {
@@ -614,6 +686,7 @@ impl Drop for Metrics {
         unregister_metric!(self.registry, self.out_bytes_block_transfer);
         unregister_metric!(self.registry, self.out_bytes_trie_transfer);
         unregister_metric!(self.registry, self.out_bytes_other);
+        unregister_metric!(self.registry, self.out_bytes_saved_by_compression);
 
         unregister_metric!(self.registry, self.out_state_connecting);
         unregister_metric!(self.registry, self.out_state_waiting);
@@ -642,11 +715,17 @@ impl Drop for Metrics {
         unregister_metric!(self.registry, self.in_bytes_block_transfer);
         unregister_metric!(self.registry, self.in_bytes_trie_transfer);
         unregister_metric!(self.registry, self.in_bytes_other);
+        unregister_metric!(self.registry, self.in_bytes_saved_by_compression);
 
         unregister_metric!(self.registry, self.requests_for_trie_accepted);
         unregister_metric!(self.registry, self.requests_for_trie_finished);
 
         unregister_metric!(self.registry, self.accumulated_outgoing_limiter_delay);
         unregister_metric!(self.registry, self.accumulated_incoming_limiter_delay);
+        unregister_metric!(self.registry, self.dropped_capture_records);
+
+        for lane_metrics in &self.lanes {
+            lane_metrics.unregister(&self.registry);
+        }
     }
 }
@@ -88,12 +88,17 @@
 //! If a conflict (multiple successful dial results) occurs, the more recent connection takes
 //! precedence over the previous one. This prevents problems when a notification of a terminated
 //! connection is overtaken by the new connection announcement.
+//!
+//! An established connection can be replaced using `replace_connection`, which dials the address
+//! again while the current connection stays routed. Once the new connection is established, the
+//! route switches over to it and the previous handle is returned in a [`DialRequest::Disconnect`].
+//! Should the new connection fail instead, the current one is kept.
 
 // Clippy has a lot of false positives due to `span.clone()`-closures.
 #![allow(clippy::redundant_clone)]
 
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet},
     error::Error,
     fmt::{self, Debug, Display, Formatter},
     mem,
@@ -327,6 +332,8 @@ where
     /// Contains a mapping from node IDs to connected socket addresses. A missing entry means that
     /// the destination is not connected.
     routes: HashMap<NodeId, SocketAddr>,
+    /// Connected addresses which are currently being dialed again to replace their connection.
+    replacing: HashSet<SocketAddr>,
     /// A set of outgoing metrics.
     #[data_size(skip)]
     metrics: OutgoingMetrics,
@@ -389,6 +396,7 @@ where
             config,
             outgoing: Default::default(),
             routes: Default::default(),
+            replacing: Default::default(),
             metrics,
         }
     }
@@ -443,6 +451,9 @@ where
         addr: SocketAddr,
         mut new_state: OutgoingState<H, E>,
     ) -> (&mut Outgoing<H, E>, Option<H>) {
+        // Any pending replacement is superseded by the state change.
+        self.replacing.remove(&addr);
+
         let (prev_state, new_outgoing) = match self.outgoing.entry(addr) {
             Entry::Vacant(vacant) => {
                 let inserted = vacant.insert(Outgoing {
@@ -579,6 +590,16 @@ where
             })
     }
 
+    /// Marks an already learned address as unforgettable, see `learn_addr` for details.
+    pub(crate) fn mark_unforgettable(&mut self, addr: SocketAddr) {
+        if let Some(outgoing) = self.outgoing.get_mut(&addr) {
+            if !outgoing.is_unforgettable {
+                outgoing.is_unforgettable = true;
+                debug!(%addr, "marked unforgettable");
+            }
+        }
+    }
+
     /// Blocks an address.
     ///
     /// Causes any current connection to the address to be terminated and future ones prohibited.
@@ -704,6 +725,19 @@ where
         }
     }
 
+    /// Returns whether the most recent valid pong received from the given peer carried `nonce`.
+    pub(super) fn is_latest_pong(&self, peer_id: NodeId, nonce: Nonce) -> bool {
+        self.routes
+            .get(&peer_id)
+            .and_then(|addr| self.outgoing.get(addr))
+            .map_or(false, |outgoing| match outgoing.state {
+                OutgoingState::Connected { ref health, .. } => health
+                    .last_pong_received
+                    .map_or(false, |pong| pong.nonce() == nonce),
+                _ => false,
+            })
+    }
+
     /// Performs housekeeping like reconnection or unblocking peers.
     ///
     /// This function must periodically be called. A good interval is every second.
@@ -911,8 +945,9 @@ where
                         handle, span
                     })
                 } else {
-                    // Otherwise, just record the connected state.
-                    self.change_outgoing_state(
+                    // Otherwise, just record the connected state. A connection established before
+                    // is replaced by the new one, it is closed after sending what it has queued.
+                    let (_, opt_handle) = self.change_outgoing_state(
                         addr,
                         OutgoingState::Connected {
                             peer_id: node_id,
@@ -920,7 +955,10 @@ where
                             health: ConnectionHealth::new(when),
                         },
                     );
-                    None
+                    opt_handle.map(|handle| {
+                        debug!("route switched to new connection, closing previous one");
+                        DialRequest::Disconnect { handle, span }
+                    })
                 }
             }
 
@@ -947,6 +985,10 @@ where
                             // connection arrives, but continue to honor the blocking.
                             None
                         }
+                        OutgoingState::Connected { .. } if self.replacing.remove(&addr) => {
+                            info!("keeping current connection, as its replacement failed");
+                            None
+                        }
                         OutgoingState::Waiting { .. } |
                         OutgoingState::Connected { .. } |
                         OutgoingState::Loopback => {
@@ -1016,6 +1058,26 @@ where
             }
         })
     }
+
+    /// Replaces an established connection with a new one, e.g. after our identity changed.
+    ///
+    /// The address is dialed again while the current connection remains routed, it is only
+    /// disconnected once the new connection has been established, see `handle_dial_outcome`.
+    ///
+    /// Does nothing if the address is not connected or already being replaced.
+    pub(crate) fn replace_connection(&mut self, addr: SocketAddr) -> Option<DialRequest<H>> {
+        let is_connected = matches!(
+            self.outgoing.get(&addr).map(|outgoing| &outgoing.state),
+            Some(OutgoingState::Connected { .. })
+        );
+        if !is_connected || !self.replacing.insert(addr) {
+            return None;
+        }
+
+        let span = make_span(addr, self.outgoing.get(&addr));
+        span.in_scope(|| debug!("replacing connection"));
+        Some(DialRequest::Dial { addr, span })
+    }
 }
 
 #[cfg(test)]
@@ -1825,4 +1887,71 @@ mod tests {
         assert!(!manager.record_pong(id, TaggedTimestamp::from_parts(clock.now(), rng.gen())));
         assert!(manager.record_pong(id, TaggedTimestamp::from_parts(clock.now(), rng.gen())));
     }
+
+    #[test]
+    fn replacing_connections_works() {
+        init_logging();
+
+        let mut rng = crate::new_rng();
+        let clock = TestClock::new();
+
+        let addr_a: SocketAddr = "1.2.3.4:1234".parse().unwrap();
+        let addr_b: SocketAddr = "5.6.7.8:5678".parse().unwrap();
+        let id_a = NodeId::random(&mut rng);
+
+        let mut manager = OutgoingManager::<u32, TestDialerError>::new(test_config());
+
+        assert!(dials(
+            addr_a,
+            &manager.learn_addr(addr_a, false, clock.now())
+        ));
+        assert!(dials(
+            addr_b,
+            &manager.learn_addr(addr_b, false, clock.now())
+        ));
+        assert!(manager
+            .handle_dial_outcome(DialOutcome::Successful {
+                addr: addr_a,
+                handle: 99,
+                node_id: id_a,
+                when: clock.now(),
+            })
+            .is_none());
+
+        // Replacing the established connection dials again, while the current one stays routed.
+        let request = manager.replace_connection(addr_a);
+        assert!(dials(addr_a, &request));
+        assert_eq!(manager.get_route(id_a), Some(&99));
+        assert_eq!(manager.metrics().out_state_connected.get(), 1);
+
+        // The replacement is only dialed once.
+        assert!(manager.replace_connection(addr_a).is_none());
+
+        // A failed replacement keeps the current connection.
+        assert!(manager
+            .handle_dial_outcome(DialOutcome::Failed {
+                addr: addr_a,
+                error: TestDialerError { id: 1 },
+                when: clock.now(),
+            })
+            .is_none());
+        assert_eq!(manager.get_route(id_a), Some(&99));
+        assert_eq!(manager.metrics().out_state_connected.get(), 1);
+
+        // Once a replacement is established, the route switches over and the previous connection is
+        // disconnected.
+        assert!(dials(addr_a, &manager.replace_connection(addr_a)));
+        let request = manager.handle_dial_outcome(DialOutcome::Successful {
+            addr: addr_a,
+            handle: 100,
+            node_id: id_a,
+            when: clock.now(),
+        });
+        assert!(disconnects(99, &request));
+        assert_eq!(manager.get_route(id_a), Some(&100));
+        assert_eq!(manager.metrics().out_state_connected.get(), 1);
+
+        // Connections which are not established are left alone.
+        assert!(manager.replace_connection(addr_b).is_none());
+    }
 }
@@ -0,0 +1,538 @@
+//! QUIC transport.
+//!
+//! As an alternative to TLS over TCP, nodes can connect to each other over QUIC, using the same
+//! identity certificates. The dialing node opens a bidirectional stream for the handshake, followed
+//! by a unidirectional stream for each outgoing message lane, starting with the index of the lane.
+//! Since QUIC delivers streams independently of each other, a large transfer on the bulk lane no
+//! longer holds up consensus messages queued behind it. A QUIC connection also survives a change of
+//! the dialing node's address, e.g. due to NAT rebinding.
+//!
+//! A node with QUIC enabled listens on the UDP port matching its TCP port, while still accepting
+//! TCP connections. When dialing, it falls back to TCP if a peer cannot be reached over QUIC, and
+//! keeps using TCP for that address for a while.
+//!
+//! Node identities use curves which `rustls` does not support, so certificates are validated and
+//! handshake signatures are created and checked with OpenSSL instead.
+
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant, SystemTime},
+};
+
+use datasize::DataSize;
+use openssl::{
+    error::ErrorStack,
+    hash::MessageDigest,
+    nid::Nid,
+    pkey::{PKey, Private},
+    sign,
+    x509::X509,
+};
+use quinn::{
+    ClientConfig, Connecting, Connection, Endpoint, IdleTimeout, RecvStream, SendStream,
+    ServerConfig, TransportConfig, VarInt,
+};
+use rustls::{
+    client::{HandshakeSignatureValid, ResolvesClientCert, ServerCertVerified, ServerCertVerifier},
+    server::{ClientCertVerified, ClientCertVerifier, ClientHello, ResolvesServerCert},
+    sign::{CertifiedKey, Signer, SigningKey},
+    Certificate, CertificateError, DigitallySignedStruct, DistinguishedName, ServerName,
+    SignatureAlgorithm, SignatureScheme,
+};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use casper_types::TimeDiff;
+
+use super::{
+    counting_format::ConnectionId,
+    error::{ConnectionError, Error},
+    lanes::{Lane, LANE_COUNT},
+};
+use crate::{
+    tls::{self, TlsCert},
+    types::NodeId,
+    utils::display_error,
+};
+
+/// The application protocol negotiated on QUIC connections.
+const ALPN_PROTOCOL: &[u8] = b"casper-node";
+
+/// The server name sent when dialing, which is not checked.
+const SERVER_NAME: &str = "this-will-not-be-checked.example.com";
+
+/// The label of the keying material connection IDs are derived from.
+const KEYING_MATERIAL_LABEL: &[u8] = b"EXPORTER-casper-connection-id";
+
+/// QUIC transport configuration.
+#[derive(DataSize, Debug, Clone, Deserialize, Serialize)]
+// Disallow unknown fields to ensure config files and command-line overrides contain valid keys.
+#[serde(deny_unknown_fields)]
+pub struct QuicConfig {
+    /// Whether to accept and dial QUIC connections, in addition to TLS over TCP.
+    pub enabled: bool,
+    /// Maximum time to wait for a QUIC connection, before falling back to TCP.
+    pub connect_timeout: TimeDiff,
+    /// Time during which an address which could not be reached over QUIC is dialed over TCP only.
+    pub fallback_duration: TimeDiff,
+    /// Time without any packets received after which a QUIC connection is closed.
+    pub idle_timeout: TimeDiff,
+}
+
+impl Default for QuicConfig {
+    fn default() -> Self {
+        QuicConfig {
+            enabled: false,
+            connect_timeout: TimeDiff::from_seconds(3),
+            fallback_duration: TimeDiff::from_seconds(600),
+            idle_timeout: TimeDiff::from_seconds(60),
+        }
+    }
+}
+
+/// A QUIC endpoint, accepting and dialing connections.
+pub(super) struct QuicEndpoint {
+    /// The underlying endpoint.
+    endpoint: Endpoint,
+    /// Our certificate, presented on new connections.
+    our_cert: Arc<OurCertificate>,
+    /// Maximum time to wait for an outgoing connection.
+    connect_timeout: Duration,
+    /// Time during which an unreachable address is not dialed again.
+    fallback_duration: Duration,
+    /// Addresses which could not be reached over QUIC, and when.
+    fallbacks: Mutex<HashMap<SocketAddr, Instant>>,
+}
+
+impl QuicEndpoint {
+    /// Binds an endpoint to the given UDP address.
+    pub(super) fn bind(
+        config: &QuicConfig,
+        bind_addr: SocketAddr,
+        our_cert: &TlsCert,
+        secret_key: &Arc<PKey<Private>>,
+        network_ca: Option<Arc<X509>>,
+    ) -> Result<Self, Error> {
+        let our_cert = Arc::new(OurCertificate(RwLock::new(certified_key(
+            our_cert, secret_key,
+        )?)));
+        let verifier = Arc::new(PeerCertVerifier { network_ca });
+        let transport = Arc::new(transport_config(config));
+
+        let mut server_crypto = rustls::ServerConfig::builder()
+            .with_safe_default_cipher_suites()
+            .with_safe_default_kx_groups()
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .map_err(Error::QuicCrypto)?
+            .with_client_cert_verifier(verifier.clone())
+            .with_cert_resolver(our_cert.clone());
+        server_crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+        let mut server_config = ServerConfig::with_crypto(Arc::new(server_crypto));
+        server_config.transport_config(transport.clone());
+        // Keep the connections of peers whose address changes.
+        server_config.migration(true);
+
+        let mut client_crypto = rustls::ClientConfig::builder()
+            .with_safe_default_cipher_suites()
+            .with_safe_default_kx_groups()
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .map_err(Error::QuicCrypto)?
+            .with_custom_certificate_verifier(verifier)
+            .with_client_cert_resolver(our_cert.clone());
+        client_crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+        let mut client_config = ClientConfig::new(Arc::new(client_crypto));
+        client_config.transport_config(transport);
+
+        let mut endpoint = Endpoint::server(server_config, bind_addr)
+            .map_err(|error| Error::QuicEndpointCreation(error, bind_addr))?;
+        endpoint.set_default_client_config(client_config);
+
+        Ok(QuicEndpoint {
+            endpoint,
+            our_cert,
+            connect_timeout: config.connect_timeout.into(),
+            fallback_duration: config.fallback_duration.into(),
+            fallbacks: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Replaces the certificate presented on new connections.
+    ///
+    /// Established connections are not affected.
+    pub(super) fn set_identity(
+        &self,
+        our_cert: &TlsCert,
+        secret_key: &Arc<PKey<Private>>,
+    ) -> Result<(), Error> {
+        let certified_key = certified_key(our_cert, secret_key)?;
+        *self.our_cert.0.write().expect("lock poisoned") = certified_key;
+        Ok(())
+    }
+
+    /// Returns `true` if the given address should be dialed over QUIC.
+    pub(super) fn should_dial(&self, addr: &SocketAddr) -> bool {
+        let mut fallbacks = self.fallbacks.lock().expect("lock poisoned");
+        fallbacks.retain(|_, since| since.elapsed() < self.fallback_duration);
+        !fallbacks.contains_key(addr)
+    }
+
+    /// Connects to the given address.
+    ///
+    /// On failure, the address is dialed over TCP only for a while.
+    pub(super) async fn connect(&self, addr: SocketAddr) -> Result<Connection, ConnectionError> {
+        let result = match self.endpoint.connect(addr, SERVER_NAME) {
+            Ok(connecting) => match tokio::time::timeout(self.connect_timeout, connecting).await {
+                Ok(result) => result.map_err(ConnectionError::QuicConnection),
+                Err(_elapsed) => Err(ConnectionError::QuicTimeout),
+            },
+            Err(error) => Err(ConnectionError::QuicConnect(error)),
+        };
+        if result.is_err() {
+            self.fallbacks
+                .lock()
+                .expect("lock poisoned")
+                .insert(addr, Instant::now());
+        }
+        result
+    }
+
+    /// Accepts the next incoming connection, or returns `None` if the endpoint has been closed.
+    pub(super) async fn accept(&self) -> Option<Connecting> {
+        self.endpoint.accept().await
+    }
+
+    /// Closes the endpoint, along with all its connections.
+    pub(super) fn close(&self) {
+        self.endpoint.close(VarInt::from_u32(0), b"shutting down");
+    }
+}
+
+/// Returns the certificate presented by the peer of an established connection.
+pub(super) fn peer_certificate(connection: &Connection) -> Result<X509, ConnectionError> {
+    let certificates = connection
+        .peer_identity()
+        .and_then(|identity| identity.downcast::<Vec<Certificate>>().ok())
+        .ok_or(ConnectionError::NoPeerCertificate)?;
+    let end_entity = certificates
+        .first()
+        .ok_or(ConnectionError::NoPeerCertificate)?;
+    X509::from_der(&end_entity.0).map_err(ConnectionError::InvalidQuicPeerCertificate)
+}
+
+/// Derives the ID of a connection from keying material exported from it.
+pub(super) fn connection_id(
+    connection: &Connection,
+    our_id: NodeId,
+    their_id: NodeId,
+) -> Result<ConnectionId, ConnectionError> {
+    let mut keying_material = [0; 12];
+    connection
+        .export_keying_material(&mut keying_material, KEYING_MATERIAL_LABEL, &[])
+        .map_err(|_| ConnectionError::QuicKeyingMaterial)?;
+    Ok(ConnectionId::from_keying_material(
+        keying_material,
+        our_id,
+        their_id,
+    ))
+}
+
+/// Opens a stream for each lane, in the order of [`Lane::ALL`].
+///
+/// Each stream starts with the index of its lane, which also makes it known to the peer right
+/// away.
+pub(super) async fn open_lane_streams(connection: &Connection) -> io::Result<Vec<SendStream>> {
+    let mut streams = Vec::with_capacity(LANE_COUNT);
+    for lane in Lane::ALL {
+        let mut stream = connection.open_uni().await?;
+        stream.write_all(&[lane.index() as u8]).await?;
+        streams.push(stream);
+    }
+    Ok(streams)
+}
+
+/// Accepts the streams opened by the peer for each lane.
+pub(super) async fn accept_lane_streams(connection: &Connection) -> io::Result<Vec<RecvStream>> {
+    let mut streams: Vec<Option<RecvStream>> = (0..LANE_COUNT).map(|_| None).collect();
+    for _ in 0..LANE_COUNT {
+        let mut stream = connection.accept_uni().await?;
+        let mut index = [0];
+        stream
+            .read_exact(&mut index)
+            .await
+            .map_err(|error| io::Error::new(io::ErrorKind::UnexpectedEof, error))?;
+        match streams.get_mut(index[0] as usize) {
+            Some(slot @ None) => *slot = Some(stream),
+            Some(Some(_)) | None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid or duplicate lane stream {}", index[0]),
+                ))
+            }
+        }
+    }
+    Ok(streams.into_iter().flatten().collect())
+}
+
+/// Creates the transport configuration shared by incoming and outgoing connections.
+fn transport_config(config: &QuicConfig) -> TransportConfig {
+    let idle_timeout = Duration::from(config.idle_timeout);
+    let mut transport = TransportConfig::default();
+    // Peers only ever open the handshake stream and a stream per lane.
+    transport.max_concurrent_bidi_streams(VarInt::from_u32(1));
+    transport.max_concurrent_uni_streams(VarInt::from_u32(LANE_COUNT as u32));
+    // An idle timeout too large to be represented disables it.
+    transport.max_idle_timeout(IdleTimeout::try_from(idle_timeout).ok());
+    transport.keep_alive_interval(Some(idle_timeout / 3));
+    transport
+}
+
+/// Returns our certificate along with a key signing with the scheme matching its curve.
+fn certified_key(
+    our_cert: &TlsCert,
+    secret_key: &Arc<PKey<Private>>,
+) -> Result<Arc<CertifiedKey>, Error> {
+    let certificate = our_cert
+        .as_x509()
+        .to_der()
+        .map_err(Error::QuicCertificate)?;
+    let curve = secret_key
+        .ec_key()
+        .map_err(Error::QuicCertificate)?
+        .group()
+        .curve_name();
+    let scheme = match curve {
+        Some(Nid::X9_62_PRIME256V1) => SignatureScheme::ECDSA_NISTP256_SHA256,
+        Some(Nid::SECP384R1) => SignatureScheme::ECDSA_NISTP384_SHA384,
+        Some(Nid::SECP521R1) => SignatureScheme::ECDSA_NISTP521_SHA512,
+        _ => return Err(Error::QuicUnsupportedKey),
+    };
+    let signing_key = OpenSslSigningKey {
+        secret_key: secret_key.clone(),
+        scheme,
+    };
+    Ok(Arc::new(CertifiedKey::new(
+        vec![Certificate(certificate)],
+        Arc::new(signing_key),
+    )))
+}
+
+/// Returns the digest used by an ECDSA signature scheme.
+fn scheme_digest(scheme: SignatureScheme) -> Option<MessageDigest> {
+    match scheme {
+        SignatureScheme::ECDSA_NISTP256_SHA256 => Some(MessageDigest::sha256()),
+        SignatureScheme::ECDSA_NISTP384_SHA384 => Some(MessageDigest::sha384()),
+        SignatureScheme::ECDSA_NISTP521_SHA512 => Some(MessageDigest::sha512()),
+        _ => None,
+    }
+}
+
+/// Our secret key, signing handshakes with OpenSSL.
+#[derive(Clone)]
+struct OpenSslSigningKey {
+    /// The secret key.
+    secret_key: Arc<PKey<Private>>,
+    /// The signature scheme matching the key's curve.
+    scheme: SignatureScheme,
+}
+
+impl SigningKey for OpenSslSigningKey {
+    fn choose_scheme(&self, offered: &[SignatureScheme]) -> Option<Box<dyn Signer>> {
+        if offered.contains(&self.scheme) {
+            Some(Box::new(self.clone()))
+        } else {
+            None
+        }
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::ECDSA
+    }
+}
+
+impl Signer for OpenSslSigningKey {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, rustls::Error> {
+        let digest = scheme_digest(self.scheme).expect("scheme chosen from our key's curve");
+        let sign = || -> Result<Vec<u8>, ErrorStack> {
+            let mut signer = sign::Signer::new(digest, &self.secret_key)?;
+            signer.update(message)?;
+            signer.sign_to_vec()
+        };
+        sign().map_err(|error| rustls::Error::General(error.to_string()))
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+}
+
+/// Resolves our certificate, when acting as either a client or a server.
+///
+/// The certificate can be replaced while the endpoint is running.
+struct OurCertificate(RwLock<Arc<CertifiedKey>>);
+
+impl OurCertificate {
+    /// Returns the current certificate.
+    fn current(&self) -> Arc<CertifiedKey> {
+        self.0.read().expect("lock poisoned").clone()
+    }
+}
+
+impl ResolvesServerCert for OurCertificate {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.current())
+    }
+}
+
+impl ResolvesClientCert for OurCertificate {
+    fn resolve(
+        &self,
+        _acceptable_issuers: &[&[u8]],
+        _sigschemes: &[SignatureScheme],
+    ) -> Option<Arc<CertifiedKey>> {
+        Some(self.current())
+    }
+
+    fn has_certs(&self) -> bool {
+        true
+    }
+}
+
+/// Validates peer certificates and handshake signatures with OpenSSL.
+struct PeerCertVerifier {
+    /// The certificate authority peer certificates must be signed by, if any.
+    network_ca: Option<Arc<X509>>,
+}
+
+impl PeerCertVerifier {
+    /// Validates a peer certificate, the same way certificates of TLS connections are validated.
+    fn validate(&self, end_entity: &Certificate) -> Result<(), rustls::Error> {
+        let cert = X509::from_der(&end_entity.0)
+            .map_err(|_| rustls::Error::InvalidCertificate(CertificateError::BadEncoding))?;
+        let validated = match &self.network_ca {
+            Some(ca_cert) => tls::validate_cert_with_authority(cert, ca_cert),
+            None => tls::validate_self_signed_cert(cert),
+        };
+        validated.map(drop).map_err(|ref err| {
+            debug!(err = display_error(err), "rejecting QUIC peer certificate");
+            rustls::Error::InvalidCertificate(CertificateError::ApplicationVerificationFailure)
+        })
+    }
+
+    /// Verifies a TLS 1.3 handshake signature made with the key of the given certificate.
+    fn verify_signature(
+        message: &[u8],
+        cert: &Certificate,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        let digest = scheme_digest(dss.scheme).ok_or_else(|| {
+            rustls::Error::General(format!("unsupported signature scheme {:?}", dss.scheme))
+        })?;
+        let public_key = X509::from_der(&cert.0)
+            .and_then(|cert| cert.public_key())
+            .map_err(|_| rustls::Error::InvalidCertificate(CertificateError::BadEncoding))?;
+        let verify = || -> Result<bool, ErrorStack> {
+            let mut verifier = sign::Verifier::new(digest, &public_key)?;
+            verifier.update(message)?;
+            verifier.verify(dss.signature())
+        };
+        if verify().unwrap_or(false) {
+            Ok(HandshakeSignatureValid::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::BadSignature,
+            ))
+        }
+    }
+
+    /// The signature schemes of all curves supported by OpenSSL.
+    fn supported_schemes() -> Vec<SignatureScheme> {
+        vec![
+            SignatureScheme::ECDSA_NISTP521_SHA512,
+            SignatureScheme::ECDSA_NISTP384_SHA384,
+            SignatureScheme::ECDSA_NISTP256_SHA256,
+        ]
+    }
+}
+
+/// Returns the error for TLS 1.2 signatures, which are never used with QUIC.
+fn tls12_unsupported() -> rustls::Error {
+    rustls::Error::General("TLS 1.2 is not supported".to_string())
+}
+
+impl ServerCertVerifier for PeerCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        self.validate(end_entity)?;
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &Certificate,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Err(tls12_unsupported())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &Certificate,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Self::verify_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        Self::supported_schemes()
+    }
+}
+
+impl ClientCertVerifier for PeerCertVerifier {
+    fn client_auth_root_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _now: SystemTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        self.validate(end_entity)?;
+        Ok(ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &Certificate,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Err(tls12_unsupported())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &Certificate,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Self::verify_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        Self::supported_schemes()
+    }
+}
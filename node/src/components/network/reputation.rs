@@ -0,0 +1,293 @@
+//! Peer reputation scoring.
+//!
+//! Every peer carries a score which rises with useful behavior, like delivering fetched items,
+//! gossiping new items or answering pings in time, and falls with unhelpful or faulty behavior.
+//! Scores decay exponentially towards zero, so that past behavior is gradually forgotten.
+//!
+//! Peers scoring below the deprioritization threshold are only chosen for gossiping and fetching
+//! once all better peers are exhausted, while peers scoring below the ban threshold are blocked.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display, Formatter},
+    time::{Duration, Instant},
+};
+
+use datasize::DataSize;
+use serde::{Deserialize, Serialize};
+
+use casper_types::TimeDiff;
+
+use crate::types::NodeId;
+
+/// The highest score a peer can reach, limiting how much credit good behavior can build up.
+const MAX_SCORE: f64 = 100.0;
+
+/// The lowest score a peer can reach.
+const MIN_SCORE: f64 = -1000.0;
+
+/// The score penalty for an offense which does not warrant an immediate ban.
+const OFFENSE_PENALTY: f64 = -50.0;
+
+/// Scores closer to zero than this are forgotten during housekeeping.
+const NEGLIGIBLE_SCORE: f64 = 0.5;
+
+/// Reputation scoring configuration.
+#[derive(DataSize, Debug, Clone, Deserialize, Serialize)]
+// Disallow unknown fields to ensure config files and command-line overrides contain valid keys.
+#[serde(deny_unknown_fields)]
+pub struct ReputationConfig {
+    /// Time after which half of a peer's score has decayed.
+    pub half_life: TimeDiff,
+    /// Peers with a score below this threshold are deprioritized when gossiping and fetching.
+    pub deprioritize_threshold: i32,
+    /// Peers with a score below this threshold are blocked.
+    pub ban_threshold: i32,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        ReputationConfig {
+            half_life: TimeDiff::from_seconds(600),
+            deprioritize_threshold: -20,
+            ban_threshold: -100,
+        }
+    }
+}
+
+/// An observed behavior of a peer, affecting its reputation.
+#[derive(Clone, Copy, DataSize, Debug, PartialEq, Eq, Serialize)]
+pub(crate) enum PeerObservation {
+    /// The peer delivered a valid item we fetched from it.
+    FetchSucceeded,
+    /// The peer did not deliver an item we fetched from it in time.
+    FetchTimedOut,
+    /// The peer did not have an item we fetched from it.
+    ItemAbsent,
+    /// The peer gossiped an item we did not hold yet.
+    UsefulGossip,
+    /// The peer answered a ping in time.
+    PongReceived,
+}
+
+impl PeerObservation {
+    /// Returns the change to a peer's score caused by the observation.
+    fn score_delta(self) -> f64 {
+        match self {
+            PeerObservation::FetchSucceeded => 1.0,
+            PeerObservation::FetchTimedOut => -5.0,
+            PeerObservation::ItemAbsent => -1.0,
+            PeerObservation::UsefulGossip => 0.5,
+            PeerObservation::PongReceived => 0.5,
+        }
+    }
+}
+
+impl Display for PeerObservation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PeerObservation::FetchSucceeded => f.write_str("delivered a fetched item"),
+            PeerObservation::FetchTimedOut => f.write_str("timed out on a fetch request"),
+            PeerObservation::ItemAbsent => f.write_str("did not have a requested item"),
+            PeerObservation::UsefulGossip => f.write_str("gossiped a new item"),
+            PeerObservation::PongReceived => f.write_str("answered a ping"),
+        }
+    }
+}
+
+/// The score of a single peer.
+#[derive(Clone, Copy, DataSize, Debug)]
+struct PeerScore {
+    /// The score at the time of the last update.
+    value: f64,
+    /// The time of the last update.
+    updated: Instant,
+}
+
+/// Reputation scores of peers.
+#[derive(DataSize, Debug)]
+pub(super) struct PeerScores {
+    /// Time after which half of a score has decayed.
+    half_life: Duration,
+    /// Threshold below which peers are deprioritized.
+    deprioritize_threshold: f64,
+    /// Threshold below which peers are blocked.
+    ban_threshold: f64,
+    /// The scores of all peers with a non-negligible score.
+    scores: HashMap<NodeId, PeerScore>,
+}
+
+impl PeerScores {
+    /// Creates a new, empty set of scores.
+    pub(super) fn new(config: &ReputationConfig) -> Self {
+        PeerScores {
+            half_life: config.half_life.into(),
+            deprioritize_threshold: config.deprioritize_threshold.into(),
+            ban_threshold: config.ban_threshold.into(),
+            scores: HashMap::new(),
+        }
+    }
+
+    /// Applies an observed behavior to the peer's score.
+    ///
+    /// Returns `true` if the peer's score fell below the ban threshold.
+    pub(super) fn observe(
+        &mut self,
+        peer_id: NodeId,
+        observation: PeerObservation,
+        now: Instant,
+    ) -> bool {
+        self.adjust(peer_id, observation.score_delta(), now)
+    }
+
+    /// Penalizes the peer for an offense.
+    ///
+    /// Returns `true` if the peer's score fell below the ban threshold.
+    pub(super) fn penalize(&mut self, peer_id: NodeId, now: Instant) -> bool {
+        self.adjust(peer_id, OFFENSE_PENALTY, now)
+    }
+
+    /// Forgets the peer's score, e.g. after it has been blocked.
+    pub(super) fn reset(&mut self, peer_id: &NodeId) {
+        self.scores.remove(peer_id);
+    }
+
+    /// Returns the current score of the peer.
+    pub(super) fn score(&self, peer_id: &NodeId, now: Instant) -> f64 {
+        self.scores
+            .get(peer_id)
+            .map_or(0.0, |score| self.decayed(score, now))
+    }
+
+    /// Returns `true` if the peer's score is below the deprioritization threshold.
+    pub(super) fn is_deprioritized(&self, peer_id: &NodeId, now: Instant) -> bool {
+        self.score(peer_id, now) < self.deprioritize_threshold
+    }
+
+    /// Returns the current scores of all peers with a non-negligible score, rounded to integers.
+    pub(super) fn snapshot(&self, now: Instant) -> BTreeMap<NodeId, i32> {
+        self.scores
+            .iter()
+            .map(|(peer_id, score)| (*peer_id, self.decayed(score, now).round() as i32))
+            .collect()
+    }
+
+    /// Forgets all scores which have decayed to a negligible value.
+    pub(super) fn prune(&mut self, now: Instant) {
+        let half_life = self.half_life;
+        self.scores
+            .retain(|_, score| decay(score, half_life, now).abs() >= NEGLIGIBLE_SCORE);
+    }
+
+    fn adjust(&mut self, peer_id: NodeId, delta: f64, now: Instant) -> bool {
+        let current = self.score(&peer_id, now);
+        let value = (current + delta).clamp(MIN_SCORE, MAX_SCORE);
+        self.scores.insert(
+            peer_id,
+            PeerScore {
+                value,
+                updated: now,
+            },
+        );
+        value < self.ban_threshold
+    }
+
+    fn decayed(&self, score: &PeerScore, now: Instant) -> f64 {
+        decay(score, self.half_life, now)
+    }
+}
+
+/// Returns the value of the score at `now`, after exponential decay with the given half-life.
+fn decay(score: &PeerScore, half_life: Duration, now: Instant) -> f64 {
+    if half_life.is_zero() {
+        return 0.0;
+    }
+    let elapsed = now.saturating_duration_since(score.updated).as_secs_f64();
+    score.value * 0.5_f64.powf(elapsed / half_life.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use casper_types::testing::TestRng;
+
+    use super::{PeerObservation, PeerScores, ReputationConfig};
+    use crate::types::NodeId;
+
+    fn scores() -> PeerScores {
+        PeerScores::new(&ReputationConfig::default())
+    }
+
+    #[test]
+    fn should_decay_by_half_after_half_life() {
+        let mut rng = TestRng::new();
+        let peer_id = NodeId::random(&mut rng);
+        let mut scores = scores();
+        let now = Instant::now();
+
+        assert!(!scores.penalize(peer_id, now));
+        assert_eq!(scores.score(&peer_id, now), -50.0);
+
+        let later = now + Duration::from_secs(600);
+        assert!((scores.score(&peer_id, later) + 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn should_deprioritize_and_ban_below_thresholds() {
+        let mut rng = TestRng::new();
+        let peer_id = NodeId::random(&mut rng);
+        let mut scores = scores();
+        let now = Instant::now();
+
+        for _ in 0..4 {
+            assert!(!scores.observe(peer_id, PeerObservation::FetchTimedOut, now));
+        }
+        assert!(!scores.is_deprioritized(&peer_id, now));
+        assert!(!scores.observe(peer_id, PeerObservation::FetchTimedOut, now));
+        assert!(scores.is_deprioritized(&peer_id, now));
+
+        // After enough time has passed, the peer is no longer deprioritized.
+        let later = now + Duration::from_secs(1200);
+        assert!(!scores.is_deprioritized(&peer_id, later));
+
+        assert!(!scores.penalize(peer_id, later));
+        assert!(scores.penalize(peer_id, later));
+    }
+
+    #[test]
+    fn should_cap_positive_score() {
+        let mut rng = TestRng::new();
+        let peer_id = NodeId::random(&mut rng);
+        let mut scores = scores();
+        let now = Instant::now();
+
+        for _ in 0..1000 {
+            scores.observe(peer_id, PeerObservation::FetchSucceeded, now);
+        }
+        assert_eq!(scores.score(&peer_id, now), 100.0);
+
+        // Built-up credit is not enough to survive a series of offenses.
+        assert!(!scores.penalize(peer_id, now));
+        assert!(!scores.penalize(peer_id, now));
+        assert!(!scores.penalize(peer_id, now));
+        assert!(!scores.penalize(peer_id, now));
+        assert!(scores.penalize(peer_id, now));
+    }
+
+    #[test]
+    fn should_prune_negligible_scores() {
+        let mut rng = TestRng::new();
+        let peer_id = NodeId::random(&mut rng);
+        let mut scores = scores();
+        let now = Instant::now();
+
+        scores.observe(peer_id, PeerObservation::FetchSucceeded, now);
+        scores.prune(now);
+        assert_eq!(scores.snapshot(now).get(&peer_id), Some(&1));
+
+        let later = now + Duration::from_secs(1200);
+        scores.prune(later);
+        assert!(scores.snapshot(later).is_empty());
+    }
+}
@@ -0,0 +1,235 @@
+//! Sentry node topology.
+//!
+//! A validator can be shielded from the public network by a set of sentry nodes. Such a protected
+//! validator only connects to its sentries, refuses incoming connections from anyone else and never
+//! gossips its own address. Its sentries relay consensus messages between it and the other
+//! validators, and keep its address out of address gossip, so that it does not propagate through
+//! the network.
+//!
+//! Sentries announce their role in the handshake, so that validators send them their consensus
+//! messages along with the validators they are connected to. As anyone could make that claim to
+//! receive consensus traffic, the announcement is only honored for the configured trusted sentries.
+//! Replies to messages relayed from a protected node are addressed to the sentry that relayed them,
+//! which passes them on.
+
+use std::{
+    collections::HashSet,
+    net::{IpAddr, SocketAddr},
+};
+
+use datasize::DataSize;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::error::{Error, Result};
+use crate::{
+    tls::KeyFingerprint,
+    types::NodeId,
+    utils::{self, display_error},
+};
+
+/// Sentry topology configuration.
+///
+/// At most one of the two lists may be non-empty. If both are empty, the node takes part in the
+/// network as usual.
+#[derive(DataSize, Debug, Clone, Default, Deserialize, Serialize)]
+// Disallow unknown fields to ensure config files and command-line overrides contain valid keys.
+#[serde(deny_unknown_fields)]
+pub struct SentryConfig {
+    /// Addresses of the sentries protecting this node.
+    ///
+    /// If set, this node connects to these addresses only.
+    pub sentry_addresses: Vec<String>,
+    /// Addresses of the nodes protected by this node, acting as their sentry.
+    pub protected_addresses: Vec<String>,
+    /// Hex-encoded node IDs of the sentries whose announcement to relay consensus messages is
+    /// honored.
+    ///
+    /// A sentry rotating its network identity has to be listed under its new node ID.
+    #[serde(default)]
+    pub trusted_sentries: Vec<String>,
+}
+
+/// The role of this node in a sentry topology.
+#[derive(DataSize, Debug, Clone, PartialEq, Eq)]
+pub(super) enum SentryTopology {
+    /// The node is neither protected nor a sentry.
+    Open,
+    /// The node is protected by the given sentries.
+    Protected {
+        /// The addresses of the sentries.
+        sentries: HashSet<SocketAddr>,
+    },
+    /// The node is a sentry for the nodes at the given addresses.
+    Sentry {
+        /// The addresses of the protected nodes.
+        protected: HashSet<SocketAddr>,
+    },
+}
+
+impl SentryTopology {
+    /// Resolves the configured addresses.
+    pub(super) fn resolve(config: &SentryConfig) -> Result<Self> {
+        match (
+            config.sentry_addresses.is_empty(),
+            config.protected_addresses.is_empty(),
+        ) {
+            (true, true) => Ok(SentryTopology::Open),
+            (false, true) => {
+                let sentries = resolve_addresses(&config.sentry_addresses);
+                if sentries.is_empty() {
+                    return Err(Error::EmptySentries);
+                }
+                Ok(SentryTopology::Protected { sentries })
+            }
+            (true, false) => Ok(SentryTopology::Sentry {
+                protected: resolve_addresses(&config.protected_addresses),
+            }),
+            (false, false) => Err(Error::ConflictingSentryConfig),
+        }
+    }
+
+    /// Returns `true` if this node is protected by sentries.
+    pub(super) fn is_protected(&self) -> bool {
+        matches!(self, SentryTopology::Protected { .. })
+    }
+
+    /// Returns `true` if this node is a sentry for other nodes.
+    pub(super) fn is_sentry(&self) -> bool {
+        matches!(self, SentryTopology::Sentry { .. })
+    }
+
+    /// Returns the IP addresses incoming connections are restricted to, if any.
+    pub(super) fn incoming_allowlist(&self) -> Option<HashSet<IpAddr>> {
+        match self {
+            SentryTopology::Protected { sentries } => {
+                Some(sentries.iter().map(SocketAddr::ip).collect())
+            }
+            SentryTopology::Open | SentryTopology::Sentry { .. } => None,
+        }
+    }
+
+    /// Returns `true` if the given address belongs to a node protected by us.
+    ///
+    /// Such addresses must never be learned from or passed on through gossip.
+    pub(super) fn is_protected_address(&self, addr: &SocketAddr) -> bool {
+        match self {
+            SentryTopology::Sentry { protected } => protected.contains(addr),
+            SentryTopology::Open | SentryTopology::Protected { .. } => false,
+        }
+    }
+}
+
+/// Parses the hex-encoded node IDs of the trusted sentries.
+pub(super) fn parse_trusted_sentries(config: &SentryConfig) -> Result<HashSet<NodeId>> {
+    config
+        .trusted_sentries
+        .iter()
+        .map(|id| {
+            let bytes = base16::decode(id.as_bytes())
+                .ok()
+                .filter(|bytes| bytes.len() == KeyFingerprint::LENGTH)
+                .ok_or_else(|| Error::InvalidSentryId(id.clone()))?;
+            let mut array = [0; KeyFingerprint::LENGTH];
+            array.copy_from_slice(&bytes);
+            Ok(NodeId::from(KeyFingerprint::from(array)))
+        })
+        .collect()
+}
+
+/// Resolves the given addresses, skipping and logging those which fail to resolve.
+fn resolve_addresses(addresses: &[String]) -> HashSet<SocketAddr> {
+    addresses
+        .iter()
+        .filter_map(|address| match utils::resolve_address(address) {
+            Ok(resolved) => Some(resolved),
+            Err(ref err) => {
+                warn!(%address, err=display_error(err), "failed to resolve sentry topology address");
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use super::{parse_trusted_sentries, SentryConfig, SentryTopology};
+    use crate::{components::network::Error, types::NodeId};
+
+    #[test]
+    fn should_resolve_roles() {
+        assert_eq!(
+            SentryTopology::resolve(&SentryConfig::default()).unwrap(),
+            SentryTopology::Open
+        );
+
+        let protected = SentryTopology::resolve(&SentryConfig {
+            sentry_addresses: vec!["10.0.0.1:35000".to_string()],
+            ..SentryConfig::default()
+        })
+        .unwrap();
+        assert!(protected.is_protected());
+        let allowlist = protected.incoming_allowlist().unwrap();
+        assert!(allowlist.contains(&"10.0.0.1".parse().unwrap()));
+        assert!(!allowlist.contains(&"10.0.0.2".parse().unwrap()));
+
+        let validator_addr: SocketAddr = "10.0.0.3:35000".parse().unwrap();
+        let sentry = SentryTopology::resolve(&SentryConfig {
+            protected_addresses: vec![validator_addr.to_string()],
+            ..SentryConfig::default()
+        })
+        .unwrap();
+        assert!(!sentry.is_protected());
+        assert!(sentry.is_sentry());
+        assert!(!protected.is_sentry());
+        assert!(sentry.incoming_allowlist().is_none());
+        assert!(sentry.is_protected_address(&validator_addr));
+    }
+
+    #[test]
+    fn should_reject_invalid_configs() {
+        let conflicting = SentryConfig {
+            sentry_addresses: vec!["10.0.0.1:35000".to_string()],
+            protected_addresses: vec!["10.0.0.3:35000".to_string()],
+            ..SentryConfig::default()
+        };
+        assert!(matches!(
+            SentryTopology::resolve(&conflicting),
+            Err(Error::ConflictingSentryConfig)
+        ));
+
+        let unresolvable = SentryConfig {
+            sentry_addresses: vec!["not an address".to_string()],
+            ..SentryConfig::default()
+        };
+        assert!(matches!(
+            SentryTopology::resolve(&unresolvable),
+            Err(Error::EmptySentries)
+        ));
+    }
+
+    #[test]
+    fn should_parse_trusted_sentries() {
+        let id = "ab".repeat(64);
+        let trusted = parse_trusted_sentries(&SentryConfig {
+            trusted_sentries: vec![id.clone()],
+            ..SentryConfig::default()
+        })
+        .unwrap();
+        assert_eq!(trusted.len(), 1);
+        let node_id: &NodeId = trusted.iter().next().unwrap();
+        assert_eq!(base16::encode_lower(node_id.hash_bytes()), id);
+
+        for invalid in ["ab".repeat(63), "not hex".to_string()] {
+            assert!(matches!(
+                parse_trusted_sentries(&SentryConfig {
+                    trusted_sentries: vec![invalid],
+                    ..SentryConfig::default()
+                }),
+                Err(Error::InvalidSentryId(_))
+            ));
+        }
+    }
+}
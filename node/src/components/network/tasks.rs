@@ -1,14 +1,15 @@
 //! Tasks run by the component.
 
 use std::{
+    collections::HashSet,
     error::Error as StdError,
     fmt::Display,
     io,
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
     pin::Pin,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc, Weak,
+        Arc, RwLock, Weak,
     },
     time::Duration,
 };
@@ -16,20 +17,19 @@ use std::{
 use bincode::Options;
 use futures::{
     future::{self, Either},
-    stream::{SplitSink, SplitStream},
+    stream::{FuturesUnordered, SplitSink},
     Future, SinkExt, StreamExt,
 };
-use openssl::{
-    pkey::{PKey, Private},
-    ssl::Ssl,
-    x509::X509,
-};
+use openssl::{ssl::Ssl, x509::X509};
 use prometheus::IntGauge;
 use rand::Rng;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use tokio::{
     net::TcpStream,
-    sync::{mpsc::UnboundedReceiver, watch, Semaphore},
+    sync::{
+        mpsc::{self, error::SendError, UnboundedReceiver},
+        watch, Semaphore,
+    },
 };
 use tokio_openssl::SslStream;
 use tokio_serde::{Deserializer, Serializer};
@@ -43,15 +43,18 @@ use casper_types::{ProtocolVersion, PublicKey, TimeDiff};
 
 use super::{
     chain_info::ChainInfo,
+    compression::{CompressionConfig, Compressor},
     counting_format::{ConnectionId, Role},
-    error::{ConnectionError, IoError},
+    error::{ConnectionError, Error, IoError},
     event::{IncomingConnection, OutgoingConnection},
     full_transport,
+    lanes::{Lane, LaneLimiterHandles, LaneMetrics, OutgoingLanes, Scheduled},
     limiter::LimiterHandle,
-    message::NodeKeyPair,
+    message::{ConsensusCertificate, NodeKeyPair},
     message_pack_format::MessagePackFormat,
+    quic::{self, QuicEndpoint},
+    transport::{IncomingStream, OutgoingSink, Transport},
     EstimatorWeights, Event, FramedTransport, FullTransport, Identity, Message, Metrics, Payload,
-    Transport,
 };
 use crate::{
     components::network::{framed_transport, BincodeFormat, Config, FromIncoming},
@@ -81,6 +84,79 @@ struct HandshakeOutcome {
     peer_consensus_public_key: Option<PublicKey>,
     /// Holds the information whether the remote node is syncing.
     is_peer_syncing: bool,
+    /// The compression negotiated with the peer, if any.
+    compressor: Option<Compressor>,
+    /// Whether the peer is a sentry relaying consensus messages.
+    peer_relays_consensus: bool,
+    /// Whether the peer accepts set reconciliation filters of gossiped items.
+    peer_accepts_reconciliation: bool,
+}
+
+/// An incoming connection, before encryption is set up.
+enum IncomingBase {
+    /// A TCP connection.
+    Tcp(TcpStream),
+    /// A QUIC connection attempt.
+    Quic(quinn::Connecting),
+}
+
+/// An encrypted connection to a peer, before the handshake.
+enum EncryptedConnection {
+    /// A TLS connection over TCP.
+    Tls(SslStream<TcpStream>),
+    /// A QUIC connection.
+    Quic(quinn::Connection),
+}
+
+impl EncryptedConnection {
+    /// Derives the ID of the connection, which is the same on both ends.
+    fn connection_id(
+        &self,
+        our_id: NodeId,
+        their_id: NodeId,
+    ) -> Result<ConnectionId, ConnectionError> {
+        match self {
+            EncryptedConnection::Tls(stream) => Ok(ConnectionId::from_connection(
+                stream.ssl(),
+                our_id,
+                their_id,
+            )),
+            EncryptedConnection::Quic(connection) => {
+                quic::connection_id(connection, our_id, their_id)
+            }
+        }
+    }
+
+    /// Returns the transport the handshake is performed on, along with the QUIC connection, if any.
+    ///
+    /// On a QUIC connection, the dialer opens a bidirectional stream for the handshake.
+    async fn into_handshake_transport(
+        self,
+        role: Role,
+        timeout: Duration,
+    ) -> Result<(Transport, Option<quinn::Connection>), ConnectionError> {
+        match self {
+            EncryptedConnection::Tls(stream) => Ok((Transport::Tls(stream), None)),
+            EncryptedConnection::Quic(connection) => {
+                let (send, recv) = match role {
+                    Role::Dialer => {
+                        io_timeout(timeout, async {
+                            connection.open_bi().await.map_err(io::Error::from)
+                        })
+                        .await
+                    }
+                    Role::Listener => {
+                        io_timeout(timeout, async {
+                            connection.accept_bi().await.map_err(io::Error::from)
+                        })
+                        .await
+                    }
+                }
+                .map_err(ConnectionError::QuicStreams)?;
+                Ok((Transport::QuicBidirectional(send, recv), Some(connection)))
+            }
+        }
+    }
 }
 
 /// Low-level TLS connection function.
@@ -89,7 +165,7 @@ struct HandshakeOutcome {
 async fn tls_connect<REv>(
     context: &NetworkContext<REv>,
     peer_addr: SocketAddr,
-) -> Result<(NodeId, Transport), ConnectionError>
+) -> Result<(NodeId, SslStream<TcpStream>), ConnectionError>
 where
     REv: 'static,
 {
@@ -101,14 +177,16 @@ where
         .set_nodelay(true)
         .map_err(ConnectionError::TcpNoDelay)?;
 
-    let mut transport = tls::create_tls_connector(context.our_cert.as_x509(), &context.secret_key)
-        .and_then(|connector| connector.configure())
-        .and_then(|mut config| {
-            config.set_verify_hostname(false);
-            config.into_ssl("this-will-not-be-checked.example.com")
-        })
-        .and_then(|ssl| SslStream::new(ssl, stream))
-        .map_err(ConnectionError::TlsInitialization)?;
+    let identity = context.identity();
+    let mut transport =
+        tls::create_tls_connector(identity.tls_certificate.as_x509(), &identity.secret_key)
+            .and_then(|connector| connector.configure())
+            .and_then(|mut config| {
+                config.set_verify_hostname(false);
+                config.into_ssl("this-will-not-be-checked.example.com")
+            })
+            .and_then(|ssl| SslStream::new(ssl, stream))
+            .map_err(ConnectionError::TlsInitialization)?;
 
     SslStream::connect(Pin::new(&mut transport))
         .await
@@ -128,7 +206,50 @@ where
     Ok((peer_id, transport))
 }
 
-/// Initiates a TLS connection to a remote address.
+/// Validates the certificate of the peer of a QUIC connection.
+fn quic_peer_id<REv>(
+    context: &NetworkContext<REv>,
+    connection: &quinn::Connection,
+) -> Result<NodeId, ConnectionError> {
+    let validated_peer_cert = context
+        .validate_peer_cert(quic::peer_certificate(connection)?)
+        .map_err(ConnectionError::PeerCertificateInvalid)?;
+
+    Ok(NodeId::from(validated_peer_cert.public_key_fingerprint()))
+}
+
+/// Connects to a remote address, over QUIC if possible, falling back to TLS over TCP.
+async fn connect<REv>(
+    context: &NetworkContext<REv>,
+    peer_addr: SocketAddr,
+) -> Result<(NodeId, EncryptedConnection), ConnectionError>
+where
+    REv: 'static,
+{
+    if let Some(quic_endpoint) = context
+        .quic_endpoint
+        .as_ref()
+        .filter(|quic_endpoint| quic_endpoint.should_dial(&peer_addr))
+    {
+        match quic_endpoint.connect(peer_addr).await {
+            Ok(connection) => {
+                let peer_id = quic_peer_id(context, &connection)?;
+                return Ok((peer_id, EncryptedConnection::Quic(connection)));
+            }
+            Err(ref err) => {
+                debug!(
+                    err = display_error(err),
+                    "could not connect over QUIC, falling back to TCP"
+                );
+            }
+        }
+    }
+
+    let (peer_id, transport) = tls_connect(context, peer_addr).await?;
+    Ok((peer_id, EncryptedConnection::Tls(transport)))
+}
+
+/// Initiates a connection to a remote address.
 pub(super) async fn connect_outgoing<P, REv>(
     context: Arc<NetworkContext<REv>>,
     peer_addr: SocketAddr,
@@ -137,7 +258,7 @@ where
     REv: 'static,
     P: Payload,
 {
-    let (peer_id, transport) = match tls_connect(&context, peer_addr).await {
+    let (peer_id, connection) = match connect(&context, peer_addr).await {
         Ok(value) => value,
         Err(error) => return OutgoingConnection::FailedEarly { peer_addr, error },
     };
@@ -145,57 +266,111 @@ where
     // Register the `peer_id` on the [`Span`].
     Span::current().record("peer_id", &field::display(peer_id));
 
-    if peer_id == context.our_id {
+    if peer_id == context.our_id() {
         info!("incoming loopback connection");
         return OutgoingConnection::Loopback { peer_addr };
     }
 
-    debug!("Outgoing TLS connection established");
+    debug!("Outgoing encrypted connection established");
 
-    // Setup connection id and framed transport.
-    let connection_id = ConnectionId::from_connection(transport.ssl(), context.our_id, peer_id);
-    let framed_transport = framed_transport(transport, context.chain_info.maximum_net_message_size);
+    match setup_outgoing::<P, _>(&context, connection, peer_addr, peer_id).await {
+        Ok(established) => established,
+        Err(error) => OutgoingConnection::Failed {
+            peer_addr,
+            peer_id,
+            error,
+        },
+    }
+}
 
-    // Negotiate the handshake, concluding the incoming connection process.
-    match negotiate_handshake::<P, _>(&context, framed_transport, connection_id).await {
-        Ok(HandshakeOutcome {
-            framed_transport,
-            public_addr,
-            peer_consensus_public_key,
-            is_peer_syncing: is_syncing,
-        }) => {
-            if let Some(ref public_key) = peer_consensus_public_key {
-                Span::current().record("consensus_key", &field::display(public_key));
-            }
+/// Performs the handshake on an outgoing connection and sets up its sink.
+async fn setup_outgoing<P, REv>(
+    context: &NetworkContext<REv>,
+    connection: EncryptedConnection,
+    peer_addr: SocketAddr,
+    peer_id: NodeId,
+) -> Result<OutgoingConnection<P>, ConnectionError>
+where
+    P: Payload,
+{
+    // Setup connection id and framed transport.
+    let connection_id = connection.connection_id(context.our_id(), peer_id)?;
+    let (transport, quic_connection) = connection
+        .into_handshake_transport(Role::Dialer, context.handshake_timeout.into())
+        .await?;
+    let framed = framed_transport(transport, context.chain_info.maximum_net_message_size);
+
+    // Negotiate the handshake, concluding the outgoing connection process.
+    let HandshakeOutcome {
+        framed_transport: framed,
+        public_addr,
+        peer_consensus_public_key,
+        is_peer_syncing: is_syncing,
+        compressor,
+        peer_relays_consensus,
+        peer_accepts_reconciliation,
+    } = negotiate_handshake::<P, _>(context, framed, connection_id).await?;
+
+    if let Some(ref public_key) = peer_consensus_public_key {
+        Span::current().record("consensus_key", &field::display(public_key));
+    }
 
-            if public_addr != peer_addr {
-                // We don't need the `public_addr`, as we already connected, but warn anyway.
-                warn!(%public_addr, %peer_addr, "peer advertises a different public address than what we connected to");
-            }
+    if public_addr != peer_addr {
+        // We don't need the `public_addr`, as we already connected, but warn anyway.
+        warn!(%public_addr, %peer_addr, "peer advertises a different public address than what we connected to");
+    }
 
+    let sink = match quic_connection {
+        None => {
             // Setup full framed transport, then close down receiving end of the transport.
             let full_transport = full_transport::<P>(
                 context.net_metrics.clone(),
                 connection_id,
-                framed_transport,
+                framed,
                 Role::Dialer,
+                compressor,
             );
             let (sink, _stream) = full_transport.split();
-
-            OutgoingConnection::Established {
-                peer_addr,
-                peer_id,
-                peer_consensus_public_key,
-                sink,
-                is_syncing,
-            }
+            OutgoingSink::Shared(sink)
         }
-        Err(error) => OutgoingConnection::Failed {
-            peer_addr,
-            peer_id,
-            error,
-        },
-    }
+        Some(quic_connection) => {
+            // The handshake stream is no longer needed, every lane gets a stream of its own.
+            drop(framed);
+            let streams = io_timeout(
+                context.handshake_timeout.into(),
+                quic::open_lane_streams(&quic_connection),
+            )
+            .await
+            .map_err(ConnectionError::QuicStreams)?;
+            OutgoingSink::PerLane(
+                streams
+                    .into_iter()
+                    .map(|stream| {
+                        Box::pin(full_transport::<P>(
+                            context.net_metrics.clone(),
+                            connection_id,
+                            framed_transport(
+                                Transport::QuicSend(stream),
+                                context.chain_info.maximum_net_message_size,
+                            ),
+                            Role::Dialer,
+                            compressor,
+                        ))
+                    })
+                    .collect(),
+            )
+        }
+    };
+
+    Ok(OutgoingConnection::Established {
+        peer_addr,
+        peer_id,
+        peer_consensus_public_key,
+        sink,
+        is_syncing,
+        relays_consensus: peer_relays_consensus,
+        accepts_reconciliation: peer_accepts_reconciliation,
+    })
 }
 
 /// A context holding all relevant information for networking communication shared across tasks.
@@ -206,14 +381,10 @@ where
     /// The handle to the reactor's event queue, used by incoming message handlers to put events
     /// onto the queue.
     event_queue: Option<EventQueueHandle<REv>>,
-    /// Our own [`NodeId`].
-    our_id: NodeId,
-    /// TLS certificate associated with this node's identity.
-    our_cert: Arc<TlsCert>,
+    /// This node's current identity, which can be rotated while running.
+    identity: RwLock<Identity>,
     /// TLS certificate authority associated with this node's identity.
     network_ca: Option<Arc<X509>>,
-    /// Secret key associated with `our_cert`.
-    secret_key: Arc<PKey<Private>>,
     /// Weak reference to the networking metrics shared by all sender/receiver tasks.
     net_metrics: Weak<Metrics>,
     /// Chain info extract from chainspec.
@@ -236,6 +407,14 @@ where
     max_in_flight_demands: usize,
     /// Flag indicating whether this node is syncing.
     is_syncing: AtomicBool,
+    /// Message compression configuration.
+    compression: CompressionConfig,
+    /// If set, incoming connections are only accepted from these IP addresses.
+    incoming_allowlist: Option<HashSet<IpAddr>>,
+    /// Whether we are a sentry, relaying consensus messages to the validators we protect.
+    relays_consensus: bool,
+    /// The QUIC endpoint, if QUIC is enabled.
+    quic_endpoint: Option<QuicEndpoint>,
 }
 
 impl<REv> NetworkContext<REv> {
@@ -253,20 +432,11 @@ impl<REv> NetworkContext<REv> {
             cfg.max_in_flight_demands as usize
         };
 
-        let Identity {
-            secret_key,
-            tls_certificate,
-            network_ca,
-        } = our_identity;
-        let our_id = NodeId::from(tls_certificate.public_key_fingerprint());
-
         NetworkContext {
-            our_id,
             public_addr: None,
             event_queue: None,
-            our_cert: tls_certificate,
-            network_ca,
-            secret_key,
+            network_ca: our_identity.network_ca.clone(),
+            identity: RwLock::new(our_identity),
             net_metrics: Arc::downgrade(net_metrics),
             chain_info,
             node_key_pair,
@@ -277,6 +447,10 @@ impl<REv> NetworkContext<REv> {
             tarpit_chance: cfg.tarpit_chance,
             max_in_flight_demands,
             is_syncing: AtomicBool::new(false),
+            compression: cfg.compression,
+            incoming_allowlist: None,
+            relays_consensus: false,
+            quic_endpoint: None,
         }
     }
 
@@ -284,14 +458,37 @@ impl<REv> NetworkContext<REv> {
         &mut self,
         our_public_addr: SocketAddr,
         event_queue: EventQueueHandle<REv>,
+        incoming_allowlist: Option<HashSet<IpAddr>>,
+        relays_consensus: bool,
+        quic_endpoint: Option<QuicEndpoint>,
     ) {
         self.public_addr = Some(our_public_addr);
         self.event_queue = Some(event_queue);
+        self.incoming_allowlist = incoming_allowlist;
+        self.relays_consensus = relays_consensus;
+        self.quic_endpoint = quic_endpoint;
     }
 
     /// Our own [`NodeId`].
     pub(super) fn our_id(&self) -> NodeId {
-        self.our_id
+        NodeId::from(&*self.identity.read().expect("lock poisoned"))
+    }
+
+    /// This node's current identity.
+    pub(super) fn identity(&self) -> Identity {
+        self.identity.read().expect("lock poisoned").clone()
+    }
+
+    /// Replaces this node's identity, which is used for all new connections from now on.
+    ///
+    /// Returns our new [`NodeId`].
+    pub(super) fn rotate_identity(&self, identity: Identity) -> Result<NodeId, Error> {
+        if let Some(quic_endpoint) = &self.quic_endpoint {
+            quic_endpoint.set_identity(&identity.tls_certificate, &identity.secret_key)?;
+        }
+        let our_id = NodeId::from(&identity);
+        *self.identity.write().expect("lock poisoned") = identity;
+        Ok(our_id)
     }
 
     /// Our own public listening address.
@@ -322,10 +519,10 @@ impl<REv> NetworkContext<REv> {
 
 /// Handles an incoming connection.
 ///
-/// Sets up a TLS stream and performs the protocol handshake.
+/// Sets up a TLS stream or accepts the QUIC connection, and performs the protocol handshake.
 async fn handle_incoming<P, REv>(
     context: Arc<NetworkContext<REv>>,
-    stream: TcpStream,
+    incoming: IncomingBase,
     peer_addr: SocketAddr,
 ) -> IncomingConnection<P>
 where
@@ -334,7 +531,15 @@ where
     for<'de> P: Serialize + Deserialize<'de>,
     for<'de> Message<P>: Serialize + Deserialize<'de>,
 {
-    let (peer_id, transport) = match server_setup_tls(&context, stream).await {
+    let setup = match incoming {
+        IncomingBase::Tcp(stream) => server_setup_tls(&context, stream)
+            .await
+            .map(|(peer_id, transport)| (peer_id, EncryptedConnection::Tls(transport))),
+        IncomingBase::Quic(connecting) => server_setup_quic(&context, connecting)
+            .await
+            .map(|(peer_id, connection)| (peer_id, EncryptedConnection::Quic(connection))),
+    };
+    let (peer_id, connection) = match setup {
         Ok(value) => value,
         Err(error) => {
             return IncomingConnection::FailedEarly { peer_addr, error };
@@ -344,53 +549,104 @@ where
     // Register the `peer_id` on the [`Span`] for logging the ID from here on out.
     Span::current().record("peer_id", &field::display(peer_id));
 
-    if peer_id == context.our_id {
+    if peer_id == context.our_id() {
         info!("incoming loopback connection");
         return IncomingConnection::Loopback;
     }
 
-    debug!("Incoming TLS connection established");
+    debug!("Incoming encrypted connection established");
+
+    match setup_incoming::<P, _>(&context, connection, peer_addr, peer_id).await {
+        Ok(established) => established,
+        Err(error) => IncomingConnection::Failed {
+            peer_addr,
+            peer_id,
+            error,
+        },
+    }
+}
 
+/// Performs the handshake on an incoming connection and sets up its stream.
+async fn setup_incoming<P, REv>(
+    context: &NetworkContext<REv>,
+    connection: EncryptedConnection,
+    peer_addr: SocketAddr,
+    peer_id: NodeId,
+) -> Result<IncomingConnection<P>, ConnectionError>
+where
+    P: Payload,
+{
     // Setup connection id and framed transport.
-    let connection_id = ConnectionId::from_connection(transport.ssl(), context.our_id, peer_id);
-    let framed_transport = framed_transport(transport, context.chain_info.maximum_net_message_size);
+    let connection_id = connection.connection_id(context.our_id(), peer_id)?;
+    let (transport, quic_connection) = connection
+        .into_handshake_transport(Role::Listener, context.handshake_timeout.into())
+        .await?;
+    let framed = framed_transport(transport, context.chain_info.maximum_net_message_size);
 
     // Negotiate the handshake, concluding the incoming connection process.
-    match negotiate_handshake::<P, _>(&context, framed_transport, connection_id).await {
-        Ok(HandshakeOutcome {
-            framed_transport,
-            public_addr,
-            peer_consensus_public_key,
-            is_peer_syncing: _,
-        }) => {
-            if let Some(ref public_key) = peer_consensus_public_key {
-                Span::current().record("consensus_key", &field::display(public_key));
-            }
+    let HandshakeOutcome {
+        framed_transport: framed,
+        public_addr,
+        peer_consensus_public_key,
+        is_peer_syncing: _,
+        compressor,
+        peer_relays_consensus: _,
+        peer_accepts_reconciliation: _,
+    } = negotiate_handshake::<P, _>(context, framed, connection_id).await?;
+
+    if let Some(ref public_key) = peer_consensus_public_key {
+        Span::current().record("consensus_key", &field::display(public_key));
+    }
 
+    let stream = match quic_connection {
+        None => {
             // Establish full transport and close the receiving end.
             let full_transport = full_transport::<P>(
                 context.net_metrics.clone(),
                 connection_id,
-                framed_transport,
+                framed,
                 Role::Listener,
+                compressor,
             );
-
             let (_sink, stream) = full_transport.split();
-
-            IncomingConnection::Established {
-                peer_addr,
-                public_addr,
-                peer_id,
-                peer_consensus_public_key,
-                stream,
-            }
+            IncomingStream::Shared(stream)
         }
-        Err(error) => IncomingConnection::Failed {
-            peer_addr,
-            peer_id,
-            error,
-        },
-    }
+        Some(quic_connection) => {
+            // The peer sends the messages of each lane on a stream of its own.
+            drop(framed);
+            let streams = io_timeout(
+                context.handshake_timeout.into(),
+                quic::accept_lane_streams(&quic_connection),
+            )
+            .await
+            .map_err(ConnectionError::QuicStreams)?;
+            IncomingStream::PerLane(
+                streams
+                    .into_iter()
+                    .map(|stream| {
+                        Box::pin(full_transport::<P>(
+                            context.net_metrics.clone(),
+                            connection_id,
+                            framed_transport(
+                                Transport::QuicRecv(stream),
+                                context.chain_info.maximum_net_message_size,
+                            ),
+                            Role::Listener,
+                            compressor,
+                        ))
+                    })
+                    .collect(),
+            )
+        }
+    };
+
+    Ok(IncomingConnection::Established {
+        peer_addr,
+        public_addr,
+        peer_id,
+        peer_consensus_public_key,
+        stream,
+    })
 }
 
 /// Server-side TLS setup.
@@ -399,10 +655,11 @@ where
 pub(super) async fn server_setup_tls<REv>(
     context: &NetworkContext<REv>,
     stream: TcpStream,
-) -> Result<(NodeId, Transport), ConnectionError> {
+) -> Result<(NodeId, SslStream<TcpStream>), ConnectionError> {
+    let identity = context.identity();
     let mut tls_stream = tls::create_tls_acceptor(
-        context.our_cert.as_x509().as_ref(),
-        context.secret_key.as_ref(),
+        identity.tls_certificate.as_x509().as_ref(),
+        identity.secret_key.as_ref(),
     )
     .and_then(|ssl_acceptor| Ssl::new(ssl_acceptor.context()))
     .and_then(|ssl| SslStream::new(ssl, stream))
@@ -428,6 +685,24 @@ pub(super) async fn server_setup_tls<REv>(
     ))
 }
 
+/// Server-side QUIC setup.
+///
+/// Completes an incoming QUIC connection, whose peer certificate was already validated during the
+/// TLS handshake.
+async fn server_setup_quic<REv>(
+    context: &NetworkContext<REv>,
+    connecting: quinn::Connecting,
+) -> Result<(NodeId, quinn::Connection), ConnectionError> {
+    let connection = io_timeout(context.handshake_timeout.into(), connecting)
+        .await
+        .map_err(|err| match err {
+            IoError::Error(err) => ConnectionError::QuicConnection(err),
+            IoError::Timeout | IoError::UnexpectedEof => ConnectionError::QuicTimeout,
+        })?;
+
+    Ok((quic_peer_id(context, &connection)?, connection))
+}
+
 /// Performs an IO-operation that can time out.
 async fn io_timeout<F, T, E>(duration: Duration, future: F) -> Result<T, IoError<E>>
 where
@@ -468,12 +743,19 @@ where
 {
     let mut encoder = MessagePackFormat;
 
+    // Prove that we hold our consensus key, if we have one.
+    let consensus_certificate = match context.node_key_pair.as_ref() {
+        Some(key_pair) => ConsensusCertificate::create(connection_id, key_pair).await,
+        None => None,
+    };
+
     // Manually encode a handshake.
     let handshake_message = context.chain_info.create_handshake::<P>(
         context.public_addr.expect("component not initialized"),
-        context.node_key_pair.as_ref(),
-        connection_id,
+        consensus_certificate,
         context.is_syncing.load(Ordering::SeqCst),
+        context.compression.enabled,
+        context.relays_consensus,
     );
 
     let serialized_handshake_message = Pin::new(&mut encoder)
@@ -513,6 +795,9 @@ where
         consensus_certificate,
         is_syncing,
         chainspec_hash,
+        accepts_compression,
+        relays_consensus,
+        accepts_reconciliation,
     } = remote_message
     {
         debug!(%protocol_version, "handshake received");
@@ -566,11 +851,20 @@ where
             .reunite(stream)
             .map_err(|_| ConnectionError::FailedToReuniteHandshakeSinkAndStream)?;
 
+        let compressor = Compressor::negotiate(
+            &context.compression,
+            accepts_compression,
+            context.chain_info.maximum_net_message_size,
+        );
+
         Ok(HandshakeOutcome {
             framed_transport,
             public_addr,
             peer_consensus_public_key,
             is_peer_syncing: is_syncing,
+            compressor,
+            peer_relays_consensus: relays_consensus,
+            peer_accepts_reconciliation: accepts_reconciliation,
         })
     } else {
         // Received a non-handshake, this is an error.
@@ -579,6 +873,8 @@ where
 }
 
 /// Runs the server core acceptor loop.
+///
+/// Accepts TCP connections on the given listener, and QUIC connections if QUIC is enabled.
 pub(super) async fn server<P, REv>(
     context: Arc<NetworkContext<REv>>,
     listener: tokio::net::TcpListener,
@@ -593,35 +889,13 @@ pub(super) async fn server<P, REv>(
 
     // We first create a future that never terminates, handling incoming connections:
     let accept_connections = async {
-        let event_queue = context.event_queue.expect("component not initialized");
         loop {
             // We handle accept errors here, since they can be caused by a temporary resource
             // shortage or the remote side closing the connection while it is waiting in
             // the queue.
             match listener.accept().await {
                 Ok((stream, peer_addr)) => {
-                    // The span setup here is used throughout the entire lifetime of the connection.
-                    let span =
-                        error_span!("incoming", %peer_addr, peer_id=Empty, consensus_key=Empty);
-
-                    let context = context.clone();
-                    let handler_span = span.clone();
-                    tokio::spawn(
-                        async move {
-                            let incoming =
-                                handle_incoming(context.clone(), stream, peer_addr).await;
-                            event_queue
-                                .schedule(
-                                    Event::IncomingConnection {
-                                        incoming: Box::new(incoming),
-                                        span,
-                                    },
-                                    QueueKind::NetworkIncoming,
-                                )
-                                .await;
-                        }
-                        .instrument(handler_span),
-                    );
+                    spawn_incoming::<P, _>(&context, IncomingBase::Tcp(stream), peer_addr);
                 }
 
                 // TODO: Handle resource errors gracefully.
@@ -632,23 +906,91 @@ pub(super) async fn server<P, REv>(
                 //       The code in its current state will consume 100% CPU if local resource
                 //       exhaustion happens, as no distinction is made and no delay introduced.
                 Err(ref err) => {
-                    warn!(%context.our_id, err=display_error(err), "dropping incoming connection during accept")
+                    warn!(our_id=%context.our_id(), err=display_error(err), "dropping incoming connection during accept")
                 }
             }
         }
     };
 
+    // QUIC connections are accepted alongside, until the endpoint is closed.
+    let accept_quic_connections = async {
+        match context.quic_endpoint {
+            Some(ref quic_endpoint) => {
+                while let Some(connecting) = quic_endpoint.accept().await {
+                    let peer_addr = connecting.remote_address();
+                    spawn_incoming::<P, _>(&context, IncomingBase::Quic(connecting), peer_addr);
+                }
+            }
+            None => future::pending().await,
+        }
+    };
+
     let shutdown_messages = async move { while shutdown_receiver.changed().await.is_ok() {} };
 
     // Now we can wait for either the `shutdown` channel's remote end to do be dropped or the
-    // infinite loop to terminate, which never happens.
-    match future::select(Box::pin(shutdown_messages), Box::pin(accept_connections)).await {
+    // infinite loops to terminate, which never happens unless the QUIC endpoint fails.
+    match future::select(
+        Box::pin(shutdown_messages),
+        future::select(
+            Box::pin(accept_connections),
+            Box::pin(accept_quic_connections),
+        ),
+    )
+    .await
+    {
         Either::Left(_) => info!(
-            %context.our_id,
+            our_id=%context.our_id(),
             "shutting down socket, no longer accepting incoming connections"
         ),
-        Either::Right(_) => unreachable!(),
+        Either::Right((Either::Left(_), _)) => unreachable!(),
+        Either::Right((Either::Right(_), _)) => {
+            warn!(our_id=%context.our_id(), "QUIC endpoint closed, no longer accepting incoming connections")
+        }
     }
+
+    if let Some(ref quic_endpoint) = context.quic_endpoint {
+        quic_endpoint.close();
+    }
+}
+
+/// Spawns a task handling an incoming connection, unless it is not allowed.
+fn spawn_incoming<P, REv>(
+    context: &Arc<NetworkContext<REv>>,
+    incoming: IncomingBase,
+    peer_addr: SocketAddr,
+) where
+    REv: From<Event<P>> + Send,
+    P: Payload,
+{
+    if let Some(ref allowlist) = context.incoming_allowlist {
+        if !allowlist.contains(&peer_addr.ip()) {
+            debug!(%peer_addr, "refusing incoming connection from non-sentry");
+            return;
+        }
+    }
+
+    let event_queue = context.event_queue.expect("component not initialized");
+
+    // The span setup here is used throughout the entire lifetime of the connection.
+    let span = error_span!("incoming", %peer_addr, peer_id=Empty, consensus_key=Empty);
+
+    let context = context.clone();
+    let handler_span = span.clone();
+    tokio::spawn(
+        async move {
+            let incoming = handle_incoming(context.clone(), incoming, peer_addr).await;
+            event_queue
+                .schedule(
+                    Event::IncomingConnection {
+                        incoming: Box::new(incoming),
+                        span,
+                    },
+                    QueueKind::NetworkIncoming,
+                )
+                .await;
+        }
+        .instrument(handler_span),
+    );
 }
 
 /// Network message reader.
@@ -656,7 +998,7 @@ pub(super) async fn server<P, REv>(
 /// Schedules all received messages until the stream is closed or an error occurs.
 pub(super) async fn message_reader<REv, P>(
     context: Arc<NetworkContext<REv>>,
-    mut stream: SplitStream<FullTransport<P>>,
+    mut stream: IncomingStream<P>,
     limiter: LimiterHandle,
     mut close_incoming_receiver: watch::Receiver<()>,
     peer_id: NodeId,
@@ -794,27 +1136,84 @@ where
 /// Network message sender.
 ///
 /// Reads from a channel and sends all messages, until the stream is closed or an error occurs.
+/// Queued messages are sorted into prioritized lanes. On a shared sink, the lanes are served
+/// according to their weights and byte budgets. On QUIC connections, each lane is sent on a stream
+/// of its own by a separate writer, fed by a queue of its own.
 pub(super) async fn message_sender<P>(
+    queue: UnboundedReceiver<MessageQueueItem<P>>,
+    sink: OutgoingSink<P>,
+    limiter: LimiterHandle,
+    lanes: OutgoingLanes<(MessageQueueItem<P>, u32)>,
+    lane_limiters: LaneLimiterHandles,
+    counter: IntGauge,
+) where
+    P: Payload,
+{
+    match sink {
+        OutgoingSink::Shared(sink) => {
+            shared_message_sender(queue, sink, limiter, lanes, lane_limiters, counter).await
+        }
+        OutgoingSink::PerLane(sinks) => {
+            per_lane_message_sender(queue, sinks, limiter, lanes, lane_limiters, counter).await
+        }
+    }
+}
+
+/// Sends all messages on a single sink, serving the lanes according to their weights and budgets.
+async fn shared_message_sender<P>(
     mut queue: UnboundedReceiver<MessageQueueItem<P>>,
     mut sink: SplitSink<FullTransport<P>, Arc<Message<P>>>,
     limiter: LimiterHandle,
+    mut lanes: OutgoingLanes<(MessageQueueItem<P>, u32)>,
+    lane_limiters: LaneLimiterHandles,
     counter: IntGauge,
 ) where
     P: Payload,
 {
-    while let Some((message, opt_responder)) = queue.recv().await {
+    let mut queue_open = true;
+
+    'sending: loop {
+        // Move all queued messages into their lanes, waiting for one if there are none.
+        if lanes.is_empty() {
+            match queue.recv().await {
+                Some(item) => {
+                    if !enqueue(&mut lanes, item, &counter) {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        while let Ok(item) = queue.try_recv() {
+            if !enqueue(&mut lanes, item, &counter) {
+                break 'sending;
+            }
+        }
+
+        let ((message, opt_responder), estimated_wire_size) =
+            match lanes.pop(|lane, size| lane_limiters.try_request_allowance(lane, size)) {
+                Some(Scheduled::Item(item)) => item,
+                Some(Scheduled::Throttled(delay)) => {
+                    // Wait for the budgets to refill, unless a new message arrives in the meantime.
+                    if queue_open {
+                        match tokio::time::timeout(delay, queue.recv()).await {
+                            Ok(Some(item)) => {
+                                if !enqueue(&mut lanes, item, &counter) {
+                                    break;
+                                }
+                            }
+                            Ok(None) => queue_open = false,
+                            Err(_elapsed) => {}
+                        }
+                    } else {
+                        tokio::time::sleep(delay).await;
+                    }
+                    continue;
+                }
+                None => continue,
+            };
         counter.dec();
 
-        let estimated_wire_size = match BincodeFormat::default().0.serialized_size(&*message) {
-            Ok(size) => size as u32,
-            Err(error) => {
-                error!(
-                    error = display_error(&error),
-                    "failed to get serialized size of outgoing message, closing outgoing connection"
-                );
-                break;
-            }
-        };
         limiter.request_allowance(estimated_wire_size).await;
 
         let mut outcome = sink.send(message).await;
@@ -832,14 +1231,203 @@ pub(super) async fn message_sender<P>(
                 err = display_error(err),
                 "message send failed, closing outgoing connection"
             );
+            break;
+        };
+    }
 
-            // To ensure, metrics are up to date, we close the queue and drain it.
-            queue.close();
-            while queue.recv().await.is_some() {
-                counter.dec();
-            }
+    // To ensure, metrics are up to date, we close the queue and drain it.
+    counter.sub(lanes.len() as i64);
+    queue.close();
+    while queue.recv().await.is_some() {
+        counter.dec();
+    }
+}
 
-            break;
+/// Sends the messages of each lane on its own sink, using a separate writer per lane.
+///
+/// The connection is closed as soon as any of the writers fails.
+async fn per_lane_message_sender<P>(
+    mut queue: UnboundedReceiver<MessageQueueItem<P>>,
+    mut sinks: Vec<Pin<Box<FullTransport<P>>>>,
+    limiter: LimiterHandle,
+    lanes: OutgoingLanes<(MessageQueueItem<P>, u32)>,
+    lane_limiters: LaneLimiterHandles,
+    counter: IntGauge,
+) where
+    P: Payload,
+{
+    let (lane_queues, mut lane_receivers): (Vec<_>, Vec<_>) =
+        Lane::ALL.iter().map(|_| mpsc::unbounded_channel()).unzip();
+
+    {
+        let mut writers: FuturesUnordered<_> = Lane::ALL
+            .iter()
+            .zip(lane_receivers.iter_mut())
+            .zip(sinks.iter_mut())
+            .map(|((&lane, lane_queue), sink)| {
+                lane_writer(
+                    lane,
+                    lane_queue,
+                    sink,
+                    &limiter,
+                    &lane_limiters,
+                    lanes.metrics(lane),
+                    &counter,
+                )
+            })
+            .collect();
+
+        // Sorts queued messages into the queues of the writers, until the queue is closed. Returns
+        // `false` if the connection should be closed right away.
+        let dispatch = async {
+            while let Some(item) = queue.recv().await {
+                let estimated_wire_size = match estimate_wire_size(&item.0) {
+                    Some(size) => size,
+                    None => {
+                        counter.dec();
+                        return false;
+                    }
+                };
+                let lane = Lane::of(item.0.classify());
+                lanes.metrics(lane).record_queued(estimated_wire_size);
+                if let Err(SendError((_, size))) =
+                    lane_queues[lane.index()].send((item, estimated_wire_size))
+                {
+                    // The writer of the lane has already stopped.
+                    counter.dec();
+                    lanes.metrics(lane).record_dropped(size);
+                }
+            }
+            // Dropping the lane queues lets the writers finish once they have sent everything.
+            drop(lane_queues);
+            true
         };
+        let mut dispatch = Box::pin(dispatch);
+        let mut dispatching = true;
+
+        loop {
+            let written = if dispatching {
+                match future::select(&mut dispatch, writers.next()).await {
+                    Either::Left((true, _)) => {
+                        dispatching = false;
+                        continue;
+                    }
+                    Either::Left((false, _)) => break,
+                    Either::Right((written, _)) => written,
+                }
+            } else {
+                writers.next().await
+            };
+
+            match written {
+                Some(Ok(())) => {}
+                Some(Err(err)) => {
+                    // We simply error-out if a sink fails, it means that our connection broke.
+                    info!(
+                        err = display_error(&err),
+                        "message send failed, closing outgoing connection"
+                    );
+                    break;
+                }
+                None => break,
+            }
+        }
+    }
+
+    // To ensure, metrics are up to date, we close the queues and drain them.
+    for (lane, lane_receiver) in Lane::ALL.iter().zip(lane_receivers.iter_mut()) {
+        lane_receiver.close();
+        while let Ok((_, size)) = lane_receiver.try_recv() {
+            counter.dec();
+            lanes.metrics(*lane).record_dropped(size);
+        }
+    }
+    queue.close();
+    while queue.recv().await.is_some() {
+        counter.dec();
+    }
+}
+
+/// Sends all messages of a single lane on its own sink, until its queue is closed or an error
+/// occurs.
+async fn lane_writer<P>(
+    lane: Lane,
+    queue: &mut UnboundedReceiver<(MessageQueueItem<P>, u32)>,
+    sink: &mut Pin<Box<FullTransport<P>>>,
+    limiter: &LimiterHandle,
+    lane_limiters: &LaneLimiterHandles,
+    metrics: &LaneMetrics,
+    counter: &IntGauge,
+) -> io::Result<()>
+where
+    P: Payload,
+{
+    while let Some(((message, opt_responder), estimated_wire_size)) = queue.recv().await {
+        counter.dec();
+        metrics.record_sent(estimated_wire_size);
+
+        lane_limiters
+            .request_allowance(lane, estimated_wire_size)
+            .await;
+        limiter.request_allowance(estimated_wire_size).await;
+
+        let mut outcome = sink.send(message).await;
+
+        // Notify via responder that the message has been buffered by the kernel.
+        if let Some(auto_closing_responder) = opt_responder {
+            // Since someone is interested in the message, flush the stream to ensure it was sent.
+            outcome = outcome.and(sink.flush().await);
+            auto_closing_responder.respond(()).await;
+        }
+
+        outcome?;
+    }
+    Ok(())
+}
+
+/// Sorts a queued message into its lane, along with its estimated wire size.
+///
+/// Returns `false` if the size of the message could not be determined, in which case the message is
+/// dropped and the connection should be closed.
+fn enqueue<P>(
+    lanes: &mut OutgoingLanes<(MessageQueueItem<P>, u32)>,
+    (message, opt_responder): MessageQueueItem<P>,
+    counter: &IntGauge,
+) -> bool
+where
+    P: Payload,
+{
+    let estimated_wire_size = match estimate_wire_size(&message) {
+        Some(size) => size,
+        None => {
+            counter.dec();
+            return false;
+        }
+    };
+    let lane = Lane::of(message.classify());
+    lanes.push(
+        lane,
+        estimated_wire_size,
+        ((message, opt_responder), estimated_wire_size),
+    );
+    true
+}
+
+/// Estimates the wire size of an outgoing message.
+///
+/// Returns `None` if the size could not be determined, after logging the error.
+fn estimate_wire_size<P>(message: &Message<P>) -> Option<u32>
+where
+    P: Payload,
+{
+    match BincodeFormat::default().0.serialized_size(message) {
+        Ok(size) => Some(size as u32),
+        Err(error) => {
+            error!(
+                error = display_error(&error),
+                "failed to get serialized size of outgoing message, closing outgoing connection"
+            );
+            None
+        }
     }
 }
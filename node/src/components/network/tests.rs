@@ -4,28 +4,34 @@
 //! instances of `net` arranged in a network.
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::{self, Debug, Display, Formatter},
-    sync::Arc,
+    net::SocketAddr,
+    sync::{Arc, Weak},
     time::{Duration, Instant},
 };
 
 use derive_more::From;
 use futures::FutureExt;
+use num_rational::Ratio;
 use prometheus::Registry;
 use reactor::ReactorEvent;
 use serde::{Deserialize, Serialize};
 use smallvec::smallvec;
 use tracing::{debug, info};
 
-use casper_types::SecretKey;
+use casper_types::{EraId, PublicKey, SecretKey, U512};
 
 use super::{
-    chain_info::ChainInfo, Config, Event as NetworkEvent, FromIncoming, GossipedAddress, Identity,
-    MessageKind, Network, Payload,
+    capture::{read_capture, Capture, CaptureConfig, CaptureDirection},
+    chain_info::ChainInfo,
+    quic::QuicConfig,
+    Config, Event as NetworkEvent, FromIncoming, GossipedAddress, Identity, MessageKind, Network,
+    Payload, SentryConfig,
 };
 use crate::{
     components::{
+        consensus::{LocalSigner, Signer},
         gossiper::{self, GossipItem, Gossiper},
         Component, InitializedComponent,
     },
@@ -36,16 +42,19 @@ use crate::{
             BeginGossipRequest, ChainspecRawBytesRequest, ContractRuntimeRequest, NetworkRequest,
             StorageRequest,
         },
-        EffectBuilder, Effects,
+        EffectBuilder, EffectExt, Effects,
     },
     protocol,
     reactor::{self, EventQueueHandle, Finalize, Reactor, Runner},
     testing::{
-        self, init_logging,
+        self,
+        capture_replay::replay_capture,
+        init_logging,
         network::{NetworkedReactor, Nodes, TestingNetwork},
         ConditionCheckReactor,
     },
-    types::{Chainspec, ChainspecRawBytes, NodeId, ValidatorMatrix},
+    types::{Chainspec, ChainspecRawBytes, EraValidatorWeights, NodeId, ValidatorMatrix},
+    utils::Loadable,
     NodeRng,
 };
 
@@ -66,6 +75,8 @@ enum Event {
     BeginAddressGossipRequest(BeginGossipRequest<GossipedAddress>),
     /// An incoming network message with an address gossiper protocol message.
     AddressGossiperIncoming(GossiperIncoming<GossipedAddress>),
+    /// An incoming consensus message, identified by a nonce.
+    ConsensusIncoming { sender: NodeId, nonce: u64 },
     #[from]
     BlocklistAnnouncement(PeerBehaviorAnnouncement),
 }
@@ -129,6 +140,7 @@ impl FromIncoming<Message> for Event {
                 sender,
                 message: Box::new(message),
             }),
+            Message::Consensus(nonce) => Event::ConsensusIncoming { sender, nonce },
         }
     }
 }
@@ -143,6 +155,8 @@ impl Display for Event {
 enum Message {
     #[from]
     AddressGossiper(gossiper::Message<GossipedAddress>),
+    /// A stand-in for a consensus message, identified by a nonce.
+    Consensus(u64),
 }
 
 impl Display for Message {
@@ -156,6 +170,7 @@ impl Payload for Message {
     fn message_kind(&self) -> MessageKind {
         match self {
             Message::AddressGossiper(_) => MessageKind::AddressGossip,
+            Message::Consensus(_) => MessageKind::Consensus,
         }
     }
 
@@ -175,6 +190,10 @@ impl Payload for Message {
 struct TestReactor {
     net: Network<Event, Message>,
     address_gossiper: Gossiper<{ GossipedAddress::ID_IS_COMPLETE_ITEM }, GossipedAddress>,
+    /// The validator sets known to the node, with only the node itself by default.
+    validator_matrix: ValidatorMatrix,
+    /// The nonces of the consensus messages received.
+    consensus_messages: HashSet<u64>,
 }
 
 impl Reactor for TestReactor {
@@ -191,14 +210,17 @@ impl Reactor for TestReactor {
         _event_queue: EventQueueHandle<Self::Event>,
         rng: &mut NodeRng,
     ) -> anyhow::Result<(Self, Effects<Self::Event>)> {
-        let secret_key = SecretKey::random(rng);
+        let secret_key = Arc::new(SecretKey::random(rng));
+        let signer: Arc<dyn Signer> = Arc::new(LocalSigner::new(Arc::clone(&secret_key)));
+        let validator_matrix = ValidatorMatrix::new_with_validator(secret_key);
         let mut net = Network::new(
             cfg,
             our_identity,
-            None,
+            Some(signer),
             registry,
             ChainInfo::create_for_testing(),
-            ValidatorMatrix::new_with_validator(Arc::new(secret_key)),
+            validator_matrix.clone(),
+            None,
         )?;
         let gossiper_config = gossiper::Config::new_with_small_timeouts();
         let address_gossiper = Gossiper::<{ GossipedAddress::ID_IS_COMPLETE_ITEM }, _>::new(
@@ -214,6 +236,8 @@ impl Reactor for TestReactor {
             TestReactor {
                 net,
                 address_gossiper,
+                validator_matrix,
+                consensus_messages: HashSet::new(),
             },
             effects,
         ))
@@ -274,6 +298,11 @@ impl Reactor for TestReactor {
                 self.address_gossiper
                     .handle_event(effect_builder, rng, incoming.into()),
             ),
+            Event::ConsensusIncoming { sender, nonce } => {
+                debug!(%sender, nonce, "received consensus message");
+                self.consensus_messages.insert(nonce);
+                Effects::new()
+            }
             Event::BlocklistAnnouncement(_announcement) => Effects::new(),
         }
     }
@@ -535,3 +564,227 @@ async fn ensure_peers_metric_is_correct() {
         net.finalize().await;
     }
 }
+
+/// Checks that captured incoming payloads are replayed into a reactor, skipping outgoing ones.
+#[tokio::test]
+async fn replay_captured_traffic() {
+    init_logging();
+
+    let mut rng = crate::new_rng();
+    let capture_dir = tempfile::tempdir().unwrap();
+    let sender = NodeId::random(&mut rng);
+    let gossiped_address = GossipedAddress::new(SocketAddr::from(([127, 0, 0, 2], 34553)));
+    let payload = Message::AddressGossiper(gossiper::Message::Gossip(gossiped_address));
+
+    let capture =
+        Capture::start(&CaptureConfig::default(), capture_dir.path(), Weak::new()).unwrap();
+    capture.record(
+        CaptureDirection::Outgoing,
+        sender,
+        &super::Message::Payload(payload.clone()),
+    );
+    capture.record(
+        CaptureDirection::Incoming,
+        sender,
+        &super::Message::Payload(payload),
+    );
+    drop(capture);
+
+    let records = read_capture::<Message>(capture_dir.path()).unwrap();
+    assert_eq!(records.len(), 2);
+
+    let (chainspec, chainspec_raw_bytes) =
+        <(Chainspec, ChainspecRawBytes)>::from_resources("local");
+    let mut runner: Runner<TestReactor> = Runner::new(
+        Config::default_local_net_first_node(testing::unused_port_on_localhost()),
+        Arc::new(chainspec),
+        Arc::new(chainspec_raw_bytes),
+        &mut rng,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(replay_capture(&mut runner, &mut rng, records).await, 1);
+
+    runner.drain_into_inner().await.finalize().await;
+}
+
+/// Check that nodes connect over QUIC, and fall back to TCP for peers without QUIC.
+#[tokio::test]
+async fn connect_over_quic_with_tcp_fallback() {
+    init_logging();
+
+    let mut rng = crate::new_rng();
+
+    let first_node_port = testing::unused_port_on_localhost() + 1;
+    let with_quic = |mut config: Config| {
+        config.quic = QuicConfig {
+            enabled: true,
+            ..QuicConfig::default()
+        };
+        config
+    };
+
+    let mut net = TestingNetwork::new();
+    net.add_node_with_config(
+        with_quic(Config::default_local_net_first_node(first_node_port)),
+        &mut rng,
+    )
+    .await
+    .unwrap();
+    net.add_node_with_config(
+        with_quic(Config::default_local_net(first_node_port)),
+        &mut rng,
+    )
+    .await
+    .unwrap();
+    // The last node only speaks TCP.
+    net.add_node_with_config(Config::default_local_net(first_node_port), &mut rng)
+        .await
+        .unwrap();
+
+    let timeout = Duration::from_secs(20);
+    let blocklist = HashSet::new();
+    net.settle_on(
+        &mut rng,
+        |nodes| network_is_complete(&blocklist, nodes),
+        timeout,
+    )
+    .await;
+
+    net.finalize().await;
+}
+
+/// Checks that a validator protected by a sentry exchanges consensus messages with another
+/// validator through its sentry, in both directions.
+#[tokio::test]
+async fn protected_validator_exchanges_consensus_messages_through_sentry() {
+    init_logging();
+
+    let mut rng = crate::new_rng();
+
+    let validator_port = testing::unused_port_on_localhost();
+    let sentry_port = testing::unused_port_on_localhost();
+    let protected_port = testing::unused_port_on_localhost();
+    let local_addr = |port: u16| SocketAddr::from(([127, 0, 0, 1], port)).to_string();
+
+    let mut net = TestingNetwork::new();
+    let sentry_config = Config {
+        bind_address: local_addr(sentry_port),
+        public_address: local_addr(sentry_port),
+        sentry: SentryConfig {
+            protected_addresses: vec![local_addr(protected_port)],
+            ..SentryConfig::default()
+        },
+        ..Config::default_local_net(validator_port)
+    };
+    let (sentry_id, _) = net
+        .add_node_with_config(sentry_config, &mut rng)
+        .await
+        .unwrap();
+    // The validator only honors the sentry's announcement as it trusts it.
+    let validator_config = Config {
+        sentry: SentryConfig {
+            trusted_sentries: vec![base16::encode_lower(sentry_id.hash_bytes())],
+            ..SentryConfig::default()
+        },
+        ..Config::default_local_net_first_node(validator_port)
+    };
+    let (validator_id, _) = net
+        .add_node_with_config(validator_config, &mut rng)
+        .await
+        .unwrap();
+    let protected_config = Config {
+        sentry: SentryConfig {
+            sentry_addresses: vec![local_addr(sentry_port)],
+            ..SentryConfig::default()
+        },
+        ..Config::default_local_net_first_node(protected_port)
+    };
+    let (protected_id, _) = net
+        .add_node_with_config(protected_config, &mut rng)
+        .await
+        .unwrap();
+
+    // The two validators are in every node's validator set, the sentry is not a validator.
+    let public_key = |node_id: &NodeId| -> PublicKey {
+        net.nodes()[node_id]
+            .reactor()
+            .inner()
+            .validator_matrix
+            .public_signing_key()
+            .clone()
+    };
+    let validators: BTreeMap<PublicKey, U512> = vec![
+        (public_key(&validator_id), U512::from(100)),
+        (public_key(&protected_id), U512::from(100)),
+    ]
+    .into_iter()
+    .collect();
+    for runner in net.nodes_mut().values_mut() {
+        runner
+            .reactor_mut()
+            .inner_mut()
+            .validator_matrix
+            .register_era_validator_weights(EraValidatorWeights::new(
+                EraId::new(0),
+                validators.clone(),
+                Ratio::new(1, 3),
+            ));
+    }
+
+    let timeout = Duration::from_secs(20);
+    net.settle_on(
+        &mut rng,
+        |nodes| {
+            let is_connected = |from: &NodeId, to: &NodeId| {
+                nodes[from].reactor().inner().net.peers().contains_key(to)
+            };
+            // The validator only sends consensus messages to the sentry once it knows it is one.
+            nodes[&validator_id]
+                .reactor()
+                .inner()
+                .net
+                .consensus_relays
+                .contains(&sentry_id)
+                && is_connected(&sentry_id, &validator_id)
+                && is_connected(&sentry_id, &protected_id)
+                && is_connected(&protected_id, &sentry_id)
+        },
+        timeout,
+    )
+    .await;
+    // The protected validator is only reachable through its sentry.
+    assert!(!net.nodes()[&validator_id]
+        .reactor()
+        .inner()
+        .net
+        .peers()
+        .contains_key(&protected_id));
+
+    for (sender, receiver, nonce) in [
+        (validator_id, protected_id, 1),
+        (protected_id, validator_id, 2),
+    ] {
+        net.process_injected_effect_on(&sender, |effect_builder| {
+            effect_builder
+                .broadcast_message_to_validators(Message::Consensus(nonce), EraId::new(0))
+                .ignore()
+        })
+        .await;
+        net.settle_on(
+            &mut rng,
+            |nodes| {
+                nodes[&receiver]
+                    .reactor()
+                    .inner()
+                    .consensus_messages
+                    .contains(&nonce)
+            },
+            timeout,
+        )
+        .await;
+    }
+
+    net.finalize().await;
+}
@@ -0,0 +1,147 @@
+//! Transports of connections.
+//!
+//! A connection is either a TLS connection over TCP, carrying all messages on a single stream, or a
+//! QUIC connection, carrying the messages of each lane on a separate stream.
+
+use std::{
+    fmt::{self, Debug, Formatter},
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures::{
+    stream::{SelectAll, SplitSink, SplitStream},
+    Stream, StreamExt,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::TcpStream,
+};
+use tokio_openssl::SslStream;
+
+use super::{FullTransport, Message, Payload};
+
+/// A base encrypted connection, or a single stream of one.
+pub(crate) enum Transport {
+    /// A TLS connection over TCP.
+    Tls(SslStream<TcpStream>),
+    /// A bidirectional QUIC stream.
+    QuicBidirectional(quinn::SendStream, quinn::RecvStream),
+    /// The sending end of a unidirectional QUIC stream.
+    QuicSend(quinn::SendStream),
+    /// The receiving end of a unidirectional QUIC stream.
+    QuicRecv(quinn::RecvStream),
+}
+
+impl AsyncRead for Transport {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Transport::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+            Transport::QuicBidirectional(_, recv) | Transport::QuicRecv(recv) => {
+                Pin::new(recv).poll_read(cx, buf)
+            }
+            // There is nothing to read from a sending stream, so it is always at its end.
+            Transport::QuicSend(_) => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+impl AsyncWrite for Transport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Transport::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+            Transport::QuicBidirectional(send, _) | Transport::QuicSend(send) => {
+                Pin::new(send).poll_write(cx, buf)
+            }
+            Transport::QuicRecv(_) => Poll::Ready(Err(not_writable())),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Transport::Tls(stream) => Pin::new(stream).poll_flush(cx),
+            Transport::QuicBidirectional(send, _) | Transport::QuicSend(send) => {
+                Pin::new(send).poll_flush(cx)
+            }
+            Transport::QuicRecv(_) => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Transport::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+            Transport::QuicBidirectional(send, _) | Transport::QuicSend(send) => {
+                Pin::new(send).poll_shutdown(cx)
+            }
+            Transport::QuicRecv(_) => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+/// Returns the error for writing to a receiving stream.
+fn not_writable() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "cannot write to receiving QUIC stream",
+    )
+}
+
+/// The sending end of an outgoing connection.
+pub(crate) enum OutgoingSink<P> {
+    /// A single sink carrying the messages of all lanes.
+    Shared(SplitSink<FullTransport<P>, Arc<Message<P>>>),
+    /// A separate sink for each lane, in the order of [`Lane::ALL`].
+    PerLane(Vec<Pin<Box<FullTransport<P>>>>),
+}
+
+impl<P> Debug for OutgoingSink<P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            OutgoingSink::Shared(_) => f.write_str("OutgoingSink::Shared"),
+            OutgoingSink::PerLane(sinks) => write!(f, "OutgoingSink::PerLane({})", sinks.len()),
+        }
+    }
+}
+
+/// The receiving end of an incoming connection.
+pub(crate) enum IncomingStream<P> {
+    /// A single stream carrying the messages of all lanes.
+    Shared(SplitStream<FullTransport<P>>),
+    /// A separate stream for each lane, read concurrently.
+    PerLane(SelectAll<Pin<Box<FullTransport<P>>>>),
+}
+
+impl<P> Stream for IncomingStream<P>
+where
+    P: Payload,
+{
+    type Item = io::Result<Message<P>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.get_mut() {
+            IncomingStream::Shared(stream) => stream.poll_next_unpin(cx),
+            IncomingStream::PerLane(streams) => streams.poll_next_unpin(cx),
+        }
+    }
+}
+
+impl<P> Debug for IncomingStream<P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            IncomingStream::Shared(_) => f.write_str("IncomingStream::Shared"),
+            IncomingStream::PerLane(streams) => {
+                write!(f, "IncomingStream::PerLane({})", streams.len())
+            }
+        }
+    }
+}
@@ -46,7 +46,7 @@ use crate::{
         EffectBuilder, EffectExt, Effects, Responder,
     },
     reactor::main_reactor::MainEvent,
-    types::{ChainspecInfo, StatusFeed},
+    types::{ChainspecInfo, PeersMap, StatusFeed},
     utils::{self, ListeningError},
     NodeRng,
 };
@@ -311,12 +311,17 @@ where
                         }),
                         main_responder: responder,
                     }),
-                Event::RpcRequest(RpcRequest::GetPeers { responder }) => effect_builder
-                    .network_peers()
-                    .event(move |peers| Event::GetPeersResult {
-                        peers,
-                        main_responder: responder,
-                    }),
+                Event::RpcRequest(RpcRequest::GetPeers { responder }) => async move {
+                    join!(
+                        effect_builder.network_peers(),
+                        effect_builder.network_peer_scores()
+                    )
+                }
+                .event(move |(peers, scores)| Event::GetPeersResult {
+                    peers,
+                    scores,
+                    main_responder: responder,
+                }),
                 Event::RpcRequest(RpcRequest::GetStatus { responder }) => {
                     let node_uptime = self.node_startup_instant.elapsed();
                     let network_name = self.network_name.clone();
@@ -396,8 +401,11 @@ where
                 } => main_responder.respond(result).ignore(),
                 Event::GetPeersResult {
                     peers,
+                    scores,
                     main_responder,
-                } => main_responder.respond(peers).ignore(),
+                } => main_responder
+                    .respond(PeersMap::with_scores(peers, &scores))
+                    .ignore(),
                 Event::GetBalanceResult {
                     result,
                     main_responder,
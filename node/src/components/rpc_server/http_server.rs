@@ -10,10 +10,13 @@ use super::{
             GetBlock, GetBlockTransfers, GetEraInfoBySwitchBlock, GetEraSummary, GetStateRootHash,
         },
         docs::ListRpcs,
-        info::{GetChainspec, GetDeploy, GetPeers, GetStatus, GetValidatorChanges},
+        info::{
+            GetChainspec, GetDeploy, GetPeers, GetStatus, GetValidatorChanges, GetValidatorFaults,
+            GetValidatorPerformance,
+        },
         state::{
-            GetAccountInfo, GetAuctionInfo, GetBalance, GetDictionaryItem, GetItem, GetTrie,
-            QueryBalance, QueryGlobalState,
+            GetAccountInfo, GetAuctionInfo, GetBalance, GetDictionaryItem, GetItem, GetKeyHistory,
+            GetTrie, QueryBalance, QueryGlobalState,
         },
         RpcWithOptionalParams, RpcWithParams, RpcWithoutParams,
     },
@@ -51,7 +54,10 @@ pub(super) async fn run<REv: ReactorEventT>(
     GetEraSummary::register_as_handler(effect_builder, api_version, &mut handlers);
     GetAuctionInfo::register_as_handler(effect_builder, api_version, &mut handlers);
     GetTrie::register_as_handler(effect_builder, api_version, &mut handlers);
+    GetKeyHistory::register_as_handler(effect_builder, api_version, &mut handlers);
     GetValidatorChanges::register_as_handler(effect_builder, api_version, &mut handlers);
+    GetValidatorFaults::register_as_handler(effect_builder, api_version, &mut handlers);
+    GetValidatorPerformance::register_as_handler(effect_builder, api_version, &mut handlers);
     ListRpcs::register_as_handler(effect_builder, api_version, &mut handlers);
     GetDictionaryItem::register_as_handler(effect_builder, api_version, &mut handlers);
     GetChainspec::register_as_handler(effect_builder, api_version, &mut handlers);
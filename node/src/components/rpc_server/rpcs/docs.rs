@@ -20,10 +20,13 @@ use super::{
     chain::{
         GetBlock, GetBlockTransfers, GetEraInfoBySwitchBlock, GetEraSummary, GetStateRootHash,
     },
-    info::{GetChainspec, GetDeploy, GetPeers, GetStatus, GetValidatorChanges},
+    info::{
+        GetChainspec, GetDeploy, GetPeers, GetStatus, GetValidatorChanges, GetValidatorFaults,
+        GetValidatorPerformance,
+    },
     state::{
-        GetAccountInfo, GetAuctionInfo, GetBalance, GetDictionaryItem, GetItem, QueryBalance,
-        QueryGlobalState,
+        GetAccountInfo, GetAuctionInfo, GetBalance, GetDictionaryItem, GetItem, GetKeyHistory,
+        QueryBalance, QueryGlobalState,
     },
     Error, ReactorEventT, RpcWithOptionalParams, RpcWithParams, RpcWithoutParams,
 };
@@ -82,6 +85,12 @@ pub(crate) static OPEN_RPC_SCHEMA: Lazy<OpenRpcSchema> = Lazy::new(|| {
     schema.push_without_params::<GetStatus>("returns the current status of the node");
     schema
         .push_without_params::<GetValidatorChanges>("returns status changes of active validators");
+    schema.push_without_params::<GetValidatorFaults>(
+        "returns the faults of validators in recent eras, with the evidence proving them",
+    );
+    schema.push_with_optional_params::<GetValidatorPerformance>(
+        "returns the validators' performance in the given era, or in the current one",
+    );
     schema.push_without_params::<GetChainspec>(
         "returns the raw bytes of the chainspec.toml, genesis accounts.toml, and \
         global_state.toml files",
@@ -109,6 +118,9 @@ pub(crate) static OPEN_RPC_SCHEMA: Lazy<OpenRpcSchema> = Lazy::new(|| {
         "returns the era summary at either a specific block (by height or hash), or the most \
         recently added block",
     );
+    schema.push_with_params::<GetKeyHistory>(
+        "returns the recorded changes of the value under a global state key",
+    );
 
     schema
 });
@@ -35,6 +35,8 @@ pub enum ErrorCode {
     FailedToGetTrie = -32011,
     /// The requested state root hash was not found.
     NoSuchStateRoot = -32012,
+    /// The validators' performance in the requested era was not found.
+    NoSuchValidatorPerformance = -32013,
 }
 
 impl From<ErrorCode> for (i64, &'static str) {
@@ -59,6 +61,9 @@ impl From<ErrorCode> for (i64, &'static str) {
             }
             ErrorCode::FailedToGetTrie => (error_code as i64, "Failed to get trie"),
             ErrorCode::NoSuchStateRoot => (error_code as i64, "No such state root"),
+            ErrorCode::NoSuchValidatorPerformance => {
+                (error_code as i64, "No such validator performance")
+            }
         }
     }
 }
@@ -11,14 +11,15 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
-use casper_types::{EraId, ExecutionResult, ProtocolVersion, PublicKey};
+use casper_types::{EraId, ExecutionResult, ProtocolVersion, PublicKey, TimeDiff};
 
 use super::{
     docs::{DocExample, DOCS_EXAMPLE_PROTOCOL_VERSION},
-    Error, ErrorCode, ReactorEventT, RpcRequest, RpcWithParams, RpcWithoutParams,
+    Error, ErrorCode, ReactorEventT, RpcRequest, RpcWithOptionalParams, RpcWithParams,
+    RpcWithoutParams,
 };
 use crate::{
-    components::consensus::ValidatorChange,
+    components::consensus::{ValidatorChange, ValidatorFault, ValidatorPerformance},
     effect::EffectBuilder,
     reactor::QueueKind,
     types::{
@@ -53,6 +54,30 @@ static GET_VALIDATOR_CHANGES_RESULT: Lazy<GetValidatorChangesResult> = Lazy::new
         changes,
     }
 });
+static GET_VALIDATOR_FAULTS_RESULT: Lazy<GetValidatorFaultsResult> =
+    Lazy::new(|| GetValidatorFaultsResult {
+        api_version: DOCS_EXAMPLE_PROTOCOL_VERSION,
+        faults: vec![ValidatorFault::doc_example().clone()],
+    });
+static GET_VALIDATOR_PERFORMANCE_PARAMS: Lazy<GetValidatorPerformanceParams> =
+    Lazy::new(|| GetValidatorPerformanceParams {
+        era_id: EraId::new(1),
+    });
+static GET_VALIDATOR_PERFORMANCE_RESULT: Lazy<GetValidatorPerformanceResult> =
+    Lazy::new(|| GetValidatorPerformanceResult {
+        api_version: DOCS_EXAMPLE_PROTOCOL_VERSION,
+        era_id: EraId::new(1),
+        validators: vec![JsonValidatorPerformance {
+            public_key: PublicKey::doc_example().clone(),
+            performance: ValidatorPerformance {
+                proposals: 12,
+                missed_proposals: 1,
+                units_and_votes: 150,
+                endorsements: 0,
+                average_lateness: TimeDiff::from_millis(320),
+            },
+        }],
+    });
 static GET_CHAINSPEC_RESULT: Lazy<GetChainspecResult> = Lazy::new(|| GetChainspecResult {
     api_version: DOCS_EXAMPLE_PROTOCOL_VERSION,
     chainspec_bytes: ChainspecRawBytes::new(vec![42, 42].into(), None, None),
@@ -184,7 +209,7 @@ pub struct GetPeersResult {
     /// The RPC API version.
     #[schemars(with = "String")]
     pub api_version: ProtocolVersion,
-    /// The node ID and network address of each connected peer.
+    /// The node ID, network address and reputation score of each connected peer.
     pub peers: PeersMap,
 }
 
@@ -213,10 +238,7 @@ impl RpcWithoutParams for GetPeers {
             )
             .await;
 
-        let result = Self::ResponseResult {
-            api_version,
-            peers: PeersMap::from(peers),
-        };
+        let result = Self::ResponseResult { api_version, peers };
         Ok(result)
     }
 }
@@ -348,6 +370,147 @@ impl RpcWithoutParams for GetValidatorChanges {
     }
 }
 
+/// Result for the "info_get_validator_faults" RPC.
+#[derive(PartialEq, Eq, Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GetValidatorFaultsResult {
+    /// The RPC API version.
+    #[schemars(with = "String")]
+    pub api_version: ProtocolVersion,
+    /// The faults seen in the eras the node keeps in memory, with the evidence proving them.
+    pub faults: Vec<ValidatorFault>,
+}
+
+impl DocExample for GetValidatorFaultsResult {
+    fn doc_example() -> &'static Self {
+        &GET_VALIDATOR_FAULTS_RESULT
+    }
+}
+
+/// "info_get_validator_faults" RPC.
+pub struct GetValidatorFaults {}
+
+#[async_trait]
+impl RpcWithoutParams for GetValidatorFaults {
+    const METHOD: &'static str = "info_get_validator_faults";
+    type ResponseResult = GetValidatorFaultsResult;
+
+    async fn do_handle_request<REv: ReactorEventT>(
+        effect_builder: EffectBuilder<REv>,
+        api_version: ProtocolVersion,
+    ) -> Result<Self::ResponseResult, Error> {
+        let faults = effect_builder.get_consensus_validator_faults().await;
+        Ok(Self::ResponseResult {
+            api_version,
+            faults,
+        })
+    }
+}
+
+/// Params for "info_get_validator_performance" RPC request.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GetValidatorPerformanceParams {
+    /// The era to get the validators' performance for.
+    pub era_id: EraId,
+}
+
+impl DocExample for GetValidatorPerformanceParams {
+    fn doc_example() -> &'static Self {
+        &GET_VALIDATOR_PERFORMANCE_PARAMS
+    }
+}
+
+/// A single validator's performance in an era.
+#[derive(PartialEq, Eq, Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct JsonValidatorPerformance {
+    /// The public key of the validator.
+    pub public_key: PublicKey,
+    /// The validator's performance.
+    pub performance: ValidatorPerformance,
+}
+
+/// Result for the "info_get_validator_performance" RPC.
+#[derive(PartialEq, Eq, Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GetValidatorPerformanceResult {
+    /// The RPC API version.
+    #[schemars(with = "String")]
+    pub api_version: ProtocolVersion,
+    /// The era the performance was observed in.
+    pub era_id: EraId,
+    /// The performance of each validator.
+    pub validators: Vec<JsonValidatorPerformance>,
+}
+
+impl GetValidatorPerformanceResult {
+    pub(crate) fn new(
+        api_version: ProtocolVersion,
+        era_id: EraId,
+        performance: BTreeMap<PublicKey, ValidatorPerformance>,
+    ) -> Self {
+        let validators = performance
+            .into_iter()
+            .map(|(public_key, performance)| JsonValidatorPerformance {
+                public_key,
+                performance,
+            })
+            .collect();
+        GetValidatorPerformanceResult {
+            api_version,
+            era_id,
+            validators,
+        }
+    }
+}
+
+impl DocExample for GetValidatorPerformanceResult {
+    fn doc_example() -> &'static Self {
+        &GET_VALIDATOR_PERFORMANCE_RESULT
+    }
+}
+
+/// "info_get_validator_performance" RPC.
+pub struct GetValidatorPerformance {}
+
+#[async_trait]
+impl RpcWithOptionalParams for GetValidatorPerformance {
+    const METHOD: &'static str = "info_get_validator_performance";
+    type OptionalRequestParams = GetValidatorPerformanceParams;
+    type ResponseResult = GetValidatorPerformanceResult;
+
+    async fn do_handle_request<REv: ReactorEventT>(
+        effect_builder: EffectBuilder<REv>,
+        api_version: ProtocolVersion,
+        maybe_params: Option<Self::OptionalRequestParams>,
+    ) -> Result<Self::ResponseResult, Error> {
+        // Completed eras are read from storage, the current era's statistics so far from
+        // consensus.
+        let maybe_performance = match maybe_params {
+            None => effect_builder.get_consensus_validator_performance().await,
+            Some(GetValidatorPerformanceParams { era_id }) => {
+                match effect_builder
+                    .get_validator_performance_from_storage(era_id)
+                    .await
+                {
+                    Some(performance) => Some((era_id, performance)),
+                    None => effect_builder
+                        .get_consensus_validator_performance()
+                        .await
+                        .filter(|(current_era_id, _)| *current_era_id == era_id),
+                }
+            }
+        };
+        let (era_id, performance) = maybe_performance.ok_or_else(|| {
+            let message = "validator performance not available on this node".to_string();
+            info!("{}", message);
+            Error::new(ErrorCode::NoSuchValidatorPerformance, message)
+        })?;
+        Ok(Self::ResponseResult::new(api_version, era_id, performance))
+    }
+}
+
 /// Result for the "info_get_chainspec" RPC.
 #[derive(PartialEq, Eq, Serialize, Deserialize, Debug, JsonSchema)]
 pub struct GetChainspecResult {
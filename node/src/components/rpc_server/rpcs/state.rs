@@ -25,6 +25,7 @@ use casper_types::{
 };
 
 use crate::{
+    components::storage::KeyHistoryEntry,
     effect::EffectBuilder,
     reactor::QueueKind,
     rpcs::{
@@ -39,6 +40,9 @@ use crate::{
     },
 };
 
+/// The maximum number of entries returned by the "state_get_key_history" RPC.
+const MAX_KEY_HISTORY_ENTRIES: u32 = 1000;
+
 static GET_ITEM_PARAMS: Lazy<GetItemParams> = Lazy::new(|| GetItemParams {
     state_root_hash: *Block::doc_example().header().state_root_hash(),
     key: "deploy-af684263911154d26fa05be9963171802801a0b6aff8f199b7391eacb8edc9e1".to_string(),
@@ -119,6 +123,19 @@ static GET_TRIE_RESULT: Lazy<GetTrieResult> = Lazy::new(|| GetTrieResult {
     api_version: DOCS_EXAMPLE_PROTOCOL_VERSION,
     maybe_trie_bytes: None,
 });
+static GET_KEY_HISTORY_PARAMS: Lazy<GetKeyHistoryParams> = Lazy::new(|| GetKeyHistoryParams {
+    key: "balance-09480c3248ef76b603d386f3f4f8a5f87f597d4eaffd475433f861af187ab5db".to_string(),
+    from_height: Some(0),
+    limit: Some(10),
+});
+static GET_KEY_HISTORY_RESULT: Lazy<GetKeyHistoryResult> = Lazy::new(|| GetKeyHistoryResult {
+    api_version: DOCS_EXAMPLE_PROTOCOL_VERSION,
+    history: vec![KeyHistoryEntry {
+        block_height: Block::doc_example().header().height(),
+        block_hash: *Block::doc_example().hash(),
+        value_hash: Some(Digest::hash(U512::from(123_456).to_bytes().unwrap())),
+    }],
+});
 static QUERY_BALANCE_PARAMS: Lazy<QueryBalanceParams> = Lazy::new(|| QueryBalanceParams {
     state_identifier: Some(GlobalStateIdentifier::BlockHash(
         *Block::doc_example().hash(),
@@ -1098,6 +1115,81 @@ impl RpcWithParams for GetTrie {
     }
 }
 
+/// Parameters for "state_get_key_history" RPC request.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GetKeyHistoryParams {
+    /// The formatted global state key.
+    pub key: String,
+    /// The height of the earliest block to include, defaults to 0.
+    pub from_height: Option<u64>,
+    /// The maximum number of entries to return, defaults to and is capped at 1000.
+    pub limit: Option<u32>,
+}
+
+impl DocExample for GetKeyHistoryParams {
+    fn doc_example() -> &'static Self {
+        &GET_KEY_HISTORY_PARAMS
+    }
+}
+
+/// Result for "state_get_key_history" RPC response.
+#[derive(PartialEq, Eq, Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GetKeyHistoryResult {
+    /// The RPC API version.
+    #[schemars(with = "String")]
+    pub api_version: ProtocolVersion,
+    /// The recorded changes of the value under the key, in order of ascending block height.
+    ///
+    /// Empty unless the key is selected by the node's key history settings.
+    pub history: Vec<KeyHistoryEntry>,
+}
+
+impl DocExample for GetKeyHistoryResult {
+    fn doc_example() -> &'static Self {
+        &GET_KEY_HISTORY_RESULT
+    }
+}
+
+/// `state_get_key_history` RPC.
+pub struct GetKeyHistory {}
+
+#[async_trait]
+impl RpcWithParams for GetKeyHistory {
+    const METHOD: &'static str = "state_get_key_history";
+    type RequestParams = GetKeyHistoryParams;
+    type ResponseResult = GetKeyHistoryResult;
+
+    async fn do_handle_request<REv: ReactorEventT>(
+        effect_builder: EffectBuilder<REv>,
+        api_version: ProtocolVersion,
+        params: Self::RequestParams,
+    ) -> Result<Self::ResponseResult, Error> {
+        let key = match Key::from_formatted_str(&params.key)
+            .map_err(|error| format!("failed to parse key: {}", error))
+        {
+            Ok(key) => key,
+            Err(error_msg) => {
+                info!("{}", error_msg);
+                return Err(Error::new(ErrorCode::FailedToParseQueryKey, error_msg));
+            }
+        };
+        let from_height = params.from_height.unwrap_or_default();
+        let limit = params.limit.map_or(MAX_KEY_HISTORY_ENTRIES, |limit| {
+            limit.min(MAX_KEY_HISTORY_ENTRIES)
+        });
+
+        let history = effect_builder
+            .get_key_history_from_storage(key, from_height, limit as usize)
+            .await;
+        Ok(Self::ResponseResult {
+            api_version,
+            history,
+        })
+    }
+}
+
 type QuerySuccess = (
     DomainStoredValue,
     Vec<TrieMerkleProof<Key, DomainStoredValue>>,
@@ -32,11 +32,15 @@
 
 pub(crate) mod disjoint_sequences;
 mod error;
+mod integrity;
+mod key_history;
 mod lmdb_ext;
 mod metrics;
 mod object_pool;
+mod snapshot;
 #[cfg(test)]
 mod tests;
+mod validator_performance;
 
 #[cfg(test)]
 use std::collections::BTreeSet;
@@ -49,8 +53,7 @@ use std::{
     io::ErrorKind,
     mem,
     path::{Path, PathBuf},
-    rc::Rc,
-    sync::Arc,
+    sync::{atomic::AtomicBool, Arc},
 };
 
 use datasize::DataSize;
@@ -103,9 +106,14 @@ use crate::{
 use disjoint_sequences::{DisjointSequences, Sequence};
 pub use error::FatalStorageError;
 use error::GetRequestError;
-use lmdb_ext::{BytesreprError, LmdbExtError, TransactionExt, WriteTransactionExt};
+pub(crate) use integrity::{IntegrityReport, StorageFault};
+pub use key_history::KeyHistoryEntry;
+use lmdb_ext::{
+    BytesreprError, CompressedSizes, LmdbExtError, TransactionExt, WriteTransactionExt,
+};
 use metrics::Metrics;
 use object_pool::ObjectPool;
+pub(crate) use snapshot::SnapshotError;
 
 const COMPONENT_NAME: &str = "storage";
 
@@ -113,8 +121,9 @@ const COMPONENT_NAME: &str = "storage";
 const STORAGE_DB_FILENAME: &str = "storage.lmdb";
 
 /// We can set this very low, as there is only a single reader/writer accessing the component at any
-/// one time.
-const MAX_TRANSACTIONS: u32 = 1;
+/// one time, plus at most one reader taking a snapshot in the background (see
+/// `Storage::snapshot_in_progress`).
+const MAX_TRANSACTIONS: u32 = 2;
 
 /// One Gibibyte.
 const GIB: usize = 1024 * 1024 * 1024;
@@ -127,8 +136,12 @@ const DEFAULT_MAX_DEPLOY_STORE_SIZE: usize = 300 * GIB;
 const DEFAULT_MAX_DEPLOY_METADATA_STORE_SIZE: usize = 300 * GIB;
 /// Default max state store size.
 const DEFAULT_MAX_STATE_STORE_SIZE: usize = 10 * GIB;
+/// Default zstd compression level for deploys and deploy metadata.
+const DEFAULT_DEPLOY_COMPRESSION_LEVEL: i32 = 3;
+/// Number of records rewritten per transaction when compressing existing deploy records.
+const COMPRESSION_MIGRATION_BATCH_SIZE: usize = 1000;
 /// Maximum number of allowed dbs.
-const MAX_DB_COUNT: u32 = 9;
+const MAX_DB_COUNT: u32 = 11;
 /// Key under which completed blocks are to be stored.
 const COMPLETED_BLOCKS_STORAGE_KEY: &[u8] = b"completed_blocks_disjoint_sequences";
 /// Name of the file created when initializing a force resync.
@@ -163,7 +176,7 @@ pub struct Storage {
     root: PathBuf,
     /// Environment holding LMDB databases.
     #[data_size(skip)]
-    env: Rc<Environment>,
+    env: Arc<Environment>,
     /// The block header database.
     #[data_size(skip)]
     block_header_db: Database,
@@ -191,6 +204,16 @@ pub struct Storage {
     /// The finalized approvals database.
     #[data_size(skip)]
     finalized_approvals_db: Database,
+    /// The key history database.
+    ///
+    /// Only `None` for storages opened read-only which were created before the index existed.
+    #[data_size(skip)]
+    key_history_db: Option<Database>,
+    /// The validator performance database.
+    ///
+    /// Only `None` for storages opened read-only which were created before the database existed.
+    #[data_size(skip)]
+    validator_performance_db: Option<Database>,
     /// A map of block height to block ID.
     block_height_index: BTreeMap<u64, BlockHash>,
     /// A map of era ID to switch block ID.
@@ -205,6 +228,8 @@ pub struct Storage {
     key_block_height_for_activation_point: Option<u64>,
     /// Whether or not memory deduplication is enabled.
     enable_mem_deduplication: bool,
+    /// The zstd level used to compress deploys and deploy metadata, if compression is enabled.
+    deploy_compression_level: Option<i32>,
     /// An in-memory pool of already loaded serialized items.
     ///
     /// Keyed by serialized item ID, contains the serialized item.
@@ -216,6 +241,12 @@ pub struct Storage {
     metrics: Option<Metrics>,
     /// The maximum TTL of a deploy.
     max_ttl: MaxTtl,
+    /// Whether a snapshot is being taken in the background, holding the only spare reader slot.
+    #[data_size(skip)]
+    snapshot_in_progress: Arc<AtomicBool>,
+    /// Faults which prevented blocks from being indexed when the storage was opened read-only.
+    #[data_size(skip)]
+    indexing_faults: Vec<StorageFault>,
 }
 
 /// A storage component event.
@@ -416,6 +447,9 @@ impl Storage {
         let block_body_db = env.create_db(Some("block_body"), DatabaseFlags::empty())?;
         let approvals_hashes_db =
             env.create_db(Some("approvals_hashes"), DatabaseFlags::empty())?;
+        let key_history_db = env.create_db(Some("key_history"), DatabaseFlags::empty())?;
+        let validator_performance_db =
+            env.create_db(Some("validator_performance"), DatabaseFlags::empty())?;
 
         // We now need to restore the block-height index. Log messages allow timing here.
         info!("indexing block store");
@@ -458,20 +492,13 @@ impl Storage {
                 }
             }
 
-            insert_to_block_header_indices(
+            insert_to_indices(
                 &mut block_height_index,
                 &mut switch_block_era_id_index,
+                &mut deploy_hash_index,
                 &block_header,
+                maybe_block_body?.as_ref(),
             )?;
-
-            if let Some(block_body) = maybe_block_body? {
-                insert_to_deploy_index(
-                    &mut deploy_hash_index,
-                    block_header.block_hash(),
-                    &block_body,
-                    block_header.height(),
-                )?;
-            }
         }
         info!("block store reindexing complete");
         drop(cursor);
@@ -496,7 +523,7 @@ impl Storage {
 
         let mut component = Self {
             root,
-            env: Rc::new(env),
+            env: Arc::new(env),
             block_header_db,
             block_body_db,
             block_metadata_db,
@@ -506,6 +533,8 @@ impl Storage {
             transfer_db,
             state_store_db,
             finalized_approvals_db,
+            key_history_db: Some(key_history_db),
+            validator_performance_db: Some(validator_performance_db),
             block_height_index,
             switch_block_era_id_index,
             deploy_hash_index,
@@ -513,10 +542,13 @@ impl Storage {
             activation_era,
             key_block_height_for_activation_point: None,
             enable_mem_deduplication: config.enable_mem_deduplication,
+            deploy_compression_level: config.deploy_compression_level(),
             serialized_item_pool: ObjectPool::new(config.mem_pool_prune_interval),
             recent_era_count,
             max_ttl,
             metrics,
+            snapshot_in_progress: Arc::new(AtomicBool::new(false)),
+            indexing_faults: vec![],
         };
 
         if force_resync {
@@ -599,6 +631,148 @@ impl Storage {
         Ok(component)
     }
 
+    /// Opens an existing storage without ever writing to it.
+    ///
+    /// Unlike [`Storage::new`], no stale entries are purged and the completed blocks index is
+    /// taken as stored. Any attempt to write via the returned component will fail.
+    pub(crate) fn open_read_only(
+        cfg: &WithDir<Config>,
+        network_name: &str,
+        max_ttl: MaxTtl,
+        recent_era_count: u64,
+        registry: Option<&Registry>,
+    ) -> Result<Self, FatalStorageError> {
+        let config = cfg.value();
+        let root = cfg.with_dir(config.path.clone()).join(network_name);
+
+        let total_size = config
+            .max_block_store_size
+            .saturating_add(config.max_deploy_store_size)
+            .saturating_add(config.max_deploy_metadata_store_size);
+
+        let env = Environment::new()
+            .set_flags(
+                EnvironmentFlags::NO_SUB_DIR
+                    | EnvironmentFlags::NO_TLS
+                    | EnvironmentFlags::NO_READAHEAD
+                    | EnvironmentFlags::READ_ONLY,
+            )
+            .set_max_readers(MAX_TRANSACTIONS)
+            .set_max_dbs(MAX_DB_COUNT)
+            .set_map_size(total_size)
+            .open(&root.join(STORAGE_DB_FILENAME))?;
+
+        let block_header_db = env.open_db(Some("block_header"))?;
+        let block_metadata_db = env.open_db(Some("block_metadata"))?;
+        let deploy_db = env.open_db(Some("deploys"))?;
+        let deploy_metadata_db = env.open_db(Some("deploy_metadata"))?;
+        let transfer_db = env.open_db(Some("transfer"))?;
+        let state_store_db = env.open_db(Some("state_store"))?;
+        let finalized_approvals_db = env.open_db(Some("finalized_approvals"))?;
+        let block_body_db = env.open_db(Some("block_body"))?;
+        let approvals_hashes_db = env.open_db(Some("approvals_hashes"))?;
+        let key_history_db = match env.open_db(Some("key_history")) {
+            Ok(db) => Some(db),
+            Err(lmdb::Error::NotFound) => None,
+            Err(error) => return Err(error.into()),
+        };
+        let validator_performance_db = match env.open_db(Some("validator_performance")) {
+            Ok(db) => Some(db),
+            Err(lmdb::Error::NotFound) => None,
+            Err(error) => return Err(error.into()),
+        };
+
+        info!("indexing block store (read-only)");
+        let mut block_height_index = BTreeMap::new();
+        let mut switch_block_era_id_index = BTreeMap::new();
+        let mut deploy_hash_index = BTreeMap::new();
+        // Unlike `Storage::new`, a corrupt or conflicting block must not prevent opening the
+        // database, so these are recorded and reported by `Storage::check_integrity` instead.
+        let mut indexing_faults = vec![];
+        {
+            let mut txn = env.begin_ro_txn()?;
+            let mut block_headers = vec![];
+            for row in txn.open_ro_cursor(block_header_db)?.iter() {
+                let (raw_key, raw_val) = row?;
+                match lmdb_ext::deserialize::<BlockHeader>(raw_val) {
+                    Ok(block_header) => block_headers.push(block_header),
+                    Err(_) => indexing_faults.push(StorageFault::UndecodableRecord {
+                        database: "block_header",
+                        key: base16::encode_lower(raw_key),
+                    }),
+                }
+            }
+            for block_header in block_headers {
+                // Undecodable block bodies are found by the integrity check's scan of all bodies.
+                let maybe_block_body = match get_body_for_block_header(
+                    &mut txn,
+                    block_header.body_hash(),
+                    block_body_db,
+                ) {
+                    Ok(maybe_block_body) => maybe_block_body,
+                    Err(LmdbExtError::DataCorrupted(_)) => None,
+                    Err(error) => return Err(error.into()),
+                };
+                if let Err(error) = insert_to_indices(
+                    &mut block_height_index,
+                    &mut switch_block_era_id_index,
+                    &mut deploy_hash_index,
+                    &block_header,
+                    maybe_block_body.as_ref(),
+                ) {
+                    indexing_faults.push(StorageFault::try_from_index_error(error)?);
+                }
+            }
+        }
+        info!(
+            faults = indexing_faults.len(),
+            "block store reindexing complete"
+        );
+
+        let metrics = registry.map(Metrics::new).transpose()?;
+
+        let mut component = Self {
+            root,
+            env: Arc::new(env),
+            block_header_db,
+            block_body_db,
+            block_metadata_db,
+            approvals_hashes_db,
+            deploy_db,
+            deploy_metadata_db,
+            transfer_db,
+            state_store_db,
+            finalized_approvals_db,
+            key_history_db,
+            validator_performance_db,
+            block_height_index,
+            switch_block_era_id_index,
+            deploy_hash_index,
+            completed_blocks: Default::default(),
+            activation_era: EraId::default(),
+            key_block_height_for_activation_point: None,
+            enable_mem_deduplication: config.enable_mem_deduplication,
+            deploy_compression_level: config.deploy_compression_level(),
+            serialized_item_pool: ObjectPool::new(config.mem_pool_prune_interval),
+            recent_era_count,
+            max_ttl,
+            metrics,
+            snapshot_in_progress: Arc::new(AtomicBool::new(false)),
+            indexing_faults,
+        };
+
+        if let Some(raw) =
+            component.read_state_store(&Cow::Borrowed(COMPLETED_BLOCKS_STORAGE_KEY))?
+        {
+            let (sequences, _) = DisjointSequences::from_vec(raw)
+                .map_err(FatalStorageError::UnexpectedDeserializationFailure)?;
+            component.completed_blocks = sequences;
+        }
+        component.update_chain_height_metrics();
+
+        Ok(component)
+    }
+
     /// Reads from the state storage database.
     ///
     /// If key is non-empty, returns bytes from under the key. Otherwise returns `Ok(None)`.
@@ -797,7 +971,7 @@ impl Storage {
                 approvals_hashes,
                 responder,
             } => {
-                let env = Rc::clone(&self.env);
+                let env = Arc::clone(&self.env);
                 let mut txn = env.begin_rw_txn()?;
                 let result = self.write_approvals_hashes(&mut txn, &approvals_hashes)?;
                 txn.commit()?;
@@ -925,7 +1099,7 @@ impl Storage {
                 execution_results,
                 responder,
             } => {
-                let env = Rc::clone(&self.env);
+                let env = Arc::clone(&self.env);
                 let mut txn = env.begin_rw_txn()?;
                 self.write_execution_results(&mut txn, &block_hash, execution_results)?;
                 txn.commit()?;
@@ -1185,6 +1359,44 @@ impl Storage {
             } => responder
                 .respond(self.put_executed_block(&block, &approvals_hashes, execution_results)?)
                 .ignore(),
+            StorageRequest::PutKeyHistory {
+                block_height,
+                entries,
+                responder,
+            } => {
+                self.put_key_history(block_height, &entries)?;
+                responder.respond(()).ignore()
+            }
+            StorageRequest::GetKeyHistory {
+                key,
+                from_height,
+                limit,
+                responder,
+            } => responder
+                .respond(self.get_key_history(&key, from_height, limit)?)
+                .ignore(),
+            StorageRequest::PutValidatorPerformance {
+                era_id,
+                performance,
+                responder,
+            } => {
+                self.put_validator_performance(era_id, &performance)?;
+                responder.respond(()).ignore()
+            }
+            StorageRequest::GetValidatorPerformance { era_id, responder } => responder
+                .respond(self.get_validator_performance(era_id)?)
+                .ignore(),
+            StorageRequest::TakeSnapshot { dir, responder } => match self.snapshot_task(dir) {
+                Ok(task) => async move {
+                    let result = match tokio::task::spawn_blocking(task).await {
+                        Ok(result) => result,
+                        Err(error) => Err(SnapshotError::Task(error)),
+                    };
+                    responder.respond(result).await
+                }
+                .ignore(),
+                Err(error) => responder.respond(Err(error)).ignore(),
+            },
             StorageRequest::GetKeyBlockHeightForActivationPoint { responder } => {
                 // If we haven't already cached the height, try to retrieve the key block header.
                 if self.key_block_height_for_activation_point.is_none() {
@@ -1246,11 +1458,100 @@ impl Storage {
         self.write_state_store(Cow::Borrowed(COMPLETED_BLOCKS_STORAGE_KEY), &serialized)
     }
 
+    /// Writes a record to the deploy or deploy metadata database, compressing it if enabled.
+    fn put_deploy_record<K: AsRef<[u8]>, V: 'static + Serialize>(
+        &self,
+        txn: &mut RwTransaction,
+        db: Database,
+        key: &K,
+        value: &V,
+        overwrite: bool,
+    ) -> Result<bool, LmdbExtError> {
+        let level = match self.deploy_compression_level {
+            Some(level) => level,
+            None => return txn.put_value(db, key, value, overwrite),
+        };
+        let (was_written, sizes) = txn.put_value_compressed(db, key, value, overwrite, level)?;
+        if was_written {
+            self.update_compression_metrics(sizes);
+        }
+        Ok(was_written)
+    }
+
+    /// Compresses all existing uncompressed records in the deploy and deploy metadata databases.
+    ///
+    /// Does nothing if deploy compression is not enabled. Records which are already compressed or
+    /// which would not become smaller are left untouched, so running this repeatedly is harmless.
+    /// Returns the number of records rewritten.
+    pub(crate) fn compress_deploy_records(&self) -> Result<usize, FatalStorageError> {
+        let level = match self.deploy_compression_level {
+            Some(level) => level,
+            None => return Ok(0),
+        };
+        let mut rewritten = 0;
+        for db in [self.deploy_db, self.deploy_metadata_db] {
+            rewritten += self.compress_records(db, level)?;
+        }
+        Ok(rewritten)
+    }
+
+    /// Compresses all uncompressed records in the given database, in batches to keep the size of
+    /// each write transaction bounded.
+    fn compress_records(&self, db: Database, level: i32) -> Result<usize, FatalStorageError> {
+        let mut rewritten = 0;
+        let mut last_key: Option<Vec<u8>> = None;
+        loop {
+            let mut txn = self.env.begin_rw_txn()?;
+            let mut batch = vec![];
+            let mut batch_end = None;
+            {
+                let mut cursor = txn.open_ro_cursor(db)?;
+                let rows = match last_key.as_ref() {
+                    Some(key) => cursor.iter_from(key),
+                    None => cursor.iter(),
+                };
+                for row in rows {
+                    let (raw_key, raw_val) = row?;
+                    if last_key.as_deref() == Some(raw_key) {
+                        // `iter_from` starts at the last key of the previous batch.
+                        continue;
+                    }
+                    batch_end = Some(raw_key.to_vec());
+                    if !lmdb_ext::is_compressed(raw_val) {
+                        batch.push((raw_key.to_vec(), raw_val.to_vec()));
+                    }
+                    if batch.len() == COMPRESSION_MIGRATION_BATCH_SIZE {
+                        break;
+                    }
+                }
+            }
+
+            for (raw_key, raw_val) in batch {
+                let compressed = lmdb_ext::compress(&raw_val, level)?;
+                if compressed.len() < raw_val.len() {
+                    txn.put(db, &raw_key, &compressed, WriteFlags::empty())?;
+                    self.update_compression_metrics(CompressedSizes {
+                        uncompressed: raw_val.len(),
+                        stored: compressed.len(),
+                    });
+                    rewritten += 1;
+                }
+            }
+            txn.commit()?;
+
+            if batch_end.is_none() {
+                return Ok(rewritten);
+            }
+            last_key = batch_end;
+        }
+    }
+
     /// Put a single deploy into storage.
     pub fn put_deploy(&self, deploy: &Deploy) -> Result<bool, FatalStorageError> {
         let mut txn = self.env.begin_rw_txn()?;
         let deploy_hash = deploy.hash();
-        let outcome = txn.put_value(self.deploy_db, deploy_hash, deploy, false)?;
+        let outcome =
+            self.put_deploy_record(&mut txn, self.deploy_db, deploy_hash, deploy, false)?;
         if outcome {
             debug!(%deploy_hash, "Storage: new deploy stored");
         } else {
@@ -1266,7 +1567,7 @@ impl Storage {
         approvals_hashes: &ApprovalsHashes,
         execution_results: HashMap<DeployHash, ExecutionResult>,
     ) -> Result<bool, FatalStorageError> {
-        let env = Rc::clone(&self.env);
+        let env = Arc::clone(&self.env);
         let mut txn = env.begin_rw_txn()?;
         let wrote = self.write_validated_block(&mut txn, block)?;
         if !wrote {
@@ -1433,7 +1734,7 @@ impl Storage {
     pub fn write_block(&mut self, block: &Block) -> Result<bool, FatalStorageError> {
         // Validate the block prior to inserting it into the database
         block.verify()?;
-        let env = Rc::clone(&self.env);
+        let env = Arc::clone(&self.env);
         let mut txn = env.begin_rw_txn()?;
         let wrote = self.write_validated_block(&mut txn, block)?;
         if wrote {
@@ -1451,7 +1752,7 @@ impl Storage {
     pub fn write_complete_block(&mut self, block: &Block) -> Result<bool, FatalStorageError> {
         // Validate the block prior to inserting it into the database
         block.verify()?;
-        let env = Rc::clone(&self.env);
+        let env = Arc::clone(&self.env);
         let mut txn = env.begin_rw_txn()?;
         let wrote = self.write_validated_block(&mut txn, block)?;
         if wrote {
@@ -1501,8 +1802,13 @@ impl Storage {
             metadata
                 .execution_results
                 .insert(*block_hash, execution_result);
-            let was_written =
-                txn.put_value(self.deploy_metadata_db, &deploy_hash, &metadata, true)?;
+            let was_written = self.put_deploy_record(
+                txn,
+                self.deploy_metadata_db,
+                &deploy_hash,
+                &metadata,
+                true,
+            )?;
             if !was_written {
                 error!(?block_hash, ?deploy_hash, "failed to write deploy metadata");
                 debug_assert!(was_written);
@@ -1583,19 +1889,13 @@ impl Storage {
             return Ok(false);
         }
 
-        {
-            insert_to_block_header_indices(
-                &mut self.block_height_index,
-                &mut self.switch_block_era_id_index,
-                block.header(),
-            )?;
-            insert_to_deploy_index(
-                &mut self.deploy_hash_index,
-                *block.hash(),
-                block.body(),
-                block.header().height(),
-            )?;
-        }
+        insert_to_indices(
+            &mut self.block_height_index,
+            &mut self.switch_block_era_id_index,
+            &mut self.deploy_hash_index,
+            block.header(),
+            Some(block.body()),
+        )?;
         Ok(true)
     }
 
@@ -2608,6 +2908,12 @@ impl Storage {
         Ok(Some(request.response(value_or_chunk)))
     }
 
+    fn update_compression_metrics(&self, sizes: CompressedSizes) {
+        if let Some(metrics) = self.metrics.as_ref() {
+            metrics.record_compression(sizes);
+        }
+    }
+
     fn update_chain_height_metrics(&self) {
         if let Some(metrics) = self.metrics.as_ref() {
             if let Some(sequence) = self.completed_blocks.highest_sequence() {
@@ -2629,6 +2935,29 @@ where
     bincode::deserialize(raw).map_err(GetRequestError::MalformedIncomingItemId)
 }
 
+/// Inserts the relevant entries for a block to the block height, switch block and deploy indices.
+///
+/// If a duplicate entry is encountered, an error is returned. The deploy index is only updated if
+/// the block header indices were.
+fn insert_to_indices(
+    block_height_index: &mut BTreeMap<u64, BlockHash>,
+    switch_block_era_id_index: &mut BTreeMap<EraId, BlockHash>,
+    deploy_hash_index: &mut BTreeMap<DeployHash, BlockHashAndHeight>,
+    block_header: &BlockHeader,
+    maybe_block_body: Option<&BlockBody>,
+) -> Result<(), FatalStorageError> {
+    insert_to_block_header_indices(block_height_index, switch_block_era_id_index, block_header)?;
+    if let Some(block_body) = maybe_block_body {
+        insert_to_deploy_index(
+            deploy_hash_index,
+            block_header.block_hash(),
+            block_body,
+            block_header.height(),
+        )?;
+    }
+    Ok(())
+}
+
 /// Inserts the relevant entries to the two indices.
 ///
 /// If a duplicate entry is encountered, neither index is updated and an error is returned.
@@ -2786,6 +3115,18 @@ pub struct Config {
     pub enable_mem_deduplication: bool,
     /// How many loads before memory duplication checks for dead references.
     pub mem_pool_prune_interval: u16,
+    /// Whether or not deploys and deploy metadata are stored zstd-compressed.
+    ///
+    /// Records written while compression was disabled remain readable, and vice versa.
+    #[serde(default)]
+    pub enable_deploy_compression: bool,
+    /// The zstd compression level used if deploy compression is enabled.
+    #[serde(default = "default_deploy_compression_level")]
+    pub deploy_compression_level: i32,
+}
+
+fn default_deploy_compression_level() -> i32 {
+    DEFAULT_DEPLOY_COMPRESSION_LEVEL
 }
 
 impl Default for Config {
@@ -2799,11 +3140,20 @@ impl Default for Config {
             max_state_store_size: DEFAULT_MAX_STATE_STORE_SIZE,
             enable_mem_deduplication: true,
             mem_pool_prune_interval: 4096,
+            enable_deploy_compression: false,
+            deploy_compression_level: DEFAULT_DEPLOY_COMPRESSION_LEVEL,
         }
     }
 }
 
 impl Config {
+    /// Returns the zstd level to compress deploys and deploy metadata with, or `None` if deploy
+    /// compression is disabled.
+    fn deploy_compression_level(&self) -> Option<i32> {
+        self.enable_deploy_compression
+            .then_some(self.deploy_compression_level)
+    }
+
     /// Returns a default `Config` suitable for tests, along with a `TempDir` which must be kept
     /// alive for the duration of the test since its destructor removes the dir from the filesystem.
     #[cfg(test)]
@@ -208,6 +208,13 @@ impl DisjointSequences {
             true
         })
     }
+
+    /// Returns `true` if `value` exists in the disjoint sequences.
+    pub(super) fn contains(&self, value: u64) -> bool {
+        self.sequences
+            .iter()
+            .any(|sequence| value >= sequence.low && value <= sequence.high)
+    }
 }
 #[cfg(test)]
 impl DisjointSequences {
@@ -217,13 +224,6 @@ impl DisjointSequences {
             self.insert(height);
         })
     }
-
-    /// Returns `true` if `value` exists in the disjoint sequences.
-    fn contains(&self, value: u64) -> bool {
-        self.sequences
-            .iter()
-            .any(|sequence| value >= sequence.low && value <= sequence.high)
-    }
 }
 
 impl FromBytes for Sequence {
@@ -0,0 +1,515 @@
+//! Offline integrity checks of the storage databases.
+//!
+//! The checks never modify the database and report every fault found rather than stopping at the
+//! first one, so they are suitable for assessing a node's storage after a disk incident.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    convert::TryFrom,
+    fmt::{self, Display, Formatter},
+};
+
+use lmdb::{Cursor, Transaction};
+
+use casper_hashing::Digest;
+use casper_types::{EraId, PublicKey, U512};
+
+use super::{
+    lmdb_ext::{self, TransactionExt},
+    FatalStorageError, Storage,
+};
+use crate::types::{BlockBody, BlockHash, BlockHeader, BlockSignatures, DeployHash};
+
+/// A single inconsistency found in storage.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum StorageFault {
+    /// A record could not be deserialized.
+    UndecodableRecord {
+        /// The name of the database holding the record.
+        database: &'static str,
+        /// The hex-encoded key of the record.
+        key: String,
+    },
+    /// A block header does not hash to the key it is stored under.
+    BlockHeaderHashMismatch {
+        /// The key the header is stored under.
+        stored_under: BlockHash,
+        /// The actual hash of the header.
+        actual: BlockHash,
+    },
+    /// A block body does not hash to the key it is stored under.
+    BlockBodyHashMismatch {
+        /// The key the body is stored under.
+        stored_under: Digest,
+        /// The actual hash of the body.
+        actual: Digest,
+    },
+    /// A block body is not referenced by any block header.
+    OrphanedBlockBody {
+        /// The hash of the block body.
+        body_hash: Digest,
+    },
+    /// A block's parent hash doesn't match the hash of the block stored one height below.
+    ParentMismatch {
+        /// The height of the block.
+        height: u64,
+        /// The parent hash recorded in the block header.
+        parent_hash: BlockHash,
+        /// The hash of the block stored at `height - 1`.
+        stored_parent_hash: BlockHash,
+    },
+    /// The finality signatures stored for a block name a different block or era.
+    MisindexedSignatures {
+        /// The block the signatures are stored under.
+        block_hash: BlockHash,
+        /// The block hash recorded in the signatures.
+        signed_block_hash: BlockHash,
+        /// The era recorded in the signatures.
+        signed_era_id: EraId,
+    },
+    /// A finality signature is cryptographically invalid.
+    InvalidSignature {
+        /// The signed block.
+        block_hash: BlockHash,
+        /// The signer.
+        public_key: PublicKey,
+    },
+    /// A finality signature was created by a key which is not a validator in the block's era.
+    UnknownSigner {
+        /// The signed block.
+        block_hash: BlockHash,
+        /// The era of the signed block.
+        era_id: EraId,
+        /// The signer.
+        public_key: PublicKey,
+    },
+    /// A height marked as complete has no block header.
+    CompletedBlockMissingHeader {
+        /// The height of the block.
+        height: u64,
+    },
+    /// A height marked as complete has no block body.
+    CompletedBlockMissingBody {
+        /// The height of the block.
+        height: u64,
+        /// The hash of the block.
+        block_hash: BlockHash,
+    },
+    /// A deploy referenced by the body of a complete block is missing.
+    MissingDeploy {
+        /// The hash of the block.
+        block_hash: BlockHash,
+        /// The hash of the missing deploy.
+        deploy_hash: DeployHash,
+    },
+    /// Two blocks claim the same entry of an index, so the second one couldn't be indexed.
+    ConflictingIndexEntry {
+        /// The name of the index.
+        index: &'static str,
+        /// The key both blocks claim.
+        key: String,
+        /// The block indexed under `key`.
+        first: BlockHash,
+        /// The block which couldn't be indexed.
+        second: BlockHash,
+    },
+}
+
+impl StorageFault {
+    /// Converts an error about a duplicate index entry into a fault.
+    ///
+    /// Any other error is returned unchanged.
+    pub(super) fn try_from_index_error(
+        error: FatalStorageError,
+    ) -> Result<Self, FatalStorageError> {
+        match error {
+            FatalStorageError::DuplicateBlockIndex {
+                height,
+                first,
+                second,
+            } => Ok(StorageFault::ConflictingIndexEntry {
+                index: "block height",
+                key: height.to_string(),
+                first,
+                second,
+            }),
+            FatalStorageError::DuplicateEraIdIndex {
+                era_id,
+                first,
+                second,
+            } => Ok(StorageFault::ConflictingIndexEntry {
+                index: "switch block era id",
+                key: era_id.to_string(),
+                first,
+                second,
+            }),
+            FatalStorageError::DuplicateDeployIndex {
+                deploy_hash,
+                first,
+                second,
+            } => Ok(StorageFault::ConflictingIndexEntry {
+                index: "deploy hash",
+                key: deploy_hash.to_string(),
+                first: first.block_hash,
+                second: second.block_hash,
+            }),
+            error => Err(error),
+        }
+    }
+}
+
+impl Display for StorageFault {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageFault::UndecodableRecord { database, key } => {
+                write!(
+                    formatter,
+                    "undecodable record in {} under {}",
+                    database, key
+                )
+            }
+            StorageFault::BlockHeaderHashMismatch {
+                stored_under,
+                actual,
+            } => write!(
+                formatter,
+                "block header stored under {} hashes to {}",
+                stored_under, actual
+            ),
+            StorageFault::BlockBodyHashMismatch {
+                stored_under,
+                actual,
+            } => write!(
+                formatter,
+                "block body stored under {} hashes to {}",
+                stored_under, actual
+            ),
+            StorageFault::OrphanedBlockBody { body_hash } => {
+                write!(formatter, "block body {} has no block header", body_hash)
+            }
+            StorageFault::ParentMismatch {
+                height,
+                parent_hash,
+                stored_parent_hash,
+            } => write!(
+                formatter,
+                "block at height {} has parent {}, but the stored block at height {} is {}",
+                height,
+                parent_hash,
+                height.saturating_sub(1),
+                stored_parent_hash
+            ),
+            StorageFault::MisindexedSignatures {
+                block_hash,
+                signed_block_hash,
+                signed_era_id,
+            } => write!(
+                formatter,
+                "signatures stored for {} are for {} in {}",
+                block_hash, signed_block_hash, signed_era_id
+            ),
+            StorageFault::InvalidSignature {
+                block_hash,
+                public_key,
+            } => write!(
+                formatter,
+                "invalid finality signature by {} for {}",
+                public_key, block_hash
+            ),
+            StorageFault::UnknownSigner {
+                block_hash,
+                era_id,
+                public_key,
+            } => write!(
+                formatter,
+                "finality signature for {} by {} which is not a validator in {}",
+                block_hash, public_key, era_id
+            ),
+            StorageFault::CompletedBlockMissingHeader { height } => write!(
+                formatter,
+                "block at height {} is marked complete but has no header",
+                height
+            ),
+            StorageFault::CompletedBlockMissingBody { height, block_hash } => write!(
+                formatter,
+                "block {} at height {} is marked complete but has no body",
+                block_hash, height
+            ),
+            StorageFault::MissingDeploy {
+                block_hash,
+                deploy_hash,
+            } => write!(
+                formatter,
+                "deploy {} of complete block {} is missing",
+                deploy_hash, block_hash
+            ),
+            StorageFault::ConflictingIndexEntry {
+                index,
+                key,
+                first,
+                second,
+            } => write!(
+                formatter,
+                "{} is indexed under {} {}, so {} couldn't be indexed",
+                first, index, key, second
+            ),
+        }
+    }
+}
+
+/// The outcome of checking the integrity of storage.
+#[derive(Debug, Default)]
+pub(crate) struct IntegrityReport {
+    /// The number of block headers checked.
+    pub(crate) block_headers_checked: usize,
+    /// The number of block bodies checked.
+    pub(crate) block_bodies_checked: usize,
+    /// The number of finality signatures checked.
+    pub(crate) signatures_checked: usize,
+    /// The number of blocks whose signers couldn't be checked as the era's validators are unknown.
+    pub(crate) signer_checks_skipped: usize,
+    /// The global state roots of all complete blocks.
+    pub(crate) complete_state_roots: Vec<Digest>,
+    /// All faults found.
+    pub(crate) faults: Vec<StorageFault>,
+}
+
+impl Storage {
+    /// Checks the integrity of all blocks, block bodies, finality signatures and deploys in storage
+    /// and cross-checks them against the completed blocks index.
+    ///
+    /// Faults which prevented blocks from being indexed when opening the storage are included.
+    pub(crate) fn check_integrity(&self) -> Result<IntegrityReport, FatalStorageError> {
+        let mut report = IntegrityReport {
+            faults: self.indexing_faults.clone(),
+            ..Default::default()
+        };
+        let mut txn = self.env.begin_ro_txn()?;
+        let mut referenced_body_hashes = HashSet::new();
+        let mut validators_cache: BTreeMap<EraId, Option<BTreeMap<PublicKey, U512>>> =
+            BTreeMap::new();
+        let mut previous: Option<(u64, BlockHash)> = None;
+
+        for (&height, block_hash) in &self.block_height_index {
+            let block_header: BlockHeader = match txn.get(self.block_header_db, block_hash) {
+                Ok(raw) => match lmdb_ext::deserialize(raw) {
+                    Ok(block_header) => block_header,
+                    Err(_) => {
+                        report.faults.push(StorageFault::UndecodableRecord {
+                            database: "block_header",
+                            key: base16::encode_lower(block_hash),
+                        });
+                        continue;
+                    }
+                },
+                Err(lmdb::Error::NotFound) => {
+                    if self.completed_blocks.contains(height) {
+                        report
+                            .faults
+                            .push(StorageFault::CompletedBlockMissingHeader { height });
+                    }
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+            report.block_headers_checked += 1;
+
+            // The freshly deserialized header has no cached hash, so this re-hashes it.
+            let actual_hash = block_header.block_hash();
+            if actual_hash != *block_hash {
+                report.faults.push(StorageFault::BlockHeaderHashMismatch {
+                    stored_under: *block_hash,
+                    actual: actual_hash,
+                });
+            }
+
+            if let Some((previous_height, previous_hash)) = previous {
+                if previous_height + 1 == height && *block_header.parent_hash() != previous_hash {
+                    report.faults.push(StorageFault::ParentMismatch {
+                        height,
+                        parent_hash: *block_header.parent_hash(),
+                        stored_parent_hash: previous_hash,
+                    });
+                }
+            }
+            previous = Some((height, *block_hash));
+            referenced_body_hashes.insert(*block_header.body_hash());
+
+            let is_complete = self.completed_blocks.contains(height);
+            if is_complete {
+                report
+                    .complete_state_roots
+                    .push(*block_header.state_root_hash());
+                self.check_complete_block_body(&mut txn, &block_header, &mut report)?;
+            }
+
+            self.check_block_signatures(
+                &mut txn,
+                &block_header,
+                &mut validators_cache,
+                &mut report,
+            )?;
+        }
+
+        for sequence in self.completed_blocks.sequences() {
+            for height in sequence.low()..=sequence.high() {
+                if !self.block_height_index.contains_key(&height) {
+                    report
+                        .faults
+                        .push(StorageFault::CompletedBlockMissingHeader { height });
+                }
+            }
+        }
+
+        for row in txn.open_ro_cursor(self.block_body_db)?.iter() {
+            let (raw_key, raw_val) = row?;
+            let stored_under = match Digest::try_from(raw_key) {
+                Ok(digest) => digest,
+                Err(_) => {
+                    report.faults.push(StorageFault::UndecodableRecord {
+                        database: "block_body",
+                        key: base16::encode_lower(raw_key),
+                    });
+                    continue;
+                }
+            };
+            let block_body: BlockBody = match lmdb_ext::deserialize(raw_val) {
+                Ok(block_body) => block_body,
+                Err(_) => {
+                    report.faults.push(StorageFault::UndecodableRecord {
+                        database: "block_body",
+                        key: base16::encode_lower(raw_key),
+                    });
+                    continue;
+                }
+            };
+            report.block_bodies_checked += 1;
+
+            let actual = block_body.hash();
+            if actual != stored_under {
+                report.faults.push(StorageFault::BlockBodyHashMismatch {
+                    stored_under,
+                    actual,
+                });
+            }
+            if !referenced_body_hashes.contains(&stored_under) {
+                report.faults.push(StorageFault::OrphanedBlockBody {
+                    body_hash: stored_under,
+                });
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Checks that the body and all deploys of a complete block are present.
+    fn check_complete_block_body<Tx: Transaction>(
+        &self,
+        txn: &mut Tx,
+        block_header: &BlockHeader,
+        report: &mut IntegrityReport,
+    ) -> Result<(), FatalStorageError> {
+        let block_hash = block_header.block_hash();
+        let block_body: BlockBody = match txn.get(self.block_body_db, block_header.body_hash()) {
+            Ok(raw) => match lmdb_ext::deserialize(raw) {
+                Ok(block_body) => block_body,
+                // Reported when iterating the block body database.
+                Err(_) => return Ok(()),
+            },
+            Err(lmdb::Error::NotFound) => {
+                report.faults.push(StorageFault::CompletedBlockMissingBody {
+                    height: block_header.height(),
+                    block_hash,
+                });
+                return Ok(());
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        for deploy_hash in block_body.deploy_and_transfer_hashes() {
+            if !txn.value_exists(self.deploy_db, deploy_hash)? {
+                report.faults.push(StorageFault::MissingDeploy {
+                    block_hash,
+                    deploy_hash: *deploy_hash,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the finality signatures of a block are valid and were created by validators of
+    /// the block's era.
+    ///
+    /// The validators of an era are taken from the switch block of the previous era. If that isn't
+    /// stored (or the block is in the genesis era), only the signatures themselves are verified.
+    fn check_block_signatures<Tx: Transaction>(
+        &self,
+        txn: &mut Tx,
+        block_header: &BlockHeader,
+        validators_cache: &mut BTreeMap<EraId, Option<BTreeMap<PublicKey, U512>>>,
+        report: &mut IntegrityReport,
+    ) -> Result<(), FatalStorageError> {
+        let block_hash = block_header.block_hash();
+        let block_signatures: BlockSignatures = match txn.get(self.block_metadata_db, &block_hash) {
+            Ok(raw) => match lmdb_ext::deserialize(raw) {
+                Ok(block_signatures) => block_signatures,
+                Err(_) => {
+                    report.faults.push(StorageFault::UndecodableRecord {
+                        database: "block_metadata",
+                        key: base16::encode_lower(&block_hash),
+                    });
+                    return Ok(());
+                }
+            },
+            Err(lmdb::Error::NotFound) => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let era_id = block_header.era_id();
+        if block_signatures.block_hash != block_hash || block_signatures.era_id != era_id {
+            report.faults.push(StorageFault::MisindexedSignatures {
+                block_hash,
+                signed_block_hash: block_signatures.block_hash,
+                signed_era_id: block_signatures.era_id,
+            });
+            return Ok(());
+        }
+
+        let maybe_validators = match validators_cache.get(&era_id) {
+            Some(maybe_validators) => maybe_validators.clone(),
+            None => {
+                let maybe_validators = match era_id.predecessor() {
+                    Some(previous_era_id) => self
+                        .get_switch_block_header_by_era_id(txn, previous_era_id)?
+                        .and_then(BlockHeader::maybe_take_next_era_validator_weights),
+                    None => None,
+                };
+                validators_cache.insert(era_id, maybe_validators.clone());
+                maybe_validators
+            }
+        };
+        if maybe_validators.is_none() {
+            report.signer_checks_skipped += 1;
+        }
+
+        for finality_signature in block_signatures.finality_signatures() {
+            report.signatures_checked += 1;
+            if finality_signature.is_verified().is_err() {
+                report.faults.push(StorageFault::InvalidSignature {
+                    block_hash,
+                    public_key: finality_signature.public_key.clone(),
+                });
+            }
+            if let Some(validators) = maybe_validators.as_ref() {
+                if !validators.contains_key(&finality_signature.public_key) {
+                    report.faults.push(StorageFault::UnknownSigner {
+                        block_hash,
+                        era_id,
+                        public_key: finality_signature.public_key,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+//! Index of the historical values of selected global state keys.
+//!
+//! Records are keyed by the serialized `Key` followed by the big-endian block height, so all
+//! records of a single key are adjacent and ordered by height.
+
+use std::mem;
+
+use datasize::DataSize;
+use lmdb::{Cursor, Transaction};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use casper_hashing::Digest;
+use casper_types::{bytesrepr::ToBytes, Key};
+
+use super::{lmdb_ext, FatalStorageError, Storage, WriteTransactionExt};
+use crate::types::BlockHash;
+
+/// A change of the value under a global state key, as recorded in the key history index.
+#[derive(Clone, DataSize, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct KeyHistoryEntry {
+    /// The height of the block which wrote the key.
+    pub block_height: u64,
+    /// The hash of the block which wrote the key.
+    pub block_hash: BlockHash,
+    /// The hash of the serialized value under the key after the block, or `None` if there is no
+    /// value under the key anymore.
+    pub value_hash: Option<Digest>,
+}
+
+impl Storage {
+    /// Records the values written to the given keys by the block at `block_height`.
+    pub(super) fn put_key_history(
+        &mut self,
+        block_height: u64,
+        entries: &[(Key, Option<Digest>)],
+    ) -> Result<(), FatalStorageError> {
+        let db = match self.key_history_db {
+            Some(db) => db,
+            None => return Ok(()),
+        };
+        let mut txn = self.env.begin_rw_txn()?;
+        for (key, value_hash) in entries {
+            let db_key = key_history_db_key(key, block_height)?;
+            let _ = txn.put_value(db, &db_key, value_hash, true)?;
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Returns up to `limit` recorded changes of the value under `key`, in order of ascending block
+    /// height and starting at `from_height`.
+    pub(super) fn get_key_history(
+        &self,
+        key: &Key,
+        from_height: u64,
+        limit: usize,
+    ) -> Result<Vec<KeyHistoryEntry>, FatalStorageError> {
+        let db = match self.key_history_db {
+            Some(db) => db,
+            None => return Ok(Vec::new()),
+        };
+        let prefix = key
+            .to_bytes()
+            .map_err(FatalStorageError::UnexpectedSerializationFailure)?;
+        let start = key_history_db_key(key, from_height)?;
+
+        let mut txn = self.env.begin_ro_txn()?;
+        let mut entries = Vec::new();
+        {
+            let mut cursor = txn.open_ro_cursor(db)?;
+            for row in cursor.iter_from(&start) {
+                let (raw_key, raw_value) = row?;
+                if entries.len() >= limit || !raw_key.starts_with(&prefix) {
+                    break;
+                }
+                let mut height_bytes = [0; mem::size_of::<u64>()];
+                height_bytes.copy_from_slice(&raw_key[prefix.len()..]);
+                let block_height = u64::from_be_bytes(height_bytes);
+                // Records of blocks which have since been removed from storage are skipped, and
+                // don't count towards the limit.
+                let block_hash = match self.block_height_index.get(&block_height) {
+                    Some(block_hash) => *block_hash,
+                    None => continue,
+                };
+                let value_hash: Option<Digest> = lmdb_ext::deserialize(raw_value)?;
+                entries.push(KeyHistoryEntry {
+                    block_height,
+                    block_hash,
+                    value_hash,
+                });
+            }
+        }
+        txn.commit()?;
+        Ok(entries)
+    }
+}
+
+/// Returns the database key of the record for `key` written by the block at `block_height`.
+fn key_history_db_key(key: &Key, block_height: u64) -> Result<Vec<u8>, FatalStorageError> {
+    let mut db_key = key
+        .to_bytes()
+        .map_err(FatalStorageError::UnexpectedSerializationFailure)?;
+    db_key.extend_from_slice(&block_height.to_be_bytes());
+    Ok(db_key)
+}
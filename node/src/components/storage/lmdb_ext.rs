@@ -9,6 +9,12 @@
 //!
 //! Serialization errors are unified into a generic, type erased `std` error to allow for easy
 //! interchange of the serialization format if desired.
+//!
+//! ## Compression
+//!
+//! Values can optionally be stored zstd-compressed. Compressed records are prefixed with a marker,
+//! which allows compressed and uncompressed records to coexist in the same database: reading a
+//! value transparently decompresses it if the marker is present.
 
 use std::any::TypeId;
 
@@ -23,6 +29,13 @@ use casper_types::{
 
 const UNBONDING_PURSE_V2_MAGIC_BYTES: &[u8] = &[121, 17, 133, 179, 91, 63, 69, 222];
 
+/// Marker prefixed to zstd-compressed records.
+///
+/// Nothing prevents an uncompressed record from starting with the same bytes, so a record carrying
+/// the marker is only treated as compressed if it decompresses and deserializes successfully;
+/// otherwise it is read as an uncompressed record.
+pub(super) const ZSTD_COMPRESSED_MAGIC_BYTES: &[u8] = &[226, 47, 150, 12, 178, 93, 201, 244];
+
 /// Error wrapper for lower-level storage errors.
 ///
 /// Used to classify storage errors, allowing more accurate reporting on potential issues and
@@ -105,6 +118,15 @@ pub(super) trait TransactionExt {
     ) -> Result<Option<V>, LmdbExtError>;
 }
 
+/// Sizes of a value written by [`WriteTransactionExt::put_value_compressed`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct CompressedSizes {
+    /// The size of the serialized value before compression.
+    pub(super) uncompressed: usize,
+    /// The size of the record actually stored.
+    pub(super) stored: usize,
+}
+
 /// Additional methods on write transactions.
 pub(super) trait WriteTransactionExt {
     /// Helper function to write a value to a database.
@@ -120,6 +142,24 @@ pub(super) trait WriteTransactionExt {
         overwrite: bool,
     ) -> Result<bool, LmdbExtError>;
 
+    /// Helper function to write a value to a database, compressed using zstd at the given level.
+    ///
+    /// If compressing does not reduce the size of the serialized value, it is stored uncompressed.
+    /// Either way, the value can be read back using [`TransactionExt::get_value`].
+    ///
+    /// Returns `true` if the value has actually been written, `false` if the key already existed,
+    /// along with the sizes of the value before and after compression.
+    ///
+    /// Setting `overwrite` to true will cause the value to always be written instead.
+    fn put_value_compressed<K: AsRef<[u8]>, V: 'static + Serialize>(
+        &mut self,
+        db: Database,
+        key: &K,
+        value: &V,
+        overwrite: bool,
+        level: i32,
+    ) -> Result<(bool, CompressedSizes), LmdbExtError>;
+
     /// Helper function to write a value to a database using the `bytesrepr` `ToBytes`/`FromBytes`
     /// serialization.
     ///
@@ -198,16 +238,30 @@ pub(crate) fn serialize_internal<V: 'static + Serialize>(
 }
 
 /// Deserializes an object from the raw bytes.
+/// If the raw bytes are a compressed record, they are decompressed first.
 /// In case the expected object is of the `UnbondingPurse` type it uses the specialized
 /// function to provide compatibility with the legacy version of the `UnbondingPurse` struct.
 /// See [`deserialize_unbonding_purse`] for more details.
 pub(crate) fn deserialize_internal<V: 'static + DeserializeOwned>(
     raw: &[u8],
 ) -> Result<Option<V>, LmdbExtError> {
+    if is_compressed(raw) {
+        let decompressed = decompress(raw).and_then(|serialized| deserialize_value(&serialized));
+        return match decompressed {
+            Ok(value) => Ok(Some(value)),
+            // The record might be an uncompressed one which merely starts with the marker.
+            Err(error) => deserialize_value(raw).map(Some).map_err(|_| error),
+        };
+    }
+    deserialize_value(raw).map(Some)
+}
+
+/// Deserializes an uncompressed object, using the specialized function for `UnbondingPurse`.
+fn deserialize_value<V: 'static + DeserializeOwned>(raw: &[u8]) -> Result<V, LmdbExtError> {
     if TypeId::of::<UnbondingPurse>() == TypeId::of::<V>() {
-        deserialize_unbonding_purse(raw).map(Some)
+        deserialize_unbonding_purse(raw)
     } else {
-        deserialize(raw).map(Some)
+        deserialize(raw)
     }
 }
 
@@ -235,6 +289,41 @@ impl WriteTransactionExt for RwTransaction<'_> {
         }
     }
 
+    fn put_value_compressed<K: AsRef<[u8]>, V: 'static + Serialize>(
+        &mut self,
+        db: Database,
+        key: &K,
+        value: &V,
+        overwrite: bool,
+        level: i32,
+    ) -> Result<(bool, CompressedSizes), LmdbExtError> {
+        let serialized = serialize_internal(value)?;
+        let uncompressed = serialized.len();
+        let compressed = compress(&serialized, level)?;
+        let buffer = if compressed.len() < uncompressed {
+            compressed
+        } else {
+            serialized
+        };
+        let sizes = CompressedSizes {
+            uncompressed,
+            stored: buffer.len(),
+        };
+
+        let flags = if overwrite {
+            WriteFlags::empty()
+        } else {
+            WriteFlags::NO_OVERWRITE
+        };
+
+        match self.put(db, key, &buffer, flags) {
+            Ok(()) => Ok((true, sizes)),
+            // If we did not add the value due to it already existing, just return `false`.
+            Err(lmdb::Error::KeyExist) => Ok((false, sizes)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
     fn put_value_bytesrepr<K: AsRef<[u8]>, V: ToBytes>(
         &mut self,
         db: Database,
@@ -306,6 +395,28 @@ pub(super) fn serialize_unbonding_purse<T: Serialize>(value: &T) -> Result<Vec<u
     Ok(serialized)
 }
 
+/// Returns `true` if the specified bytes carry the marker of a compressed record.
+#[inline(always)]
+pub(super) fn is_compressed(raw: &[u8]) -> bool {
+    raw.starts_with(ZSTD_COMPRESSED_MAGIC_BYTES)
+}
+
+/// Compresses an already serialized value using zstd at the given level.
+///
+/// The result is prefixed with the "magic bytes" marking it as a compressed record.
+pub(super) fn compress(serialized: &[u8], level: i32) -> Result<Vec<u8>, LmdbExtError> {
+    let mut compressed = ZSTD_COMPRESSED_MAGIC_BYTES.to_vec();
+    zstd::stream::copy_encode(serialized, &mut compressed, level)
+        .map_err(|err| LmdbExtError::Other(Box::new(err)))?;
+    Ok(compressed)
+}
+
+/// Decompresses a compressed record, returning the serialized value.
+fn decompress(raw: &[u8]) -> Result<Vec<u8>, LmdbExtError> {
+    zstd::stream::decode_all(&raw[ZSTD_COMPRESSED_MAGIC_BYTES.len()..])
+        .map_err(|err| LmdbExtError::DataCorrupted(Box::new(err)))
+}
+
 /// Deserializes from a buffer.
 #[inline(always)]
 pub(super) fn deserialize_bytesrepr<T: FromBytes>(raw: &[u8]) -> Result<T, LmdbExtError> {
@@ -1,5 +1,6 @@
-use prometheus::{self, IntGauge, Registry};
+use prometheus::{self, Gauge, IntCounter, IntGauge, Registry};
 
+use super::lmdb_ext::CompressedSizes;
 use crate::unregister_metric;
 
 const CHAIN_HEIGHT_NAME: &str = "chain_height";
@@ -13,6 +14,18 @@ const LOWEST_AVAILABLE_BLOCK_NAME: &str = "lowest_available_block_height";
 const LOWEST_AVAILABLE_BLOCK_HELP: &str =
     "lowest height of the available block range (the highest contiguous chain of complete blocks)";
 
+const DEPLOY_UNCOMPRESSED_BYTES_NAME: &str = "storage_deploy_uncompressed_bytes";
+const DEPLOY_UNCOMPRESSED_BYTES_HELP: &str =
+    "total size of compressed deploy and deploy metadata records written, before compression";
+
+const DEPLOY_COMPRESSED_BYTES_NAME: &str = "storage_deploy_compressed_bytes";
+const DEPLOY_COMPRESSED_BYTES_HELP: &str =
+    "total size of compressed deploy and deploy metadata records written, after compression";
+
+const DEPLOY_COMPRESSION_RATIO_NAME: &str = "storage_deploy_compression_ratio";
+const DEPLOY_COMPRESSION_RATIO_HELP: &str =
+    "ratio of compressed to uncompressed size of all deploy and deploy metadata records written";
+
 /// Metrics for the storage component.
 #[derive(Debug)]
 pub struct Metrics {
@@ -20,6 +33,9 @@ pub struct Metrics {
     pub(super) chain_height: IntGauge,
     pub(super) highest_available_block: IntGauge,
     pub(super) lowest_available_block: IntGauge,
+    deploy_uncompressed_bytes: IntCounter,
+    deploy_compressed_bytes: IntCounter,
+    deploy_compression_ratio: Gauge,
     registry: Registry,
 }
 
@@ -31,18 +47,45 @@ impl Metrics {
             IntGauge::new(HIGHEST_AVAILABLE_BLOCK_NAME, HIGHEST_AVAILABLE_BLOCK_HELP)?;
         let lowest_available_block =
             IntGauge::new(LOWEST_AVAILABLE_BLOCK_NAME, LOWEST_AVAILABLE_BLOCK_HELP)?;
+        let deploy_uncompressed_bytes = IntCounter::new(
+            DEPLOY_UNCOMPRESSED_BYTES_NAME,
+            DEPLOY_UNCOMPRESSED_BYTES_HELP,
+        )?;
+        let deploy_compressed_bytes =
+            IntCounter::new(DEPLOY_COMPRESSED_BYTES_NAME, DEPLOY_COMPRESSED_BYTES_HELP)?;
+        let deploy_compression_ratio =
+            Gauge::new(DEPLOY_COMPRESSION_RATIO_NAME, DEPLOY_COMPRESSION_RATIO_HELP)?;
 
         registry.register(Box::new(chain_height.clone()))?;
         registry.register(Box::new(highest_available_block.clone()))?;
         registry.register(Box::new(lowest_available_block.clone()))?;
+        registry.register(Box::new(deploy_uncompressed_bytes.clone()))?;
+        registry.register(Box::new(deploy_compressed_bytes.clone()))?;
+        registry.register(Box::new(deploy_compression_ratio.clone()))?;
 
         Ok(Metrics {
             chain_height,
             highest_available_block,
             lowest_available_block,
+            deploy_uncompressed_bytes,
+            deploy_compressed_bytes,
+            deploy_compression_ratio,
             registry: registry.clone(),
         })
     }
+
+    /// Records the sizes of a compressed record written to storage.
+    pub(super) fn record_compression(&self, sizes: CompressedSizes) {
+        self.deploy_uncompressed_bytes
+            .inc_by(sizes.uncompressed as u64);
+        self.deploy_compressed_bytes.inc_by(sizes.stored as u64);
+
+        let uncompressed = self.deploy_uncompressed_bytes.get();
+        if uncompressed > 0 {
+            self.deploy_compression_ratio
+                .set(self.deploy_compressed_bytes.get() as f64 / uncompressed as f64);
+        }
+    }
 }
 
 impl Drop for Metrics {
@@ -50,5 +93,8 @@ impl Drop for Metrics {
         unregister_metric!(self.registry, self.chain_height);
         unregister_metric!(self.registry, self.highest_available_block);
         unregister_metric!(self.registry, self.lowest_available_block);
+        unregister_metric!(self.registry, self.deploy_uncompressed_bytes);
+        unregister_metric!(self.registry, self.deploy_compressed_bytes);
+        unregister_metric!(self.registry, self.deploy_compression_ratio);
     }
 }
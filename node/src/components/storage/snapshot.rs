@@ -0,0 +1,167 @@
+//! Consistent snapshots of the storage databases.
+
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use lmdb::{
+    Cursor, Database, DatabaseFlags, Environment, EnvironmentFlags, RoTransaction, Transaction,
+    WriteFlags,
+};
+use thiserror::Error;
+use tokio::task::JoinError;
+
+use super::{FatalStorageError, Storage, MAX_DB_COUNT, MAX_TRANSACTIONS, STORAGE_DB_FILENAME};
+use crate::types::BlockHeader;
+
+/// Number of records written per transaction when copying a database.
+const COPY_BATCH_SIZE: usize = 100_000;
+
+/// Error taking a snapshot of the storage.
+#[derive(Debug, Error)]
+pub(crate) enum SnapshotError {
+    /// There is no complete block yet to take the snapshot at.
+    #[error("no complete block in storage")]
+    NoCompleteBlock,
+    /// Another snapshot is still being taken.
+    #[error("another snapshot is already in progress")]
+    InProgress,
+    /// The snapshot would overwrite an existing database.
+    #[error("snapshot target {} already exists", .0.display())]
+    TargetExists(PathBuf),
+    /// Error reading the highest complete block.
+    #[error(transparent)]
+    Storage(#[from] FatalStorageError),
+    /// Error reading or copying the databases.
+    #[error("failed to copy databases: {0}")]
+    Lmdb(#[from] lmdb::Error),
+    /// The task copying the databases failed.
+    #[error("snapshot task failed: {0}")]
+    Task(#[from] JoinError),
+}
+
+impl Storage {
+    /// Returns a task copying all databases into a new environment in `dir`.
+    ///
+    /// The copy is taken within a single read transaction started by the task, so it is
+    /// consistent, and contains at least everything up to and including the highest block which
+    /// is complete at the time of this call. The task returns that block's header.
+    ///
+    /// As the task only reads from the databases, it can be run on a separate thread while the
+    /// component keeps on handling events. Only live records are copied, so the copy is compacted.
+    ///
+    /// Only one snapshot can be in progress at a time, as its long-lived read transaction occupies
+    /// the only reader slot not needed by the component itself. The slot is released when the task
+    /// has run or is dropped.
+    pub(super) fn snapshot_task(
+        &self,
+        dir: PathBuf,
+    ) -> Result<impl FnOnce() -> Result<BlockHeader, SnapshotError> + Send + 'static, SnapshotError>
+    {
+        let block_header = {
+            let mut txn = self.env.begin_ro_txn()?;
+            let maybe_block_header = self.get_highest_complete_block_header(&mut txn)?;
+            txn.commit()?;
+            maybe_block_header.ok_or(SnapshotError::NoCompleteBlock)?
+        };
+
+        let target = dir.join(STORAGE_DB_FILENAME);
+        if target.exists() {
+            return Err(SnapshotError::TargetExists(target));
+        }
+        let guard = SnapshotGuard::acquire(&self.snapshot_in_progress)?;
+
+        let env = Arc::clone(&self.env);
+        let databases = self.databases();
+        Ok(move || {
+            let _guard = guard;
+            let map_size = env.info()?.map_size();
+            let destination = Environment::new()
+                .set_flags(
+                    EnvironmentFlags::NO_SUB_DIR
+                        | EnvironmentFlags::NO_TLS
+                        | EnvironmentFlags::NO_READAHEAD,
+                )
+                .set_max_readers(MAX_TRANSACTIONS)
+                .set_max_dbs(MAX_DB_COUNT)
+                .set_map_size(map_size)
+                .open(&target)?;
+
+            let txn = env.begin_ro_txn()?;
+            for (name, db) in databases {
+                let destination_db = destination.create_db(Some(name), DatabaseFlags::empty())?;
+                copy_database(&txn, db, &destination, destination_db)?;
+            }
+            txn.commit()?;
+            destination.sync(true)?;
+
+            Ok(block_header)
+        })
+    }
+
+    /// Returns the names and handles of all databases.
+    fn databases(&self) -> Vec<(&'static str, Database)> {
+        let mut databases = vec![
+            ("block_header", self.block_header_db),
+            ("block_metadata", self.block_metadata_db),
+            ("deploys", self.deploy_db),
+            ("deploy_metadata", self.deploy_metadata_db),
+            ("transfer", self.transfer_db),
+            ("state_store", self.state_store_db),
+            ("finalized_approvals", self.finalized_approvals_db),
+            ("block_body", self.block_body_db),
+            ("approvals_hashes", self.approvals_hashes_db),
+        ];
+        if let Some(key_history_db) = self.key_history_db {
+            databases.push(("key_history", key_history_db));
+        }
+        if let Some(validator_performance_db) = self.validator_performance_db {
+            databases.push(("validator_performance", validator_performance_db));
+        }
+        databases
+    }
+}
+
+/// Marks a snapshot as in progress until dropped.
+struct SnapshotGuard(Arc<AtomicBool>);
+
+impl SnapshotGuard {
+    /// Marks a snapshot as in progress, or fails if another one already is.
+    fn acquire(in_progress: &Arc<AtomicBool>) -> Result<Self, SnapshotError> {
+        if in_progress.swap(true, Ordering::SeqCst) {
+            return Err(SnapshotError::InProgress);
+        }
+        Ok(SnapshotGuard(Arc::clone(in_progress)))
+    }
+}
+
+impl Drop for SnapshotGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Copies all records of `db` as seen by `txn` into `destination_db`.
+fn copy_database(
+    txn: &RoTransaction,
+    db: Database,
+    destination: &Environment,
+    destination_db: Database,
+) -> Result<(), lmdb::Error> {
+    let mut cursor = txn.open_ro_cursor(db)?;
+    let mut destination_txn = destination.begin_rw_txn()?;
+    for (copied, row) in cursor.iter().enumerate() {
+        let (key, value) = row?;
+        // Both databases use the same key ordering, so we can append.
+        destination_txn.put(destination_db, &key, &value, WriteFlags::APPEND)?;
+        if (copied + 1) % COPY_BATCH_SIZE == 0 {
+            destination_txn.commit()?;
+            destination_txn = destination.begin_rw_txn()?;
+        }
+    }
+    destination_txn.commit()
+}
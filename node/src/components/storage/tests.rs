@@ -8,27 +8,35 @@ use std::{
     sync::Arc,
 };
 
+use lmdb::{Transaction, WriteFlags};
 use rand::{prelude::SliceRandom, Rng};
 use serde::{Deserialize, Serialize};
 use smallvec::smallvec;
 
+use casper_hashing::Digest;
 use casper_types::{
     generate_ed25519_keypair, system::auction::UnbondingPurse, testing::TestRng, AccessRights,
-    EraId, ExecutionResult, ProtocolVersion, PublicKey, SecretKey, TimeDiff, URef, U512,
+    EraId, ExecutionResult, Key, ProtocolVersion, PublicKey, SecretKey, TimeDiff, URef, U512,
 };
 
 use super::{
     move_storage_files_to_network_subdir, should_move_storage_files_to_network_subdir, Config,
-    Storage,
+    IntegrityReport, KeyHistoryEntry, SnapshotError, Storage, StorageFault,
 };
 use crate::{
-    components::fetcher::{FetchItem, FetchResponse},
+    components::{
+        consensus::ValidatorPerformance,
+        fetcher::{FetchItem, FetchResponse},
+    },
     effect::{
         requests::{MarkBlockCompletedRequest, StorageRequest},
         Multiple,
     },
     storage::{
-        lmdb_ext::{deserialize_internal, serialize_internal},
+        lmdb_ext::{
+            compress, deserialize_internal, is_compressed, serialize_internal,
+            ZSTD_COMPRESSED_MAGIC_BYTES,
+        },
         FORCE_RESYNC_FILE_NAME,
     },
     testing::{ComponentHarness, UnitTestEvent},
@@ -56,6 +64,8 @@ fn new_config(harness: &ComponentHarness<UnitTestEvent>) -> Config {
         max_state_store_size: 50 * MIB,
         enable_mem_deduplication: true,
         mem_pool_prune_interval: 4,
+        enable_deploy_compression: false,
+        deploy_compression_level: 3,
     }
 }
 
@@ -1849,3 +1859,423 @@ fn unbonding_purse_serialization_roundtrip() {
     // Explicitly assert that the `new_validator` is not `None`
     assert!(deserialized.new_validator().is_some())
 }
+
+#[test]
+fn compressed_record_roundtrip() {
+    let mut rng = TestRng::new();
+    let deploy = Deploy::random(&mut rng);
+
+    let serialized = serialize_internal(&deploy).expect("serialization");
+    assert!(!is_compressed(&serialized));
+    let compressed = compress(&serialized, 3).expect("compression");
+    assert!(is_compressed(&compressed));
+
+    // Both the compressed and the uncompressed record should deserialize to the same value.
+    for raw in [&serialized, &compressed] {
+        let deserialized: Deploy = deserialize_internal(raw)
+            .expect("should deserialize w/o error")
+            .expect("should be Some");
+        assert_eq!(deploy, deserialized);
+    }
+
+    // Values using their own format marker should survive compression too.
+    let unbonding_purse = UnbondingPurse::new(
+        URef::new([14; 32], AccessRights::READ_ADD_WRITE),
+        PublicKey::random(&mut rng),
+        PublicKey::random(&mut rng),
+        EraId::new(rng.gen()),
+        U512::from(rng.gen::<u64>()),
+        Some(PublicKey::random(&mut rng)),
+    );
+    let serialized = serialize_internal(&unbonding_purse).expect("serialization");
+    let compressed = compress(&serialized, 3).expect("compression");
+    let deserialized: UnbondingPurse = deserialize_internal(&compressed)
+        .expect("should deserialize w/o error")
+        .expect("should be Some");
+    assert_eq!(unbonding_purse, deserialized);
+}
+
+#[test]
+fn should_read_uncompressed_record_starting_like_compressed_one() {
+    // Fixed-size arrays are serialized without a length prefix, so the record starts with the
+    // compression marker.
+    let mut value = [7_u8; 16];
+    value[..ZSTD_COMPRESSED_MAGIC_BYTES.len()].copy_from_slice(ZSTD_COMPRESSED_MAGIC_BYTES);
+
+    let serialized = serialize_internal(&value).expect("serialization");
+    assert!(is_compressed(&serialized));
+    let deserialized: [u8; 16] = deserialize_internal(&serialized)
+        .expect("should deserialize w/o error")
+        .expect("should be Some");
+    assert_eq!(value, deserialized);
+}
+
+/// Returns the faults of the report, except for missing deploys.
+///
+/// The blocks of the sync leap test chain reference random deploys which are never stored.
+fn faults_other_than_missing_deploys(report: &IntegrityReport) -> Vec<StorageFault> {
+    report
+        .faults
+        .iter()
+        .filter(|fault| !matches!(fault, StorageFault::MissingDeploy { .. }))
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn should_check_integrity_of_consistent_storage() {
+    let (storage, _, blocks) = create_sync_leap_test_chain(&[], false, None);
+
+    let report = storage.check_integrity().unwrap();
+    assert!(faults_other_than_missing_deploys(&report).is_empty());
+    let deploy_count: usize = blocks
+        .iter()
+        .map(|block| block.deploy_and_transfer_hashes().count())
+        .sum();
+    assert_eq!(report.faults.len(), deploy_count);
+    assert_eq!(report.block_headers_checked, blocks.len());
+    assert_eq!(report.block_bodies_checked, blocks.len());
+    assert_eq!(report.complete_state_roots.len(), blocks.len());
+}
+
+#[test]
+fn should_find_missing_deploy_of_complete_block() {
+    let mut harness = ComponentHarness::default();
+    let mut storage = storage_fixture(&harness);
+
+    let deploy = Deploy::random(&mut harness.rng);
+    let block = Block::random_with_deploys(&mut harness.rng, iter::once(&deploy));
+    storage.write_block(&block).unwrap();
+    storage.mark_block_complete(block.height()).unwrap();
+
+    let report = storage.check_integrity().unwrap();
+    assert_eq!(
+        report.faults,
+        vec![StorageFault::MissingDeploy {
+            block_hash: *block.hash(),
+            deploy_hash: *deploy.hash(),
+        }]
+    );
+
+    storage.put_deploy(&deploy).unwrap();
+    let report = storage.check_integrity().unwrap();
+    assert!(report.faults.is_empty(), "{:?}", report.faults);
+}
+
+#[test]
+fn should_find_signature_by_non_validator() {
+    let (mut storage, _, blocks) = create_sync_leap_test_chain(&[], false, None);
+    let block = &blocks[5];
+
+    let (secret_key, public_key) = generate_ed25519_keypair();
+    let finality_signature = FinalitySignature::create(
+        *block.hash(),
+        block.header().era_id(),
+        &secret_key,
+        public_key.clone(),
+    );
+    let mut block_signatures = storage
+        .read_block_signatures(block.hash())
+        .unwrap()
+        .unwrap();
+    block_signatures.insert_proof(public_key.clone(), finality_signature.signature);
+    storage
+        .write_finality_signatures(&block_signatures)
+        .unwrap();
+
+    let report = storage.check_integrity().unwrap();
+    assert_eq!(
+        faults_other_than_missing_deploys(&report),
+        vec![StorageFault::UnknownSigner {
+            block_hash: *block.hash(),
+            era_id: block.header().era_id(),
+            public_key,
+        }]
+    );
+}
+
+#[test]
+fn should_report_blocks_which_cannot_be_indexed_when_opened_read_only() {
+    let mut harness = ComponentHarness::default();
+    let block = Block::random_with_specifics(
+        &mut harness.rng,
+        EraId::new(1),
+        7,
+        ProtocolVersion::from_parts(1, 0, 0),
+        false,
+        None,
+    );
+    let conflicting_block = Block::random_with_specifics(
+        &mut harness.rng,
+        EraId::new(1),
+        7,
+        ProtocolVersion::from_parts(1, 0, 0),
+        false,
+        None,
+    );
+    let undecodable_key = [0xAB; Digest::LENGTH];
+
+    {
+        let mut storage = storage_fixture(&harness);
+        storage.write_block(&block).unwrap();
+        // Bypass the indices, which would reject the conflicting block.
+        let mut txn = storage.env.begin_rw_txn().unwrap();
+        txn.put(
+            storage.block_header_db,
+            conflicting_block.hash(),
+            &bincode::serialize(conflicting_block.header()).unwrap(),
+            WriteFlags::empty(),
+        )
+        .unwrap();
+        txn.put(
+            storage.block_header_db,
+            &undecodable_key,
+            &[0xFF],
+            WriteFlags::empty(),
+        )
+        .unwrap();
+        txn.commit().unwrap();
+    }
+
+    let storage = Storage::open_read_only(
+        &WithDir::new(harness.tmp.path(), new_config(&harness)),
+        "test",
+        MAX_TTL.into(),
+        RECENT_ERA_COUNT,
+        None,
+    )
+    .unwrap();
+    let faults = storage.check_integrity().unwrap().faults;
+    assert!(faults.contains(&StorageFault::UndecodableRecord {
+        database: "block_header",
+        key: base16::encode_lower(&undecodable_key),
+    }));
+    // Which of the two blocks is indexed depends on the order of their hashes.
+    assert!(faults.iter().any(|fault| matches!(
+        fault,
+        StorageFault::ConflictingIndexEntry { index: "block height", key, .. } if key == "7"
+    )));
+}
+
+#[test]
+fn should_read_deploys_stored_before_and_after_enabling_compression() {
+    let harness = ComponentHarness::default();
+    let mut rng = TestRng::new();
+    let uncompressed_deploy = Deploy::random(&mut rng);
+    let compressed_deploy = Deploy::random(&mut rng);
+
+    {
+        let storage = storage_fixture(&harness);
+        assert!(storage.put_deploy(&uncompressed_deploy).unwrap());
+        // Without compression enabled, the migration is a no-op.
+        assert_eq!(storage.compress_deploy_records().unwrap(), 0);
+    }
+
+    let cfg = Config {
+        enable_deploy_compression: true,
+        ..new_config(&harness)
+    };
+    let storage = Storage::new(
+        &WithDir::new(harness.tmp.path(), cfg),
+        None,
+        ProtocolVersion::from_parts(1, 0, 0),
+        EraId::default(),
+        "test",
+        MAX_TTL.into(),
+        RECENT_ERA_COUNT,
+        None,
+        false,
+    )
+    .expect("could not create storage component fixture");
+    assert!(storage.put_deploy(&compressed_deploy).unwrap());
+
+    // Records written with and without compression should coexist.
+    for deploy in [&uncompressed_deploy, &compressed_deploy] {
+        assert_eq!(
+            storage.get_deploy_by_hash(*deploy.hash()).as_ref(),
+            Some(deploy)
+        );
+    }
+
+    // Compressing the existing records must not change their values and only needs to be done
+    // once.
+    storage.compress_deploy_records().unwrap();
+    assert_eq!(storage.compress_deploy_records().unwrap(), 0);
+    for deploy in [&uncompressed_deploy, &compressed_deploy] {
+        assert_eq!(
+            storage.get_deploy_by_hash(*deploy.hash()).as_ref(),
+            Some(deploy)
+        );
+    }
+}
+
+#[test]
+fn should_take_snapshot_of_highest_complete_block() {
+    let (mut storage, _, blocks) = create_sync_leap_test_chain(&[], false, None);
+    storage.persist_completed_blocks().unwrap();
+    let highest_block = blocks.last().unwrap();
+
+    let snapshot_root = tempfile::tempdir().unwrap();
+    let dir = snapshot_root.path().join("snapshot");
+    fs::create_dir(&dir).unwrap();
+    let task = storage.snapshot_task(dir.clone()).unwrap();
+    let block_header = task().unwrap();
+    assert_eq!(block_header, *highest_block.header());
+
+    let config = Config {
+        path: snapshot_root.path().to_owned(),
+        ..new_config(&ComponentHarness::default())
+    };
+    let snapshot = Storage::open_read_only(
+        &WithDir::new(snapshot_root.path(), config),
+        "snapshot",
+        MAX_TTL.into(),
+        RECENT_ERA_COUNT,
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        snapshot.read_highest_complete_block().unwrap().as_ref(),
+        Some(highest_block)
+    );
+    assert!(faults_other_than_missing_deploys(&snapshot.check_integrity().unwrap()).is_empty());
+
+    // Snapshots never overwrite existing databases.
+    assert!(matches!(
+        storage.snapshot_task(dir),
+        Err(SnapshotError::TargetExists(_))
+    ));
+}
+
+#[test]
+fn should_reject_concurrent_snapshots() {
+    let (mut storage, _, _) = create_sync_leap_test_chain(&[], false, None);
+    storage.persist_completed_blocks().unwrap();
+
+    let snapshot_root = tempfile::tempdir().unwrap();
+    let first_dir = snapshot_root.path().join("first");
+    let second_dir = snapshot_root.path().join("second");
+    fs::create_dir(&first_dir).unwrap();
+    fs::create_dir(&second_dir).unwrap();
+
+    // While the first snapshot holds the spare reader slot, a second one is rejected, and the
+    // component can still read.
+    let first_task = storage.snapshot_task(first_dir).unwrap();
+    assert!(matches!(
+        storage.snapshot_task(second_dir.clone()),
+        Err(SnapshotError::InProgress)
+    ));
+    assert!(storage.read_highest_complete_block().unwrap().is_some());
+
+    // Once the first snapshot is done, or its task is dropped without running, the next one can
+    // be taken.
+    first_task().unwrap();
+    let second_task = storage.snapshot_task(second_dir).unwrap();
+    drop(second_task);
+    let third_dir = snapshot_root.path().join("third");
+    fs::create_dir(&third_dir).unwrap();
+    assert!(storage.snapshot_task(third_dir).is_ok());
+}
+
+#[test]
+fn should_record_key_history() {
+    let (mut storage, _, blocks) = create_sync_leap_test_chain(&[], false, None);
+    let mut rng = TestRng::new();
+
+    let key = Key::Balance(rng.gen());
+    let other_key = Key::Balance(rng.gen());
+    let value_hash = |value: u64| Some(Digest::hash(value.to_le_bytes()));
+
+    storage
+        .put_key_history(2, &[(key, value_hash(1)), (other_key, value_hash(2))])
+        .unwrap();
+    storage.put_key_history(5, &[(key, value_hash(3))]).unwrap();
+    storage.put_key_history(9, &[(key, None)]).unwrap();
+    // Records of heights without a stored block are skipped.
+    storage
+        .put_key_history(100, &[(key, value_hash(4))])
+        .unwrap();
+
+    let entry = |height: u64, value_hash: Option<Digest>| KeyHistoryEntry {
+        block_height: height,
+        block_hash: *blocks[height as usize].hash(),
+        value_hash,
+    };
+    assert_eq!(
+        storage.get_key_history(&key, 0, 10).unwrap(),
+        vec![
+            entry(2, value_hash(1)),
+            entry(5, value_hash(3)),
+            entry(9, None)
+        ]
+    );
+    assert_eq!(
+        storage.get_key_history(&key, 3, 1).unwrap(),
+        vec![entry(5, value_hash(3))]
+    );
+    assert_eq!(
+        storage.get_key_history(&other_key, 0, 10).unwrap(),
+        vec![entry(2, value_hash(2))]
+    );
+    assert!(storage
+        .get_key_history(&Key::Balance(rng.gen()), 0, 10)
+        .unwrap()
+        .is_empty());
+
+    // Skipped records don't count towards the limit.
+    let third_key = Key::Balance(rng.gen());
+    storage
+        .put_key_history(4, &[(third_key, value_hash(5))])
+        .unwrap();
+    storage
+        .put_key_history(7, &[(third_key, value_hash(6))])
+        .unwrap();
+    let _ = storage.block_height_index.remove(&4);
+    assert_eq!(
+        storage.get_key_history(&third_key, 0, 1).unwrap(),
+        vec![entry(7, value_hash(6))]
+    );
+}
+
+#[test]
+fn should_store_validator_performance() {
+    let mut harness = ComponentHarness::default();
+    let mut storage = storage_fixture(&harness);
+
+    let public_key = PublicKey::random(&mut harness.rng);
+    let performance = |proposals: u64| {
+        let validator_performance = ValidatorPerformance {
+            proposals,
+            missed_proposals: 2,
+            units_and_votes: 30,
+            endorsements: 4,
+            average_lateness: TimeDiff::from_millis(500),
+        };
+        iter::once((public_key.clone(), validator_performance)).collect::<BTreeMap<_, _>>()
+    };
+
+    let era_id = EraId::new(3);
+    assert_eq!(storage.get_validator_performance(era_id).unwrap(), None);
+    storage
+        .put_validator_performance(era_id, &performance(5))
+        .unwrap();
+    assert_eq!(
+        storage.get_validator_performance(era_id).unwrap(),
+        Some(performance(5))
+    );
+
+    // A later record for the same era replaces the earlier one.
+    storage
+        .put_validator_performance(era_id, &performance(6))
+        .unwrap();
+    assert_eq!(
+        storage.get_validator_performance(era_id).unwrap(),
+        Some(performance(6))
+    );
+    assert_eq!(
+        storage
+            .get_validator_performance(era_id.successor())
+            .unwrap(),
+        None
+    );
+}
@@ -0,0 +1,45 @@
+//! Statistics about the validators' participation in consensus, recorded at the end of each era.
+//!
+//! Records are keyed by the big-endian era ID.
+
+use std::collections::BTreeMap;
+
+use lmdb::Transaction;
+
+use casper_types::{EraId, PublicKey};
+
+use super::{FatalStorageError, Storage, TransactionExt, WriteTransactionExt};
+use crate::components::consensus::ValidatorPerformance;
+
+impl Storage {
+    /// Records the validators' performance in the given era, replacing any earlier record.
+    pub(super) fn put_validator_performance(
+        &mut self,
+        era_id: EraId,
+        performance: &BTreeMap<PublicKey, ValidatorPerformance>,
+    ) -> Result<(), FatalStorageError> {
+        let db = match self.validator_performance_db {
+            Some(db) => db,
+            None => return Ok(()),
+        };
+        let mut txn = self.env.begin_rw_txn()?;
+        let _ = txn.put_value(db, &era_id.value().to_be_bytes(), performance, true)?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Returns the validators' performance in the given era, if it was recorded.
+    pub(super) fn get_validator_performance(
+        &self,
+        era_id: EraId,
+    ) -> Result<Option<BTreeMap<PublicKey, ValidatorPerformance>>, FatalStorageError> {
+        let db = match self.validator_performance_db {
+            Some(db) => db,
+            None => return Ok(None),
+        };
+        let mut txn = self.env.begin_ro_txn()?;
+        let performance = txn.get_value(db, &era_id.value().to_be_bytes())?;
+        txn.commit()?;
+        Ok(performance)
+    }
+}
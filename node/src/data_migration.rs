@@ -1,4 +1,8 @@
-use std::{env, fs, io, path::PathBuf, sync::Arc};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -9,6 +13,7 @@ use casper_hashing::Digest;
 use casper_types::{crypto, ProtocolVersion, PublicKey, SecretKey, Signature};
 
 use crate::{
+    components::storage::{FatalStorageError, Storage},
     reactor::main_reactor::Config,
     types::{chainspec, Chainspec, ChainspecRawBytes},
     utils::{LoadError, Loadable, WithDir},
@@ -81,6 +86,10 @@ pub(crate) enum Error {
     /// Error loading the chainspec.
     #[error("error loading chainspec: {0}")]
     LoadChainspec(chainspec::Error),
+
+    /// Error opening or migrating the storage.
+    #[error("error migrating storage: {0}")]
+    Storage(#[from] FatalStorageError),
 }
 
 #[derive(Serialize, Deserialize)]
@@ -199,17 +208,17 @@ pub(crate) fn migrate_data(
     new_config: WithDir<Config>,
 ) -> Result<(), Error> {
     let (new_root, new_config) = new_config.into_parts();
-    let new_protocol_version = <(Chainspec, ChainspecRawBytes)>::from_path(&new_root)
-        .map_err(Error::LoadChainspec)?
-        .0
-        .protocol_config
-        .version;
+    let (chainspec, _) =
+        <(Chainspec, ChainspecRawBytes)>::from_path(&new_root).map_err(Error::LoadChainspec)?;
+    let new_protocol_version = chainspec.protocol_config.version;
     let secret_key: Arc<SecretKey> = new_config
         .consensus
         .secret_key_path
         .load(&new_root)
         .map_err(Error::LoadSecretKey)?;
 
+    compress_deploy_records(&new_root, &new_config, &chainspec)?;
+
     // Get this by actually migrating the global state data.
     let state_hash = Digest::default();
 
@@ -220,6 +229,38 @@ pub(crate) fn migrate_data(
     Ok(())
 }
 
+/// Compresses all existing deploys and deploy metadata if deploy compression is enabled in the new
+/// config.
+///
+/// Records which are already compressed are skipped, so this only does any work the first time it
+/// is run after compression has been enabled.
+fn compress_deploy_records(
+    root: &Path,
+    config: &Config,
+    chainspec: &Chainspec,
+) -> Result<(), Error> {
+    if !config.storage.enable_deploy_compression {
+        return Ok(());
+    }
+
+    let storage = Storage::new(
+        &WithDir::new(root, config.storage.clone()),
+        None,
+        chainspec.protocol_config.version,
+        chainspec.protocol_config.activation_point.era_id(),
+        &chainspec.network_config.name,
+        chainspec.deploy_config.max_ttl.into(),
+        chainspec.core_config.recent_era_count(),
+        None,
+        false,
+    )?;
+
+    info!("compressing existing deploys and deploy metadata");
+    let rewritten = storage.compress_deploy_records()?;
+    info!(rewritten, "compressed existing deploys and deploy metadata");
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use rand::Rng;
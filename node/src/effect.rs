@@ -102,6 +102,7 @@ use std::{
     fmt::{self, Debug, Display, Formatter},
     future::Future,
     mem,
+    path::PathBuf,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -135,13 +136,20 @@ use crate::{
             BlockSynchronizerStatus, GlobalStateSynchronizerError, GlobalStateSynchronizerResponse,
             TrieAccumulatorError, TrieAccumulatorResponse,
         },
-        consensus::{ClContext, EraDump, ProposedBlock, ValidatorChange},
+        consensus::{
+            BlockTimelines, ClContext, EraDump, ProposedBlock, ValidatorChange, ValidatorFault,
+            ValidatorPerformance,
+        },
         contract_runtime::{ContractRuntimeError, EraValidatorsRequest},
         deploy_acceptor,
         diagnostics_port::StopAtSpec,
         fetcher::{FetchItem, FetchResult},
         gossiper::GossipItem,
-        network::{blocklist::BlocklistJustification, FromIncoming, NetworkInsights},
+        network::{
+            blocklist::BlocklistJustification, reputation::PeerObservation, FromIncoming,
+            NetworkInsights,
+        },
+        storage::{KeyHistoryEntry, SnapshotError},
         upgrade_watcher::NextUpgrade,
     },
     contract_runtime::SpeculativeExecutionState,
@@ -762,7 +770,7 @@ impl<REv> EffectBuilder<REv> {
     }
 
     /// Gets a structure describing the current network status.
-    pub(crate) async fn get_network_insights(self) -> NetworkInsights
+    pub(crate) async fn get_network_insights(self) -> Result<NetworkInsights, String>
     where
         REv: From<NetworkInfoRequest>,
     {
@@ -773,6 +781,18 @@ impl<REv> EffectBuilder<REv> {
         .await
     }
 
+    /// Rotates our network identity, returning our new node ID.
+    pub(crate) async fn rotate_network_identity(self) -> Result<NodeId, String>
+    where
+        REv: From<NetworkInfoRequest>,
+    {
+        self.make_request(
+            |responder| NetworkInfoRequest::RotateIdentity { responder },
+            QueueKind::Control,
+        )
+        .await
+    }
+
     /// Gets a map of the current network peers to their socket addresses.
     pub(crate) async fn network_peers(self) -> BTreeMap<NodeId, String>
     where
@@ -785,6 +805,18 @@ impl<REv> EffectBuilder<REv> {
         .await
     }
 
+    /// Gets a map of the network peers to their reputation scores.
+    pub(crate) async fn network_peer_scores(self) -> BTreeMap<NodeId, i32>
+    where
+        REv: From<NetworkInfoRequest>,
+    {
+        self.make_request(
+            |responder| NetworkInfoRequest::PeerScores { responder },
+            QueueKind::Api,
+        )
+        .await
+    }
+
     /// Gets up to `count` fully-connected network peers in random order.
     pub async fn get_fully_connected_peers(self, count: usize) -> Vec<NodeId>
     where
@@ -1846,9 +1878,10 @@ impl<REv> EffectBuilder<REv> {
             .await
     }
 
-    /// Blocks a specific peer due to a transgression.
+    /// Reports a transgression of a specific peer, which will either block it right away or lower
+    /// its reputation, depending on the severity of the offense.
     ///
-    /// This function will also emit a log message for the block.
+    /// This function will also emit a log message for the offense.
     pub(crate) async fn announce_block_peer_with_justification(
         self,
         offender: NodeId,
@@ -1856,7 +1889,7 @@ impl<REv> EffectBuilder<REv> {
     ) where
         REv: From<PeerBehaviorAnnouncement>,
     {
-        warn!(%offender, %justification, "banning peer");
+        warn!(%offender, %justification, "peer committed offense");
         self.event_queue
             .schedule(
                 PeerBehaviorAnnouncement::OffenseCommitted {
@@ -1868,6 +1901,25 @@ impl<REv> EffectBuilder<REv> {
             .await
     }
 
+    /// Reports an observed behavior of a specific peer, affecting its reputation.
+    pub(crate) async fn announce_peer_behavior_observed(
+        self,
+        peer: NodeId,
+        observation: PeerObservation,
+    ) where
+        REv: From<PeerBehaviorAnnouncement>,
+    {
+        self.event_queue
+            .schedule(
+                PeerBehaviorAnnouncement::BehaviorObserved {
+                    peer: Box::new(peer),
+                    observation,
+                },
+                QueueKind::NetworkInfo,
+            )
+            .await
+    }
+
     /// Gets the next scheduled upgrade, if any.
     pub(crate) async fn get_next_upgrade(self) -> Option<NextUpgrade>
     where
@@ -2063,6 +2115,39 @@ impl<REv> EffectBuilder<REv> {
             .await
     }
 
+    /// Returns the evidence against faulty validators in the open eras.
+    pub(crate) async fn get_consensus_validator_faults(self) -> Vec<ValidatorFault>
+    where
+        REv: From<ConsensusRequest>,
+    {
+        self.make_request(ConsensusRequest::ValidatorFaults, QueueKind::Consensus)
+            .await
+    }
+
+    /// Returns the current era's ID and each validator's performance in it so far.
+    pub(crate) async fn get_consensus_validator_performance(
+        self,
+    ) -> Option<(EraId, BTreeMap<PublicKey, ValidatorPerformance>)>
+    where
+        REv: From<ConsensusRequest>,
+    {
+        self.make_request(ConsensusRequest::ValidatorPerformance, QueueKind::Consensus)
+            .await
+    }
+
+    /// Returns the finality timeline of the recent block at the given height, or of all recent
+    /// blocks.
+    pub(crate) async fn get_consensus_finality_timeline(self, height: Option<u64>) -> BlockTimelines
+    where
+        REv: From<ConsensusRequest>,
+    {
+        self.make_request(
+            |responder| ConsensusRequest::FinalityTimeline(height, responder),
+            QueueKind::Consensus,
+        )
+        .await
+    }
+
     /// Dump consensus state for a specific era, using the supplied function to serialize the
     /// output.
     pub(crate) async fn diagnostics_port_dump_consensus_state(
@@ -2084,6 +2169,118 @@ impl<REv> EffectBuilder<REv> {
         .await
     }
 
+    /// Records the values written to keys of the key history index by the block at the given
+    /// height.
+    pub(crate) async fn put_key_history_to_storage(
+        self,
+        block_height: u64,
+        entries: Vec<(Key, Option<Digest>)>,
+    ) where
+        REv: From<StorageRequest>,
+    {
+        self.make_request(
+            |responder| StorageRequest::PutKeyHistory {
+                block_height,
+                entries,
+                responder,
+            },
+            QueueKind::ToStorage,
+        )
+        .await
+    }
+
+    /// Gets up to `limit` recorded changes of the value under `key` from the key history index,
+    /// starting at the block at `from_height`.
+    pub(crate) async fn get_key_history_from_storage(
+        self,
+        key: Key,
+        from_height: u64,
+        limit: usize,
+    ) -> Vec<KeyHistoryEntry>
+    where
+        REv: From<StorageRequest>,
+    {
+        self.make_request(
+            |responder| StorageRequest::GetKeyHistory {
+                key,
+                from_height,
+                limit,
+                responder,
+            },
+            QueueKind::FromStorage,
+        )
+        .await
+    }
+
+    /// Records each validator's performance in the given era.
+    pub(crate) async fn put_validator_performance_to_storage(
+        self,
+        era_id: EraId,
+        performance: BTreeMap<PublicKey, ValidatorPerformance>,
+    ) where
+        REv: From<StorageRequest>,
+    {
+        self.make_request(
+            |responder| StorageRequest::PutValidatorPerformance {
+                era_id,
+                performance,
+                responder,
+            },
+            QueueKind::ToStorage,
+        )
+        .await
+    }
+
+    /// Gets each validator's performance in the given era from storage, if it was recorded.
+    pub(crate) async fn get_validator_performance_from_storage(
+        self,
+        era_id: EraId,
+    ) -> Option<BTreeMap<PublicKey, ValidatorPerformance>>
+    where
+        REv: From<StorageRequest>,
+    {
+        self.make_request(
+            |responder| StorageRequest::GetValidatorPerformance { era_id, responder },
+            QueueKind::FromStorage,
+        )
+        .await
+    }
+
+    /// Takes a consistent snapshot of the storage databases in the given directory, as of the
+    /// highest complete block.
+    ///
+    /// Returns the header of that block.
+    pub(crate) async fn take_storage_snapshot(
+        self,
+        dir: PathBuf,
+    ) -> Result<BlockHeader, SnapshotError>
+    where
+        REv: From<StorageRequest>,
+    {
+        self.make_request(
+            |responder| StorageRequest::TakeSnapshot { dir, responder },
+            QueueKind::FromStorage,
+        )
+        .await
+    }
+
+    /// Copies the global state into a new environment in the given directory.
+    ///
+    /// Returns the number of tries copied.
+    pub(crate) async fn take_global_state_snapshot(
+        self,
+        dir: PathBuf,
+    ) -> Result<usize, engine_state::Error>
+    where
+        REv: From<ContractRuntimeRequest>,
+    {
+        self.make_request(
+            |responder| ContractRuntimeRequest::SnapshotGlobalState { dir, responder },
+            QueueKind::ContractRuntime,
+        )
+        .await
+    }
+
     /// Dump the event queue contents to the diagnostics port, using the given serializer.
     pub(crate) async fn diagnostics_port_dump_queue(self, dump_format: QueueDumpFormat)
     where
@@ -22,7 +22,7 @@ use crate::{
         diagnostics_port::FileSerializer,
         fetcher::FetchItem,
         gossiper::GossipItem,
-        network::blocklist::BlocklistJustification,
+        network::{blocklist::BlocklistJustification, reputation::PeerObservation},
         upgrade_watcher::NextUpgrade,
     },
     effect::Responder,
@@ -272,6 +272,13 @@ pub(crate) enum PeerBehaviorAnnouncement {
         /// Justification for blocking the peer.
         justification: Box<BlocklistJustification>,
     },
+    /// A given peer showed behavior affecting its reputation.
+    BehaviorObserved {
+        /// The peer ID of the observed node.
+        peer: Box<NodeId>,
+        /// The observed behavior.
+        observation: PeerObservation,
+    },
 }
 
 impl Display for PeerBehaviorAnnouncement {
@@ -283,6 +290,9 @@ impl Display for PeerBehaviorAnnouncement {
             } => {
                 write!(f, "peer {} committed offense: {}", offender, justification)
             }
+            PeerBehaviorAnnouncement::BehaviorObserved { peer, observation } => {
+                write!(f, "peer {} {}", peer, observation)
+            }
         }
     }
 }
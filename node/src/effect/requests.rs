@@ -7,6 +7,7 @@ use std::{
     collections::{BTreeMap, HashMap, HashSet},
     fmt::{self, Debug, Display, Formatter},
     mem,
+    path::PathBuf,
     sync::Arc,
 };
 
@@ -37,13 +38,17 @@ use crate::{
             BlockSynchronizerStatus, GlobalStateSynchronizerError, GlobalStateSynchronizerResponse,
             TrieAccumulatorError, TrieAccumulatorResponse,
         },
-        consensus::{ClContext, ProposedBlock, ValidatorChange},
+        consensus::{
+            BlockTimelines, ClContext, ProposedBlock, ValidatorChange, ValidatorFault,
+            ValidatorPerformance,
+        },
         contract_runtime::EraValidatorsRequest,
         deploy_acceptor,
         diagnostics_port::StopAtSpec,
         fetcher::{FetchItem, FetchResult},
         gossiper::GossipItem,
         network::NetworkInsights,
+        storage::{KeyHistoryEntry, SnapshotError},
         upgrade_watcher::NextUpgrade,
     },
     contract_runtime::{ContractRuntimeError, SpeculativeExecutionState},
@@ -56,7 +61,7 @@ use crate::{
         BlockSignatures, BlockWithMetadata, ChainspecRawBytes, Deploy, DeployHash, DeployHeader,
         DeployId, DeployMetadataExt, DeployWithFinalizedApprovals, FinalitySignature,
         FinalitySignatureId, FinalizedApprovals, FinalizedBlock, LegacyDeploy, MetaBlockState,
-        NodeId, StatusFeed, TrieOrChunk, TrieOrChunkId,
+        NodeId, PeersMap, StatusFeed, TrieOrChunk, TrieOrChunkId,
     },
     utils::{DisplayIter, Source},
 };
@@ -211,7 +216,19 @@ pub(crate) enum NetworkInfoRequest {
     },
     /// Get detailed insights into the nodes networking.
     Insight {
-        responder: Responder<NetworkInsights>,
+        /// Responder to be called with the insights, or the reason they are not available.
+        responder: Responder<Result<NetworkInsights, String>>,
+    },
+    /// Get the reputation scores of peers.
+    PeerScores {
+        /// Responder to be called with a map from [NodeId]s to their current reputation score.
+        /// Peers without a score are omitted.
+        responder: Responder<BTreeMap<NodeId, i32>>,
+    },
+    /// Rotate our network identity, loading it again from the configuration.
+    RotateIdentity {
+        /// Responder to be called with our new [NodeId], or the reason the rotation failed.
+        responder: Responder<Result<NodeId, String>>,
     },
 }
 
@@ -230,6 +247,12 @@ impl Display for NetworkInfoRequest {
             NetworkInfoRequest::Insight { responder: _ } => {
                 formatter.write_str("get networking insights")
             }
+            NetworkInfoRequest::PeerScores { responder: _ } => {
+                formatter.write_str("get peer reputation scores")
+            }
+            NetworkInfoRequest::RotateIdentity { responder: _ } => {
+                formatter.write_str("rotate network identity")
+            }
         }
     }
 }
@@ -500,6 +523,50 @@ pub(crate) enum StorageRequest {
     },
     /// Retrieve the height of the final block of the previous protocol version, if known.
     GetKeyBlockHeightForActivationPoint { responder: Responder<Option<u64>> },
+    /// Record the values written to keys of the key history index by a block.
+    PutKeyHistory {
+        /// Height of the block which wrote the keys.
+        block_height: u64,
+        /// The written keys, with the hash of their value after the block.
+        entries: Vec<(Key, Option<Digest>)>,
+        /// Responder to call when done.
+        responder: Responder<()>,
+    },
+    /// Retrieve the recorded changes of the value under a key, in order of ascending block height.
+    GetKeyHistory {
+        /// The key to retrieve the history of.
+        key: Key,
+        /// Height of the earliest block to include.
+        from_height: u64,
+        /// Maximum number of entries to return.
+        limit: usize,
+        /// Responder to call with the result.
+        responder: Responder<Vec<KeyHistoryEntry>>,
+    },
+    /// Store the validators' performance in an era.
+    PutValidatorPerformance {
+        /// The era the performance was recorded in.
+        era_id: EraId,
+        /// The performance of each validator.
+        performance: BTreeMap<PublicKey, ValidatorPerformance>,
+        /// Responder to call when done.
+        responder: Responder<()>,
+    },
+    /// Retrieve the validators' performance in an era.
+    GetValidatorPerformance {
+        /// The era to retrieve the performance for.
+        era_id: EraId,
+        /// Responder to call with the result. Returns `None` if no performance was stored for
+        /// the era.
+        responder: Responder<Option<BTreeMap<PublicKey, ValidatorPerformance>>>,
+    },
+    /// Take a consistent snapshot of all databases as of the highest complete block.
+    TakeSnapshot {
+        /// Directory to create the copy of the databases in.
+        dir: PathBuf,
+        /// Responder to call with the header of the highest complete block in the snapshot.
+        responder: Responder<Result<BlockHeader, SnapshotError>>,
+    },
 }
 
 impl Display for StorageRequest {
@@ -622,6 +689,32 @@ impl Display for StorageRequest {
                     "get key block height for current activation point"
                 )
             }
+            StorageRequest::PutKeyHistory {
+                block_height,
+                entries,
+                ..
+            } => write!(
+                formatter,
+                "put history of {} keys at height {}",
+                entries.len(),
+                block_height
+            ),
+            StorageRequest::GetKeyHistory {
+                key, from_height, ..
+            } => write!(
+                formatter,
+                "get history of {} from height {}",
+                key, from_height
+            ),
+            StorageRequest::PutValidatorPerformance { era_id, .. } => {
+                write!(formatter, "put validator performance in {}", era_id)
+            }
+            StorageRequest::GetValidatorPerformance { era_id, .. } => {
+                write!(formatter, "get validator performance in {}", era_id)
+            }
+            StorageRequest::TakeSnapshot { dir, .. } => {
+                write!(formatter, "take snapshot in {}", dir.display())
+            }
         }
     }
 }
@@ -742,10 +835,10 @@ pub(crate) enum RpcRequest {
         /// Responder to call with the result.
         responder: Responder<Option<Box<(Deploy, DeployMetadataExt)>>>,
     },
-    /// Return the connected peers.
+    /// Return the connected peers along with their reputation scores.
     GetPeers {
         /// Responder to call with the result.
-        responder: Responder<BTreeMap<NodeId, String>>,
+        responder: Responder<PeersMap>,
     },
     /// Return string formatted status or `None` if an error occurred.
     GetStatus {
@@ -927,6 +1020,13 @@ pub(crate) enum ContractRuntimeRequest {
         /// Results
         responder: Responder<Result<Option<ExecutionResult>, engine_state::Error>>,
     },
+    /// Copy the global state into a new environment.
+    SnapshotGlobalState {
+        /// Directory to create the copy of the global state in.
+        dir: PathBuf,
+        /// Responder to call with the number of tries copied.
+        responder: Responder<Result<usize, engine_state::Error>>,
+    },
 }
 
 impl Display for ContractRuntimeRequest {
@@ -981,6 +1081,9 @@ impl Display for ContractRuntimeRequest {
                     execution_prestate.state_root_hash
                 )
             }
+            ContractRuntimeRequest::SnapshotGlobalState { dir, .. } => {
+                write!(formatter, "snapshot global state in {}", dir.display())
+            }
         }
     }
 }
@@ -1073,6 +1176,13 @@ pub(crate) enum ConsensusRequest {
     Status(Responder<Option<(PublicKey, Option<TimeDiff>)>>),
     /// Request for a list of validator status changes, by public key.
     ValidatorChanges(Responder<BTreeMap<PublicKey, Vec<(EraId, ValidatorChange)>>>),
+    /// Request for the evidence against faulty validators in the open eras.
+    ValidatorFaults(Responder<Vec<ValidatorFault>>),
+    /// Request for the current era's ID and each validator's performance in it so far.
+    ValidatorPerformance(Responder<Option<(EraId, BTreeMap<PublicKey, ValidatorPerformance>)>>),
+    /// Request for the finality timeline of the recent block at the given height, or of all recent
+    /// blocks.
+    FinalityTimeline(Option<u64>, Responder<BlockTimelines>),
 }
 
 /// ChainspecLoader component requests.
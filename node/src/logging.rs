@@ -262,12 +262,35 @@ where
 
 /// Initializes the logging system with the default parameters.
 ///
-/// See `init_params` for details.
-#[cfg(test)]
+/// See `init_with_config` for details.
 pub fn init() -> anyhow::Result<()> {
     init_with_config(&Default::default())
 }
 
+/// Initializes the logging system with the default parameters, writing to `stderr`.
+///
+/// Used by subcommands printing their results to `stdout`, which must not be interleaved with log
+/// output.
+// The `io::stderr as fn()...` cast is necessary, as is the `FormatDebugFn` cast.
+#[allow(trivial_casts)]
+pub fn init_to_stderr() -> anyhow::Result<()> {
+    let formatter = format::debug_fn(format_into_debug_writer as FormatDebugFn);
+
+    let filter = EnvFilter::new(
+        env::var(LOG_CONFIGURATION_ENVVAR)
+            .as_deref()
+            .unwrap_or("warn,casper_node=info"),
+    );
+
+    tracing_subscriber::fmt()
+        .with_writer(io::stderr as fn() -> std::io::Stderr)
+        .with_env_filter(filter)
+        .fmt_fields(formatter)
+        .event_format(FmtEvent::new(false, false))
+        .try_init()
+        .map_err(|error| anyhow!(error))
+}
+
 /// A handle for reloading the logger.
 #[allow(clippy::type_complexity)] // Cannot be helped, unfortunately.
 pub enum ReloadHandle {
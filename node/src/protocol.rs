@@ -2,6 +2,7 @@
 
 use std::{
     fmt::{self, Display, Formatter},
+    net::SocketAddr,
     sync::Arc,
 };
 
@@ -161,6 +162,27 @@ impl Payload for Message {
             Message::FinalitySignature(_) => false,
         }
     }
+
+    fn requires_reconciliation(&self) -> bool {
+        matches!(
+            self,
+            Message::DeployGossiper(gossiper::Message::Reconcile(_))
+                | Message::BlockGossiper(gossiper::Message::Reconcile(_))
+                | Message::FinalitySignatureGossiper(gossiper::Message::Reconcile(_))
+                | Message::AddressGossiper(gossiper::Message::Reconcile(_))
+        )
+    }
+
+    fn gossiped_address(&self) -> Option<SocketAddr> {
+        let gossiped_address = match self {
+            Message::AddressGossiper(gossiper::Message::Gossip(item_id))
+            | Message::AddressGossiper(gossiper::Message::GossipResponse { item_id, .. })
+            | Message::AddressGossiper(gossiper::Message::GetItem(item_id)) => *item_id,
+            Message::AddressGossiper(gossiper::Message::Item(item)) => **item,
+            _ => return None,
+        };
+        Some(gossiped_address.into())
+    }
 }
 
 impl Message {
@@ -29,6 +29,7 @@
 //! With all these set up, a reactor can be executed using a [`Runner`], either in a step-wise
 //! manner using [`Runner::crank`] or indefinitely using [`Runner::run`].
 
+pub(crate) mod archive_reactor;
 mod event_queue_metrics;
 pub(crate) mod main_reactor;
 mod queue_kind;
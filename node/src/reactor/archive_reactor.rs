@@ -0,0 +1,286 @@
+//! Archive reactor for nodes serving queries from existing data.
+//!
+//! The archive reactor opens the storage and global state of a node read-only and runs only the
+//! RPC, REST and event stream servers on top of them. It neither joins the network nor takes part
+//! in consensus or syncing, so the data it serves never changes.
+
+mod event;
+
+use std::{collections::BTreeMap, sync::Arc, time::Instant};
+
+use datasize::DataSize;
+use prometheus::Registry;
+use tracing::{error, info};
+
+use casper_types::Timestamp;
+
+use crate::{
+    components::{
+        block_synchronizer::BlockSynchronizerStatus,
+        consensus::BlockTimelines,
+        contract_runtime::ContractRuntime,
+        deploy_acceptor,
+        event_stream_server::{self, EventStreamServer},
+        metrics::Metrics,
+        network::Identity as NetworkIdentity,
+        rest_server::{self, RestServer},
+        rpc_server::{self, RpcServer},
+        storage::Storage,
+        Component, InitializedComponent,
+    },
+    effect::{
+        announcements::ControlAnnouncement,
+        requests::{
+            AcceptDeployRequest, BlockSynchronizerRequest, ChainspecRawBytesRequest,
+            ConsensusRequest, NetworkInfoRequest, ReactorStatusRequest, UpgradeWatcherRequest,
+        },
+        EffectBuilder, EffectExt, Effects,
+    },
+    reactor::{
+        self,
+        main_reactor::{Config, Error, ReactorState},
+        EventQueueHandle, QueueKind,
+    },
+    types::{Chainspec, ChainspecRawBytes},
+    utils::WithDir,
+    NodeRng,
+};
+pub(crate) use event::ArchiveEvent;
+
+/// Archive node reactor.
+#[derive(DataSize, Debug)]
+pub(crate) struct ArchiveReactor {
+    storage: Storage,
+    contract_runtime: ContractRuntime,
+    rpc_server: RpcServer,
+    rest_server: RestServer,
+    event_stream_server: EventStreamServer,
+    metrics: Metrics,
+
+    chainspec_raw_bytes: Arc<ChainspecRawBytes>,
+    started: Timestamp,
+}
+
+impl reactor::Reactor for ArchiveReactor {
+    type Event = ArchiveEvent;
+    type Config = WithDir<Config>;
+    type Error = Error;
+
+    fn dispatch_event(
+        &mut self,
+        effect_builder: EffectBuilder<ArchiveEvent>,
+        rng: &mut NodeRng,
+        event: ArchiveEvent,
+    ) -> Effects<ArchiveEvent> {
+        match event {
+            ArchiveEvent::ControlAnnouncement(ctrl_ann) => {
+                error!("unhandled control announcement: {}", ctrl_ann);
+                Effects::new()
+            }
+            ArchiveEvent::FatalAnnouncement(fatal_ann) => {
+                let ctrl_ann = ArchiveEvent::ControlAnnouncement(ControlAnnouncement::FatalError {
+                    file: fatal_ann.file,
+                    line: fatal_ann.line,
+                    msg: fatal_ann.msg,
+                });
+                effect_builder
+                    .into_inner()
+                    .schedule(ctrl_ann, QueueKind::Control)
+                    .ignore()
+            }
+
+            ArchiveEvent::Storage(event) => reactor::wrap_effects(
+                ArchiveEvent::Storage,
+                self.storage.handle_event(effect_builder, rng, event),
+            ),
+            ArchiveEvent::StorageRequest(req) => reactor::wrap_effects(
+                ArchiveEvent::Storage,
+                self.storage.handle_event(effect_builder, rng, req.into()),
+            ),
+            ArchiveEvent::ContractRuntime(event) => reactor::wrap_effects(
+                ArchiveEvent::ContractRuntime,
+                self.contract_runtime
+                    .handle_event(effect_builder, rng, event),
+            ),
+            ArchiveEvent::ContractRuntimeRequest(req) => reactor::wrap_effects(
+                ArchiveEvent::ContractRuntime,
+                self.contract_runtime
+                    .handle_event(effect_builder, rng, req.into()),
+            ),
+            ArchiveEvent::RpcServer(event) => reactor::wrap_effects(
+                ArchiveEvent::RpcServer,
+                self.rpc_server.handle_event(effect_builder, rng, event),
+            ),
+            ArchiveEvent::RestServer(event) => reactor::wrap_effects(
+                ArchiveEvent::RestServer,
+                self.rest_server.handle_event(effect_builder, rng, event),
+            ),
+            ArchiveEvent::EventStreamServer(event) => reactor::wrap_effects(
+                ArchiveEvent::EventStreamServer,
+                self.event_stream_server
+                    .handle_event(effect_builder, rng, event),
+            ),
+            ArchiveEvent::MetricsRequest(req) => reactor::wrap_effects(
+                ArchiveEvent::MetricsRequest,
+                self.metrics.handle_event(effect_builder, rng, req),
+            ),
+
+            ArchiveEvent::ChainspecRawBytesRequest(
+                ChainspecRawBytesRequest::GetChainspecRawBytes(responder),
+            ) => responder.respond(self.chainspec_raw_bytes.clone()).ignore(),
+            // Upgrades are never activated, as the node is not following the chain.
+            ArchiveEvent::UpgradeWatcherRequest(UpgradeWatcherRequest(responder)) => {
+                responder.respond(None).ignore()
+            }
+            ArchiveEvent::ConsensusRequest(ConsensusRequest::Status(responder)) => {
+                responder.respond(None).ignore()
+            }
+            ArchiveEvent::ConsensusRequest(ConsensusRequest::ValidatorChanges(responder)) => {
+                responder.respond(BTreeMap::new()).ignore()
+            }
+            ArchiveEvent::ConsensusRequest(ConsensusRequest::ValidatorFaults(responder)) => {
+                responder.respond(Vec::new()).ignore()
+            }
+            ArchiveEvent::ConsensusRequest(ConsensusRequest::ValidatorPerformance(responder)) => {
+                responder.respond(None).ignore()
+            }
+            ArchiveEvent::ConsensusRequest(ConsensusRequest::FinalityTimeline(_, responder)) => {
+                responder.respond(BlockTimelines(Vec::new())).ignore()
+            }
+            ArchiveEvent::NetworkInfoRequest(NetworkInfoRequest::Peers { responder }) => {
+                responder.respond(BTreeMap::new()).ignore()
+            }
+            ArchiveEvent::NetworkInfoRequest(NetworkInfoRequest::PeerScores { responder }) => {
+                responder.respond(BTreeMap::new()).ignore()
+            }
+            ArchiveEvent::NetworkInfoRequest(NetworkInfoRequest::FullyConnectedPeers {
+                responder,
+                ..
+            }) => responder.respond(Vec::new()).ignore(),
+            ArchiveEvent::NetworkInfoRequest(NetworkInfoRequest::Insight { responder }) => {
+                responder
+                    .respond(Err(
+                        "network insights are not available in archive mode".to_string()
+                    ))
+                    .ignore()
+            }
+            ArchiveEvent::NetworkInfoRequest(NetworkInfoRequest::RotateIdentity { responder }) => {
+                responder
+                    .respond(Err(
+                        "there is no network identity in archive mode".to_string()
+                    ))
+                    .ignore()
+            }
+            ArchiveEvent::BlockSynchronizerRequest(BlockSynchronizerRequest::Status {
+                responder,
+            }) => responder
+                .respond(BlockSynchronizerStatus::new(None, None))
+                .ignore(),
+            ArchiveEvent::BlockSynchronizerRequest(req) => {
+                error!(%req, "unexpected block synchronizer request in archive mode");
+                Effects::new()
+            }
+            ArchiveEvent::ReactorStatusRequest(ReactorStatusRequest(responder)) => responder
+                .respond((ReactorState::Archive, self.started))
+                .ignore(),
+            ArchiveEvent::AcceptDeployRequest(AcceptDeployRequest { responder, .. }) => responder
+                .respond(Err(deploy_acceptor::Error::ArchiveMode))
+                .ignore(),
+
+            ArchiveEvent::NetworkRequest(_)
+            | ArchiveEvent::ContractRuntimeAnnouncement(_)
+            | ArchiveEvent::MetaBlockAnnouncement(_)
+            | ArchiveEvent::UnexecutedBlockAnnouncement(_) => {
+                error!(%event, "unexpected event in archive mode");
+                Effects::new()
+            }
+        }
+    }
+
+    fn new(
+        config: Self::Config,
+        chainspec: Arc<Chainspec>,
+        chainspec_raw_bytes: Arc<ChainspecRawBytes>,
+        _network_identity: NetworkIdentity,
+        registry: &Registry,
+        _event_queue: EventQueueHandle<Self::Event>,
+        _rng: &mut NodeRng,
+    ) -> Result<(Self, Effects<ArchiveEvent>), Error> {
+        let node_startup_instant = Instant::now();
+
+        let metrics = Metrics::new(registry.clone());
+
+        let protocol_version = chainspec.protocol_config.version;
+        let (root_dir, config) = config.into_parts();
+
+        let storage = Storage::open_read_only(
+            &WithDir::new(&root_dir, config.storage.clone()),
+            &chainspec.network_config.name,
+            chainspec.deploy_config.max_ttl.into(),
+            chainspec.core_config.recent_era_count(),
+            Some(registry),
+        )?;
+        let contract_runtime = ContractRuntime::new_read_only(
+            storage.root_path(),
+            &config.contract_runtime,
+            chainspec.as_ref(),
+            registry,
+        )?;
+        info!(
+            available_block_range = %storage.get_available_block_range(),
+            "opened storage and global state read-only"
+        );
+
+        let mut rpc_server = RpcServer::new(
+            config.rpc_server.clone(),
+            config.speculative_exec_server.clone(),
+            protocol_version,
+            chainspec.network_config.name.clone(),
+            node_startup_instant,
+        );
+        let mut rest_server = RestServer::new(
+            config.rest_server.clone(),
+            protocol_version,
+            chainspec.network_config.name.clone(),
+            node_startup_instant,
+        );
+        // The event stream server persists its event index on shutdown, so it is kept out of the
+        // read-only storage directory.
+        let mut event_stream_server = EventStreamServer::new(
+            config.event_stream_server.clone(),
+            root_dir.clone(),
+            protocol_version,
+        );
+
+        <RpcServer as InitializedComponent<ArchiveEvent>>::start_initialization(&mut rpc_server);
+        <RestServer as InitializedComponent<ArchiveEvent>>::start_initialization(&mut rest_server);
+        <EventStreamServer as InitializedComponent<ArchiveEvent>>::start_initialization(
+            &mut event_stream_server,
+        );
+        let mut effects = reactor::wrap_effects(
+            ArchiveEvent::RpcServer,
+            async {}.event(|()| rpc_server::Event::Initialize),
+        );
+        effects.extend(reactor::wrap_effects(
+            ArchiveEvent::RestServer,
+            async {}.event(|()| rest_server::Event::Initialize),
+        ));
+        effects.extend(reactor::wrap_effects(
+            ArchiveEvent::EventStreamServer,
+            async {}.event(|()| event_stream_server::Event::Initialize),
+        ));
+
+        let reactor = ArchiveReactor {
+            storage,
+            contract_runtime,
+            rpc_server,
+            rest_server,
+            event_stream_server,
+            metrics,
+            chainspec_raw_bytes,
+            started: Timestamp::now(),
+        };
+
+        Ok((reactor, effects))
+    }
+}
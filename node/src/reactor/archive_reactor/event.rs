@@ -0,0 +1,167 @@
+use std::fmt::{self, Debug, Display, Formatter};
+
+use derive_more::From;
+use serde::Serialize;
+
+use crate::{
+    components::{contract_runtime, event_stream_server, rest_server, rpc_server, storage},
+    effect::{
+        announcements::{
+            ContractRuntimeAnnouncement, ControlAnnouncement, FatalAnnouncement,
+            MetaBlockAnnouncement, UnexecutedBlockAnnouncement,
+        },
+        requests::{
+            AcceptDeployRequest, BlockSynchronizerRequest, ChainspecRawBytesRequest,
+            ConsensusRequest, ContractRuntimeRequest, MetricsRequest, NetworkInfoRequest,
+            NetworkRequest, ReactorStatusRequest, RestRequest, RpcRequest, StorageRequest,
+            UpgradeWatcherRequest,
+        },
+    },
+    protocol::Message,
+    reactor::ReactorEvent,
+};
+
+/// Top-level event for the archive reactor.
+#[derive(Debug, From, Serialize)]
+#[must_use]
+pub(crate) enum ArchiveEvent {
+    #[from]
+    ControlAnnouncement(ControlAnnouncement),
+    #[from]
+    FatalAnnouncement(FatalAnnouncement),
+
+    #[from]
+    Storage(storage::Event),
+    #[from]
+    StorageRequest(#[serde(skip_serializing)] StorageRequest),
+    #[from]
+    ContractRuntime(#[serde(skip_serializing)] contract_runtime::Event),
+    #[from]
+    ContractRuntimeRequest(#[serde(skip_serializing)] ContractRuntimeRequest),
+    #[from]
+    RpcServer(#[serde(skip_serializing)] rpc_server::Event),
+    #[from]
+    RestServer(#[serde(skip_serializing)] rest_server::Event),
+    #[from]
+    EventStreamServer(#[serde(skip_serializing)] event_stream_server::Event),
+    #[from]
+    MetricsRequest(#[serde(skip_serializing)] MetricsRequest),
+
+    // Requests answered by the reactor itself, as the components serving them in a regular node
+    // are not running.
+    #[from]
+    ChainspecRawBytesRequest(#[serde(skip_serializing)] ChainspecRawBytesRequest),
+    #[from]
+    UpgradeWatcherRequest(#[serde(skip_serializing)] UpgradeWatcherRequest),
+    #[from]
+    ConsensusRequest(#[serde(skip_serializing)] ConsensusRequest),
+    #[from]
+    NetworkInfoRequest(#[serde(skip_serializing)] NetworkInfoRequest),
+    #[from]
+    BlockSynchronizerRequest(#[serde(skip_serializing)] BlockSynchronizerRequest),
+    #[from]
+    ReactorStatusRequest(ReactorStatusRequest),
+    #[from]
+    AcceptDeployRequest(AcceptDeployRequest),
+
+    // Events required by the components' bounds, which are never raised in archive mode.
+    #[from]
+    NetworkRequest(#[serde(skip_serializing)] NetworkRequest<Message>),
+    #[from]
+    ContractRuntimeAnnouncement(#[serde(skip_serializing)] ContractRuntimeAnnouncement),
+    #[from]
+    MetaBlockAnnouncement(MetaBlockAnnouncement),
+    #[from]
+    UnexecutedBlockAnnouncement(UnexecutedBlockAnnouncement),
+}
+
+impl ReactorEvent for ArchiveEvent {
+    fn is_control(&self) -> bool {
+        matches!(self, ArchiveEvent::ControlAnnouncement(_))
+    }
+
+    fn try_into_control(self) -> Option<ControlAnnouncement> {
+        if let Self::ControlAnnouncement(ctrl_ann) = self {
+            Some(ctrl_ann)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn description(&self) -> &'static str {
+        match self {
+            ArchiveEvent::ControlAnnouncement(_) => "ControlAnnouncement",
+            ArchiveEvent::FatalAnnouncement(_) => "FatalAnnouncement",
+            ArchiveEvent::Storage(_) => "Storage",
+            ArchiveEvent::StorageRequest(_) => "StorageRequest",
+            ArchiveEvent::ContractRuntime(_) => "ContractRuntime",
+            ArchiveEvent::ContractRuntimeRequest(_) => "ContractRuntimeRequest",
+            ArchiveEvent::RpcServer(_) => "RpcServer",
+            ArchiveEvent::RestServer(_) => "RestServer",
+            ArchiveEvent::EventStreamServer(_) => "EventStreamServer",
+            ArchiveEvent::MetricsRequest(_) => "MetricsRequest",
+            ArchiveEvent::ChainspecRawBytesRequest(_) => "ChainspecRawBytesRequest",
+            ArchiveEvent::UpgradeWatcherRequest(_) => "UpgradeWatcherRequest",
+            ArchiveEvent::ConsensusRequest(_) => "ConsensusRequest",
+            ArchiveEvent::NetworkInfoRequest(_) => "NetworkInfoRequest",
+            ArchiveEvent::BlockSynchronizerRequest(_) => "BlockSynchronizerRequest",
+            ArchiveEvent::ReactorStatusRequest(_) => "ReactorStatusRequest",
+            ArchiveEvent::AcceptDeployRequest(_) => "AcceptDeployRequest",
+            ArchiveEvent::NetworkRequest(_) => "NetworkRequest",
+            ArchiveEvent::ContractRuntimeAnnouncement(_) => "ContractRuntimeAnnouncement",
+            ArchiveEvent::MetaBlockAnnouncement(_) => "MetaBlockAnnouncement",
+            ArchiveEvent::UnexecutedBlockAnnouncement(_) => "UnexecutedBlockAnnouncement",
+        }
+    }
+}
+
+impl Display for ArchiveEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveEvent::ControlAnnouncement(ctrl_ann) => write!(f, "control: {}", ctrl_ann),
+            ArchiveEvent::FatalAnnouncement(fatal_ann) => write!(f, "fatal: {}", fatal_ann),
+            ArchiveEvent::Storage(event) => write!(f, "storage: {}", event),
+            ArchiveEvent::StorageRequest(req) => write!(f, "storage request: {}", req),
+            ArchiveEvent::ContractRuntime(event) => write!(f, "contract runtime: {}", event),
+            ArchiveEvent::ContractRuntimeRequest(req) => {
+                write!(f, "contract runtime request: {}", req)
+            }
+            ArchiveEvent::RpcServer(event) => write!(f, "rpc server: {}", event),
+            ArchiveEvent::RestServer(event) => write!(f, "rest server: {}", event),
+            ArchiveEvent::EventStreamServer(event) => {
+                write!(f, "event stream server: {}", event)
+            }
+            ArchiveEvent::MetricsRequest(req) => write!(f, "metrics request: {}", req),
+            ArchiveEvent::ChainspecRawBytesRequest(req) => {
+                write!(f, "chainspec loader request: {}", req)
+            }
+            ArchiveEvent::UpgradeWatcherRequest(req) => {
+                write!(f, "upgrade watcher request: {}", req)
+            }
+            ArchiveEvent::ConsensusRequest(req) => write!(f, "consensus request: {:?}", req),
+            ArchiveEvent::NetworkInfoRequest(req) => write!(f, "network info request: {}", req),
+            ArchiveEvent::BlockSynchronizerRequest(req) => Display::fmt(req, f),
+            ArchiveEvent::ReactorStatusRequest(req) => Display::fmt(req, f),
+            ArchiveEvent::AcceptDeployRequest(req) => Display::fmt(req, f),
+            ArchiveEvent::NetworkRequest(req) => write!(f, "network request: {}", req),
+            ArchiveEvent::ContractRuntimeAnnouncement(ann) => {
+                write!(f, "block-executor announcement: {}", ann)
+            }
+            ArchiveEvent::MetaBlockAnnouncement(ann) => Display::fmt(ann, f),
+            ArchiveEvent::UnexecutedBlockAnnouncement(ann) => Display::fmt(ann, f),
+        }
+    }
+}
+
+impl From<RpcRequest> for ArchiveEvent {
+    fn from(request: RpcRequest) -> Self {
+        ArchiveEvent::RpcServer(rpc_server::Event::RpcRequest(request))
+    }
+}
+
+impl From<RestRequest> for ArchiveEvent {
+    fn from(request: RestRequest) -> Self {
+        ArchiveEvent::RestServer(rest_server::Event::RestRequest(request))
+    }
+}
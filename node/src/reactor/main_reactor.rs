@@ -241,6 +241,9 @@ impl reactor::Reactor for MainReactor {
             MainEvent::MetaBlockAnnouncement(MetaBlockAnnouncement(meta_block)) => {
                 self.handle_meta_block(effect_builder, rng, meta_block)
             }
+            MainEvent::CreatedFinalitySignature(finality_signature) => {
+                self.handle_created_finality_signature(effect_builder, rng, *finality_signature)
+            }
             MainEvent::UnexecutedBlockAnnouncement(UnexecutedBlockAnnouncement(block_height)) => {
                 let only_from_available_block_range = true;
                 if let Ok(Some(block_header)) = self
@@ -364,6 +367,7 @@ impl reactor::Reactor for MainReactor {
                         );
                         effects.extend(self.dispatch_event(effect_builder, rng, event));
                     }
+                    PeerBehaviorAnnouncement::BehaviorObserved { .. } => {}
                 }
                 effects.extend(self.dispatch_event(
                     effect_builder,
@@ -1003,20 +1007,6 @@ impl reactor::Reactor for MainReactor {
 
         let trusted_hash = config.value().node.trusted_hash;
         let (root_dir, config) = config.into_parts();
-        let (our_secret_key, our_public_key) = config.consensus.load_keys(&root_dir)?;
-        let validator_matrix = ValidatorMatrix::new(
-            chainspec.core_config.finality_threshold_fraction,
-            chainspec
-                .protocol_config
-                .global_state_update
-                .as_ref()
-                .and_then(|global_state_update| global_state_update.validators.clone()),
-            chainspec.protocol_config.activation_point.era_id(),
-            our_secret_key.clone(),
-            our_public_key.clone(),
-            chainspec.core_config.auction_delay,
-        );
-
         let storage_config = WithDir::new(&root_dir, config.storage.clone());
 
         let hard_reset_to_start_of_era = chainspec.hard_reset_to_start_of_era();
@@ -1032,6 +1022,27 @@ impl reactor::Reactor for MainReactor {
             config.node.force_resync,
         )?;
 
+        // A validator that lost its double-signing protection log could equivocate, so that must
+        // not go unnoticed: check whether we were a validator in the most recent eras.
+        let recent_switch_block_headers = storage.read_highest_switch_block_headers(2)?;
+        let signer = config.consensus.load_signer(&root_dir, |public_key| {
+            recent_switch_block_headers
+                .iter()
+                .filter_map(|header| header.next_era_validator_weights())
+                .any(|weights| weights.contains_key(public_key))
+        })?;
+        let validator_matrix = ValidatorMatrix::new(
+            chainspec.core_config.finality_threshold_fraction,
+            chainspec
+                .protocol_config
+                .global_state_update
+                .as_ref()
+                .and_then(|global_state_update| global_state_update.validators.clone()),
+            chainspec.protocol_config.activation_point.era_id(),
+            Arc::clone(&signer),
+            chainspec.core_config.auction_delay,
+        );
+
         let max_delegators_per_validator =
             if chainspec.core_config.max_delegators_per_validator == 0 {
                 None
@@ -1064,10 +1075,11 @@ impl reactor::Reactor for MainReactor {
         let network = Network::new(
             config.network.clone(),
             network_identity,
-            Some((our_secret_key.clone(), our_public_key.clone())),
+            Some(Arc::clone(&signer)),
             registry,
             chainspec.as_ref(),
             validator_matrix.clone(),
+            Some(storage.root_path()),
         )?;
 
         let address_gossiper = Gossiper::<{ GossipedAddress::ID_IS_COMPLETE_ITEM }, _>::new(
@@ -1112,7 +1124,8 @@ impl reactor::Reactor for MainReactor {
             "deploy_gossiper",
             config.gossip,
             registry,
-        )?;
+        )?
+        .with_reconciliation(config.gossip.deploy_reconciliation());
         let finality_signature_gossiper =
             Gossiper::<{ FinalitySignature::ID_IS_COMPLETE_ITEM }, _>::new(
                 "finality_signature_gossiper",
@@ -1123,8 +1136,7 @@ impl reactor::Reactor for MainReactor {
         // consensus
         let consensus = EraSupervisor::new(
             storage.root_path(),
-            our_secret_key,
-            our_public_key,
+            signer,
             config.consensus,
             chainspec.clone(),
             registry,
@@ -1238,6 +1250,42 @@ impl MainReactor {
         effects
     }
 
+    fn handle_created_finality_signature(
+        &mut self,
+        effect_builder: EffectBuilder<MainEvent>,
+        rng: &mut NodeRng,
+        finality_signature: FinalitySignature,
+    ) -> Effects<MainEvent> {
+        debug!(%finality_signature, "registering our finality signature");
+        let mut effects = reactor::wrap_effects(
+            MainEvent::Storage,
+            effect_builder
+                .put_finality_signature_to_storage(finality_signature.clone())
+                .ignore(),
+        );
+
+        effects.extend(reactor::wrap_effects(
+            MainEvent::BlockAccumulator,
+            self.block_accumulator.handle_event(
+                effect_builder,
+                rng,
+                block_accumulator::Event::CreatedFinalitySignature {
+                    finality_signature: Box::new(finality_signature.clone()),
+                },
+            ),
+        ));
+
+        let era_id = finality_signature.era_id;
+        let payload = Message::FinalitySignature(Box::new(finality_signature));
+        effects.extend(reactor::wrap_effects(
+            MainEvent::Network,
+            effect_builder
+                .broadcast_message_to_validators(payload, era_id)
+                .ignore(),
+        ));
+        effects
+    }
+
     fn handle_meta_block(
         &mut self,
         effect_builder: EffectBuilder<MainEvent>,
@@ -1332,44 +1380,18 @@ impl MainReactor {
         }
 
         if state.register_we_have_tried_to_sign().was_updated() {
-            // When this node is a validator in this era, sign and announce.
-            if let Some(finality_signature) = self
+            // When this node is a validator in this era, sign and announce once signed.
+            if let Some(signing) = self
                 .validator_matrix
                 .create_finality_signature(block.header())
             {
-                debug!(
-                    %finality_signature,
-                    "MetaBlock: registering finality signature: {} {}",
-                    block.height(),
-                    block.hash(),
-                );
-
-                effects.extend(reactor::wrap_effects(
-                    MainEvent::Storage,
-                    effect_builder
-                        .put_finality_signature_to_storage(finality_signature.clone())
-                        .ignore(),
-                ));
-
-                effects.extend(reactor::wrap_effects(
-                    MainEvent::BlockAccumulator,
-                    self.block_accumulator.handle_event(
-                        effect_builder,
-                        rng,
-                        block_accumulator::Event::CreatedFinalitySignature {
-                            finality_signature: Box::new(finality_signature.clone()),
-                        },
-                    ),
-                ));
-
-                let era_id = finality_signature.era_id;
-                let payload = Message::FinalitySignature(Box::new(finality_signature));
-                effects.extend(reactor::wrap_effects(
-                    MainEvent::Network,
-                    effect_builder
-                        .broadcast_message_to_validators(payload, era_id)
-                        .ignore(),
-                ));
+                effects.extend(signing.events(|maybe_finality_signature| {
+                    maybe_finality_signature
+                        .map(|finality_signature| {
+                            MainEvent::CreatedFinalitySignature(Box::new(finality_signature))
+                        })
+                        .into_iter()
+                }));
             }
         }
 
@@ -1434,6 +1456,16 @@ impl MainReactor {
                 block.height(),
                 block.hash(),
             );
+            effects.extend(reactor::wrap_effects(
+                MainEvent::Consensus,
+                self.consensus.handle_event(
+                    effect_builder,
+                    rng,
+                    consensus::Event::BlockCompleted {
+                        height: block.height(),
+                    },
+                ),
+            ));
         }
 
         debug!(
@@ -225,6 +225,10 @@ impl MainReactor {
                     }
                 }
             }
+            ReactorState::Archive => (
+                Duration::ZERO,
+                fatal!(effect_builder, "main reactor cannot run in archive mode").ignore(),
+            ),
         }
     }
 
@@ -1,14 +1,14 @@
 use thiserror::Error;
 
 use casper_execution_engine::core::engine_state;
-use casper_types::{bytesrepr, crypto::ErrorExt as CryptoError};
+use casper_types::bytesrepr;
 
 use crate::{
     components::{
-        contract_runtime, contract_runtime::BlockExecutionError, diagnostics_port, network,
-        storage, upgrade_watcher,
+        consensus::SignerError, contract_runtime, contract_runtime::BlockExecutionError,
+        diagnostics_port, network, storage, upgrade_watcher,
     },
-    utils::{ListeningError, LoadError},
+    utils::ListeningError,
 };
 
 /// Error type returned by the validator reactor.
@@ -58,9 +58,9 @@ pub(crate) enum Error {
     #[error("diagnostics port: {0}")]
     DiagnosticsPort(#[from] diagnostics_port::Error),
 
-    /// Error while loading the signing key pair.
-    #[error("signing key pair load error: {0}")]
-    LoadSigningKeyPair(#[from] LoadError<CryptoError>),
+    /// Error while setting up the signer.
+    #[error("signer error: {0}")]
+    Signer(#[from] SignerError),
 }
 
 impl From<bytesrepr::Error> for Error {
@@ -70,4 +70,6 @@ pub enum ReactorState {
     Validate,
     /// Node should be shut down for upgrade.
     ShutdownForUpgrade,
+    /// Node is serving queries from read-only storage, without following the chain.
+    Archive,
 }
@@ -37,7 +37,7 @@ use crate::{
         self, filter_reactor::FilterReactor, network::TestingNetwork, ConditionCheckReactor,
     },
     types::{
-        chainspec::{AccountConfig, AccountsConfig, ValidatorConfig},
+        chainspec::{AccountConfig, AccountsConfig, ConsensusProtocolName, ValidatorConfig},
         ActivationPoint, AvailableBlockRange, Block, BlockHash, BlockHeader, BlockPayload,
         Chainspec, ChainspecRawBytes, Deploy, ExitCode, NodeId, NodeRng, SyncHandling,
     },
@@ -163,10 +163,9 @@ impl TestChain {
         self.first_node_port
     }
 
-    /// Creates an initializer/validator configuration for the `idx`th validator.
-    fn create_node_config(&mut self, idx: usize, first_node_port: u16) -> Config {
-        // Set the network configuration.
-        let mut cfg = Config {
+    /// Creates a configuration for the `idx`th node with only the network settings set.
+    fn network_config(idx: usize, first_node_port: u16) -> Config {
+        Config {
             network: if idx == 0 {
                 network::Config::default_local_net_first_node(first_node_port)
             } else {
@@ -174,7 +173,13 @@ impl TestChain {
             },
             gossip: gossiper::Config::new_with_small_timeouts(),
             ..Default::default()
-        };
+        }
+    }
+
+    /// Creates an initializer/validator configuration for the `idx`th validator.
+    fn create_node_config(&mut self, idx: usize, first_node_port: u16) -> Config {
+        // Set the network configuration.
+        let mut cfg = Self::network_config(idx, first_node_port);
 
         // Additionally set up storage in a temporary directory.
         let (storage_cfg, temp_dir) = storage::Config::default_for_tests();
@@ -191,6 +196,19 @@ impl TestChain {
         cfg
     }
 
+    /// Creates a configuration for the `idx`th validator that uses the storage and secret key
+    /// created by `create_node_config`, as if the node was restarted.
+    fn restarted_node_config(&self, idx: usize, first_node_port: u16) -> Config {
+        let mut cfg = Self::network_config(idx, first_node_port);
+        let temp_dir = &self.storages[idx];
+        cfg.consensus.secret_key_path = External::Path(temp_dir.path().join("secret_key"));
+        cfg.storage = storage::Config {
+            path: temp_dir.path().join("lmdb"),
+            ..Default::default()
+        };
+        cfg
+    }
+
     async fn create_initialized_network(
         &mut self,
         rng: &mut NodeRng,
@@ -215,6 +233,36 @@ impl TestChain {
 
         Ok(network)
     }
+
+    /// Restarts the nodes of a network created by `create_initialized_network`, which must have
+    /// been dropped, with their previous storage but the given chainspec, e.g. after an upgrade.
+    async fn create_restarted_network(
+        &self,
+        rng: &mut NodeRng,
+        chainspec: Arc<Chainspec>,
+    ) -> anyhow::Result<TestingNetwork<FilterReactor<MainReactor>>> {
+        let root = RESOURCES_PATH.join("local");
+        // The old nodes' listening ports might not be available again yet.
+        let first_node_port = testing::unused_port_on_localhost();
+
+        let mut network: TestingNetwork<FilterReactor<MainReactor>> = TestingNetwork::new();
+
+        for idx in 0..self.keys.len() {
+            info!("restarting node {}", idx);
+            let cfg = self.restarted_node_config(idx, first_node_port);
+            network
+                .add_node_with_config_and_chainspec(
+                    WithDir::new(root.clone(), cfg),
+                    Arc::clone(&chainspec),
+                    Arc::clone(&self.chainspec_raw_bytes),
+                    rng,
+                )
+                .await
+                .expect("could not add node to reactor");
+        }
+
+        Ok(network)
+    }
 }
 
 /// Given an era number, returns a predicate to check if all of the nodes are in the specified era.
@@ -940,6 +988,106 @@ async fn dont_upgrade_without_switch_block() {
     }
 }
 
+/// Returns the consensus protocols of the open eras from `from_era_id` on, in the given node.
+fn consensus_protocols(
+    runner: &Runner<ConditionCheckReactor<FilterReactor<MainReactor>>>,
+    from_era_id: EraId,
+) -> Vec<Option<ConsensusProtocolName>> {
+    runner
+        .main_reactor()
+        .consensus()
+        .open_eras()
+        .range(from_era_id..)
+        .map(|(_, era)| era.consensus_protocol())
+        .collect()
+}
+
+#[tokio::test]
+async fn should_switch_from_highway_to_zug_at_upgrade() {
+    testing::init_logging();
+
+    let mut rng = crate::new_rng();
+
+    const NETWORK_SIZE: usize = 3;
+    const INITIALIZATION_TIMEOUT: Duration = Duration::from_secs(20);
+    const ACTIVATION_ERA: EraId = EraId::new(2);
+    let upgraded_version = ProtocolVersion::from_parts(2, 0, 0);
+
+    let mut chain = TestChain::new(&mut rng, NETWORK_SIZE, None);
+    chain.chainspec_mut().core_config.consensus_protocol = ConsensusProtocolName::Highway;
+    chain.chainspec_mut().core_config.minimum_era_height = 2;
+    chain.chainspec_mut().core_config.era_duration = TimeDiff::from_millis(0);
+    chain.chainspec_mut().core_config.minimum_block_time = "1second".parse().unwrap();
+
+    let mut net = chain
+        .create_initialized_network(&mut rng)
+        .await
+        .expect("network initialization failed");
+
+    // Wait until initialization is finished, so upgrade watcher won't reject test requests.
+    net.settle_on(
+        &mut rng,
+        move |nodes: &Nodes| {
+            nodes
+                .values()
+                .all(|runner| !matches!(runner.main_reactor().state, ReactorState::Initialize))
+        },
+        INITIALIZATION_TIMEOUT,
+    )
+    .await;
+
+    // An upgrade is scheduled for era 2, after the switch block in era 1 (height 2).
+    for runner in net.runners_mut() {
+        runner
+            .process_injected_effects(|effect_builder| {
+                let upgrade =
+                    NextUpgrade::new(ActivationPoint::EraId(ACTIVATION_ERA), upgraded_version);
+                effect_builder
+                    .announce_upgrade_activation_point_read(upgrade)
+                    .ignore()
+            })
+            .await;
+    }
+
+    // Run until the nodes shut down for the upgrade.
+    net.settle_on_exit(&mut rng, ExitCode::Success, Duration::from_secs(90))
+        .await;
+    for runner in net.nodes().values() {
+        let protocols = consensus_protocols(runner, EraId::new(0));
+        assert!(!protocols.is_empty());
+        assert!(protocols
+            .iter()
+            .all(|protocol| *protocol == Some(ConsensusProtocolName::Highway)));
+    }
+    drop(net);
+
+    // Restart all nodes with the upgraded chainspec, which switches to Zug. No consensus state is
+    // handed over: The nodes start the new protocol from the upgrade's immediate switch block.
+    let mut upgraded_chainspec = (*chain.chainspec()).clone();
+    upgraded_chainspec.protocol_config.version = upgraded_version;
+    upgraded_chainspec.protocol_config.activation_point = ActivationPoint::EraId(ACTIVATION_ERA);
+    upgraded_chainspec.core_config.consensus_protocol = ConsensusProtocolName::Zug;
+    let mut net = chain
+        .create_restarted_network(&mut rng, Arc::new(upgraded_chainspec))
+        .await
+        .expect("network restart failed");
+
+    // The network keeps finalizing blocks and completing eras with Zug.
+    net.settle_on(
+        &mut rng,
+        has_completed_era(ACTIVATION_ERA.successor()),
+        Duration::from_secs(120),
+    )
+    .await;
+    for runner in net.nodes().values() {
+        let protocols = consensus_protocols(runner, ACTIVATION_ERA);
+        assert!(!protocols.is_empty());
+        assert!(protocols
+            .iter()
+            .all(|protocol| *protocol == Some(ConsensusProtocolName::Zug)));
+    }
+}
+
 #[tokio::test]
 async fn should_store_finalized_approvals() {
     testing::init_logging();
@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use thiserror::Error;
+use tracing::{info, warn};
+
+use casper_execution_engine::{
+    shared::newtypes::CorrelationId,
+    storage::{
+        error::lmdb::Error as GlobalStateError, global_state::lmdb::LmdbGlobalState,
+        transaction_source::lmdb::LmdbEnvironment, trie_store::lmdb::LmdbTrieStore,
+    },
+};
+
+use crate::{
+    components::storage::{FatalStorageError, Storage},
+    reactor::main_reactor::Config,
+    types::{chainspec, Chainspec, ChainspecRawBytes},
+    utils::{Loadable, WithDir},
+};
+
+/// Error returned if the storage could not be verified.
+///
+/// Note that faults found in the stored data are not errors, they are part of the outcome.
+#[derive(Debug, Error)]
+pub(crate) enum Error {
+    /// Error loading the chainspec.
+    #[error("error loading chainspec: {0}")]
+    LoadChainspec(chainspec::Error),
+
+    /// Error opening or reading the storage.
+    #[error("error reading storage: {0}")]
+    Storage(#[from] FatalStorageError),
+
+    /// Error opening or reading the global state.
+    #[error("error reading global state: {0}")]
+    GlobalState(#[from] GlobalStateError),
+}
+
+/// Verifies the integrity of the storage and, unless `skip_global_state` is set, of the global
+/// state tries of all complete blocks.
+///
+/// Neither database is modified. Every fault found is logged. Returns `true` if no faults were
+/// found.
+pub(crate) fn verify_storage(
+    config: WithDir<Config>,
+    skip_global_state: bool,
+) -> Result<bool, Error> {
+    let (root, config) = config.into_parts();
+    let (chainspec, _) =
+        <(Chainspec, ChainspecRawBytes)>::from_path(&root).map_err(Error::LoadChainspec)?;
+
+    let storage = Storage::open_read_only(
+        &WithDir::new(&root, config.storage),
+        &chainspec.network_config.name,
+        chainspec.deploy_config.max_ttl.into(),
+        chainspec.core_config.recent_era_count(),
+        None,
+    )?;
+
+    info!("verifying blocks, finality signatures and deploys");
+    let report = storage.check_integrity()?;
+    for fault in &report.faults {
+        warn!(%fault, "storage fault");
+    }
+    info!(
+        block_headers_checked = report.block_headers_checked,
+        block_bodies_checked = report.block_bodies_checked,
+        signatures_checked = report.signatures_checked,
+        signer_checks_skipped = report.signer_checks_skipped,
+        faults = report.faults.len(),
+        "storage verified"
+    );
+    let mut fault_count = report.faults.len();
+
+    if skip_global_state {
+        info!("skipping global state verification");
+    } else {
+        info!(
+            state_roots = report.complete_state_roots.len(),
+            "verifying global state"
+        );
+        let environment = Arc::new(LmdbEnvironment::open_read_only(
+            storage.root_path(),
+            config.contract_runtime.max_global_state_size_or_default(),
+            config.contract_runtime.max_readers_or_default(),
+        )?);
+        let trie_store = Arc::new(LmdbTrieStore::open(&environment, None)?);
+        let global_state = LmdbGlobalState::open(environment, trie_store)?;
+        let trie_faults =
+            global_state.check_integrity(CorrelationId::new(), report.complete_state_roots)?;
+        for fault in &trie_faults {
+            warn!(%fault, "global state fault");
+        }
+        info!(faults = trie_faults.len(), "global state verified");
+        fault_count += trie_faults.len();
+    }
+
+    Ok(fault_count == 0)
+}
@@ -3,6 +3,7 @@
 //! Contains various parts and components to aid writing tests and simulations using the
 //! `casper-node` library.
 
+pub(crate) mod capture_replay;
 mod condition_check_reactor;
 mod fake_deploy_acceptor;
 pub(crate) mod filter_reactor;
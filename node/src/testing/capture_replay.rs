@@ -0,0 +1,59 @@
+//! Replay of captured network traffic.
+//!
+//! Feeds the messages a node received, as recorded by the network component's capture mode, into a
+//! test reactor, to reproduce the exact sequence of messages which triggered a misbehavior.
+
+use serde::Serialize;
+use tracing::debug;
+
+use casper_types::testing::TestRng;
+
+use crate::{
+    components::network::{CaptureDirection, CaptureRecord, FromIncoming, Message},
+    effect::EffectExt,
+    reactor::{Reactor, Runner, TryCrankOutcome},
+};
+
+/// Replays the payloads of all incoming messages of a capture, in order.
+///
+/// Each payload is announced as received from the peer it was captured from, after which the
+/// reactor is cranked until it has no more events to process. Outgoing messages, as well as pings
+/// and pongs, are skipped, since the networking component itself is bypassed.
+///
+/// Returns the number of replayed payloads, stopping early if the reactor exits.
+pub(crate) async fn replay_capture<R, P>(
+    runner: &mut Runner<R>,
+    rng: &mut TestRng,
+    records: impl IntoIterator<Item = CaptureRecord<Message<P>>>,
+) -> usize
+where
+    R: Reactor,
+    R::Event: Serialize + FromIncoming<P>,
+    R::Error: From<prometheus::Error>,
+    P: Send + 'static,
+{
+    let mut replayed = 0;
+    for record in records {
+        let payload = match (record.direction, record.message) {
+            (CaptureDirection::Incoming, Message::Payload(payload)) => payload,
+            _ => continue,
+        };
+        let sender = record.peer_id;
+        debug!(%sender, timestamp = %record.timestamp, "replaying captured message");
+        runner
+            .process_injected_effects(|effect_builder| {
+                effect_builder.announce_incoming(sender, payload).ignore()
+            })
+            .await;
+        replayed += 1;
+
+        loop {
+            match runner.try_crank(rng).await {
+                TryCrankOutcome::ProcessedAnEvent => {}
+                TryCrankOutcome::NoEventsToProcess => break,
+                TryCrankOutcome::ShouldExit(_) | TryCrankOutcome::Exited => return replayed,
+            }
+        }
+    }
+    replayed
+}
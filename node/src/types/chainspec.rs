@@ -58,7 +58,7 @@ const CHAINSPEC_NETWORK_MESSAGE_SAFETY_MARGIN: usize = 256;
 
 /// A collection of configuration settings describing the state of the system at genesis and after
 /// upgrades to basic system functionality occurring after genesis.
-#[derive(DataSize, PartialEq, Eq, Serialize, Debug)]
+#[derive(Clone, DataSize, PartialEq, Eq, Serialize, Debug)]
 pub struct Chainspec {
     /// Protocol config.
     #[serde(rename = "protocol")]
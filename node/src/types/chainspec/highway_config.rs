@@ -17,7 +17,7 @@ use casper_types::{
 // Disallow unknown fields to ensure config files and command-line overrides contain valid keys.
 #[serde(deny_unknown_fields)]
 pub struct HighwayConfig {
-    /// The upper limit for Highway round lengths.
+    /// The upper limit for Highway round lengths and Zug proposal timeouts.
     pub maximum_round_length: TimeDiff,
     /// The factor by which rewards for a round are multiplied if the greatest summit has ≤50%
     /// quorum, i.e. no finality.
@@ -16,6 +16,9 @@ pub struct PeerEntry {
     pub node_id: String,
     /// Node address.
     pub address: String,
+    /// Reputation score of the node, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<i32>,
 }
 
 /// Map of peer IDs to network addresses.
@@ -28,6 +31,24 @@ impl PeersMap {
     pub fn into_inner(self) -> Vec<PeerEntry> {
         self.0
     }
+
+    /// Creates a map of peers to network addresses, including the reputation scores of peers.
+    ///
+    /// Peers without a reputation score have a score of zero.
+    pub(crate) fn with_scores(
+        peers: BTreeMap<NodeId, String>,
+        scores: &BTreeMap<NodeId, i32>,
+    ) -> Self {
+        let ret = peers
+            .into_iter()
+            .map(|(node_id, address)| PeerEntry {
+                score: Some(scores.get(&node_id).copied().unwrap_or_default()),
+                node_id: node_id.to_string(),
+                address,
+            })
+            .collect();
+        PeersMap(ret)
+    }
 }
 
 impl From<BTreeMap<NodeId, String>> for PeersMap {
@@ -37,6 +58,7 @@ impl From<BTreeMap<NodeId, String>> for PeersMap {
             .map(|(node_id, address)| PeerEntry {
                 node_id: node_id.to_string(),
                 address,
+                score: None,
             })
             .collect();
         PeersMap(ret)
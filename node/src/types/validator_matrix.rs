@@ -7,15 +7,21 @@ use std::{
 };
 
 use datasize::DataSize;
+use futures::Future;
 use itertools::Itertools;
 use num_rational::Ratio;
 use serde::Serialize;
 use static_assertions::const_assert;
-use tracing::info;
+use tracing::{error, info};
 
-use casper_types::{EraId, PublicKey, SecretKey, U512};
+#[cfg(test)]
+use casper_types::SecretKey;
+use casper_types::{EraId, PublicKey, U512};
 
 use super::{BlockHeader, FinalitySignature};
+#[cfg(test)]
+use crate::components::consensus::LocalSigner;
+use crate::components::consensus::{self, Signer, SigningRequest};
 
 const MAX_VALIDATOR_MATRIX_ENTRIES: usize = 6;
 const_assert!(MAX_VALIDATOR_MATRIX_ENTRIES % 2 == 0);
@@ -47,7 +53,8 @@ pub(crate) struct ValidatorMatrix {
     chainspec_activation_era: EraId,
     #[data_size(skip)]
     finality_threshold_fraction: Ratio<u64>,
-    secret_signing_key: Arc<SecretKey>,
+    #[data_size(skip)]
+    signer: Arc<dyn Signer>,
     public_signing_key: PublicKey,
     auction_delay: u64,
     retrograde_latch: Option<EraId>,
@@ -58,17 +65,17 @@ impl ValidatorMatrix {
         finality_threshold_fraction: Ratio<u64>,
         chainspec_validators: Option<BTreeMap<PublicKey, U512>>,
         chainspec_activation_era: EraId,
-        secret_signing_key: Arc<SecretKey>,
-        public_signing_key: PublicKey,
+        signer: Arc<dyn Signer>,
         auction_delay: u64,
     ) -> Self {
+        let public_signing_key = signer.public_key().clone();
         let inner = Arc::new(RwLock::new(BTreeMap::new()));
         ValidatorMatrix {
             inner,
             finality_threshold_fraction,
             chainspec_validators: chainspec_validators.map(Arc::new),
             chainspec_activation_era,
-            secret_signing_key,
+            signer,
             public_signing_key,
             auction_delay,
             retrograde_latch: None,
@@ -78,7 +85,8 @@ impl ValidatorMatrix {
     /// Creates a new validator matrix with just a single validator.
     #[cfg(test)]
     pub(crate) fn new_with_validator(secret_signing_key: Arc<SecretKey>) -> Self {
-        let public_signing_key = PublicKey::from(&*secret_signing_key);
+        let signer = Arc::new(LocalSigner::new(secret_signing_key));
+        let public_signing_key = signer.public_key().clone();
         let finality_threshold_fraction = Ratio::new(1, 3);
         let era_id = EraId::new(0);
         let weights = EraValidatorWeights::new(
@@ -92,7 +100,7 @@ impl ValidatorMatrix {
             chainspec_activation_era: EraId::from(0),
             finality_threshold_fraction,
             public_signing_key,
-            secret_signing_key,
+            signer,
             auction_delay: 1,
             retrograde_latch: None,
         }
@@ -262,22 +270,41 @@ impl ValidatorMatrix {
             .any(|validator_weights| validator_weights.is_validator(public_key))
     }
 
+    /// Returns a future creating our finality signature for the given block, if we are a validator
+    /// in its era.
+    ///
+    /// The signature is created on a blocking thread, as the signer may have to wait for a remote
+    /// signer. The future resolves to `None` if signing failed.
     pub(crate) fn create_finality_signature(
         &self,
         block_header: &BlockHeader,
-    ) -> Option<FinalitySignature> {
-        if self
+    ) -> Option<impl Future<Output = Option<FinalitySignature>> + Send + 'static> {
+        if !self
             .is_self_validator_in_era(block_header.era_id())
             .unwrap_or(false)
         {
-            return Some(FinalitySignature::create(
-                block_header.block_hash(),
-                block_header.era_id(),
-                &self.secret_signing_key,
-                self.public_signing_key.clone(),
-            ));
+            return None;
         }
-        None
+        let era_id = block_header.era_id();
+        let block_hash = block_header.block_hash();
+        let request = SigningRequest::FinalitySignature {
+            era_id,
+            block_height: block_header.height(),
+            block_hash,
+        };
+        let signer = Arc::clone(&self.signer);
+        let public_key = self.public_signing_key.clone();
+        Some(async move {
+            match consensus::sign_in_background(signer, request.clone()).await {
+                Ok(signature) => Some(FinalitySignature::new(
+                    block_hash, era_id, signature, public_key,
+                )),
+                Err(error) => {
+                    error!(%error, %request, "failed to create finality signature");
+                    None
+                }
+            }
+        })
     }
 
     fn read_inner(&self) -> RwLockReadGuard<BTreeMap<EraId, EraValidatorWeights>> {
@@ -0,0 +1,126 @@
+//! Rendering of an era as a Graphviz graph.
+//!
+//! Every validator's units form a cluster, connected by their swimlane. Citations of other
+//! validators' units are shown as dashed edges, but only the ones that are new compared to the
+//! creator's previous unit, since the full panoramas would add an edge to every validator for
+//! every unit.
+
+use std::fmt::Write;
+
+use casper_hashing::Digest;
+
+use crate::{
+    dump::Unit,
+    view::{self, EraView},
+};
+
+/// Renders the era as a Graphviz graph in the DOT language.
+pub(crate) fn render(view: &EraView) -> String {
+    let dump = view.dump;
+    let state = &dump.highway_state;
+    let mut out = String::new();
+    let _ = writeln!(out, "digraph \"era {}\" {{", dump.id);
+    let _ = writeln!(
+        out,
+        "  graph [rankdir=LR, fontname=\"monospace\", labelloc=t, label=\"Era {} starting at \
+         {}, height {}; {} units, ftt {}\"];",
+        dump.id,
+        dump.start_time,
+        dump.start_height,
+        state.units.len(),
+        view.ftt
+    );
+    let _ = writeln!(
+        out,
+        "  node [shape=box, style=\"rounded,filled\", fillcolor=white, fontname=\"monospace\", \
+         fontsize=10];"
+    );
+    let _ = writeln!(out, "  edge [fontname=\"monospace\", fontsize=8];");
+
+    for (idx, validator) in (0..).zip(&view.validators) {
+        let _ = writeln!(out, "  subgraph \"cluster_v{}\" {{", idx);
+        let mut label = format!("{} (weight {})", view.validator_name(idx), validator.weight);
+        if let Some(fault) = &validator.fault {
+            let _ = write!(label, "\\n{}", fault);
+        }
+        let color = if view.is_faulty(idx) { "red" } else { "gray" };
+        let _ = writeln!(out, "    label=\"{}\"; color={};", label, color);
+        for (hash, unit) in view.units.iter().filter(|(_, unit)| unit.creator == idx) {
+            let _ = writeln!(out, "    {};", unit_node(view, hash, unit));
+        }
+        for (n, wire_unit) in view.missing_evidence(idx).into_iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "    \"e{}_{}\" [label=\"evidence #{}\\n{}\", color=red, fontcolor=red, \
+                 style=\"rounded,dashed\"];",
+                idx,
+                n,
+                wire_unit.seq_number,
+                view::time_of_day(wire_unit.timestamp)
+            );
+        }
+        let _ = writeln!(out, "  }}");
+    }
+
+    for (hash, unit) in &view.units {
+        if let Some(previous) = unit.previous() {
+            let _ = writeln!(
+                out,
+                "  \"u{:x}\" -> \"u{:x}\" [penwidth=2, weight=10];",
+                previous, hash
+            );
+        }
+        for cited in view.new_citations(unit) {
+            let _ = writeln!(
+                out,
+                "  \"u{:x}\" -> \"u{:x}\" [style=dashed, color=gray];",
+                cited, hash
+            );
+        }
+        for endorsed in &unit.endorsed {
+            let _ = writeln!(
+                out,
+                "  \"u{:x}\" -> \"u{:x}\" [style=dotted, color=darkgreen, label=\"endorses\", \
+                 constraint=false];",
+                hash, endorsed
+            );
+        }
+    }
+    let _ = writeln!(out, "}}");
+    out
+}
+
+/// Returns the node statement for a unit.
+fn unit_node(view: &EraView, hash: &Digest, unit: &Unit) -> String {
+    let mut label = format!("#{} {}", unit.seq_number, view::short_hash(hash));
+    let _ = write!(label, "\\n{}", view::time_of_day(unit.timestamp));
+    let mut attrs = vec![format!("tooltip=\"{:x}\"", hash)];
+    if let Some(proposal) = view.proposals.get(hash) {
+        let _ = write!(
+            label,
+            "\\nblock {} level {}",
+            proposal.height,
+            proposal.summit.level()
+        );
+        attrs.push("fillcolor=lightblue".to_string());
+    }
+    if let Some(level) = view.summit_level(unit) {
+        let _ = write!(label, "\\nsummit L{}", level);
+    }
+    let endorsers = view.dump.highway_state.endorsers(hash);
+    if !endorsers.is_empty() {
+        let _ = write!(label, "\\nendorsed by {}", endorsers.len());
+        attrs.push("color=darkgreen, peripheries=2".to_string());
+    }
+    if view.is_evidence(unit) {
+        attrs.push("fillcolor=salmon, color=red, penwidth=2".to_string());
+    } else if view.is_faulty(unit.creator) {
+        attrs.push("color=red".to_string());
+    }
+    format!(
+        "\"u{:x}\" [label=\"{}\", {}]",
+        hash,
+        label,
+        attrs.join(", ")
+    )
+}
@@ -0,0 +1,345 @@
+//! Deserializable mirror of the node's `EraDump`.
+//!
+//! The node's consensus types are private to the `casper-node` crate, so this module declares the
+//! subset of their fields that is needed for rendering. Only the JSON output format of the
+//! diagnostics port's `dump-consensus` command is supported: unlike bincode, JSON is
+//! self-describing, so all fields that are not declared here are simply skipped.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    iter,
+};
+
+use anyhow::{anyhow, Context};
+use serde::{de::IgnoredAny, Deserialize};
+
+use casper_hashing::Digest;
+use casper_types::{EraId, PublicKey, TimeDiff, Timestamp, U512};
+
+/// Debug dump of an era, as produced by the diagnostics port.
+#[derive(Debug, Deserialize)]
+pub(crate) struct EraDump {
+    /// The era that was dumped.
+    pub(crate) id: EraId,
+    /// The scheduled starting time of this era.
+    pub(crate) start_time: Timestamp,
+    /// The height of this era's first block.
+    pub(crate) start_height: u64,
+    /// Validators that have been faulty in any of the recent switch blocks.
+    pub(crate) faulty: BTreeSet<PublicKey>,
+    /// Validators that are excluded from proposing new blocks.
+    pub(crate) cannot_propose: BTreeSet<PublicKey>,
+    /// Accusations collected in this era so far.
+    pub(crate) accusations: BTreeSet<PublicKey>,
+    /// The validator weights, ordered by public key, i.e. by validator index.
+    pub(crate) validators: BTreeMap<PublicKey, U512>,
+    /// The state of the era's Highway instance.
+    pub(crate) highway_state: State,
+}
+
+impl EraDump {
+    /// Parses an era dump from the given JSON input.
+    ///
+    /// The input may be the raw output of the diagnostics port, in which case the era dump is
+    /// preceded by the JSON command outcome.
+    pub(crate) fn from_json(input: &str) -> anyhow::Result<EraDump> {
+        let mut values = serde_json::Deserializer::from_str(input).into_iter::<IgnoredAny>();
+        let mut last_error = None;
+        loop {
+            let start = values.byte_offset();
+            match values.next() {
+                None => break,
+                Some(Err(error)) => return Err(error).context("input is not valid JSON"),
+                Some(Ok(IgnoredAny)) => {
+                    match serde_json::from_str(&input[start..values.byte_offset()]) {
+                        Ok(dump) => return Ok(dump),
+                        Err(error) => last_error = Some(error),
+                    }
+                }
+            }
+        }
+        match last_error {
+            Some(error) => Err(error).context("input does not contain an era dump"),
+            None => Err(anyhow!("input is empty")),
+        }
+    }
+}
+
+/// A validator's index in the era's validator set.
+pub(crate) type ValidatorIndex = u32;
+
+/// The protocol state of a Highway instance.
+#[derive(Debug, Deserialize)]
+pub(crate) struct State {
+    /// The validators' weights, scaled to fit into a `u64`.
+    pub(crate) weights: Vec<u64>,
+    /// All units imported so far, by hash.
+    pub(crate) units: BTreeMap<Digest, Unit>,
+    /// All blocks, by hash. A block has the same hash as the unit that proposed it.
+    pub(crate) blocks: BTreeMap<Digest, Block>,
+    /// All faulty validators, with the evidence against them if it is available.
+    pub(crate) faults: BTreeMap<ValidatorIndex, Fault>,
+    /// The full panorama, i.e. the latest units of every validator.
+    pub(crate) panorama: Vec<Observation>,
+    /// All units that have been endorsed by a quorum, with the endorsers' signatures.
+    pub(crate) endorsements: BTreeMap<Digest, Vec<Option<IgnoredAny>>>,
+    /// Endorsements of units that haven't reached a quorum yet, by endorser.
+    pub(crate) incomplete_endorsements: BTreeMap<Digest, BTreeMap<ValidatorIndex, IgnoredAny>>,
+    /// The timestamp of the latest ping or unit of each validator.
+    pub(crate) pings: Vec<Timestamp>,
+}
+
+impl State {
+    /// Returns the unit with the given hash, or an error if the unit is not in the dump.
+    pub(crate) fn unit(&self, hash: &Digest) -> anyhow::Result<&Unit> {
+        self.units
+            .get(hash)
+            .ok_or_else(|| anyhow!("unit {} missing from the dump", hash))
+    }
+
+    /// Returns the block with the given hash, or an error if the block is not in the dump.
+    pub(crate) fn block(&self, hash: &Digest) -> anyhow::Result<&Block> {
+        self.blocks
+            .get(hash)
+            .ok_or_else(|| anyhow!("block {} missing from the dump", hash))
+    }
+
+    /// Returns the total weight of all validators.
+    pub(crate) fn total_weight(&self) -> u64 {
+        self.weights.iter().sum()
+    }
+
+    /// Returns the weight of the given validator.
+    pub(crate) fn weight(&self, idx: ValidatorIndex) -> u64 {
+        self.weights[idx as usize]
+    }
+
+    /// Returns whether the validator is known to be faulty.
+    pub(crate) fn is_faulty(&self, idx: ValidatorIndex) -> bool {
+        self.faults.contains_key(&idx)
+    }
+
+    /// Returns the validators who endorsed the unit, whether or not the endorsements are complete.
+    pub(crate) fn endorsers(&self, hash: &Digest) -> Vec<ValidatorIndex> {
+        if let Some(signatures) = self.endorsements.get(hash) {
+            return (0..)
+                .zip(signatures)
+                .filter(|(_, signature)| signature.is_some())
+                .map(|(idx, _)| idx)
+                .collect();
+        }
+        self.incomplete_endorsements
+            .get(hash)
+            .map_or_else(Vec::new, |signatures| signatures.keys().copied().collect())
+    }
+
+    /// Returns an iterator over units (with hashes) by the same creator, in reverse chronological
+    /// order, starting with the specified unit.
+    ///
+    /// If a unit is missing from the dump, the iterator ends with an error.
+    pub(crate) fn swimlane<'a>(
+        &'a self,
+        hash: &'a Digest,
+    ) -> impl Iterator<Item = anyhow::Result<(&'a Digest, &'a Unit)>> {
+        let mut next = Some(hash);
+        iter::from_fn(move || {
+            let current = next.take()?;
+            let result = self.unit(current).map(|unit| {
+                next = unit.previous();
+                (current, unit)
+            });
+            Some(result)
+        })
+    }
+
+    /// Returns the ancestor of the block with the given `hash`, on the specified `height`, or
+    /// `None` if the block's height is lower than that.
+    pub(crate) fn find_ancestor_proposal<'a>(
+        &'a self,
+        hash: &'a Digest,
+        height: u64,
+    ) -> anyhow::Result<Option<&'a Digest>> {
+        let block = self.block(hash)?;
+        if block.height < height {
+            return Ok(None);
+        }
+        if block.height == height {
+            return Ok(Some(hash));
+        }
+        // We want to make the greatest step 2^i such that 2^i <= diff.
+        let max_i = log2(block.height - height) as usize;
+        let i = max_i.min(block.skip_idx.len().saturating_sub(1));
+        let ancestor = block
+            .skip_idx
+            .get(i)
+            .ok_or_else(|| anyhow!("block {} at height {} has no parent", hash, block.height))?;
+        self.find_ancestor_proposal(ancestor, height)
+    }
+}
+
+/// A unit sent to or received from the network.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Unit {
+    /// The list of latest units and faults observed by the creator of this unit.
+    pub(crate) panorama: Vec<Observation>,
+    /// The number of earlier units by the same creator.
+    pub(crate) seq_number: u64,
+    /// The validator who created and sent this unit.
+    pub(crate) creator: ValidatorIndex,
+    /// The block this unit votes for: either its own proposal or the creator's fork choice.
+    pub(crate) block: Digest,
+    /// A skip list index of the creator's swimlane, i.e. the previous unit by the same creator.
+    pub(crate) skip_idx: Vec<Digest>,
+    /// This unit's timestamp, in milliseconds since the epoch.
+    pub(crate) timestamp: Timestamp,
+    /// The length of the round this unit belongs to.
+    pub(crate) round_len: TimeDiff,
+    /// Units that this one claims are endorsed.
+    pub(crate) endorsed: BTreeSet<Digest>,
+}
+
+impl Unit {
+    /// Returns the creator's previous unit.
+    pub(crate) fn previous(&self) -> Option<&Digest> {
+        self.skip_idx.first()
+    }
+
+    /// Returns the ID of the round this unit belongs to, i.e. the round's start timestamp.
+    pub(crate) fn round_id(&self) -> Timestamp {
+        let round_len = self.round_len.millis().max(1);
+        Timestamp::from(self.timestamp.millis() - self.timestamp.millis() % round_len)
+    }
+}
+
+/// A block proposed in a unit.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Block {
+    /// The total number of ancestors, i.e. the height in the blockchain.
+    pub(crate) height: u64,
+    /// A skip list index of the block's ancestors.
+    pub(crate) skip_idx: Vec<Digest>,
+}
+
+/// A validator's status, as observed in a panorama.
+#[derive(Debug, Deserialize)]
+pub(crate) enum Observation {
+    /// No unit by that validator was observed yet.
+    None,
+    /// The validator's latest unit.
+    Correct(Digest),
+    /// The validator has been seen equivocating.
+    Faulty,
+}
+
+impl Observation {
+    /// Returns the unit hash, if this is a correct observation.
+    pub(crate) fn correct(&self) -> Option<&Digest> {
+        match self {
+            Observation::Correct(hash) => Some(hash),
+            Observation::None | Observation::Faulty => None,
+        }
+    }
+}
+
+/// A reason for a validator to be marked as faulty.
+#[derive(Debug, Deserialize)]
+pub(crate) enum Fault {
+    /// The validator was known to be malicious from the beginning.
+    Banned,
+    /// There is direct evidence of the validator's fault.
+    Direct(Evidence),
+    /// The validator is known to be faulty, but the evidence is not in this Highway instance.
+    Indirect,
+}
+
+/// Evidence that a validator is faulty.
+#[derive(Debug, Deserialize)]
+pub(crate) enum Evidence {
+    /// The validator produced two units with the same sequence number.
+    Equivocation(SignedWireUnit, SignedWireUnit),
+    /// The validator endorsed two conflicting units.
+    Endorsements {
+        /// The endorsement for `unit1`.
+        endorsement1: SignedEndorsement,
+        /// The unit with the lower (or equal) sequence number.
+        unit1: SignedWireUnit,
+        /// The endorsement for `unit2`, by the same creator as endorsement1.
+        endorsement2: SignedEndorsement,
+        /// The unit with the higher (or equal) sequence number, on a conflicting fork.
+        unit2: SignedWireUnit,
+    },
+}
+
+impl Evidence {
+    /// Returns the units the evidence consists of.
+    pub(crate) fn units(&self) -> [&WireUnit; 2] {
+        match self {
+            Evidence::Equivocation(unit1, unit2) | Evidence::Endorsements { unit1, unit2, .. } => {
+                [&unit1.hashed_wire_unit, &unit2.hashed_wire_unit]
+            }
+        }
+    }
+
+    /// Returns a short description of the fault.
+    pub(crate) fn description(&self) -> String {
+        match self {
+            Evidence::Equivocation(unit1, _) => format!(
+                "equivocation at sequence number {}",
+                unit1.hashed_wire_unit.seq_number
+            ),
+            Evidence::Endorsements {
+                endorsement1,
+                endorsement2,
+                ..
+            } => format!(
+                "endorsed conflicting units {} and {}",
+                endorsement1.endorsement.unit, endorsement2.endorsement.unit
+            ),
+        }
+    }
+}
+
+/// A signed unit as it is sent over the wire.
+#[derive(Debug, Deserialize)]
+pub(crate) struct SignedWireUnit {
+    /// The unit. Its hash is not part of the serialized form.
+    pub(crate) hashed_wire_unit: WireUnit,
+}
+
+/// A unit as it is sent over the wire.
+#[derive(Debug, Deserialize)]
+pub(crate) struct WireUnit {
+    /// The validator who created and sent this unit.
+    pub(crate) creator: ValidatorIndex,
+    /// The number of earlier units by the same creator.
+    pub(crate) seq_number: u64,
+    /// This unit's timestamp.
+    pub(crate) timestamp: Timestamp,
+    /// The proposed block, if any.
+    pub(crate) value: Option<IgnoredAny>,
+}
+
+/// A signed endorsement.
+#[derive(Debug, Deserialize)]
+pub(crate) struct SignedEndorsement {
+    /// The endorsement.
+    pub(crate) endorsement: Endorsement,
+}
+
+/// An endorsement of a unit.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Endorsement {
+    /// The endorsed unit.
+    pub(crate) unit: Digest,
+}
+
+/// Returns the base-2 logarithm of `x`, rounded down.
+fn log2(x: u64) -> u32 {
+    // Find the least power of two strictly greater than x and count its trailing zeros.
+    // Then subtract 1 to get the zeros of the greatest power of two less or equal than x.
+    x.saturating_add(1)
+        .checked_next_power_of_two()
+        .unwrap_or(0)
+        .trailing_zeros()
+        .saturating_sub(1)
+}
@@ -0,0 +1,396 @@
+//! Rendering of an era as a self-contained HTML page with an SVG timeline.
+//!
+//! The timeline has one lane per validator, with time on the horizontal axis and vertical lines at
+//! the start of every round. Below it, tables list the validators and all proposals with their
+//! summits.
+
+use std::{collections::BTreeSet, fmt::Write};
+
+use casper_hashing::Digest;
+use casper_types::{AsymmetricType, Timestamp};
+
+use crate::{
+    dump::Unit,
+    view::{self, EraView},
+};
+
+/// The height of a validator's lane, in pixels.
+const LANE_HEIGHT: f64 = 48.0;
+/// The width of the lane labels on the left, in pixels.
+const LABEL_WIDTH: f64 = 220.0;
+/// The height of the time axis at the top, in pixels.
+const AXIS_HEIGHT: f64 = 40.0;
+/// The minimum distance between two labels on the time axis, in pixels.
+const MIN_AXIS_LABEL_DISTANCE: f64 = 100.0;
+
+const STYLE: &str = "
+body { font-family: sans-serif; font-size: 13px; margin: 16px; }
+.timeline { overflow-x: auto; border: 1px solid #ccc; }
+table { border-collapse: collapse; margin-top: 16px; }
+th, td { border: 1px solid #ccc; padding: 2px 6px; text-align: left; }
+td.mono { font-family: monospace; }
+tr.faulty { background: #fdd; }
+.legend span { margin-right: 16px; }
+";
+
+/// Renders the era as an HTML page, with the given horizontal scale in pixels per second.
+pub(crate) fn render(view: &EraView, pixels_per_second: f64) -> String {
+    let dump = view.dump;
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Era {}</title>\n\
+         <style>{}</style>\n</head>\n<body>",
+        dump.id, STYLE
+    );
+    let _ = writeln!(
+        out,
+        "<h1>Era {}</h1>\n<p>Started at {}, height {}. {} units, {} proposals, {} faulty \
+         validators. Summits computed with a fault tolerance threshold of {} of {}.</p>",
+        dump.id,
+        dump.start_time,
+        dump.start_height,
+        dump.highway_state.units.len(),
+        view.proposals.len(),
+        view.validators
+            .iter()
+            .filter(|validator| validator.fault.is_some())
+            .count(),
+        view.ftt,
+        dump.highway_state.total_weight()
+    );
+    let _ = writeln!(
+        out,
+        "<p class=\"legend\"><span>&#9632; proposal</span><span>&#9679; unit</span>\
+         <span style=\"color:green\">&#9711; endorsed</span>\
+         <span style=\"color:red\">&#10005; evidence</span>\
+         <span>L<i>n</i>: level in the summit for the unit's block</span>\
+         <span style=\"color:#999\">&#8212; new citation</span></p>"
+    );
+    let _ = writeln!(out, "<div class=\"timeline\">");
+    timeline(&mut out, view, pixels_per_second / 1000.0);
+    let _ = writeln!(out, "</div>");
+    validator_table(&mut out, view);
+    proposal_table(&mut out, view);
+    let _ = writeln!(out, "</body>\n</html>");
+    out
+}
+
+/// Maps timestamps and validators to coordinates in the timeline.
+struct Layout {
+    /// The timestamp at the left edge of the timeline.
+    start: u64,
+    /// The horizontal scale, in pixels per millisecond.
+    pixels_per_ms: f64,
+}
+
+impl Layout {
+    fn x(&self, timestamp: Timestamp) -> f64 {
+        LABEL_WIDTH + timestamp.millis().saturating_sub(self.start) as f64 * self.pixels_per_ms
+    }
+
+    fn y(&self, idx: u32) -> f64 {
+        AXIS_HEIGHT + (f64::from(idx) + 0.5) * LANE_HEIGHT
+    }
+}
+
+/// Writes the SVG timeline.
+fn timeline(out: &mut String, view: &EraView, pixels_per_ms: f64) {
+    let state = &view.dump.highway_state;
+    let timestamps = || {
+        let unit_timestamps = view.units.iter().map(|(_, unit)| unit.timestamp);
+        let evidence_timestamps = view
+            .validators
+            .iter()
+            .flat_map(|validator| validator.evidence.iter().map(|unit| unit.timestamp));
+        unit_timestamps.chain(evidence_timestamps)
+    };
+    let start = timestamps()
+        .chain(Some(view.dump.start_time))
+        .min()
+        .unwrap_or(view.dump.start_time);
+    let end = timestamps().max().unwrap_or(start);
+    let layout = Layout {
+        start: start.millis(),
+        pixels_per_ms,
+    };
+    let width = layout.x(end) + LANE_HEIGHT;
+    let height = AXIS_HEIGHT + LANE_HEIGHT * view.validators.len() as f64;
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" \
+         font-family=\"monospace\" font-size=\"11\">",
+        width, height
+    );
+
+    // Lanes and their labels.
+    for (idx, validator) in (0..).zip(&view.validators) {
+        let top = layout.y(idx) - LANE_HEIGHT / 2.0;
+        let fill = if view.is_faulty(idx) {
+            "#fdd"
+        } else if idx % 2 == 0 {
+            "#f6f6f6"
+        } else {
+            "#fff"
+        };
+        let _ = writeln!(
+            out,
+            "<rect x=\"0\" y=\"{:.1}\" width=\"{:.0}\" height=\"{:.1}\" fill=\"{}\"/>",
+            top, width, LANE_HEIGHT, fill
+        );
+        let _ = writeln!(
+            out,
+            "<text x=\"4\" y=\"{:.1}\"><title>{}</title>{} w={}</text>",
+            layout.y(idx) + 4.0,
+            escape(&validator.public_key.to_hex()),
+            escape(&view.validator_name(idx)),
+            validator.weight
+        );
+    }
+
+    // Round boundaries.
+    let rounds: BTreeSet<_> = view.units.iter().map(|(_, unit)| unit.round_id()).collect();
+    let mut last_label_x = f64::NEG_INFINITY;
+    for round_id in rounds {
+        let x = layout.x(round_id);
+        let _ = writeln!(
+            out,
+            "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"#ccc\"/>",
+            x,
+            AXIS_HEIGHT / 2.0,
+            x,
+            height
+        );
+        if x - last_label_x >= MIN_AXIS_LABEL_DISTANCE {
+            let _ = writeln!(
+                out,
+                "<text x=\"{:.1}\" y=\"{:.1}\" fill=\"#666\">{}</text>",
+                x + 2.0,
+                AXIS_HEIGHT / 2.0,
+                view::time_of_day(round_id)
+            );
+            last_label_x = x;
+        }
+    }
+
+    // Edges: swimlanes, new citations and endorsements.
+    let pos = |unit: &Unit| (layout.x(unit.timestamp), layout.y(unit.creator));
+    for (_, unit) in &view.units {
+        let (x, y) = pos(unit);
+        let mut line = |from: &Digest, style: &str| {
+            if let Some(other) = state.units.get(from) {
+                let (x0, y0) = pos(other);
+                let _ = writeln!(
+                    out,
+                    "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" {}/>",
+                    x0, y0, x, y, style
+                );
+            }
+        };
+        if let Some(previous) = unit.previous() {
+            line(previous, "stroke=\"#555\" stroke-width=\"2\"");
+        }
+        for cited in view.new_citations(unit) {
+            line(cited, "stroke=\"#999\" stroke-opacity=\"0.5\"");
+        }
+        for endorsed in &unit.endorsed {
+            line(endorsed, "stroke=\"green\" stroke-dasharray=\"4 2\"");
+        }
+    }
+
+    // Units.
+    for (hash, unit) in &view.units {
+        let (x, y) = pos(unit);
+        let endorsers = state.endorsers(hash);
+        let (fill, stroke) = if view.is_evidence(unit) {
+            ("#f66", "red")
+        } else if view.is_faulty(unit.creator) {
+            ("#fcc", "red")
+        } else if view.proposals.contains_key(*hash) {
+            ("#4a90d9", "#333")
+        } else {
+            ("#fff", "#333")
+        };
+        let stroke = if endorsers.is_empty() {
+            stroke
+        } else {
+            "green"
+        };
+        let stroke_width = if endorsers.is_empty() { 1 } else { 3 };
+        let mut title = format!(
+            "{:x}\n{}\n#{} at {}, round length {}",
+            hash,
+            view.validator_name(unit.creator),
+            unit.seq_number,
+            unit.timestamp,
+            unit.round_len
+        );
+        if let Some(proposal) = view.proposals.get(*hash) {
+            let _ = write!(
+                title,
+                "\nproposes block {}, summit level {}",
+                proposal.height,
+                proposal.summit.level()
+            );
+        } else if let Some(block) = view.proposals.get(&unit.block) {
+            let _ = write!(title, "\nvotes for block {}", block.height);
+        }
+        if !endorsers.is_empty() {
+            let _ = write!(title, "\nendorsed by {:?}", endorsers);
+        }
+        let (shape, tag) = if view.proposals.contains_key(*hash) {
+            let rect = format!(
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"12\" height=\"12\"",
+                x - 6.0,
+                y - 6.0
+            );
+            (rect, "rect")
+        } else {
+            let circle = format!("<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"6\"", x, y);
+            (circle, "circle")
+        };
+        let _ = writeln!(
+            out,
+            "{} fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"><title>{}</title></{}>",
+            shape,
+            fill,
+            stroke,
+            stroke_width,
+            escape(&title),
+            tag
+        );
+        if let Some(level) = view.summit_level(unit) {
+            let _ = writeln!(
+                out,
+                "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" font-size=\"9\">L{}</text>",
+                x,
+                y - 9.0,
+                level
+            );
+        }
+    }
+
+    // Evidence units that are not part of the state, e.g. the second unit of an equivocation.
+    for (idx, validator) in (0..).zip(&view.validators) {
+        for wire_unit in view.missing_evidence(idx) {
+            let (x, y) = (layout.x(wire_unit.timestamp), layout.y(idx) + 12.0);
+            let _ = writeln!(
+                out,
+                "<path d=\"M{:.1},{:.1} l10,10 m0,-10 l-10,10\" stroke=\"red\" \
+                 stroke-width=\"2\"><title>{}</title></path>",
+                x - 5.0,
+                y - 5.0,
+                escape(&format!(
+                    "evidence #{} at {}{}\n{}",
+                    wire_unit.seq_number,
+                    wire_unit.timestamp,
+                    if wire_unit.value.is_some() {
+                        " with proposal"
+                    } else {
+                        ""
+                    },
+                    validator.fault.as_deref().unwrap_or_default()
+                ))
+            );
+        }
+    }
+    let _ = writeln!(out, "</svg>");
+}
+
+/// Writes the table of validators.
+fn validator_table(out: &mut String, view: &EraView) {
+    let state = &view.dump.highway_state;
+    let _ = writeln!(
+        out,
+        "<h2>Validators</h2>\n<table>\n<tr><th>Index</th><th>Public key</th><th>Weight</th>\
+         <th>Units</th><th>Latest unit or ping</th><th>Status</th></tr>"
+    );
+    for (idx, validator) in (0..).zip(&view.validators) {
+        let units = view
+            .units
+            .iter()
+            .filter(|(_, unit)| unit.creator == idx)
+            .count();
+        let latest = state
+            .pings
+            .get(idx as usize)
+            .map_or_else(String::new, ToString::to_string);
+        let mut status = vec![];
+        if let Some(fault) = &validator.fault {
+            status.push(fault.clone());
+        }
+        if validator.accused {
+            status.push("accused".to_string());
+        }
+        if validator.cannot_propose {
+            status.push("cannot propose".to_string());
+        }
+        let _ = writeln!(
+            out,
+            "<tr{}><td>{}</td><td class=\"mono\">{}</td><td>{}</td><td>{}</td><td>{}</td>\
+             <td>{}</td></tr>",
+            if validator.fault.is_some() {
+                " class=\"faulty\""
+            } else {
+                ""
+            },
+            idx,
+            escape(&validator.public_key.to_hex()),
+            validator.weight,
+            units,
+            escape(&latest),
+            escape(&status.join(", "))
+        );
+    }
+    let _ = writeln!(out, "</table>");
+}
+
+/// Writes the table of proposals with the committee weights of their summits.
+fn proposal_table(out: &mut String, view: &EraView) {
+    let state = &view.dump.highway_state;
+    let total_weight = state.total_weight().max(1);
+    let _ = writeln!(
+        out,
+        "<h2>Proposals</h2>\n<table>\n<tr><th>Height</th><th>Proposer</th><th>Timestamp</th>\
+         <th>Hash</th><th>Summit level</th><th>Committee weight by level</th></tr>"
+    );
+    for (hash, proposal) in view.sorted_proposals() {
+        let committee_weights: Vec<_> = (0..proposal.summit.horizons.len())
+            .map(|level| {
+                let weight = proposal.summit.committee_weight(state, level);
+                format!(
+                    "L{}: {:.0}%",
+                    level,
+                    weight as f64 * 100.0 / total_weight as f64
+                )
+            })
+            .collect();
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"mono\">{:x}</td><td>{}</td>\
+             <td>{}</td></tr>",
+            proposal.height,
+            escape(&view.validator_name(proposal.unit.creator)),
+            proposal.unit.timestamp,
+            hash,
+            proposal.summit.level(),
+            committee_weights.join(", ")
+        );
+    }
+    let _ = writeln!(out, "</table>");
+}
+
+/// Escapes text for use in HTML content and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
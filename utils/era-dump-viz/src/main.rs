@@ -0,0 +1,125 @@
+//! Renders consensus era dumps of casper-node as Graphviz graphs, SVG images or HTML timelines.
+
+mod dot;
+mod dump;
+mod html;
+mod summit;
+mod view;
+
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, bail, Context};
+use clap::Parser;
+use num_rational::Ratio;
+
+use dump::EraDump;
+use view::EraView;
+
+/// Renders an era dump, as written by the diagnostics port's `dump-consensus` command in JSON
+/// format, showing the era's units, citations, endorsements, equivocations and summits.
+#[derive(Parser)]
+#[clap(version = "1.0")]
+struct Opts {
+    /// Path to the JSON era dump. Reads from stdin if omitted.
+    #[clap(parse(from_os_str))]
+    input: Option<PathBuf>,
+    /// Path to the output file. Writes to stdout if omitted.
+    #[clap(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+    /// Output format: `html`, `dot` or `svg`. Rendering SVG requires Graphviz's `dot` command.
+    #[clap(short, long, default_value = "html")]
+    format: Format,
+    /// The finality threshold fraction used to compute the summits, as in the chainspec.
+    #[clap(long, default_value = "1/3")]
+    finality_threshold_fraction: Ratio<u64>,
+    /// The horizontal scale of the HTML timeline, in pixels per second.
+    #[clap(long, default_value = "20")]
+    pixels_per_second: f64,
+}
+
+/// The output format.
+enum Format {
+    /// An HTML page with a timeline of the units.
+    Html,
+    /// A Graphviz graph in the DOT language.
+    Dot,
+    /// A Graphviz graph rendered as SVG.
+    Svg,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "html" => Ok(Format::Html),
+            "dot" => Ok(Format::Dot),
+            "svg" => Ok(Format::Svg),
+            _ => Err("invalid output format, must be one of 'html', 'dot', 'svg'".to_string()),
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let opts: Opts = Opts::parse();
+    if opts.finality_threshold_fraction >= Ratio::from_integer(1) {
+        bail!("the finality threshold fraction must be less than 1");
+    }
+
+    let input = match &opts.input {
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("unable to read era dump {}", path.display()))?,
+        None => {
+            let mut input = String::new();
+            io::stdin()
+                .read_to_string(&mut input)
+                .context("unable to read era dump from stdin")?;
+            input
+        }
+    };
+    let dump = EraDump::from_json(&input)?;
+
+    // Same as the node's fault tolerance threshold, based on the Highway weights.
+    let total_weight = u128::from(dump.highway_state.total_weight());
+    let ftt = (total_weight * u128::from(*opts.finality_threshold_fraction.numer())
+        / u128::from(*opts.finality_threshold_fraction.denom())) as u64;
+    let view = EraView::new(&dump, ftt)?;
+
+    let rendered = match opts.format {
+        Format::Html => html::render(&view, opts.pixels_per_second).into_bytes(),
+        Format::Dot => dot::render(&view).into_bytes(),
+        Format::Svg => run_graphviz(&dot::render(&view))?,
+    };
+    match &opts.output {
+        Some(path) => fs::write(path, rendered)
+            .with_context(|| format!("unable to write output file {}", path.display()))?,
+        None => io::stdout().write_all(&rendered)?,
+    }
+    Ok(())
+}
+
+/// Renders the graph as SVG using Graphviz.
+fn run_graphviz(graph: &str) -> anyhow::Result<Vec<u8>> {
+    let mut child = Command::new("dot")
+        .arg("-Tsvg")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("unable to run Graphviz's `dot` command; is Graphviz installed?")?;
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("no stdin for `dot`"))?
+        .write_all(graph.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!("`dot` failed with {}", output.status);
+    }
+    Ok(output.stdout)
+}
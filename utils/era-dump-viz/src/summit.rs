@@ -0,0 +1,350 @@
+//! Summit computation, following the node's Highway finality detector.
+//!
+//! A summit is a sequence of committees of validators, where each member of the level-n committee
+//! has produced a unit that can see level-(n-1) units by a quorum of the level-n committee. The
+//! finality detector considers a proposal finalized once its summit has a level of at least 1; the
+//! higher the level, the more faulty weight the finality can tolerate.
+
+use std::collections::BTreeMap;
+
+use casper_hashing::Digest;
+
+use crate::dump::{Observation, State, Unit, ValidatorIndex};
+
+/// The highest summit level the finality detector looks for.
+const MAX_LEVEL: usize = 63;
+
+/// The summit for a proposal.
+#[derive(Debug)]
+pub(crate) struct Summit {
+    /// Entry `n` maps each member of the level-n committee to the sequence number of their
+    /// earliest level-n unit.
+    pub(crate) horizons: Vec<BTreeMap<ValidatorIndex, u64>>,
+}
+
+impl Summit {
+    /// Returns the summit level, or 0 if the proposal is not finalized.
+    pub(crate) fn level(&self) -> usize {
+        self.horizons.len().saturating_sub(1)
+    }
+
+    /// Returns the highest level of the unit by `creator` with the given sequence number, if it is
+    /// part of the summit.
+    pub(crate) fn unit_level(&self, creator: ValidatorIndex, seq_number: u64) -> Option<usize> {
+        self.horizons
+            .iter()
+            .rposition(|horizon| horizon.get(&creator).map_or(false, |sn| seq_number >= *sn))
+    }
+
+    /// Returns the total weight of the level-n committee.
+    pub(crate) fn committee_weight(&self, state: &State, level: usize) -> u64 {
+        self.horizons.get(level).map_or(0, |horizon| {
+            horizon.keys().map(|idx| state.weight(*idx)).sum()
+        })
+    }
+}
+
+/// Finds the highest summit for the proposal `candidate`, using the latest units in the state's
+/// panorama and the fault tolerance threshold `ftt`.
+///
+/// Like the finality detector, this starts with the highest possible level and lowers the target
+/// level until it finds a summit of exactly that level, since the quorum depends on the target.
+///
+/// Returns an error if a unit or block the summit depends on is missing from the dump.
+pub(crate) fn find_summit(state: &State, candidate: &Digest, ftt: u64) -> anyhow::Result<Summit> {
+    let latest: Vec<_> = state.panorama.iter().map(Observation::correct).collect();
+    let level0 = Horizon::level0(candidate, state, &latest)?;
+    let mut target_lvl = MAX_LEVEL;
+    loop {
+        let quorum = quorum_for_lvl(target_lvl, state.total_weight(), ftt);
+        let mut horizons = vec![level0.clone()];
+        while horizons.len() <= target_lvl {
+            match horizons[horizons.len() - 1].next(quorum)? {
+                Some(horizon) => horizons.push(horizon),
+                None => break,
+            }
+        }
+        let lvl = horizons.len() - 1;
+        if lvl == target_lvl || lvl == 0 {
+            let horizons = horizons
+                .into_iter()
+                .map(|horizon| horizon.into_map())
+                .collect();
+            return Ok(Summit { horizons });
+        }
+        target_lvl = lvl;
+    }
+}
+
+/// Returns the quorum required by a summit with the specified level and the given fault tolerance
+/// threshold. The level must be at least 1.
+fn quorum_for_lvl(lvl: usize, total_w: u64, ftt: u64) -> u64 {
+    let pow_lvl = 1u128 << lvl.min(63);
+    let numerator = (pow_lvl - 1) * u128::from(total_w) + pow_lvl * u128::from(ftt);
+    let denominator = 2 * pow_lvl - 2;
+    ((numerator + denominator - 1) / denominator) as u64
+}
+
+/// A list containing the earliest level-n units of each member of some committee, for some n.
+#[derive(Clone, Debug)]
+struct Horizon<'a> {
+    /// Assigns to each member of a committee the sequence number of the earliest unit that
+    /// qualifies them for that committee.
+    sequence_numbers: Vec<Option<u64>>,
+    /// The state this horizon belongs to.
+    state: &'a State,
+    /// The latest units that are eligible for the summit.
+    latest: &'a [Option<&'a Digest>],
+}
+
+impl<'a> Horizon<'a> {
+    /// Creates a horizon assigning to each validator their level-0 unit, i.e. the oldest unit in
+    /// their current streak of units for `candidate` (and descendants), or `None` if their latest
+    /// unit is not for `candidate`.
+    fn level0(
+        candidate: &'a Digest,
+        state: &'a State,
+        latest: &'a [Option<&'a Digest>],
+    ) -> anyhow::Result<Self> {
+        let height = state.block(candidate)?.height;
+        let to_lvl0unit = |maybe_hash: &Option<&'a Digest>| -> anyhow::Result<Option<u64>> {
+            let mut lvl0_seq_number = None;
+            if let Some(hash) = *maybe_hash {
+                for result in state.swimlane(hash) {
+                    let (_, unit) = result?;
+                    if state.find_ancestor_proposal(&unit.block, height)? != Some(candidate) {
+                        break;
+                    }
+                    lvl0_seq_number = Some(unit.seq_number);
+                }
+            }
+            Ok(lvl0_seq_number)
+        };
+        Ok(Horizon {
+            sequence_numbers: latest
+                .iter()
+                .map(to_lvl0unit)
+                .collect::<anyhow::Result<_>>()?,
+            state,
+            latest,
+        })
+    }
+
+    /// Returns a horizon `s` of units each of which can see a quorum of units in `self` by
+    /// validators that are part of `s`.
+    fn next(&self, quorum: u64) -> anyhow::Result<Option<Self>> {
+        let committee = self.prune_committee(quorum, self.members().collect())?;
+        if committee.is_empty() {
+            Ok(None)
+        } else {
+            self.next_from_committee(quorum, &committee).map(Some)
+        }
+    }
+
+    /// Returns the members of the committee.
+    fn members(&self) -> impl Iterator<Item = ValidatorIndex> + '_ {
+        (0..)
+            .zip(&self.sequence_numbers)
+            .filter(|(_, sn)| sn.is_some())
+            .map(|(idx, _)| idx)
+    }
+
+    /// Returns the greatest subset of the `committee` of validators whose latest units can see a
+    /// quorum of units by the subset in `self`.
+    fn prune_committee(
+        &self,
+        quorum: u64,
+        mut committee: Vec<ValidatorIndex>,
+    ) -> anyhow::Result<Vec<ValidatorIndex>> {
+        loop {
+            let mut new_committee = Vec::new();
+            for idx in &committee {
+                // Only validators with a latest unit are committee members.
+                if let Some(latest) = self.latest[*idx as usize] {
+                    if self.seen_weight(self.state.unit(latest)?, &committee)? >= quorum {
+                        new_committee.push(*idx);
+                    }
+                }
+            }
+            if new_committee.len() == committee.len() {
+                return Ok(new_committee);
+            }
+            committee = new_committee;
+        }
+    }
+
+    /// Returns the horizon containing the earliest unit of each of the `committee` members that
+    /// can see a quorum of units by `committee` members in `self`.
+    fn next_from_committee(
+        &self,
+        quorum: u64,
+        committee: &[ValidatorIndex],
+    ) -> anyhow::Result<Self> {
+        let mut sequence_numbers = vec![None; self.latest.len()];
+        for idx in committee {
+            let latest = match self.latest[*idx as usize] {
+                Some(latest) => latest,
+                None => continue,
+            };
+            for result in self.state.swimlane(latest) {
+                let (_, unit) = result?;
+                if self.seen_weight(unit, committee)? < quorum {
+                    break;
+                }
+                sequence_numbers[*idx as usize] = Some(unit.seq_number);
+            }
+        }
+        Ok(Horizon {
+            sequence_numbers,
+            state: self.state,
+            latest: self.latest,
+        })
+    }
+
+    /// Returns the total weight of the `committee`'s members whose unit in this horizon is seen
+    /// by `unit`.
+    fn seen_weight(&self, unit: &Unit, committee: &[ValidatorIndex]) -> anyhow::Result<u64> {
+        let mut weight = 0;
+        for idx in committee {
+            if self.can_see(unit, *idx)? {
+                weight += self.state.weight(*idx);
+            }
+        }
+        Ok(weight)
+    }
+
+    /// Returns whether `unit` can see `idx`'s unit in `self`, where `unit` is considered to see
+    /// itself.
+    fn can_see(&self, unit: &Unit, idx: ValidatorIndex) -> anyhow::Result<bool> {
+        let self_sn = match self.sequence_numbers[idx as usize] {
+            Some(self_sn) => self_sn,
+            None => return Ok(false),
+        };
+        if unit.creator == idx {
+            return Ok(unit.seq_number >= self_sn);
+        }
+        match unit
+            .panorama
+            .get(idx as usize)
+            .and_then(Observation::correct)
+        {
+            Some(hash) => Ok(self.state.unit(hash)?.seq_number >= self_sn),
+            None => Ok(false),
+        }
+    }
+
+    /// Returns the sequence numbers of the committee members' units.
+    fn into_map(self) -> BTreeMap<ValidatorIndex, u64> {
+        (0..)
+            .zip(self.sequence_numbers)
+            .filter_map(|(idx, sn)| Some((idx, sn?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use casper_hashing::Digest;
+    use casper_types::TimeDiff;
+
+    use super::find_summit;
+    use crate::dump::{Block, Observation, State, Unit};
+
+    /// Adds a unit to the state and returns its hash.
+    fn add_unit(
+        state: &mut State,
+        creator: u32,
+        cited: &[Option<Digest>],
+        block: Option<Digest>,
+    ) -> Digest {
+        let panorama: Vec<_> = cited
+            .iter()
+            .map(|hash| hash.map_or(Observation::None, Observation::Correct))
+            .collect();
+        let previous = panorama[creator as usize].correct().copied();
+        let seq_number = previous.map_or(0, |hash| state.unit(&hash).unwrap().seq_number + 1);
+        let hash = Digest::hash(format!("{} {}", creator, seq_number));
+        let block = block.unwrap_or_else(|| {
+            state.blocks.insert(
+                hash,
+                Block {
+                    height: 0,
+                    skip_idx: vec![],
+                },
+            );
+            hash
+        });
+        let unit = Unit {
+            panorama,
+            seq_number,
+            creator,
+            block,
+            skip_idx: previous.into_iter().collect(),
+            timestamp: seq_number.into(),
+            round_len: TimeDiff::from_millis(1),
+            endorsed: Default::default(),
+        };
+        state.units.insert(hash, unit);
+        state.panorama[creator as usize] = Observation::Correct(hash);
+        hash
+    }
+
+    #[test]
+    fn should_find_summit_levels() {
+        let mut state = State {
+            weights: vec![1, 1, 1],
+            units: BTreeMap::new(),
+            blocks: BTreeMap::new(),
+            faults: BTreeMap::new(),
+            panorama: vec![Observation::None, Observation::None, Observation::None],
+            endorsements: BTreeMap::new(),
+            incomplete_endorsements: BTreeMap::new(),
+            pings: vec![],
+        };
+
+        // Validator 0 proposes a block, and everyone confirms it.
+        let a0 = add_unit(&mut state, 0, &[None, None, None], None);
+        let b0 = add_unit(&mut state, 1, &[Some(a0), None, None], Some(a0));
+        let c0 = add_unit(&mut state, 2, &[Some(a0), Some(b0), None], Some(a0));
+        assert_eq!(0, find_summit(&state, &a0, 1).unwrap().level());
+
+        // With a fault tolerance threshold of 1, every level needs all three validators. After
+        // one more round, the block has a level-1 summit.
+        let a1 = add_unit(&mut state, 0, &[Some(a0), Some(b0), Some(c0)], Some(a0));
+        let b1 = add_unit(&mut state, 1, &[Some(a1), Some(b0), Some(c0)], Some(a0));
+        let c1 = add_unit(&mut state, 2, &[Some(a1), Some(b1), Some(c0)], Some(a0));
+        let summit = find_summit(&state, &a0, 1).unwrap();
+        assert_eq!(1, summit.level());
+        assert_eq!(Some(1), summit.unit_level(0, 1));
+        assert_eq!(Some(0), summit.unit_level(0, 0));
+
+        // With another round, the units of both rounds form a level-3 summit.
+        let a2 = add_unit(&mut state, 0, &[Some(a1), Some(b1), Some(c1)], Some(a0));
+        let b2 = add_unit(&mut state, 1, &[Some(a2), Some(b1), Some(c1)], Some(a0));
+        add_unit(&mut state, 2, &[Some(a2), Some(b2), Some(c1)], Some(a0));
+        assert_eq!(3, find_summit(&state, &a0, 1).unwrap().level());
+    }
+
+    #[test]
+    fn should_fail_if_units_are_missing() {
+        let mut state = State {
+            weights: vec![1, 1],
+            units: BTreeMap::new(),
+            blocks: BTreeMap::new(),
+            faults: BTreeMap::new(),
+            panorama: vec![Observation::None, Observation::None],
+            endorsements: BTreeMap::new(),
+            incomplete_endorsements: BTreeMap::new(),
+            pings: vec![],
+        };
+        let a0 = add_unit(&mut state, 0, &[None, None], None);
+        let b0 = add_unit(&mut state, 1, &[Some(a0), None], Some(a0));
+        add_unit(&mut state, 0, &[Some(a0), Some(b0)], Some(a0));
+
+        // A partial dump, e.g. with an earlier unit missing, results in an error.
+        state.units.remove(&b0);
+        assert!(find_summit(&state, &a0, 0).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! The information extracted from an era dump for rendering.
+
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+
+use casper_hashing::Digest;
+use casper_types::{AsymmetricType, PublicKey, Timestamp};
+
+use crate::{
+    dump::{EraDump, Fault, Unit, ValidatorIndex, WireUnit},
+    summit::{self, Summit},
+};
+
+/// An era dump, prepared for rendering.
+pub(crate) struct EraView<'a> {
+    /// The era dump.
+    pub(crate) dump: &'a EraDump,
+    /// The validators, by index.
+    pub(crate) validators: Vec<ValidatorView<'a>>,
+    /// All units, sorted by timestamp.
+    pub(crate) units: Vec<(&'a Digest, &'a Unit)>,
+    /// All proposals with their summits, by hash.
+    pub(crate) proposals: BTreeMap<&'a Digest, Proposal<'a>>,
+    /// The fault tolerance threshold used to compute the summits.
+    pub(crate) ftt: u64,
+}
+
+/// A validator of the era.
+pub(crate) struct ValidatorView<'a> {
+    /// The validator's public key.
+    pub(crate) public_key: &'a PublicKey,
+    /// The validator's weight in the Highway instance.
+    pub(crate) weight: u64,
+    /// A description of the validator's fault, if it is faulty.
+    pub(crate) fault: Option<String>,
+    /// The conflicting units from the evidence against the validator, if any.
+    pub(crate) evidence: Vec<&'a WireUnit>,
+    /// Whether the validator is excluded from proposing.
+    pub(crate) cannot_propose: bool,
+    /// Whether the validator has been accused of equivocating in this era.
+    pub(crate) accused: bool,
+}
+
+/// A proposed block.
+pub(crate) struct Proposal<'a> {
+    /// The unit containing the proposal.
+    pub(crate) unit: &'a Unit,
+    /// The block height.
+    pub(crate) height: u64,
+    /// The summit for this proposal.
+    pub(crate) summit: Summit,
+}
+
+impl<'a> EraView<'a> {
+    /// Prepares the dump for rendering, computing the summits with the given fault tolerance
+    /// threshold.
+    ///
+    /// Returns an error if the dump is missing units or blocks needed for a summit.
+    pub(crate) fn new(dump: &'a EraDump, ftt: u64) -> anyhow::Result<Self> {
+        let state = &dump.highway_state;
+        let validators = dump
+            .validators
+            .keys()
+            .zip(0..)
+            .map(|(public_key, idx)| {
+                let (fault, evidence) = match state.faults.get(&idx) {
+                    None if dump.faulty.contains(public_key) => {
+                        (Some("faulty in a recent era".to_string()), vec![])
+                    }
+                    None => (None, vec![]),
+                    Some(Fault::Banned) => (Some("banned".to_string()), vec![]),
+                    Some(Fault::Indirect) => (Some("faulty in another era".to_string()), vec![]),
+                    Some(Fault::Direct(evidence)) => {
+                        (Some(evidence.description()), evidence.units().to_vec())
+                    }
+                };
+                ValidatorView {
+                    public_key,
+                    weight: state.weights.get(idx as usize).copied().unwrap_or_default(),
+                    fault,
+                    evidence,
+                    cannot_propose: dump.cannot_propose.contains(public_key),
+                    accused: dump.accusations.contains(public_key),
+                }
+            })
+            .collect();
+        let mut units: Vec<_> = state.units.iter().collect();
+        units.sort_by_key(|(hash, unit)| (unit.timestamp, unit.creator, unit.seq_number, *hash));
+        let proposals = state
+            .blocks
+            .iter()
+            .map(|(hash, block)| -> anyhow::Result<_> {
+                let proposal = Proposal {
+                    unit: state.unit(hash)?,
+                    height: block.height,
+                    summit: summit::find_summit(state, hash, ftt).with_context(|| {
+                        format!("unable to compute the summit of proposal {}", hash)
+                    })?,
+                };
+                Ok((hash, proposal))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(EraView {
+            dump,
+            validators,
+            units,
+            proposals,
+            ftt,
+        })
+    }
+
+    /// Returns the validator's name, i.e. its index and an abbreviated public key.
+    pub(crate) fn validator_name(&self, idx: ValidatorIndex) -> String {
+        match self.validators.get(idx as usize) {
+            Some(validator) => format!("v{} {}", idx, short_key(validator.public_key)),
+            None => format!("v{}", idx),
+        }
+    }
+
+    /// Returns whether the validator is faulty.
+    pub(crate) fn is_faulty(&self, idx: ValidatorIndex) -> bool {
+        self.dump.highway_state.is_faulty(idx)
+            || self
+                .validators
+                .get(idx as usize)
+                .map_or(false, |validator| validator.fault.is_some())
+    }
+
+    /// Returns whether the unit is one of the conflicting units in the evidence against its
+    /// creator.
+    pub(crate) fn is_evidence(&self, unit: &Unit) -> bool {
+        self.validators
+            .get(unit.creator as usize)
+            .map_or(false, |validator| {
+                validator
+                    .evidence
+                    .iter()
+                    .any(|wire_unit| matches(wire_unit, unit))
+            })
+    }
+
+    /// Returns the units in the evidence against the validator that are not in the state, e.g.
+    /// the second unit of an equivocation.
+    pub(crate) fn missing_evidence(&self, idx: ValidatorIndex) -> Vec<&'a WireUnit> {
+        let state = &self.dump.highway_state;
+        self.validators
+            .get(idx as usize)
+            .map_or_else(Vec::new, |validator| {
+                validator
+                    .evidence
+                    .iter()
+                    .copied()
+                    .filter(|wire_unit| !state.units.values().any(|unit| matches(wire_unit, unit)))
+                    .collect()
+            })
+    }
+
+    /// Returns the proposals sorted by height and timestamp.
+    pub(crate) fn sorted_proposals(&self) -> Vec<(&'a Digest, &Proposal<'a>)> {
+        let mut proposals: Vec<_> = self.proposals.iter().map(|(h, p)| (*h, p)).collect();
+        proposals.sort_by_key(|(hash, proposal)| (proposal.height, proposal.unit.timestamp, *hash));
+        proposals
+    }
+
+    /// Returns the level of the unit in the summit for the block it votes for, if it is part of
+    /// that summit.
+    pub(crate) fn summit_level(&self, unit: &Unit) -> Option<usize> {
+        let proposal = self.proposals.get(&unit.block)?;
+        proposal
+            .summit
+            .unit_level(unit.creator, unit.seq_number)
+            .filter(|_| proposal.summit.level() > 0)
+    }
+
+    /// Returns the units cited by `unit` that its creator's previous unit didn't cite yet, i.e. the
+    /// new information this unit conveys, excluding the previous unit itself.
+    pub(crate) fn new_citations(&self, unit: &'a Unit) -> Vec<&'a Digest> {
+        let state = &self.dump.highway_state;
+        // If the previous unit is missing from the dump, all citations are shown as new.
+        let previous = unit.previous().and_then(|hash| state.unit(hash).ok());
+        (0..)
+            .zip(&unit.panorama)
+            .filter(|(idx, _)| *idx != unit.creator)
+            .filter_map(|(idx, obs)| {
+                let hash = obs.correct()?;
+                let seen_before = previous
+                    .and_then(|prev| prev.panorama.get(idx as usize))
+                    .and_then(|prev_obs| prev_obs.correct())
+                    == Some(hash);
+                if seen_before {
+                    None
+                } else {
+                    Some(hash)
+                }
+            })
+            .collect()
+    }
+}
+
+/// Returns whether the wire unit is the same as the unit.
+///
+/// Serialized wire units don't include their hash, so they are compared by creator, sequence
+/// number and timestamp.
+fn matches(wire_unit: &WireUnit, unit: &Unit) -> bool {
+    wire_unit.creator == unit.creator
+        && wire_unit.seq_number == unit.seq_number
+        && wire_unit.timestamp == unit.timestamp
+}
+
+/// Returns the time of day of the timestamp in UTC, with milliseconds.
+pub(crate) fn time_of_day(timestamp: Timestamp) -> String {
+    let millis = timestamp.millis();
+    let seconds = millis / 1000;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60,
+        millis % 1000
+    )
+}
+
+/// Returns the first bytes of a public key's hex representation.
+pub(crate) fn short_key(public_key: &PublicKey) -> String {
+    public_key.to_hex().chars().take(10).collect()
+}
+
+/// Returns the first bytes of a hash's hex representation.
+pub(crate) fn short_hash(hash: &Digest) -> String {
+    format!("{:x}", hash).chars().take(10).collect()
+}